@@ -45,8 +45,8 @@ use libafl::{
 };
 
 use libafl_frida::{
-    asan::asan_rt::AsanRuntime, coverage_rt::CoverageRuntime, coverage_rt::MAP_SIZE,
-    executor::FridaInProcessExecutor, helper::FridaInstrumentationHelper, FridaOptions,
+    asan::asan_rt::AsanRuntime, coverage_rt::CoverageRuntime, executor::FridaInProcessExecutor,
+    helper::FridaInstrumentationHelper, FridaOptions,
 };
 use libafl_targets::cmplog::{CmpLogObserver, CMPLOG_MAP};
 
@@ -220,7 +220,7 @@ unsafe fn fuzz(
         let edges_observer = HitcountsMapObserver::new(StdMapObserver::new_from_ptr(
             "edges",
             frida_helper.map_ptr_mut().unwrap(),
-            MAP_SIZE,
+            frida_helper.map_size().unwrap(),
         ));
 
         // Create an observation channel to keep track of the execution time
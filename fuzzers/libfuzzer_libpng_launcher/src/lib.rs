@@ -141,7 +141,7 @@ pub fn libafl_main() {
 
     let shmem_provider = StdShMemProvider::new().expect("Failed to init shared memory");
 
-    let monitor = TuiMonitor::new("Test fuzzer on libpng".into(), true);
+    let monitor = TuiMonitor::new("Test fuzzer on libpng".into(), true, 1024);
 
     let mut run_client = |state: Option<StdState<_, _, _, _, _>>, mut restarting_mgr, _core_id| {
         // Create an observation channel using the coverage map
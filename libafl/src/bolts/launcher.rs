@@ -17,7 +17,9 @@ use crate::bolts::os::{dup2, fork, ForkResult};
 #[cfg(feature = "std")]
 use crate::{
     bolts::{os::Cores, shmem::ShMemProvider},
-    events::{EventConfig, LlmpRestartingEventManager, ManagerKind, RestartingMgr},
+    events::{
+        EventConfig, LlmpRestartingEventManager, ManagerKind, ObserverSerdeMode, RestartingMgr,
+    },
     inputs::Input,
     monitors::Monitor,
     observers::ObserversTuple,
@@ -61,6 +63,9 @@ where
     monitor: MT,
     /// The configuration
     configuration: EventConfig,
+    /// How the clients serialize the observers in their new testcase events
+    #[builder(default = ObserverSerdeMode::Full)]
+    observer_serde_mode: ObserverSerdeMode,
     /// The 'main' function to run for each client forked. This probably shouldn't return
     #[builder(default, setter(strip_option))]
     run_client: Option<CF>,
@@ -98,6 +103,7 @@ where
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct("Launcher")
             .field("configuration", &self.configuration)
+            .field("observer_serde_mode", &self.observer_serde_mode)
             .field("broker_port", &self.broker_port)
             .field("core", &self.cores)
             .field("spawn_broker", &self.spawn_broker)
@@ -117,6 +123,23 @@ where
     SP: ShMemProvider + 'static,
     S: DeserializeOwned,
 {
+    /// Fails if a core to run on is not one of the `num_cores` of the machine, instead of silently
+    /// running fewer clients
+    fn check_cores(&self, num_cores: usize) -> Result<(), Error> {
+        match self
+            .cores
+            .ids
+            .iter()
+            .find(|core_id| core_id.id >= num_cores)
+        {
+            Some(core_id) => Err(Error::IllegalArgument(format!(
+                "Cannot bind a client to core {}, the machine has {} cores",
+                core_id.id, num_cores
+            ))),
+            None => Ok(()),
+        }
+    }
+
     /// Launch the broker and the clients and fuzz
     #[cfg(all(unix, feature = "std", feature = "fork"))]
     #[allow(clippy::similar_names)]
@@ -129,6 +152,7 @@ where
 
         let core_ids = core_affinity::get_core_ids().unwrap();
         let num_cores = core_ids.len();
+        self.check_cores(num_cores)?;
         let mut handles = vec![];
 
         println!("spawning on cores: {:?}", self.cores);
@@ -153,6 +177,8 @@ where
                     }
                     ForkResult::Child => {
                         println!("{:?} PostFork", unsafe { libc::getpid() });
+                        // Bind first, for the memory of the client to be on the NUMA node of its core
+                        core_affinity::set_for_current(*bind_to);
                         self.shmem_provider.post_fork(true)?;
 
                         #[cfg(feature = "std")]
@@ -171,6 +197,7 @@ where
                                 cpu_core: Some(*bind_to),
                             })
                             .configuration(self.configuration)
+                            .observer_serde_mode(self.observer_serde_mode)
                             .build()
                             .launch()?;
 
@@ -239,6 +266,7 @@ where
                         cpu_core: Some(CoreId { id: core_id }),
                     })
                     .configuration(self.configuration)
+                    .observer_serde_mode(self.observer_serde_mode)
                     .build()
                     .launch()?;
 
@@ -257,6 +285,7 @@ where
 
                 let core_ids = core_affinity::get_core_ids().unwrap();
                 let num_cores = core_ids.len();
+                self.check_cores(num_cores)?;
                 let mut handles = vec![];
 
                 println!("spawning on cores: {:?}", self.cores);
@@ -1947,6 +1947,21 @@ where
     pub fn loop_forever<F>(&mut self, on_new_msg: &mut F, sleep_time: Option<Duration>)
     where
         F: FnMut(ClientId, Tag, Flags, &[u8]) -> Result<LlmpMsgHookResult, Error>,
+    {
+        self.loop_forever_with_tick(on_new_msg, &mut |_| Ok(()), sleep_time);
+    }
+
+    /// Loops infinitely like [`LlmpBroker::loop_forever`], and calls `on_tick` with the outgoing
+    /// map after each round, for the broker to send its own messages to the clients.
+    /// Never returns. Panics on error.
+    pub fn loop_forever_with_tick<F, T>(
+        &mut self,
+        on_new_msg: &mut F,
+        on_tick: &mut T,
+        sleep_time: Option<Duration>,
+    ) where
+        F: FnMut(ClientId, Tag, Flags, &[u8]) -> Result<LlmpMsgHookResult, Error>,
+        T: FnMut(&mut LlmpSender<SP>) -> Result<(), Error>,
     {
         #[cfg(unix)]
         if let Err(_e) = unsafe { setup_signal_handler(&mut GLOBAL_SIGHANDLER_STATE) } {
@@ -1958,6 +1973,7 @@ where
         while !self.is_shutting_down() {
             self.once(on_new_msg)
                 .expect("An error occurred when brokering. Exiting.");
+            on_tick(&mut self.llmp_out).expect("An error occurred when brokering. Exiting.");
 
             #[cfg(feature = "std")]
             if let Some(time) = sleep_time {
@@ -0,0 +1,570 @@
+//! Coverage collection with Intel Processor Trace, through the `perf` subsystem of Linux.
+//!
+//! The [`IntelPT`] tracer records the control flow of a process into a ring buffer shared with the
+//! kernel. As the target does not get disassembled, [`decode_trace`] approximates the edges from
+//! the packets alone: transitions between the targets of indirect branches (and returns), and the
+//! outcomes of the conditional branches that follow them.
+
+use alloc::vec::Vec;
+use core::{
+    mem::size_of,
+    ptr,
+    sync::atomic::{fence, Ordering},
+};
+use std::{ffi::CString, fs, io, os::unix::io::RawFd, path::Path};
+
+use libc::{c_void, pid_t};
+
+use crate::Error;
+
+/// The file containing the `perf` event type of Intel PT, if supported by the CPU and the kernel
+pub const INTEL_PT_TYPE_PATH: &str = "/sys/bus/event_source/devices/intel_pt/type";
+
+/// The default number of pages of the trace buffer (4 MiB with 4 KiB pages)
+pub const INTEL_PT_DEFAULT_AUX_PAGES: usize = 1024;
+
+/// The number of conditional branches following an indirect branch that are told apart.
+/// Longer runs of conditional branches, such as loops, restart from the indirect branch,
+/// so that each additional iteration does not count as new coverage.
+const TNT_CHAIN_MAX: usize = 32;
+
+/// The number of pages of the data area of the buffer, holding the (unused) side-band records
+const DATA_PAGES: usize = 8;
+
+const PERF_FLAG_FD_CLOEXEC: libc::c_ulong = 8;
+
+const PERF_EVENT_IOC_ENABLE: libc::c_ulong = 0x2400;
+const PERF_EVENT_IOC_DISABLE: libc::c_ulong = 0x2401;
+const PERF_EVENT_IOC_RESET: libc::c_ulong = 0x2403;
+const PERF_EVENT_IOC_SET_FILTER: libc::c_ulong = 0x4008_2406;
+
+/// `perf_event_attr.flags`: start disabled
+const ATTR_DISABLED: u64 = 1 << 0;
+/// `perf_event_attr.flags`: do not trace the kernel
+const ATTR_EXCLUDE_KERNEL: u64 = 1 << 5;
+/// `perf_event_attr.flags`: do not trace the hypervisor
+const ATTR_EXCLUDE_HV: u64 = 1 << 6;
+
+/// `intel_pt` config: emit branch packets
+const PT_CONFIG_BRANCH: u64 = 1 << 13;
+/// `intel_pt` config: no return compression, so that returns emit their target
+const PT_CONFIG_NORETCOMP: u64 = 1 << 11;
+
+/// The `perf_event_attr` struct of the kernel, in its fifth version
+#[repr(C)]
+#[derive(Debug, Default)]
+struct PerfEventAttr {
+    type_: u32,
+    size: u32,
+    config: u64,
+    sample_period: u64,
+    sample_type: u64,
+    read_format: u64,
+    flags: u64,
+    wakeup_events: u32,
+    bp_type: u32,
+    config1: u64,
+    config2: u64,
+    branch_sample_type: u64,
+    sample_regs_user: u64,
+    sample_stack_user: u32,
+    clockid: i32,
+    sample_regs_intr: u64,
+    aux_watermark: u32,
+    sample_max_stack: u16,
+    reserved_2: u16,
+}
+
+/// Offsets of the fields of `perf_event_mmap_page` we use
+const MMAP_DATA_HEAD: usize = 1024;
+const MMAP_DATA_TAIL: usize = 1032;
+const MMAP_AUX_HEAD: usize = 1056;
+const MMAP_AUX_TAIL: usize = 1064;
+const MMAP_AUX_OFFSET: usize = 1072;
+const MMAP_AUX_SIZE: usize = 1080;
+
+/// Returns `true` if Intel PT can be used on this machine
+#[must_use]
+pub fn intel_pt_available() -> bool {
+    Path::new(INTEL_PT_TYPE_PATH).exists()
+}
+
+/// A tracer recording the control flow of a process with Intel PT.
+///
+/// The tracer starts disabled: [`IntelPT::enable`] it right before running the target, and
+/// [`IntelPT::disable`] it afterwards, before decoding the trace with [`IntelPT::decode_into`].
+#[derive(Debug)]
+pub struct IntelPT {
+    fd: RawFd,
+    base: *mut u8,
+    base_size: usize,
+    aux: *mut u8,
+    aux_size: usize,
+    trace: Vec<u8>,
+}
+
+impl IntelPT {
+    /// Opens a tracer for the process `pid`, `0` being the current process,
+    /// with a trace buffer of `aux_pages` pages, which needs to be a power of two.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_possible_wrap,
+        clippy::cast_sign_loss,
+        clippy::cast_ptr_alignment
+    )]
+    pub fn new(pid: pid_t, aux_pages: usize) -> Result<Self, Error> {
+        if !aux_pages.is_power_of_two() {
+            return Err(Error::IllegalArgument(format!(
+                "The number of pages of the Intel PT buffer needs to be a power of two, got {}",
+                aux_pages
+            )));
+        }
+        let pt_type = fs::read_to_string(INTEL_PT_TYPE_PATH)
+            .map_err(|err| {
+                Error::NotImplemented(format!("Intel PT is not available on this system: {}", err))
+            })?
+            .trim()
+            .parse::<u32>()
+            .map_err(|err| Error::Unknown(format!("Invalid Intel PT event type: {}", err)))?;
+
+        let attr = PerfEventAttr {
+            type_: pt_type,
+            size: size_of::<PerfEventAttr>() as u32,
+            config: PT_CONFIG_BRANCH | PT_CONFIG_NORETCOMP,
+            flags: ATTR_DISABLED | ATTR_EXCLUDE_KERNEL | ATTR_EXCLUDE_HV,
+            ..PerfEventAttr::default()
+        };
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_perf_event_open,
+                &attr as *const PerfEventAttr,
+                pid,
+                -1,
+                -1,
+                PERF_FLAG_FD_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            return Err(Error::Unknown(format!(
+                "Could not open the Intel PT event (check /proc/sys/kernel/perf_event_paranoid): {}",
+                io::Error::last_os_error()
+            )));
+        }
+        let fd = fd as RawFd;
+
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let base_size = (1 + DATA_PAGES) * page_size;
+        let aux_size = aux_pages * page_size;
+        let base = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                base_size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            )
+        };
+        if base == libc::MAP_FAILED {
+            let err = io::Error::last_os_error();
+            unsafe { libc::close(fd) };
+            return Err(Error::Unknown(format!(
+                "Could not map the perf buffer: {}",
+                err
+            )));
+        }
+        let base = base as *mut u8;
+        unsafe {
+            ptr::write_volatile(base.add(MMAP_AUX_OFFSET) as *mut u64, base_size as u64);
+            ptr::write_volatile(base.add(MMAP_AUX_SIZE) as *mut u64, aux_size as u64);
+        }
+        let aux = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                aux_size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                base_size as libc::off_t,
+            )
+        };
+        if aux == libc::MAP_FAILED {
+            let err = io::Error::last_os_error();
+            unsafe {
+                libc::munmap(base as *mut c_void, base_size);
+                libc::close(fd);
+            }
+            return Err(Error::Unknown(format!(
+                "Could not map the Intel PT buffer: {}",
+                err
+            )));
+        }
+
+        Ok(Self {
+            fd,
+            base,
+            base_size,
+            aux: aux as *mut u8,
+            aux_size,
+            trace: Vec::new(),
+        })
+    }
+
+    /// Restricts the tracing to the given address ranges, using the `perf` filter syntax,
+    /// e.g. `filter 0x1000/0x2000@/path/to/binary` for the range at file offset `0x1000`,
+    /// of size `0x2000`, of the given binary. Several filters are separated by commas.
+    pub fn set_filter(&mut self, filter: &str) -> Result<(), Error> {
+        let filter = CString::new(filter)
+            .map_err(|_| Error::IllegalArgument("The filter contains a nul byte".into()))?;
+        self.ioctl(PERF_EVENT_IOC_SET_FILTER, filter.as_ptr() as libc::c_ulong)
+    }
+
+    /// Starts tracing
+    pub fn enable(&mut self) -> Result<(), Error> {
+        self.ioctl(PERF_EVENT_IOC_ENABLE, 0)
+    }
+
+    /// Stops tracing
+    pub fn disable(&mut self) -> Result<(), Error> {
+        self.ioctl(PERF_EVENT_IOC_DISABLE, 0)
+    }
+
+    /// Drops the trace recorded so far
+    pub fn reset(&mut self) -> Result<(), Error> {
+        self.ioctl(PERF_EVENT_IOC_RESET, 0)?;
+        unsafe {
+            let aux_head = self.read_header(MMAP_AUX_HEAD);
+            let data_head = self.read_header(MMAP_DATA_HEAD);
+            self.write_header(MMAP_AUX_TAIL, aux_head);
+            self.write_header(MMAP_DATA_TAIL, data_head);
+        }
+        Ok(())
+    }
+
+    /// Decodes the trace recorded since the last call into the edge `map`, and frees the buffer.
+    /// If the buffer overflowed, only its most recent part gets decoded.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn decode_into(&mut self, map: &mut [u8]) -> Result<(), Error> {
+        let (head, tail) = unsafe {
+            let head = self.read_header(MMAP_AUX_HEAD);
+            (head, self.read_header(MMAP_AUX_TAIL))
+        };
+        let len = (head.wrapping_sub(tail) as usize).min(self.aux_size);
+        let start = (head as usize).wrapping_sub(len) % self.aux_size;
+
+        self.trace.clear();
+        let aux = unsafe { core::slice::from_raw_parts(self.aux, self.aux_size) };
+        let first = len.min(self.aux_size - start);
+        self.trace.extend_from_slice(&aux[start..start + first]);
+        self.trace.extend_from_slice(&aux[..len - first]);
+
+        unsafe {
+            self.write_header(MMAP_AUX_TAIL, head);
+            let data_head = self.read_header(MMAP_DATA_HEAD);
+            self.write_header(MMAP_DATA_TAIL, data_head);
+        }
+
+        decode_trace(&self.trace, map);
+        Ok(())
+    }
+
+    fn ioctl(&mut self, request: libc::c_ulong, arg: libc::c_ulong) -> Result<(), Error> {
+        #[allow(clippy::useless_conversion)]
+        let ret = unsafe { libc::ioctl(self.fd, request.try_into().unwrap(), arg) };
+        if ret < 0 {
+            Err(Error::Unknown(format!(
+                "Intel PT ioctl {:#x} failed: {}",
+                request,
+                io::Error::last_os_error()
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Reads a head of the `perf_event_mmap_page`, the kernel writes it concurrently
+    #[allow(clippy::cast_ptr_alignment)]
+    unsafe fn read_header(&self, offset: usize) -> u64 {
+        let value = ptr::read_volatile(self.base.add(offset) as *const u64);
+        fence(Ordering::Acquire);
+        value
+    }
+
+    /// Writes a tail of the `perf_event_mmap_page`, once we are done with the data
+    #[allow(clippy::cast_ptr_alignment)]
+    unsafe fn write_header(&mut self, offset: usize, value: u64) {
+        fence(Ordering::Release);
+        ptr::write_volatile(self.base.add(offset) as *mut u64, value);
+    }
+}
+
+impl Drop for IntelPT {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.aux as *mut c_void, self.aux_size);
+            libc::munmap(self.base as *mut c_void, self.base_size);
+            libc::close(self.fd);
+        }
+    }
+}
+
+/// Mixes a value into the running location
+#[inline]
+fn mix(loc: u64, value: u64) -> u64 {
+    (loc ^ value)
+        .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        .rotate_left(31)
+}
+
+/// The state of the packet decoder
+#[derive(Debug, Default)]
+struct PacketDecoder {
+    /// The last IP, the base of compressed IPs
+    last_ip: u64,
+    /// The current location, hashed from the last indirect branch and the conditional branches since
+    loc: u64,
+    /// The location of the last indirect branch
+    branch_loc: u64,
+    /// The number of conditional branches since the last indirect branch
+    chain: usize,
+}
+
+impl PacketDecoder {
+    #[allow(clippy::cast_possible_truncation)]
+    fn hit(map: &mut [u8], loc: u64) {
+        let idx = (loc as usize) % map.len();
+        map[idx] = map[idx].wrapping_add(1);
+    }
+
+    fn branch_to(&mut self, map: &mut [u8], ip: u64) {
+        Self::hit(map, mix(self.loc, ip));
+        self.branch_loc = mix(0, ip);
+        self.loc = self.branch_loc;
+        self.chain = 0;
+    }
+
+    fn tnt(&mut self, map: &mut [u8], taken: bool) {
+        self.loc = mix(self.loc, if taken { 2 } else { 1 });
+        Self::hit(map, self.loc);
+        self.chain += 1;
+        if self.chain >= TNT_CHAIN_MAX {
+            self.loc = self.branch_loc;
+            self.chain = 0;
+        }
+    }
+
+    fn lose_track(&mut self) {
+        self.loc = 0;
+        self.branch_loc = 0;
+        self.chain = 0;
+    }
+
+    /// Decodes the IP of a `TIP`-like packet, returns its length and the IP, if in context
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    fn decode_ip(&mut self, packet: &[u8]) -> Option<(usize, Option<u64>)> {
+        let payload_len = match packet[0] >> 5 {
+            0 => return Some((1, None)),
+            1 => 2,
+            2 => 4,
+            3 | 4 => 6,
+            6 => 8,
+            _ => return None,
+        };
+        let payload = packet.get(1..=payload_len)?;
+        let mut bytes = [0_u8; 8];
+        bytes[..payload_len].copy_from_slice(payload);
+        let value = u64::from_le_bytes(bytes);
+        let ip = match packet[0] >> 5 {
+            1 => (self.last_ip & !0xffff) | value,
+            2 => (self.last_ip & !0xffff_ffff) | value,
+            // Sign extend bit 47
+            3 => ((value << 16) as i64 >> 16) as u64,
+            4 => (self.last_ip & !0xffff_ffff_ffff) | value,
+            _ => value,
+        };
+        self.last_ip = ip;
+        Some((1 + payload_len, Some(ip)))
+    }
+
+    /// Decodes the packet at the start of `trace` and returns its length,
+    /// or `None` if it is unknown or truncated.
+    fn decode_packet(&mut self, trace: &[u8], map: &mut [u8]) -> Option<usize> {
+        let header = trace[0];
+        match header {
+            // PAD
+            0x00 => Some(1),
+            0x02 => self.decode_extended(trace, map),
+            // TSC
+            0x19 => (trace.len() >= 8).then(|| 8),
+            // MTC, MODE
+            0x59 | 0x99 => (trace.len() >= 2).then(|| 2),
+            // Short TNT: the highest set bit stops the branches, the oldest being right below it
+            _ if header & 1 == 0 => {
+                let stop = 7 - header.leading_zeros();
+                for bit in (1..stop).rev() {
+                    self.tnt(map, header & (1 << bit) != 0);
+                }
+                Some(1)
+            }
+            // CYC, continued while the lowest bit of the following bytes is set
+            _ if header & 3 == 3 => {
+                if header & 4 == 0 {
+                    return Some(1);
+                }
+                let len = trace[1..].iter().position(|b| b & 1 == 0)?;
+                Some(len + 2)
+            }
+            _ => match header & 0x1f {
+                // TIP
+                0x0d => {
+                    let (len, ip) = self.decode_ip(trace)?;
+                    match ip {
+                        Some(ip) => self.branch_to(map, ip),
+                        None => self.lose_track(),
+                    }
+                    Some(len)
+                }
+                // TIP.PGE, tracing got enabled at the IP
+                0x11 => {
+                    let (len, ip) = self.decode_ip(trace)?;
+                    self.lose_track();
+                    if let Some(ip) = ip {
+                        self.branch_to(map, ip);
+                    }
+                    Some(len)
+                }
+                // TIP.PGD
+                0x01 => {
+                    let (len, _) = self.decode_ip(trace)?;
+                    self.lose_track();
+                    Some(len)
+                }
+                // FUP, the source of an asynchronous event
+                0x1d => self.decode_ip(trace).map(|(len, _)| len),
+                _ => None,
+            },
+        }
+    }
+
+    /// Decodes the extended packet (starting with `0x02`) at the start of `trace`
+    fn decode_extended(&mut self, trace: &[u8], map: &mut [u8]) -> Option<usize> {
+        let opcode = *trace.get(1)?;
+        let len = match opcode {
+            // Long TNT
+            0xa3 => {
+                let payload = trace.get(2..8)?;
+                let mut bytes = [0_u8; 8];
+                bytes[..6].copy_from_slice(payload);
+                let bits = u64::from_le_bytes(bytes);
+                let stop = 63 - bits.leading_zeros();
+                for bit in (0..stop).rev() {
+                    self.tnt(map, bits & (1 << bit) != 0);
+                }
+                8
+            }
+            // PSB, the IP compression starts over
+            0x82 => {
+                self.last_ip = 0;
+                16
+            }
+            // OVF, packets got lost
+            0xf3 => {
+                self.lose_track();
+                2
+            }
+            // PSBEND, TRACESTOP, EXSTOP
+            0x23 | 0x83 | 0x62 | 0xe2 => 2,
+            // CBR, PWRE
+            0x03 | 0x22 => 4,
+            // VMCS, TMA, PWRX
+            0xc8 | 0x73 | 0xa2 => 7,
+            // PIP
+            0x43 => 8,
+            // MWAIT
+            0xc2 => 10,
+            // MNT
+            0xc3 => 11,
+            // PTWRITE, with a 4 or 8 bytes payload
+            _ if opcode & 0x1f == 0x12 => match (opcode >> 5) & 3 {
+                0 => 6,
+                1 => 10,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        (trace.len() >= len).then(|| len)
+    }
+}
+
+/// The PSB packet, the synchronization point of the trace
+const PSB: [u8; 16] = [
+    0x02, 0x82, 0x02, 0x82, 0x02, 0x82, 0x02, 0x82, 0x02, 0x82, 0x02, 0x82, 0x02, 0x82, 0x02, 0x82,
+];
+
+/// Decodes the Intel PT packets in `trace`, adding the edges they describe to `map`.
+///
+/// Without disassembling the target, the edges are approximated by the transitions between the
+/// targets of indirect branches, returns and far transfers, and the outcomes of the (up to 32)
+/// conditional branches following each of them. On unknown or corrupted packets, the decoding
+/// resumes at the next synchronization point.
+pub fn decode_trace(trace: &[u8], map: &mut [u8]) {
+    if map.is_empty() {
+        return;
+    }
+    let mut decoder = PacketDecoder::default();
+    let mut pos = 0;
+    while pos < trace.len() {
+        if let Some(len) = decoder.decode_packet(&trace[pos..], map) {
+            pos += len;
+        } else {
+            decoder.lose_track();
+            match trace[pos + 1..]
+                .windows(PSB.len())
+                .position(|window| window == PSB)
+            {
+                Some(offset) => pos += 1 + offset,
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_trace, PSB};
+
+    /// A trace entering at `0x401000`, taking or not a conditional branch, then returning to `0x401234`
+    fn trace(taken: bool) -> Vec<u8> {
+        let mut trace = PSB.to_vec();
+        // PSBEND
+        trace.extend_from_slice(&[0x02, 0x23]);
+        // TIP.PGE with a sign extended 6 bytes IP
+        trace.extend_from_slice(&[0x71, 0x00, 0x10, 0x40, 0x00, 0x00, 0x00]);
+        // Short TNT with a single branch
+        trace.push(if taken { 0b110 } else { 0b100 });
+        // TIP with the 2 lower bytes of the IP
+        trace.extend_from_slice(&[0x2d, 0x34, 0x12]);
+        // TIP.PGD, out of context
+        trace.push(0x01);
+        trace
+    }
+
+    #[test]
+    fn test_decode_trace() {
+        let mut taken = vec![0_u8; 1 << 16];
+        decode_trace(&trace(true), &mut taken);
+        assert_eq!(taken.iter().map(|&b| u32::from(b)).sum::<u32>(), 3);
+
+        let mut not_taken = vec![0_u8; 1 << 16];
+        decode_trace(&trace(false), &mut not_taken);
+        assert_eq!(not_taken.iter().map(|&b| u32::from(b)).sum::<u32>(), 3);
+        assert_ne!(taken, not_taken);
+
+        // Garbage gets skipped up to the next PSB
+        let mut resynced = vec![0_u8; 1 << 16];
+        let mut garbled = vec![0x02, 0xff, 0x42];
+        garbled.extend_from_slice(&trace(true));
+        decode_trace(&garbled, &mut resynced);
+        assert_eq!(taken, resynced);
+    }
+}
@@ -21,6 +21,12 @@ pub mod unix_signals;
 #[cfg(all(unix, feature = "std"))]
 pub mod pipes;
 
+#[cfg(all(feature = "std", target_os = "linux", target_arch = "x86_64"))]
+pub mod intel_pt;
+
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub mod rapl;
+
 #[cfg(all(unix, feature = "std"))]
 use std::ffi::CString;
 
@@ -105,6 +111,36 @@ pub fn dup2(fd: i32, device: i32) -> Result<(), Error> {
     }
 }
 
+/// Limits the address space of the current process to `memlimit_mb` MiB, and disables its core
+/// dumps. Meant to be called in a child, before `exec`ing the target, e.g. in
+/// [`std::os::unix::process::CommandExt::pre_exec`].
+#[cfg(all(unix, feature = "std"))]
+#[allow(trivial_numeric_casts)]
+pub fn set_memlimit(memlimit_mb: u64) -> std::io::Result<()> {
+    let memlimit: libc::rlim_t = (memlimit_mb as libc::rlim_t) << 20;
+    let r = libc::rlimit {
+        rlim_cur: memlimit,
+        rlim_max: memlimit,
+    };
+    let r0 = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+
+    #[cfg(target_os = "openbsd")]
+    let mut ret = unsafe { libc::setrlimit(libc::RLIMIT_RSS, &r) };
+    #[cfg(not(target_os = "openbsd"))]
+    let mut ret = unsafe { libc::setrlimit(libc::RLIMIT_AS, &r) };
+    if ret < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    ret = unsafe { libc::setrlimit(libc::RLIMIT_CORE, &r0) };
+    if ret < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
 /// Core ID
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CoreId {
@@ -0,0 +1,120 @@
+//! Energy measurement through the Running Average Power Limit (RAPL) counters of the CPU,
+//! exposed by the `powercap` interface of Linux.
+//!
+//! The counters measure whole CPU packages: with multiple clients per package, the energy of a
+//! client is an estimate, shared with everything else running on the package.
+
+use alloc::{string::ToString, vec::Vec};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::Error;
+
+/// The directory of the `powercap` zones
+pub const POWERCAP_PATH: &str = "/sys/class/powercap";
+
+/// A RAPL zone, that is, one CPU package
+#[derive(Debug, Clone)]
+struct RaplZone {
+    energy_path: PathBuf,
+    max_energy_uj: u64,
+}
+
+fn read_u64(path: &Path) -> Result<u64, Error> {
+    Ok(fs::read_to_string(path)?.trim().parse()?)
+}
+
+/// Reads the energy counters of all the CPU packages
+#[derive(Debug, Clone)]
+pub struct Rapl {
+    zones: Vec<RaplZone>,
+}
+
+impl Rapl {
+    /// Finds the RAPL zones of all the packages.
+    /// Fails if RAPL is not available, or if the counters are not readable (they are only readable
+    /// by root on recent kernels).
+    pub fn new() -> Result<Self, Error> {
+        Self::with_powercap_path(POWERCAP_PATH)
+    }
+
+    /// Finds the RAPL zones in the given `powercap` directory, see [`Rapl::new`]
+    pub fn with_powercap_path<P>(powercap_path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut zones = vec![];
+        for entry in fs::read_dir(powercap_path)? {
+            let path = entry?.path();
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            // Top-level zones are `intel-rapl:N`, subzones `intel-rapl:N:M`
+            if !name.starts_with("intel-rapl:") || name.matches(':').count() != 1 {
+                continue;
+            }
+            let zone = RaplZone {
+                energy_path: path.join("energy_uj"),
+                max_energy_uj: read_u64(&path.join("max_energy_range_uj"))?,
+            };
+            read_u64(&zone.energy_path)?;
+            zones.push(zone);
+        }
+        if zones.is_empty() {
+            return Err(Error::NotImplemented(
+                "No RAPL zone found, is the intel_rapl module loaded?".into(),
+            ));
+        }
+        Ok(Self { zones })
+    }
+
+    /// The current values of the energy counters, in microjoules, one per package
+    pub fn read(&self) -> Result<Vec<u64>, Error> {
+        self.zones
+            .iter()
+            .map(|zone| read_u64(&zone.energy_path))
+            .collect()
+    }
+
+    /// The energy consumed by all the packages since `previous`, a result of [`Rapl::read`],
+    /// in microjoules, taking care of counters wrapping around
+    #[must_use]
+    pub fn consumed_since(&self, previous: &[u64], current: &[u64]) -> u64 {
+        self.zones
+            .iter()
+            .zip(previous.iter().zip(current.iter()))
+            .map(|(zone, (previous, current))| {
+                if current >= previous {
+                    current - previous
+                } else {
+                    zone.max_energy_uj - previous + current
+                }
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Rapl, RaplZone};
+
+    #[test]
+    fn test_rapl_wraparound() {
+        let rapl = Rapl {
+            zones: vec![
+                RaplZone {
+                    energy_path: "energy_uj".into(),
+                    max_energy_uj: 1000,
+                },
+                RaplZone {
+                    energy_path: "energy_uj".into(),
+                    max_energy_uj: 1000,
+                },
+            ],
+        };
+        assert_eq!(rapl.consumed_since(&[100, 900], &[300, 50]), 350);
+    }
+}
@@ -90,6 +90,18 @@ impl TopAccountingMetadata {
             max_accounting: vec![0; acc_len],
         }
     }
+
+    /// Forgets the corpus entry `idx`, removed from the corpus, shifting the indexes of the next
+    /// entries
+    pub fn remove_corpus_idx(&mut self, idx: usize) {
+        self.map.retain(|_, corpus_idx| *corpus_idx != idx);
+        for corpus_idx in self.map.values_mut() {
+            if *corpus_idx > idx {
+                *corpus_idx -= 1;
+            }
+        }
+        self.changed = true;
+    }
 }
 
 /// A minimizer scheduler using coverage accounting
@@ -126,6 +138,9 @@ where
         idx: usize,
         testcase: &Option<Testcase<I>>,
     ) -> Result<(), Error> {
+        if let Some(top_accounting) = state.metadata_mut().get_mut::<TopAccountingMetadata>() {
+            top_accounting.remove_corpus_idx(idx);
+        }
         self.inner.on_remove(state, idx, testcase)
     }
 
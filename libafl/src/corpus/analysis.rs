@@ -0,0 +1,272 @@
+//! Analysis of the coverage of a corpus: what each entry covers on its own, how much entries
+//! overlap, and which entries are redundant. Useful before sharing or minimizing a corpus.
+
+use alloc::{string::String, vec::Vec};
+use core::marker::PhantomData;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Corpus,
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::Input,
+    observers::{MapObserver, ObserversTuple},
+    state::HasCorpus,
+    Error,
+};
+
+/// The default Jaccard index from which pairs of entries get reported as overlapping
+pub const DEFAULT_OVERLAP_THRESHOLD: f64 = 0.9;
+
+/// The coverage of a corpus entry
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EntryCoverage {
+    /// The index of the entry in the corpus
+    pub idx: usize,
+    /// The filename of the entry, if stored on disk
+    pub filename: Option<String>,
+    /// How the execution of the entry finished
+    pub exit_kind: ExitKind,
+    /// The number of map entries the entry covers
+    pub edges: usize,
+    /// The number of map entries no other entry covers
+    pub unique_edges: usize,
+    /// The entry is not needed to keep the coverage of the corpus
+    pub redundant: bool,
+}
+
+/// The overlap of the coverage of two corpus entries
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EntryOverlap {
+    /// The index of the first entry
+    pub first: usize,
+    /// The index of the second entry
+    pub second: usize,
+    /// The number of map entries both entries cover
+    pub shared_edges: usize,
+    /// The Jaccard index of the coverage of both entries, `1.0` for identical coverage
+    pub jaccard: f64,
+}
+
+/// The machine-readable result of a [`CorpusAnalyzer`]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CorpusReport {
+    /// The number of map entries covered by the whole corpus
+    pub total_edges: usize,
+    /// The coverage of each entry
+    pub entries: Vec<EntryCoverage>,
+    /// The pairs of entries overlapping at least as much as the threshold
+    pub overlaps: Vec<EntryOverlap>,
+    /// The indexes of the entries that can be dropped while keeping the coverage of the corpus
+    pub redundant: Vec<usize>,
+}
+
+impl CorpusReport {
+    /// Computes the report from the covered map entries of each corpus entry, sorted
+    fn new(
+        coverages: Vec<(usize, Option<String>, ExitKind, Vec<usize>)>,
+        overlap_threshold: f64,
+    ) -> Self {
+        let mut counts: HashMap<usize, usize> = HashMap::new();
+        for (_, _, _, edges) in &coverages {
+            for edge in edges {
+                *counts.entry(*edge).or_default() += 1;
+            }
+        }
+
+        // Greedily keep the entries adding the most coverage, the others are redundant
+        let mut order: Vec<usize> = (0..coverages.len()).collect();
+        order.sort_by_key(|&i| core::cmp::Reverse(coverages[i].3.len()));
+        let mut covered: HashSet<usize> = HashSet::new();
+        let mut redundant_flags = vec![true; coverages.len()];
+        for i in order {
+            let mut adds = false;
+            for edge in &coverages[i].3 {
+                adds |= covered.insert(*edge);
+            }
+            redundant_flags[i] = !adds;
+        }
+
+        let mut overlaps = vec![];
+        for (i, first) in coverages.iter().enumerate() {
+            for second in &coverages[i + 1..] {
+                let shared_edges = shared_count(&first.3, &second.3);
+                let union = first.3.len() + second.3.len() - shared_edges;
+                #[allow(clippy::cast_precision_loss)]
+                let jaccard = if union == 0 {
+                    1.0
+                } else {
+                    shared_edges as f64 / union as f64
+                };
+                if jaccard >= overlap_threshold {
+                    overlaps.push(EntryOverlap {
+                        first: first.0,
+                        second: second.0,
+                        shared_edges,
+                        jaccard,
+                    });
+                }
+            }
+        }
+
+        let entries: Vec<EntryCoverage> = coverages
+            .into_iter()
+            .zip(redundant_flags)
+            .map(
+                |((idx, filename, exit_kind, edges), redundant)| EntryCoverage {
+                    idx,
+                    filename,
+                    exit_kind,
+                    edges: edges.len(),
+                    unique_edges: edges.iter().filter(|edge| counts[edge] == 1).count(),
+                    redundant,
+                },
+            )
+            .collect();
+        Self {
+            total_edges: counts.len(),
+            redundant: entries
+                .iter()
+                .filter(|entry| entry.redundant)
+                .map(|entry| entry.idx)
+                .collect(),
+            entries,
+            overlaps,
+        }
+    }
+
+    /// Serializes the report to JSON
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Writes the report as JSON to `path`
+    pub fn write_json<P>(&self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+}
+
+/// The number of elements of two sorted slices in common
+fn shared_count(a: &[usize], b: &[usize]) -> usize {
+    let (mut i, mut j, mut shared) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            core::cmp::Ordering::Less => i += 1,
+            core::cmp::Ordering::Greater => j += 1,
+            core::cmp::Ordering::Equal => {
+                shared += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    shared
+}
+
+/// Runs each entry of the corpus through the executor, and reports the coverage of the entries
+/// as seen by the map observer, in a [`CorpusReport`]
+#[derive(Clone, Debug)]
+pub struct CorpusAnalyzer<O> {
+    map_observer_name: String,
+    overlap_threshold: f64,
+    phantom: PhantomData<O>,
+}
+
+impl<O> CorpusAnalyzer<O>
+where
+    O: MapObserver,
+{
+    /// Creates a new [`CorpusAnalyzer`] for the coverage of the given map observer
+    #[must_use]
+    pub fn new(map_observer: &O) -> Self {
+        Self {
+            map_observer_name: map_observer.name().to_string(),
+            overlap_threshold: DEFAULT_OVERLAP_THRESHOLD,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets the Jaccard index from which pairs of entries get reported as overlapping,
+    /// `0.0` reports all pairs
+    #[must_use]
+    pub fn with_overlap_threshold(mut self, overlap_threshold: f64) -> Self {
+        self.overlap_threshold = overlap_threshold;
+        self
+    }
+
+    /// Runs the whole corpus, and analyzes its coverage
+    pub fn analyze<E, EM, I, OT, S, Z>(
+        &self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        mgr: &mut EM,
+    ) -> Result<CorpusReport, Error>
+    where
+        E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+        I: Input,
+        OT: ObserversTuple<I, S>,
+        S: HasCorpus<I>,
+    {
+        let mut coverages = vec![];
+        for idx in 0..state.corpus().count() {
+            let (input, filename) = {
+                let mut testcase = state.corpus().get(idx)?.borrow_mut();
+                let filename = testcase.filename().clone();
+                (testcase.load_input()?.clone(), filename)
+            };
+
+            executor.observers_mut().pre_exec_all(state, &input)?;
+            let exit_kind = executor.run_target(fuzzer, state, mgr, &input)?;
+            executor
+                .observers_mut()
+                .post_exec_all(state, &input, &exit_kind)?;
+
+            let map = executor
+                .observers()
+                .match_name::<O>(&self.map_observer_name)
+                .ok_or_else(|| Error::KeyNotFound("MapObserver not found".into()))?;
+            let initial = map.initial();
+            let edges = (0..map.usable_count())
+                .filter(|i| *map.get(*i) != initial)
+                .collect();
+            coverages.push((idx, filename, exit_kind, edges));
+        }
+        Ok(CorpusReport::new(coverages, self.overlap_threshold))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CorpusReport;
+    use crate::executors::ExitKind;
+
+    #[test]
+    fn test_corpus_report() {
+        let report = CorpusReport::new(
+            vec![
+                (0, None, ExitKind::Ok, vec![1, 2, 3]),
+                (1, None, ExitKind::Ok, vec![1, 2]),
+                (2, None, ExitKind::Ok, vec![3, 4]),
+            ],
+            0.6,
+        );
+        assert_eq!(report.total_edges, 4);
+        let unique: Vec<usize> = report.entries.iter().map(|e| e.unique_edges).collect();
+        assert_eq!(unique, vec![0, 0, 1]);
+        assert_eq!(report.redundant, vec![1]);
+        assert_eq!(report.overlaps.len(), 1);
+        assert_eq!(report.overlaps[0].first, 0);
+        assert_eq!(report.overlaps[0].second, 1);
+        assert_eq!(report.overlaps[0].shared_edges, 2);
+    }
+}
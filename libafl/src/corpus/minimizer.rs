@@ -40,6 +40,17 @@ impl TopRatedsMetadata {
             map: HashMap::default(),
         }
     }
+
+    /// Forgets the corpus entry `idx`, removed from the corpus, shifting the indexes of the next
+    /// entries
+    pub fn remove_corpus_idx(&mut self, idx: usize) {
+        self.map.retain(|_, corpus_idx| *corpus_idx != idx);
+        for corpus_idx in self.map.values_mut() {
+            if *corpus_idx > idx {
+                *corpus_idx -= 1;
+            }
+        }
+    }
 }
 
 impl Default for TopRatedsMetadata {
@@ -108,8 +119,12 @@ where
         self.base.on_add(state, idx)
     }
 
-    /// Replaces the testcase at the given idx
+    /// Replaces the testcase at the given idx, rating it again if it still has its metadata
     fn on_replace(&self, state: &mut S, idx: usize, testcase: &Testcase<I>) -> Result<(), Error> {
+        let has_meta = state.corpus().get(idx)?.borrow().has_metadata::<M>();
+        if has_meta {
+            self.update_score(state, idx)?;
+        }
         self.base.on_replace(state, idx, testcase)
     }
 
@@ -120,6 +135,9 @@ where
         idx: usize,
         testcase: &Option<Testcase<I>>,
     ) -> Result<(), Error> {
+        if let Some(top_rated) = state.metadata_mut().get_mut::<TopRatedsMetadata>() {
+            top_rated.remove_corpus_idx(idx);
+        }
         self.base.on_remove(state, idx, testcase)
     }
 
@@ -177,6 +195,11 @@ where
                     .map
                     .get(elem)
                 {
+                    // Rated again, e.g. after a replacement
+                    if *old_idx == idx {
+                        new_favoreds.push(*elem);
+                        continue;
+                    }
                     let mut old = state.corpus().get(*old_idx)?.borrow_mut();
                     if factor > F::compute(&mut *old)? {
                         continue;
@@ -32,6 +32,21 @@ pub use minimizer::{
 pub mod powersched;
 pub use powersched::PowerQueueCorpusScheduler;
 
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "std")]
+pub use analysis::{CorpusAnalyzer, CorpusReport};
+
+#[cfg(feature = "std")]
+pub mod verify;
+#[cfg(feature = "std")]
+pub use verify::{CorpusVerifier, VerificationReport};
+
+#[cfg(all(feature = "std", unix))]
+pub mod reproducer;
+#[cfg(all(feature = "std", unix))]
+pub use reproducer::{Reproducer, ReproducerTarget};
+
 use alloc::borrow::ToOwned;
 use core::cell::RefCell;
 
@@ -0,0 +1,260 @@
+//! Standalone reproducers for the objectives found by a campaign, to be attached to bug reports.
+//!
+//! A [`Reproducer`] writes the input of a testcase into a directory, next to a script (or a small
+//! Rust `main`) that runs the target on it exactly like the campaign did, without the fuzzer.
+
+use alloc::{string::String, vec::Vec};
+use std::{
+    ffi::{OsStr, OsString},
+    fmt::Write as _,
+    fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    bolts::AsSlice,
+    corpus::Testcase,
+    executors::command::{InputLocation, StdCommandConfigurator},
+    inputs::{HasTargetBytes, Input},
+    Error,
+};
+
+/// The name of the file holding the input, in the reproducer directory
+pub const REPRODUCER_INPUT: &str = "input";
+
+/// How the campaign ran the target
+#[derive(Debug, Clone)]
+pub enum ReproducerTarget {
+    /// An external program, as run by a [`crate::executors::CommandExecutor`]
+    Command {
+        /// The program
+        program: OsString,
+        /// The arguments
+        args: Vec<OsString>,
+        /// The environment variables set for the program
+        envs: Vec<(OsString, OsString)>,
+        /// The working directory, if set
+        cwd: Option<PathBuf>,
+        /// How the input gets delivered
+        input_location: InputLocation,
+    },
+    /// An in-process harness with the signature of `LLVMFuzzerTestOneInput`, in the (instrumented)
+    /// target the reproducer gets linked with
+    Harness {
+        /// The symbol of the harness
+        symbol: String,
+    },
+}
+
+impl ReproducerTarget {
+    /// The target of a campaign using a `libFuzzer`-style harness
+    #[must_use]
+    pub fn libfuzzer() -> Self {
+        Self::Harness {
+            symbol: "LLVMFuzzerTestOneInput".into(),
+        }
+    }
+
+    /// The target of a campaign running `command`, with the input delivered at `input_location`
+    #[must_use]
+    pub fn command(command: &Command, input_location: InputLocation) -> Self {
+        Self::Command {
+            program: command.get_program().to_os_string(),
+            args: command.get_args().map(OsStr::to_os_string).collect(),
+            envs: command
+                .get_envs()
+                .filter_map(|(key, value)| value.map(|value| (key.into(), value.into())))
+                .collect(),
+            cwd: command.get_current_dir().map(PathBuf::from),
+            input_location,
+        }
+    }
+}
+
+impl From<&StdCommandConfigurator> for ReproducerTarget {
+    fn from(configurator: &StdCommandConfigurator) -> Self {
+        Self::command(&configurator.command, configurator.input_location.clone())
+    }
+}
+
+/// Writes standalone reproducers for testcases
+#[derive(Debug, Clone)]
+pub struct Reproducer {
+    target: ReproducerTarget,
+}
+
+impl Reproducer {
+    /// Creates a new [`Reproducer`] for the given target
+    #[must_use]
+    pub fn new(target: ReproducerTarget) -> Self {
+        Self { target }
+    }
+
+    /// Writes the reproducer of `testcase` into `dir`: the input as [`REPRODUCER_INPUT`], and
+    /// either a `repro.sh` script running the program, or a `repro.rs` to be linked with the target
+    /// of the harness. Returns the path of the script or source.
+    pub fn write<I>(&self, testcase: &mut Testcase<I>, dir: &Path) -> Result<PathBuf, Error>
+    where
+        I: Input + HasTargetBytes,
+    {
+        let input = testcase.load_input()?.target_bytes().as_slice().to_vec();
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(REPRODUCER_INPUT), &input)?;
+
+        let (file, content) = match &self.target {
+            ReproducerTarget::Command {
+                program,
+                args,
+                envs,
+                cwd,
+                input_location,
+            } => (
+                "repro.sh",
+                shell_script(program, args, envs, cwd.as_deref(), input_location),
+            ),
+            ReproducerTarget::Harness { symbol } => ("repro.rs", rust_main(symbol)),
+        };
+        let path = dir.join(file);
+        fs::write(&path, content)?;
+        if file == "repro.sh" {
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+        }
+        Ok(path)
+    }
+}
+
+/// Quotes a word for `sh`
+fn shell_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
+
+/// A script running the program on the input stored next to it
+fn shell_script(
+    program: &OsString,
+    args: &[OsString],
+    envs: &[(OsString, OsString)],
+    cwd: Option<&Path>,
+    input_location: &InputLocation,
+) -> String {
+    let input = format!("\"$REPRO_DIR/{}\"", REPRODUCER_INPUT);
+    let mut script = String::from(
+        "#!/bin/sh\n# Reproducer generated by LibAFL\nREPRO_DIR=\"$(cd \"$(dirname \"$0\")\" && pwd)\"\n",
+    );
+    if let InputLocation::Arg { .. } = input_location {
+        // The command substitution strips the trailing newlines, kept by the `x` appended.
+        // An argument can't hold NUL bytes, for the campaign either.
+        writeln!(
+            script,
+            "REPRO_ARG=\"$(cat {}; printf x)\"\nREPRO_ARG=\"${{REPRO_ARG%x}}\"",
+            input
+        )
+        .unwrap();
+    }
+    if let Some(cwd) = cwd {
+        writeln!(
+            script,
+            "cd {} || exit 1",
+            shell_quote(&cwd.to_string_lossy())
+        )
+        .unwrap();
+    }
+    script.push_str("exec env");
+    for (key, value) in envs {
+        write!(
+            script,
+            " {}",
+            shell_quote(&format!(
+                "{}={}",
+                key.to_string_lossy(),
+                value.to_string_lossy()
+            ))
+        )
+        .unwrap();
+    }
+    write!(script, " {}", shell_quote(&program.to_string_lossy())).unwrap();
+    for (i, arg) in args.iter().enumerate() {
+        let word = match input_location {
+            InputLocation::Arg { argnum } if *argnum == i => "\"$REPRO_ARG\"".into(),
+            InputLocation::File { out_file } if out_file.path.as_os_str() == arg => input.clone(),
+            _ => shell_quote(&arg.to_string_lossy()),
+        };
+        write!(script, " {}", word).unwrap();
+    }
+    match input_location {
+        InputLocation::StdIn => writeln!(script, " < {}", input).unwrap(),
+        _ => script.push_str(" < /dev/null\n"),
+    }
+    script
+}
+
+/// A `main` calling the harness on the input stored next to it, or on the file given as argument
+fn rust_main(symbol: &str) -> String {
+    format!(
+        r#"//! Reproducer generated by LibAFL.
+//! Build it linked with the instrumented target, e.g.
+//! `rustc repro.rs -C link-arg=/path/to/target.a`, then run `./repro [input]`.
+
+extern "C" {{
+    fn {symbol}(data: *const u8, size: usize) -> i32;
+}}
+
+fn main() {{
+    let input = match std::env::args().nth(1) {{
+        Some(path) => std::fs::read(path).expect("could not read the input"),
+        None => include_bytes!("{input}").to_vec(),
+    }};
+    unsafe {{
+        {symbol}(input.as_ptr(), input.len());
+    }}
+}}
+"#,
+        symbol = symbol,
+        input = REPRODUCER_INPUT
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, process::Command};
+
+    use super::{Reproducer, ReproducerTarget};
+    use crate::{corpus::Testcase, executors::command::InputLocation, inputs::BytesInput};
+
+    #[test]
+    fn test_command_reproducer() {
+        let dir =
+            std::env::temp_dir().join(format!("libafl_test_reproducer_{}", std::process::id()));
+        let mut command = Command::new("cat");
+        command.arg("-v");
+        let reproducer = Reproducer::new(ReproducerTarget::command(&command, InputLocation::StdIn));
+        let mut testcase = Testcase::<BytesInput>::new(BytesInput::new(b"it's a crash".to_vec()));
+        let script = reproducer.write(&mut testcase, &dir).unwrap();
+
+        assert_eq!(fs::read(dir.join("input")).unwrap(), b"it's a crash");
+        let script = fs::read_to_string(script).unwrap();
+        assert!(script.ends_with("exec env 'cat' '-v' < \"$REPRO_DIR/input\"\n"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_command_reproducer_arg() {
+        let dir =
+            std::env::temp_dir().join(format!("libafl_test_reproducer_arg_{}", std::process::id()));
+        let mut command = Command::new("sh");
+        command.args(["-c", "printf %s \"$1\"", "sh", "@@"]);
+        let reproducer = Reproducer::new(ReproducerTarget::command(
+            &command,
+            InputLocation::Arg { argnum: 3 },
+        ));
+        let input = b"it's a\n crash\n\n".to_vec();
+        let mut testcase = Testcase::<BytesInput>::new(BytesInput::new(input.clone()));
+        let script = reproducer.write(&mut testcase, &dir).unwrap();
+
+        // The argument is the input, with its trailing newlines
+        let output = Command::new("sh").arg(&script).output().unwrap();
+        assert_eq!(output.stdout, input);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,307 @@
+//! Dry-run verification of a corpus and its objectives: replays every entry without mutating,
+//! and reports the entries that do not behave as when they were found anymore, e.g. after an
+//! update of the target or of the harness.
+
+use alloc::{string::String, vec::Vec};
+use core::marker::PhantomData;
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Corpus,
+    executors::{Executor, ExitKind, HasObservers},
+    feedbacks::MapIndexesMetadata,
+    inputs::Input,
+    observers::{MapObserver, ObserversTuple},
+    state::{HasCorpus, HasMetadata, HasSolutions},
+    Error,
+};
+
+/// The outcome of the replay of an entry
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum VerificationStatus {
+    /// The entry still behaves as when it was found
+    Reproduced,
+    /// The corpus entry does not cover some of the map entries it covered when it was added,
+    /// as recorded in its [`MapIndexesMetadata`]
+    LostCoverage {
+        /// The number of map entries not covered anymore
+        missing: usize,
+        /// The number of map entries covered when the entry was added
+        total: usize,
+    },
+    /// The corpus entry does not cover anything anymore
+    NoCoverage,
+    /// The corpus entry does not exit normally anymore
+    UnexpectedExit,
+    /// The objective exits normally now, it does not crash or time out anymore
+    NotReproduced,
+}
+
+impl VerificationStatus {
+    /// The entry became stale
+    #[must_use]
+    pub fn is_stale(&self) -> bool {
+        *self != Self::Reproduced
+    }
+}
+
+/// The replay of an entry of the corpus, or of the objectives
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EntryVerification {
+    /// The index of the entry in the corpus, or in the objectives
+    pub idx: usize,
+    /// The filename of the entry, if stored on disk
+    pub filename: Option<String>,
+    /// How the execution of the entry finished
+    pub exit_kind: ExitKind,
+    /// The outcome of the replay
+    pub status: VerificationStatus,
+}
+
+/// The machine-readable result of a [`CorpusVerifier`]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerificationReport {
+    /// The replay of each corpus entry
+    pub corpus: Vec<EntryVerification>,
+    /// The replay of each objective
+    pub objectives: Vec<EntryVerification>,
+}
+
+impl VerificationReport {
+    /// The corpus entries that became stale
+    pub fn stale_corpus(&self) -> impl Iterator<Item = &EntryVerification> {
+        self.corpus.iter().filter(|entry| entry.status.is_stale())
+    }
+
+    /// The objectives that do not reproduce anymore
+    pub fn stale_objectives(&self) -> impl Iterator<Item = &EntryVerification> {
+        self.objectives
+            .iter()
+            .filter(|entry| entry.status.is_stale())
+    }
+
+    /// All the entries still reproduce
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.stale_corpus().next().is_none() && self.stale_objectives().next().is_none()
+    }
+
+    /// Serializes the report to JSON
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Writes the report as JSON to `path`
+    pub fn write_json<P>(&self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+}
+
+/// The status of a corpus entry that exited normally, given the map entries it covered when it
+/// was added, if recorded, and whether each map entry is covered now
+fn coverage_status<F>(
+    recorded: Option<&[usize]>,
+    covered: F,
+    any_covered: bool,
+) -> VerificationStatus
+where
+    F: Fn(usize) -> bool,
+{
+    match recorded {
+        Some(recorded) => {
+            let missing = recorded.iter().filter(|idx| !covered(**idx)).count();
+            if missing == 0 {
+                VerificationStatus::Reproduced
+            } else {
+                VerificationStatus::LostCoverage {
+                    missing,
+                    total: recorded.len(),
+                }
+            }
+        }
+        None if any_covered => VerificationStatus::Reproduced,
+        None => VerificationStatus::NoCoverage,
+    }
+}
+
+/// The status of an objective, given how its replay finished: the objectives are the inputs
+/// crashing, timing out or running out of memory, which they still do unless they exit normally.
+/// The objective feedback is not asked again, as its state, e.g. of a `MaxMapFeedback`, already
+/// holds the objective, and would reject it as nothing new.
+fn objective_status(exit_kind: ExitKind) -> VerificationStatus {
+    if exit_kind == ExitKind::Ok {
+        VerificationStatus::NotReproduced
+    } else {
+        VerificationStatus::Reproduced
+    }
+}
+
+/// Replays the corpus and the objectives without mutating, and reports the stale entries in a
+/// [`VerificationReport`].
+///
+/// Corpus entries are checked against the coverage of the map observer, in detail if the
+/// feedback tracked the indexes of the entries (see [`MapIndexesMetadata`]).
+/// Objectives are replayed in a separate executor, which has to survive the crashes of the
+/// target, e.g. an [`crate::executors::InProcessForkExecutor`] or a
+/// [`crate::executors::CommandExecutor`], and are checked against how they exit.
+#[derive(Clone, Debug)]
+pub struct CorpusVerifier<O> {
+    map_observer_name: String,
+    phantom: PhantomData<O>,
+}
+
+impl<O> CorpusVerifier<O>
+where
+    O: MapObserver,
+{
+    /// Creates a new [`CorpusVerifier`] for the coverage of the given map observer
+    #[must_use]
+    pub fn new(map_observer: &O) -> Self {
+        Self {
+            map_observer_name: map_observer.name().to_string(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Runs an input, returning the exit kind
+    fn run<E, EM, I, OT, S, Z>(
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error>
+    where
+        E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+        I: Input,
+        OT: ObserversTuple<I, S>,
+    {
+        executor.observers_mut().pre_exec_all(state, input)?;
+        let exit_kind = executor.run_target(fuzzer, state, mgr, input)?;
+        executor
+            .observers_mut()
+            .post_exec_all(state, input, &exit_kind)?;
+        Ok(exit_kind)
+    }
+
+    /// Replays the whole corpus with `executor`, and all the objectives with
+    /// `objective_executor`, surviving the crashes of the target
+    pub fn verify<E, E2, EM, I, OT, OT2, S, Z>(
+        &self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        objective_executor: &mut E2,
+        state: &mut S,
+        mgr: &mut EM,
+    ) -> Result<VerificationReport, Error>
+    where
+        E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+        E2: Executor<EM, I, S, Z> + HasObservers<I, OT2, S>,
+        I: Input,
+        OT: ObserversTuple<I, S>,
+        OT2: ObserversTuple<I, S>,
+        S: HasCorpus<I> + HasSolutions<I>,
+    {
+        let mut report = VerificationReport::default();
+
+        for idx in 0..state.corpus().count() {
+            let (input, filename, recorded) = {
+                let mut testcase = state.corpus().get(idx)?.borrow_mut();
+                let filename = testcase.filename().clone();
+                let recorded = testcase
+                    .metadata()
+                    .get::<MapIndexesMetadata>()
+                    .map(|meta| meta.list.clone());
+                (testcase.load_input()?.clone(), filename, recorded)
+            };
+
+            let exit_kind = Self::run(fuzzer, executor, state, mgr, &input)?;
+            let status = if exit_kind == ExitKind::Ok {
+                let map = executor
+                    .observers()
+                    .match_name::<O>(&self.map_observer_name)
+                    .ok_or_else(|| Error::KeyNotFound("MapObserver not found".into()))?;
+                let initial = map.initial();
+                coverage_status(
+                    recorded.as_deref(),
+                    |i| i < map.usable_count() && *map.get(i) != initial,
+                    (0..map.usable_count()).any(|i| *map.get(i) != initial),
+                )
+            } else {
+                VerificationStatus::UnexpectedExit
+            };
+            report.corpus.push(EntryVerification {
+                idx,
+                filename,
+                exit_kind,
+                status,
+            });
+        }
+
+        for idx in 0..state.solutions().count() {
+            let (input, filename) = {
+                let mut testcase = state.solutions().get(idx)?.borrow_mut();
+                let filename = testcase.filename().clone();
+                (testcase.load_input()?.clone(), filename)
+            };
+
+            let exit_kind = Self::run(fuzzer, objective_executor, state, mgr, &input)?;
+            report.objectives.push(EntryVerification {
+                idx,
+                filename,
+                status: objective_status(exit_kind),
+                exit_kind,
+            });
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{coverage_status, objective_status, VerificationStatus};
+    use crate::executors::ExitKind;
+
+    #[test]
+    fn test_coverage_status() {
+        let covered = |idx: usize| idx < 4;
+        assert_eq!(
+            coverage_status(Some(&[1, 2, 3]), covered, true),
+            VerificationStatus::Reproduced
+        );
+        assert_eq!(
+            coverage_status(Some(&[2, 3, 4, 5]), covered, true),
+            VerificationStatus::LostCoverage {
+                missing: 2,
+                total: 4
+            }
+        );
+        assert_eq!(
+            coverage_status(None, covered, false),
+            VerificationStatus::NoCoverage
+        );
+    }
+
+    #[test]
+    fn test_objective_status() {
+        assert_eq!(
+            objective_status(ExitKind::Crash),
+            VerificationStatus::Reproduced
+        );
+        assert_eq!(
+            objective_status(ExitKind::Timeout),
+            VerificationStatus::Reproduced
+        );
+        assert_eq!(
+            objective_status(ExitKind::Ok),
+            VerificationStatus::NotReproduced
+        );
+    }
+}
@@ -11,14 +11,17 @@ use crate::bolts::{
 };
 #[cfg(feature = "std")]
 use crate::bolts::{llmp::LlmpConnection, shmem::StdShMemProvider, staterestore::StateRestorer};
+#[cfg(feature = "std")]
+use crate::events::PAUSE_POLL_INTERVAL;
 use crate::{
     bolts::{
         llmp::{self, Flags, LlmpClient, LlmpClientDescription, Tag},
         shmem::ShMemProvider,
     },
     events::{
-        BrokerEventResult, Event, EventConfig, EventFirer, EventManager, EventManagerId,
-        EventProcessor, EventRestarter, HasEventManagerId, ProgressReporter,
+        deserialize_observers_buf, BrokerEventResult, Event, EventConfig, EventFirer, EventManager,
+        EventManagerId, EventProcessor, EventRestarter, HasEventManagerId, ObserverSerdeMode,
+        ProgressReporter,
     },
     executors::{Executor, HasObservers},
     fuzzer::{EvaluatorObservers, ExecutionProcessor},
@@ -30,7 +33,7 @@ use crate::{
 use alloc::string::ToString;
 #[cfg(feature = "std")]
 use core::sync::atomic::{compiler_fence, Ordering};
-use core::{marker::PhantomData, time::Duration};
+use core::{cell::RefCell, marker::PhantomData, time::Duration};
 #[cfg(feature = "std")]
 use core_affinity::CoreId;
 use serde::de::DeserializeOwned;
@@ -112,10 +115,11 @@ where
 
     /// Run forever in the broker
     pub fn broker_loop(&mut self) -> Result<(), Error> {
-        let monitor = &mut self.monitor;
+        // Both hooks need the monitor, the second one to forward its pause requests
+        let monitor = RefCell::new(&mut self.monitor);
         #[cfg(feature = "llmp_compression")]
         let compressor = &self.compressor;
-        self.llmp.loop_forever(
+        self.llmp.loop_forever_with_tick(
             &mut |client_id: u32, tag: Tag, _flags: Flags, msg: &[u8]| {
                 if tag == LLMP_TAG_EVENT_TO_BOTH {
                     #[cfg(not(feature = "llmp_compression"))]
@@ -130,7 +134,7 @@ where
                         msg
                     };
                     let event: Event<I> = postcard::from_bytes(event_bytes)?;
-                    match Self::handle_in_broker(monitor, client_id, &event)? {
+                    match Self::handle_in_broker(*monitor.borrow_mut(), client_id, &event)? {
                         BrokerEventResult::Forward => Ok(llmp::LlmpMsgHookResult::ForwardToClients),
                         BrokerEventResult::Handled => Ok(llmp::LlmpMsgHookResult::Handled),
                     }
@@ -138,6 +142,13 @@ where
                     Ok(llmp::LlmpMsgHookResult::ForwardToClients)
                 }
             },
+            &mut |llmp_out| {
+                if let Some(paused) = monitor.borrow_mut().take_pause_request() {
+                    let serialized = postcard::to_allocvec(&Event::<I>::Pause { paused })?;
+                    llmp_out.send_buf(LLMP_TAG_EVENT_TO_BOTH, &serialized)?;
+                }
+                Ok(())
+            },
             Some(Duration::from_millis(5)),
         );
 
@@ -188,6 +199,24 @@ where
                 monitor.display(event.name().to_string(), client_id);
                 Ok(BrokerEventResult::Handled)
             }
+            Event::UpdateStability {
+                stability,
+                phantom: _,
+            } => {
+                let client = monitor.client_stats_mut_for(client_id);
+                client.update_stability(*stability);
+                monitor.display(event.name().to_string(), client_id);
+                Ok(BrokerEventResult::Handled)
+            }
+            Event::UpdateCoverageSummary {
+                summary,
+                phantom: _,
+            } => {
+                let client = monitor.client_stats_mut_for(client_id);
+                client.update_coverage_summary(summary.clone());
+                monitor.display(event.name().to_string(), client_id);
+                Ok(BrokerEventResult::Handled)
+            }
             #[cfg(feature = "introspection")]
             Event::UpdatePerfMonitor {
                 time,
@@ -212,10 +241,17 @@ where
                 // Correctly handled the event
                 Ok(BrokerEventResult::Handled)
             }
-            Event::Objective { objective_size } => {
+            Event::Pause { paused: _ } => Ok(BrokerEventResult::Handled),
+            Event::Objective {
+                objective_size,
+                input_hash,
+                exit_kind,
+                signal,
+            } => {
                 let client = monitor.client_stats_mut_for(client_id);
                 client.update_objective_size(*objective_size as u64);
                 monitor.display(event.name().to_string(), client_id);
+                monitor.on_objective(client_id, *input_hash, *exit_kind, *signal);
                 Ok(BrokerEventResult::Handled)
             }
             Event::Log {
@@ -223,10 +259,7 @@ where
                 message,
                 phantom: _,
             } => {
-                let (_, _) = (severity_level, message);
-                // TODO rely on Monitor
-                #[cfg(feature = "std")]
-                println!("[LOG {}]: {}", severity_level, message);
+                monitor.log(*severity_level, message, client_id);
                 Ok(BrokerEventResult::Handled)
             } //_ => Ok(BrokerEventResult::Forward),
         }
@@ -247,6 +280,9 @@ where
     #[cfg(feature = "llmp_compression")]
     compressor: GzipCompressor,
     configuration: EventConfig,
+    observer_serde_mode: ObserverSerdeMode,
+    /// Whether the broker paused this client, see [`Event::Pause`]
+    paused: bool,
     phantom: PhantomData<(I, OT, S)>,
 }
 
@@ -275,6 +311,8 @@ where
             #[cfg(feature = "llmp_compression")]
             compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
             configuration,
+            observer_serde_mode: ObserverSerdeMode::Full,
+            paused: false,
             phantom: PhantomData,
         })
     }
@@ -293,6 +331,8 @@ where
             #[cfg(feature = "llmp_compression")]
             compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
             configuration,
+            observer_serde_mode: ObserverSerdeMode::Full,
+            paused: false,
             phantom: PhantomData,
         })
     }
@@ -309,6 +349,8 @@ where
             #[cfg(feature = "llmp_compression")]
             compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
             configuration,
+            observer_serde_mode: ObserverSerdeMode::Full,
+            paused: false,
             phantom: PhantomData,
         })
     }
@@ -329,16 +371,69 @@ where
             #[cfg(feature = "llmp_compression")]
             compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
             configuration,
+            observer_serde_mode: ObserverSerdeMode::Full,
+            paused: false,
             phantom: PhantomData,
         })
     }
 
+    /// Sets how the observers are serialized in the [`Event::NewTestcase`] events of this client.
+    /// The other clients read the events of any [`ObserverSerdeMode`].
+    pub fn set_observer_serde_mode(&mut self, observer_serde_mode: ObserverSerdeMode) {
+        self.observer_serde_mode = observer_serde_mode;
+    }
+
     /// Write the config for a client [`EventManager`] to env vars, a new client can reattach using [`LlmpEventManager::existing_client_from_env()`].
     #[cfg(feature = "std")]
     pub fn to_env(&self, env_name: &str) {
         self.llmp.to_env(env_name).unwrap();
     }
 
+    /// Receives the pending events, and handles them
+    fn receive_and_handle<E, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        executor: &mut E,
+    ) -> Result<usize, Error>
+    where
+        OT: ObserversTuple<I, S> + DeserializeOwned,
+        E: Executor<Self, I, S, Z> + HasObservers<I, OT, S>,
+        Z: ExecutionProcessor<I, OT, S> + EvaluatorObservers<I, OT, S>,
+    {
+        // TODO: Get around local event copy by moving handle_in_client
+        let mut events = vec![];
+        let self_id = self.llmp.sender.id;
+        while let Some((client_id, tag, _flags, msg)) = self.llmp.recv_buf_with_flags()? {
+            assert!(
+                tag != _LLMP_TAG_EVENT_TO_BROKER,
+                "EVENT_TO_BROKER parcel should not have arrived in the client!"
+            );
+
+            if client_id == self_id {
+                continue;
+            }
+            #[cfg(not(feature = "llmp_compression"))]
+            let event_bytes = msg;
+            #[cfg(feature = "llmp_compression")]
+            let compressed;
+            #[cfg(feature = "llmp_compression")]
+            let event_bytes = if _flags & LLMP_FLAG_COMPRESSED == LLMP_FLAG_COMPRESSED {
+                compressed = self.compressor.decompress(msg)?;
+                &compressed
+            } else {
+                msg
+            };
+            let event: Event<I> = postcard::from_bytes(event_bytes)?;
+            events.push((client_id, event));
+        }
+        let count = events.len();
+        events.drain(..).try_for_each(|(client_id, event)| {
+            self.handle_in_client(fuzzer, executor, state, client_id, event)
+        })?;
+        Ok(count)
+    }
+
     // Handle arriving events in the client
     #[allow(clippy::unused_self)]
     fn handle_in_client<E, Z>(
@@ -354,6 +449,12 @@ where
         E: Executor<Self, I, S, Z> + HasObservers<I, OT, S>,
         Z: ExecutionProcessor<I, OT, S> + EvaluatorObservers<I, OT, S>,
     {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            event = event.name(),
+            client_id = _client_id,
+            "handling event in client"
+        );
         match event {
             Event::NewTestcase {
                 input,
@@ -373,7 +474,7 @@ where
                 let _res = if client_config.match_with(&self.configuration)
                     && observers_buf.is_some()
                 {
-                    let observers: OT = postcard::from_bytes(observers_buf.as_ref().unwrap())?;
+                    let observers: OT = deserialize_observers_buf(observers_buf.as_ref().unwrap())?;
                     fuzzer.process_execution(state, self, input, &observers, &exit_kind, false)?
                 } else {
                     fuzzer.evaluate_input_with_observers(state, executor, self, input, false)?
@@ -384,6 +485,15 @@ where
                 }
                 Ok(())
             }
+            Event::Pause { paused } => {
+                #[cfg(feature = "std")]
+                println!(
+                    "{} by the broker",
+                    if paused { "Paused" } else { "Resumed" }
+                );
+                self.paused = paused;
+                Ok(())
+            }
             _ => Err(Error::Unknown(format!(
                 "Received illegal message that message should not have arrived: {:?}.",
                 event.name()
@@ -401,6 +511,8 @@ where
 {
     #[cfg(feature = "llmp_compression")]
     fn fire<S2>(&mut self, _state: &mut S2, event: Event<I>) -> Result<(), Error> {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(event = event.name(), "firing event");
         let serialized = postcard::to_allocvec(&event)?;
         let flags: Flags = LLMP_FLAG_INITIALIZED;
 
@@ -421,6 +533,8 @@ where
 
     #[cfg(not(feature = "llmp_compression"))]
     fn fire<S2>(&mut self, _state: &mut S2, event: Event<I>) -> Result<(), Error> {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(event = event.name(), "firing event");
         let serialized = postcard::to_allocvec(&event)?;
         self.llmp.send_buf(LLMP_TAG_EVENT_TO_BOTH, &serialized)?;
         Ok(())
@@ -429,6 +543,10 @@ where
     fn configuration(&self) -> EventConfig {
         self.configuration
     }
+
+    fn observer_serde_mode(&self) -> ObserverSerdeMode {
+        self.observer_serde_mode
+    }
 }
 
 impl<I, OT, S, SP> EventRestarter<S> for LlmpEventManager<I, OT, S, SP>
@@ -455,36 +573,13 @@ where
     Z: ExecutionProcessor<I, OT, S> + EvaluatorObservers<I, OT, S>, //CE: CustomEvent<I>,
 {
     fn process(&mut self, fuzzer: &mut Z, state: &mut S, executor: &mut E) -> Result<usize, Error> {
-        // TODO: Get around local event copy by moving handle_in_client
-        let mut events = vec![];
-        let self_id = self.llmp.sender.id;
-        while let Some((client_id, tag, _flags, msg)) = self.llmp.recv_buf_with_flags()? {
-            assert!(
-                tag != _LLMP_TAG_EVENT_TO_BROKER,
-                "EVENT_TO_BROKER parcel should not have arrived in the client!"
-            );
-
-            if client_id == self_id {
-                continue;
-            }
-            #[cfg(not(feature = "llmp_compression"))]
-            let event_bytes = msg;
-            #[cfg(feature = "llmp_compression")]
-            let compressed;
-            #[cfg(feature = "llmp_compression")]
-            let event_bytes = if _flags & LLMP_FLAG_COMPRESSED == LLMP_FLAG_COMPRESSED {
-                compressed = self.compressor.decompress(msg)?;
-                &compressed
-            } else {
-                msg
-            };
-            let event: Event<I> = postcard::from_bytes(event_bytes)?;
-            events.push((client_id, event));
+        let mut count = self.receive_and_handle(fuzzer, state, executor)?;
+        // Wait for the broker to resume this client, before the next execution
+        while self.paused {
+            #[cfg(feature = "std")]
+            std::thread::sleep(PAUSE_POLL_INTERVAL);
+            count += self.receive_and_handle(fuzzer, state, executor)?;
         }
-        let count = events.len();
-        events.drain(..).try_for_each(|(client_id, event)| {
-            self.handle_in_client(fuzzer, executor, state, client_id, event)
-        })?;
         Ok(count)
     }
 }
@@ -563,6 +658,10 @@ where
     fn configuration(&self) -> EventConfig {
         self.llmp_mgr.configuration()
     }
+
+    fn observer_serde_mode(&self) -> ObserverSerdeMode {
+        self.llmp_mgr.observer_serde_mode()
+    }
 }
 
 #[cfg(feature = "std")]
@@ -636,6 +735,8 @@ const _ENV_FUZZER_SENDER: &str = "_AFL_ENV_FUZZER_SENDER";
 const _ENV_FUZZER_RECEIVER: &str = "_AFL_ENV_FUZZER_RECEIVER";
 /// The llmp (2 way) connection from a fuzzer to the broker (broadcasting all other fuzzer messages)
 const _ENV_FUZZER_BROKER_CLIENT_INITIAL: &str = "_AFL_ENV_FUZZER_BROKER_CLIENT";
+/// The core the respawned fuzzer instances bind to, as they may not inherit the affinity
+const _ENV_FUZZER_CORE_ID: &str = "_AFL_ENV_FUZZER_CORE_ID";
 
 #[cfg(feature = "std")]
 impl<I, OT, S, SP> LlmpRestartingEventManager<I, OT, S, SP>
@@ -731,6 +832,9 @@ where
     shmem_provider: SP,
     /// The configuration
     configuration: EventConfig,
+    /// How the observers are serialized in the [`Event::NewTestcase`] events of the client
+    #[builder(default = ObserverSerdeMode::Full)]
+    observer_serde_mode: ObserverSerdeMode,
     /// The monitor to use
     #[builder(default = None)]
     monitor: Option<MT>,
@@ -815,6 +919,12 @@ where
                 }
                 ManagerKind::Client { cpu_core } => {
                     // We are a client
+                    // Bind before allocating the maps, for the pages to be on the NUMA node of the core
+                    if let Some(core_id) = cpu_core {
+                        println!("Setting core affinity to {:?}", core_id);
+                        core_affinity::set_for_current(core_id);
+                        std::env::set_var(_ENV_FUZZER_CORE_ID, core_id.id.to_string());
+                    }
                     let mgr = LlmpEventManager::<I, OT, S, SP>::new_on_port(
                         self.shmem_provider.clone(),
                         self.broker_port,
@@ -825,11 +935,6 @@ where
                 }
             };
 
-            if let Some(core_id) = core_id {
-                println!("Setting core affinity to {:?}", core_id);
-                core_affinity::set_for_current(core_id);
-            }
-
             // We are the fuzzer respawner in a llmp client
             mgr.to_env(_ENV_FUZZER_BROKER_CLIENT_INITIAL);
 
@@ -890,7 +995,10 @@ where
             (
                 StateRestorer::from_env(&mut self.shmem_provider, _ENV_FUZZER_SENDER)?,
                 self.shmem_provider.clone(),
-                None,
+                std::env::var(_ENV_FUZZER_CORE_ID)
+                    .ok()
+                    .and_then(|id| id.parse().ok())
+                    .map(|id| core_affinity::CoreId { id }),
             )
         };
 
@@ -924,6 +1032,8 @@ where
         };
         // We reset the staterestorer, the next staterestorer and receiver (after crash) will reuse the page from the initial message.
         mgr.staterestorer.reset();
+        mgr.llmp_mgr
+            .set_observer_serde_mode(self.observer_serde_mode);
 
         /* TODO: Not sure if this is needed
         // We commit an empty NO_RESTART message to this buf, against infinite loops,
@@ -0,0 +1,218 @@
+//! A [`tracing`] subscriber sending the spans and events of the client to the broker, as
+//! [`Event::Log`](super::Event::Log), so they show up in the log pane of the monitor.
+//!
+//! Install it in each client with [`init_event_log_subscriber`]; the collected logs get sent
+//! along with the periodic stats, see [`ProgressReporter`](super::ProgressReporter).
+
+use alloc::{string::String, vec::Vec};
+use core::{
+    cell::RefCell,
+    fmt::{self, Write as _},
+    sync::atomic::{AtomicU64, Ordering},
+};
+use std::sync::Mutex;
+
+use hashbrown::HashMap;
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event, Level, Metadata, Subscriber,
+};
+
+use crate::{events::LogSeverity, Error};
+
+/// The maximum number of logs kept between two reports, the older ones get dropped
+pub const MAX_PENDING_LOGS: usize = 1024;
+
+std::thread_local! {
+    /// The spans entered on this thread
+    static CURRENT_SPANS: RefCell<Vec<u64>> = RefCell::new(Vec::new());
+}
+
+/// Takes the logs collected by the current subscriber since the last call, if it is an
+/// [`EventLogSubscriber`]
+#[must_use]
+pub fn take_pending_logs() -> Vec<(LogSeverity, String)> {
+    tracing::dispatcher::get_default(|dispatch| {
+        dispatch
+            .downcast_ref::<EventLogSubscriber>()
+            .map(EventLogSubscriber::take_logs)
+            .unwrap_or_default()
+    })
+}
+
+/// Formats the fields of spans and events as `message key=value ...`
+struct FieldFormatter<'a>(&'a mut String);
+
+impl Visit for FieldFormatter<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        if field.name() == "message" {
+            write!(self.0, "{:?}", value).unwrap();
+        } else {
+            write!(self.0, "{}={:?}", field.name(), value).unwrap();
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.record_debug(field, &format_args!("{}", value));
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+}
+
+/// A [`Subscriber`] collecting the events up to a level, prefixed with the spans they happened in
+#[derive(Debug)]
+pub struct EventLogSubscriber {
+    max_level: Level,
+    next_id: AtomicU64,
+    /// The description of the open spans, and their reference count
+    spans: Mutex<HashMap<u64, (String, usize)>>,
+    /// The logs not sent to the broker yet
+    pending: Mutex<Vec<(LogSeverity, String)>>,
+}
+
+impl EventLogSubscriber {
+    /// Creates a new [`EventLogSubscriber`], collecting the events up to `max_level`
+    #[must_use]
+    pub fn new(max_level: Level) -> Self {
+        Self {
+            max_level,
+            next_id: AtomicU64::new(1),
+            spans: Mutex::new(HashMap::new()),
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Takes the logs collected since the last call
+    pub fn take_logs(&self) -> Vec<(LogSeverity, String)> {
+        core::mem::take(&mut *self.pending.lock().unwrap())
+    }
+}
+
+impl Subscriber for EventLogSubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        *metadata.level() <= self.max_level
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut fields = String::new();
+        span.record(&mut FieldFormatter(&mut fields));
+        let desc = if fields.is_empty() {
+            span.metadata().name().into()
+        } else {
+            format!("{}{{{}}}", span.metadata().name(), fields)
+        };
+        self.spans.lock().unwrap().insert(id, (desc, 1));
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        if let Some((desc, _)) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            let mut fields = String::new();
+            values.record(&mut FieldFormatter(&mut fields));
+            write!(desc, "{{{}}}", fields).unwrap();
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut message = String::new();
+        {
+            let spans = self.spans.lock().unwrap();
+            CURRENT_SPANS.with(|current| {
+                for id in current.borrow().iter() {
+                    if let Some((desc, _)) = spans.get(id) {
+                        write!(message, "{}: ", desc).unwrap();
+                    }
+                }
+            });
+        }
+        let mut fields = String::new();
+        event.record(&mut FieldFormatter(&mut fields));
+        message.push_str(&fields);
+
+        let severity = match *event.metadata().level() {
+            Level::ERROR => LogSeverity::Error,
+            Level::WARN => LogSeverity::Warn,
+            Level::INFO => LogSeverity::Info,
+            _ => LogSeverity::Debug,
+        };
+        let mut pending = self.pending.lock().unwrap();
+        if pending.len() >= MAX_PENDING_LOGS {
+            pending.remove(0);
+        }
+        pending.push((severity, message));
+    }
+
+    fn enter(&self, span: &Id) {
+        CURRENT_SPANS.with(|current| current.borrow_mut().push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &Id) {
+        CURRENT_SPANS.with(|current| {
+            let mut current = current.borrow_mut();
+            if let Some(pos) = current.iter().rposition(|id| *id == span.into_u64()) {
+                current.remove(pos);
+            }
+        });
+    }
+
+    fn clone_span(&self, span: &Id) -> Id {
+        if let Some((_, refs)) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            *refs += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        let mut spans = self.spans.lock().unwrap();
+        let id = span.into_u64();
+        match spans.get_mut(&id) {
+            Some((_, refs)) if *refs > 1 => {
+                *refs -= 1;
+                false
+            }
+            Some(_) => {
+                spans.remove(&id);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Installs an [`EventLogSubscriber`] as the global subscriber of this process, sending the
+/// events up to `max_level` to the broker
+pub fn init_event_log_subscriber(max_level: Level) -> Result<(), Error> {
+    tracing::subscriber::set_global_default(EventLogSubscriber::new(max_level))
+        .map_err(|err| Error::IllegalState(format!("Could not set the subscriber: {}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing::Level;
+
+    use super::{take_pending_logs, EventLogSubscriber};
+
+    #[test]
+    fn test_event_log_subscriber() {
+        tracing::subscriber::with_default(EventLogSubscriber::new(Level::DEBUG), || {
+            let _span = tracing::debug_span!("fuzz_one", corpus_idx = 3).entered();
+            tracing::info!(stage = "mutational", "never firing");
+            tracing::trace!("filtered out");
+            let logs = take_pending_logs();
+            assert_eq!(logs.len(), 1);
+            assert_eq!(
+                logs[0].1,
+                "fuzz_one{corpus_idx=3}: never firing stage=\"mutational\""
+            );
+        });
+    }
+}
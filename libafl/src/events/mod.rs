@@ -4,12 +4,16 @@ pub mod simple;
 pub use simple::*;
 pub mod llmp;
 pub use llmp::*;
+pub mod observer_serde;
+pub use observer_serde::*;
+
+#[cfg(all(feature = "tracing", feature = "std"))]
+pub mod log_subscriber;
+#[cfg(all(feature = "tracing", feature = "std"))]
+pub use log_subscriber::{init_event_log_subscriber, EventLogSubscriber};
 
 use ahash::AHasher;
-use alloc::{
-    string::{String, ToString},
-    vec::Vec,
-};
+use alloc::{string::String, vec::Vec};
 use core::{fmt, hash::Hasher, marker::PhantomData, time::Duration};
 use serde::{Deserialize, Serialize};
 
@@ -20,7 +24,7 @@ use crate::{
     bolts::current_time,
     executors::ExitKind,
     inputs::Input,
-    monitors::UserStats,
+    monitors::{CoverageSummary, UserStats},
     observers::ObserversTuple,
     state::{HasClientPerfMonitor, HasExecutions},
     Error,
@@ -39,6 +43,10 @@ use crate::monitors::ClientPerfMonitor;
 #[cfg(feature = "introspection")]
 use alloc::boxed::Box;
 
+/// How often a paused fuzzer checks whether it got resumed, see [`Event::Pause`]
+#[cfg(feature = "std")]
+pub(crate) const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 /// The log event severity
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub enum LogSeverity {
@@ -204,6 +212,20 @@ where
         /// [`PhantomData`]
         phantom: PhantomData<I>,
     },
+    /// New stability of the coverage map, measured during the calibration
+    UpdateStability {
+        /// The ratio of the covered map entries behaving deterministically
+        stability: f32,
+        /// [`PhantomData`]
+        phantom: PhantomData<I>,
+    },
+    /// New summary of the coverage map of a client, see [`crate::stages::CoverageSummaryStage`]
+    UpdateCoverageSummary {
+        /// The downsampled coverage map
+        summary: CoverageSummary,
+        /// [`PhantomData`]
+        phantom: PhantomData<I>,
+    },
     /// New monitor with performance monitor.
     #[cfg(feature = "introspection")]
     UpdatePerfMonitor {
@@ -217,10 +239,21 @@ where
         /// phantomm data
         phantom: PhantomData<I>,
     },
+    /// Pauses or resumes the clients, sent by the broker, see [`crate::monitors::Monitor::take_pause_request`]
+    Pause {
+        /// Whether the clients should pause, or resume
+        paused: bool,
+    },
     /// A new objective was found
     Objective {
         /// Objective corpus size
         objective_size: usize,
+        /// The hash of the objective input, see [`crate::inputs::input_hash`]
+        input_hash: u64,
+        /// How the execution of the objective input finished
+        exit_kind: ExitKind,
+        /// The signal the target crashed with, if known
+        signal: Option<i32>,
     },
     /// Write a new log
     Log {
@@ -262,6 +295,14 @@ where
                 name: _,
                 value: _,
                 phantom: _,
+            }
+            | Event::UpdateStability {
+                stability: _,
+                phantom: _,
+            }
+            | Event::UpdateCoverageSummary {
+                summary: _,
+                phantom: _,
             } => "Stats",
             #[cfg(feature = "introspection")]
             Event::UpdatePerfMonitor {
@@ -270,7 +311,8 @@ where
                 introspection_monitor: _,
                 phantom: _,
             } => "PerfMonitor",
-            Event::Objective { objective_size: _ } => "Objective",
+            Event::Pause { paused: _ } => "Pause",
+            Event::Objective { .. } => "Objective",
             Event::Log {
                 severity_level: _,
                 message: _,
@@ -321,7 +363,12 @@ where
     where
         OT: ObserversTuple<I, S> + Serialize,
     {
-        Ok(postcard::to_allocvec(observers)?)
+        serialize_observers_buf(observers, self.observer_serde_mode())
+    }
+
+    /// How the observers are serialized in the [`Event::NewTestcase`] events
+    fn observer_serde_mode(&self) -> ObserverSerdeMode {
+        ObserverSerdeMode::Full
     }
 
     /// Get the configuration
@@ -362,13 +409,17 @@ where
                 },
             )?;
 
-            if let Some(x) = state.stability() {
-                let stability = f64::from(*x);
+            // Send the logs collected by the `EventLogSubscriber`, if installed
+            #[cfg(all(feature = "tracing", feature = "std"))]
+            for (severity_level, message) in log_subscriber::take_pending_logs() {
+                self.log(state, severity_level, message)?;
+            }
+
+            if let Some(stability) = *state.stability() {
                 self.fire(
                     state,
-                    Event::UpdateUserStats {
-                        name: "stability".to_string(),
-                        value: UserStats::Float(stability),
+                    Event::UpdateStability {
+                        stability,
                         phantom: PhantomData,
                     },
                 )?;
@@ -426,7 +477,7 @@ pub trait EventProcessor<E, I, S, Z> {
     where
         OT: ObserversTuple<I, S> + serde::de::DeserializeOwned,
     {
-        Ok(postcard::from_bytes(observers_buf)?)
+        deserialize_observers_buf(observers_buf)
     }
 }
 /// The id of this [`EventManager`].
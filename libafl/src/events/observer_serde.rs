@@ -0,0 +1,246 @@
+//! The serialization of the observers sent along with the [`super::Event::NewTestcase`] events.
+//!
+//! The serialized map observers mostly hold zeros, and their size makes the bandwidth of the
+//! events the bottleneck with large maps and many clients. The [`ObserverSerdeMode::Sparse`] mode
+//! only sends the bytes around the non-zero entries. The first byte of a buffer tells its mode,
+//! so that the receivers read the buffers of the clients of any mode.
+//!
+//! The sparse encoding is not a diff against the observers sent before: each buffer stands on its
+//! own. The broker forwards the events to all the clients, including the ones joining or
+//! restarting later, without keeping state per client, so a receiver can't rely on the previous
+//! buffers of a sender.
+//! With `llmp_compression`, the [`super::llmp::LlmpEventManager`] compresses the large events
+//! anyway, the observers included, so there is no compressed mode.
+
+use alloc::vec::Vec;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::Error;
+
+/// The minimum number of zeros in a row ending a run of other bytes, in the sparse encoding
+const SPARSE_MIN_ZEROS: usize = 4;
+
+/// The maximum length of the observers decoded from a sparse buffer, against buffers claiming
+/// huge runs of zeros
+pub const MAX_SPARSE_DECODED_LEN: usize = 1 << 28;
+
+/// How the observers are serialized in the [`super::Event::NewTestcase`] events
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObserverSerdeMode {
+    /// The observers serialized with `postcard`
+    Full,
+    /// The observers serialized with `postcard`, with their runs of zeros replaced by their length
+    Sparse,
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for ObserverSerdeMode {
+    fn default() -> Self {
+        Self::Full
+    }
+}
+
+impl ObserverSerdeMode {
+    /// The first byte of the buffers of this mode
+    fn tag(self) -> u8 {
+        match self {
+            Self::Full => 0,
+            Self::Sparse => 1,
+        }
+    }
+
+    /// The mode of the buffers starting with `tag`
+    fn from_tag(tag: u8) -> Result<Self, Error> {
+        match tag {
+            0 => Ok(Self::Full),
+            1 => Ok(Self::Sparse),
+            _ => Err(Error::IllegalArgument(format!(
+                "Unknown observers serialization mode {}",
+                tag
+            ))),
+        }
+    }
+}
+
+/// Appends `value` to `buf` as a LEB128 varint
+#[allow(clippy::cast_possible_truncation)]
+fn write_varint(buf: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// Reads a LEB128 varint of `buf` at `pos`, moving `pos` after it
+fn read_varint(buf: &[u8], pos: &mut usize) -> Result<usize, Error> {
+    let mut value = 0_usize;
+    let mut shift = 0;
+    loop {
+        let byte = *buf
+            .get(*pos)
+            .ok_or_else(|| Error::IllegalArgument("Truncated sparse buffer".into()))?;
+        *pos += 1;
+        if shift >= usize::BITS {
+            return Err(Error::IllegalArgument(
+                "Invalid varint in sparse buffer".into(),
+            ));
+        }
+        value |= usize::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+/// Encodes `bytes` as a sequence of runs: the number of zeros, as a varint, then the number of
+/// other bytes, as a varint, and these bytes. Runs of other bytes only end at
+/// [`SPARSE_MIN_ZEROS`] zeros in a row.
+#[must_use]
+pub fn encode_sparse(bytes: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let zeros = bytes[pos..].iter().take_while(|byte| **byte == 0).count();
+        pos += zeros;
+        let mut end = pos;
+        while end < bytes.len() {
+            let next_zeros = bytes[end..]
+                .iter()
+                .take(SPARSE_MIN_ZEROS)
+                .take_while(|byte| **byte == 0)
+                .count();
+            if next_zeros == SPARSE_MIN_ZEROS || end + next_zeros == bytes.len() {
+                break;
+            }
+            end += next_zeros.max(1);
+        }
+        write_varint(&mut encoded, zeros);
+        write_varint(&mut encoded, end - pos);
+        encoded.extend_from_slice(&bytes[pos..end]);
+        pos = end;
+    }
+    encoded
+}
+
+/// Decodes the bytes encoded by [`encode_sparse`], failing if they are longer than `max_len`
+pub fn decode_sparse(encoded: &[u8], max_len: usize) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+    let mut pos = 0;
+    while pos < encoded.len() {
+        let zeros = read_varint(encoded, &mut pos)?;
+        let len = read_varint(encoded, &mut pos)?;
+        let literal = pos
+            .checked_add(len)
+            .and_then(|end| encoded.get(pos..end))
+            .ok_or_else(|| Error::IllegalArgument("Truncated sparse buffer".into()))?;
+        bytes
+            .len()
+            .checked_add(zeros)
+            .and_then(|decoded_len| decoded_len.checked_add(len))
+            .filter(|decoded_len| *decoded_len <= max_len)
+            .ok_or_else(|| {
+                Error::IllegalArgument(format!(
+                    "Sparse buffer decoding to more than {} bytes",
+                    max_len
+                ))
+            })?;
+        bytes.resize(bytes.len() + zeros, 0);
+        bytes.extend_from_slice(literal);
+        pos += len;
+    }
+    Ok(bytes)
+}
+
+/// Serializes the `observers` in the `mode`, for an [`super::Event::NewTestcase`]
+pub fn serialize_observers_buf<OT>(
+    observers: &OT,
+    mode: ObserverSerdeMode,
+) -> Result<Vec<u8>, Error>
+where
+    OT: Serialize,
+{
+    let serialized = postcard::to_allocvec(observers)?;
+    let mut buf = vec![mode.tag()];
+    match mode {
+        ObserverSerdeMode::Full => buf.extend_from_slice(&serialized),
+        ObserverSerdeMode::Sparse => buf.extend_from_slice(&encode_sparse(&serialized)),
+    }
+    Ok(buf)
+}
+
+/// Deserializes the observers of an [`super::Event::NewTestcase`], serialized in any mode by
+/// [`serialize_observers_buf`]
+pub fn deserialize_observers_buf<OT>(buf: &[u8]) -> Result<OT, Error>
+where
+    OT: DeserializeOwned,
+{
+    let (tag, serialized) = buf
+        .split_first()
+        .ok_or_else(|| Error::IllegalArgument("Empty observers buffer".into()))?;
+    match ObserverSerdeMode::from_tag(*tag)? {
+        ObserverSerdeMode::Full => Ok(postcard::from_bytes(serialized)?),
+        ObserverSerdeMode::Sparse => Ok(postcard::from_bytes(&decode_sparse(
+            serialized,
+            MAX_SPARSE_DECODED_LEN,
+        )?)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        bolts::tuples::{tuple_list, tuple_list_type, Named},
+        events::observer_serde::{
+            decode_sparse, deserialize_observers_buf, encode_sparse, serialize_observers_buf,
+            ObserverSerdeMode, MAX_SPARSE_DECODED_LEN,
+        },
+        observers::StdMapObserver,
+    };
+
+    #[test]
+    fn test_sparse_roundtrip() {
+        for bytes in [
+            vec![],
+            vec![0; 10],
+            vec![1, 2, 3],
+            vec![0, 0, 7, 0, 0, 0, 0, 0, 9, 0, 1, 0, 0],
+            (0..1000_u32)
+                .map(|i| u8::from(i % 7 == 0))
+                .collect::<Vec<_>>(),
+        ] {
+            assert_eq!(decode_sparse(&encode_sparse(&bytes), 1000).unwrap(), bytes);
+        }
+        assert!(encode_sparse(&vec![0; 65536]).len() < 8);
+    }
+
+    #[test]
+    fn test_sparse_max_len() {
+        let encoded = encode_sparse(&[0, 0, 0, 0, 0, 0, 0, 0, 1, 2]);
+        assert!(decode_sparse(&encoded, 10).is_ok());
+        assert!(decode_sparse(&encoded, 9).is_err());
+        // a run of `usize::MAX` zeros
+        let mut huge = vec![0xff; 9];
+        huge.extend_from_slice(&[0x01, 0x00]);
+        assert!(decode_sparse(&huge, MAX_SPARSE_DECODED_LEN).is_err());
+    }
+
+    #[test]
+    fn test_observers_buf_modes() {
+        let mut map = vec![0_u8; 4096];
+        map[100] = 1;
+        let observers = tuple_list!(StdMapObserver::new_owned("map", map));
+        let full = serialize_observers_buf(&observers, ObserverSerdeMode::Full).unwrap();
+        let sparse = serialize_observers_buf(&observers, ObserverSerdeMode::Sparse).unwrap();
+        assert!(sparse.len() * 100 < full.len());
+        for buf in [full, sparse] {
+            let deserialized: tuple_list_type!(StdMapObserver<u8>) =
+                deserialize_observers_buf(&buf).unwrap();
+            assert_eq!(deserialized.0.name(), "map");
+        }
+    }
+}
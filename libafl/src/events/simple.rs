@@ -27,6 +27,8 @@ use crate::{
 };
 
 use super::ProgressReporter;
+#[cfg(feature = "std")]
+use super::PAUSE_POLL_INTERVAL;
 
 /// The llmp connection from the actual fuzzer to the process supervising it
 const _ENV_FUZZER_SENDER: &str = "_AFL_ENV_FUZZER_SENDER";
@@ -84,6 +86,13 @@ where
             let event = self.events.pop().unwrap();
             self.handle_in_client(state, event)?;
         }
+        // Wait for the monitor to resume the fuzzer, before the next execution
+        if self.monitor.take_pause_request() == Some(true) {
+            while self.monitor.take_pause_request() != Some(false) {
+                #[cfg(feature = "std")]
+                std::thread::sleep(PAUSE_POLL_INTERVAL);
+            }
+        }
         Ok(count)
     }
 }
@@ -171,6 +180,24 @@ where
                 monitor.display(event.name().to_string(), 0);
                 Ok(BrokerEventResult::Handled)
             }
+            Event::UpdateStability {
+                stability,
+                phantom: _,
+            } => {
+                monitor.client_stats_mut_for(0).update_stability(*stability);
+                monitor.display(event.name().to_string(), 0);
+                Ok(BrokerEventResult::Handled)
+            }
+            Event::UpdateCoverageSummary {
+                summary,
+                phantom: _,
+            } => {
+                monitor
+                    .client_stats_mut_for(0)
+                    .update_coverage_summary(summary.clone());
+                monitor.display(event.name().to_string(), 0);
+                Ok(BrokerEventResult::Handled)
+            }
             #[cfg(feature = "introspection")]
             Event::UpdatePerfMonitor {
                 time,
@@ -185,11 +212,18 @@ where
                 monitor.display(event.name().to_string(), 0);
                 Ok(BrokerEventResult::Handled)
             }
-            Event::Objective { objective_size } => {
+            Event::Pause { paused: _ } => Ok(BrokerEventResult::Handled),
+            Event::Objective {
+                objective_size,
+                input_hash,
+                exit_kind,
+                signal,
+            } => {
                 monitor
                     .client_stats_mut_for(0)
                     .update_objective_size(*objective_size as u64);
                 monitor.display(event.name().to_string(), 0);
+                monitor.on_objective(0, *input_hash, *exit_kind, *signal);
                 Ok(BrokerEventResult::Handled)
             }
             Event::Log {
@@ -197,9 +231,7 @@ where
                 message,
                 phantom: _,
             } => {
-                let (_, _) = (message, severity_level);
-                #[cfg(feature = "std")]
-                println!("[LOG {}]: {}", severity_level, message);
+                monitor.log(*severity_level, message, 0);
                 Ok(BrokerEventResult::Handled)
             } //_ => Ok(BrokerEventResult::Forward),
         }
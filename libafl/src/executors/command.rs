@@ -8,20 +8,33 @@ use core::{
 use std::process::Child;
 use std::{
     ffi::{OsStr, OsString},
-    io::Write,
-    os::unix::prelude::OsStringExt,
+    io::{Read, Write},
+    os::unix::{
+        prelude::{OsStrExt, OsStringExt},
+        process::CommandExt,
+    },
     path::{Path, PathBuf},
     process::{Command, Stdio},
+    thread::{self, JoinHandle},
+    time::Instant,
 };
 
 use crate::{
     bolts::{
         fs::{OutFile, DEFAULT_OUTFILE},
+        os::set_memlimit,
         tuples::MatchName,
         AsSlice,
     },
     inputs::HasTargetBytes,
-    observers::{ASANBacktraceObserver, ObserversTuple},
+    observers::{
+        exit_status::{is_oom_report, is_sanitizer_report, observe_exit_status, split_wait_status},
+        mem_usage::{observe_mem_usage, wait_with_max_rss},
+        sanitizer::{observe_sanitizer_report, MAX_REPORT_LEN},
+        ASANBacktraceObserver, MemUsageObserver, ObserversTuple, SanitizerReportObserver,
+        StdErrObserver, StdOutObserver, MEM_USAGE_OBSERVER_NAME, SANITIZER_REPORT_OBSERVER_NAME,
+        STDERR_OBSERVER_NAME, STDOUT_OBSERVER_NAME,
+    },
 };
 #[cfg(feature = "std")]
 use crate::{inputs::Input, Error};
@@ -29,11 +42,40 @@ use crate::{inputs::Input, Error};
 #[cfg(all(feature = "std", unix))]
 use crate::executors::{Executor, ExitKind};
 
-#[cfg(all(feature = "std", unix))]
-use std::time::Duration;
+use core::time::Duration;
 
 use super::HasObservers;
 
+/// The default timeout of an execution of the [`CommandExecutor`]
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The placeholder for the input file in the arguments, as in AFL
+const AFL_INPUT_PLACEHOLDER: &[u8] = b"@@";
+
+/// Replaces the [`AFL_INPUT_PLACEHOLDER`]s in `arg` with `path`, if any
+fn replace_input_placeholder(arg: &OsStr, path: &OsStr) -> Option<OsString> {
+    let bytes = arg.as_bytes();
+    let placeholder_len = AFL_INPUT_PLACEHOLDER.len();
+    let mut replaced = vec![];
+    let mut found = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(AFL_INPUT_PLACEHOLDER) {
+            replaced.extend_from_slice(path.as_bytes());
+            i += placeholder_len;
+            found = true;
+        } else {
+            replaced.push(bytes[i]);
+            i += 1;
+        }
+    }
+    if found {
+        Some(OsString::from_vec(replaced))
+    } else {
+        None
+    }
+}
+
 /// How to deliver input to an external program
 /// `StdIn`: The traget reads from stdin
 /// `File`: The target reads from the specified [`OutFile`]
@@ -54,6 +96,85 @@ pub enum InputLocation {
     },
 }
 
+/// The maximum number of bytes of stdout and of stderr to capture for the `observers`, if any.
+/// The [`ASANBacktraceObserver`] and the [`SanitizerReportObserver`] need up to
+/// [`MAX_REPORT_LEN`] bytes of stderr.
+fn output_capture<OT>(observers: &OT) -> (Option<usize>, Option<usize>)
+where
+    OT: MatchName,
+{
+    let stdout_len = observers
+        .match_name::<StdOutObserver>(STDOUT_OBSERVER_NAME)
+        .map(StdOutObserver::max_len);
+    let stderr_len = observers
+        .match_name::<StdErrObserver>(STDERR_OBSERVER_NAME)
+        .map(StdErrObserver::max_len);
+    let stderr_len = if observers
+        .match_name::<ASANBacktraceObserver>("ASANBacktraceObserver")
+        .is_some()
+        || observers
+            .match_name::<SanitizerReportObserver>(SANITIZER_REPORT_OBSERVER_NAME)
+            .is_some()
+    {
+        Some(stderr_len.map_or(MAX_REPORT_LEN, |len| len.max(MAX_REPORT_LEN)))
+    } else {
+        stderr_len
+    };
+    (stdout_len, stderr_len)
+}
+
+/// Reads the output of the child in a thread, not to block it on a full pipe, keeping at most
+/// `max_len` bytes
+fn spawn_output_reader<R>(mut stream: R, max_len: usize) -> JoinHandle<Vec<u8>>
+where
+    R: Read + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut output = vec![];
+        let mut buf = [0; 4096];
+        loop {
+            match stream.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => {
+                    let kept = len.min(max_len.saturating_sub(output.len()));
+                    output.extend_from_slice(&buf[..kept]);
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => (),
+                Err(_) => break,
+            }
+        }
+        output
+    })
+}
+
+/// Waits for the output read by `reader`
+fn join_output_reader(reader: JoinHandle<Vec<u8>>) -> Result<Vec<u8>, Error> {
+    reader
+        .join()
+        .map_err(|_| Error::Unknown("Reading the output of the child failed".into()))
+}
+
+/// Waits for the child `pid` up to `timeout`, killing it then, with `wait4`, for the
+/// [`MemUsageObserver`]. Returns its raw wait status, or `None` on a timeout, and its peak
+/// resident set size. The child gets polled, `wait4` having no timeout.
+fn wait_timeout_with_max_rss(pid: i32, timeout: Duration) -> Result<(Option<i32>, u64), Error> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some((status, max_rss)) = wait_with_max_rss(pid, libc::WNOHANG)? {
+            return Ok((Some(status), max_rss));
+        }
+        if Instant::now() >= deadline {
+            unsafe {
+                libc::kill(pid, libc::SIGKILL);
+            }
+            let (_, max_rss) =
+                wait_with_max_rss(pid, 0)?.expect("wait4 returned no status without WNOHANG");
+            return Ok((None, max_rss));
+        }
+        thread::sleep(Duration::from_micros(100));
+    }
+}
+
 /// Clones a [`Command`] (without stdio and stdout/stderr - they are not accesible)
 fn clone_command(cmd: &Command) -> Command {
     let mut new_cmd = Command::new(cmd.get_program());
@@ -80,6 +201,17 @@ pub struct StdCommandConfigurator {
     pub input_location: InputLocation,
     /// The Command to execute
     pub command: Command,
+    /// The time after which a run is killed and reported as [`ExitKind::Timeout`]
+    pub timeout: Duration,
+    /// The exit code reported as [`ExitKind::Crash`], for targets exiting on errors,
+    /// e.g. the `exitcode` of sanitizers not aborting
+    pub crash_exit_code: Option<i32>,
+    /// If the stdout of the child is piped, for a [`StdOutObserver`]
+    pub pipe_stdout: bool,
+    /// If the stderr of the child is piped, for a [`StdErrObserver`] or an [`ASANBacktraceObserver`]
+    pub pipe_stderr: bool,
+    /// The memory limit of the child in MiB, `0` for none
+    pub memlimit: u64,
 }
 
 impl CommandConfigurator for StdCommandConfigurator {
@@ -91,9 +223,23 @@ impl CommandConfigurator for StdCommandConfigurator {
             InputLocation::Arg { argnum } => {
                 let args = self.command.get_args();
                 let mut cmd = Command::new(self.command.get_program());
+                cmd.stdin(Stdio::null());
+                // A fresh `Command`, without the `pre_exec` of the configured one
+                if self.memlimit != 0 {
+                    let memlimit = self.memlimit;
+                    unsafe {
+                        cmd.pre_exec(move || set_memlimit(memlimit));
+                    }
+                }
 
-                if !self.debug_child {
+                if self.pipe_stdout {
+                    cmd.stdout(Stdio::piped());
+                } else if !self.debug_child {
                     cmd.stdout(Stdio::null());
+                }
+                if self.pipe_stderr {
+                    cmd.stderr(Stdio::piped());
+                } else if !self.debug_child {
                     cmd.stderr(Stdio::null());
                 }
 
@@ -115,12 +261,13 @@ impl CommandConfigurator for StdCommandConfigurator {
                 Ok(cmd.spawn()?)
             }
             InputLocation::StdIn => {
-                self.command.stdin(Stdio::piped()).spawn()?;
-                let mut handle = self.command.spawn()?;
+                let mut handle = self.command.stdin(Stdio::piped()).spawn()?;
                 let mut stdin = handle.stdin.take().unwrap();
-                stdin.write_all(input.target_bytes().as_slice())?;
-                stdin.flush()?;
-                drop(stdin);
+                let bytes = input.target_bytes().as_slice().to_vec();
+                // Written on its own thread, as the output of the child only gets read once it's
+                // spawned: a child echoing its input would block on a full pipe, and so would we.
+                // The child may exit without reading all of it, so the errors are ignored.
+                thread::spawn(move || drop(stdin.write_all(&bytes)));
                 Ok(handle)
             }
             InputLocation::File { out_file } => {
@@ -129,6 +276,18 @@ impl CommandConfigurator for StdCommandConfigurator {
             }
         }
     }
+
+    fn exec_timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn crash_exit_code(&self) -> Option<i32> {
+        self.crash_exit_code
+    }
+
+    fn memlimit(&self) -> u64 {
+        self.memlimit
+    }
 }
 
 /// A `CommandExecutor` is a wrapper around [`std::process::Command`] to execute a target as a child process.
@@ -143,6 +302,8 @@ where
     observers: OT,
     /// cache if the AsanBacktraceObserver is present
     has_asan_observer: bool,
+    /// The lengths of stdout and of stderr to capture, for the observers
+    capture_len: (Option<usize>, Option<usize>),
     phantom: PhantomData<(EM, I, S, Z)>,
 }
 
@@ -205,25 +366,36 @@ where
         let has_asan_observer = observers
             .match_name::<ASANBacktraceObserver>("ASANBacktraceObserver")
             .is_some();
-        if has_asan_observer {
+        let capture_len = output_capture(&observers);
+        if capture_len.0.is_some() {
+            command.stdout(Stdio::piped());
+        }
+        if capture_len.1.is_some() {
             command.stderr(Stdio::piped());
         }
 
         Ok(Self {
             observers,
             has_asan_observer,
+            capture_len,
             inner: StdCommandConfigurator {
                 input_location: InputLocation::File {
                     out_file: OutFile::create(path)?,
                 },
                 command,
                 debug_child,
+                timeout: DEFAULT_COMMAND_TIMEOUT,
+                crash_exit_code: None,
+                pipe_stdout: capture_len.0.is_some(),
+                pipe_stderr: capture_len.1.is_some(),
+                memlimit: 0,
             },
             phantom: PhantomData,
         })
     }
 
-    /// Parses an AFL-like comandline, replacing `@@` with the input file.
+    /// Parses an AFL-like comandline, replacing `@@` with the input file, also within arguments,
+    /// e.g. `--input=@@`.
     /// If no `@@` was found, will use stdin for input.
     /// The arg 0 is the program.
     pub fn parse_afl_cmdline<IT, O>(
@@ -235,36 +407,34 @@ where
         IT: IntoIterator<Item = O>,
         O: AsRef<OsStr>,
     {
-        let mut atat_at = None;
         let mut builder = CommandExecutorBuilder::new();
         builder.debug_child(debug_child);
-        let afl_delim = OsStr::new("@@");
+        let mut uses_file = false;
 
         for (pos, arg) in args.into_iter().enumerate() {
+            let has_placeholder = arg
+                .as_ref()
+                .as_bytes()
+                .windows(AFL_INPUT_PLACEHOLDER.len())
+                .any(|window| window == AFL_INPUT_PLACEHOLDER);
             if pos == 0 {
-                if arg.as_ref() == afl_delim {
+                if has_placeholder {
                     return Err(Error::IllegalArgument(
                         "The first argument must not be @@ but the program to execute".into(),
                     ));
                 }
                 builder.program(arg);
-            } else if arg.as_ref() == afl_delim {
-                if atat_at.is_some() {
-                    return Err(Error::IllegalArgument(
-                        "Multiple @@ in afl commandline are not permitted".into(),
-                    ));
-                }
-                atat_at = Some(pos);
-                builder.input(InputLocation::File {
-                    out_file: OutFile::create(DEFAULT_OUTFILE)?,
-                });
-                builder.arg(DEFAULT_OUTFILE);
             } else {
+                uses_file |= has_placeholder;
                 builder.arg(arg);
             }
         }
 
-        if atat_at.is_none() {
+        if uses_file {
+            builder.input(InputLocation::File {
+                out_file: OutFile::create(DEFAULT_OUTFILE)?,
+            });
+        } else {
             builder.input(InputLocation::StdIn);
         }
 
@@ -293,38 +463,101 @@ where
 
         let mut child = self.inner.spawn_child(input)?;
 
-        let res = match child
-            .wait_timeout(Duration::from_secs(5))
-            .expect("waiting on child failed")
-            .map(|status| status.signal())
+        let (stdout_len, stderr_len) = self.capture_len;
+        let stdout_reader = match stdout_len {
+            Some(max_len) => Some(spawn_output_reader(
+                child.stdout.take().ok_or_else(|| {
+                    Error::IllegalState(
+                        "Using StdOutObserver but stdout was not `Stdio::pipe` in CommandExecutor"
+                            .into(),
+                    )
+                })?,
+                max_len,
+            )),
+            None => None,
+        };
+        let stderr_reader = match stderr_len {
+            Some(max_len) => Some(spawn_output_reader(
+                child.stderr.take().ok_or_else(|| {
+                    Error::IllegalState(
+                        "Using StdErrObserver or ASANBacktraceObserver but stderr was not `Stdio::pipe` in CommandExecutor".into(),
+                    )
+                })?,
+                max_len,
+            )),
+            None => None,
+        };
+
+        let status = if self
+            .observers
+            .match_name::<MemUsageObserver>(MEM_USAGE_OBSERVER_NAME)
+            .is_some()
         {
-            // for reference: https://www.man7.org/linux/man-pages/man7/signal.7.html
-            Some(Some(9)) => Ok(ExitKind::Oom),
-            Some(Some(_)) => Ok(ExitKind::Crash),
-            Some(None) => Ok(ExitKind::Ok),
-            None => {
+            #[allow(clippy::cast_possible_wrap)]
+            let (status, max_rss) =
+                wait_timeout_with_max_rss(child.id() as i32, self.inner.exec_timeout())?;
+            observe_mem_usage(&mut self.observers, max_rss);
+            status.map(split_wait_status)
+        } else {
+            let status = child
+                .wait_timeout(self.inner.exec_timeout())
+                .expect("waiting on child failed");
+            if status.is_none() {
                 // if this fails, there is not much we can do. let's hope it failed because the process finished
                 // in the meantime.
                 drop(child.kill());
                 // finally, try to wait to properly clean up system resources.
                 drop(child.wait());
-                Ok(ExitKind::Timeout)
             }
+            status.map(|status| (status.signal(), status.code()))
         };
-
-        if self.has_asan_observer {
-            let stderr = child.stderr.as_mut().ok_or_else(|| {
-                Error::IllegalState(
-                    "Using ASANBacktraceObserver but stderr was not `Stdio::pipe` in CommandExecutor".into(),
-                )
-            })?;
-            self.observers
-                .match_name_mut::<ASANBacktraceObserver>("ASANBacktraceObserver")
-                .unwrap()
-                .parse_asan_output_from_childstderr(stderr);
+        let mut exit_kind = match status {
+            // for reference: https://www.man7.org/linux/man-pages/man7/signal.7.html
+            // a `SIGKILL` under a memory limit, most likely by the kernel running out of memory
+            Some((Some(libc::SIGKILL), _)) if self.inner.memlimit() != 0 => ExitKind::Oom,
+            Some((Some(_), _)) => ExitKind::Crash,
+            Some((None, code)) if code.is_some() && code == self.inner.crash_exit_code() => {
+                ExitKind::Crash
+            }
+            Some((None, _)) => ExitKind::Ok,
+            None => ExitKind::Timeout,
         };
 
-        res
+        if let Some(reader) = stdout_reader {
+            let stdout = join_output_reader(reader)?;
+            if let Some(observer) = self
+                .observers
+                .match_name_mut::<StdOutObserver>(STDOUT_OBSERVER_NAME)
+            {
+                observer.observe(&stdout);
+            }
+        }
+        let mut sanitizer_abort = false;
+        if let Some(reader) = stderr_reader {
+            let stderr = join_output_reader(reader)?;
+            let stderr_str = String::from_utf8_lossy(&stderr);
+            sanitizer_abort = is_sanitizer_report(&stderr_str);
+            if exit_kind == ExitKind::Crash && is_oom_report(&stderr_str) {
+                exit_kind = ExitKind::Oom;
+            }
+            if let Some(observer) = self
+                .observers
+                .match_name_mut::<StdErrObserver>(STDERR_OBSERVER_NAME)
+            {
+                observer.observe(&stderr);
+            }
+            if self.has_asan_observer {
+                self.observers
+                    .match_name_mut::<ASANBacktraceObserver>("ASANBacktraceObserver")
+                    .unwrap()
+                    .parse_asan_output(&String::from_utf8_lossy(&stderr));
+            }
+            observe_sanitizer_report(&mut self.observers, &stderr_str, exit_kind);
+        }
+        let (signal, exit_code) = status.unwrap_or_default();
+        observe_exit_status(&mut self.observers, signal, exit_code, sanitizer_abort);
+
+        Ok(exit_kind)
     }
 }
 
@@ -341,15 +574,20 @@ impl<EM, I, OT: ObserversTuple<I, S>, S, T: Debug, Z> HasObservers<I, OT, S>
 }
 
 /// The builder for a default [`CommandExecutor`] that should fit most use-cases.
+/// The arguments may contain `@@`, replaced with the path of the input file, as in AFL.
 #[derive(Debug, Clone)]
 pub struct CommandExecutorBuilder {
     debug_child: bool,
     program: Option<OsString>,
-    args_before: Vec<OsString>,
+    args: Vec<OsString>,
     input_location: Option<InputLocation>,
-    args_after: Vec<OsString>,
+    /// The position of the input in the arguments, where `input` was called
+    input_arg_pos: usize,
     cwd: Option<PathBuf>,
     envs: Vec<(OsString, OsString)>,
+    timeout: Duration,
+    crash_exit_code: Option<i32>,
+    memlimit: u64,
 }
 
 impl Default for CommandExecutorBuilder {
@@ -364,12 +602,15 @@ impl CommandExecutorBuilder {
     fn new() -> CommandExecutorBuilder {
         CommandExecutorBuilder {
             program: None,
-            args_before: vec![],
+            args: vec![],
             input_location: None,
-            args_after: vec![],
+            input_arg_pos: 0,
             cwd: None,
             envs: vec![],
             debug_child: false,
+            timeout: DEFAULT_COMMAND_TIMEOUT,
+            crash_exit_code: None,
+            memlimit: 0,
         }
     }
 
@@ -384,6 +625,8 @@ impl CommandExecutorBuilder {
     }
 
     /// Set the input mode and location.
+    /// An input file, or argument, goes after the arguments added so far, unless the arguments
+    /// contain `@@` placeholders for the input file.
     /// This option is mandatory, if not set, the `build` method will error.
     pub fn input(&mut self, input: InputLocation) -> &mut Self {
         // This is an error in the user code, no point in returning Err.
@@ -392,18 +635,16 @@ impl CommandExecutorBuilder {
             "input location already set, cannot set it again"
         );
         self.input_location = Some(input);
+        self.input_arg_pos = self.args.len();
         self
     }
 
     /// Adds an argument to the program's commandline.
+    /// A `@@` in it gets replaced with the path of the input file.
     pub fn arg<O: AsRef<OsStr>>(&mut self, arg: O) -> &mut CommandExecutorBuilder {
-        match self.input_location {
-            Some(InputLocation::StdIn) => self.args_before.push(arg.as_ref().to_owned()),
-            Some(_) | None => self.args_after.push(arg.as_ref().to_owned()),
-        };
+        self.args.push(arg.as_ref().to_owned());
         self
     }
-
     /// Adds a range of arguments to the program's commandline.
     pub fn args<IT, O>(&mut self, args: IT) -> &mut CommandExecutorBuilder
     where
@@ -453,6 +694,31 @@ impl CommandExecutorBuilder {
         self
     }
 
+    /// Sets the time after which a run is killed and reported as [`ExitKind::Timeout`].
+    /// Defaults to [`DEFAULT_COMMAND_TIMEOUT`].
+    pub fn timeout(&mut self, timeout: Duration) -> &mut CommandExecutorBuilder {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Reports the runs exiting with `exit_code` as [`ExitKind::Crash`], for targets exiting on
+    /// errors instead of aborting, e.g. with `ASAN_OPTIONS=exitcode=...`.
+    pub fn crash_exit_code(&mut self, exit_code: i32) -> &mut CommandExecutorBuilder {
+        self.crash_exit_code = Some(exit_code);
+        self
+    }
+
+    /// Limits the address space of the target to `memlimit` MiB, `0` for none, the default.
+    /// Under a limit, a target killed by `SIGKILL` is reported as [`ExitKind::Oom`], as is a target
+    /// whose captured stderr holds the out-of-memory report of a sanitizer. The sanitizers reserve more address space than any sensible
+    /// limit, so don't limit the memory of sanitized targets.
+    /// The limit is set with `setrlimit`, as the [`CommandExecutor`] only exists on unix, there is no
+    /// job object variant for Windows.
+    pub fn memlimit(&mut self, memlimit: u64) -> &mut CommandExecutorBuilder {
+        self.memlimit = memlimit;
+        self
+    }
+
     /// Builds the `ComandExecutor`
     pub fn build<EM, I, OT, S, Z>(
         &self,
@@ -468,19 +734,34 @@ impl CommandExecutorBuilder {
                 "ComandExecutor::builder: no program set!".into(),
             ));
         };
-        let mut command = Command::new(program);
-        command.args(&self.args_before);
+        let mut args = self.args.clone();
         match &self.input_location {
             Some(InputLocation::StdIn) => {
-                command.stdin(Stdio::piped());
+                if args
+                    .iter()
+                    .any(|arg| replace_input_placeholder(arg, OsStr::new("")).is_some())
+                {
+                    return Err(Error::IllegalArgument(
+                        "ComandExecutor::builder: @@ in the arguments, but the input is on stdin"
+                            .into(),
+                    ));
+                }
             }
             Some(InputLocation::File { out_file }) => {
-                command.stdin(Stdio::null());
-                command.arg(&out_file.path);
+                let path = out_file.path.as_os_str();
+                let mut replaced = false;
+                for arg in &mut args {
+                    if let Some(new_arg) = replace_input_placeholder(arg, path) {
+                        *arg = new_arg;
+                        replaced = true;
+                    }
+                }
+                if !replaced {
+                    args.insert(self.input_arg_pos, path.to_owned());
+                }
             }
             Some(InputLocation::Arg { .. }) => {
-                command.stdin(Stdio::null());
-                command.arg("DUMMY");
+                args.insert(self.input_arg_pos, "DUMMY".into());
             }
             None => {
                 return Err(Error::IllegalArgument(
@@ -488,7 +769,14 @@ impl CommandExecutorBuilder {
                 ))
             }
         }
-        command.args(&self.args_after);
+
+        let mut command = Command::new(program);
+        command.args(&args);
+        if let Some(InputLocation::StdIn) = &self.input_location {
+            command.stdin(Stdio::piped());
+        } else {
+            command.stdin(Stdio::null());
+        }
         command.envs(
             self.envs
                 .iter()
@@ -497,15 +785,22 @@ impl CommandExecutorBuilder {
         if let Some(cwd) = &self.cwd {
             command.current_dir(cwd);
         }
+        if self.memlimit != 0 {
+            let memlimit = self.memlimit;
+            unsafe {
+                command.pre_exec(move || set_memlimit(memlimit));
+            }
+        }
         if !self.debug_child {
             command.stdout(Stdio::null());
             command.stderr(Stdio::null());
         }
-        if observers
-            .match_name::<ASANBacktraceObserver>("ASANBacktraceObserver")
-            .is_some()
-        {
-            // we need stderr for ASANBackt
+        // the output observers, and the ASANBacktraceObserver, need the output of the child
+        let (stdout_len, stderr_len) = output_capture(&observers);
+        if stdout_len.is_some() {
+            command.stdout(Stdio::piped());
+        }
+        if stderr_len.is_some() {
             command.stderr(Stdio::piped());
         }
 
@@ -513,6 +808,11 @@ impl CommandExecutorBuilder {
             debug_child: self.debug_child,
             input_location: self.input_location.clone().unwrap(),
             command,
+            timeout: self.timeout,
+            crash_exit_code: self.crash_exit_code,
+            pipe_stdout: stdout_len.is_some(),
+            pipe_stderr: stderr_len.is_some(),
+            memlimit: self.memlimit,
         };
         Ok(configurator.into_executor(observers))
     }
@@ -555,6 +855,22 @@ pub trait CommandConfigurator: Sized + Debug {
     where
         I: Input + HasTargetBytes;
 
+    /// The time after which a run is killed and reported as [`ExitKind::Timeout`]
+    fn exec_timeout(&self) -> Duration {
+        DEFAULT_COMMAND_TIMEOUT
+    }
+
+    /// The exit code reported as [`ExitKind::Crash`], if any
+    fn crash_exit_code(&self) -> Option<i32> {
+        None
+    }
+
+    /// The memory limit of the child in MiB, `0` for none.
+    /// Under a limit, a child killed by `SIGKILL` is reported as [`ExitKind::Oom`].
+    fn memlimit(&self) -> u64 {
+        0
+    }
+
     /// Create an `Executor` from this `CommandConfigurator`.
     fn into_executor<EM, I, OT, S, Z>(self, observers: OT) -> CommandExecutor<EM, I, OT, S, Self, Z>
     where
@@ -563,10 +879,12 @@ pub trait CommandConfigurator: Sized + Debug {
         let has_asan_observer = observers
             .match_name::<ASANBacktraceObserver>("ASANBacktraceObserver")
             .is_some();
+        let capture_len = output_capture(&observers);
 
         CommandExecutor {
             observers,
             has_asan_observer,
+            capture_len,
             inner: self,
             phantom: PhantomData,
         }
@@ -575,14 +893,18 @@ pub trait CommandConfigurator: Sized + Debug {
 
 #[cfg(test)]
 mod tests {
+    use std::fs;
+
     use crate::{
+        bolts::{fs::OutFile, tuples::tuple_list},
         events::SimpleEventManager,
         executors::{
             command::{CommandExecutor, InputLocation},
-            Executor,
+            Executor, ExitKind, HasObservers,
         },
         inputs::BytesInput,
         monitors::SimpleMonitor,
+        observers::{ExitStatusObserver, StdOutObserver},
     };
 
     #[test]
@@ -609,6 +931,128 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    #[cfg(unix)]
+    fn test_builder_input_placeholder() {
+        let path = std::env::temp_dir().join(format!(
+            "libafl_test_builder_input_placeholder_{}",
+            std::process::id()
+        ));
+        let mut builder = CommandExecutor::builder();
+        builder
+            .program("ls")
+            .arg("-l")
+            .arg("--input=@@")
+            .input(InputLocation::File {
+                out_file: OutFile::create(&path).unwrap(),
+            });
+        let mut executor = builder.build::<(), BytesInput, (), (), ()>(()).unwrap();
+        let args: Vec<_> = executor.inner().command.get_args().collect();
+        let input_arg = format!("--input={}", path.display());
+        assert_eq!(args, ["-l", input_arg.as_str()]);
+        drop(fs::remove_file(&path));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_builder_stdout_capture() {
+        let mut builder = CommandExecutor::builder();
+        builder
+            .program("echo")
+            .arg("hello")
+            .input(InputLocation::StdIn);
+        let mut executor = builder
+            .build::<(), BytesInput, _, (), ()>(tuple_list!(StdOutObserver::new()))
+            .unwrap();
+        executor
+            .run_target(&mut (), &mut (), &mut (), &BytesInput::new(vec![]))
+            .unwrap();
+        assert_eq!(executor.observers().0.output(), Some(&b"hello\n"[..]));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_builder_stdin_bigger_than_pipe() {
+        // More than the buffer of a pipe, for both stdin and stdout
+        let input = b"0123456789abcdef".repeat(16 * 1024);
+        let mut builder = CommandExecutor::builder();
+        builder.program("cat").input(InputLocation::StdIn);
+        let mut executor = builder
+            .build::<(), BytesInput, _, (), ()>(tuple_list!(
+                StdOutObserver::new().with_max_len(input.len())
+            ))
+            .unwrap();
+        assert_eq!(
+            executor
+                .run_target(&mut (), &mut (), &mut (), &BytesInput::new(input.clone()))
+                .unwrap(),
+            ExitKind::Ok
+        );
+        assert_eq!(executor.observers().0.output(), Some(&input[..]));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_builder_exit_status() {
+        let mut builder = CommandExecutor::builder();
+        builder
+            .program("sh")
+            .args(["-c", "exit 3"])
+            .input(InputLocation::StdIn);
+        let mut executor = builder
+            .build::<(), BytesInput, _, (), ()>(tuple_list!(ExitStatusObserver::new()))
+            .unwrap();
+        executor
+            .run_target(&mut (), &mut (), &mut (), &BytesInput::new(vec![]))
+            .unwrap();
+        let observer = &executor.observers().0;
+        assert_eq!(observer.exit_code(), Some(3));
+        assert_eq!(observer.signal(), None);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_builder_memlimit() {
+        let mut builder = CommandExecutor::builder();
+        builder
+            .program("sh")
+            .args(["-c", "ulimit -v"])
+            .memlimit(64)
+            .input(InputLocation::StdIn);
+        let mut executor = builder
+            .build::<(), BytesInput, _, (), ()>(tuple_list!(StdOutObserver::new()))
+            .unwrap();
+        executor
+            .run_target(&mut (), &mut (), &mut (), &BytesInput::new(vec![]))
+            .unwrap();
+        assert_eq!(executor.observers().0.output(), Some(&b"65536\n"[..]));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_builder_memlimit_arg() {
+        // `dd` can't allocate its 128 MiB buffer under the limit, and the target then gets killed,
+        // as by the kernel running out of memory
+        let script = BytesInput::new(
+            b"dd if=/dev/zero of=/dev/null bs=128M count=1 2>/dev/null || kill -9 $$".to_vec(),
+        );
+        for (memlimit, exit_kind) in [(0, ExitKind::Ok), (64, ExitKind::Oom)] {
+            let mut builder = CommandExecutor::builder();
+            builder
+                .program("sh")
+                .arg("-c")
+                .memlimit(memlimit)
+                .input(InputLocation::Arg { argnum: 1 });
+            let mut executor = builder.build::<(), BytesInput, _, (), ()>(()).unwrap();
+            assert_eq!(
+                executor
+                    .run_target(&mut (), &mut (), &mut (), &script)
+                    .unwrap(),
+                exit_kind
+            );
+        }
+    }
+
     #[test]
     #[cfg(unix)]
     fn test_parse_afl_cmdline() {
@@ -0,0 +1,286 @@
+//! The [`DiffExecutor`] runs each input through two executors, e.g. two implementations of a parser,
+//! for differential fuzzing.
+
+#[cfg(feature = "std")]
+use ahash::AHasher;
+#[cfg(feature = "std")]
+use core::hash::Hasher;
+use core::{cell::UnsafeCell, fmt::Debug, marker::PhantomData, ptr};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+use crate::observers::{StdOutObserver, STDOUT_OBSERVER_NAME};
+use crate::{
+    bolts::{ownedref::OwnedPtrMut, tuples::MatchName},
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::Input,
+    observers::{DiffObserver, ObserversTuple, DIFF_OBSERVER_NAME},
+    Error,
+};
+
+/// A [`DiffExecutor`] runs each input through a primary and a secondary executor.
+/// Its observers are the ones of both executors, in a [`DiffObserversTuple`], for the feedbacks
+/// to compare them.
+/// A [`DiffObserver`] in the observers of the primary executor gets the [`ExitKind`]s of both runs,
+/// and the hashes of their stdout, if captured by a [`crate::observers::StdOutObserver`] on each
+/// side, for the [`crate::feedbacks::DiffFeedback`] to compare them.
+/// The run is reported with the [`ExitKind`] of the primary target, or of the secondary one if the
+/// primary one exited normally.
+#[derive(Debug)]
+pub struct DiffExecutor<A, B, OTA, OTB> {
+    primary: A,
+    secondary: B,
+    observers: UnsafeCell<DiffObserversTuple<OTA, OTB>>,
+}
+
+impl<A, B, OTA, OTB> DiffExecutor<A, B, OTA, OTB> {
+    /// Creates a new [`DiffExecutor`], running each input through `primary`, then `secondary`
+    pub fn new<EM, I, S, Z>(primary: A, secondary: B) -> Self
+    where
+        A: Executor<EM, I, S, Z> + HasObservers<I, OTA, S>,
+        B: Executor<EM, I, S, Z> + HasObservers<I, OTB, S>,
+        I: Input,
+        OTA: ObserversTuple<I, S>,
+        OTB: ObserversTuple<I, S>,
+    {
+        Self {
+            primary,
+            secondary,
+            observers: UnsafeCell::new(DiffObserversTuple {
+                primary: OwnedPtrMut::Ptr(ptr::null_mut()),
+                secondary: OwnedPtrMut::Ptr(ptr::null_mut()),
+                phantom: PhantomData,
+            }),
+        }
+    }
+
+    /// Retrieve the primary `Executor` that is wrapped by this `DiffExecutor`.
+    pub fn primary(&mut self) -> &mut A {
+        &mut self.primary
+    }
+
+    /// Retrieve the secondary `Executor` that is wrapped by this `DiffExecutor`.
+    pub fn secondary(&mut self) -> &mut B {
+        &mut self.secondary
+    }
+}
+
+/// The hash of the stdout captured by the [`StdOutObserver`] in `observers`, if any
+#[allow(unused_variables)]
+fn stdout_hash<OT>(observers: &OT) -> Option<u64>
+where
+    OT: MatchName,
+{
+    #[cfg(feature = "std")]
+    if let Some(output) = observers
+        .match_name::<StdOutObserver>(STDOUT_OBSERVER_NAME)
+        .and_then(StdOutObserver::output)
+    {
+        let mut hasher = AHasher::new_with_keys(0, 0);
+        hasher.write(output);
+        return Some(hasher.finish());
+    }
+    None
+}
+
+impl<A, B, EM, I, OTA, OTB, S, Z> Executor<EM, I, S, Z> for DiffExecutor<A, B, OTA, OTB>
+where
+    A: Executor<EM, I, S, Z> + HasObservers<I, OTA, S>,
+    B: Executor<EM, I, S, Z> + HasObservers<I, OTB, S>,
+    I: Input,
+    OTA: ObserversTuple<I, S>,
+    OTB: ObserversTuple<I, S>,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        let primary_exit_kind = self.primary.run_target(fuzzer, state, mgr, input)?;
+        self.primary.post_run_reset();
+        let secondary_exit_kind = self.secondary.run_target(fuzzer, state, mgr, input)?;
+        self.secondary.post_run_reset();
+
+        let stdout_hashes = (
+            stdout_hash(self.primary.observers()),
+            stdout_hash(self.secondary.observers()),
+        );
+        if let Some(observer) = self
+            .primary
+            .observers_mut()
+            .match_name_mut::<DiffObserver>(DIFF_OBSERVER_NAME)
+        {
+            observer.observe_exit_kinds(primary_exit_kind, secondary_exit_kind);
+            observer.observe_stdout_hashes(stdout_hashes.0, stdout_hashes.1);
+        }
+
+        if primary_exit_kind == ExitKind::Ok {
+            Ok(secondary_exit_kind)
+        } else {
+            Ok(primary_exit_kind)
+        }
+    }
+}
+
+/// The observers of both executors of a [`DiffExecutor`], matched by name in the observers of
+/// the primary executor first
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(
+    bound = "OTA: Serialize + serde::de::DeserializeOwned, OTB: Serialize + serde::de::DeserializeOwned"
+)]
+pub struct DiffObserversTuple<OTA, OTB> {
+    primary: OwnedPtrMut<OTA>,
+    secondary: OwnedPtrMut<OTB>,
+    phantom: PhantomData<(OTA, OTB)>,
+}
+
+impl<OTA, OTB> DiffObserversTuple<OTA, OTB> {
+    /// The observers of the primary executor
+    #[must_use]
+    pub fn primary(&self) -> &OTA {
+        self.primary.as_ref()
+    }
+
+    /// The observers of the secondary executor
+    #[must_use]
+    pub fn secondary(&self) -> &OTB {
+        self.secondary.as_ref()
+    }
+}
+
+impl<OTA, OTB> MatchName for DiffObserversTuple<OTA, OTB>
+where
+    OTA: MatchName,
+    OTB: MatchName,
+{
+    fn match_name<T>(&self, name: &str) -> Option<&T> {
+        self.primary
+            .as_ref()
+            .match_name(name)
+            .or_else(|| self.secondary.as_ref().match_name(name))
+    }
+
+    fn match_name_mut<T>(&mut self, name: &str) -> Option<&mut T> {
+        match self.primary.as_mut().match_name_mut(name) {
+            Some(observer) => Some(observer),
+            None => self.secondary.as_mut().match_name_mut(name),
+        }
+    }
+}
+
+impl<I, OTA, OTB, S> ObserversTuple<I, S> for DiffObserversTuple<OTA, OTB>
+where
+    OTA: ObserversTuple<I, S>,
+    OTB: ObserversTuple<I, S>,
+{
+    fn pre_exec_all(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        self.primary.as_mut().pre_exec_all(state, input)?;
+        self.secondary.as_mut().pre_exec_all(state, input)
+    }
+
+    fn post_exec_all(
+        &mut self,
+        state: &mut S,
+        input: &I,
+        exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.primary
+            .as_mut()
+            .post_exec_all(state, input, exit_kind)?;
+        self.secondary
+            .as_mut()
+            .post_exec_all(state, input, exit_kind)
+    }
+
+    fn pre_exec_child_all(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        self.primary.as_mut().pre_exec_child_all(state, input)?;
+        self.secondary.as_mut().pre_exec_child_all(state, input)
+    }
+
+    fn post_exec_child_all(
+        &mut self,
+        state: &mut S,
+        input: &I,
+        exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.primary
+            .as_mut()
+            .post_exec_child_all(state, input, exit_kind)?;
+        self.secondary
+            .as_mut()
+            .post_exec_child_all(state, input, exit_kind)
+    }
+}
+
+impl<A, B, I, OTA, OTB, S> HasObservers<I, DiffObserversTuple<OTA, OTB>, S>
+    for DiffExecutor<A, B, OTA, OTB>
+where
+    A: HasObservers<I, OTA, S>,
+    B: HasObservers<I, OTB, S>,
+    OTA: ObserversTuple<I, S>,
+    OTB: ObserversTuple<I, S>,
+{
+    #[inline]
+    fn observers(&self) -> &DiffObserversTuple<OTA, OTB> {
+        // The executors may have moved since the last call
+        unsafe {
+            let observers = &mut *self.observers.get();
+            observers.primary =
+                OwnedPtrMut::Ptr(self.primary.observers() as *const OTA as *mut OTA);
+            observers.secondary =
+                OwnedPtrMut::Ptr(self.secondary.observers() as *const OTB as *mut OTB);
+            &*self.observers.get()
+        }
+    }
+
+    #[inline]
+    fn observers_mut(&mut self) -> &mut DiffObserversTuple<OTA, OTB> {
+        let observers = self.observers.get_mut();
+        observers.primary = OwnedPtrMut::Ptr(self.primary.observers_mut() as *mut OTA);
+        observers.secondary = OwnedPtrMut::Ptr(self.secondary.observers_mut() as *mut OTB);
+        observers
+    }
+}
+
+#[cfg(all(test, feature = "std", unix))]
+mod tests {
+    use crate::{
+        bolts::tuples::tuple_list,
+        executors::{
+            command::{CommandExecutor, InputLocation},
+            DiffExecutor, Executor, HasObservers,
+        },
+        inputs::BytesInput,
+        observers::{DiffObserver, StdOutObserver},
+    };
+
+    #[test]
+    fn test_diff_executor_stdout() {
+        let mut primary = CommandExecutor::builder();
+        primary.program("echo").arg("a").input(InputLocation::StdIn);
+        let primary = primary
+            .build::<(), BytesInput, _, (), ()>(tuple_list!(
+                DiffObserver::new(),
+                StdOutObserver::new()
+            ))
+            .unwrap();
+        let mut secondary = CommandExecutor::builder();
+        secondary
+            .program("echo")
+            .arg("b")
+            .input(InputLocation::StdIn);
+        let secondary = secondary
+            .build::<(), BytesInput, _, (), ()>(tuple_list!(StdOutObserver::new()))
+            .unwrap();
+
+        let mut executor = DiffExecutor::new(primary, secondary);
+        executor
+            .run_target(&mut (), &mut (), &mut (), &BytesInput::new(vec![]))
+            .unwrap();
+        let observer = &executor.observers().primary().0;
+        assert!(!observer.exit_kinds_differ());
+        assert!(observer.stdout_differs());
+    }
+}
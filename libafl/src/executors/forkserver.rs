@@ -3,41 +3,67 @@
 use core::{
     fmt::{self, Debug, Formatter},
     marker::PhantomData,
+    mem,
     time::Duration,
 };
 use std::{
+    fs,
     io::{self, prelude::*, ErrorKind},
-    os::unix::{io::RawFd, process::CommandExt},
+    os::unix::{
+        io::{FromRawFd, RawFd},
+        process::CommandExt,
+    },
     process::{Command, Stdio},
+    time::Instant,
 };
 
 use crate::{
     bolts::{
         fs::OutFile,
-        os::{dup2, pipes::Pipe},
+        os::{dup2, pipes::Pipe, set_memlimit},
         shmem::{ShMem, ShMemProvider, StdShMemProvider},
         AsMutSlice, AsSlice,
     },
     executors::{Executor, ExitKind, HasObservers},
     inputs::{HasTargetBytes, Input},
-    observers::{get_asan_runtime_flags_with_log_path, ASANBacktraceObserver, ObserversTuple},
+    mutators::Tokens,
+    observers::{
+        exit_status::{is_oom_report, is_sanitizer_report, observe_exit_status, split_wait_status},
+        get_asan_runtime_flags_with_log_path,
+        sanitizer::observe_sanitizer_report,
+        ASANBacktraceObserver, ObserversTuple, SanitizerReportObserver, StdErrObserver,
+        StdOutObserver, ASAN_LOG_PATH, SANITIZER_REPORT_OBSERVER_NAME, STDERR_OBSERVER_NAME,
+        STDOUT_OBSERVER_NAME,
+    },
     Error,
 };
 
 use nix::{
+    errno::Errno,
+    fcntl::{fcntl, FcntlArg, OFlag},
     sys::{
         select::{pselect, FdSet},
         signal::{kill, SigSet, Signal},
         time::{TimeSpec, TimeValLike},
     },
-    unistd::Pid,
+    unistd::{dup, Pid},
 };
 
 const FORKSRV_FD: i32 = 198;
 #[allow(clippy::cast_possible_wrap)]
 const FS_OPT_ENABLED: i32 = 0x80000001_u32 as i32;
 #[allow(clippy::cast_possible_wrap)]
+const FS_OPT_MAPSIZE: i32 = 0x40000000_u32 as i32;
+#[allow(clippy::cast_possible_wrap)]
+const FS_OPT_AUTODICT: i32 = 0x10000000_u32 as i32;
+#[allow(clippy::cast_possible_wrap)]
 const FS_OPT_SHDMEM_FUZZ: i32 = 0x01000000_u32 as i32;
+#[allow(clippy::cast_possible_wrap)]
+const FS_OPT_OLD_AFLPP_WORKAROUND: i32 = 0x0f000000_u32 as i32;
+/// The signature AFL++ embeds in the binaries using `__AFL_LOOP`
+const PERSIST_SIG: &[u8] = b"##SIG_AFL_PERSISTENT##";
+/// The signature AFL++ embeds in the binaries using `__AFL_INIT`
+const DEFER_SIG: &[u8] = b"##SIG_AFL_DEFER_FORKSRV##";
 const SHMEM_FUZZ_HDR_SIZE: usize = 4;
 const MAX_FILE: usize = 1024 * 1024;
 
@@ -45,7 +71,7 @@ const MAX_FILE: usize = 1024 * 1024;
 pub trait ConfigTarget {
     /// Sets the sid
     fn setsid(&mut self) -> &mut Self;
-    /// Sets a mem limit
+    /// Sets a limit of the address space, in MiB, `0` for none
     fn setlimit(&mut self, memlimit: u64) -> &mut Self;
     /// Sets the stdin
     fn setstdin(&mut self, fd: RawFd, use_stdin: bool) -> &mut Self;
@@ -119,36 +145,49 @@ impl ConfigTarget for Command {
         }
     }
 
-    #[allow(trivial_numeric_casts)]
     fn setlimit(&mut self, memlimit: u64) -> &mut Self {
         if memlimit == 0 {
             return self;
         }
-        let func = move || {
-            let memlimit: libc::rlim_t = (memlimit as libc::rlim_t) << 20;
-            let r = libc::rlimit {
-                rlim_cur: memlimit,
-                rlim_max: memlimit,
-            };
-            let r0 = libc::rlimit {
-                rlim_cur: 0,
-                rlim_max: 0,
-            };
+        unsafe { self.pre_exec(move || set_memlimit(memlimit)) }
+    }
+}
 
-            #[cfg(target_os = "openbsd")]
-            let mut ret = unsafe { libc::setrlimit(libc::RLIMIT_RSS, &r) };
-            #[cfg(not(target_os = "openbsd"))]
-            let mut ret = unsafe { libc::setrlimit(libc::RLIMIT_AS, &r) };
-            if ret < 0 {
-                return Err(io::Error::last_os_error());
-            }
-            ret = unsafe { libc::setrlimit(libc::RLIMIT_CORE, &r0) };
-            if ret < 0 {
-                return Err(io::Error::last_os_error());
+/// An output stream of the children, piped to the fuzzer and read while they run, as a child
+/// writing more than the capacity of the pipe would block until it times out
+#[derive(Debug)]
+struct CapturedOutput {
+    pipe: Pipe,
+    output: Vec<u8>,
+    max_len: usize,
+}
+
+impl CapturedOutput {
+    fn read_end(&self) -> RawFd {
+        self.pipe.read_end().unwrap()
+    }
+
+    /// Reads everything available in the non-blocking pipe, keeping at most `max_len` bytes
+    fn drain(&mut self) -> Result<(), Error> {
+        let mut buf = [0; 4096];
+        loop {
+            match self.pipe.read(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(len) => {
+                    let kept = len.min(self.max_len.saturating_sub(self.output.len()));
+                    self.output.extend_from_slice(&buf[..kept]);
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(err) if err.kind() == ErrorKind::Interrupted => (),
+                Err(err) => return Err(err.into()),
             }
-            Ok(())
-        };
-        unsafe { self.pre_exec(func) }
+        }
+    }
+
+    /// The output written since the last call
+    fn take(&mut self) -> Result<Vec<u8>, Error> {
+        self.drain()?;
+        Ok(mem::take(&mut self.output))
     }
 }
 
@@ -158,9 +197,66 @@ impl ConfigTarget for Command {
 pub struct Forkserver {
     st_pipe: Pipe,
     ctl_pipe: Pipe,
+    stdout: Option<CapturedOutput>,
+    stderr: Option<CapturedOutput>,
     child_pid: Pid,
     status: i32,
     last_run_timed_out: i32,
+    memlimit: u64,
+}
+
+/// The options of a [`Forkserver`], all off by default.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ForkserverOptions {
+    is_persistent: bool,
+    is_deferred_frksrv: bool,
+    debug_output: bool,
+    capture_stdout: bool,
+    capture_stderr: bool,
+}
+
+impl ForkserverOptions {
+    /// Creates new [`ForkserverOptions`], with all the options off
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tells an AFL++ target to run in persistent mode, `__AFL_LOOP`
+    #[must_use]
+    pub fn with_persistent(mut self, is_persistent: bool) -> Self {
+        self.is_persistent = is_persistent;
+        self
+    }
+
+    /// Tells an AFL++ target to start the forkserver at `__AFL_INIT`, instead of before `main`
+    #[must_use]
+    pub fn with_deferred_frksrv(mut self, is_deferred_frksrv: bool) -> Self {
+        self.is_deferred_frksrv = is_deferred_frksrv;
+        self
+    }
+
+    /// Lets the children print to `stdout`/`stderr`, if their output is not captured
+    #[must_use]
+    pub fn with_debug_output(mut self, debug_output: bool) -> Self {
+        self.debug_output = debug_output;
+        self
+    }
+
+    /// Pipes the `stdout` of the children to the fuzzer, see [`Forkserver::read_stdout`]
+    #[must_use]
+    pub fn with_capture_stdout(mut self, capture_stdout: bool) -> Self {
+        self.capture_stdout = capture_stdout;
+        self
+    }
+
+    /// Pipes the `stderr` of the children to the fuzzer, see [`Forkserver::read_stderr`]
+    #[must_use]
+    pub fn with_capture_stderr(mut self, capture_stderr: bool) -> Self {
+        self.capture_stderr = capture_stderr;
+        self
+    }
 }
 
 impl Forkserver {
@@ -172,17 +268,60 @@ impl Forkserver {
         use_stdin: bool,
         memlimit: u64,
         debug_output: bool,
+    ) -> Result<Self, Error> {
+        Self::with_options(
+            target,
+            args,
+            out_filefd,
+            use_stdin,
+            memlimit,
+            ForkserverOptions::new().with_debug_output(debug_output),
+        )
+    }
+
+    /// Create a new [`Forkserver`] with the given [`ForkserverOptions`].
+    /// The captured output gets read while waiting for the children, see
+    /// [`Forkserver::set_output_max_len`].
+    pub fn with_options(
+        target: String,
+        args: Vec<String>,
+        out_filefd: RawFd,
+        use_stdin: bool,
+        memlimit: u64,
+        options: ForkserverOptions,
     ) -> Result<Self, Error> {
         let mut st_pipe = Pipe::new().unwrap();
         let mut ctl_pipe = Pipe::new().unwrap();
-
-        let (stdout, stderr) = if debug_output {
-            (Stdio::inherit(), Stdio::inherit())
+        let mut stdout_pipe = if options.capture_stdout {
+            Some(Pipe::new()?)
+        } else {
+            None
+        };
+        let mut stderr_pipe = if options.capture_stderr {
+            Some(Pipe::new()?)
         } else {
-            (Stdio::null(), Stdio::null())
+            None
         };
 
-        match Command::new(target)
+        let output = |pipe: &Option<Pipe>| -> Result<Stdio, Error> {
+            Ok(match pipe {
+                // The `Stdio` closes its own copy of the write end
+                Some(pipe) => unsafe { Stdio::from_raw_fd(dup(pipe.write_end().unwrap())?) },
+                None if options.debug_output => Stdio::inherit(),
+                None => Stdio::null(),
+            })
+        };
+        let (stdout, stderr) = (output(&stdout_pipe)?, output(&stderr_pipe)?);
+
+        let mut command = Command::new(target);
+        if options.is_persistent {
+            command.env("__AFL_PERSISTENT", "1");
+        }
+        if options.is_deferred_frksrv {
+            command.env("__AFL_DEFER_FORKSRV", "1");
+        }
+
+        match command
             .args(args)
             .stdin(Stdio::null())
             .stdout(stdout)
@@ -213,15 +352,40 @@ impl Forkserver {
         ctl_pipe.close_read_end();
         st_pipe.close_write_end();
 
+        // The output gets read whenever available, without waiting for more
+        for pipe in [&mut stdout_pipe, &mut stderr_pipe].into_iter().flatten() {
+            pipe.close_write_end();
+            fcntl(
+                pipe.read_end().unwrap(),
+                FcntlArg::F_SETFL(OFlag::O_NONBLOCK),
+            )?;
+        }
+        let captured = |pipe: Option<Pipe>| {
+            pipe.map(|pipe| CapturedOutput {
+                pipe,
+                output: vec![],
+                max_len: 0,
+            })
+        };
+
         Ok(Self {
             st_pipe,
             ctl_pipe,
+            stdout: captured(stdout_pipe),
+            stderr: captured(stderr_pipe),
             child_pid: Pid::from_raw(0),
             status: 0,
             last_run_timed_out: 0,
+            memlimit,
         })
     }
 
+    /// The memory limit of the target in MiB, `0` for none
+    #[must_use]
+    pub fn memlimit(&self) -> u64 {
+        self.memlimit
+    }
+
     /// If the last run timed out
     #[must_use]
     pub fn last_run_timed_out(&self) -> i32 {
@@ -259,21 +423,48 @@ impl Forkserver {
     pub fn read_st(&mut self) -> Result<(usize, i32), Error> {
         let mut buf: [u8; 4] = [0_u8; 4];
 
+        self.wait_st(None)?;
+
         let rlen = self.st_pipe.read(&mut buf)?;
         let val: i32 = i32::from_ne_bytes(buf);
         Ok((rlen, val))
     }
 
-    /// Write to the ctl pipe
-    pub fn write_ctl(&mut self, val: i32) -> Result<usize, Error> {
-        let slen = self.ctl_pipe.write(&val.to_ne_bytes())?;
+    /// Sets how many bytes of the captured stdout and stderr get kept until read, the rest being
+    /// read and dropped. Nothing gets kept by default.
+    pub fn set_output_max_len(&mut self, stdout_max_len: usize, stderr_max_len: usize) {
+        if let Some(stdout) = &mut self.stdout {
+            stdout.max_len = stdout_max_len;
+        }
+        if let Some(stderr) = &mut self.stderr {
+            stderr.max_len = stderr_max_len;
+        }
+    }
 
-        Ok(slen)
+    /// Reads the stdout written by the children since the last read, if captured
+    pub fn read_stdout(&mut self) -> Result<Vec<u8>, Error> {
+        self.stdout
+            .as_mut()
+            .map_or(Ok(vec![]), CapturedOutput::take)
     }
 
-    /// Read a message from the child process.
-    pub fn read_st_timed(&mut self, timeout: &TimeSpec) -> Result<Option<i32>, Error> {
-        let mut buf: [u8; 4] = [0_u8; 4];
+    /// Reads the stderr written by the children since the last read, if captured
+    pub fn read_stderr(&mut self) -> Result<Vec<u8>, Error> {
+        self.stderr
+            .as_mut()
+            .map_or(Ok(vec![]), CapturedOutput::take)
+    }
+
+    /// Drops the captured output not read yet, e.g. written by the forkserver itself
+    pub fn discard_output(&mut self) -> Result<(), Error> {
+        self.read_stdout()?;
+        self.read_stderr()?;
+        Ok(())
+    }
+
+    /// Waits for the st pipe to get readable, up to `timeout`, reading the captured output
+    /// meanwhile. Returns `false` on timeout.
+    fn wait_st(&mut self, timeout: Option<&TimeSpec>) -> Result<bool, Error> {
         let st_read = match self.st_pipe.read_end() {
             Some(fd) => fd,
             None => {
@@ -283,18 +474,62 @@ impl Forkserver {
                 )));
             }
         };
-        let mut readfds = FdSet::new();
-        readfds.insert(st_read);
-        // We'll pass a copied timeout to keep the original timeout intact, because select updates timeout to indicate how much time was left. See select(2)
-        let sret = pselect(
-            Some(readfds.highest().unwrap() + 1),
-            &mut readfds,
-            None,
-            None,
-            Some(timeout),
-            Some(&SigSet::empty()),
-        )?;
-        if sret > 0 {
+        #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+        let deadline = timeout.map(|timeout| {
+            Instant::now() + Duration::new(timeout.tv_sec() as u64, timeout.tv_nsec() as u32)
+        });
+        loop {
+            let mut readfds = FdSet::new();
+            readfds.insert(st_read);
+            for output in [&self.stdout, &self.stderr].into_iter().flatten() {
+                readfds.insert(output.read_end());
+            }
+            let remaining = deadline
+                .map(|deadline| TimeSpec::from(deadline.saturating_duration_since(Instant::now())));
+            let sret = match pselect(
+                Some(readfds.highest().unwrap() + 1),
+                &mut readfds,
+                None,
+                None,
+                remaining.as_ref(),
+                Some(&SigSet::empty()),
+            ) {
+                Ok(sret) => sret,
+                Err(Errno::EINTR) => continue,
+                Err(err) => return Err(err.into()),
+            };
+            if sret == 0 {
+                return Ok(false);
+            }
+            for output in [&mut self.stdout, &mut self.stderr].into_iter().flatten() {
+                if readfds.contains(output.read_end()) {
+                    output.drain()?;
+                }
+            }
+            if readfds.contains(st_read) {
+                return Ok(true);
+            }
+        }
+    }
+
+    /// Read `size` bytes from the st pipe
+    pub fn read_st_bytes(&mut self, size: usize) -> Result<Vec<u8>, Error> {
+        let mut buf = vec![0; size];
+        self.st_pipe.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Write to the ctl pipe
+    pub fn write_ctl(&mut self, val: i32) -> Result<usize, Error> {
+        let slen = self.ctl_pipe.write(&val.to_ne_bytes())?;
+
+        Ok(slen)
+    }
+
+    /// Read a message from the child process.
+    pub fn read_st_timed(&mut self, timeout: &TimeSpec) -> Result<Option<i32>, Error> {
+        let mut buf: [u8; 4] = [0_u8; 4];
+        if self.wait_st(Some(timeout))? {
             if self.st_pipe.read_exact(&mut buf).is_ok() {
                 let val: i32 = i32::from_ne_bytes(buf);
                 Ok(Some(val))
@@ -331,6 +566,13 @@ pub trait HasForkserver {
 
     /// The map of the fuzzer, mutable
     fn shmem_mut(&mut self) -> &mut Option<<<Self as HasForkserver>::SP as ShMemProvider>::ShMem>;
+
+    /// Fills the [`StdOutObserver`] and the [`StdErrObserver`], if any, with the output of the
+    /// last run, and the [`ExitStatusObserver`], if any, with the status of the last run and
+    /// `sanitizer_abort`, or if the captured stderr contains the report of a sanitizer, parsed
+    /// if the last run, exiting with `exit_kind`, crashed.
+    /// Returns if the captured stderr reports that the target ran out of memory.
+    fn observe_run(&mut self, exit_kind: ExitKind, sanitizer_abort: bool) -> Result<bool, Error>;
 }
 
 /// The timeout forkserver executor that wraps around the standard forkserver executor and sets a timeout before each run.
@@ -376,17 +618,10 @@ where
         let mut exit_kind = ExitKind::Ok;
 
         let last_run_timed_out = self.executor.forkserver().last_run_timed_out();
+        self.executor.forkserver_mut().discard_output()?;
 
         match &mut self.executor.shmem_mut() {
-            Some(shmem) => {
-                let target_bytes = input.target_bytes();
-                let size = target_bytes.as_slice().len();
-                let size_in_bytes = size.to_ne_bytes();
-                // The first four bytes tells the size of the shmem.
-                shmem.as_mut_slice()[..4].copy_from_slice(&size_in_bytes[..4]);
-                shmem.as_mut_slice()[SHMEM_FUZZ_HDR_SIZE..(SHMEM_FUZZ_HDR_SIZE + size)]
-                    .copy_from_slice(target_bytes.as_slice());
-            }
+            Some(shmem) => write_shmem_input(shmem, input.target_bytes().as_slice()),
             None => {
                 self.executor
                     .out_file_mut()
@@ -430,33 +665,93 @@ where
             .read_st_timed(&self.timeout)?
         {
             self.executor.forkserver_mut().set_status(status);
-            if libc::WIFSIGNALED(self.executor.forkserver().status()) {
-                exit_kind = ExitKind::Crash;
+            if libc::WIFSIGNALED(status) {
+                exit_kind = signaled_exit_kind(status, self.executor.forkserver().memlimit());
             }
         } else {
             self.executor.forkserver_mut().set_last_run_timed_out(1);
 
             // We need to kill the child in case he has timed out, or we can't get the correct pid in the next call to self.executor.forkserver_mut().read_st()?
             let _ = kill(self.executor.forkserver().child_pid(), self.signal);
-            let (recv_status_len, _) = self.executor.forkserver_mut().read_st()?;
+            let (recv_status_len, status) = self.executor.forkserver_mut().read_st()?;
             if recv_status_len != 4 {
                 return Err(Error::Forkserver(
                     "Could not kill timed-out child".to_string(),
                 ));
             }
+            self.executor.forkserver_mut().set_status(status);
             exit_kind = ExitKind::Timeout;
         }
 
         self.executor
             .forkserver_mut()
             .set_child_pid(Pid::from_raw(0));
+        if self.executor.observe_run(exit_kind, false)? && exit_kind == ExitKind::Crash {
+            exit_kind = ExitKind::Oom;
+        }
 
         Ok(exit_kind)
     }
 }
 
+/// The [`ExitKind`] of a child terminated by a signal, in its `status`: killed under a memory
+/// limit, it ran out of memory, it crashed otherwise
+fn signaled_exit_kind(status: i32, memlimit: u64) -> ExitKind {
+    if memlimit != 0 && libc::WTERMSIG(status) == libc::SIGKILL {
+        ExitKind::Oom
+    } else {
+        ExitKind::Crash
+    }
+}
+
+/// The map size in the handshake status of the forkserver, with [`FS_OPT_MAPSIZE`]
+#[allow(clippy::cast_sign_loss)]
+fn fs_opt_get_mapsize(status: i32) -> usize {
+    (((status & 0x00ff_fffe) >> 1) + 1) as usize
+}
+
+/// If `binary` contains `signature`
+fn contains_signature(binary: &[u8], signature: &[u8]) -> bool {
+    binary
+        .windows(signature.len())
+        .any(|window| window == signature)
+}
+
+/// Parses the autodictionary sent by the forkserver, tokens prefixed by their length on one byte
+fn parse_autodict(dict: &[u8]) -> Tokens {
+    let mut tokens = Tokens::new();
+    let mut offset = 0;
+    while offset < dict.len() {
+        let len = dict[offset] as usize;
+        offset += 1;
+        if offset + len > dict.len() {
+            break;
+        }
+        tokens.add_token(&dict[offset..offset + len].to_vec());
+        offset += len;
+    }
+    tokens
+}
+
+/// Writes the testcase into the shared memory, after its length, as `__AFL_SHM_FUZZ` targets read it.
+/// Testcases bigger than the shared memory get truncated.
+fn write_shmem_input<SHM>(shmem: &mut SHM, bytes: &[u8])
+where
+    SHM: ShMem,
+{
+    let size = bytes.len().min(MAX_FILE);
+    // The first four bytes tell the size of the testcase, which fits since it's at most `MAX_FILE`.
+    #[allow(clippy::cast_possible_truncation)]
+    let size_in_bytes = (size as u32).to_ne_bytes();
+    let buf = shmem.as_mut_slice();
+    buf[..SHMEM_FUZZ_HDR_SIZE].copy_from_slice(&size_in_bytes);
+    buf[SHMEM_FUZZ_HDR_SIZE..(SHMEM_FUZZ_HDR_SIZE + size)].copy_from_slice(&bytes[..size]);
+}
+
 /// This [`Executor`] can run binaries compiled for AFL/AFL++ that make use of a forkserver.
 /// Shared memory feature is also available, but you have to set things up in your code.
+/// The AFL++ binaries in persistent mode (`__AFL_LOOP`) or with a deferred forkserver (`__AFL_INIT`)
+/// are detected by their signatures, and set up accordingly.
 /// Please refer to AFL++'s docs. <https://github.com/AFLplusplus/AFLplusplus/blob/stable/instrumentation/README.persistent_mode.md>
 pub struct ForkserverExecutor<I, OT, S, SP>
 where
@@ -470,6 +765,12 @@ where
     forkserver: Forkserver,
     observers: OT,
     map: Option<SP::ShMem>,
+    is_persistent: bool,
+    is_deferred_frksrv: bool,
+    map_size: Option<usize>,
+    autotokens: Option<Tokens>,
+    /// The maximum lengths of the captured stdout and stderr, if observed
+    capture_len: (Option<usize>, Option<usize>),
     phantom: PhantomData<(I, S)>,
     /// Cache that indicates if we have a asan observer registered.
     has_asan_observer: Option<bool>,
@@ -489,6 +790,11 @@ where
             .field("forkserver", &self.forkserver)
             .field("observers", &self.observers)
             .field("map", &self.map)
+            .field("is_persistent", &self.is_persistent)
+            .field("is_deferred_frksrv", &self.is_deferred_frksrv)
+            .field("map_size", &self.map_size)
+            .field("autotokens", &self.autotokens)
+            .field("capture_len", &self.capture_len)
             .finish()
     }
 }
@@ -507,7 +813,7 @@ where
         observers: OT,
         debug_child: bool,
     ) -> Result<Self, Error> {
-        Self::new_internal(target, arguments, observers, debug_child, None)
+        Self::new_internal(target, arguments, observers, debug_child, 0, None)
     }
 }
 
@@ -517,7 +823,9 @@ where
     OT: ObserversTuple<I, S>,
     SP: ShMemProvider,
 {
-    /// Creates a new [`ForkserverExecutor`] with the given target, arguments and observers.
+    /// Creates a new [`ForkserverExecutor`] with the given target, arguments and observers,
+    /// delivering the testcases over shared memory, if the target supports AFL++'s `__AFL_SHM_FUZZ`
+    /// protocol, and over the input file or stdin otherwise.
     pub fn with_shmem_inputs(
         target: String,
         arguments: &[String],
@@ -530,16 +838,43 @@ where
             arguments,
             observers,
             debug_child,
+            0,
             Some(shmem_provider),
         )
     }
 
+    /// Creates a new [`ForkserverExecutor`] with the given target, arguments and observers,
+    /// limiting the address space of the target to `memlimit` MiB, like `afl-fuzz -m`, and
+    /// delivering the testcases over shared memory if a `shmem_provider` is given.
+    /// Under a limit, a target killed by `SIGKILL` is reported as [`ExitKind::Oom`], as is a target
+    /// whose captured stderr holds the out-of-memory report of a sanitizer. The sanitizers reserve more address space than any sensible
+    /// limit, so don't limit the memory of sanitized targets.
+    pub fn with_memlimit(
+        target: String,
+        arguments: &[String],
+        observers: OT,
+        debug_child: bool,
+        memlimit: u64,
+        shmem_provider: Option<&mut SP>,
+    ) -> Result<Self, Error> {
+        Self::new_internal(
+            target,
+            arguments,
+            observers,
+            debug_child,
+            memlimit,
+            shmem_provider,
+        )
+    }
+
     /// Creates a new [`ForkserverExecutor`] with the given target, arguments and observers, with debug mode
+    #[allow(clippy::too_many_lines)]
     fn new_internal(
         target: String,
         arguments: &[String],
         observers: OT,
         debug_child: bool,
+        memlimit: u64,
         shmem_provider: Option<&mut SP>,
     ) -> Result<Self, Error> {
         let mut args = Vec::<String>::new();
@@ -557,7 +892,7 @@ where
 
         let out_file = OutFile::create(&out_filename)?;
 
-        let map = match shmem_provider {
+        let mut map = match shmem_provider {
             None => None,
             Some(provider) => {
                 // setup shared memory
@@ -570,16 +905,37 @@ where
             }
         };
 
-        let mut forkserver = Forkserver::new(
+        // AFL++ tells the persistent and deferred binaries by the signatures embedded in them
+        let binary = fs::read(&target).unwrap_or_default();
+        let is_persistent = contains_signature(&binary, PERSIST_SIG);
+        let is_deferred_frksrv = contains_signature(&binary, DEFER_SIG);
+        drop(binary);
+
+        let capture_len = (
+            observers
+                .match_name::<StdOutObserver>(STDOUT_OBSERVER_NAME)
+                .map(StdOutObserver::max_len),
+            observers
+                .match_name::<StdErrObserver>(STDERR_OBSERVER_NAME)
+                .map(StdErrObserver::max_len),
+        );
+
+        let mut forkserver = Forkserver::with_options(
             target.clone(),
             args.clone(),
             out_file.as_raw_fd(),
             use_stdin,
-            0,
-            debug_child,
+            memlimit,
+            ForkserverOptions::new()
+                .with_persistent(is_persistent)
+                .with_deferred_frksrv(is_deferred_frksrv)
+                .with_debug_output(debug_child)
+                .with_capture_stdout(capture_len.0.is_some())
+                .with_capture_stderr(capture_len.1.is_some()),
         )?;
+        forkserver.set_output_max_len(capture_len.0.unwrap_or(0), capture_len.1.unwrap_or(0));
 
-        let (rlen, status) = forkserver.read_st()?; // Initial handshake, read 4-bytes hello message from the forkserver.
+        let (rlen, mut status) = forkserver.read_st()?; // Initial handshake, read 4-bytes hello message from the forkserver.
 
         if rlen != 4 {
             return Err(Error::Forkserver(
@@ -587,11 +943,30 @@ where
             ));
         }
         println!("All right - fork server is up.");
+        let mut map_size = None;
+        let mut autotokens = None;
         // If forkserver is responding, we then check if there's any option enabled.
         if status & FS_OPT_ENABLED == FS_OPT_ENABLED {
-            if (status & FS_OPT_SHDMEM_FUZZ == FS_OPT_SHDMEM_FUZZ) & map.is_some() {
-                println!("Using SHARED MEMORY FUZZING feature.");
-                let send_status = FS_OPT_ENABLED | FS_OPT_SHDMEM_FUZZ;
+            if status & FS_OPT_OLD_AFLPP_WORKAROUND == FS_OPT_OLD_AFLPP_WORKAROUND {
+                status &= !FS_OPT_OLD_AFLPP_WORKAROUND;
+            }
+            if status & FS_OPT_MAPSIZE == FS_OPT_MAPSIZE {
+                map_size = Some(fs_opt_get_mapsize(status));
+            }
+
+            let use_shmem_fuzz =
+                (status & FS_OPT_SHDMEM_FUZZ == FS_OPT_SHDMEM_FUZZ) & map.is_some();
+            let send_autodict = status & FS_OPT_AUTODICT == FS_OPT_AUTODICT;
+            // The target waits for an answer to these options, both at once
+            if use_shmem_fuzz || send_autodict {
+                let mut send_status = FS_OPT_ENABLED;
+                if use_shmem_fuzz {
+                    println!("Using SHARED MEMORY FUZZING feature.");
+                    send_status |= FS_OPT_SHDMEM_FUZZ;
+                }
+                if send_autodict {
+                    send_status |= FS_OPT_AUTODICT;
+                }
 
                 let send_len = forkserver.write_ctl(send_status)?;
                 if send_len != 4 {
@@ -599,9 +974,30 @@ where
                         "Writing to forkserver failed.".to_string(),
                     ));
                 }
+
+                if send_autodict {
+                    let (read_len, dict_size) = forkserver.read_st()?;
+                    let dict_size = usize::try_from(dict_size).unwrap_or_default();
+                    if read_len != 4 || !(2..=MAX_FILE).contains(&dict_size) {
+                        return Err(Error::Forkserver(
+                            "Reading the autodictionary size from the forkserver failed."
+                                .to_string(),
+                        ));
+                    }
+                    let dict = forkserver.read_st_bytes(dict_size)?;
+                    autotokens = Some(parse_autodict(&dict));
+                }
+            }
+            if !use_shmem_fuzz && map.is_some() {
+                // The target reads its input from the file or stdin, as usual
+                println!(
+                    "The target does not support shared memory fuzzing, using the input file."
+                );
+                map = None;
             }
         } else {
             println!("Forkserver Options are not available.");
+            map = None;
         }
 
         Ok(Self {
@@ -612,10 +1008,40 @@ where
             forkserver,
             observers,
             map,
+            is_persistent,
+            is_deferred_frksrv,
+            map_size,
+            autotokens,
+            capture_len,
             phantom: PhantomData,
         })
     }
 
+    /// If the target uses AFL++'s persistent mode, `__AFL_LOOP`, running several testcases per fork
+    #[must_use]
+    pub fn is_persistent(&self) -> bool {
+        self.is_persistent
+    }
+
+    /// If the target starts its forkserver late, at `__AFL_INIT`
+    #[must_use]
+    pub fn is_deferred_frksrv(&self) -> bool {
+        self.is_deferred_frksrv
+    }
+
+    /// The size of the coverage map of the target, if it told it in the handshake
+    #[must_use]
+    pub fn map_size(&self) -> Option<usize> {
+        self.map_size
+    }
+
+    /// The tokens the target collected at compile time and sent in the handshake, if any,
+    /// to add to the [`Tokens`] of the state
+    #[must_use]
+    pub fn autotokens(&self) -> Option<&Tokens> {
+        self.autotokens.as_ref()
+    }
+
     /// The `target` binary that's going to run.
     pub fn target(&self) -> &String {
         &self.target
@@ -653,17 +1079,11 @@ where
     ) -> Result<ExitKind, Error> {
         let mut exit_kind = ExitKind::Ok;
 
+        self.forkserver.discard_output()?;
+
         // Write to testcase
         match &mut self.map {
-            Some(map) => {
-                let target_bytes = input.target_bytes();
-                let size = target_bytes.as_slice().len();
-                let size_in_bytes = size.to_ne_bytes();
-                // The first four bytes tells the size of the shmem.
-                map.as_mut_slice()[..4].copy_from_slice(&size_in_bytes[..4]);
-                map.as_mut_slice()[SHMEM_FUZZ_HDR_SIZE..(SHMEM_FUZZ_HDR_SIZE + size)]
-                    .copy_from_slice(target_bytes.as_slice());
-            }
+            Some(map) => write_shmem_input(map, input.target_bytes().as_slice()),
             None => {
                 self.out_file.write_buf(input.target_bytes().as_slice())?;
             }
@@ -702,8 +1122,9 @@ where
 
         self.forkserver.set_status(status);
 
+        let mut sanitizer_abort = false;
         if libc::WIFSIGNALED(self.forkserver.status()) {
-            exit_kind = ExitKind::Crash;
+            exit_kind = signaled_exit_kind(status, self.forkserver.memlimit());
             if self.has_asan_observer.is_none() {
                 self.has_asan_observer = Some(
                     self.observers()
@@ -711,15 +1132,31 @@ where
                         .is_some(),
                 );
             }
-            if self.has_asan_observer.unwrap() {
-                self.observers_mut()
-                    .match_name_mut::<ASANBacktraceObserver>("ASANBacktraceObserver")
-                    .unwrap()
-                    .parse_asan_output_from_asan_log_file(pid);
+            let has_report_observer = self
+                .observers()
+                .match_name::<SanitizerReportObserver>(SANITIZER_REPORT_OBSERVER_NAME)
+                .is_some();
+            if self.has_asan_observer.unwrap() || has_report_observer {
+                // The sanitizer only writes its log when reporting an error
+                let log_path = format!("{}.{}", ASAN_LOG_PATH, pid);
+                if let Ok(log) = fs::read_to_string(&log_path) {
+                    let _ = fs::remove_file(&log_path);
+                    sanitizer_abort = true;
+                    if self.has_asan_observer.unwrap() {
+                        self.observers_mut()
+                            .match_name_mut::<ASANBacktraceObserver>("ASANBacktraceObserver")
+                            .unwrap()
+                            .parse_asan_output(&log);
+                    }
+                    observe_sanitizer_report(self.observers_mut(), &log, exit_kind);
+                }
             }
         }
 
         self.forkserver.set_child_pid(Pid::from_raw(0));
+        if self.observe_run(exit_kind, sanitizer_abort)? && exit_kind == ExitKind::Crash {
+            exit_kind = ExitKind::Oom;
+        }
 
         Ok(exit_kind)
     }
@@ -779,6 +1216,35 @@ where
     fn shmem_mut(&mut self) -> &mut Option<SP::ShMem> {
         &mut self.map
     }
+
+    fn observe_run(
+        &mut self,
+        exit_kind: ExitKind,
+        mut sanitizer_abort: bool,
+    ) -> Result<bool, Error> {
+        let mut oom = false;
+        if self.capture_len.0.is_some() {
+            let output = self.forkserver.read_stdout()?;
+            self.observers
+                .match_name_mut::<StdOutObserver>(STDOUT_OBSERVER_NAME)
+                .unwrap()
+                .observe(&output);
+        }
+        if self.capture_len.1.is_some() {
+            let output = self.forkserver.read_stderr()?;
+            let output_str = String::from_utf8_lossy(&output);
+            sanitizer_abort |= is_sanitizer_report(&output_str);
+            oom = is_oom_report(&output_str);
+            observe_sanitizer_report(&mut self.observers, &output_str, exit_kind);
+            self.observers
+                .match_name_mut::<StdErrObserver>(STDERR_OBSERVER_NAME)
+                .unwrap()
+                .observe(&output);
+        }
+        let (signal, exit_code) = split_wait_status(self.forkserver.status());
+        observe_exit_status(&mut self.observers, signal, exit_code, sanitizer_abort);
+        Ok(oom)
+    }
 }
 
 impl<E, I, OT, S> HasObservers<I, OT, S> for TimeoutForkserverExecutor<E>
@@ -848,4 +1314,92 @@ mod tests {
         };
         assert!(result);
     }
+    #[test]
+    fn test_forkserver_output_drained() {
+        use nix::{
+            fcntl::{fcntl, FcntlArg, OFlag},
+            sys::time::{TimeSpec, TimeValLike},
+            unistd::{write, Pid},
+        };
+        use std::thread;
+
+        use crate::{
+            bolts::os::pipes::Pipe,
+            executors::forkserver::{CapturedOutput, Forkserver},
+        };
+
+        let st_pipe = Pipe::new().unwrap();
+        let stdout_pipe = Pipe::new().unwrap();
+        fcntl(
+            stdout_pipe.read_end().unwrap(),
+            FcntlArg::F_SETFL(OFlag::O_NONBLOCK),
+        )
+        .unwrap();
+        let (st_write, stdout_write) = (
+            st_pipe.write_end().unwrap(),
+            stdout_pipe.write_end().unwrap(),
+        );
+        let mut forkserver = Forkserver {
+            st_pipe,
+            ctl_pipe: Pipe::new().unwrap(),
+            stdout: Some(CapturedOutput {
+                pipe: stdout_pipe,
+                output: vec![],
+                max_len: 1024,
+            }),
+            stderr: None,
+            child_pid: Pid::from_raw(0),
+            status: 0,
+            last_run_timed_out: 0,
+            memlimit: 0,
+        };
+
+        // A child writing more than the capacity of the pipe before exiting
+        let child = thread::spawn(move || {
+            let output = [b'a'; 4096];
+            for _ in 0..64 {
+                let mut written = 0;
+                while written < output.len() {
+                    written += write(stdout_write, &output[written..]).unwrap();
+                }
+            }
+            write(st_write, &0_i32.to_ne_bytes()).unwrap();
+        });
+
+        let status = forkserver.read_st_timed(&TimeSpec::seconds(10)).unwrap();
+        child.join().unwrap();
+        assert_eq!(status, Some(0));
+        assert_eq!(forkserver.read_stdout().unwrap(), vec![b'a'; 1024]);
+        assert!(forkserver.read_stderr().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fs_opt_get_mapsize() {
+        use crate::executors::forkserver::{fs_opt_get_mapsize, FS_OPT_ENABLED, FS_OPT_MAPSIZE};
+
+        // As AFL++'s `FS_OPT_SET_MAPSIZE` encodes it
+        for map_size in [1, 2, 65536, 0x0080_0000] {
+            let status = FS_OPT_ENABLED | FS_OPT_MAPSIZE | (((map_size - 1) << 1) & 0x00ff_fffe);
+            assert_eq!(
+                fs_opt_get_mapsize(status),
+                usize::try_from(map_size).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_autodict() {
+        use crate::executors::forkserver::parse_autodict;
+
+        let tokens = parse_autodict(b"\x05MAGIC\x02ab\x00\x05MAGIC\x03xyz");
+        assert_eq!(
+            tokens.tokens(),
+            &[b"MAGIC".to_vec(), b"ab".to_vec(), vec![], b"xyz".to_vec()]
+        );
+
+        // A token cut short by the end of the dictionary is left out
+        let tokens = parse_autodict(b"\x02ab\x05MAG");
+        assert_eq!(tokens.tokens(), &[b"ab".to_vec()]);
+        assert!(parse_autodict(b"").tokens().is_empty());
+    }
 }
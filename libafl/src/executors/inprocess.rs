@@ -3,6 +3,7 @@
 //!
 //! Needs the `fork` feature flag.
 
+use alloc::{boxed::Box, vec::Vec};
 use core::{
     ffi::c_void,
     fmt::{self, Debug, Formatter},
@@ -24,7 +25,7 @@ use libc::{siginfo_t, ucontext_t};
 
 #[cfg(all(feature = "std", unix))]
 use nix::{
-    sys::wait::{waitpid, WaitStatus},
+    sys::wait::WaitStatus,
     unistd::{fork, ForkResult},
 };
 
@@ -36,9 +37,16 @@ use crate::bolts::os::unix_signals::setup_signal_handler;
 #[cfg(all(windows, feature = "std"))]
 use crate::bolts::os::windows_exceptions::setup_exception_handler;
 #[cfg(all(feature = "std", unix))]
-use crate::bolts::shmem::ShMemProvider;
+use crate::bolts::{os::set_memlimit, shmem::ShMemProvider};
+#[cfg(all(feature = "std", unix))]
+use crate::observers::{
+    exit_status::observe_exit_status,
+    mem_usage::{observe_mem_usage, wait_with_max_rss},
+};
 #[cfg(feature = "std")]
 use crate::observers::{BacktraceObserver, HarnessType};
+#[cfg(all(feature = "std", unix))]
+use core::time::Duration;
 
 #[cfg(windows)]
 use windows::Win32::System::Threading::SetThreadStackGuarantee;
@@ -65,6 +73,10 @@ use crate::executors::inprocess::bt_signal_handlers::{
 };
 
 /// The inmem executor simply calls a target function, then returns afterwards.
+/// This is the persistent mode on every platform: on Windows, the crashes of the harness, e.g.
+/// access violations, are caught by a vectored exception handler and reported as
+/// [`ExitKind::Crash`], the timeouts of a [`crate::executors::TimeoutExecutor`] as
+/// [`ExitKind::Timeout`].
 #[allow(dead_code)]
 pub struct InProcessExecutor<'a, H, I, OT, S>
 where
@@ -114,6 +126,9 @@ where
         let ret = (self.harness_fn)(input);
 
         self.handlers.post_run_target();
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(exit_kind = ?ret, "harness returned");
         Ok(ret)
     }
 }
@@ -222,15 +237,51 @@ where
     pub fn handlers_mut(&mut self) -> &mut InProcessHandlers {
         &mut self.handlers
     }
+
+    /// Adds a hook run in the crash and timeout handlers, in the signal handler context, before
+    /// the input is evaluated and saved, e.g. to flush the logs of the target
+    #[must_use]
+    pub fn with_pre_crash_hook(mut self, hook: InProcessCrashHook) -> Self {
+        self.handlers.pre_crash_hooks.push(hook);
+        self
+    }
+
+    /// Adds a hook run in the crash and timeout handlers, in the signal handler context, after
+    /// the input is saved, before the process exits to be restarted, e.g. to reset peripherals
+    #[must_use]
+    pub fn with_post_crash_hook(mut self, hook: InProcessCrashHook) -> Self {
+        self.handlers.post_crash_hooks.push(hook);
+        self
+    }
 }
 
+/// A hook run in the crash and timeout handlers of the [`InProcessExecutor`], with the
+/// [`ExitKind`] of the run. The state and the executor of the run are available with
+/// [`inprocess_get_state`] and [`inprocess_get_executor`].
+/// Keep in mind that it runs in a signal handler, maybe in a corrupted process.
+pub type InProcessCrashHook = Box<dyn Fn(ExitKind)>;
+
 /// The inmem executor's handlers.
-#[derive(Debug)]
 pub struct InProcessHandlers {
     /// On crash C function pointer
     pub crash_handler: *const c_void,
     /// On timeout C function pointer
     pub timeout_handler: *const c_void,
+    /// The hooks run before the crashing or timeouting input is saved
+    pub pre_crash_hooks: Vec<InProcessCrashHook>,
+    /// The hooks run after the crashing or timeouting input is saved
+    pub post_crash_hooks: Vec<InProcessCrashHook>,
+}
+
+impl Debug for InProcessHandlers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InProcessHandlers")
+            .field("crash_handler", &self.crash_handler)
+            .field("timeout_handler", &self.timeout_handler)
+            .field("pre_crash_hooks", &self.pre_crash_hooks.len())
+            .field("post_crash_hooks", &self.post_crash_hooks.len())
+            .finish()
+    }
 }
 
 impl InProcessHandlers {
@@ -257,6 +308,7 @@ impl InProcessHandlers {
             );
             data.crash_handler = self.crash_handler;
             data.timeout_handler = self.timeout_handler;
+            data.handlers_ptr = self as *const _ as *const c_void;
             // Direct raw pointers access /aliasing is pretty undefined behavior.
             // Since the state and event may have moved in memory, refresh them right before the signal may happen
             write_volatile(&mut data.state_ptr, _state as *mut _ as *mut c_void);
@@ -277,6 +329,7 @@ impl InProcessHandlers {
             );
             data.crash_handler = self.crash_handler;
             data.timeout_handler = self.timeout_handler;
+            data.handlers_ptr = self as *const _ as *const c_void;
             // Direct raw pointers access /aliasing is pretty undefined behavior.
             // Since the state and event may have moved in memory, refresh them right before the signal may happen
             write_volatile(&mut data.state_ptr, _state as *mut _ as *mut c_void);
@@ -323,6 +376,8 @@ impl InProcessHandlers {
                     as *const c_void,
                 timeout_handler: unix_signal_handler::inproc_timeout_handler::<E, EM, I, OF, OT, S, Z>
                     as *const _,
+                pre_crash_hooks: vec![],
+                post_crash_hooks: vec![],
             })
         }
         #[cfg(all(windows, feature = "std"))]
@@ -350,12 +405,16 @@ impl InProcessHandlers {
                     S,
                     Z,
                 > as *const c_void,
+                pre_crash_hooks: vec![],
+                post_crash_hooks: vec![],
             })
         }
         #[cfg(not(any(unix, all(windows, feature = "std"))))]
         Ok(Self {
             crash_handler: ptr::null(),
             timeout_handler: ptr::null(),
+            pre_crash_hooks: vec![],
+            post_crash_hooks: vec![],
         })
     }
 
@@ -365,6 +424,8 @@ impl InProcessHandlers {
         Self {
             crash_handler: ptr::null(),
             timeout_handler: ptr::null(),
+            pre_crash_hooks: vec![],
+            post_crash_hooks: vec![],
         }
     }
 }
@@ -396,6 +457,7 @@ pub struct InProcessExecutorHandlerData {
     pub current_input_ptr: *const c_void,
     pub crash_handler: *const c_void,
     pub timeout_handler: *const c_void,
+    pub handlers_ptr: *const c_void,
     #[cfg(windows)]
     pub tp_timer: *mut c_void,
     #[cfg(windows)]
@@ -406,6 +468,30 @@ pub struct InProcessExecutorHandlerData {
     pub timeout_input_ptr: *mut c_void,
 }
 
+impl InProcessExecutorHandlerData {
+    /// Runs the [`InProcessHandlers::pre_crash_hooks`] of the current run
+    #[cfg(any(unix, all(windows, feature = "std")))]
+    fn run_pre_crash_hooks(&self, exit_kind: ExitKind) {
+        if let Some(handlers) = unsafe { (self.handlers_ptr as *const InProcessHandlers).as_ref() }
+        {
+            for hook in &handlers.pre_crash_hooks {
+                hook(exit_kind);
+            }
+        }
+    }
+
+    /// Runs the [`InProcessHandlers::post_crash_hooks`] of the current run
+    #[cfg(any(unix, all(windows, feature = "std")))]
+    fn run_post_crash_hooks(&self, exit_kind: ExitKind) {
+        if let Some(handlers) = unsafe { (self.handlers_ptr as *const InProcessHandlers).as_ref() }
+        {
+            for hook in &handlers.post_crash_hooks {
+                hook(exit_kind);
+            }
+        }
+    }
+}
+
 unsafe impl Send for InProcessExecutorHandlerData {}
 unsafe impl Sync for InProcessExecutorHandlerData {}
 
@@ -456,6 +542,8 @@ pub static mut GLOBAL_STATE: InProcessExecutorHandlerData = InProcessExecutorHan
     crash_handler: ptr::null(),
     /// The timeout handler fn
     timeout_handler: ptr::null(),
+    /// The handlers of the current run, with the crash hooks
+    handlers_ptr: ptr::null(),
     #[cfg(windows)]
     tp_timer: ptr::null_mut(),
     #[cfg(windows)]
@@ -632,8 +720,8 @@ mod unix_signal_handler {
         },
         feedbacks::Feedback,
         fuzzer::HasObjective,
-        inputs::Input,
-        observers::ObserversTuple,
+        inputs::{input_hash, Input},
+        observers::{exit_status::observe_exit_status, ObserversTuple},
         state::{HasClientPerfMonitor, HasMetadata, HasSolutions},
     };
 
@@ -721,6 +809,8 @@ mod unix_signal_handler {
         let input = (data.current_input_ptr as *const I).as_ref().unwrap();
         data.current_input_ptr = ptr::null();
 
+        data.run_pre_crash_hooks(ExitKind::Timeout);
+
         observers
             .post_exec_all(state, input, &ExitKind::Timeout)
             .expect("Observers post_exec_all failed");
@@ -746,11 +836,16 @@ mod unix_signal_handler {
                     state,
                     Event::Objective {
                         objective_size: state.solutions().count(),
+                        input_hash: input_hash(input),
+                        exit_kind: ExitKind::Timeout,
+                        signal: None,
                     },
                 )
                 .expect("Could not send timeouting input");
         }
 
+        data.run_post_crash_hooks(ExitKind::Timeout);
+
         event_mgr.on_restart(state).unwrap();
 
         #[cfg(feature = "std")]
@@ -833,9 +928,12 @@ mod unix_signal_handler {
             let input = (data.current_input_ptr as *const I).as_ref().unwrap();
             data.current_input_ptr = ptr::null();
 
+            data.run_pre_crash_hooks(ExitKind::Crash);
+
             #[cfg(feature = "std")]
             eprintln!("Triggering post_exec_all from crash_handler");
 
+            observe_exit_status(observers, Some(signal.into()), None, false);
             observers
                 .post_exec_all(state, input, &ExitKind::Crash)
                 .expect("Observers post_exec_all failed");
@@ -874,11 +972,16 @@ mod unix_signal_handler {
                         state,
                         Event::Objective {
                             objective_size: state.solutions().count(),
+                            input_hash: input_hash(input),
+                            exit_kind: ExitKind::Crash,
+                            signal: Some(signal as i32),
                         },
                     )
                     .expect("Could not send crashing input");
             }
 
+            data.run_post_crash_hooks(ExitKind::Crash);
+
             event_mgr.on_restart(state).unwrap();
 
             #[cfg(feature = "std")]
@@ -912,7 +1015,7 @@ mod windows_exception_handler {
         },
         feedbacks::Feedback,
         fuzzer::HasObjective,
-        inputs::Input,
+        inputs::{input_hash, Input},
         observers::ObserversTuple,
         state::{HasClientPerfMonitor, HasMetadata, HasSolutions},
     };
@@ -992,6 +1095,8 @@ mod windows_exception_handler {
                 let input = (data.timeout_input_ptr as *const I).as_ref().unwrap();
                 data.timeout_input_ptr = ptr::null_mut();
 
+                data.run_pre_crash_hooks(ExitKind::Timeout);
+
                 let interesting = fuzzer
                     .objective_mut()
                     .is_interesting(state, event_mgr, input, observers, &ExitKind::Timeout)
@@ -1013,11 +1118,16 @@ mod windows_exception_handler {
                             state,
                             Event::Objective {
                                 objective_size: state.solutions().count(),
+                                input_hash: input_hash(input),
+                                exit_kind: ExitKind::Timeout,
+                                signal: None,
                             },
                         )
                         .expect("Could not send timeouting input");
                 }
 
+                data.run_post_crash_hooks(ExitKind::Timeout);
+
                 event_mgr.on_restart(state).unwrap();
 
                 #[cfg(feature = "std")]
@@ -1141,6 +1251,8 @@ mod windows_exception_handler {
             // Make sure we don't crash in the crash handler forever.
             data.current_input_ptr = ptr::null();
 
+            data.run_pre_crash_hooks(ExitKind::Crash);
+
             let interesting = fuzzer
                 .objective_mut()
                 .is_interesting(state, event_mgr, input, observers, &ExitKind::Crash)
@@ -1163,11 +1275,16 @@ mod windows_exception_handler {
                         state,
                         Event::Objective {
                             objective_size: state.solutions().count(),
+                            input_hash: input_hash(input),
+                            exit_kind: ExitKind::Crash,
+                            signal: None,
                         },
                     )
                     .expect("Could not send crashing input");
             }
 
+            data.run_post_crash_hooks(ExitKind::Crash);
+
             event_mgr.on_restart(state).unwrap();
 
             #[cfg(feature = "std")]
@@ -1344,7 +1461,13 @@ impl Handler for InProcessForkExecutorGlobalData {
     #[cfg(unix)]
     fn handle(&mut self, signal: Signal, info: siginfo_t, context: &mut ucontext_t) {
         match signal {
-            Signal::SigUser2 | Signal::SigAlarm => (),
+            Signal::SigUser2 => (),
+            // The timeout of the child, see `InProcessForkExecutor::with_timeout`: let it kill the
+            // child, for the parent to tell it
+            Signal::SigAlarm => unsafe {
+                libc::signal(libc::SIGALRM, libc::SIG_DFL);
+                libc::raise(libc::SIGALRM);
+            },
             _ => unsafe {
                 if !FORK_EXECUTOR_GLOBAL_DATA.crash_handler.is_null() {
                     let func: ForkHandlerFuncPtr =
@@ -1388,7 +1511,21 @@ impl Handler for InProcessForkExecutorGlobalData {
     }
 }
 
+/// The exit code of the child of the [`InProcessForkExecutor`] for an [`ExitKind::Oom`]
+#[cfg(all(feature = "std", unix))]
+const FORK_CHILD_OOM_EXIT_CODE: i32 = 0x4f;
+/// The exit code of the child of the [`InProcessForkExecutor`] for an [`ExitKind::Timeout`]
+#[cfg(all(feature = "std", unix))]
+const FORK_CHILD_TIMEOUT_EXIT_CODE: i32 = 0x54;
+
 /// [`InProcessForkExecutor`] is an executor that forks the current process before each execution.
+/// The harness runs in the child, so crashes, leaks and changes to the global state never reach the
+/// fuzzer, which needs no restarting event manager, at the cost of a fork per execution.
+/// The observers stay in the parent: the harness has to write its coverage into a map in shared
+/// memory, e.g. a [`crate::observers::StdMapObserver::new_from_ptr`] over a `ShMem` of the
+/// `shmem_provider`, allocated before the executor.
+/// The child exiting with a signal, or with any exit code but `0`, e.g. after a panic, is reported
+/// as [`ExitKind::Crash`], except for a `SIGKILL` under a memory limit, reported as [`ExitKind::Oom`].
 #[cfg(all(feature = "std", unix))]
 pub struct InProcessForkExecutor<'a, H, I, OT, S, SP>
 where
@@ -1401,6 +1538,8 @@ where
     shmem_provider: SP,
     observers: OT,
     handlers: InChildProcessHandlers,
+    timeout: Option<Duration>,
+    memlimit: u64,
     phantom: PhantomData<(I, S)>,
 }
 
@@ -1416,6 +1555,8 @@ where
         f.debug_struct("InProcessForkExecutor")
             .field("observers", &self.observers)
             .field("shmem_provider", &self.shmem_provider)
+            .field("timeout", &self.timeout)
+            .field("memlimit", &self.memlimit)
             .finish()
     }
 }
@@ -1448,43 +1589,100 @@ where
                     self.handlers
                         .pre_run_target(self, fuzzer, state, mgr, input);
 
-                    match self
+                    if let Some(backtrace_observer) = self
                         .observers()
                         .match_name::<BacktraceObserver>("BacktraceObserver")
-                        .unwrap()
-                        .harness_type()
                     {
-                        crate::observers::HarnessType::FFI => {
-                            setup_signal_handler(&mut FORK_EXECUTOR_GLOBAL_DATA)?;
+                        match backtrace_observer.harness_type() {
+                            crate::observers::HarnessType::FFI => {
+                                setup_signal_handler(&mut FORK_EXECUTOR_GLOBAL_DATA)?;
+                            }
+                            crate::observers::HarnessType::RUST => {
+                                setup_child_panic_hook::<
+                                    InProcessForkExecutor<H, I, OT, S, SP>,
+                                    I,
+                                    OT,
+                                    S,
+                                    InProcessForkExecutorGlobalData,
+                                >(&FORK_EXECUTOR_GLOBAL_DATA);
+                            }
                         }
-                        crate::observers::HarnessType::RUST => {
-                            setup_child_panic_hook::<
-                                InProcessForkExecutor<H, I, OT, S, SP>,
-                                I,
-                                OT,
-                                S,
-                                InProcessForkExecutorGlobalData,
-                            >(&FORK_EXECUTOR_GLOBAL_DATA);
+                    }
+
+                    if let Some(timeout) = self.timeout {
+                        // The child gets killed by `SIGALRM` once the timeout elapsed, even if the
+                        // parent installed a handler for it, e.g. for an `InProcessExecutor`
+                        libc::signal(libc::SIGALRM, libc::SIG_DFL);
+                        #[allow(clippy::cast_possible_wrap)]
+                        let it_value = libc::timeval {
+                            tv_sec: timeout.as_secs() as libc::time_t,
+                            tv_usec: libc::suseconds_t::from(timeout.subsec_micros()),
+                        };
+                        let timer = libc::itimerval {
+                            it_interval: libc::timeval {
+                                tv_sec: 0,
+                                tv_usec: 0,
+                            },
+                            it_value,
+                        };
+                        libc::setitimer(libc::ITIMER_REAL, &timer, ptr::null_mut());
+                    }
+
+                    if self.memlimit != 0 {
+                        if let Err(err) = set_memlimit(self.memlimit) {
+                            println!("Could not set the memory limit of the child: {}", err);
                         }
                     }
 
-                    (self.harness_fn)(input);
+                    let exit_code = match (self.harness_fn)(input) {
+                        ExitKind::Ok => 0,
+                        ExitKind::Crash => libc::abort(),
+                        ExitKind::Oom => FORK_CHILD_OOM_EXIT_CODE,
+                        ExitKind::Timeout => FORK_CHILD_TIMEOUT_EXIT_CODE,
+                    };
 
-                    std::process::exit(0);
+                    std::process::exit(exit_code);
 
                     Ok(ExitKind::Ok)
                 }
                 Ok(ForkResult::Parent { child }) => {
                     // Parent
-                    println!("from parent {} child is {}", std::process::id(), child);
                     self.shmem_provider.post_fork(false)?;
                     self.handlers
                         .pre_run_target(self, fuzzer, state, mgr, input);
 
-                    let res = waitpid(child, None)?;
+                    let (status, max_rss) = wait_with_max_rss(child.as_raw(), 0)?
+                        .expect("wait4 returned no status without WNOHANG");
+                    observe_mem_usage(self.observers_mut(), max_rss);
+                    let res = WaitStatus::from_raw(child, status)?;
+
+                    match res {
+                        WaitStatus::Signaled(_, signal, _) => observe_exit_status(
+                            self.observers_mut(),
+                            Some(signal as i32),
+                            None,
+                            false,
+                        ),
+                        WaitStatus::Exited(_, code) => {
+                            observe_exit_status(self.observers_mut(), None, Some(code), false);
+                        }
+                        _ => (),
+                    }
 
                     match res {
+                        WaitStatus::Signaled(_, nix::sys::signal::Signal::SIGALRM, _)
+                        | WaitStatus::Exited(_, FORK_CHILD_TIMEOUT_EXIT_CODE) => {
+                            Ok(ExitKind::Timeout)
+                        }
+                        // a `SIGKILL` under a memory limit, most likely by the kernel running out of memory
+                        WaitStatus::Signaled(_, nix::sys::signal::Signal::SIGKILL, _)
+                            if self.memlimit != 0 =>
+                        {
+                            Ok(ExitKind::Oom)
+                        }
+                        WaitStatus::Exited(_, FORK_CHILD_OOM_EXIT_CODE) => Ok(ExitKind::Oom),
                         WaitStatus::Signaled(_, _, _) => Ok(ExitKind::Crash),
+                        WaitStatus::Exited(_, code) if code != 0 => Ok(ExitKind::Crash),
                         _ => Ok(ExitKind::Ok),
                     }
                 }
@@ -1531,10 +1729,28 @@ where
             shmem_provider,
             observers,
             handlers,
+            timeout: None,
+            memlimit: 0,
             phantom: PhantomData,
         })
     }
 
+    /// Kills the child after `timeout`, reporting an [`ExitKind::Timeout`].
+    /// The harness must not use `SIGALRM` itself.
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Limits the address space of the child to `memlimit` MiB, `0` for none, the default.
+    /// Under a limit, a child killed by `SIGKILL` is reported as [`ExitKind::Oom`].
+    #[must_use]
+    pub fn with_memlimit(mut self, memlimit: u64) -> Self {
+        self.memlimit = memlimit;
+        self
+    }
+
     /// Retrieve the harness function.
     #[inline]
     pub fn harness(&self) -> &H {
@@ -1611,6 +1827,8 @@ mod tests {
             shmem_provider: provider,
             observers: tuple_list!(),
             handlers: InChildProcessHandlers::nop(),
+            timeout: None,
+            memlimit: 0,
             phantom: PhantomData,
         };
         let input = NopInput {};
@@ -1618,4 +1836,121 @@ mod tests {
             .run_target(&mut (), &mut (), &mut (), &input)
             .is_ok());
     }
+
+    #[test]
+    #[cfg(all(feature = "std", feature = "fork", unix))]
+    fn test_inprocessfork_exit_kinds() {
+        use core::time::Duration;
+
+        use crate::executors::inprocess::InChildProcessHandlers;
+
+        let mut harness = |_buf: &NopInput| -> ExitKind {
+            std::thread::sleep(Duration::from_secs(10));
+            ExitKind::Ok
+        };
+        let mut in_process_fork_executor = InProcessForkExecutor::<_, NopInput, (), (), _> {
+            harness_fn: &mut harness,
+            shmem_provider: StdShMemProvider::new().unwrap(),
+            observers: tuple_list!(),
+            handlers: InChildProcessHandlers::nop(),
+            timeout: None,
+            memlimit: 0,
+            phantom: PhantomData,
+        }
+        .with_timeout(Duration::from_millis(10));
+        let input = NopInput {};
+        assert_eq!(
+            in_process_fork_executor
+                .run_target(&mut (), &mut (), &mut (), &input)
+                .unwrap(),
+            ExitKind::Timeout
+        );
+
+        let mut harness = |_buf: &NopInput| -> ExitKind { std::process::exit(1) };
+        let mut in_process_fork_executor = InProcessForkExecutor::<_, NopInput, (), (), _> {
+            harness_fn: &mut harness,
+            shmem_provider: StdShMemProvider::new().unwrap(),
+            observers: tuple_list!(),
+            handlers: InChildProcessHandlers::nop(),
+            timeout: None,
+            memlimit: 0,
+            phantom: PhantomData,
+        };
+        assert_eq!(
+            in_process_fork_executor
+                .run_target(&mut (), &mut (), &mut (), &input)
+                .unwrap(),
+            ExitKind::Crash
+        );
+
+        // A `SIGKILL` is only an out of memory under a memory limit
+        let mut harness = |_buf: &NopInput| -> ExitKind {
+            unsafe {
+                libc::raise(libc::SIGKILL);
+            }
+            ExitKind::Ok
+        };
+        let mut in_process_fork_executor = InProcessForkExecutor::<_, NopInput, (), (), _> {
+            harness_fn: &mut harness,
+            shmem_provider: StdShMemProvider::new().unwrap(),
+            observers: tuple_list!(),
+            handlers: InChildProcessHandlers::nop(),
+            timeout: None,
+            memlimit: 0,
+            phantom: PhantomData,
+        };
+        assert_eq!(
+            in_process_fork_executor
+                .run_target(&mut (), &mut (), &mut (), &input)
+                .unwrap(),
+            ExitKind::Crash
+        );
+        let mut in_process_fork_executor = in_process_fork_executor.with_memlimit(1024);
+        assert_eq!(
+            in_process_fork_executor
+                .run_target(&mut (), &mut (), &mut (), &input)
+                .unwrap(),
+            ExitKind::Oom
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_crash_hooks() {
+        use alloc::{format, rc::Rc, string::String, vec::Vec};
+        use core::{cell::RefCell, ffi::c_void, ptr};
+
+        use crate::executors::inprocess::InProcessExecutorHandlerData;
+
+        let log = Rc::new(RefCell::new(Vec::<String>::new()));
+        let hook = |name: &'static str| {
+            let log = log.clone();
+            Box::new(move |exit_kind: ExitKind| {
+                log.borrow_mut().push(format!("{} {:?}", name, exit_kind));
+            })
+        };
+        let mut handlers = InProcessHandlers::nop();
+        handlers.pre_crash_hooks.push(hook("pre"));
+        handlers.post_crash_hooks.push(hook("post"));
+
+        // The handler data of a run, as the crash and timeout handlers get it
+        let mut data = InProcessExecutorHandlerData {
+            state_ptr: ptr::null_mut(),
+            event_mgr_ptr: ptr::null_mut(),
+            fuzzer_ptr: ptr::null_mut(),
+            executor_ptr: ptr::null(),
+            current_input_ptr: ptr::null(),
+            crash_handler: ptr::null(),
+            timeout_handler: ptr::null(),
+            handlers_ptr: ptr::null(),
+        };
+        data.run_pre_crash_hooks(ExitKind::Crash);
+        assert!(log.borrow().is_empty());
+
+        data.handlers_ptr = ptr::addr_of!(handlers) as *const c_void;
+        data.run_pre_crash_hooks(ExitKind::Crash);
+        data.run_post_crash_hooks(ExitKind::Crash);
+        data.run_pre_crash_hooks(ExitKind::Timeout);
+        assert_eq!(*log.borrow(), ["pre Crash", "post Crash", "pre Timeout"]);
+    }
 }
@@ -0,0 +1,277 @@
+//! The [`IntelPTCommandExecutor`] runs uninstrumented native binaries, tracing them with Intel PT
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    ptr,
+    time::Duration,
+};
+use std::{
+    ffi::OsString,
+    io::{self, Write},
+    os::unix::{
+        prelude::{ExitStatusExt, OsStringExt},
+        process::CommandExt,
+    },
+    process::{Child, Command, Stdio},
+};
+
+use libc::{c_void, pid_t};
+use wait_timeout::ChildExt;
+
+use crate::{
+    bolts::{
+        os::intel_pt::{IntelPT, INTEL_PT_DEFAULT_AUX_PAGES},
+        AsSlice,
+    },
+    executors::{command::InputLocation, Executor, ExitKind, HasObservers},
+    inputs::{HasTargetBytes, Input},
+    observers::{IntelPTObserver, ObserversTuple},
+    Error,
+};
+
+/// An [`Executor`] spawning a native binary for each run, and tracing it with Intel PT.
+///
+/// The child stops right after its `exec`, so that the tracer can be attached before it runs
+/// any instruction. The trace gets decoded by the [`IntelPTObserver`] with the given name,
+/// which needs to be part of the observers.
+pub struct IntelPTCommandExecutor<I, OT, S> {
+    command: Command,
+    input_location: InputLocation,
+    observer_name: String,
+    filter: Option<String>,
+    aux_pages: usize,
+    timeout: Duration,
+    debug_child: bool,
+    observers: OT,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<I, OT, S> Debug for IntelPTCommandExecutor<I, OT, S>
+where
+    OT: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IntelPTCommandExecutor")
+            .field("command", &self.command)
+            .field("input_location", &self.input_location)
+            .field("observer_name", &self.observer_name)
+            .field("filter", &self.filter)
+            .field("aux_pages", &self.aux_pages)
+            .field("timeout", &self.timeout)
+            .field("observers", &self.observers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<I, OT, S> IntelPTCommandExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    /// Creates a new [`IntelPTCommandExecutor`] running `command`, with the input delivered at
+    /// `input_location`, and the trace decoded by the [`IntelPTObserver`] named `observer_name`
+    pub fn new(
+        command: Command,
+        input_location: InputLocation,
+        observer_name: &str,
+        observers: OT,
+    ) -> Result<Self, Error> {
+        if observers
+            .match_name::<IntelPTObserver>(observer_name)
+            .is_none()
+        {
+            return Err(Error::KeyNotFound(format!(
+                "No IntelPTObserver named {} in the observers",
+                observer_name
+            )));
+        }
+        Ok(Self {
+            command,
+            input_location,
+            observer_name: observer_name.to_string(),
+            filter: None,
+            aux_pages: INTEL_PT_DEFAULT_AUX_PAGES,
+            timeout: Duration::from_secs(5),
+            debug_child: false,
+            observers,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Restricts the tracing, see [`IntelPT::set_filter`]
+    #[must_use]
+    pub fn with_filter(mut self, filter: &str) -> Self {
+        self.filter = Some(filter.to_string());
+        self
+    }
+
+    /// Sets the size of the trace buffer, in pages (a power of two)
+    #[must_use]
+    pub fn with_aux_pages(mut self, aux_pages: usize) -> Self {
+        self.aux_pages = aux_pages;
+        self
+    }
+
+    /// Sets the timeout of each run
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Keeps the output of the child visible
+    #[must_use]
+    pub fn debug_child(mut self, debug_child: bool) -> Self {
+        self.debug_child = debug_child;
+        self
+    }
+
+    /// Builds the command of a run, delivering the input
+    fn prepare_command(&mut self, input: &I) -> Result<Command, Error> {
+        let mut cmd = Command::new(self.command.get_program());
+        for (i, arg) in self.command.get_args().enumerate() {
+            match self.input_location {
+                InputLocation::Arg { argnum } if argnum == i => {
+                    cmd.arg(OsString::from_vec(input.target_bytes().as_slice().to_vec()));
+                }
+                _ => {
+                    cmd.arg(arg);
+                }
+            }
+        }
+        cmd.envs(
+            self.command
+                .get_envs()
+                .filter_map(|(key, value)| value.map(|value| (key, value))),
+        );
+        if let Some(cwd) = self.command.get_current_dir() {
+            cmd.current_dir(cwd);
+        }
+        if !self.debug_child {
+            cmd.stdout(Stdio::null());
+            cmd.stderr(Stdio::null());
+        }
+        match &mut self.input_location {
+            InputLocation::StdIn => {
+                cmd.stdin(Stdio::piped());
+            }
+            InputLocation::File { out_file } => {
+                out_file.write_buf(input.target_bytes().as_slice())?;
+                cmd.stdin(Stdio::null());
+            }
+            InputLocation::Arg { .. } => {
+                cmd.stdin(Stdio::null());
+            }
+        }
+        // Stop at the exec, to attach the tracer before the target runs
+        unsafe {
+            cmd.pre_exec(|| {
+                if libc::ptrace(
+                    libc::PTRACE_TRACEME,
+                    0,
+                    ptr::null_mut::<c_void>(),
+                    ptr::null_mut::<c_void>(),
+                ) == -1
+                {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        Ok(cmd)
+    }
+
+    /// Attaches a tracer to the child stopped at its exec, and lets it run
+    #[allow(clippy::cast_possible_wrap)]
+    fn trace_child(&mut self, child: &Child) -> Result<(), Error> {
+        let pid = child.id() as pid_t;
+        let mut status = 0;
+        if unsafe { libc::waitpid(pid, &mut status, 0) } != pid || !libc::WIFSTOPPED(status) {
+            return Err(Error::Unknown(format!(
+                "The child did not stop at its exec (status {:#x})",
+                status
+            )));
+        }
+        let mut pt = IntelPT::new(pid, self.aux_pages)?;
+        if let Some(filter) = &self.filter {
+            pt.set_filter(filter)?;
+        }
+        pt.enable()?;
+        self.observers
+            .match_name_mut::<IntelPTObserver>(&self.observer_name)
+            .ok_or_else(|| {
+                Error::KeyNotFound(format!("No IntelPTObserver named {}", self.observer_name))
+            })?
+            .attach(pt);
+        if unsafe {
+            libc::ptrace(
+                libc::PTRACE_DETACH,
+                pid,
+                ptr::null_mut::<c_void>(),
+                ptr::null_mut::<c_void>(),
+            )
+        } == -1
+        {
+            return Err(Error::Unknown(format!(
+                "Could not detach from the child: {}",
+                io::Error::last_os_error()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<EM, I, OT, S, Z> Executor<EM, I, S, Z> for IntelPTCommandExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    fn run_target(
+        &mut self,
+        _fuzzer: &mut Z,
+        _state: &mut S,
+        _mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        let mut child = self.prepare_command(input)?.spawn()?;
+        if let Err(err) = self.trace_child(&child) {
+            drop(child.kill());
+            drop(child.wait());
+            return Err(err);
+        }
+        if let Some(mut stdin) = child.stdin.take() {
+            // The child may exit without reading its input
+            drop(stdin.write_all(input.target_bytes().as_slice()));
+        }
+
+        match child
+            .wait_timeout(self.timeout)
+            .expect("waiting on child failed")
+            .map(|status| status.signal())
+        {
+            // for reference: https://www.man7.org/linux/man-pages/man7/signal.7.html
+            Some(Some(9)) => Ok(ExitKind::Oom),
+            Some(Some(_)) => Ok(ExitKind::Crash),
+            Some(None) => Ok(ExitKind::Ok),
+            None => {
+                drop(child.kill());
+                drop(child.wait());
+                Ok(ExitKind::Timeout)
+            }
+        }
+    }
+}
+
+impl<I, OT, S> HasObservers<I, OT, S> for IntelPTCommandExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    fn observers(&self) -> &OT {
+        &self.observers
+    }
+
+    fn observers_mut(&mut self) -> &mut OT {
+        &mut self.observers
+    }
+}
@@ -10,16 +10,21 @@ pub use inprocess::InProcessForkExecutor;
 #[cfg(any(unix, feature = "std"))]
 pub mod timeout;
 #[cfg(any(unix, feature = "std"))]
-pub use timeout::TimeoutExecutor;
+pub use timeout::{AdaptiveTimeoutExecutor, TimeoutExecutor};
 
 #[cfg(all(feature = "std", feature = "fork", unix))]
 pub mod forkserver;
 #[cfg(all(feature = "std", feature = "fork", unix))]
-pub use forkserver::{Forkserver, ForkserverExecutor, TimeoutForkserverExecutor};
+pub use forkserver::{
+    Forkserver, ForkserverExecutor, ForkserverOptions, TimeoutForkserverExecutor,
+};
 
 pub mod combined;
 pub use combined::CombinedExecutor;
 
+pub mod differential;
+pub use differential::{DiffExecutor, DiffObserversTuple};
+
 pub mod shadow;
 pub use shadow::ShadowExecutor;
 
@@ -31,6 +36,26 @@ pub mod command;
 #[cfg(all(feature = "std", unix))]
 pub use command::CommandExecutor;
 
+#[cfg(all(feature = "std", unix))]
+pub mod remote;
+#[cfg(all(feature = "std", unix))]
+pub use remote::RemoteCommandExecutor;
+
+#[cfg(feature = "std")]
+pub mod network;
+#[cfg(feature = "std")]
+pub use network::NetworkExecutor;
+
+#[cfg(all(feature = "std", target_os = "linux", target_arch = "x86_64"))]
+pub mod intel_pt;
+#[cfg(all(feature = "std", target_os = "linux", target_arch = "x86_64"))]
+pub use intel_pt::IntelPTCommandExecutor;
+
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub mod ptrace;
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub use ptrace::PtraceCommandExecutor;
+
 use crate::{
     bolts::AsSlice,
     inputs::{HasTargetBytes, Input},
@@ -0,0 +1,285 @@
+//! The [`NetworkExecutor`] sends the inputs to a target service over TCP or UDP, for protocol fuzzing.
+//! Use [`Executor::with_observers`] to add observers, e.g. of a process-liveness check.
+
+use alloc::vec::Vec;
+use core::time::Duration;
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::{SocketAddr, TcpStream, UdpSocket},
+};
+
+use crate::{
+    bolts::AsSlice,
+    executors::{Executor, ExitKind},
+    inputs::{HasTargetBytes, Input},
+    Error,
+};
+
+/// The default timeout of the connection, of the sends and of the receives
+pub const DEFAULT_NETWORK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The maximum size of a received datagram or response
+const MAX_RESPONSE_SIZE: usize = 64 * 1024;
+
+/// The transport protocol used by the [`NetworkExecutor`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkProtocol {
+    /// A new TCP connection for each input
+    Tcp,
+    /// A new UDP socket for each input, sending a datagram per message
+    Udp,
+}
+
+/// A connection to the target, for one run
+#[derive(Debug)]
+enum Connection {
+    Tcp(TcpStream),
+    Udp(UdpSocket),
+}
+
+impl Connection {
+    fn send(&mut self, bytes: &[u8]) -> io::Result<()> {
+        match self {
+            Self::Tcp(stream) => stream.write_all(bytes),
+            Self::Udp(socket) => socket.send(bytes).map(drop),
+        }
+    }
+
+    /// Receives the response, until the target closes the connection or stops answering
+    fn recv(&mut self, response: &mut Vec<u8>) -> io::Result<()> {
+        let mut buf = vec![0; MAX_RESPONSE_SIZE];
+        match self {
+            Self::Tcp(stream) => loop {
+                match stream.read(&mut buf) {
+                    Ok(0) => return Ok(()),
+                    Ok(len) => response.extend_from_slice(&buf[..len]),
+                    // Timing out after receiving some data is the end of the response
+                    Err(err) if is_timeout(&err) && !response.is_empty() => return Ok(()),
+                    Err(err) => return Err(err),
+                }
+                if response.len() >= MAX_RESPONSE_SIZE {
+                    return Ok(());
+                }
+            },
+            Self::Udp(socket) => {
+                let len = socket.recv(&mut buf)?;
+                response.extend_from_slice(&buf[..len]);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// If the error is the timeout of a socket
+fn is_timeout(err: &io::Error) -> bool {
+    matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
+
+/// If the error means that the target is gone, e.g. crashed
+fn is_target_gone(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        ErrorKind::ConnectionRefused
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::BrokenPipe
+    )
+}
+
+/// An [`Executor`] sending each input to a network service at `addr`, after an optional prelude of
+/// messages, e.g. a handshake, and optionally reading the responses.
+/// A target resetting the connection, or no longer accepting connections after the input, is
+/// reported as [`ExitKind::Crash`], a target not answering in time as [`ExitKind::Timeout`].
+/// The service has to be started, and restarted, separately.
+#[derive(Debug)]
+pub struct NetworkExecutor {
+    addr: SocketAddr,
+    protocol: NetworkProtocol,
+    prelude: Vec<Vec<u8>>,
+    read_response: bool,
+    check_liveness: bool,
+    timeout: Duration,
+    response: Vec<u8>,
+}
+
+impl NetworkExecutor {
+    /// Creates a new [`NetworkExecutor`] sending the inputs to `addr` over `protocol`
+    #[must_use]
+    pub fn new(addr: SocketAddr, protocol: NetworkProtocol) -> Self {
+        Self {
+            addr,
+            protocol,
+            prelude: vec![],
+            read_response: false,
+            check_liveness: false,
+            timeout: DEFAULT_NETWORK_TIMEOUT,
+            response: vec![],
+        }
+    }
+
+    /// Adds a message to send before each input, e.g. of a handshake.
+    /// When reading responses, the response to each message of the prelude is read, and dropped.
+    #[must_use]
+    pub fn with_prelude_message(mut self, message: Vec<u8>) -> Self {
+        self.prelude.push(message);
+        self
+    }
+
+    /// Reads the response of the target to the prelude messages and to the input, the response to
+    /// the input then being available with [`NetworkExecutor::response`]
+    #[must_use]
+    pub fn with_read_response(mut self, read_response: bool) -> Self {
+        self.read_response = read_response;
+        self
+    }
+
+    /// Checks that the TCP target still accepts connections after each input, reporting a
+    /// [`ExitKind::Crash`] otherwise, for the input killing the service to be the one reported
+    #[must_use]
+    pub fn with_liveness_check(mut self, check_liveness: bool) -> Self {
+        self.check_liveness = check_liveness;
+        self
+    }
+
+    /// Sets the timeout of the connection, of each send and of each receive
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The address of the target
+    #[must_use]
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The response of the target to the last input, if reading responses
+    #[must_use]
+    pub fn response(&self) -> &[u8] {
+        &self.response
+    }
+
+    /// Connects to the target
+    fn connect(&self) -> io::Result<Connection> {
+        match self.protocol {
+            NetworkProtocol::Tcp => {
+                let stream = TcpStream::connect_timeout(&self.addr, self.timeout)?;
+                stream.set_read_timeout(Some(self.timeout))?;
+                stream.set_write_timeout(Some(self.timeout))?;
+                stream.set_nodelay(true)?;
+                Ok(Connection::Tcp(stream))
+            }
+            NetworkProtocol::Udp => {
+                let local: SocketAddr = if self.addr.is_ipv4() {
+                    ([0, 0, 0, 0], 0).into()
+                } else {
+                    ([0_u16; 8], 0).into()
+                };
+                let socket = UdpSocket::bind(local)?;
+                socket.connect(self.addr)?;
+                socket.set_read_timeout(Some(self.timeout))?;
+                socket.set_write_timeout(Some(self.timeout))?;
+                Ok(Connection::Udp(socket))
+            }
+        }
+    }
+
+    /// Sends the prelude and the input, and reads the responses
+    fn exchange(&mut self, connection: &mut Connection, input: &[u8]) -> io::Result<()> {
+        for message in &self.prelude {
+            connection.send(message)?;
+            if self.read_response {
+                connection.recv(&mut vec![])?;
+            }
+        }
+        connection.send(input)?;
+        if self.read_response {
+            connection.recv(&mut self.response)?;
+        }
+        Ok(())
+    }
+}
+
+impl<EM, I, S, Z> Executor<EM, I, S, Z> for NetworkExecutor
+where
+    I: Input + HasTargetBytes,
+{
+    fn run_target(
+        &mut self,
+        _fuzzer: &mut Z,
+        _state: &mut S,
+        _mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        self.response.clear();
+
+        let mut connection = match self.connect() {
+            Ok(connection) => connection,
+            Err(err) => {
+                return Err(Error::IllegalState(format!(
+                    "Could not connect to the target at {}: {}",
+                    self.addr, err
+                )))
+            }
+        };
+
+        let exit_kind = match self.exchange(&mut connection, input.target_bytes().as_slice()) {
+            Ok(()) => ExitKind::Ok,
+            Err(err) if is_target_gone(&err) => ExitKind::Crash,
+            Err(err) if is_timeout(&err) => ExitKind::Timeout,
+            Err(err) => return Err(err.into()),
+        };
+        drop(connection);
+
+        if exit_kind == ExitKind::Ok
+            && self.check_liveness
+            && self.protocol == NetworkProtocol::Tcp
+            && matches!(
+                TcpStream::connect_timeout(&self.addr, self.timeout),
+                Err(err) if is_target_gone(&err) || is_timeout(&err)
+            )
+        {
+            return Ok(ExitKind::Crash);
+        }
+        Ok(exit_kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Read, net::TcpListener, thread};
+
+    use crate::{
+        executors::{
+            network::{NetworkExecutor, NetworkProtocol},
+            Executor, ExitKind,
+        },
+        inputs::BytesInput,
+    };
+
+    #[test]
+    fn test_network_executor_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = vec![];
+            stream.read_to_end(&mut received).unwrap();
+            received
+        });
+
+        let mut executor = NetworkExecutor::new(addr, NetworkProtocol::Tcp)
+            .with_prelude_message(b"HELLO ".to_vec());
+        let exit_kind = executor
+            .run_target(
+                &mut (),
+                &mut (),
+                &mut (),
+                &BytesInput::new(b"fuzz".to_vec()),
+            )
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Ok);
+        assert_eq!(server.join().unwrap(), b"HELLO fuzz");
+    }
+}
@@ -0,0 +1,386 @@
+//! The [`PtraceCommandExecutor`] runs native binaries under `ptrace`, to capture their state when
+//! they crash, for the triage of the objectives
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    time::Duration,
+};
+use std::{
+    ffi::OsString,
+    fs,
+    io::{self, Write},
+    os::unix::{prelude::OsStringExt, process::CommandExt},
+    process::{Child, Command, Stdio},
+    thread,
+    time::Instant,
+};
+
+use libc::pid_t;
+#[cfg(target_arch = "x86_64")]
+use nix::sys::ptrace::AddressType;
+use nix::{
+    sys::{
+        ptrace,
+        signal::Signal,
+        wait::{waitpid, WaitPidFlag, WaitStatus},
+    },
+    unistd::Pid,
+};
+
+use crate::{
+    bolts::AsSlice,
+    executors::{command::InputLocation, Executor, ExitKind, HasObservers},
+    inputs::{HasTargetBytes, Input},
+    observers::{
+        exit_status::observe_exit_status, parse_proc_maps, CrashContextObserver, ObserversTuple,
+        CRASH_CONTEXT_OBSERVER_NAME,
+    },
+    Error,
+};
+
+/// The maximum number of frames of the backtraces
+#[cfg(target_arch = "x86_64")]
+const MAX_BACKTRACE_FRAMES: usize = 64;
+
+/// How long to sleep between two polls of the child
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// If the target stopping on `signal` crashes
+fn is_crash_signal(signal: Signal) -> bool {
+    matches!(
+        signal,
+        Signal::SIGSEGV | Signal::SIGBUS | Signal::SIGILL | Signal::SIGFPE | Signal::SIGABRT
+    )
+}
+
+/// An [`Executor`] spawning a native binary for each run, under `ptrace`.
+///
+/// When the target stops on a crashing signal, the executor captures the signal, the faulting
+/// address, the registers and a backtrace into the [`CrashContextObserver`] of its observers, if
+/// any, before killing it. The registers and the backtrace are only captured on `x86_64`, and the
+/// backtrace follows the frame pointers, so it needs a target keeping them, e.g. built with
+/// `-fno-omit-frame-pointer`. Only the main thread of the target gets traced: a crash in another
+/// thread is reported, without its context.
+pub struct PtraceCommandExecutor<I, OT, S> {
+    command: Command,
+    input_location: InputLocation,
+    timeout: Duration,
+    debug_child: bool,
+    observers: OT,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<I, OT, S> Debug for PtraceCommandExecutor<I, OT, S>
+where
+    OT: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PtraceCommandExecutor")
+            .field("command", &self.command)
+            .field("input_location", &self.input_location)
+            .field("timeout", &self.timeout)
+            .field("observers", &self.observers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<I, OT, S> PtraceCommandExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    /// Creates a new [`PtraceCommandExecutor`] running `command`, with the input delivered at
+    /// `input_location`
+    pub fn new(command: Command, input_location: InputLocation, observers: OT) -> Self {
+        Self {
+            command,
+            input_location,
+            timeout: Duration::from_secs(5),
+            debug_child: false,
+            observers,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets the timeout of each run
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Keeps the output of the child visible
+    #[must_use]
+    pub fn debug_child(mut self, debug_child: bool) -> Self {
+        self.debug_child = debug_child;
+        self
+    }
+
+    /// Builds the command of a run, delivering the input
+    fn prepare_command(&mut self, input: &I) -> Result<Command, Error> {
+        let mut cmd = Command::new(self.command.get_program());
+        for (i, arg) in self.command.get_args().enumerate() {
+            match self.input_location {
+                InputLocation::Arg { argnum } if argnum == i => {
+                    cmd.arg(OsString::from_vec(input.target_bytes().as_slice().to_vec()));
+                }
+                _ => {
+                    cmd.arg(arg);
+                }
+            }
+        }
+        cmd.envs(
+            self.command
+                .get_envs()
+                .filter_map(|(key, value)| value.map(|value| (key, value))),
+        );
+        if let Some(cwd) = self.command.get_current_dir() {
+            cmd.current_dir(cwd);
+        }
+        if !self.debug_child {
+            cmd.stdout(Stdio::null());
+            cmd.stderr(Stdio::null());
+        }
+        match &mut self.input_location {
+            InputLocation::StdIn => {
+                cmd.stdin(Stdio::piped());
+            }
+            InputLocation::File { out_file } => {
+                out_file.write_buf(input.target_bytes().as_slice())?;
+                cmd.stdin(Stdio::null());
+            }
+            InputLocation::Arg { .. } => {
+                cmd.stdin(Stdio::null());
+            }
+        }
+        // Stop at the exec, for the tracer to take over before the target runs
+        unsafe {
+            cmd.pre_exec(|| ptrace::traceme().map_err(io::Error::from));
+        }
+        Ok(cmd)
+    }
+
+    /// Captures the state of the child, stopped on the crashing `signal`, into the
+    /// [`CrashContextObserver`], if any
+    fn capture_crash_context(&mut self, pid: Pid, signal: Signal) {
+        let observer = match self
+            .observers
+            .match_name_mut::<CrashContextObserver>(CRASH_CONTEXT_OBSERVER_NAME)
+        {
+            Some(observer) => observer,
+            None => return,
+        };
+
+        // Only the faults raised by the kernel have an address, not the signals sent by a process
+        let fault_addr = ptrace::getsiginfo(pid)
+            .ok()
+            .filter(|info| signal != Signal::SIGABRT && info.si_code > 0)
+            .map(|info| unsafe { info.si_addr() } as usize as u64);
+        observer.observe_signal(signal as i32, fault_addr);
+        if let Ok(maps) = fs::read_to_string(format!("/proc/{}/maps", pid)) {
+            observer.observe_modules(parse_proc_maps(&maps));
+        }
+
+        #[cfg(target_arch = "x86_64")]
+        if let Ok(regs) = ptrace::getregs(pid) {
+            observer.observe_backtrace(frame_pointer_backtrace(pid, regs.rip, regs.rbp));
+            observer.observe_registers(
+                regs.rip,
+                [
+                    ("rax", regs.rax),
+                    ("rbx", regs.rbx),
+                    ("rcx", regs.rcx),
+                    ("rdx", regs.rdx),
+                    ("rsi", regs.rsi),
+                    ("rdi", regs.rdi),
+                    ("rbp", regs.rbp),
+                    ("rsp", regs.rsp),
+                    ("r8", regs.r8),
+                    ("r9", regs.r9),
+                    ("r10", regs.r10),
+                    ("r11", regs.r11),
+                    ("r12", regs.r12),
+                    ("r13", regs.r13),
+                    ("r14", regs.r14),
+                    ("r15", regs.r15),
+                    ("rip", regs.rip),
+                    ("eflags", regs.eflags),
+                    ("fs_base", regs.fs_base),
+                    ("gs_base", regs.gs_base),
+                ]
+                .iter()
+                .map(|(name, value)| ((*name).to_string(), *value))
+                .collect(),
+            );
+        }
+    }
+
+    /// Kills the child, and waits for it to be gone
+    fn kill_child(child: &mut Child, pid: Pid) {
+        let _ = child.kill();
+        let _ = waitpid(pid, None);
+    }
+}
+
+/// Walks the frame pointers of the stopped child `pid`, from the frame `fp` of the function at `pc`
+#[cfg(target_arch = "x86_64")]
+#[allow(clippy::cast_sign_loss)]
+fn frame_pointer_backtrace(pid: Pid, pc: u64, mut fp: u64) -> Vec<u64> {
+    let mut backtrace = vec![pc];
+    while backtrace.len() < MAX_BACKTRACE_FRAMES && fp != 0 {
+        let ret = match ptrace::read(pid, fp.wrapping_add(8) as usize as AddressType) {
+            Ok(ret) if ret != 0 => ret as u64,
+            _ => break,
+        };
+        backtrace.push(ret);
+        let next = match ptrace::read(pid, fp as usize as AddressType) {
+            Ok(next) => next as u64,
+            Err(_) => break,
+        };
+        // The callers are higher up the stack
+        if next <= fp {
+            break;
+        }
+        fp = next;
+    }
+    backtrace
+}
+
+impl<EM, I, OT, S, Z> Executor<EM, I, S, Z> for PtraceCommandExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    #[allow(clippy::cast_possible_wrap)]
+    fn run_target(
+        &mut self,
+        _fuzzer: &mut Z,
+        _state: &mut S,
+        _mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        let mut child = self.prepare_command(input)?.spawn()?;
+        let pid = Pid::from_raw(child.id() as pid_t);
+        match waitpid(pid, None) {
+            Ok(WaitStatus::Stopped(_, Signal::SIGTRAP)) => (),
+            status => {
+                Self::kill_child(&mut child, pid);
+                return Err(Error::Unknown(format!(
+                    "The child did not stop at its exec ({:?})",
+                    status
+                )));
+            }
+        }
+        if let Some(mut stdin) = child.stdin.take() {
+            // The child may exit, or stop, without reading its input
+            let bytes = input.target_bytes().as_slice().to_vec();
+            thread::spawn(move || {
+                let _ = stdin.write_all(&bytes);
+            });
+        }
+
+        if let Err(err) = ptrace::cont(pid, None) {
+            Self::kill_child(&mut child, pid);
+            return Err(Error::Unknown(format!(
+                "Could not resume the child: {}",
+                err
+            )));
+        }
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let status = match waitpid(pid, Some(WaitPidFlag::WNOHANG)) {
+                Ok(status) => status,
+                Err(err) => {
+                    Self::kill_child(&mut child, pid);
+                    return Err(Error::Unknown(format!(
+                        "Could not wait for the child: {}",
+                        err
+                    )));
+                }
+            };
+            let signal = match status {
+                WaitStatus::StillAlive => {
+                    if Instant::now() >= deadline {
+                        Self::kill_child(&mut child, pid);
+                        return Ok(ExitKind::Timeout);
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                    continue;
+                }
+                WaitStatus::Exited(_, exit_code) => {
+                    observe_exit_status(&mut self.observers, None, Some(exit_code), false);
+                    return Ok(ExitKind::Ok);
+                }
+                WaitStatus::Signaled(_, signal, _) => {
+                    observe_exit_status(&mut self.observers, Some(signal as i32), None, false);
+                    // for reference: https://www.man7.org/linux/man-pages/man7/signal.7.html
+                    return Ok(if signal == Signal::SIGKILL {
+                        ExitKind::Oom
+                    } else {
+                        ExitKind::Crash
+                    });
+                }
+                WaitStatus::Stopped(_, signal) if is_crash_signal(signal) => {
+                    self.capture_crash_context(pid, signal);
+                    Self::kill_child(&mut child, pid);
+                    observe_exit_status(&mut self.observers, Some(signal as i32), None, false);
+                    return Ok(ExitKind::Crash);
+                }
+                // Deliver the other signals to the target
+                WaitStatus::Stopped(_, signal) => Some(signal),
+                _ => None,
+            };
+            // If the child is gone, the next wait tells how
+            let _ = ptrace::cont(pid, signal);
+        }
+    }
+}
+
+impl<I, OT, S> HasObservers<I, OT, S> for PtraceCommandExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    fn observers(&self) -> &OT {
+        &self.observers
+    }
+
+    fn observers_mut(&mut self) -> &mut OT {
+        &mut self.observers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use crate::{
+        bolts::tuples::tuple_list,
+        executors::{
+            command::InputLocation, Executor, ExitKind, HasObservers, PtraceCommandExecutor,
+        },
+        inputs::BytesInput,
+        observers::{CrashContextObserver, Exploitability},
+    };
+
+    #[test]
+    fn test_ptrace_crash_context() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "kill -FPE $$"]);
+        let mut executor = PtraceCommandExecutor::<BytesInput, _, ()>::new(
+            command,
+            InputLocation::StdIn,
+            tuple_list!(CrashContextObserver::new()),
+        );
+        let exit_kind = executor
+            .run_target(&mut (), &mut (), &mut (), &BytesInput::new(vec![]))
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Crash);
+        let observer = &executor.observers().0;
+        assert_eq!(observer.signal(), Some(libc::SIGFPE));
+        // Sent by a process, so without a faulting address
+        assert_eq!(observer.fault_addr(), None);
+        assert_eq!(observer.classify(), Some(Exploitability::NotExploitable));
+    }
+}
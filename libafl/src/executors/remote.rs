@@ -0,0 +1,461 @@
+//! The [`RemoteCommandExecutor`] runs a harness on a remote host, or on a device, over `ssh` or
+//! `adb`, for each run
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    time::Duration,
+};
+use std::{
+    io::{Read, Write},
+    path::PathBuf,
+    process::{Command, Stdio},
+    time::Instant,
+};
+
+use wait_timeout::ChildExt;
+
+use crate::{
+    bolts::AsSlice,
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::{HasTargetBytes, Input},
+    observers::{exit_status::observe_exit_status, MapObserver, ObserversTuple},
+    Error,
+};
+
+/// The default path of the input on the remote side
+pub const REMOTE_DEFAULT_INPUT: &str = "/tmp/.libafl_cur_input";
+
+/// The placeholder for the input file in the arguments, as in AFL
+const AFL_INPUT_PLACEHOLDER: &str = "@@";
+
+/// The marker preceding the exit status of the harness in the output of the remote shell
+const STATUS_MARKER: &str = "__LIBAFL_STATUS__";
+
+/// The time given to the connection, on top of the timeout of the harness, before retrying
+const CONNECTION_SLACK: Duration = Duration::from_secs(10);
+
+/// The exit status of the remote `timeout` killing the harness with `SIGKILL`
+const TIMEOUT_KILLED_STATUS: i32 = 128 + 9;
+
+/// How to reach the remote side
+#[derive(Debug, Clone)]
+pub enum RemoteTransport {
+    /// Over `ssh`, sharing one connection between the runs, as a `ControlMaster` at `control_path`
+    Ssh {
+        /// The destination, as given to `ssh`, e.g. `user@host`
+        destination: String,
+        /// The port, if not the default one
+        port: Option<u16>,
+        /// The socket of the shared connection
+        control_path: PathBuf,
+    },
+    /// Over `adb`, to an Android device
+    Adb {
+        /// The serial of the device, if more than one is connected, e.g. `192.168.1.2:5555`
+        serial: Option<String>,
+    },
+}
+
+impl RemoteTransport {
+    /// Reaches `destination` over `ssh`, sharing one connection between the runs
+    #[must_use]
+    pub fn ssh(destination: &str) -> Self {
+        Self::Ssh {
+            destination: destination.to_string(),
+            port: None,
+            control_path: std::env::temp_dir().join(format!(
+                "libafl_ssh_{}_{}",
+                std::process::id(),
+                destination.replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+            )),
+        }
+    }
+
+    /// Reaches the device with the given `serial`, or the only connected one, over `adb`
+    #[must_use]
+    pub fn adb(serial: Option<&str>) -> Self {
+        Self::Adb {
+            serial: serial.map(ToString::to_string),
+        }
+    }
+
+    /// The command running the shell `script` on the remote side, `binary` if its output is
+    /// binary
+    fn command(&self, script: &str, binary: bool) -> Command {
+        match self {
+            Self::Ssh {
+                destination,
+                port,
+                control_path,
+            } => {
+                let mut cmd = Command::new("ssh");
+                cmd.args(["-o", "BatchMode=yes", "-o", "ControlMaster=auto", "-o"])
+                    .arg(format!("ControlPath={}", control_path.display()))
+                    .args(["-o", "ControlPersist=600"]);
+                if let Some(port) = port {
+                    cmd.arg("-p").arg(port.to_string());
+                }
+                cmd.arg(destination).arg(script);
+                cmd
+            }
+            Self::Adb { serial } => {
+                let mut cmd = Command::new("adb");
+                if let Some(serial) = serial {
+                    cmd.arg("-s").arg(serial);
+                }
+                // `shell` may mangle the line endings, `exec-out` passes the output as is
+                cmd.arg(if binary { "exec-out" } else { "shell" })
+                    .arg(script);
+                cmd
+            }
+        }
+    }
+
+    /// Drops the connection, for the next command to connect again
+    fn reconnect(&self) {
+        // The next command tells if the connection is back
+        let _ = match self {
+            Self::Ssh {
+                destination,
+                control_path,
+                ..
+            } => Command::new("ssh")
+                .arg("-o")
+                .arg(format!("ControlPath={}", control_path.display()))
+                .args(["-O", "exit"])
+                .arg(destination)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status(),
+            Self::Adb {
+                serial: Some(serial),
+            } if serial.contains(':') => Command::new("adb")
+                .arg("connect")
+                .arg(serial)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status(),
+            Self::Adb { .. } => Command::new("adb")
+                .arg("reconnect")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status(),
+        };
+    }
+}
+
+/// Quotes `arg` for the remote shell
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// Parses the exit status of the harness from the `output` of the remote shell, if it ran
+fn parse_status(output: &str) -> Option<i32> {
+    output
+        .lines()
+        .rev()
+        .find_map(|line| line.strip_prefix(STATUS_MARKER))
+        .and_then(|status| status.trim().parse().ok())
+}
+
+/// Copies a coverage file into the `u8` map of the [`MapObserver`] `M` named `name`
+fn fill_map<I, M, OT, S>(observers: &mut OT, name: &str, coverage: &[u8]) -> Result<(), Error>
+where
+    M: MapObserver<Entry = u8>,
+    OT: ObserversTuple<I, S>,
+{
+    let observer = observers
+        .match_name_mut::<M>(name)
+        .ok_or_else(|| Error::KeyNotFound(format!("No MapObserver named {}", name)))?;
+    observer.reset_map()?;
+    for (i, byte) in coverage.iter().take(observer.usable_count()).enumerate() {
+        *observer.get_mut(i) = *byte;
+    }
+    Ok(())
+}
+
+/// Fills a map observer with the coverage file of a run
+type CoverageFiller<OT> = fn(&mut OT, &str, &[u8]) -> Result<(), Error>;
+
+/// An [`Executor`] running a harness on a remote host, or on a device, over `ssh` or `adb`.
+///
+/// Each run writes the input to a file on the remote side and runs the harness in one round trip,
+/// with the input in its arguments, in place of `@@`, or on its stdin. The harness runs under the
+/// `timeout` of the remote side, which needs to have one, as with `coreutils` or `toybox`.
+/// The exit status of the harness, and its terminating signal, if any, go into the
+/// [`crate::observers::ExitStatusObserver`] of the observers, and its coverage file, if any, is
+/// copied back into a [`MapObserver`].
+/// The `ssh` transport shares one connection between the runs, and the runs failing to connect are
+/// retried after reconnecting.
+pub struct RemoteCommandExecutor<I, OT, S> {
+    transport: RemoteTransport,
+    args: Vec<String>,
+    remote_input: String,
+    timeout: Duration,
+    retries: usize,
+    debug_child: bool,
+    coverage: Option<(String, String, CoverageFiller<OT>)>,
+    observers: OT,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<I, OT, S> Debug for RemoteCommandExecutor<I, OT, S>
+where
+    OT: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteCommandExecutor")
+            .field("transport", &self.transport)
+            .field("args", &self.args)
+            .field("remote_input", &self.remote_input)
+            .field("timeout", &self.timeout)
+            .field("retries", &self.retries)
+            .field("observers", &self.observers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<I, OT, S> RemoteCommandExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    /// Creates a new [`RemoteCommandExecutor`] running the harness `args`, a program and its
+    /// arguments on the remote side, over `transport`.
+    /// The input replaces the `@@` in the arguments, if any, or goes to the stdin of the harness.
+    pub fn new<IT, O>(transport: RemoteTransport, args: IT, observers: OT) -> Result<Self, Error>
+    where
+        IT: IntoIterator<Item = O>,
+        O: AsRef<str>,
+    {
+        let args: Vec<String> = args
+            .into_iter()
+            .map(|arg| arg.as_ref().to_string())
+            .collect();
+        if args.is_empty() {
+            return Err(Error::IllegalArgument(
+                "No harness to run on the remote side".to_string(),
+            ));
+        }
+        Ok(Self {
+            transport,
+            args,
+            remote_input: REMOTE_DEFAULT_INPUT.to_string(),
+            timeout: Duration::from_secs(5),
+            retries: 3,
+            debug_child: false,
+            coverage: None,
+            observers,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Sets the path of the input on the remote side
+    #[must_use]
+    pub fn with_remote_input(mut self, remote_input: &str) -> Self {
+        self.remote_input = remote_input.to_string();
+        self
+    }
+
+    /// Sets the timeout of each run, on the remote side
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets how many times a run failing to connect is retried, after reconnecting
+    #[must_use]
+    pub fn with_retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Copies the coverage file the harness writes at `remote_path`, in each run, into the map of
+    /// the [`MapObserver`] `M` named `observer_name`, truncated to its size
+    #[must_use]
+    pub fn with_coverage_file<M>(mut self, remote_path: &str, observer_name: &str) -> Self
+    where
+        M: MapObserver<Entry = u8>,
+    {
+        self.coverage = Some((
+            remote_path.to_string(),
+            observer_name.to_string(),
+            fill_map::<I, M, OT, S>,
+        ));
+        self
+    }
+
+    /// Keeps the output of the harness visible, on the stderr of the transport
+    #[must_use]
+    pub fn debug_child(mut self, debug_child: bool) -> Self {
+        self.debug_child = debug_child;
+        self
+    }
+
+    /// The shell script of a run, writing its stdin to the input file then running the harness
+    fn script(&self) -> String {
+        let remote_input = shell_quote(&self.remote_input);
+        let mut stdin = true;
+        let mut harness = String::new();
+        for arg in &self.args {
+            if arg.contains(AFL_INPUT_PLACEHOLDER) {
+                stdin = false;
+            }
+            harness.push(' ');
+            harness.push_str(&shell_quote(
+                &arg.replace(AFL_INPUT_PLACEHOLDER, &self.remote_input),
+            ));
+        }
+        let cleanup = match &self.coverage {
+            Some((remote_path, _, _)) => format!("rm -f {}; ", shell_quote(remote_path)),
+            None => String::new(),
+        };
+        format!(
+            "{cleanup}cat > {input}; timeout -s KILL {timeout}{harness} < {stdin}{output}; echo {marker}$?",
+            cleanup = cleanup,
+            input = remote_input,
+            timeout = self.timeout.as_secs_f64(),
+            harness = harness,
+            stdin = if stdin { &remote_input } else { "/dev/null" },
+            output = if self.debug_child {
+                " 1>&2"
+            } else {
+                " > /dev/null 2>&1"
+            },
+            marker = STATUS_MARKER,
+        )
+    }
+
+    /// Runs the harness once on the remote side, returning its exit status and how long it took,
+    /// or `None` if the transport failed
+    fn run_remote(&self, script: &str, input: &[u8]) -> Result<Option<(i32, Duration)>, Error> {
+        let mut child = self
+            .transport
+            .command(script, false)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(if self.debug_child {
+                Stdio::inherit()
+            } else {
+                Stdio::null()
+            })
+            .spawn()?;
+        let start_time = Instant::now();
+        if let Some(mut stdin) = child.stdin.take() {
+            // The transport may fail without reading the input
+            let _ = stdin.write_all(input);
+        }
+        if child
+            .wait_timeout(self.timeout + CONNECTION_SLACK)?
+            .is_none()
+        {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        let elapsed = start_time.elapsed();
+        let mut output = String::new();
+        if let Some(mut stdout) = child.stdout.take() {
+            let _ = stdout.read_to_string(&mut output);
+        }
+        Ok(parse_status(&output).map(|status| (status, elapsed)))
+    }
+
+    /// Copies the coverage file of the last run into its map observer
+    fn fetch_coverage(&mut self) -> Result<(), Error> {
+        if let Some((remote_path, observer_name, fill)) = &self.coverage {
+            let output = self
+                .transport
+                .command(&format!("cat {}", shell_quote(remote_path)), true)
+                .stderr(Stdio::null())
+                .output()?;
+            fill(&mut self.observers, observer_name, &output.stdout)?;
+        }
+        Ok(())
+    }
+}
+
+impl<EM, I, OT, S, Z> Executor<EM, I, S, Z> for RemoteCommandExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    fn run_target(
+        &mut self,
+        _fuzzer: &mut Z,
+        _state: &mut S,
+        _mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        let script = self.script();
+        let target_bytes = input.target_bytes();
+        let mut attempt = 0;
+        let (status, elapsed) = loop {
+            if let Some(res) = self.run_remote(&script, target_bytes.as_slice())? {
+                break res;
+            }
+            if attempt >= self.retries {
+                return Err(Error::Unknown(format!(
+                    "Could not run the harness on {:?}",
+                    self.transport
+                )));
+            }
+            attempt += 1;
+            self.transport.reconnect();
+        };
+        self.fetch_coverage()?;
+
+        // The remote shell reports the harness killed by a signal as 128 + signal
+        if status == TIMEOUT_KILLED_STATUS && elapsed >= self.timeout {
+            Ok(ExitKind::Timeout)
+        } else if status > 128 {
+            observe_exit_status(&mut self.observers, Some(status - 128), None, false);
+            Ok(ExitKind::Crash)
+        } else {
+            observe_exit_status(&mut self.observers, None, Some(status), false);
+            Ok(ExitKind::Ok)
+        }
+    }
+}
+
+impl<I, OT, S> HasObservers<I, OT, S> for RemoteCommandExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    fn observers(&self) -> &OT {
+        &self.observers
+    }
+
+    fn observers_mut(&mut self) -> &mut OT {
+        &mut self.observers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        executors::remote::{parse_status, shell_quote, RemoteCommandExecutor, RemoteTransport},
+        inputs::BytesInput,
+    };
+
+    #[test]
+    fn test_remote_script() {
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(parse_status("output\n__LIBAFL_STATUS__139\n"), Some(139));
+        assert_eq!(parse_status("Connection refused\n"), None);
+
+        let executor = RemoteCommandExecutor::<BytesInput, (), ()>::new(
+            RemoteTransport::adb(None),
+            ["/data/local/tmp/harness", "-f", "@@"],
+            (),
+        )
+        .unwrap();
+        assert_eq!(
+            executor.script(),
+            "cat > '/tmp/.libafl_cur_input'; timeout -s KILL 5 '/data/local/tmp/harness' '-f' \
+             '/tmp/.libafl_cur_input' < /dev/null > /dev/null 2>&1; echo __LIBAFL_STATUS__$?"
+        );
+    }
+}
@@ -12,7 +12,10 @@ use crate::{
     Error,
 };
 
-/// A [`ShadowExecutor`] wraps an executor and a set of shadow observers
+/// A [`ShadowExecutor`] wraps an executor and a set of shadow observers.
+/// The shadow observers, e.g. the cmplog ones, are not run by the fuzzer, but by the stages
+/// needing them, e.g. the [`crate::stages::ShadowTracingStage`], so that one executor serves both
+/// the fuzzing and the tracing, with the same instrumented harness.
 pub struct ShadowExecutor<E: Debug, I: Debug, S, SOT: Debug> {
     /// The wrapped executor
     executor: E,
@@ -44,7 +47,19 @@ where
         }
     }
 
-    /// The shadow observers are not considered by the feedbacks and the manager, mutable
+    /// The wrapped executor
+    #[inline]
+    pub fn executor(&self) -> &E {
+        &self.executor
+    }
+
+    /// The wrapped executor, mutable
+    #[inline]
+    pub fn executor_mut(&mut self) -> &mut E {
+        &mut self.executor
+    }
+
+    /// The shadow observers are not considered by the feedbacks and the manager
     #[inline]
     pub fn shadow_observers(&self) -> &SOT {
         &self.shadow_observers
@@ -7,9 +7,12 @@ use core::{
 };
 
 use crate::{
+    corpus::Corpus,
     executors::{Executor, ExitKind, HasObservers},
     inputs::Input,
     observers::ObserversTuple,
+    stages::CalibrationMetadata,
+    state::{HasCorpus, HasMetadata},
     Error,
 };
 
@@ -252,7 +255,9 @@ impl<E: HasInProcessHandlers> TimeoutExecutor<E> {
             critical,
         }
     }
+}
 
+impl<E> TimeoutExecutor<E> {
     /// Set the timeout for this executor
     #[cfg(windows)]
     pub fn set_timeout(&mut self, exec_tmout: Duration) {
@@ -411,3 +416,194 @@ where
         self.executor.observers_mut()
     }
 }
+
+/// The default factor between the mean exec time of a corpus entry and the timeout of its mutants
+pub const DEFAULT_ADAPTIVE_TIMEOUT_MULTIPLIER: u32 = 5;
+
+/// The default minimum adaptive timeout, as the timers are not precise enough for shorter ones
+pub const DEFAULT_ADAPTIVE_TIMEOUT_MIN: Duration = Duration::from_millis(20);
+
+/// A [`TimeoutExecutor`] setting the timeout of each run from the [`CalibrationMetadata`] of the
+/// corpus entry being fuzzed: a multiple of its mean exec time, between a minimum and a maximum.
+/// Inputs not derived from a calibrated corpus entry, e.g. the initial inputs, get the maximum.
+#[derive(Debug)]
+pub struct AdaptiveTimeoutExecutor<E> {
+    executor: TimeoutExecutor<E>,
+    multiplier: u32,
+    min_timeout: Duration,
+    max_timeout: Duration,
+    current_timeout: Duration,
+}
+
+impl<E> AdaptiveTimeoutExecutor<E> {
+    /// Creates a new [`AdaptiveTimeoutExecutor`], wrapping the given [`TimeoutExecutor`], with
+    /// timeouts of at most `max_timeout`
+    pub fn new(mut executor: TimeoutExecutor<E>, max_timeout: Duration) -> Self {
+        executor.set_timeout(max_timeout);
+        Self {
+            executor,
+            multiplier: DEFAULT_ADAPTIVE_TIMEOUT_MULTIPLIER,
+            min_timeout: DEFAULT_ADAPTIVE_TIMEOUT_MIN,
+            max_timeout,
+            current_timeout: max_timeout,
+        }
+    }
+
+    /// Sets the factor between the mean exec time of a corpus entry and the timeout of its mutants
+    #[must_use]
+    pub fn with_multiplier(mut self, multiplier: u32) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Sets the minimum timeout
+    #[must_use]
+    pub fn with_min_timeout(mut self, min_timeout: Duration) -> Self {
+        self.min_timeout = min_timeout;
+        self
+    }
+
+    /// The timeout of the last run
+    #[must_use]
+    pub fn current_timeout(&self) -> Duration {
+        self.current_timeout
+    }
+
+    /// The timeout for the mutants of a corpus entry with the given mean exec time
+    fn timeout_for(&self, exec_time_mean: Option<Duration>) -> Duration {
+        exec_time_mean.map_or(self.max_timeout, |mean| {
+            (mean * self.multiplier)
+                .max(self.min_timeout)
+                .min(self.max_timeout)
+        })
+    }
+
+    /// Retrieve the inner [`TimeoutExecutor`]
+    pub fn inner(&mut self) -> &mut TimeoutExecutor<E> {
+        &mut self.executor
+    }
+}
+
+impl<E, EM, I, S, Z> Executor<EM, I, S, Z> for AdaptiveTimeoutExecutor<E>
+where
+    E: Debug,
+    TimeoutExecutor<E>: Executor<EM, I, S, Z>,
+    I: Input,
+    S: HasCorpus<I>,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        let exec_time_mean = match state.corpus().current() {
+            Some(idx) => state.corpus().get(*idx).ok().and_then(|testcase| {
+                testcase
+                    .borrow()
+                    .metadata()
+                    .get::<CalibrationMetadata>()
+                    .map(|meta| meta.exec_time_mean)
+            }),
+            None => None,
+        };
+        let timeout = self.timeout_for(exec_time_mean);
+        if timeout != self.current_timeout {
+            self.executor.set_timeout(timeout);
+            self.current_timeout = timeout;
+        }
+        self.executor.run_target(fuzzer, state, mgr, input)
+    }
+
+    fn post_run_reset(&mut self) {
+        Executor::<EM, I, S, Z>::post_run_reset(&mut self.executor);
+    }
+}
+
+impl<E, I, OT, S> HasObservers<I, OT, S> for AdaptiveTimeoutExecutor<E>
+where
+    E: HasObservers<I, OT, S>,
+    OT: ObserversTuple<I, S>,
+{
+    #[inline]
+    fn observers(&self) -> &OT {
+        self.executor.observers()
+    }
+
+    #[inline]
+    fn observers_mut(&mut self) -> &mut OT {
+        self.executor.observers_mut()
+    }
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use core::time::Duration;
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        executors::{AdaptiveTimeoutExecutor, Executor, NopExecutor, TimeoutExecutor},
+        inputs::BytesInput,
+        stages::CalibrationMetadata,
+        state::{HasCorpus, HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_adaptive_timeout() {
+        let max = Duration::from_secs(1);
+        let mut executor =
+            AdaptiveTimeoutExecutor::new(TimeoutExecutor::new(NopExecutor {}, max), max);
+
+        // Uncalibrated inputs get the maximum, the others a multiple of their mean exec time
+        assert_eq!(executor.timeout_for(None), max);
+        assert_eq!(
+            executor.timeout_for(Some(Duration::from_millis(10))),
+            Duration::from_millis(50)
+        );
+        assert_eq!(
+            executor.timeout_for(Some(Duration::from_millis(1))),
+            Duration::from_millis(20)
+        );
+        assert_eq!(executor.timeout_for(Some(Duration::from_millis(500))), max);
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            tuple_list!(),
+        );
+        let input = BytesInput::new(vec![0; 4]);
+        let mut testcase = Testcase::new(input.clone());
+        testcase.add_metadata(CalibrationMetadata::new(
+            2,
+            0,
+            &[Duration::from_millis(30), Duration::from_millis(50)],
+            0,
+            0,
+        ));
+        let idx = state.corpus_mut().add(testcase).unwrap();
+
+        // The initial inputs are run before any corpus entry is fuzzed
+        executor
+            .run_target(&mut (), &mut state, &mut (), &input)
+            .unwrap();
+        assert_eq!(executor.current_timeout(), max);
+
+        *state.corpus_mut().current_mut() = Some(idx);
+        executor
+            .run_target(&mut (), &mut state, &mut (), &input)
+            .unwrap();
+        assert_eq!(executor.current_timeout(), Duration::from_millis(200));
+
+        let mut executor = executor
+            .with_multiplier(2)
+            .with_min_timeout(Duration::from_millis(100));
+        executor
+            .run_target(&mut (), &mut state, &mut (), &input)
+            .unwrap();
+        assert_eq!(executor.current_timeout(), Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,203 @@
+//! The [`DiffFeedback`] reports the inputs on which two observers disagree, for differential fuzzing
+//! with the [`crate::executors::DiffExecutor`].
+
+use alloc::string::{String, ToString};
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+};
+
+use crate::{
+    bolts::tuples::Named,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    observers::{DiffObserver, ObserversTuple, DIFF_OBSERVER_NAME},
+    state::HasClientPerfMonitor,
+    Error,
+};
+
+/// A [`DiffFeedback`] reports as interesting the inputs for which the `differ` function tells two
+/// observers apart, e.g. the observers of the values computed by two implementations of a parser.
+/// Use it as an objective, for the disagreements to be solutions.
+pub struct DiffFeedback<F, O1, O2>
+where
+    F: FnMut(&O1, &O2) -> bool,
+{
+    name: String,
+    o1_name: String,
+    o2_name: String,
+    differ: F,
+    phantom: PhantomData<(O1, O2)>,
+}
+
+impl<F, O1, O2> DiffFeedback<F, O1, O2>
+where
+    F: FnMut(&O1, &O2) -> bool,
+{
+    /// Creates a new [`DiffFeedback`], comparing the observers named `o1_name` and `o2_name` with
+    /// `differ`, returning `true` if they differ
+    #[must_use]
+    pub fn new(name: &str, o1_name: &str, o2_name: &str, differ: F) -> Self {
+        Self {
+            name: name.to_string(),
+            o1_name: o1_name.to_string(),
+            o2_name: o2_name.to_string(),
+            differ,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl DiffFeedback<fn(&DiffObserver, &DiffObserver) -> bool, DiffObserver, DiffObserver> {
+    /// Creates a new [`DiffFeedback`] reporting the inputs on which the targets of a
+    /// [`crate::executors::DiffExecutor`] exit differently, according to its [`DiffObserver`]
+    #[must_use]
+    pub fn exit_kinds() -> Self {
+        Self::new(
+            "DiffExitKindFeedback",
+            DIFF_OBSERVER_NAME,
+            DIFF_OBSERVER_NAME,
+            |observer, _| observer.exit_kinds_differ(),
+        )
+    }
+
+    /// Creates a new [`DiffFeedback`] reporting the inputs on which the targets of a
+    /// [`crate::executors::DiffExecutor`] print different stdout, according to its [`DiffObserver`]
+    #[must_use]
+    pub fn stdout() -> Self {
+        Self::new(
+            "DiffStdOutFeedback",
+            DIFF_OBSERVER_NAME,
+            DIFF_OBSERVER_NAME,
+            |observer, _| observer.stdout_differs(),
+        )
+    }
+}
+
+impl<F, I, O1, O2, S> Feedback<I, S> for DiffFeedback<F, O1, O2>
+where
+    F: FnMut(&O1, &O2) -> bool,
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let o1 = observers
+            .match_name::<O1>(&self.o1_name)
+            .expect("A DiffFeedback needs its first observer");
+        let o2 = observers
+            .match_name::<O2>(&self.o2_name)
+            .expect("A DiffFeedback needs its second observer");
+        Ok((self.differ)(o1, o2))
+    }
+}
+
+impl<F, O1, O2> Named for DiffFeedback<F, O1, O2>
+where
+    F: FnMut(&O1, &O2) -> bool,
+{
+    #[inline]
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<F, O1, O2> Debug for DiffFeedback<F, O1, O2>
+where
+    F: FnMut(&O1, &O2) -> bool,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiffFeedback")
+            .field("name", &self.name)
+            .field("o1_name", &self.o1_name)
+            .field("o2_name", &self.o2_name)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, feature = "std", unix))]
+mod tests {
+    use crate::{
+        bolts::{
+            rands::StdRand,
+            tuples::{tuple_list, MatchName},
+        },
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::{
+            command::{CommandExecutor, InputLocation},
+            DiffExecutor, Executor, ExitKind, HasObservers,
+        },
+        feedbacks::{DiffFeedback, Feedback},
+        inputs::BytesInput,
+        observers::{DiffObserver, ObserversTuple, StdOutObserver, STDOUT_OBSERVER_NAME},
+        state::StdState,
+    };
+
+    #[test]
+    fn test_diff_feedback_exit_kinds() {
+        let mut primary = CommandExecutor::builder();
+        primary.program("true").input(InputLocation::StdIn);
+        let primary = primary
+            .build::<(), BytesInput, _, _, ()>(tuple_list!(DiffObserver::new()))
+            .unwrap();
+        let mut secondary = CommandExecutor::builder();
+        secondary
+            .program("sh")
+            .arg("-c")
+            .arg("kill -SEGV $$")
+            .input(InputLocation::StdIn);
+        let secondary = secondary
+            .build::<(), BytesInput, _, _, ()>(tuple_list!(StdOutObserver::new()))
+            .unwrap();
+        let mut executor = DiffExecutor::new(primary, secondary);
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            tuple_list!(),
+        );
+        let input = BytesInput::new(vec![]);
+        executor
+            .observers_mut()
+            .pre_exec_all(&mut state, &input)
+            .unwrap();
+        let exit_kind = executor
+            .run_target(&mut (), &mut state, &mut (), &input)
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Crash);
+        executor
+            .observers_mut()
+            .post_exec_all(&mut state, &input, &exit_kind)
+            .unwrap();
+
+        // The observers of the secondary executor get matched too
+        assert!(executor
+            .observers()
+            .match_name::<StdOutObserver>(STDOUT_OBSERVER_NAME)
+            .is_some());
+        let mut feedback = DiffFeedback::exit_kinds();
+        assert!(feedback
+            .is_interesting(
+                &mut state,
+                &mut NopEventManager {},
+                &input,
+                executor.observers(),
+                &exit_kind
+            )
+            .unwrap());
+    }
+}
@@ -435,6 +435,8 @@ where
                     }
                 }
             }
+            // The edges discovered so far and the size of the map, the monitors show them as
+            // coverage, see `monitors::EDGES_USER_STAT`
             manager.fire(
                 state,
                 Event::UpdateUserStats {
@@ -0,0 +1,73 @@
+//! The [`MemUsageFeedback`] reports the runs exceeding a limit of memory, as observed by a
+//! [`MemUsageObserver`], to find the inputs exhausting the memory of the target without crashing it.
+
+use alloc::string::{String, ToString};
+
+use crate::{
+    bolts::tuples::Named,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    observers::{MemUsageObserver, ObserversTuple, MEM_USAGE_OBSERVER_NAME},
+    state::HasClientPerfMonitor,
+    Error,
+};
+
+/// A [`MemUsageFeedback`] reports as interesting the runs in which the peak resident set size of
+/// the target, according to the [`MemUsageObserver`], exceeded a limit.
+/// Use it as an objective, or-ed with the `CrashFeedback`, to keep the inputs exhausting the
+/// memory as solutions.
+#[derive(Debug, Clone)]
+pub struct MemUsageFeedback {
+    name: String,
+    limit: u64,
+}
+
+impl MemUsageFeedback {
+    /// Creates a new [`MemUsageFeedback`], reporting the runs exceeding `limit` bytes
+    #[must_use]
+    pub fn new(limit: u64) -> Self {
+        Self {
+            name: "MemUsageFeedback".to_string(),
+            limit,
+        }
+    }
+
+    /// The limit of the peak resident set size, in bytes
+    #[must_use]
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+}
+
+impl<I, S> Feedback<I, S> for MemUsageFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let observer = observers
+            .match_name::<MemUsageObserver>(MEM_USAGE_OBSERVER_NAME)
+            .expect("A MemUsageFeedback needs a MemUsageObserver");
+        Ok(matches!(observer.max_rss(), Some(max_rss) if max_rss > self.limit))
+    }
+}
+
+impl Named for MemUsageFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -4,11 +4,32 @@
 pub mod map;
 pub use map::*;
 
+pub mod differential;
+pub use differential::DiffFeedback;
+
+#[cfg(all(feature = "std", unix))]
+pub mod mem_usage;
+#[cfg(all(feature = "std", unix))]
+pub use mem_usage::MemUsageFeedback;
+
+pub mod stack_depth;
+pub use stack_depth::{MaxStackDepthFeedback, StackDepthFeedbackState};
+
 #[cfg(feature = "std")]
 pub mod concolic;
 #[cfg(feature = "std")]
 pub use concolic::ConcolicFeedback;
 
+#[cfg(feature = "std")]
+pub mod output;
+#[cfg(feature = "std")]
+pub use output::{OutputRegexFeedback, OutputStream};
+
+#[cfg(feature = "std")]
+pub mod sanitizer;
+#[cfg(feature = "std")]
+pub use sanitizer::AsanReportFeedback;
+
 #[cfg(feature = "std")]
 pub mod new_hash_feedback;
 #[cfg(feature = "std")]
@@ -0,0 +1,121 @@
+//! The [`OutputRegexFeedback`] reports the runs whose output matches a regex, for the findings only
+//! visible in the output of the target, such as sanitizer reports or panic messages.
+
+use alloc::string::{String, ToString};
+
+use regex::bytes::Regex;
+
+use crate::{
+    bolts::tuples::Named,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    observers::{
+        ObserversTuple, StdErrObserver, StdOutObserver, STDERR_OBSERVER_NAME, STDOUT_OBSERVER_NAME,
+    },
+    state::HasClientPerfMonitor,
+    Error,
+};
+
+/// The regex of the error reports of the sanitizers, e.g.
+/// `==1234==ERROR: AddressSanitizer: heap-buffer-overflow`
+pub const SANITIZER_REPORT_REGEX: &str = r"==\d+==ERROR: \w+Sanitizer|: runtime error: ";
+
+/// The output an [`OutputRegexFeedback`] matches on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    /// The stdout, captured by a [`StdOutObserver`]
+    StdOut,
+    /// The stderr, captured by a [`StdErrObserver`]
+    StdErr,
+}
+
+/// A [`OutputRegexFeedback`] reports as interesting the runs whose stdout, or stderr, matches a
+/// regex. The output needs to be captured by a [`StdOutObserver`], or a [`StdErrObserver`], in the
+/// observers. Use it as an objective, for the runs printing e.g. a sanitizer report or a
+/// target-specific panic message to be solutions, even if the target exits normally.
+#[derive(Debug, Clone)]
+pub struct OutputRegexFeedback {
+    name: String,
+    stream: OutputStream,
+    regex: Regex,
+}
+
+impl OutputRegexFeedback {
+    /// Creates a new [`OutputRegexFeedback`], matching `stream` with `regex`
+    pub fn new(name: &str, stream: OutputStream, regex: &str) -> Result<Self, Error> {
+        let regex = Regex::new(regex)
+            .map_err(|err| Error::IllegalArgument(format!("Invalid regex {}: {}", regex, err)))?;
+        Ok(Self {
+            name: name.to_string(),
+            stream,
+            regex,
+        })
+    }
+
+    /// Creates a new [`OutputRegexFeedback`], matching the stdout with `regex`
+    pub fn stdout(regex: &str) -> Result<Self, Error> {
+        Self::new("StdOutRegexFeedback", OutputStream::StdOut, regex)
+    }
+
+    /// Creates a new [`OutputRegexFeedback`], matching the stderr with `regex`
+    pub fn stderr(regex: &str) -> Result<Self, Error> {
+        Self::new("StdErrRegexFeedback", OutputStream::StdErr, regex)
+    }
+
+    /// Creates a new [`OutputRegexFeedback`] reporting the runs in which a sanitizer reported an
+    /// error on stderr, matching [`SANITIZER_REPORT_REGEX`]
+    #[must_use]
+    pub fn sanitizer_report() -> Self {
+        Self::new(
+            "SanitizerReportFeedback",
+            OutputStream::StdErr,
+            SANITIZER_REPORT_REGEX,
+        )
+        .expect("The sanitizer report regex is valid")
+    }
+
+    /// The regex of this feedback
+    #[must_use]
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+}
+
+impl<I, S> Feedback<I, S> for OutputRegexFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        Ok(match self.stream {
+            OutputStream::StdOut => observers
+                .match_name::<StdOutObserver>(STDOUT_OBSERVER_NAME)
+                .expect("An OutputRegexFeedback on the stdout needs a StdOutObserver")
+                .is_match(&self.regex),
+            OutputStream::StdErr => observers
+                .match_name::<StdErrObserver>(STDERR_OBSERVER_NAME)
+                .expect("An OutputRegexFeedback on the stderr needs a StdErrObserver")
+                .is_match(&self.regex),
+        })
+    }
+}
+
+impl Named for OutputRegexFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -0,0 +1,91 @@
+//! The [`AsanReportFeedback`] reports the runs in which a sanitizer reported an error, storing the
+//! parsed report on the testcase
+
+use alloc::string::{String, ToString};
+
+use crate::{
+    bolts::tuples::Named,
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    observers::{
+        AsanReportMetadata, ObserversTuple, SanitizerReportObserver, SANITIZER_REPORT_OBSERVER_NAME,
+    },
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error,
+};
+
+/// A [`AsanReportFeedback`] reports as interesting the runs in which a sanitizer reported an
+/// error, according to the [`SanitizerReportObserver`], and stores the parsed report on the
+/// testcase, as an [`AsanReportMetadata`].
+/// Use it as an objective, alone or or-ed with a `CrashFeedback`, for the solutions to tell
+/// the kind of their bug.
+#[derive(Debug, Clone)]
+pub struct AsanReportFeedback {
+    name: String,
+    report: Option<AsanReportMetadata>,
+}
+
+impl AsanReportFeedback {
+    /// Creates a new [`AsanReportFeedback`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            name: "AsanReportFeedback".to_string(),
+            report: None,
+        }
+    }
+}
+
+impl Default for AsanReportFeedback {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I, S> Feedback<I, S> for AsanReportFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        self.report = observers
+            .match_name::<SanitizerReportObserver>(SANITIZER_REPORT_OBSERVER_NAME)
+            .expect("An AsanReportFeedback needs a SanitizerReportObserver")
+            .report()
+            .cloned();
+        Ok(self.report.is_some())
+    }
+
+    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if let Some(report) = self.report.take() {
+            testcase.add_metadata(report);
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.report = None;
+        Ok(())
+    }
+}
+
+impl Named for AsanReportFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
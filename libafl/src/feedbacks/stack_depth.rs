@@ -0,0 +1,133 @@
+//! The [`MaxStackDepthFeedback`] rewards the inputs reaching a new maximum stack depth, as
+//! observed by a [`StackDepthObserver`]. Deep recursions are invisible to the edge coverage.
+
+use alloc::string::{String, ToString};
+use core::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::{MatchName, Named},
+    events::{Event, EventFirer},
+    executors::ExitKind,
+    feedbacks::{Feedback, FeedbackState},
+    inputs::Input,
+    monitors::UserStats,
+    observers::{ObserversTuple, StackDepthObserver},
+    state::{HasClientPerfMonitor, HasFeedbackStates},
+    Error,
+};
+
+/// The state of a [`MaxStackDepthFeedback`], the maximum stack depth reached so far
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StackDepthFeedbackState {
+    /// The maximum stack depth reached so far
+    pub max_depth: usize,
+    /// Name identifier of this instance
+    pub name: String,
+}
+
+impl FeedbackState for StackDepthFeedbackState {
+    fn reset(&mut self) -> Result<(), Error> {
+        self.max_depth = 0;
+        Ok(())
+    }
+}
+
+impl Named for StackDepthFeedbackState {
+    #[inline]
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl StackDepthFeedbackState {
+    /// Create a new [`StackDepthFeedbackState`]
+    #[must_use]
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            max_depth: 0,
+            name: name.to_string(),
+        }
+    }
+
+    /// Create a new [`StackDepthFeedbackState`] for a [`StackDepthObserver`]
+    #[must_use]
+    pub fn with_observer(observer: &StackDepthObserver) -> Self {
+        Self {
+            max_depth: 0,
+            name: observer.name().to_string(),
+        }
+    }
+}
+
+/// A [`MaxStackDepthFeedback`] considers interesting the runs reaching a stack depth above the
+/// maximum reached so far, kept in its [`StackDepthFeedbackState`], like a
+/// [`crate::feedbacks::MaxMapFeedback`] of a single entry.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MaxStackDepthFeedback {
+    name: String,
+    observer_name: String,
+}
+
+impl<I, S> Feedback<I, S> for MaxStackDepthFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor + HasFeedbackStates,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let depth = observers
+            .match_name::<StackDepthObserver>(&self.observer_name)
+            .expect("A MaxStackDepthFeedback needs a StackDepthObserver")
+            .max_depth();
+
+        let depth_state = state
+            .feedback_states_mut()
+            .match_name_mut::<StackDepthFeedbackState>(&self.observer_name)
+            .unwrap();
+        if depth <= depth_state.max_depth {
+            return Ok(false);
+        }
+        depth_state.max_depth = depth;
+
+        manager.fire(
+            state,
+            Event::UpdateUserStats {
+                name: self.name.clone(),
+                value: UserStats::Number(depth as u64),
+                phantom: PhantomData,
+            },
+        )?;
+        Ok(true)
+    }
+}
+
+impl Named for MaxStackDepthFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl MaxStackDepthFeedback {
+    /// Creates a new [`MaxStackDepthFeedback`] for a [`StackDepthObserver`], with the
+    /// [`StackDepthFeedbackState`] of the same name
+    #[must_use]
+    pub fn new(observer: &StackDepthObserver) -> Self {
+        Self {
+            name: "MaxStackDepthFeedback".to_string(),
+            observer_name: observer.name().to_string(),
+        }
+    }
+}
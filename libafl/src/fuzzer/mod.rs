@@ -1,25 +1,26 @@
 //! The `Fuzzer` is the main struct for a fuzz campaign.
 
 use crate::{
-    bolts::current_time,
+    bolts::{current_time, tuples::MatchName},
     corpus::{Corpus, CorpusScheduler, Testcase},
-    events::{Event, EventConfig, EventFirer, EventManager, ProgressReporter},
+    events::{Event, EventConfig, EventFirer, EventManager, LogSeverity, ProgressReporter},
     executors::{Executor, ExitKind, HasObservers},
     feedbacks::Feedback,
-    inputs::Input,
+    inputs::{input_hash, Input},
     mark_feature_time,
-    observers::ObserversTuple,
+    observers::{ExitStatusObserver, ObserversTuple, EXIT_STATUS_OBSERVER_NAME},
     stages::StagesTuple,
     start_timer,
-    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasSolutions},
+    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata, HasSolutions},
     Error,
 };
 
 #[cfg(feature = "introspection")]
 use crate::monitors::PerfFeature;
 
-use alloc::string::ToString;
+use alloc::{format, string::ToString, vec::Vec};
 use core::{marker::PhantomData, time::Duration};
+use serde::{Deserialize, Serialize};
 
 /// Send a monitor update all 15 (or more) seconds
 const STATS_TIMEOUT_DEFAULT: Duration = Duration::from_secs(15);
@@ -65,6 +66,17 @@ where
     fn objective_mut(&mut self) -> &mut OF;
 }
 
+/// The signal terminating the last run, if any. The executors running the target in a child
+/// process fill it in the [`ExitStatusObserver`] from the wait status of the child.
+fn exit_signal<OT>(observers: &OT) -> Option<i32>
+where
+    OT: MatchName,
+{
+    observers
+        .match_name::<ExitStatusObserver>(EXIT_STATUS_OBSERVER_NAME)
+        .and_then(ExitStatusObserver::signal)
+}
+
 /// Evaluate if an input is interesting using the feedback
 pub trait ExecutionProcessor<I, OT, S>
 where
@@ -231,6 +243,56 @@ pub enum ExecuteInputResult {
     Solution,
 }
 
+/// How the [`StdFuzzer`] re-runs the new objectives, before trusting them
+#[derive(Clone, Copy, Debug)]
+pub struct ObjectiveVerification {
+    /// The number of times each new objective gets re-run
+    pub runs: usize,
+    /// Drops the objectives not reproducing in any run, neither added to the solutions nor
+    /// reported, instead of only annotating them as flaky
+    pub discard_flaky: bool,
+}
+
+/// The reproducibility of an objective, re-run by the [`StdFuzzer`] as it was found,
+/// see [`StdFuzzer::with_objective_verification`]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReproducibilityMetadata {
+    /// The exit kind of the run finding the objective
+    pub exit_kind: ExitKind,
+    /// The exit kinds of the re-runs
+    pub exit_kinds: Vec<ExitKind>,
+}
+
+crate::impl_serdeany!(ReproducibilityMetadata);
+
+impl ReproducibilityMetadata {
+    /// The number of re-runs exiting as the run finding the objective
+    #[must_use]
+    pub fn reproduced(&self) -> usize {
+        self.exit_kinds
+            .iter()
+            .filter(|exit_kind| **exit_kind == self.exit_kind)
+            .count()
+    }
+
+    /// The ratio of the re-runs exiting as the run finding the objective
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn score(&self) -> f32 {
+        if self.exit_kinds.is_empty() {
+            1.0
+        } else {
+            self.reproduced() as f32 / self.exit_kinds.len() as f32
+        }
+    }
+
+    /// The objective did not reproduce in any re-run
+    #[must_use]
+    pub fn is_flaky(&self) -> bool {
+        !self.exit_kinds.is_empty() && self.reproduced() == 0
+    }
+}
+
 /// Your default fuzzer instance, for everyday use.
 #[derive(Debug)]
 pub struct StdFuzzer<CS, F, I, OF, OT, S>
@@ -244,6 +306,7 @@ where
     scheduler: CS,
     feedback: F,
     objective: OF,
+    objective_verification: Option<ObjectiveVerification>,
     phantom: PhantomData<(I, OT, S)>,
 }
 
@@ -304,7 +367,7 @@ where
     F: Feedback<I, S>,
     I: Input,
     OF: Feedback<I, S>,
-    OT: ObserversTuple<I, S> + serde::Serialize + serde::de::DeserializeOwned,
+    OT: ObserversTuple<I, S> + Serialize + serde::de::DeserializeOwned,
     S: HasCorpus<I> + HasSolutions<I> + HasClientPerfMonitor + HasExecutions,
 {
     /// Evaluate if a set of observation channels has an interesting state
@@ -317,6 +380,43 @@ where
         exit_kind: &ExitKind,
         send_events: bool,
     ) -> Result<(ExecuteInputResult, Option<usize>), Error>
+    where
+        EM: EventFirer<I>,
+    {
+        let res = self.execution_result(state, manager, &input, observers, *exit_kind)?;
+        let signal = exit_signal(observers);
+        self.add_execution(
+            state,
+            manager,
+            input,
+            observers,
+            *exit_kind,
+            signal,
+            send_events,
+            res,
+            None,
+        )
+    }
+}
+
+impl<CS, F, I, OF, OT, S> StdFuzzer<CS, F, I, OF, OT, S>
+where
+    CS: CorpusScheduler<I, S>,
+    F: Feedback<I, S>,
+    I: Input,
+    OF: Feedback<I, S>,
+    OT: ObserversTuple<I, S> + Serialize + serde::de::DeserializeOwned,
+    S: HasCorpus<I> + HasSolutions<I> + HasClientPerfMonitor + HasExecutions,
+{
+    /// Whether the observers of a run make its input a solution, a corpus entry, or neither
+    fn execution_result<EM>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &I,
+        observers: &OT,
+        exit_kind: ExitKind,
+    ) -> Result<ExecuteInputResult, Error>
     where
         EM: EventFirer<I>,
     {
@@ -325,12 +425,12 @@ where
         #[cfg(not(feature = "introspection"))]
         let is_solution = self
             .objective_mut()
-            .is_interesting(state, manager, &input, observers, exit_kind)?;
+            .is_interesting(state, manager, input, observers, &exit_kind)?;
 
         #[cfg(feature = "introspection")]
         let is_solution = self
             .objective_mut()
-            .is_interesting_introspection(state, manager, &input, observers, exit_kind)?;
+            .is_interesting_introspection(state, manager, input, observers, &exit_kind)?;
 
         if is_solution {
             res = ExecuteInputResult::Solution;
@@ -338,18 +438,39 @@ where
             #[cfg(not(feature = "introspection"))]
             let is_corpus = self
                 .feedback_mut()
-                .is_interesting(state, manager, &input, observers, exit_kind)?;
+                .is_interesting(state, manager, input, observers, &exit_kind)?;
 
             #[cfg(feature = "introspection")]
             let is_corpus = self
                 .feedback_mut()
-                .is_interesting_introspection(state, manager, &input, observers, exit_kind)?;
+                .is_interesting_introspection(state, manager, input, observers, &exit_kind)?;
 
             if is_corpus {
                 res = ExecuteInputResult::Corpus;
             }
         }
+        Ok(res)
+    }
 
+    /// Adds the input of a run to the corpus or to the solutions, as `res` tells, and fires the
+    /// event, the solution annotated with its `reproducibility` if verified, and with the `signal`
+    /// terminating the run, if any
+    #[allow(clippy::too_many_arguments)]
+    fn add_execution<EM>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: I,
+        observers: &OT,
+        exit_kind: ExitKind,
+        signal: Option<i32>,
+        send_events: bool,
+        res: ExecuteInputResult,
+        reproducibility: Option<ReproducibilityMetadata>,
+    ) -> Result<(ExecuteInputResult, Option<usize>), Error>
+    where
+        EM: EventFirer<I>,
+    {
         match res {
             ExecuteInputResult::None => {
                 self.feedback_mut().discard_metadata(state, &input)?;
@@ -366,6 +487,9 @@ where
                 let idx = state.corpus_mut().add(testcase)?;
                 self.scheduler_mut().on_add(state, idx)?;
 
+                #[cfg(feature = "tracing")]
+                tracing::debug!(corpus_idx = idx, ?exit_kind, "new corpus entry");
+
                 if send_events {
                     // TODO set None for fast targets
                     let observers_buf = if manager.configuration() == EventConfig::AlwaysUnique {
@@ -378,7 +502,7 @@ where
                         Event::NewTestcase {
                             input,
                             observers_buf,
-                            exit_kind: *exit_kind,
+                            exit_kind,
                             corpus_size: state.corpus().count(),
                             client_config: manager.configuration(),
                             time: current_time(),
@@ -393,15 +517,25 @@ where
                 self.feedback_mut().discard_metadata(state, &input)?;
 
                 // The input is a solution, add it to the respective corpus
+                let input_hash = input_hash(&input);
                 let mut testcase = Testcase::with_executions(input, *state.executions());
                 self.objective_mut().append_metadata(state, &mut testcase)?;
+                if let Some(reproducibility) = reproducibility {
+                    testcase.add_metadata(reproducibility);
+                }
                 state.solutions_mut().add(testcase)?;
 
+                #[cfg(feature = "tracing")]
+                tracing::info!(?exit_kind, "new objective");
+
                 if send_events {
                     manager.fire(
                         state,
                         Event::Objective {
                             objective_size: state.solutions().count(),
+                            input_hash,
+                            exit_kind,
+                            signal,
                         },
                     )?;
                 }
@@ -415,7 +549,7 @@ where
 impl<CS, F, I, OF, OT, S> EvaluatorObservers<I, OT, S> for StdFuzzer<CS, F, I, OF, OT, S>
 where
     CS: CorpusScheduler<I, S>,
-    OT: ObserversTuple<I, S> + serde::Serialize + serde::de::DeserializeOwned,
+    OT: ObserversTuple<I, S> + Serialize + serde::de::DeserializeOwned,
     F: Feedback<I, S>,
     I: Input,
     OF: Feedback<I, S>,
@@ -436,8 +570,42 @@ where
         EM: EventManager<E, I, S, Self>,
     {
         let exit_kind = self.execute_input(state, executor, manager, &input)?;
-        let observers = executor.observers();
-        self.process_execution(state, manager, input, observers, &exit_kind, send_events)
+        let res = self.execution_result(state, manager, &input, executor.observers(), exit_kind)?;
+        // Before the verification runs overwrite the observers
+        let signal = exit_signal(executor.observers());
+
+        // Re-run the objectives before they get stored and reported
+        let mut reproducibility = None;
+        if res == ExecuteInputResult::Solution {
+            if let Some(verification) = self.objective_verification {
+                let verified = self.verify_objective(
+                    state,
+                    executor,
+                    manager,
+                    &input,
+                    exit_kind,
+                    verification,
+                )?;
+                if verified.is_flaky() && verification.discard_flaky {
+                    self.feedback_mut().discard_metadata(state, &input)?;
+                    self.objective_mut().discard_metadata(state, &input)?;
+                    return Ok((ExecuteInputResult::None, None));
+                }
+                reproducibility = Some(verified);
+            }
+        }
+
+        self.add_execution(
+            state,
+            manager,
+            input,
+            executor.observers(),
+            exit_kind,
+            signal,
+            send_events,
+            res,
+            reproducibility,
+        )
     }
 }
 
@@ -445,7 +613,7 @@ impl<CS, E, EM, F, I, OF, OT, S> Evaluator<E, EM, I, S> for StdFuzzer<CS, F, I,
 where
     CS: CorpusScheduler<I, S>,
     E: Executor<EM, I, S, Self> + HasObservers<I, OT, S>,
-    OT: ObserversTuple<I, S> + serde::Serialize + serde::de::DeserializeOwned,
+    OT: ObserversTuple<I, S> + Serialize + serde::de::DeserializeOwned,
     EM: EventManager<E, I, S, Self>,
     F: Feedback<I, S>,
     I: Input,
@@ -531,6 +699,9 @@ where
         // Get the next index from the scheduler
         let idx = self.scheduler.next(state)?;
 
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("fuzz_one", corpus_idx = idx).entered();
+
         // Mark the elapsed time for the scheduler
         #[cfg(feature = "introspection")]
         state.introspection_monitor_mut().mark_scheduler_time();
@@ -571,10 +742,66 @@ where
             scheduler,
             feedback,
             objective,
+            objective_verification: None,
             phantom: PhantomData,
         }
     }
 
+    /// Re-runs each new objective found evaluating an input, and annotates it with its
+    /// [`ReproducibilityMetadata`], to tell the flaky objectives of flaky targets.
+    /// The re-runs happen before the objective gets added to the solutions and reported.
+    /// The target must run isolated, e.g. forked, as the re-runs of a crash would crash an
+    /// in-process target.
+    #[must_use]
+    pub fn with_objective_verification(mut self, verification: ObjectiveVerification) -> Self {
+        self.objective_verification = Some(verification);
+        self
+    }
+
+    /// Re-runs the input of a new objective, found by a run exiting with `exit_kind`, before it
+    /// gets added to the solutions
+    fn verify_objective<E, EM>(
+        &mut self,
+        state: &mut S,
+        executor: &mut E,
+        manager: &mut EM,
+        input: &I,
+        exit_kind: ExitKind,
+        verification: ObjectiveVerification,
+    ) -> Result<ReproducibilityMetadata, Error>
+    where
+        E: Executor<EM, I, S, Self> + HasObservers<I, OT, S>,
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let mut exit_kinds = Vec::with_capacity(verification.runs);
+        for _ in 0..verification.runs {
+            exit_kinds.push(self.execute_input(state, executor, manager, input)?);
+        }
+        let reproducibility = ReproducibilityMetadata {
+            exit_kind,
+            exit_kinds,
+        };
+
+        if reproducibility.is_flaky() {
+            manager.log(
+                state,
+                LogSeverity::Warn,
+                format!(
+                    "Objective ({:?}) did not reproduce in {} runs{}",
+                    exit_kind,
+                    verification.runs,
+                    if verification.discard_flaky {
+                        ", discarded"
+                    } else {
+                        ""
+                    }
+                ),
+            )?;
+        }
+        Ok(reproducibility)
+    }
+
     /// Runs the input and triggers observers and feedback
     pub fn execute_input<E, EM>(
         &mut self,
@@ -666,3 +893,213 @@ where
         Ok(exit_kind)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::{rc::Rc, string::String, vec::Vec};
+    use core::cell::RefCell;
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{Corpus, InMemoryCorpus, QueueCorpusScheduler},
+        events::{NopEventManager, SimpleEventManager},
+        executors::{Executor, ExitKind, HasObservers, WithObservers},
+        feedbacks::CrashFeedback,
+        fuzzer::{
+            EvaluatorObservers, ExecuteInputResult, ExecutionProcessor, ObjectiveVerification,
+            ReproducibilityMetadata, StdFuzzer,
+        },
+        inputs::BytesInput,
+        monitors::SimpleMonitor,
+        observers::ExitStatusObserver,
+        state::{HasMetadata, HasSolutions, StdState},
+        Error,
+    };
+
+    /// Exits as scripted, one exit kind per run
+    #[derive(Debug)]
+    struct ScriptedExecutor {
+        exit_kinds: Vec<ExitKind>,
+    }
+
+    impl<EM, S, Z> Executor<EM, BytesInput, S, Z> for ScriptedExecutor {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            _state: &mut S,
+            _mgr: &mut EM,
+            _input: &BytesInput,
+        ) -> Result<ExitKind, Error> {
+            Ok(self.exit_kinds.remove(0))
+        }
+    }
+
+    type TestState =
+        StdState<InMemoryCorpus<BytesInput>, (), BytesInput, StdRand, InMemoryCorpus<BytesInput>>;
+
+    /// Finds a crash on the first run, then re-runs it twice
+    fn verify(exit_kinds: Vec<ExitKind>, discard_flaky: bool) -> (ExecuteInputResult, TestState) {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            tuple_list!(),
+        );
+        let mut fuzzer: StdFuzzer<_, _, _, _, (), _> =
+            StdFuzzer::new(QueueCorpusScheduler::new(), (), CrashFeedback::new())
+                .with_objective_verification(ObjectiveVerification {
+                    runs: 2,
+                    discard_flaky,
+                });
+        let mut executor = WithObservers::new(ScriptedExecutor { exit_kinds }, ());
+        let (res, _) = fuzzer
+            .evaluate_input_with_observers(
+                &mut state,
+                &mut executor,
+                &mut NopEventManager {},
+                BytesInput::new(vec![0; 4]),
+                true,
+            )
+            .unwrap();
+        (res, state)
+    }
+
+    #[test]
+    fn test_objective_verification() {
+        // Flaky and discarded: neither stored nor reported
+        let (res, state) = verify(vec![ExitKind::Crash, ExitKind::Ok, ExitKind::Ok], true);
+        assert_eq!(res, ExecuteInputResult::None);
+        assert_eq!(state.solutions().count(), 0);
+
+        // Flaky, kept with its reproducibility
+        let (res, state) = verify(vec![ExitKind::Crash, ExitKind::Ok, ExitKind::Ok], false);
+        assert_eq!(res, ExecuteInputResult::Solution);
+        assert_eq!(state.solutions().count(), 1);
+
+        let (res, state) = verify(vec![ExitKind::Crash, ExitKind::Ok, ExitKind::Crash], true);
+        assert_eq!(res, ExecuteInputResult::Solution);
+        let solution = state.solutions().get(0).unwrap().borrow();
+        let reproducibility = solution
+            .metadata()
+            .get::<ReproducibilityMetadata>()
+            .unwrap();
+        assert_eq!(reproducibility.exit_kind, ExitKind::Crash);
+        assert_eq!(reproducibility.reproduced(), 1);
+    }
+
+    #[test]
+    fn test_objective_signal() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            tuple_list!(),
+        );
+        let reported = Rc::new(RefCell::new(Vec::new()));
+        let monitor_reported = reported.clone();
+        let mut mgr = SimpleEventManager::new(SimpleMonitor::new(move |line: String| {
+            monitor_reported.borrow_mut().push(line);
+        }));
+        let mut fuzzer: StdFuzzer<_, _, _, _, _, _> =
+            StdFuzzer::new(QueueCorpusScheduler::new(), (), CrashFeedback::new());
+        // As filled by a forking executor from the wait status of the child
+        let mut observers = tuple_list!(ExitStatusObserver::new());
+        observers.0.observe(Some(11), None, false);
+        let (res, _) = fuzzer
+            .process_execution(
+                &mut state,
+                &mut mgr,
+                BytesInput::new(vec![0; 4]),
+                &observers,
+                &ExitKind::Crash,
+                true,
+            )
+            .unwrap();
+        assert_eq!(res, ExecuteInputResult::Solution);
+        assert!(reported
+            .borrow()
+            .iter()
+            .any(|line| line.contains("exit kind: Crash, signal: 11")));
+    }
+
+    /// Exits as scripted, with the signal of each run in its [`ExitStatusObserver`], as a forking
+    /// executor fills it
+    #[derive(Debug)]
+    struct SignalExecutor {
+        runs: Vec<(ExitKind, Option<i32>)>,
+        observers: (ExitStatusObserver, ()),
+    }
+
+    impl<EM, S, Z> Executor<EM, BytesInput, S, Z> for SignalExecutor {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            _state: &mut S,
+            _mgr: &mut EM,
+            _input: &BytesInput,
+        ) -> Result<ExitKind, Error> {
+            let (exit_kind, signal) = self.runs.remove(0);
+            self.observers.0.observe(signal, None, false);
+            Ok(exit_kind)
+        }
+    }
+
+    impl<S> HasObservers<BytesInput, (ExitStatusObserver, ()), S> for SignalExecutor {
+        fn observers(&self) -> &(ExitStatusObserver, ()) {
+            &self.observers
+        }
+
+        fn observers_mut(&mut self) -> &mut (ExitStatusObserver, ()) {
+            &mut self.observers
+        }
+    }
+
+    #[test]
+    fn test_objective_signal_verified() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            tuple_list!(),
+        );
+        let reported = Rc::new(RefCell::new(Vec::new()));
+        let monitor_reported = reported.clone();
+        let mut mgr = SimpleEventManager::new(SimpleMonitor::new(move |line: String| {
+            monitor_reported.borrow_mut().push(line);
+        }));
+        let mut fuzzer: StdFuzzer<_, _, _, _, _, _> =
+            StdFuzzer::new(QueueCorpusScheduler::new(), (), CrashFeedback::new())
+                .with_objective_verification(ObjectiveVerification {
+                    runs: 2,
+                    discard_flaky: false,
+                });
+        // A flaky target, exiting differently in the verification runs
+        let mut executor = SignalExecutor {
+            runs: vec![
+                (ExitKind::Crash, Some(11)),
+                (ExitKind::Ok, None),
+                (ExitKind::Crash, Some(6)),
+            ],
+            observers: tuple_list!(ExitStatusObserver::new()),
+        };
+        let (res, _) = fuzzer
+            .evaluate_input_with_observers(
+                &mut state,
+                &mut executor,
+                &mut mgr,
+                BytesInput::new(vec![0; 4]),
+                true,
+            )
+            .unwrap();
+        assert_eq!(res, ExecuteInputResult::Solution);
+        // The signal of the run finding the objective, not of the last verification run
+        assert!(reported
+            .borrow()
+            .iter()
+            .any(|line| line.contains("exit kind: Crash, signal: 11")));
+        assert!(!reported
+            .borrow()
+            .iter()
+            .any(|line| line.contains("signal: 6")));
+    }
+}
@@ -80,6 +80,16 @@ pub trait Input: Clone + Serialize + serde::de::DeserializeOwned + Debug + Hash
     fn wrapped_as_testcase(&mut self) {}
 }
 
+/// A stable hash of the serialized `input`, the same in all the clients, builds and hosts, to tell
+/// inputs apart in the logs
+#[must_use]
+pub fn input_hash<I>(input: &I) -> u64
+where
+    I: Input,
+{
+    xxhash_rust::xxh3::xxh3_64(&postcard::to_allocvec(input).unwrap_or_default())
+}
+
 /// An input for tests, mainly. There is no real use much else.
 #[derive(Copy, Clone, Serialize, Deserialize, Debug, Hash)]
 pub struct NopInput {}
@@ -110,3 +120,17 @@ pub trait HasBytesVec {
     /// The internal bytes map (as mutable borrow)
     fn bytes_mut(&mut self) -> &mut Vec<u8>;
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::inputs::{input_hash, BytesInput};
+
+    #[test]
+    fn test_input_hash() {
+        // The hash must not change between versions, as it is shown in the logs
+        assert_eq!(
+            input_hash(&BytesInput::new(vec![1, 2, 3])),
+            12679547351699786381
+        );
+    }
+}
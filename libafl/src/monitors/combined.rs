@@ -0,0 +1,290 @@
+//! Monitors forwarding the stats to several monitors at once, for example to display them in the
+//! console while persisting them to disk.
+
+use alloc::{string::String, vec::Vec};
+use core::time::Duration;
+
+use crate::{
+    bolts::current_time,
+    events::LogSeverity,
+    executors::ExitKind,
+    monitors::{ClientStats, Monitor},
+};
+
+/// Copies the stats of the clients into a monitor, before it displays them
+fn sync_client_stats<M>(monitor: &mut M, client_stats: &[ClientStats])
+where
+    M: Monitor,
+{
+    let stats = monitor.client_stats_mut();
+    stats.clear();
+    stats.extend_from_slice(client_stats);
+}
+
+/// Forwards the stats to two monitors.
+/// The `first` monitor keeps the stats, and they get copied to the `second` before it displays them.
+/// Nest them to combine more monitors, or use a [`TeeMonitor`].
+#[derive(Clone, Debug)]
+pub struct CombinedMonitor<A, B>
+where
+    A: Monitor,
+    B: Monitor,
+{
+    first: A,
+    second: B,
+}
+
+impl<A, B> Monitor for CombinedMonitor<A, B>
+where
+    A: Monitor,
+    B: Monitor,
+{
+    /// the client monitor, mutable
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        self.first.client_stats_mut()
+    }
+
+    /// the client monitor
+    fn client_stats(&self) -> &[ClientStats] {
+        self.first.client_stats()
+    }
+
+    /// Time this fuzzing run stated
+    fn start_time(&mut self) -> Duration {
+        self.first.start_time()
+    }
+
+    fn display(&mut self, event_msg: String, sender_id: u32) {
+        sync_client_stats(&mut self.second, self.first.client_stats());
+        self.second.display(event_msg.clone(), sender_id);
+        self.first.display(event_msg, sender_id);
+    }
+
+    fn log(&mut self, severity_level: LogSeverity, message: &str, sender_id: u32) {
+        self.first.log(severity_level, message, sender_id);
+        self.second.log(severity_level, message, sender_id);
+    }
+
+    fn take_pause_request(&mut self) -> Option<bool> {
+        let first = self.first.take_pause_request();
+        let second = self.second.take_pause_request();
+        second.or(first)
+    }
+
+    fn on_objective(
+        &mut self,
+        sender_id: u32,
+        input_hash: u64,
+        exit_kind: ExitKind,
+        signal: Option<i32>,
+    ) {
+        self.first
+            .on_objective(sender_id, input_hash, exit_kind, signal);
+        self.second
+            .on_objective(sender_id, input_hash, exit_kind, signal);
+    }
+}
+
+impl<A, B> CombinedMonitor<A, B>
+where
+    A: Monitor,
+    B: Monitor,
+{
+    /// Creates a new [`CombinedMonitor`], forwarding to `first` and `second`
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+
+    /// The first monitor
+    pub fn first(&self) -> &A {
+        &self.first
+    }
+
+    /// The second monitor
+    pub fn second(&self) -> &B {
+        &self.second
+    }
+}
+
+/// A tuple list of [`Monitor`]s, see [`TeeMonitor`]
+pub trait MonitorsTuple {
+    /// Copies the stats of the clients into all the monitors, and displays them
+    fn display_all(&mut self, client_stats: &[ClientStats], event_msg: &str, sender_id: u32);
+
+    /// Logs a message in all the monitors
+    fn log_all(&mut self, severity_level: LogSeverity, message: &str, sender_id: u32);
+
+    /// Takes the pause requests of all the monitors, the last one wins
+    fn take_pause_request_all(&mut self) -> Option<bool>;
+
+    /// Reports an objective to all the monitors
+    fn on_objective_all(
+        &mut self,
+        sender_id: u32,
+        input_hash: u64,
+        exit_kind: ExitKind,
+        signal: Option<i32>,
+    );
+}
+
+impl MonitorsTuple for () {
+    fn display_all(&mut self, _client_stats: &[ClientStats], _event_msg: &str, _sender_id: u32) {}
+
+    fn log_all(&mut self, _severity_level: LogSeverity, _message: &str, _sender_id: u32) {}
+
+    fn take_pause_request_all(&mut self) -> Option<bool> {
+        None
+    }
+
+    fn on_objective_all(
+        &mut self,
+        _sender_id: u32,
+        _input_hash: u64,
+        _exit_kind: ExitKind,
+        _signal: Option<i32>,
+    ) {
+    }
+}
+
+impl<Head, Tail> MonitorsTuple for (Head, Tail)
+where
+    Head: Monitor,
+    Tail: MonitorsTuple,
+{
+    fn display_all(&mut self, client_stats: &[ClientStats], event_msg: &str, sender_id: u32) {
+        sync_client_stats(&mut self.0, client_stats);
+        self.0.display(event_msg.into(), sender_id);
+        self.1.display_all(client_stats, event_msg, sender_id);
+    }
+
+    fn log_all(&mut self, severity_level: LogSeverity, message: &str, sender_id: u32) {
+        self.0.log(severity_level, message, sender_id);
+        self.1.log_all(severity_level, message, sender_id);
+    }
+
+    fn take_pause_request_all(&mut self) -> Option<bool> {
+        let head = self.0.take_pause_request();
+        self.1.take_pause_request_all().or(head)
+    }
+
+    fn on_objective_all(
+        &mut self,
+        sender_id: u32,
+        input_hash: u64,
+        exit_kind: ExitKind,
+        signal: Option<i32>,
+    ) {
+        self.0
+            .on_objective(sender_id, input_hash, exit_kind, signal);
+        self.1
+            .on_objective_all(sender_id, input_hash, exit_kind, signal);
+    }
+}
+
+/// Forwards the stats to all the monitors of a tuple list, e.g.
+/// `TeeMonitor::new(tuple_list!(tui_monitor, json_monitor))`.
+/// It keeps the stats, and copies them to each monitor before it displays them.
+#[derive(Clone, Debug)]
+pub struct TeeMonitor<MT>
+where
+    MT: MonitorsTuple,
+{
+    monitors: MT,
+    start_time: Duration,
+    client_stats: Vec<ClientStats>,
+}
+
+impl<MT> Monitor for TeeMonitor<MT>
+where
+    MT: MonitorsTuple,
+{
+    /// the client monitor, mutable
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        &mut self.client_stats
+    }
+
+    /// the client monitor
+    fn client_stats(&self) -> &[ClientStats] {
+        &self.client_stats
+    }
+
+    /// Time this fuzzing run stated
+    fn start_time(&mut self) -> Duration {
+        self.start_time
+    }
+
+    fn display(&mut self, event_msg: String, sender_id: u32) {
+        self.monitors
+            .display_all(&self.client_stats, &event_msg, sender_id);
+    }
+
+    fn log(&mut self, severity_level: LogSeverity, message: &str, sender_id: u32) {
+        self.monitors.log_all(severity_level, message, sender_id);
+    }
+
+    fn take_pause_request(&mut self) -> Option<bool> {
+        self.monitors.take_pause_request_all()
+    }
+
+    fn on_objective(
+        &mut self,
+        sender_id: u32,
+        input_hash: u64,
+        exit_kind: ExitKind,
+        signal: Option<i32>,
+    ) {
+        self.monitors
+            .on_objective_all(sender_id, input_hash, exit_kind, signal);
+    }
+}
+
+impl<MT> TeeMonitor<MT>
+where
+    MT: MonitorsTuple,
+{
+    /// Creates a new [`TeeMonitor`], forwarding to the `monitors`
+    pub fn new(monitors: MT) -> Self {
+        Self::with_time(monitors, current_time())
+    }
+
+    /// Creates a new [`TeeMonitor`] with a given `start_time`
+    pub fn with_time(monitors: MT, start_time: Duration) -> Self {
+        Self {
+            monitors,
+            start_time,
+            client_stats: vec![],
+        }
+    }
+
+    /// The monitors
+    pub fn monitors(&self) -> &MT {
+        &self.monitors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CombinedMonitor, TeeMonitor};
+    use crate::{
+        bolts::tuples::tuple_list,
+        monitors::{Monitor, MultiMonitor, SimpleMonitor},
+    };
+
+    #[test]
+    fn test_combined_monitor() {
+        let mut monitor =
+            CombinedMonitor::new(SimpleMonitor::new(|_| {}), MultiMonitor::new(|_| {}));
+        monitor.client_stats_mut_for(1).corpus_size = 2;
+        monitor.display("Testcase".into(), 1);
+        assert_eq!(monitor.second().client_stats()[1].corpus_size, 2);
+
+        let mut monitor = TeeMonitor::new(tuple_list!(
+            SimpleMonitor::new(|_| {}),
+            MultiMonitor::new(|_| {})
+        ));
+        monitor.client_stats_mut_for(1).objective_size = 3;
+        monitor.display("Objective".into(), 1);
+        assert_eq!(monitor.monitors().0.client_stats()[1].objective_size, 3);
+        assert_eq!(monitor.monitors().1 .0.client_stats()[1].objective_size, 3);
+    }
+}
@@ -0,0 +1,467 @@
+//! Monitor appending the stats of the campaign to a file on disk, as TOML or JSON, rotating the
+//! file by size or age so that long campaigns do not fill the disk with a single file.
+
+use alloc::{string::String, vec::Vec};
+use core::{fmt::Write as _, time::Duration};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    bolts::current_time,
+    events::LogSeverity,
+    executors::ExitKind,
+    monitors::{
+        json::{client_json, global_json},
+        ClientStats, Monitor, UserStats,
+    },
+    Error,
+};
+
+/// The default interval between two records of the stats
+pub const ON_DISK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The default number of rotated files kept
+pub const ON_DISK_RETENTION: usize = 5;
+
+/// The format of the records of an [`OnDiskTOMLMonitor`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnDiskFormat {
+    /// One `[[stats]]` table per record, so that the whole file stays a valid TOML document
+    Toml,
+    /// One JSON object per line, with the global stats and the stats of the clients as written
+    /// by the [`crate::monitors::JsonMonitor`]
+    Json,
+}
+
+/// When a [`RotatingFile`] moves on to a new file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    /// Never, the file keeps growing
+    Never,
+    /// Before the file grows beyond this many bytes
+    Size(u64),
+    /// Once the file is older than this
+    Interval(Duration),
+}
+
+/// A file that gets appended to, and rotated according to a [`Rotation`] policy.
+/// On rotation, `path` becomes `path.1`, `path.1` becomes `path.2`, and so on, keeping at
+/// most `retention` rotated files.
+#[derive(Debug, Clone)]
+pub struct RotatingFile {
+    path: PathBuf,
+    rotation: Rotation,
+    retention: usize,
+    opened_at: Duration,
+}
+
+impl RotatingFile {
+    /// Creates a new [`RotatingFile`] appending to `path`, and never rotating it
+    pub fn new<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Self {
+            path,
+            rotation: Rotation::Never,
+            retention: ON_DISK_RETENTION,
+            opened_at: current_time(),
+        })
+    }
+
+    /// Sets when to rotate the file
+    #[must_use]
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Sets how many rotated files to keep, the older ones get deleted
+    #[must_use]
+    pub fn with_retention(mut self, retention: usize) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// The path of the current file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The path of the rotated file number `idx`, `1` being the newest one
+    #[must_use]
+    pub fn rotated_path(&self, idx: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", idx));
+        path.into()
+    }
+
+    /// Appends `data`, rotating the file first if the policy says so
+    pub fn append(&mut self, data: &[u8]) -> Result<(), Error> {
+        let len = fs::metadata(&self.path).map_or(0, |meta| meta.len());
+        let rotate = match self.rotation {
+            Rotation::Never => false,
+            Rotation::Size(max_size) => len > 0 && len + data.len() as u64 > max_size,
+            Rotation::Interval(interval) => {
+                len > 0
+                    && current_time()
+                        .checked_sub(self.opened_at)
+                        .unwrap_or_default()
+                        >= interval
+            }
+        };
+        if rotate {
+            self.rotate()?;
+        }
+        OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&self.path)?
+            .write_all(data)?;
+        Ok(())
+    }
+
+    /// Moves the current file to `path.1`, shifting the older ones, and starts a new file
+    pub fn rotate(&mut self) -> Result<(), Error> {
+        if self.retention == 0 {
+            File::create(&self.path)?;
+        } else {
+            let oldest = self.rotated_path(self.retention);
+            if oldest.exists() {
+                fs::remove_file(oldest)?;
+            }
+            for idx in (1..self.retention).rev() {
+                let rotated = self.rotated_path(idx);
+                if rotated.exists() {
+                    fs::rename(rotated, self.rotated_path(idx + 1))?;
+                }
+            }
+            if self.path.exists() {
+                fs::rename(&self.path, self.rotated_path(1))?;
+            }
+        }
+        self.opened_at = current_time();
+        Ok(())
+    }
+}
+
+/// Escapes a TOML basic string
+fn toml_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => write!(escaped, "\\u{:04X}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Formats a float as TOML, which requires a fractional part
+fn toml_float(value: f64) -> String {
+    if value.is_finite() {
+        format!("{:?}", value)
+    } else {
+        "nan".into()
+    }
+}
+
+/// Wraps a [`Monitor`], and periodically appends the global stats and the stats of each
+/// client, user stats included, to a file, as TOML by default.
+/// The file can be rotated by size or age with [`OnDiskTOMLMonitor::with_rotation`].
+#[derive(Debug, Clone)]
+pub struct OnDiskTOMLMonitor<M>
+where
+    M: Monitor,
+{
+    base: M,
+    file: RotatingFile,
+    format: OnDiskFormat,
+    interval: Duration,
+    last_update: Duration,
+}
+
+impl<M> Monitor for OnDiskTOMLMonitor<M>
+where
+    M: Monitor,
+{
+    /// the client monitor, mutable
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        self.base.client_stats_mut()
+    }
+
+    /// the client monitor
+    fn client_stats(&self) -> &[ClientStats] {
+        self.base.client_stats()
+    }
+
+    /// Time this fuzzing run stated
+    fn start_time(&mut self) -> Duration {
+        self.base.start_time()
+    }
+
+    fn log(&mut self, severity_level: LogSeverity, message: &str, sender_id: u32) {
+        self.base.log(severity_level, message, sender_id);
+    }
+
+    fn take_pause_request(&mut self) -> Option<bool> {
+        self.base.take_pause_request()
+    }
+
+    fn on_objective(
+        &mut self,
+        sender_id: u32,
+        input_hash: u64,
+        exit_kind: ExitKind,
+        signal: Option<i32>,
+    ) {
+        self.base
+            .on_objective(sender_id, input_hash, exit_kind, signal);
+    }
+
+    fn display(&mut self, event_msg: String, sender_id: u32) {
+        self.base.display(event_msg, sender_id);
+        if current_time()
+            .checked_sub(self.last_update)
+            .unwrap_or_default()
+            >= self.interval
+        {
+            if let Err(err) = self.update() {
+                println!("Could not write the stats to disk: {:?}", err);
+            }
+        }
+    }
+}
+
+impl<M> OnDiskTOMLMonitor<M>
+where
+    M: Monitor,
+{
+    /// Creates a new [`OnDiskTOMLMonitor`] appending to the file at `path`, and displaying
+    /// through `base`
+    pub fn new<P>(path: P, base: M) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self {
+            base,
+            file: RotatingFile::new(path)?,
+            format: OnDiskFormat::Toml,
+            interval: ON_DISK_INTERVAL,
+            last_update: Duration::from_secs(0),
+        })
+    }
+
+    /// Sets the format of the records
+    #[must_use]
+    pub fn with_format(mut self, format: OnDiskFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets the interval between two records
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets when to rotate the file, never by default
+    #[must_use]
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.file = self.file.with_rotation(rotation);
+        self
+    }
+
+    /// Sets how many rotated files to keep, [`ON_DISK_RETENTION`] by default
+    #[must_use]
+    pub fn with_retention(mut self, retention: usize) -> Self {
+        self.file = self.file.with_retention(retention);
+        self
+    }
+
+    /// The file the stats get appended to
+    pub fn file(&self) -> &RotatingFile {
+        &self.file
+    }
+
+    /// Appends a record of the current stats to the file
+    pub fn update(&mut self) -> Result<(), Error> {
+        let now = current_time();
+        let record = match self.format {
+            OnDiskFormat::Toml => self.toml_record(now),
+            OnDiskFormat::Json => {
+                let mut line = self.json_record(now).to_string();
+                line.push('\n');
+                line
+            }
+        };
+        self.file.append(record.as_bytes())?;
+        self.last_update = now;
+        Ok(())
+    }
+
+    /// The global stats
+    fn global_stats(&mut self, now: Duration) -> Vec<(&'static str, u64)> {
+        let run_time = now.checked_sub(self.base.start_time()).unwrap_or_default();
+        vec![
+            ("time", now.as_secs()),
+            ("run_time", run_time.as_secs()),
+            ("clients", self.base.client_stats().len() as u64),
+            ("corpus", self.base.corpus_size()),
+            ("objectives", self.base.objective_size()),
+            ("executions", self.base.total_execs()),
+            ("exec_sec", self.base.execs_per_sec()),
+        ]
+    }
+
+    /// The stats of the client with id `id`
+    fn client_stats_of(&mut self, id: usize, now: Duration) -> Vec<(&'static str, u64)> {
+        let client = &mut self.base.client_stats_mut()[id];
+        vec![
+            ("id", id as u64),
+            ("corpus", client.corpus_size),
+            ("objectives", client.objective_size),
+            ("executions", client.executions),
+            ("exec_sec", client.execs_per_sec(now)),
+        ]
+    }
+
+    /// A `[[stats]]` table, with the clients as `[[stats.client]]` tables
+    fn toml_record(&mut self, now: Duration) -> String {
+        let mut out = String::from("[[stats]]\n");
+        for (key, value) in self.global_stats(now) {
+            writeln!(out, "{} = {}", key, value).unwrap();
+        }
+        if let Some(stability) = self.base.stability() {
+            writeln!(out, "stability = {}", toml_float(f64::from(stability))).unwrap();
+        }
+
+        for id in 0..self.base.client_stats().len() {
+            out.push_str("\n[[stats.client]]\n");
+            for (key, value) in self.client_stats_of(id, now) {
+                writeln!(out, "{} = {}", key, value).unwrap();
+            }
+            let client = &self.base.client_stats()[id];
+            if let Some(stability) = client.stability {
+                writeln!(out, "stability = {}", toml_float(f64::from(stability))).unwrap();
+            }
+            if !client.user_monitor.is_empty() {
+                out.push_str("\n[stats.client.user_stats]\n");
+                for (name, stat) in &client.user_monitor {
+                    let value = match stat {
+                        UserStats::Number(n) => n.to_string(),
+                        UserStats::Float(n) => toml_float(*n),
+                        UserStats::Ratio(..) | UserStats::String(_) => {
+                            toml_string(&stat.to_string())
+                        }
+                    };
+                    writeln!(out, "{} = {}", toml_string(name), value).unwrap();
+                }
+            }
+        }
+        out.push('\n');
+        out
+    }
+
+    /// A JSON object, with the clients in the `client` array
+    fn json_record(&mut self, now: Duration) -> Value {
+        let run_time = now.checked_sub(self.base.start_time()).unwrap_or_default();
+        let mut record = global_json(&mut self.base);
+        record.insert("time".into(), json!(now.as_secs()));
+        record.insert("run_time".into(), json!(run_time.as_secs()));
+        let clients = self
+            .base
+            .client_stats_mut()
+            .iter_mut()
+            .enumerate()
+            .map(|(id, client)| Value::Object(client_json(client, id as u32, now)))
+            .collect();
+        record.insert("client".into(), Value::Array(clients));
+        Value::Object(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+    use std::fs;
+
+    use super::{OnDiskFormat, OnDiskTOMLMonitor, RotatingFile, Rotation};
+    use crate::monitors::{Monitor, SimpleMonitor, UserStats};
+
+    #[test]
+    fn test_rotating_file() {
+        let dir =
+            std::env::temp_dir().join(format!("libafl_test_rotating_file_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut file = RotatingFile::new(dir.join("stats"))
+            .unwrap()
+            .with_rotation(Rotation::Size(8))
+            .with_retention(2);
+        for data in ["aaaa\n", "bbbb\n", "cccc\n", "dddd\n"] {
+            file.append(data.as_bytes()).unwrap();
+        }
+        assert_eq!(fs::read_to_string(file.path()).unwrap(), "dddd\n");
+        assert_eq!(fs::read_to_string(file.rotated_path(1)).unwrap(), "cccc\n");
+        assert_eq!(fs::read_to_string(file.rotated_path(2)).unwrap(), "bbbb\n");
+        assert!(!file.rotated_path(3).exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_on_disk_monitor() {
+        let dir = std::env::temp_dir().join(format!(
+            "libafl_test_on_disk_monitor_{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut monitor =
+            OnDiskTOMLMonitor::new(dir.join("stats.toml"), SimpleMonitor::new(|_| {}))
+                .unwrap()
+                .with_interval(Duration::from_secs(0));
+        let client = monitor.client_stats_mut_for(1);
+        client.corpus_size = 12;
+        client.update_user_stats("edges".into(), UserStats::Ratio(25, 100));
+        monitor.display("Testcase".into(), 1);
+        let toml = fs::read_to_string(monitor.file().path()).unwrap();
+        assert!(toml.starts_with("[[stats]]\ntime = "));
+        assert!(toml.contains("\n[[stats.client]]\nid = 1\ncorpus = 12\n"));
+        assert!(toml.contains("\n[stats.client.user_stats]\n\"edges\" = \"25/100 (25.0%)\"\n"));
+
+        let mut monitor =
+            OnDiskTOMLMonitor::new(dir.join("stats.json"), SimpleMonitor::new(|_| {}))
+                .unwrap()
+                .with_format(OnDiskFormat::Json);
+        let client = monitor.client_stats_mut_for(1);
+        client.objective_size = 3;
+        client.stability = Some(f32::NAN);
+        monitor.display("Objective".into(), 1);
+        let json = fs::read_to_string(monitor.file().path()).unwrap();
+        let record: serde_json::Value = serde_json::from_str(json.trim_end()).unwrap();
+        assert_eq!(record["objectives"], 3);
+        assert_eq!(record["client"][1]["objectives"], 3);
+        // No NaN in JSON
+        assert!(record["client"][1]["stability"].is_null());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
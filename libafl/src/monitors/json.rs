@@ -0,0 +1,233 @@
+//! Monitor streaming the stats as JSON lines, to post-process campaigns with external tooling.
+
+use alloc::{string::String, vec::Vec};
+use core::{
+    fmt::{self, Debug, Formatter},
+    time::Duration,
+};
+use std::{fs::OpenOptions, io::Write, net::TcpStream, path::PathBuf};
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    bolts::current_time,
+    monitors::{ClientStats, Monitor, UserStats},
+};
+
+/// Where a [`JsonMonitor`] writes to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonOutput {
+    /// Appends to a file
+    File(PathBuf),
+    /// Sends to a TCP socket, e.g. `127.0.0.1:5000`, reconnecting if the connection drops
+    Socket(String),
+}
+
+/// Appends one JSON object per line for each update of the stats, holding the timestamp, the
+/// event, the global stats, and the stats of the client that sent the update, user stats
+/// included.
+pub struct JsonMonitor {
+    output: JsonOutput,
+    socket: Option<TcpStream>,
+    start_time: Duration,
+    client_stats: Vec<ClientStats>,
+}
+
+impl Debug for JsonMonitor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonMonitor")
+            .field("output", &self.output)
+            .field("start_time", &self.start_time)
+            .field("client_stats", &self.client_stats)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Clone for JsonMonitor {
+    /// The clone opens its own connection
+    fn clone(&self) -> Self {
+        Self {
+            output: self.output.clone(),
+            socket: None,
+            start_time: self.start_time,
+            client_stats: self.client_stats.clone(),
+        }
+    }
+}
+
+impl Monitor for JsonMonitor {
+    /// the client monitor, mutable
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        &mut self.client_stats
+    }
+
+    /// the client monitor
+    fn client_stats(&self) -> &[ClientStats] {
+        &self.client_stats
+    }
+
+    /// Time this fuzzing run stated
+    fn start_time(&mut self) -> Duration {
+        self.start_time
+    }
+
+    fn display(&mut self, event_msg: String, sender_id: u32) {
+        let mut line = self.record(&event_msg, sender_id).to_string();
+        line.push('\n');
+        if let Err(err) = self.write(line.as_bytes()) {
+            println!("Could not write the JSON stats: {:?}", err);
+        }
+    }
+}
+
+impl JsonMonitor {
+    /// Creates the monitor, writing to `output`, using the `current_time` as `start_time`.
+    #[must_use]
+    pub fn new(output: JsonOutput) -> Self {
+        Self::with_time(output, current_time())
+    }
+
+    /// Creates the monitor, appending to the file at `path`
+    #[must_use]
+    pub fn with_file<P>(path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self::new(JsonOutput::File(path.into()))
+    }
+
+    /// Creates the monitor with a given `start_time`.
+    #[must_use]
+    pub fn with_time(output: JsonOutput, start_time: Duration) -> Self {
+        Self {
+            output,
+            socket: None,
+            start_time,
+            client_stats: vec![],
+        }
+    }
+
+    /// The JSON object of an update
+    fn record(&mut self, event_msg: &str, sender_id: u32) -> Value {
+        let now = current_time();
+        let global = global_json(self);
+        let client = client_json(self.client_stats_mut_for(sender_id), sender_id, now);
+
+        json!({
+            "timestamp": now.as_secs_f64(),
+            "run_time": now.checked_sub(self.start_time).unwrap_or_default().as_secs(),
+            "event": event_msg,
+            "global": global,
+            "client": client,
+        })
+    }
+
+    /// Writes a line to the output
+    fn write(&mut self, line: &[u8]) -> std::io::Result<()> {
+        match &self.output {
+            JsonOutput::File(path) => OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(path)?
+                .write_all(line),
+            JsonOutput::Socket(addr) => {
+                if self.socket.is_none() {
+                    self.socket = Some(TcpStream::connect(addr)?);
+                }
+                let res = self.socket.as_mut().unwrap().write_all(line);
+                if res.is_err() {
+                    // Reconnect on the next update
+                    self.socket = None;
+                }
+                res
+            }
+        }
+    }
+}
+
+/// A float as JSON, `null` if not finite, as JSON has no `NaN` or infinity
+pub(crate) fn json_float(value: f64) -> Value {
+    if value.is_finite() {
+        json!(value)
+    } else {
+        Value::Null
+    }
+}
+
+/// The global stats of `monitor`, as written by the JSON monitors
+pub(crate) fn global_json<M>(monitor: &mut M) -> Map<String, Value>
+where
+    M: Monitor + ?Sized,
+{
+    let mut global = Map::new();
+    global.insert("clients".into(), json!(monitor.client_stats().len()));
+    global.insert("corpus".into(), json!(monitor.corpus_size()));
+    global.insert("objectives".into(), json!(monitor.objective_size()));
+    global.insert("executions".into(), json!(monitor.total_execs()));
+    global.insert("exec_sec".into(), json!(monitor.execs_per_sec()));
+    if let Some(stability) = monitor.stability() {
+        global.insert("stability".into(), json_float(f64::from(stability)));
+    }
+    global
+}
+
+/// The stats of the client with id `id`, user stats included, as written by the JSON monitors
+pub(crate) fn client_json(client: &mut ClientStats, id: u32, now: Duration) -> Map<String, Value> {
+    let mut user_stats = Map::new();
+    for (name, stat) in &client.user_monitor {
+        let value = match stat {
+            UserStats::Number(n) => json!(n),
+            UserStats::Float(n) => json_float(*n),
+            UserStats::String(s) => json!(s),
+            UserStats::Ratio(a, b) => json!([a, b]),
+        };
+        user_stats.insert(name.clone(), value);
+    }
+    let mut stats = Map::new();
+    stats.insert("id".into(), json!(id));
+    stats.insert("corpus".into(), json!(client.corpus_size));
+    stats.insert("objectives".into(), json!(client.objective_size));
+    stats.insert("executions".into(), json!(client.executions));
+    stats.insert("exec_sec".into(), json!(client.execs_per_sec(now)));
+    if let Some(stability) = client.stability {
+        stats.insert("stability".into(), json_float(f64::from(stability)));
+    }
+    stats.insert("user_stats".into(), Value::Object(user_stats));
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::JsonMonitor;
+    use crate::monitors::{Monitor, UserStats};
+
+    #[test]
+    fn test_json_monitor() {
+        let path = std::env::temp_dir().join(format!(
+            "libafl_test_json_monitor_{}.jsonl",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let mut monitor = JsonMonitor::with_file(&path);
+        let client = monitor.client_stats_mut_for(1);
+        client.corpus_size = 3;
+        client.update_user_stats("edges".into(), UserStats::Ratio(1, 4));
+        client.update_user_stats("ratio".into(), UserStats::Float(f64::NAN));
+        monitor.display("Testcase".into(), 1);
+        monitor.display("Objective".into(), 1);
+
+        let lines = fs::read_to_string(&path).unwrap();
+        let records: Vec<serde_json::Value> = lines
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1]["event"], "Objective");
+        assert_eq!(records[0]["global"]["corpus"], 3);
+        assert_eq!(records[0]["client"]["user_stats"]["edges"][1], 4);
+        assert!(records[0]["client"]["user_stats"]["ratio"].is_null());
+        fs::remove_file(path).unwrap();
+    }
+}
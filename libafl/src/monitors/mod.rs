@@ -3,6 +3,51 @@
 pub mod multi;
 pub use multi::MultiMonitor;
 
+pub mod combined;
+pub use combined::{CombinedMonitor, MonitorsTuple, TeeMonitor};
+
+#[cfg(feature = "std")]
+pub mod stats_export;
+#[cfg(feature = "std")]
+pub use stats_export::StatsExportMonitor;
+
+#[cfg(feature = "std")]
+pub mod plot;
+#[cfg(feature = "std")]
+pub use plot::PlotFileMonitor;
+
+#[cfg(feature = "notification_monitor")]
+pub mod notification;
+#[cfg(feature = "notification_monitor")]
+pub use notification::{NotificationMonitor, WebhookFormat};
+
+#[cfg(feature = "std")]
+pub mod disk;
+#[cfg(feature = "std")]
+pub use disk::{OnDiskFormat, OnDiskTOMLMonitor, RotatingFile, Rotation};
+
+#[cfg(feature = "std")]
+pub mod prometheus;
+#[cfg(feature = "std")]
+pub use prometheus::PrometheusMonitor;
+
+#[cfg(feature = "std")]
+pub mod timeseries;
+#[cfg(feature = "influxdb_http")]
+pub use timeseries::InfluxHttpBackend;
+#[cfg(feature = "std")]
+pub use timeseries::{GraphiteBackend, InfluxUdpBackend, TimeseriesBackend, TimeseriesMonitor};
+
+#[cfg(feature = "std")]
+pub mod remote;
+#[cfg(feature = "std")]
+pub use remote::{RemoteAggregator, RemoteMonitor};
+
+#[cfg(feature = "std")]
+pub mod json;
+#[cfg(feature = "std")]
+pub use json::{JsonMonitor, JsonOutput};
+
 #[cfg(all(feature = "tui_monitor", feature = "std"))]
 #[allow(missing_docs)]
 pub mod tui;
@@ -11,15 +56,43 @@ use alloc::{string::String, vec::Vec};
 
 #[cfg(feature = "introspection")]
 use alloc::string::ToString;
+#[cfg(feature = "introspection")]
+use core::fmt::Write as _;
 
 use core::{fmt, time::Duration};
 use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
 
-use crate::bolts::{current_time, format_duration_hms};
+use crate::{
+    bolts::{current_time, format_duration_hms},
+    events::LogSeverity,
+    executors::ExitKind,
+};
 
 const CLIENT_STATS_TIME_WINDOW_SECS: u64 = 5; // 5 seconds
 
+/// The name of the standard user stat holding the discovered edges and the size of the coverage
+/// map, as [`UserStats::Ratio`]. The [`crate::feedbacks::MapFeedback`] reports it under the name
+/// of its feedback state, which is the name of the observer, usually `edges`.
+pub const EDGES_USER_STAT: &str = "edges";
+
+/// Formats an objective reported to [`Monitor::on_objective`], to print it
+pub(crate) fn format_objective(
+    sender_id: u32,
+    input_hash: u64,
+    exit_kind: ExitKind,
+    signal: Option<i32>,
+) -> String {
+    let mut fmt = format!(
+        "[Objective #{}] input: {:016x}, exit kind: {:?}",
+        sender_id, input_hash, exit_kind
+    );
+    if let Some(signal) = signal {
+        fmt += &format!(", signal: {}", signal);
+    }
+    fmt
+}
+
 /// User-defined stat types
 /// TODO define aggregation function (avg, median, max, ...)
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -35,6 +108,7 @@ pub enum UserStats {
 }
 
 impl fmt::Display for UserStats {
+    #[allow(clippy::cast_precision_loss)]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             UserStats::Number(n) => write!(f, "{}", n),
@@ -44,15 +118,47 @@ impl fmt::Display for UserStats {
                 if *b == 0 {
                     write!(f, "{}/{}", a, b)
                 } else {
-                    write!(f, "{}/{} ({}%)", a, b, a * 100 / b)
+                    write!(f, "{}/{} ({:.1}%)", a, b, (*a as f64) * 100.0 / (*b as f64))
                 }
             }
         }
     }
 }
 
+/// A downsampled coverage map, holding the ratio of covered entries of each bucket of
+/// consecutive entries, to draw heatmaps
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoverageSummary {
+    /// The number of entries of the map
+    pub map_len: usize,
+    /// The ratio of covered entries of each bucket, scaled to `0..=255`
+    pub buckets: Vec<u8>,
+}
+
+impl CoverageSummary {
+    /// Summarizes `map` in at most `buckets` buckets, an entry is covered if it is not the default
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn from_map<T>(map: &[T], buckets: usize) -> Self
+    where
+        T: Default + PartialEq,
+    {
+        let map_len = map.len();
+        let count = buckets.min(map_len);
+        let empty = T::default();
+        let buckets = (0..count)
+            .map(|i| {
+                let bucket = &map[i * map_len / count..(i + 1) * map_len / count];
+                let covered = bucket.iter().filter(|x| **x != empty).count();
+                (covered * 255 / bucket.len()) as u8
+            })
+            .collect();
+        Self { map_len, buckets }
+    }
+}
+
 /// A simple struct to keep track of client monitor
-#[derive(Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct ClientStats {
     // monitor (maybe we need a separated struct?)
     /// The corpus size for this client
@@ -67,6 +173,10 @@ pub struct ClientStats {
     pub last_window_time: Duration,
     /// The last executions per sec
     pub last_execs_per_sec: f32,
+    /// The ratio of the covered map entries behaving deterministically, if measured
+    pub stability: Option<f32>,
+    /// The last coverage summary, if the client sends them
+    pub coverage_summary: Option<CoverageSummary>,
     /// User-defined monitor
     pub user_monitor: HashMap<String, UserStats>,
     /// Client performance statistics
@@ -128,6 +238,16 @@ impl ClientStats {
         self.last_execs_per_sec as u64
     }
 
+    /// We got a new information about the stability of this client, insert it.
+    pub fn update_stability(&mut self, stability: f32) {
+        self.stability = Some(stability);
+    }
+
+    /// We got a new coverage summary for this client, insert it.
+    pub fn update_coverage_summary(&mut self, coverage_summary: CoverageSummary) {
+        self.coverage_summary = Some(coverage_summary);
+    }
+
     /// Update the user-defined stat with name and value
     pub fn update_user_stats(&mut self, name: String, value: UserStats) {
         self.user_monitor.insert(name, value);
@@ -138,6 +258,16 @@ impl ClientStats {
         self.user_monitor.get(name)
     }
 
+    /// The discovered edges and the size of the coverage map, from the [`EDGES_USER_STAT`].
+    /// Other ratios, such as the ones of the cmp maps, are not edges and get ignored.
+    #[must_use]
+    pub fn edges(&self) -> Option<(u64, u64)> {
+        match self.user_monitor.get(EDGES_USER_STAT) {
+            Some(UserStats::Ratio(covered, total)) => Some((*covered, *total)),
+            _ => None,
+        }
+    }
+
     /// Update the current [`ClientPerfMonitor`] with the given [`ClientPerfMonitor`]
     #[cfg(feature = "introspection")]
     pub fn update_introspection_monitor(&mut self, introspection_monitor: ClientPerfMonitor) {
@@ -159,6 +289,13 @@ pub trait Monitor {
     /// show the monitor to the user
     fn display(&mut self, event_msg: String, sender_id: u32);
 
+    /// Show a log message sent by a client, printed to `stdout` by default
+    #[allow(unused_variables)]
+    fn log(&mut self, severity_level: LogSeverity, message: &str, sender_id: u32) {
+        #[cfg(feature = "std")]
+        println!("[LOG {} #{}]: {}", severity_level, sender_id, message);
+    }
+
     /// Amount of elements in the corpus (combined for all children)
     fn corpus_size(&self) -> u64 {
         self.client_stats()
@@ -190,6 +327,49 @@ pub trait Monitor {
             .fold(0_u64, |acc, x| acc + x.execs_per_sec(cur_time))
     }
 
+    /// The stability, averaged over the clients that measured it
+    #[allow(clippy::cast_precision_loss)]
+    fn stability(&self) -> Option<f32> {
+        let (sum, count) = self
+            .client_stats()
+            .iter()
+            .filter_map(|x| x.stability)
+            .fold((0.0, 0_usize), |(sum, count), x| (sum + x, count + 1));
+        if count == 0 {
+            None
+        } else {
+            Some(sum / count as f32)
+        }
+    }
+
+    /// The discovered edges and the size of the coverage map, the highest among the clients,
+    /// see [`ClientStats::edges`]
+    fn edges(&self) -> Option<(u64, u64)> {
+        self.client_stats()
+            .iter()
+            .filter_map(ClientStats::edges)
+            .max()
+    }
+
+    /// Takes the pending request to pause (`true`) or resume (`false`) the clients, if any.
+    /// The broker polls it and forwards the request to the clients as [`crate::events::Event::Pause`].
+    fn take_pause_request(&mut self) -> Option<bool> {
+        None
+    }
+
+    /// Called for each objective found by a client, after its stats got displayed, with the hash
+    /// of the input (see [`crate::inputs::input_hash`]), how its execution finished, and the
+    /// signal the target crashed with, if known. Does nothing by default.
+    #[allow(unused_variables)]
+    fn on_objective(
+        &mut self,
+        sender_id: u32,
+        input_hash: u64,
+        exit_kind: ExitKind,
+        signal: Option<i32>,
+    ) {
+    }
+
     /// The client monitor for a specific id, creating new if it doesn't exist
     fn client_stats_mut_for(&mut self, client_id: u32) -> &mut ClientStats {
         let client_stat_count = self.client_stats().len();
@@ -278,7 +458,7 @@ where
     }
 
     fn display(&mut self, event_msg: String, sender_id: u32) {
-        let fmt = format!(
+        let mut fmt = format!(
             "[{} #{}] run time: {}, clients: {}, corpus: {}, objectives: {}, executions: {}, exec/sec: {}",
             event_msg,
             sender_id,
@@ -289,6 +469,12 @@ where
             self.total_execs(),
             self.execs_per_sec()
         );
+        if let Some(stability) = self.stability() {
+            fmt += &format!(", stability: {:.2}%", stability * 100.0);
+        }
+        if let Some((covered, total)) = self.edges() {
+            fmt += &format!(", edges: {}", UserStats::Ratio(covered, total));
+        }
         (self.print_fn)(fmt);
 
         // Only print perf monitor if the feature is enabled
@@ -305,6 +491,16 @@ where
             (self.print_fn)("".to_string());
         }
     }
+
+    fn on_objective(
+        &mut self,
+        sender_id: u32,
+        input_hash: u64,
+        exit_kind: ExitKind,
+        signal: Option<i32>,
+    ) {
+        (self.print_fn)(format_objective(sender_id, input_hash, exit_kind, signal));
+    }
 }
 
 impl<F> SimpleMonitor<F>
@@ -702,6 +898,45 @@ impl ClientPerfMonitor {
     pub fn feedbacks(&self) -> &HashMap<String, u64> {
         &self.feedbacks
     }
+
+    /// The cycles spent in the scheduler, the manager, each feature of each stage, and each
+    /// feedback, in the folded stacks format rendered by `flamegraph.pl` or `inferno`.
+    /// Each line holds the frames below `root` separated by `;`, then the cycles spent there.
+    #[must_use]
+    pub fn folded_stacks(&self, root: &str) -> String {
+        let mut lines = vec![
+            ("Scheduler".to_string(), self.scheduler),
+            ("Manager".to_string(), self.manager),
+        ];
+        for (stage_index, features) in self.used_stages() {
+            for (feature_index, cycles) in features.iter().enumerate() {
+                let feature: PerfFeature = feature_index.into();
+                lines.push((format!("Stage {};{:?}", stage_index, feature), *cycles));
+            }
+        }
+        let mut feedbacks: Vec<_> = self.feedbacks.iter().collect();
+        feedbacks.sort();
+        for (feedback_name, feedback_time) in feedbacks {
+            // `;` separates the frames
+            lines.push((
+                format!("Feedbacks;{}", feedback_name.replace(';', ":")),
+                *feedback_time,
+            ));
+        }
+        let measured: u64 = lines.iter().map(|(_, cycles)| cycles).sum();
+        lines.push((
+            "Not Measured".to_string(),
+            self.elapsed_cycles().saturating_sub(measured),
+        ));
+
+        let mut folded = String::new();
+        for (stack, cycles) in lines {
+            if cycles > 0 {
+                writeln!(folded, "{};{} {}", root, stack, cycles).unwrap();
+            }
+        }
+        folded
+    }
 }
 
 #[cfg(feature = "introspection")]
@@ -784,3 +1019,78 @@ impl Default for ClientPerfMonitor {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    #[cfg(feature = "introspection")]
+    use super::{ClientPerfMonitor, PerfFeature};
+    use super::{CoverageSummary, Monitor, SimpleMonitor, UserStats, EDGES_USER_STAT};
+    use crate::executors::ExitKind;
+
+    #[test]
+    fn test_coverage_summary() {
+        let mut map = [0_u8; 10];
+        map[0] = 1;
+        map[1] = 3;
+        map[9] = 1;
+        let summary = CoverageSummary::from_map(&map, 2);
+        assert_eq!(summary.map_len, 10);
+        assert_eq!(summary.buckets, [102, 51]);
+        assert_eq!(CoverageSummary::from_map(&map[..1], 4).buckets, [255]);
+    }
+
+    #[test]
+    fn test_edges() {
+        let mut monitor = SimpleMonitor::new(|_| {});
+        monitor
+            .client_stats_mut_for(1)
+            .update_user_stats("cmps".into(), UserStats::Ratio(9, 10));
+        monitor
+            .client_stats_mut_for(1)
+            .update_user_stats(EDGES_USER_STAT.into(), UserStats::Number(7));
+        assert_eq!(monitor.edges(), None);
+        monitor
+            .client_stats_mut_for(2)
+            .update_user_stats(EDGES_USER_STAT.into(), UserStats::Ratio(1234, 65536));
+        monitor
+            .client_stats_mut_for(1)
+            .update_user_stats(EDGES_USER_STAT.into(), UserStats::Ratio(1000, 65536));
+        assert_eq!(monitor.edges(), Some((1234, 65536)));
+        assert_eq!(
+            UserStats::Ratio(1234, 65536).to_string(),
+            "1234/65536 (1.9%)"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "introspection")]
+    fn test_folded_stacks() {
+        let mut monitor = ClientPerfMonitor::new();
+        monitor.set_current_time(monitor.start_time + 100);
+        monitor.update_scheduler(10);
+        monitor.update_feature(PerfFeature::Mutate, 20);
+        monitor.update_feedback("map;edges", 30);
+        assert_eq!(
+            monitor.folded_stacks("fuzzer"),
+            "fuzzer;Scheduler 10\nfuzzer;Stage 0;Mutate 20\nfuzzer;Feedbacks;map:edges 30\nfuzzer;Not Measured 40\n"
+        );
+    }
+
+    #[test]
+    fn test_on_objective() {
+        let mut lines = vec![];
+        let mut monitor = SimpleMonitor::new(|line| lines.push(line));
+        monitor.on_objective(1, 0xab, ExitKind::Crash, Some(11));
+        monitor.on_objective(2, 0xcd, ExitKind::Timeout, None);
+        drop(monitor);
+        assert_eq!(
+            lines,
+            [
+                "[Objective #1] input: 00000000000000ab, exit kind: Crash, signal: 11",
+                "[Objective #2] input: 00000000000000cd, exit kind: Timeout",
+            ]
+        );
+    }
+}
@@ -8,7 +8,8 @@ use alloc::string::ToString;
 
 use crate::{
     bolts::{current_time, format_duration_hms},
-    monitors::{ClientStats, Monitor},
+    executors::ExitKind,
+    monitors::{format_objective, ClientStats, Monitor, UserStats},
 };
 
 /// Tracking monitor during fuzzing and display both per-client and cumulative info.
@@ -49,7 +50,7 @@ where
             String::new()
         };
         let head = format!("{}{} {}", event_msg, pad, sender);
-        let global_fmt = format!(
+        let mut global_fmt = format!(
             "[{}]  (GLOBAL) run time: {}, clients: {}, corpus: {}, objectives: {}, executions: {}, exec/sec: {}",
             head,
             format_duration_hms(&(current_time() - self.start_time)),
@@ -59,6 +60,12 @@ where
             self.total_execs(),
             self.execs_per_sec()
         );
+        if let Some(stability) = self.stability() {
+            global_fmt += &format!(", stability: {:.2}%", stability * 100.0);
+        }
+        if let Some((covered, total)) = self.edges() {
+            global_fmt += &format!(", edges: {}", UserStats::Ratio(covered, total));
+        }
         (self.print_fn)(global_fmt);
 
         let client = self.client_stats_mut_for(sender_id);
@@ -70,6 +77,9 @@ where
             " {}   (CLIENT) corpus: {}, objectives: {}, executions: {}, exec/sec: {}",
             pad, client.corpus_size, client.objective_size, client.executions, exec_sec
         );
+        if let Some(stability) = client.stability {
+            fmt += &format!(", stability: {:.2}%", stability * 100.0);
+        }
         for (key, val) in &client.user_monitor {
             fmt += &format!(", {}: {}", key, val);
         }
@@ -88,6 +98,16 @@ where
             (self.print_fn)("\n".to_string());
         }
     }
+
+    fn on_objective(
+        &mut self,
+        sender_id: u32,
+        input_hash: u64,
+        exit_kind: ExitKind,
+        signal: Option<i32>,
+    ) {
+        (self.print_fn)(format_objective(sender_id, input_hash, exit_kind, signal));
+    }
 }
 
 impl<F> MultiMonitor<F>
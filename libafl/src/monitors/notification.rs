@@ -0,0 +1,246 @@
+//! Monitor posting notifications to a webhook, for example of Slack, Discord or Teams, when the
+//! fuzzer finds new objectives or slows down, so that long campaigns need no babysitting.
+
+use alloc::{string::String, vec::Vec};
+use core::time::Duration;
+use std::thread;
+
+use serde_json::{json, Value};
+
+use crate::{
+    bolts::current_time,
+    events::LogSeverity,
+    executors::ExitKind,
+    monitors::{ClientStats, Monitor},
+};
+
+/// The time after the start of the campaign during which slowdowns are not notified, as the
+/// speed is not meaningful yet
+pub const NOTIFICATION_WARMUP: Duration = Duration::from_secs(60);
+
+/// The payload format of a webhook
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookFormat {
+    /// Slack incoming webhooks, `{"text": ...}`
+    Slack,
+    /// Discord webhooks, `{"content": ...}`
+    Discord,
+    /// Microsoft Teams incoming webhooks, as a `MessageCard`
+    Teams,
+    /// A plain JSON object with the message and the stats, for custom receivers
+    Json,
+}
+
+impl WebhookFormat {
+    /// The payload posting `message`
+    #[must_use]
+    pub fn payload(self, title: &str, message: &str, stats: &Value) -> Value {
+        let text = format!("[{}] {}", title, message);
+        match self {
+            WebhookFormat::Slack => json!({ "text": text }),
+            WebhookFormat::Discord => json!({ "content": text }),
+            WebhookFormat::Teams => json!({
+                "@type": "MessageCard",
+                "@context": "https://schema.org/extensions",
+                "summary": title,
+                "title": title,
+                "text": message,
+            }),
+            WebhookFormat::Json => json!({
+                "title": title,
+                "message": message,
+                "stats": stats,
+            }),
+        }
+    }
+}
+
+/// Wraps a [`Monitor`], and posts to a webhook whenever the number of objectives increases, or
+/// whenever the executions per second drop below a threshold, if set.
+///
+/// The notifications are posted from a background thread, not to slow down the broker.
+#[derive(Debug, Clone)]
+pub struct NotificationMonitor<M>
+where
+    M: Monitor,
+{
+    base: M,
+    url: String,
+    format: WebhookFormat,
+    title: String,
+    min_execs_per_sec: Option<u64>,
+    last_objectives: u64,
+    /// Whether the slowdown has been notified already, so that it gets notified once
+    slow: bool,
+}
+
+impl<M> Monitor for NotificationMonitor<M>
+where
+    M: Monitor,
+{
+    /// the client monitor, mutable
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        self.base.client_stats_mut()
+    }
+
+    /// the client monitor
+    fn client_stats(&self) -> &[ClientStats] {
+        self.base.client_stats()
+    }
+
+    /// Time this fuzzing run stated
+    fn start_time(&mut self) -> Duration {
+        self.base.start_time()
+    }
+
+    fn log(&mut self, severity_level: LogSeverity, message: &str, sender_id: u32) {
+        self.base.log(severity_level, message, sender_id);
+    }
+
+    fn take_pause_request(&mut self) -> Option<bool> {
+        self.base.take_pause_request()
+    }
+
+    fn on_objective(
+        &mut self,
+        sender_id: u32,
+        input_hash: u64,
+        exit_kind: ExitKind,
+        signal: Option<i32>,
+    ) {
+        self.base
+            .on_objective(sender_id, input_hash, exit_kind, signal);
+    }
+
+    fn display(&mut self, event_msg: String, sender_id: u32) {
+        self.base.display(event_msg, sender_id);
+
+        let objectives = self.base.objective_size();
+        if objectives > self.last_objectives {
+            let message = format!(
+                "{} new objective(s) found by client #{}, {} in total",
+                objectives - self.last_objectives,
+                sender_id,
+                objectives
+            );
+            self.last_objectives = objectives;
+            self.notify(&message);
+        }
+
+        if let Some(min_execs_per_sec) = self.min_execs_per_sec {
+            let run_time = current_time()
+                .checked_sub(self.base.start_time())
+                .unwrap_or_default();
+            let execs_per_sec = self.base.execs_per_sec();
+            if execs_per_sec >= min_execs_per_sec {
+                self.slow = false;
+            } else if !self.slow && run_time >= NOTIFICATION_WARMUP {
+                self.slow = true;
+                let message = format!(
+                    "the speed dropped to {} exec/sec, below {} exec/sec",
+                    execs_per_sec, min_execs_per_sec
+                );
+                self.notify(&message);
+            }
+        }
+    }
+}
+
+impl<M> NotificationMonitor<M>
+where
+    M: Monitor,
+{
+    /// Creates a new [`NotificationMonitor`], posting to the webhook at `url`, and displaying
+    /// through `base`
+    pub fn new(base: M, url: &str, format: WebhookFormat) -> Self {
+        Self {
+            base,
+            url: url.into(),
+            format,
+            title: "LibAFL".into(),
+            min_execs_per_sec: None,
+            last_objectives: 0,
+            slow: false,
+        }
+    }
+
+    /// Sets the title of the notifications, to tell campaigns apart
+    #[must_use]
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Notifies when the executions per second drop below `min_execs_per_sec`
+    #[must_use]
+    pub fn with_min_execs_per_sec(mut self, min_execs_per_sec: u64) -> Self {
+        self.min_execs_per_sec = Some(min_execs_per_sec);
+        self
+    }
+
+    /// Posts `message` to the webhook, in the background
+    pub fn notify(&mut self, message: &str) {
+        let stats = json!({
+            "clients": self.base.client_stats().len(),
+            "corpus": self.base.corpus_size(),
+            "objectives": self.base.objective_size(),
+            "executions": self.base.total_execs(),
+            "exec_sec": self.base.execs_per_sec(),
+        });
+        let payload = self
+            .format
+            .payload(&self.title, message, &stats)
+            .to_string();
+        let url = self.url.clone();
+        thread::spawn(move || {
+            if let Err(err) = ureq::post(&url)
+                .set("Content-Type", "application/json")
+                .send_string(&payload)
+            {
+                println!("Could not post the notification: {}", err);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read},
+        net::TcpListener,
+    };
+
+    use super::{NotificationMonitor, WebhookFormat};
+    use crate::monitors::{Monitor, SimpleMonitor};
+
+    #[test]
+    fn test_notification_monitor() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let mut monitor =
+            NotificationMonitor::new(SimpleMonitor::new(|_| {}), &url, WebhookFormat::Slack);
+        monitor.client_stats_mut_for(1).objective_size = 2;
+        monitor.display("Objective".into(), 1);
+
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            if let Some(len) = line.to_lowercase().strip_prefix("content-length:") {
+                content_length = len.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            payload["text"],
+            "[LibAFL] 2 new objective(s) found by client #1, 2 in total"
+        );
+    }
+}
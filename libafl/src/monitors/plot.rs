@@ -0,0 +1,212 @@
+//! Monitor writing the `plot_data` file of AFL, so that `afl-plot` and the scripts and dashboards
+//! built around it keep working with `LibAFL`-based fuzzers.
+
+use alloc::{string::String, vec::Vec};
+use core::time::Duration;
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    bolts::current_time,
+    monitors::{ClientStats, Monitor, UserStats},
+    Error,
+};
+
+/// The default interval between two lines of the `plot_data` file, the one of AFL
+pub const PLOT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The header of the `plot_data` file
+pub const PLOT_DATA_HEADER: &str = "# unix_time, cycles_done, cur_path, paths_total, pending_total, pending_favs, map_size, unique_crashes, unique_hangs, max_depth, execs_per_sec, total_execs, edges_found\n";
+
+/// A snapshot of the stats of the campaign, as AFL reports them
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PlotRecord {
+    pub time: Duration,
+    pub corpus_size: u64,
+    pub objective_size: u64,
+    pub execs_per_sec: u64,
+    pub execs: u64,
+    /// The covered entries of the coverage map
+    pub covered: u64,
+    /// The size of the coverage map
+    pub total: u64,
+}
+
+impl PlotRecord {
+    /// Takes a snapshot of the stats of `monitor`.
+    /// The coverage is the highest among all clients of the ratio stat named `coverage_stat`,
+    /// or of their edges if `None`, see [`ClientStats::edges`].
+    pub fn from_monitor<M>(monitor: &mut M, coverage_stat: Option<&str>) -> Self
+    where
+        M: Monitor + ?Sized,
+    {
+        let (covered, total) = match coverage_stat {
+            Some(name) => monitor
+                .client_stats()
+                .iter()
+                .filter_map(|client: &ClientStats| match client.user_monitor.get(name) {
+                    Some(UserStats::Ratio(covered, total)) => Some((*covered, *total)),
+                    _ => None,
+                })
+                .max(),
+            None => monitor.edges(),
+        }
+        .unwrap_or((0, 0));
+        Self {
+            time: current_time(),
+            corpus_size: monitor.corpus_size(),
+            objective_size: monitor.objective_size(),
+            execs_per_sec: monitor.execs_per_sec(),
+            execs: monitor.total_execs(),
+            covered,
+            total,
+        }
+    }
+
+    /// The percentage of the coverage map covered
+    #[allow(clippy::cast_precision_loss)]
+    pub fn bitmap_cvg(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.covered as f64 * 100.0 / self.total as f64
+        }
+    }
+
+    /// The line of the `plot_data` file
+    pub fn plot_line(&self) -> String {
+        format!(
+            "{}, 0, 0, {}, 0, 0, {:.2}%, {}, 0, 0, {}, {}, {}\n",
+            self.time.as_secs(),
+            self.corpus_size,
+            self.bitmap_cvg(),
+            self.objective_size,
+            self.execs_per_sec,
+            self.execs,
+            self.covered
+        )
+    }
+}
+
+/// Periodically appends the stats to a `plot_data` file in the format of AFL.
+/// Combine it with a displaying monitor using a [`super::CombinedMonitor`].
+#[derive(Debug, Clone)]
+pub struct PlotFileMonitor {
+    path: PathBuf,
+    interval: Duration,
+    coverage_stat: Option<String>,
+    last_plot: Duration,
+    start_time: Duration,
+    client_stats: Vec<ClientStats>,
+}
+
+impl Monitor for PlotFileMonitor {
+    /// the client monitor, mutable
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        &mut self.client_stats
+    }
+
+    /// the client monitor
+    fn client_stats(&self) -> &[ClientStats] {
+        &self.client_stats
+    }
+
+    /// Time this fuzzing run stated
+    fn start_time(&mut self) -> Duration {
+        self.start_time
+    }
+
+    fn display(&mut self, _event_msg: String, _sender_id: u32) {
+        if current_time() - self.last_plot >= self.interval {
+            if let Err(err) = self.plot() {
+                println!("Could not write the plot data: {:?}", err);
+            }
+        }
+    }
+}
+
+impl PlotFileMonitor {
+    /// Creates a new [`PlotFileMonitor`] appending to the `plot_data` file at `path`.
+    /// The header gets written if the file does not exist yet.
+    pub fn new<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if !path.exists() {
+            fs::write(&path, PLOT_DATA_HEADER)?;
+        }
+        Ok(Self {
+            path,
+            interval: PLOT_INTERVAL,
+            coverage_stat: None,
+            last_plot: Duration::from_secs(0),
+            start_time: current_time(),
+            client_stats: vec![],
+        })
+    }
+
+    /// Sets the interval between two lines of the `plot_data` file
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the name of the user stat holding the coverage, that is, the name of the map feedback.
+    /// By default, the highest coverage of all ratio stats gets plotted.
+    #[must_use]
+    pub fn with_coverage_stat(mut self, name: &str) -> Self {
+        self.coverage_stat = Some(name.into());
+        self
+    }
+
+    /// Appends the current stats to the `plot_data` file
+    pub fn plot(&mut self) -> Result<(), Error> {
+        let coverage_stat = self.coverage_stat.clone();
+        let record = PlotRecord::from_monitor(self, coverage_stat.as_deref());
+        OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&self.path)?
+            .write_all(record.plot_line().as_bytes())?;
+        self.last_plot = record.time;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{PlotFileMonitor, PLOT_DATA_HEADER};
+    use crate::monitors::{Monitor, UserStats};
+
+    #[test]
+    fn test_plot_file_monitor() {
+        let path =
+            std::env::temp_dir().join(format!("libafl_test_plot_data_{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut monitor = PlotFileMonitor::new(&path).unwrap();
+        let client = monitor.client_stats_mut_for(1);
+        client.corpus_size = 12;
+        client.objective_size = 1;
+        client.update_user_stats("edges".into(), UserStats::Ratio(25, 100));
+        monitor.display("Testcase".into(), 1);
+
+        let plot_data = fs::read_to_string(&path).unwrap();
+        let mut lines = plot_data.lines();
+        assert_eq!(lines.next(), PLOT_DATA_HEADER.lines().next());
+        let fields: Vec<&str> = lines.next().unwrap().split(", ").collect();
+        assert_eq!(fields.len(), 13);
+        assert_eq!(&fields[3..8], ["12", "0", "0", "25.00%", "1"]);
+        assert_eq!(fields[12], "25");
+        fs::remove_file(path).unwrap();
+    }
+}
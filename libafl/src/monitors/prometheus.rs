@@ -0,0 +1,268 @@
+//! Monitor exposing the stats of the campaign as Prometheus metrics, over an embedded HTTP
+//! endpoint, to scrape long-running campaigns into Grafana and similar tools.
+
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::{fmt::Write as _, time::Duration};
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::Mutex,
+    thread,
+};
+
+use crate::{
+    bolts::{current_time, format_duration_hms},
+    monitors::{ClientStats, Monitor, UserStats},
+    Error,
+};
+
+/// Tracks the stats of the clients, prints them like the [`super::SimpleMonitor`], and serves
+/// them as Prometheus metrics on every path of its HTTP endpoint.
+///
+/// Global metrics are `libafl_clients`, `libafl_corpus_size`, `libafl_objectives`,
+/// `libafl_executions_total`, `libafl_execs_per_sec` and `libafl_run_time_seconds`.
+/// The same metrics exist per client, prefixed with `libafl_client_` and labeled with the
+/// `client` id, and so do the numeric user stats, as `libafl_user_stat` labeled with their `name`.
+#[derive(Clone, Debug)]
+pub struct PrometheusMonitor<F>
+where
+    F: FnMut(String),
+{
+    print_fn: F,
+    start_time: Duration,
+    client_stats: Vec<ClientStats>,
+    metrics: Arc<Mutex<String>>,
+    local_addr: SocketAddr,
+}
+
+impl<F> Monitor for PrometheusMonitor<F>
+where
+    F: FnMut(String),
+{
+    /// the client monitor, mutable
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        &mut self.client_stats
+    }
+
+    /// the client monitor
+    fn client_stats(&self) -> &[ClientStats] {
+        &self.client_stats
+    }
+
+    /// Time this fuzzing run stated
+    fn start_time(&mut self) -> Duration {
+        self.start_time
+    }
+
+    fn display(&mut self, event_msg: String, sender_id: u32) {
+        let fmt = format!(
+            "[{} #{}] run time: {}, clients: {}, corpus: {}, objectives: {}, executions: {}, exec/sec: {}",
+            event_msg,
+            sender_id,
+            format_duration_hms(&(current_time() - self.start_time)),
+            self.client_stats().len(),
+            self.corpus_size(),
+            self.objective_size(),
+            self.total_execs(),
+            self.execs_per_sec()
+        );
+        (self.print_fn)(fmt);
+
+        let metrics = self.render();
+        *self.metrics.lock().unwrap() = metrics;
+    }
+}
+
+impl<F> PrometheusMonitor<F>
+where
+    F: FnMut(String),
+{
+    /// Creates the monitor, serving the metrics at `listen_addr`, e.g. `0.0.0.0:9100`
+    pub fn new<A>(listen_addr: A, print_fn: F) -> Result<Self, Error>
+    where
+        A: ToSocketAddrs,
+    {
+        let listener = TcpListener::bind(listen_addr)?;
+        let local_addr = listener.local_addr()?;
+        let metrics = Arc::new(Mutex::new(String::new()));
+        let served = metrics.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // A failing scraper should not stop the endpoint
+                let _ = serve_metrics(stream, &served);
+            }
+        });
+        Ok(Self {
+            print_fn,
+            start_time: current_time(),
+            client_stats: vec![],
+            metrics,
+            local_addr,
+        })
+    }
+
+    /// The address the metrics are served at
+    #[must_use]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// The metrics, in the Prometheus text format
+    #[allow(clippy::cast_precision_loss)]
+    fn render(&mut self) -> String {
+        let mut out = String::new();
+        let run_time = current_time()
+            .checked_sub(self.start_time)
+            .unwrap_or_default();
+        let clients = self.client_stats.len() as u64;
+        let corpus_size = self.corpus_size();
+        let objective_size = self.objective_size();
+        let total_execs = self.total_execs();
+        let execs_per_sec = self.execs_per_sec();
+        for (name, kind, help, value) in [
+            ("clients", "gauge", "The number of clients", clients),
+            (
+                "corpus_size",
+                "gauge",
+                "The number of corpus entries",
+                corpus_size,
+            ),
+            (
+                "objectives",
+                "gauge",
+                "The number of objectives found",
+                objective_size,
+            ),
+            (
+                "executions_total",
+                "counter",
+                "The number of executions",
+                total_execs,
+            ),
+            (
+                "execs_per_sec",
+                "gauge",
+                "The executions per second",
+                execs_per_sec,
+            ),
+            (
+                "run_time_seconds",
+                "counter",
+                "The time the campaign is running",
+                run_time.as_secs(),
+            ),
+        ] {
+            writeln!(out, "# HELP libafl_{} {}", name, help).unwrap();
+            writeln!(out, "# TYPE libafl_{} {}", name, kind).unwrap();
+            writeln!(out, "libafl_{} {}", name, value).unwrap();
+        }
+
+        let cur_time = current_time();
+        for (name, kind) in [
+            ("corpus_size", "gauge"),
+            ("objectives", "gauge"),
+            ("executions_total", "counter"),
+            ("execs_per_sec", "gauge"),
+        ] {
+            writeln!(out, "# TYPE libafl_client_{} {}", name, kind).unwrap();
+            for (id, client) in self.client_stats.iter_mut().enumerate() {
+                let value = match name {
+                    "corpus_size" => client.corpus_size,
+                    "objectives" => client.objective_size,
+                    "executions_total" => client.executions,
+                    _ => client.execs_per_sec(cur_time),
+                };
+                writeln!(out, "libafl_client_{}{{client=\"{}\"}} {}", name, id, value).unwrap();
+            }
+        }
+
+        writeln!(out, "# TYPE libafl_user_stat gauge").unwrap();
+        for (id, client) in self.client_stats.iter().enumerate() {
+            for (name, stat) in &client.user_monitor {
+                let value = match stat {
+                    UserStats::Number(n) => *n as f64,
+                    UserStats::Float(n) => *n,
+                    UserStats::Ratio(a, b) => {
+                        if *b == 0 {
+                            0.0
+                        } else {
+                            *a as f64 / *b as f64
+                        }
+                    }
+                    UserStats::String(_) => continue,
+                };
+                writeln!(
+                    out,
+                    "libafl_user_stat{{client=\"{}\",name=\"{}\"}} {}",
+                    id,
+                    escape_label(name),
+                    value
+                )
+                .unwrap();
+            }
+        }
+        out
+    }
+}
+
+/// Escapes a label value
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Answers a HTTP request with the metrics
+fn serve_metrics(mut stream: TcpStream, metrics: &Mutex<String>) -> Result<(), Error> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    // Read the request head, the path does not matter
+    let mut request = Vec::new();
+    let mut buf = [0_u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < 16 * 1024 {
+        let len = stream.read(&mut buf)?;
+        if len == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..len]);
+    }
+    let body = metrics.lock().unwrap().clone();
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+    };
+
+    use super::PrometheusMonitor;
+    use crate::monitors::{Monitor, UserStats};
+
+    #[test]
+    fn test_prometheus_monitor() {
+        let mut monitor = PrometheusMonitor::new("127.0.0.1:0", |_| {}).unwrap();
+        let client = monitor.client_stats_mut_for(1);
+        client.corpus_size = 42;
+        client.executions = 1000;
+        client.update_user_stats("edges".into(), UserStats::Ratio(1, 4));
+        monitor.display("Testcase".into(), 1);
+
+        let mut stream = TcpStream::connect(monitor.local_addr()).unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("\nlibafl_corpus_size 42\n"));
+        assert!(response.contains("\nlibafl_client_executions_total{client=\"1\"} 1000\n"));
+        assert!(response.contains("\nlibafl_user_stat{client=\"1\",name=\"edges\"} 0.25\n"));
+    }
+}
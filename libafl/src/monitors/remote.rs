@@ -0,0 +1,631 @@
+//! Monitors gathering the stats of a campaign running on several machines: each machine pushes
+//! its stats over TCP, optionally TLS, with a [`RemoteMonitor`], and a [`RemoteAggregator`]
+//! merges them into any local [`Monitor`], for example the `TuiMonitor`, for a single view of
+//! the whole campaign.
+
+#[cfg(feature = "remote_monitor_tls")]
+use alloc::sync::Arc;
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    fmt::{self, Debug, Formatter},
+    time::Duration,
+};
+#[cfg(feature = "remote_monitor_tls")]
+use std::path::Path;
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+    thread,
+};
+
+use hashbrown::HashMap;
+#[cfg(feature = "remote_monitor_tls")]
+use rustls::{
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer, ServerName},
+    ClientConfig, ClientConnection, RootCertStore, ServerConfig, ServerConnection, StreamOwned,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::current_time,
+    events::LogSeverity,
+    executors::ExitKind,
+    monitors::{ClientStats, Monitor},
+    Error,
+};
+
+/// The default interval between two pushes of the stats
+pub const REMOTE_PUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The default timeout to connect to the [`RemoteAggregator`], and to send it a message.
+/// The [`RemoteMonitor`] runs in the broker, which must not hang on an unreachable aggregator.
+pub const REMOTE_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The maximum size of a message, larger ones are rejected by the [`RemoteAggregator`]
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// The TLS configuration of the [`RemoteAggregator`]
+#[cfg(feature = "remote_monitor_tls")]
+type ServerTlsConfig = Arc<ServerConfig>;
+/// Without TLS support, the [`RemoteAggregator`] has no TLS configuration
+#[cfg(not(feature = "remote_monitor_tls"))]
+type ServerTlsConfig = ();
+
+/// A message from a [`RemoteMonitor`] to a [`RemoteAggregator`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum RemoteMessage {
+    /// The stats of all the clients of a machine
+    Stats {
+        /// The name of the machine
+        machine: String,
+        /// The stats of its clients
+        client_stats: Vec<ClientStats>,
+    },
+    /// A log message of a client of a machine
+    Log {
+        /// The name of the machine
+        machine: String,
+        /// The severity of the message
+        severity_level: LogSeverity,
+        /// The message
+        message: String,
+        /// The id of the client on the machine
+        sender_id: u32,
+    },
+    /// An objective found by a client of a machine, see [`Monitor::on_objective`]
+    Objective {
+        /// The name of the machine
+        machine: String,
+        /// The id of the client on the machine
+        sender_id: u32,
+        /// The hash of the objective input
+        input_hash: u64,
+        /// How the execution of the objective input finished
+        exit_kind: ExitKind,
+        /// The signal the target crashed with, if known
+        signal: Option<i32>,
+    },
+}
+
+/// Writes a message, prefixed with its length
+fn write_message<W>(writer: &mut W, message: &RemoteMessage) -> Result<(), Error>
+where
+    W: Write + ?Sized,
+{
+    let buf = postcard::to_allocvec(message)?;
+    writer.write_all(&(buf.len() as u32).to_le_bytes())?;
+    writer.write_all(&buf)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a message, prefixed with its length
+fn read_message<R>(reader: &mut R) -> Result<RemoteMessage, Error>
+where
+    R: Read + ?Sized,
+{
+    let mut len = [0_u8; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(Error::IllegalState(format!(
+            "Remote message of {} bytes is too large",
+            len
+        )));
+    }
+    let mut buf = vec![0; len];
+    reader.read_exact(&mut buf)?;
+    Ok(postcard::from_bytes(&buf)?)
+}
+
+#[cfg(feature = "remote_monitor_tls")]
+fn tls_error<E>(err: E) -> Error
+where
+    E: fmt::Display,
+{
+    Error::IllegalState(format!("TLS error: {}", err))
+}
+
+/// Tracks the stats of the clients of this machine, and pushes them to a [`RemoteAggregator`].
+/// The log messages get forwarded right away.
+/// After a failed connection, the messages get dropped until the next push interval, when the
+/// monitor connects again, so that an unreachable aggregator does not slow down the broker.
+/// Combine it with a displaying monitor using a [`super::CombinedMonitor`] to see the stats
+/// locally as well.
+pub struct RemoteMonitor {
+    addr: String,
+    machine: String,
+    interval: Duration,
+    last_push: Duration,
+    connect_timeout: Duration,
+    /// The time of the last failed connection, if not connected since
+    last_connect_failure: Option<Duration>,
+    stream: Option<Box<dyn Write + Send>>,
+    #[cfg(feature = "remote_monitor_tls")]
+    tls: Option<(Arc<ClientConfig>, ServerName<'static>)>,
+    start_time: Duration,
+    client_stats: Vec<ClientStats>,
+}
+
+impl Debug for RemoteMonitor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteMonitor")
+            .field("addr", &self.addr)
+            .field("machine", &self.machine)
+            .field("interval", &self.interval)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("start_time", &self.start_time)
+            .field("client_stats", &self.client_stats)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Monitor for RemoteMonitor {
+    /// the client monitor, mutable
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        &mut self.client_stats
+    }
+
+    /// the client monitor
+    fn client_stats(&self) -> &[ClientStats] {
+        &self.client_stats
+    }
+
+    /// Time this fuzzing run stated
+    fn start_time(&mut self) -> Duration {
+        self.start_time
+    }
+
+    fn display(&mut self, _event_msg: String, _sender_id: u32) {
+        if current_time()
+            .checked_sub(self.last_push)
+            .unwrap_or_default()
+            >= self.interval
+        {
+            if let Err(err) = self.push() {
+                println!("Could not push the stats to {}: {:?}", self.addr, err);
+            }
+        }
+    }
+
+    fn log(&mut self, severity_level: LogSeverity, message: &str, sender_id: u32) {
+        let message = RemoteMessage::Log {
+            machine: self.machine.clone(),
+            severity_level,
+            message: message.into(),
+            sender_id,
+        };
+        if let Err(err) = self.send(&message) {
+            println!("Could not forward the log to {}: {:?}", self.addr, err);
+        }
+    }
+
+    fn on_objective(
+        &mut self,
+        sender_id: u32,
+        input_hash: u64,
+        exit_kind: ExitKind,
+        signal: Option<i32>,
+    ) {
+        let message = RemoteMessage::Objective {
+            machine: self.machine.clone(),
+            sender_id,
+            input_hash,
+            exit_kind,
+            signal,
+        };
+        if let Err(err) = self.send(&message) {
+            println!(
+                "Could not forward the objective to {}: {:?}",
+                self.addr, err
+            );
+        }
+    }
+}
+
+impl RemoteMonitor {
+    /// Creates a new [`RemoteMonitor`], pushing to the [`RemoteAggregator`] at `addr`, e.g.
+    /// `stats.example.com:1338`. The machine is named after its hostname.
+    #[must_use]
+    pub fn new(addr: &str) -> Self {
+        let machine = hostname::get().map_or_else(
+            |_| "unknown".into(),
+            |name| name.to_string_lossy().to_string(),
+        );
+        Self {
+            addr: addr.into(),
+            machine,
+            interval: REMOTE_PUSH_INTERVAL,
+            last_push: Duration::from_secs(0),
+            connect_timeout: REMOTE_CONNECT_TIMEOUT,
+            last_connect_failure: None,
+            stream: None,
+            #[cfg(feature = "remote_monitor_tls")]
+            tls: None,
+            start_time: current_time(),
+            client_stats: vec![],
+        }
+    }
+
+    /// Sets the name of this machine, shown by the aggregator
+    #[must_use]
+    pub fn with_machine_name(mut self, machine: &str) -> Self {
+        self.machine = machine.into();
+        self
+    }
+
+    /// Sets the interval between two pushes
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the timeout to connect to the aggregator, and to send it a message
+    #[must_use]
+    pub fn with_connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// Connects over TLS, trusting the certificates in the PEM file `ca_cert`, and checking
+    /// that the aggregator has a certificate for `server_name`
+    #[cfg(feature = "remote_monitor_tls")]
+    pub fn with_tls<P>(mut self, ca_cert: P, server_name: &str) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut roots = RootCertStore::empty();
+        for cert in CertificateDer::pem_file_iter(ca_cert).map_err(tls_error)? {
+            roots.add(cert.map_err(tls_error)?).map_err(tls_error)?;
+        }
+        let config =
+            ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .map_err(tls_error)?
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+        let server_name = ServerName::try_from(server_name.to_string()).map_err(|err| {
+            Error::IllegalArgument(format!("Invalid server name {}: {}", server_name, err))
+        })?;
+        self.tls = Some((Arc::new(config), server_name));
+        Ok(self)
+    }
+
+    /// Pushes the stats of all the clients to the aggregator
+    pub fn push(&mut self) -> Result<(), Error> {
+        self.last_push = current_time();
+        let message = RemoteMessage::Stats {
+            machine: self.machine.clone(),
+            client_stats: self.client_stats.clone(),
+        };
+        self.send(&message)
+    }
+
+    /// Sends a message, connecting first if needed.
+    /// The connection gets dropped on failure, and opened again for the next message, or after
+    /// the push interval if the connection failed.
+    fn send(&mut self, message: &RemoteMessage) -> Result<(), Error> {
+        if self.stream.is_none() {
+            let now = current_time();
+            if let Some(failure) = self.last_connect_failure {
+                if now.checked_sub(failure).unwrap_or_default() < self.interval {
+                    return Err(Error::IllegalState(format!(
+                        "Not connected to {}, connecting again later",
+                        self.addr
+                    )));
+                }
+            }
+            match self.connect() {
+                Ok(stream) => {
+                    self.last_connect_failure = None;
+                    self.stream = Some(stream);
+                }
+                Err(err) => {
+                    self.last_connect_failure = Some(now);
+                    return Err(err);
+                }
+            }
+        }
+        let res = write_message(self.stream.as_mut().unwrap(), message);
+        if res.is_err() {
+            self.stream = None;
+        }
+        res
+    }
+
+    fn connect(&self) -> Result<Box<dyn Write + Send>, Error> {
+        let mut last_err = None;
+        let mut stream = None;
+        for addr in self.addr.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, self.connect_timeout) {
+                Ok(connected) => {
+                    stream = Some(connected);
+                    break;
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+        let stream = match (stream, last_err) {
+            (Some(stream), _) => stream,
+            (None, Some(err)) => return Err(err.into()),
+            (None, None) => {
+                return Err(Error::IllegalArgument(format!(
+                    "{} does not resolve to any address",
+                    self.addr
+                )))
+            }
+        };
+        stream.set_nodelay(true)?;
+        stream.set_write_timeout(Some(self.connect_timeout))?;
+        #[cfg(feature = "remote_monitor_tls")]
+        if let Some((config, server_name)) = &self.tls {
+            let conn =
+                ClientConnection::new(config.clone(), server_name.clone()).map_err(tls_error)?;
+            return Ok(Box::new(StreamOwned::new(conn, stream)));
+        }
+        Ok(Box::new(stream))
+    }
+}
+
+/// Receives the stats pushed by [`RemoteMonitor`]s, and merges them into a local [`Monitor`].
+/// Each client of each machine becomes a client of the local monitor, numbered from `1` in the
+/// order they show up.
+#[derive(Debug)]
+pub struct RemoteAggregator<M>
+where
+    M: Monitor,
+{
+    monitor: M,
+    receiver: Receiver<RemoteMessage>,
+    local_addr: SocketAddr,
+    /// The local id of each client of each machine
+    client_ids: HashMap<(String, usize), u32>,
+}
+
+impl<M> RemoteAggregator<M>
+where
+    M: Monitor,
+{
+    /// Creates a new [`RemoteAggregator`], listening at `listen_addr`, e.g. `0.0.0.0:1338`,
+    /// and merging the stats into `monitor`
+    pub fn new<A>(listen_addr: A, monitor: M) -> Result<Self, Error>
+    where
+        A: ToSocketAddrs,
+    {
+        Self::listen(listen_addr, monitor, None)
+    }
+
+    /// Creates a new [`RemoteAggregator`] accepting TLS connections only, authenticating with
+    /// the certificate chain and the private key of the PEM files `cert_chain` and `key`
+    #[cfg(feature = "remote_monitor_tls")]
+    pub fn with_tls<A, P>(listen_addr: A, monitor: M, cert_chain: P, key: P) -> Result<Self, Error>
+    where
+        A: ToSocketAddrs,
+        P: AsRef<Path>,
+    {
+        let certs = CertificateDer::pem_file_iter(cert_chain)
+            .map_err(tls_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(tls_error)?;
+        let key = PrivateKeyDer::from_pem_file(key).map_err(tls_error)?;
+        let config =
+            ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .map_err(tls_error)?
+                .with_no_client_auth()
+                .with_single_cert(certs, key)
+                .map_err(tls_error)?;
+        Self::listen(listen_addr, monitor, Some(Arc::new(config)))
+    }
+
+    fn listen<A>(listen_addr: A, monitor: M, tls: Option<ServerTlsConfig>) -> Result<Self, Error>
+    where
+        A: ToSocketAddrs,
+    {
+        let listener = TcpListener::bind(listen_addr)?;
+        let local_addr = listener.local_addr()?;
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let sender = sender.clone();
+                // Only `Copy` without TLS support
+                #[allow(clippy::clone_on_copy)]
+                let tls = tls.clone();
+                thread::spawn(move || {
+                    // A misbehaving machine only loses its own connection
+                    let _ = receive_messages(stream, tls, &sender);
+                });
+            }
+        });
+        Ok(Self {
+            monitor,
+            receiver,
+            local_addr,
+            client_ids: HashMap::default(),
+        })
+    }
+
+    /// The address the aggregator listens at
+    #[must_use]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// The local monitor
+    pub fn monitor(&self) -> &M {
+        &self.monitor
+    }
+
+    /// Merges the messages received within `timeout`, returns how many were merged
+    pub fn process(&mut self, timeout: Duration) -> Result<usize, Error> {
+        let mut count = 0;
+        match self.receiver.recv_timeout(timeout) {
+            Ok(message) => {
+                self.merge(message);
+                count += 1;
+            }
+            Err(RecvTimeoutError::Timeout) => return Ok(0),
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(Error::IllegalState(
+                    "The remote aggregator stopped listening".into(),
+                ))
+            }
+        }
+        while let Ok(message) = self.receiver.try_recv() {
+            self.merge(message);
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Merges the stats of the machines forever
+    pub fn run(&mut self) -> Result<(), Error> {
+        loop {
+            self.process(Duration::from_secs(1))?;
+        }
+    }
+
+    fn merge(&mut self, message: RemoteMessage) {
+        match message {
+            RemoteMessage::Stats {
+                machine,
+                client_stats,
+            } => {
+                for (idx, stats) in client_stats.into_iter().enumerate() {
+                    // Skip the clients that did not start yet, like the broker of the machine
+                    if stats.executions == 0 {
+                        continue;
+                    }
+                    let next_id = self.client_ids.len() as u32 + 1;
+                    let id = *self
+                        .client_ids
+                        .entry((machine.clone(), idx))
+                        .or_insert(next_id);
+                    *self.monitor.client_stats_mut_for(id) = stats;
+                    self.monitor.display(machine.clone(), id);
+                }
+            }
+            RemoteMessage::Log {
+                machine,
+                severity_level,
+                message,
+                sender_id,
+            } => {
+                let id = self
+                    .client_ids
+                    .get(&(machine.clone(), sender_id as usize))
+                    .copied()
+                    .unwrap_or(0);
+                self.monitor
+                    .log(severity_level, &format!("[{}] {}", machine, message), id);
+            }
+            RemoteMessage::Objective {
+                machine,
+                sender_id,
+                input_hash,
+                exit_kind,
+                signal,
+            } => {
+                let id = self
+                    .client_ids
+                    .get(&(machine, sender_id as usize))
+                    .copied()
+                    .unwrap_or(0);
+                self.monitor.on_objective(id, input_hash, exit_kind, signal);
+            }
+        }
+    }
+}
+
+/// Forwards the messages of a machine to the aggregator
+#[allow(clippy::needless_pass_by_value)]
+fn receive_messages(
+    stream: TcpStream,
+    tls: Option<ServerTlsConfig>,
+    sender: &Sender<RemoteMessage>,
+) -> Result<(), Error> {
+    #[cfg(feature = "remote_monitor_tls")]
+    let mut reader: Box<dyn Read> = match tls {
+        Some(config) => Box::new(StreamOwned::new(
+            ServerConnection::new(config).map_err(tls_error)?,
+            stream,
+        )),
+        None => Box::new(stream),
+    };
+    #[cfg(not(feature = "remote_monitor_tls"))]
+    let mut reader: Box<dyn Read> = {
+        debug_assert!(tls.is_none());
+        Box::new(stream)
+    };
+    loop {
+        let message = read_message(&mut reader)?;
+        if sender.send(message).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+    use std::net::TcpListener;
+
+    use super::{RemoteAggregator, RemoteMonitor};
+    use crate::{
+        events::LogSeverity,
+        monitors::{Monitor, SimpleMonitor},
+        Error,
+    };
+
+    #[test]
+    fn test_remote_monitor() {
+        let mut aggregator =
+            RemoteAggregator::new("127.0.0.1:0", SimpleMonitor::new(|_| {})).unwrap();
+        let addr = aggregator.local_addr().to_string();
+
+        let mut monitors = [
+            RemoteMonitor::new(&addr).with_machine_name("a"),
+            RemoteMonitor::new(&addr).with_machine_name("b"),
+        ];
+        for (i, monitor) in monitors.iter_mut().enumerate() {
+            let client = monitor.client_stats_mut_for(1);
+            client.executions = 100;
+            client.corpus_size = 10 + i as u64;
+            monitor.display("Testcase".into(), 1);
+        }
+        monitors[1].log(LogSeverity::Info, "hello", 1);
+
+        let mut merged = 0;
+        while merged < 3 {
+            merged += aggregator.process(Duration::from_secs(5)).unwrap();
+        }
+        let stats = aggregator.monitor().client_stats();
+        assert_eq!(stats.len(), 3);
+        let mut corpus_sizes = [stats[1].corpus_size, stats[2].corpus_size];
+        corpus_sizes.sort_unstable();
+        assert_eq!(corpus_sizes, [10, 11]);
+        assert_eq!(aggregator.monitor().corpus_size(), 21);
+    }
+
+    #[test]
+    fn test_remote_monitor_unreachable() {
+        // A port nobody listens at anymore
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let mut monitor = RemoteMonitor::new(&addr).with_interval(Duration::from_secs(3600));
+        assert!(monitor.push().is_err());
+        assert!(monitor.last_connect_failure.is_some());
+        // No new attempt until the next interval
+        let err = monitor.push().unwrap_err();
+        assert!(matches!(err, Error::IllegalState(msg) if msg.contains("connecting again later")));
+    }
+}
@@ -0,0 +1,270 @@
+//! Monitor exporting the stats of the campaign in the file formats `FuzzBench` and `ClusterFuzz`
+//! understand, so that `LibAFL`-based fuzzers can run on these platforms without glue scripts.
+//!
+//! The [`StatsExportMonitor`] writes, into its output directory:
+//! * `fuzzer_stats`, in the format of AFL, read by the AFL-based integrations of `FuzzBench`
+//!   and by the AFL engine of `ClusterFuzz`,
+//! * `plot_data`, the AFL coverage over time, appended to if it exists, as in a resumed campaign,
+//! * `libfuzzer_stats`, the final stats lines of `libFuzzer`, parsed by `ClusterFuzz` from the logs
+//!   of `libFuzzer`-based engines.
+//!
+//! Only the stats `LibAFL` tracks get exported, e.g. there are no queue cycles, and the crashes
+//! and hangs are the objectives reported with an [`ExitKind::Crash`] (or [`ExitKind::Oom`]) and
+//! an [`ExitKind::Timeout`].
+
+use alloc::{string::String, vec::Vec};
+use core::{fmt::Write as _, time::Duration};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    bolts::current_time,
+    events::LogSeverity,
+    executors::ExitKind,
+    monitors::{
+        plot::{PlotRecord, PLOT_DATA_HEADER},
+        ClientStats, Monitor,
+    },
+    Error,
+};
+
+/// The default interval between two updates of the stats files
+pub const STATS_EXPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Wraps a [`Monitor`], and periodically exports the stats to files in the formats of AFL and
+/// `libFuzzer`, see the [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct StatsExportMonitor<M>
+where
+    M: Monitor,
+{
+    base: M,
+    out_dir: PathBuf,
+    interval: Duration,
+    coverage_stat: Option<String>,
+    last_export: Duration,
+    /// The objectives reported as crashes
+    saved_crashes: u64,
+    /// The objectives reported as timeouts
+    saved_hangs: u64,
+}
+
+impl<M> StatsExportMonitor<M>
+where
+    M: Monitor,
+{
+    /// Creates a new [`StatsExportMonitor`] writing into `out_dir`, and displaying through `base`.
+    /// The `plot_data` of a previous run in `out_dir` gets appended to.
+    pub fn new<P>(base: M, out_dir: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let out_dir = out_dir.as_ref().to_path_buf();
+        fs::create_dir_all(&out_dir)?;
+        let plot_data = out_dir.join("plot_data");
+        if !plot_data.exists() {
+            fs::write(plot_data, PLOT_DATA_HEADER)?;
+        }
+        Ok(Self {
+            base,
+            out_dir,
+            interval: STATS_EXPORT_INTERVAL,
+            coverage_stat: None,
+            last_export: Duration::from_secs(0),
+            saved_crashes: 0,
+            saved_hangs: 0,
+        })
+    }
+
+    /// Sets the interval between two updates of the stats files
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the name of the user stat holding the coverage, that is, the name of the map feedback.
+    /// By default, the highest coverage of all ratio stats gets exported.
+    #[must_use]
+    pub fn with_coverage_stat(mut self, name: &str) -> Self {
+        self.coverage_stat = Some(name.into());
+        self
+    }
+
+    /// Writes the stats files
+    pub fn export(&mut self) -> Result<(), Error> {
+        let start_time = self.base.start_time();
+        let record = PlotRecord::from_monitor(&mut self.base, self.coverage_stat.as_deref());
+        let now = record.time;
+
+        let mut afl_stats = String::new();
+        for (key, value) in [
+            ("start_time", start_time.as_secs().to_string()),
+            ("last_update", now.as_secs().to_string()),
+            ("run_time", (now - start_time).as_secs().to_string()),
+            ("fuzzer_pid", std::process::id().to_string()),
+            ("execs_done", record.execs.to_string()),
+            ("execs_per_sec", record.execs_per_sec.to_string()),
+            ("paths_total", record.corpus_size.to_string()),
+            ("corpus_count", record.corpus_size.to_string()),
+            ("saved_crashes", self.saved_crashes.to_string()),
+            ("saved_hangs", self.saved_hangs.to_string()),
+            ("bitmap_cvg", format!("{:.2}%", record.bitmap_cvg())),
+            ("edges_found", record.covered.to_string()),
+            ("total_edges", record.total.to_string()),
+            ("afl_banner", "libafl".into()),
+            (
+                "afl_version",
+                format!("libafl-{}", env!("CARGO_PKG_VERSION")),
+            ),
+        ] {
+            writeln!(afl_stats, "{:<18}: {}", key, value).unwrap();
+        }
+        self.write_atomic("fuzzer_stats", &afl_stats)?;
+
+        OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(self.out_dir.join("plot_data"))?
+            .write_all(record.plot_line().as_bytes())?;
+
+        let libfuzzer_stats = format!(
+            "stat::number_of_executed_units: {}\nstat::average_exec_per_sec:     {}\n",
+            record.execs, record.execs_per_sec
+        );
+        self.write_atomic("libfuzzer_stats", &libfuzzer_stats)?;
+
+        self.last_export = now;
+        Ok(())
+    }
+
+    /// Replaces a stats file at once, as the platforms may read it at any time
+    fn write_atomic(&self, name: &str, content: &str) -> Result<(), Error> {
+        let tmp = self.out_dir.join(format!(".{}.tmp", name));
+        fs::write(&tmp, content)?;
+        fs::rename(tmp, self.out_dir.join(name))?;
+        Ok(())
+    }
+}
+
+impl<M> Monitor for StatsExportMonitor<M>
+where
+    M: Monitor,
+{
+    /// the client monitor, mutable
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        self.base.client_stats_mut()
+    }
+
+    /// the client monitor
+    fn client_stats(&self) -> &[ClientStats] {
+        self.base.client_stats()
+    }
+
+    /// Time this fuzzing run stated
+    fn start_time(&mut self) -> Duration {
+        self.base.start_time()
+    }
+
+    fn log(&mut self, severity_level: LogSeverity, message: &str, sender_id: u32) {
+        self.base.log(severity_level, message, sender_id);
+    }
+
+    fn take_pause_request(&mut self) -> Option<bool> {
+        self.base.take_pause_request()
+    }
+
+    fn on_objective(
+        &mut self,
+        sender_id: u32,
+        input_hash: u64,
+        exit_kind: ExitKind,
+        signal: Option<i32>,
+    ) {
+        match exit_kind {
+            ExitKind::Crash | ExitKind::Oom => self.saved_crashes += 1,
+            ExitKind::Timeout => self.saved_hangs += 1,
+            ExitKind::Ok => (),
+        }
+        self.base
+            .on_objective(sender_id, input_hash, exit_kind, signal);
+    }
+
+    fn display(&mut self, event_msg: String, sender_id: u32) {
+        self.base.display(event_msg, sender_id);
+        if current_time() - self.last_export >= self.interval {
+            if let Err(err) = self.export() {
+                println!("Could not export the stats: {:?}", err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fs};
+
+    use super::StatsExportMonitor;
+    use crate::{
+        executors::ExitKind,
+        monitors::{plot::PLOT_DATA_HEADER, Monitor, NopMonitor, UserStats},
+    };
+
+    /// Parses the `key : value` lines of a stats file
+    fn parse_stats(stats: &str) -> HashMap<String, String> {
+        stats
+            .lines()
+            .map(|line| {
+                let (key, value) = line.split_once(": ").unwrap();
+                (key.trim().to_string(), value.trim().to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_stats_export() {
+        let out_dir =
+            std::env::temp_dir().join(format!("libafl_test_stats_export_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&out_dir);
+        let mut monitor = StatsExportMonitor::new(NopMonitor::new(), &out_dir).unwrap();
+        let client = monitor.client_stats_mut_for(1);
+        client.corpus_size = 12;
+        client.objective_size = 3;
+        client.executions = 1000;
+        client.update_user_stats("edges".into(), UserStats::Ratio(25, 100));
+        monitor.on_objective(1, 0, ExitKind::Crash, Some(11));
+        monitor.on_objective(1, 1, ExitKind::Oom, None);
+        monitor.on_objective(1, 2, ExitKind::Timeout, None);
+        monitor.export().unwrap();
+
+        let fuzzer_stats = parse_stats(&fs::read_to_string(out_dir.join("fuzzer_stats")).unwrap());
+        assert_eq!(fuzzer_stats["execs_done"], "1000");
+        assert_eq!(fuzzer_stats["corpus_count"], "12");
+        assert_eq!(fuzzer_stats["saved_crashes"], "2");
+        assert_eq!(fuzzer_stats["saved_hangs"], "1");
+        assert_eq!(fuzzer_stats["bitmap_cvg"], "25.00%");
+        assert_eq!(fuzzer_stats["edges_found"], "25");
+        assert_eq!(fuzzer_stats["total_edges"], "100");
+        // Not tracked, not exported
+        assert!(!fuzzer_stats.contains_key("cycles_done"));
+
+        let libfuzzer_stats =
+            parse_stats(&fs::read_to_string(out_dir.join("libfuzzer_stats")).unwrap());
+        assert_eq!(libfuzzer_stats["stat::number_of_executed_units"], "1000");
+        assert_eq!(libfuzzer_stats.len(), 2);
+
+        // A resumed campaign appends to the plot data
+        let mut monitor = StatsExportMonitor::new(NopMonitor::new(), &out_dir).unwrap();
+        monitor.client_stats_mut_for(1).corpus_size = 13;
+        monitor.export().unwrap();
+        let plot_data = fs::read_to_string(out_dir.join("plot_data")).unwrap();
+        let mut lines = plot_data.lines();
+        assert_eq!(lines.next(), PLOT_DATA_HEADER.lines().next());
+        let paths_total: Vec<&str> = lines.map(|line| line.split(", ").nth(3).unwrap()).collect();
+        assert_eq!(paths_total, ["12", "13"]);
+        fs::remove_dir_all(out_dir).unwrap();
+    }
+}
@@ -0,0 +1,510 @@
+//! Monitor pushing the stats of the campaign to a time series database, such as `InfluxDB` or
+//! Graphite, to follow fleets of fuzzers in the dashboards already built around them.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt::Write as _, time::Duration};
+use std::{
+    io::Write,
+    net::{TcpStream, ToSocketAddrs, UdpSocket},
+};
+
+use hashbrown::HashMap;
+
+use crate::{
+    bolts::current_time,
+    monitors::{ClientStats, Monitor, UserStats},
+    Error,
+};
+
+/// The default interval between two pushes of the stats
+pub const TIMESERIES_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The value of a field of a [`TimeseriesPoint`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeseriesValue {
+    /// An integer value
+    Int(u64),
+    /// A floating point value
+    Float(f64),
+}
+
+/// A point of a time series, with its tags and fields
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeseriesPoint {
+    /// The name of the measurement
+    pub measurement: String,
+    /// The tags, as key-value pairs
+    pub tags: Vec<(String, String)>,
+    /// The fields, as key-value pairs
+    pub fields: Vec<(String, TimeseriesValue)>,
+    /// The time of the point, since the epoch
+    pub time: Duration,
+}
+
+impl TimeseriesPoint {
+    /// The point in the `InfluxDB` line protocol, with a timestamp in nanoseconds
+    #[must_use]
+    pub fn to_influx_line(&self) -> String {
+        let mut line = escape_influx(&self.measurement, false);
+        for (key, value) in &self.tags {
+            write!(
+                line,
+                ",{}={}",
+                escape_influx(key, true),
+                escape_influx(value, true)
+            )
+            .unwrap();
+        }
+        for (i, (key, value)) in self.fields.iter().enumerate() {
+            line.push(if i == 0 { ' ' } else { ',' });
+            line.push_str(&escape_influx(key, true));
+            match value {
+                TimeseriesValue::Int(n) => write!(line, "={}i", n).unwrap(),
+                TimeseriesValue::Float(n) => write!(line, "={}", n).unwrap(),
+            }
+        }
+        writeln!(line, " {}", self.time.as_nanos()).unwrap();
+        line
+    }
+
+    /// The point in the Graphite plaintext protocol, one line per field named
+    /// `measurement.field`, with the tags of Graphite 1.1 and a timestamp in seconds
+    #[must_use]
+    pub fn to_graphite_lines(&self) -> String {
+        let mut tags = String::new();
+        for (key, value) in &self.tags {
+            write!(tags, ";{}={}", escape_graphite(key), escape_graphite(value)).unwrap();
+        }
+        let mut lines = String::new();
+        for (key, value) in &self.fields {
+            write!(
+                lines,
+                "{}.{}{} ",
+                escape_graphite(&self.measurement),
+                escape_graphite(key),
+                tags
+            )
+            .unwrap();
+            match value {
+                TimeseriesValue::Int(n) => write!(lines, "{}", n).unwrap(),
+                TimeseriesValue::Float(n) => write!(lines, "{}", n).unwrap(),
+            }
+            writeln!(lines, " {}", self.time.as_secs()).unwrap();
+        }
+        lines
+    }
+}
+
+/// Escapes a measurement, or a tag or field key or value if `in_tag`, for the line protocol
+fn escape_influx(value: &str, in_tag: bool) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == ',' || c == ' ' || (in_tag && c == '=') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Replaces the characters Graphite does not accept in names and tags
+fn escape_graphite(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "-_.:".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// A backend of the [`TimeseriesMonitor`], sending the points to a time series database
+pub trait TimeseriesBackend {
+    /// Sends the `points`
+    fn send(&mut self, points: &[TimeseriesPoint]) -> Result<(), Error>;
+}
+
+/// Sends the points to `InfluxDB` in the line protocol over UDP, one datagram per point
+#[derive(Debug)]
+pub struct InfluxUdpBackend {
+    socket: UdpSocket,
+}
+
+impl InfluxUdpBackend {
+    /// Creates a new [`InfluxUdpBackend`], sending to the UDP listener of `InfluxDB` at `addr`
+    pub fn new<A>(addr: A) -> Result<Self, Error>
+    where
+        A: ToSocketAddrs,
+    {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect(addr)?;
+        Ok(Self { socket })
+    }
+}
+
+impl TimeseriesBackend for InfluxUdpBackend {
+    fn send(&mut self, points: &[TimeseriesPoint]) -> Result<(), Error> {
+        for point in points {
+            self.socket.send(point.to_influx_line().as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// Sends the points to `InfluxDB` in the line protocol over HTTP
+#[cfg(feature = "influxdb_http")]
+#[derive(Debug)]
+pub struct InfluxHttpBackend {
+    agent: ureq::Agent,
+    url: String,
+    token: Option<String>,
+}
+
+#[cfg(feature = "influxdb_http")]
+impl InfluxHttpBackend {
+    /// Creates a new [`InfluxHttpBackend`], posting to the write endpoint at `url`, for example
+    /// `http://localhost:8086/write?db=fuzzing` for `InfluxDB` 1.x, or
+    /// `http://localhost:8086/api/v2/write?org=org&bucket=fuzzing` for `InfluxDB` 2.x
+    #[must_use]
+    pub fn new(url: &str) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(5))
+                .build(),
+            url: url.into(),
+            token: None,
+        }
+    }
+
+    /// Authenticates with the API `token` of `InfluxDB` 2.x
+    #[must_use]
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+}
+
+#[cfg(feature = "influxdb_http")]
+impl TimeseriesBackend for InfluxHttpBackend {
+    fn send(&mut self, points: &[TimeseriesPoint]) -> Result<(), Error> {
+        let body: String = points.iter().map(TimeseriesPoint::to_influx_line).collect();
+        let mut request = self.agent.post(&self.url);
+        if let Some(token) = &self.token {
+            request = request.set("Authorization", &format!("Token {}", token));
+        }
+        request
+            .send_string(&body)
+            .map_err(|err| Error::Unknown(format!("Could not write to InfluxDB: {}", err)))?;
+        Ok(())
+    }
+}
+
+/// Sends the points to Graphite in the plaintext protocol over TCP.
+/// The connection is opened on the first send, and opened again after a failure.
+#[derive(Debug)]
+pub struct GraphiteBackend {
+    addr: String,
+    stream: Option<TcpStream>,
+}
+
+impl GraphiteBackend {
+    /// Creates a new [`GraphiteBackend`], sending to the plaintext listener of Graphite at
+    /// `addr`, usually on port 2003
+    #[must_use]
+    pub fn new(addr: &str) -> Self {
+        Self {
+            addr: addr.into(),
+            stream: None,
+        }
+    }
+}
+
+impl TimeseriesBackend for GraphiteBackend {
+    fn send(&mut self, points: &[TimeseriesPoint]) -> Result<(), Error> {
+        if self.stream.is_none() {
+            self.stream = Some(TcpStream::connect(&self.addr)?);
+        }
+        let lines: String = points
+            .iter()
+            .map(TimeseriesPoint::to_graphite_lines)
+            .collect();
+        let res = self.stream.as_mut().unwrap().write_all(lines.as_bytes());
+        if res.is_err() {
+            self.stream = None;
+        }
+        Ok(res?)
+    }
+}
+
+/// Periodically pushes the stats to a time series database, through a [`TimeseriesBackend`].
+///
+/// The global stats go to the `libafl` measurement, and the stats of each client, including the
+/// numeric user stats, to `libafl_client`, tagged with the `client` id.
+/// Tags set with [`TimeseriesMonitor::with_tag`] go to all points, to tell campaigns apart.
+/// Combine it with a displaying monitor using a [`super::CombinedMonitor`].
+#[derive(Debug)]
+pub struct TimeseriesMonitor<B>
+where
+    B: TimeseriesBackend,
+{
+    backend: B,
+    measurement: String,
+    tags: Vec<(String, String)>,
+    client_tags: HashMap<usize, Vec<(String, String)>>,
+    interval: Duration,
+    last_push: Duration,
+    start_time: Duration,
+    client_stats: Vec<ClientStats>,
+}
+
+impl<B> Monitor for TimeseriesMonitor<B>
+where
+    B: TimeseriesBackend,
+{
+    /// the client monitor, mutable
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        &mut self.client_stats
+    }
+
+    /// the client monitor
+    fn client_stats(&self) -> &[ClientStats] {
+        &self.client_stats
+    }
+
+    /// Time this fuzzing run stated
+    fn start_time(&mut self) -> Duration {
+        self.start_time
+    }
+
+    fn display(&mut self, _event_msg: String, _sender_id: u32) {
+        if current_time()
+            .checked_sub(self.last_push)
+            .unwrap_or_default()
+            >= self.interval
+        {
+            if let Err(err) = self.push() {
+                println!("Could not push the stats: {:?}", err);
+            }
+        }
+    }
+}
+
+impl<B> TimeseriesMonitor<B>
+where
+    B: TimeseriesBackend,
+{
+    /// Creates a new [`TimeseriesMonitor`], pushing the stats through `backend`
+    pub fn new(backend: B) -> Self {
+        Self {
+            backend,
+            measurement: "libafl".into(),
+            tags: vec![],
+            client_tags: HashMap::default(),
+            interval: TIMESERIES_INTERVAL,
+            last_push: Duration::from_secs(0),
+            start_time: current_time(),
+            client_stats: vec![],
+        }
+    }
+
+    /// Sets the name of the measurements, `libafl` by default
+    #[must_use]
+    pub fn with_measurement(mut self, measurement: &str) -> Self {
+        self.measurement = measurement.into();
+        self
+    }
+
+    /// Adds a tag to all the points, for example `campaign`
+    #[must_use]
+    pub fn with_tag(mut self, key: &str, value: &str) -> Self {
+        self.tags.push((key.into(), value.into()));
+        self
+    }
+
+    /// Adds a tag to the points of the client with id `client_id`, for example its `target`
+    #[must_use]
+    pub fn with_client_tag(mut self, client_id: u32, key: &str, value: &str) -> Self {
+        self.client_tags
+            .entry(client_id as usize)
+            .or_default()
+            .push((key.into(), value.into()));
+        self
+    }
+
+    /// Sets the interval between two pushes
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// The backend
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// The points of the current stats, the global one first, then one per client
+    #[allow(clippy::cast_precision_loss)]
+    pub fn points(&mut self) -> Vec<TimeseriesPoint> {
+        let time = current_time();
+        let run_time = time.checked_sub(self.start_time).unwrap_or_default();
+
+        let mut fields = vec![
+            (
+                "clients".into(),
+                TimeseriesValue::Int(self.client_stats.len() as u64),
+            ),
+            (
+                "corpus_size".into(),
+                TimeseriesValue::Int(self.corpus_size()),
+            ),
+            (
+                "objectives".into(),
+                TimeseriesValue::Int(self.objective_size()),
+            ),
+            (
+                "executions".into(),
+                TimeseriesValue::Int(self.total_execs()),
+            ),
+            (
+                "execs_per_sec".into(),
+                TimeseriesValue::Int(self.execs_per_sec()),
+            ),
+            ("run_time".into(), TimeseriesValue::Int(run_time.as_secs())),
+        ];
+        if let Some(stability) = self.stability() {
+            fields.push((
+                "stability".into(),
+                TimeseriesValue::Float(f64::from(stability)),
+            ));
+        }
+        let mut points = vec![TimeseriesPoint {
+            measurement: self.measurement.clone(),
+            tags: self.tags.clone(),
+            fields,
+            time,
+        }];
+
+        for (id, client) in self.client_stats.iter_mut().enumerate() {
+            let mut tags = self.tags.clone();
+            tags.push(("client".into(), id.to_string()));
+            if let Some(client_tags) = self.client_tags.get(&id) {
+                tags.extend_from_slice(client_tags);
+            }
+            let mut fields = vec![
+                (
+                    "corpus_size".into(),
+                    TimeseriesValue::Int(client.corpus_size),
+                ),
+                (
+                    "objectives".into(),
+                    TimeseriesValue::Int(client.objective_size),
+                ),
+                ("executions".into(), TimeseriesValue::Int(client.executions)),
+                (
+                    "execs_per_sec".into(),
+                    TimeseriesValue::Int(client.execs_per_sec(time)),
+                ),
+            ];
+            if let Some(stability) = client.stability {
+                fields.push((
+                    "stability".into(),
+                    TimeseriesValue::Float(f64::from(stability)),
+                ));
+            }
+            for (name, stat) in &client.user_monitor {
+                let value = match stat {
+                    UserStats::Number(n) => TimeseriesValue::Int(*n),
+                    UserStats::Float(n) => TimeseriesValue::Float(*n),
+                    UserStats::Ratio(a, b) => {
+                        if *b == 0 {
+                            TimeseriesValue::Float(0.0)
+                        } else {
+                            TimeseriesValue::Float(*a as f64 / *b as f64)
+                        }
+                    }
+                    UserStats::String(_) => continue,
+                };
+                fields.push((name.clone(), value));
+            }
+            points.push(TimeseriesPoint {
+                measurement: format!("{}_client", self.measurement),
+                tags,
+                fields,
+                time,
+            });
+        }
+        points
+    }
+
+    /// Pushes the current stats through the backend
+    pub fn push(&mut self) -> Result<(), Error> {
+        let points = self.points();
+        self.last_push = points[0].time;
+        self.backend.send(&points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+    use std::net::UdpSocket;
+
+    use super::{InfluxUdpBackend, TimeseriesMonitor, TimeseriesPoint, TimeseriesValue};
+    use crate::monitors::{Monitor, UserStats};
+
+    #[test]
+    fn test_timeseries_point() {
+        let point = TimeseriesPoint {
+            measurement: "libafl client".into(),
+            tags: vec![("campaign".into(), "png,v2".into())],
+            fields: vec![
+                ("corpus_size".into(), TimeseriesValue::Int(42)),
+                ("stability".into(), TimeseriesValue::Float(0.5)),
+            ],
+            time: Duration::from_secs(1_600_000_000),
+        };
+        assert_eq!(
+            point.to_influx_line(),
+            "libafl\\ client,campaign=png\\,v2 corpus_size=42i,stability=0.5 1600000000000000000\n"
+        );
+        assert_eq!(
+            point.to_graphite_lines(),
+            "libafl_client.corpus_size;campaign=png_v2 42 1600000000\nlibafl_client.stability;campaign=png_v2 0.5 1600000000\n"
+        );
+    }
+
+    #[test]
+    fn test_timeseries_monitor() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let backend = InfluxUdpBackend::new(receiver.local_addr().unwrap()).unwrap();
+        let mut monitor = TimeseriesMonitor::new(backend)
+            .with_tag("campaign", "test")
+            .with_client_tag(1, "target", "png");
+        let client = monitor.client_stats_mut_for(1);
+        client.corpus_size = 12;
+        client.update_user_stats("edges".into(), UserStats::Ratio(1, 4));
+        monitor.display("Testcase".into(), 1);
+
+        let mut lines = vec![];
+        let mut buf = [0; 4096];
+        for _ in 0..3 {
+            let len = receiver.recv(&mut buf).unwrap();
+            lines.push(String::from_utf8_lossy(&buf[..len]).to_string());
+        }
+        assert!(lines[0].starts_with("libafl,campaign=test clients=2i,corpus_size=12i,"));
+        assert!(lines[1].starts_with("libafl_client,campaign=test,client=0 corpus_size=0i,"));
+        assert!(lines[2]
+            .starts_with("libafl_client,campaign=test,client=1,target=png corpus_size=12i,"));
+        assert!(lines[2].contains(",edges=0.25 "));
+    }
+}
@@ -6,11 +6,17 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
 use tui::{backend::CrosstermBackend, Terminal};
 
+pub use tui::style::Color;
+
 use std::{
     collections::VecDeque,
+    fmt::Write as _,
+    fs,
     io::{self, BufRead},
+    path::{Path, PathBuf},
     string::String,
     sync::{Arc, RwLock},
     thread,
@@ -23,22 +29,209 @@ use super::{ClientPerfMonitor, PerfFeature};
 
 use crate::{
     bolts::{current_time, format_duration_hms},
-    monitors::{ClientStats, Monitor, UserStats},
+    events::LogSeverity,
+    executors::ExitKind,
+    monitors::{format_objective, ClientStats, CoverageSummary, Monitor, UserStats},
+    Error,
 };
 
 mod ui;
 use ui::TuiUI;
 
 const DEFAULT_TIME_WINDOW: u64 = 60 * 10; // 10 min
-const DEFAULT_LOGS_NUMBER: usize = 128;
 
-#[derive(Debug, Copy, Clone)]
+/// The default interval between two saves of the TUI state, see [`TuiMonitor::with_saved_state`]
+pub const DEFAULT_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+/// The default number of log entries kept by the [`TuiMonitor`]
+pub const DEFAULT_LOGS_NUMBER: usize = 128;
+/// The default interval between two redraws of the TUI
+pub const DEFAULT_TICK_RATE: Duration = Duration::from_millis(250);
+
+/// The colors of the TUI
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TuiTheme {
+    /// The title of the fuzzer
+    pub title: Color,
+    /// The titles of the panes
+    pub pane_title: Color,
+    /// The names of the chart tabs
+    pub tabs: Color,
+    /// The selected chart tab, and the data of the charts
+    pub highlight: Color,
+    /// The axes of the charts
+    pub axis: Color,
+    /// The alerts, such as the paused clients
+    pub alert: Color,
+}
+
+impl Default for TuiTheme {
+    fn default() -> Self {
+        Self {
+            title: Color::LightMagenta,
+            pane_title: Color::LightCyan,
+            tabs: Color::LightGreen,
+            highlight: Color::LightYellow,
+            axis: Color::Gray,
+            alert: Color::LightRed,
+        }
+    }
+}
+
+impl TuiTheme {
+    /// A theme using the default color of the terminal only, for terminals without colors
+    #[must_use]
+    pub fn monochrome() -> Self {
+        Self {
+            title: Color::Reset,
+            pane_title: Color::Reset,
+            tabs: Color::Reset,
+            highlight: Color::Reset,
+            axis: Color::Reset,
+            alert: Color::Reset,
+        }
+    }
+
+    /// A theme with darker colors, readable on terminals with a light background
+    #[must_use]
+    pub fn light() -> Self {
+        Self {
+            title: Color::Magenta,
+            pane_title: Color::Blue,
+            tabs: Color::Green,
+            highlight: Color::Red,
+            axis: Color::DarkGray,
+            alert: Color::Red,
+        }
+    }
+}
+
+/// A chart of the TUI, switched pressing `g`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TuiChart {
+    /// The executions per second
+    Speed,
+    /// The size of the corpus
+    Corpus,
+    /// The number of objectives
+    Objectives,
+}
+
+impl TuiChart {
+    /// The name of the chart tab
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            TuiChart::Speed => "speed",
+            TuiChart::Corpus => "corpus",
+            TuiChart::Objectives => "objectives",
+        }
+    }
+}
+
+/// The panes and the charts shown by the TUI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TuiLayout {
+    /// Shows the stats and the charts
+    pub stats: bool,
+    /// Shows the logs, toggled pressing `t`
+    pub logs: bool,
+    /// Shows the heatmap of the coverage, toggled pressing `h`
+    pub heatmap: bool,
+    /// The charts to switch between, none to hide the charts
+    pub charts: Vec<TuiChart>,
+}
+
+impl Default for TuiLayout {
+    fn default() -> Self {
+        Self {
+            stats: true,
+            logs: true,
+            heatmap: false,
+            charts: vec![TuiChart::Speed, TuiChart::Corpus, TuiChart::Objectives],
+        }
+    }
+}
+
+/// A log keeping at most `max_entries` entries, dropping the oldest ones first.
+/// Each entry remembers the client it comes from, if any, to filter the log by client.
+#[derive(Debug, Clone)]
+pub struct BoundedLog {
+    entries: VecDeque<(Option<u32>, String)>,
+    max_entries: usize,
+}
+
+impl BoundedLog {
+    /// Creates a new [`BoundedLog`], keeping at most `max_entries` entries
+    #[must_use]
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(max_entries),
+            max_entries,
+        }
+    }
+
+    /// Appends an entry not coming from a client, dropping the oldest one if the log is full
+    pub fn push(&mut self, entry: String) {
+        self.push_entry(None, entry);
+    }
+
+    /// Appends an entry of the client `sender_id`, dropping the oldest one if the log is full
+    pub fn push_from(&mut self, sender_id: u32, entry: String) {
+        self.push_entry(Some(sender_id), entry);
+    }
+
+    fn push_entry(&mut self, sender_id: Option<u32>, entry: String) {
+        if self.max_entries == 0 {
+            return;
+        }
+        while self.entries.len() >= self.max_entries {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((sender_id, entry));
+    }
+
+    /// The number of entries
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the log has no entries
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The maximum number of entries kept
+    #[must_use]
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+
+    /// Iterates over the entries, from the oldest to the newest
+    #[must_use]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &String> + ExactSizeIterator {
+        self.entries.iter().map(|(_, entry)| entry)
+    }
+
+    /// Iterates over the entries along with the client they come from, from the oldest to the newest
+    #[must_use]
+    pub fn iter_with_sender(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (Option<u32>, &String)> + ExactSizeIterator {
+        self.entries
+            .iter()
+            .map(|(sender_id, entry)| (*sender_id, entry))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
 pub struct TimedStat {
     pub time: Duration,
     pub item: u64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TimedStats {
     pub series: VecDeque<TimedStat>,
     pub window: Duration,
@@ -76,6 +269,16 @@ impl TimedStats {
         }
     }
 
+    /// The series as CSV, with the time in seconds since the start of the campaign
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("time,value\n");
+        for stat in &self.series {
+            writeln!(csv, "{},{}", stat.time.as_secs_f64(), stat.item).unwrap();
+        }
+        csv
+    }
+
     pub fn update_window(&mut self, window: Duration) {
         self.window = window;
         while !self.series.is_empty()
@@ -94,6 +297,8 @@ pub struct PerfTuiContext {
     pub unmeasured: f64,
     pub stages: Vec<Vec<(String, f64)>>,
     pub feedbacks: Vec<(String, f64)>,
+    /// The cycles the percentages come from, exported as flamegraph
+    pub cycles: ClientPerfMonitor,
 }
 
 #[cfg(feature = "introspection")]
@@ -158,25 +363,59 @@ impl PerfTuiContext {
         }
 
         self.unmeasured = other_percent;
+        self.cycles = m.clone();
     }
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ClientTuiContext {
     pub corpus: u64,
     pub objectives: u64,
     pub executions: u64,
     pub exec_sec: u64,
+    pub stability: Option<f32>,
+    pub coverage_summary: Option<CoverageSummary>,
+
+    pub corpus_size_timed: TimedStats,
+    pub objective_size_timed: TimedStats,
+    pub execs_per_sec_timed: TimedStats,
 
     pub user_stats: HashMap<String, UserStats>,
 }
 
+impl Default for ClientTuiContext {
+    fn default() -> Self {
+        Self {
+            corpus: 0,
+            objectives: 0,
+            executions: 0,
+            exec_sec: 0,
+            stability: None,
+            coverage_summary: None,
+
+            corpus_size_timed: TimedStats::new(Duration::from_secs(DEFAULT_TIME_WINDOW)),
+            objective_size_timed: TimedStats::new(Duration::from_secs(DEFAULT_TIME_WINDOW)),
+            execs_per_sec_timed: TimedStats::new(Duration::from_secs(DEFAULT_TIME_WINDOW)),
+
+            user_stats: HashMap::default(),
+        }
+    }
+}
+
 impl ClientTuiContext {
-    pub fn grab_data(&mut self, client: &ClientStats, exec_sec: u64) {
+    /// Grabs the stats of the client, at `run_time` since the start of the campaign
+    pub fn grab_data(&mut self, client: &ClientStats, exec_sec: u64, run_time: Duration) {
         self.corpus = client.corpus_size;
         self.objectives = client.objective_size;
         self.executions = client.executions;
         self.exec_sec = exec_sec;
+        self.stability = client.stability;
+        self.coverage_summary.clone_from(&client.coverage_summary);
+
+        self.corpus_size_timed.add(run_time, client.corpus_size);
+        self.objective_size_timed
+            .add(run_time, client.objective_size);
+        self.execs_per_sec_timed.add(run_time, exec_sec);
 
         for (key, val) in &client.user_monitor {
             self.user_stats.insert(key.clone(), val.clone());
@@ -198,17 +437,28 @@ pub struct TuiContext {
 
     pub clients: HashMap<usize, ClientTuiContext>,
 
-    pub client_logs: VecDeque<String>,
+    pub client_logs: BoundedLog,
 
     pub clients_num: usize,
     pub total_execs: u64,
+    pub stability: Option<f32>,
+    /// The discovered edges and the size of the coverage map, see [`Monitor::edges`]
+    pub edges: Option<(u64, u64)>,
     pub start_time: Duration,
+
+    /// The directory the series are exported to, pressing `s`
+    pub export_dir: PathBuf,
+
+    /// Whether the clients are paused, toggled pressing `p`
+    pub paused: bool,
+    /// The pause request not forwarded to the clients yet
+    pub pause_request: Option<bool>,
 }
 
 impl TuiContext {
-    /// Create a new TUI context
+    /// Create a new TUI context, keeping at most `max_logs` log entries
     #[must_use]
-    pub fn new(start_time: Duration) -> Self {
+    pub fn new(start_time: Duration, max_logs: usize) -> Self {
         Self {
             graphs: vec!["corpus".into(), "objectives".into(), "exec/sec".into()],
             corpus_size_timed: TimedStats::new(Duration::from_secs(DEFAULT_TIME_WINDOW)),
@@ -219,13 +469,134 @@ impl TuiContext {
             introspection: HashMap::default(),
             clients: HashMap::default(),
 
-            client_logs: VecDeque::with_capacity(DEFAULT_LOGS_NUMBER),
+            client_logs: BoundedLog::new(max_logs),
 
             clients_num: 0,
             total_execs: 0,
+            stability: None,
+            edges: None,
             start_time,
+
+            export_dir: PathBuf::from("."),
+
+            paused: false,
+            pause_request: None,
         }
     }
+
+    /// The part of the context to persist, to resume the campaign
+    #[must_use]
+    pub fn saved_state(&self) -> TuiSavedState {
+        TuiSavedState {
+            start_time: self.start_time,
+            total_execs: self.total_execs,
+            corpus_size_timed: self.corpus_size_timed.clone(),
+            objective_size_timed: self.objective_size_timed.clone(),
+            execs_per_sec_timed: self.execs_per_sec_timed.clone(),
+            clients: self.clients.clone(),
+            paused: self.paused,
+        }
+    }
+
+    /// Restores a persisted state. If the clients were paused, the new ones get paused as well.
+    pub fn restore(&mut self, saved: TuiSavedState) {
+        self.start_time = saved.start_time;
+        self.total_execs = saved.total_execs;
+        self.corpus_size_timed = saved.corpus_size_timed;
+        self.objective_size_timed = saved.objective_size_timed;
+        self.execs_per_sec_timed = saved.execs_per_sec_timed;
+        self.clients = saved.clients;
+        self.paused = saved.paused;
+        if saved.paused {
+            self.pause_request = Some(true);
+        }
+    }
+
+    /// Writes the corpus, objectives and exec/sec series to CSV files in `dir`.
+    /// With the `introspection` feature, also writes where the clients spend their time to
+    /// `introspection.folded`, to render with `flamegraph.pl` or `inferno-flamegraph`.
+    pub fn export_csv(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        for (name, stats) in [
+            ("corpus_size", &self.corpus_size_timed),
+            ("objective_size", &self.objective_size_timed),
+            ("execs_per_sec", &self.execs_per_sec_timed),
+        ] {
+            fs::write(dir.join(format!("{}.csv", name)), stats.to_csv())?;
+        }
+        #[cfg(feature = "introspection")]
+        {
+            let mut clients: Vec<_> = self.introspection.iter().collect();
+            clients.sort_by_key(|(id, _)| **id);
+            let folded: String = clients
+                .into_iter()
+                .map(|(id, client)| client.cycles.folded_stacks(&format!("client #{}", id)))
+                .collect();
+            fs::write(dir.join("introspection.folded"), folded)?;
+        }
+        Ok(())
+    }
+}
+
+/// The state of the TUI persisted to disk, so that the graphs survive a restart of the fuzzer
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TuiSavedState {
+    pub start_time: Duration,
+    pub total_execs: u64,
+    pub corpus_size_timed: TimedStats,
+    pub objective_size_timed: TimedStats,
+    pub execs_per_sec_timed: TimedStats,
+    pub clients: HashMap<usize, ClientTuiContext>,
+    /// Whether the clients were paused, missing in the states saved by older versions
+    #[serde(default)]
+    pub paused: bool,
+}
+
+impl TuiSavedState {
+    /// Loads the state saved at `path`, if any
+    pub fn load(path: &Path) -> Result<Option<Self>, Error> {
+        match fs::read(path) {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Saves the state at `path`, replacing the previous one at once
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Exports the series to the export directory, and logs the outcome in the log pane
+fn export_stats(context: &RwLock<TuiContext>) {
+    let mut ctx = context.write().unwrap();
+    let dir = ctx.export_dir.clone();
+    let msg = match ctx.export_csv(&dir) {
+        Ok(()) => format!("[TUI] Exported the stats to {}", dir.display()),
+        Err(err) => format!(
+            "[TUI] Could not export the stats to {}: {}",
+            dir.display(),
+            err
+        ),
+    };
+    ctx.client_logs.push(msg);
+}
+
+/// Pauses or resumes the clients, and logs it in the log pane
+fn toggle_pause(context: &RwLock<TuiContext>) {
+    let mut ctx = context.write().unwrap();
+    ctx.paused = !ctx.paused;
+    ctx.pause_request = Some(ctx.paused);
+    let msg = if ctx.paused {
+        "[TUI] Pausing the clients"
+    } else {
+        "[TUI] Resuming the clients"
+    };
+    ctx.client_logs.push(msg.into());
 }
 
 /// Tracking monitor during fuzzing and display with tui-rs.
@@ -235,6 +606,11 @@ pub struct TuiMonitor {
 
     start_time: Duration,
     client_stats: Vec<ClientStats>,
+
+    /// Where to persist the state of the TUI, if anywhere
+    save_path: Option<PathBuf>,
+    save_interval: Duration,
+    last_save: Duration,
 }
 
 impl Monitor for TuiMonitor {
@@ -253,13 +629,36 @@ impl Monitor for TuiMonitor {
         self.start_time
     }
 
+    fn take_pause_request(&mut self) -> Option<bool> {
+        let request = self.context.write().unwrap().pause_request.take();
+        // The clients could stay paused for long, without events to save the state
+        if request.is_some() {
+            self.save_state();
+        }
+        request
+    }
+
+    fn on_objective(
+        &mut self,
+        sender_id: u32,
+        input_hash: u64,
+        exit_kind: ExitKind,
+        signal: Option<i32>,
+    ) {
+        let mut ctx = self.context.write().unwrap();
+        ctx.client_logs.push_from(
+            sender_id,
+            format_objective(sender_id, input_hash, exit_kind, signal),
+        );
+    }
+
     fn display(&mut self, event_msg: String, sender_id: u32) {
         let cur_time = current_time();
+        let run_time = cur_time - self.start_time;
 
         {
             let execsec = self.execs_per_sec();
             let totalexec = self.total_execs();
-            let run_time = cur_time - self.start_time;
 
             let mut ctx = self.context.write().unwrap();
             ctx.corpus_size_timed.add(run_time, self.corpus_size());
@@ -267,6 +666,8 @@ impl Monitor for TuiMonitor {
                 .add(run_time, self.objective_size());
             ctx.execs_per_sec_timed.add(run_time, execsec);
             ctx.total_execs = totalexec;
+            ctx.stability = self.stability();
+            ctx.edges = self.edges();
             ctx.clients_num = self.client_stats.len();
         }
 
@@ -284,6 +685,9 @@ impl Monitor for TuiMonitor {
             "[{}] corpus: {}, objectives: {}, executions: {}, exec/sec: {}",
             head, client.corpus_size, client.objective_size, client.executions, exec_sec
         );
+        if let Some(stability) = client.stability {
+            fmt += &format!(", stability: {:.2}%", stability * 100.0);
+        }
         for (key, val) in &client.user_monitor {
             fmt += &format!(", {}: {}", key, val);
         }
@@ -294,11 +698,8 @@ impl Monitor for TuiMonitor {
             ctx.clients
                 .entry(sender_id as usize)
                 .or_default()
-                .grab_data(client, exec_sec);
-            while ctx.client_logs.len() >= DEFAULT_LOGS_NUMBER {
-                ctx.client_logs.pop_front();
-            }
-            ctx.client_logs.push_back(fmt);
+                .grab_data(client, exec_sec, run_time);
+            ctx.client_logs.push_from(sender_id, fmt);
         }
 
         #[cfg(feature = "introspection")]
@@ -314,40 +715,284 @@ impl Monitor for TuiMonitor {
                     .grab_data(&client.introspection_monitor);
             }
         }
+
+        if cur_time - self.last_save >= self.save_interval {
+            self.save_state();
+        }
+    }
+
+    fn log(&mut self, severity_level: LogSeverity, message: &str, sender_id: u32) {
+        let mut ctx = self.context.write().unwrap();
+        ctx.client_logs.push_from(
+            sender_id,
+            format!("[LOG {} #{}] {}", severity_level, sender_id, message),
+        );
     }
 }
 
 impl TuiMonitor {
-    /// Creates the monitor
+    /// Creates the monitor, keeping at most `max_logs` entries in the log pane,
+    /// see [`DEFAULT_LOGS_NUMBER`]
     #[must_use]
-    pub fn new(title: String, enhanced_graphics: bool) -> Self {
-        Self::with_time(title, enhanced_graphics, current_time())
+    pub fn new(title: String, enhanced_graphics: bool, max_logs: usize) -> Self {
+        Self::with_time(title, enhanced_graphics, max_logs, current_time())
     }
 
     /// Creates the monitor with a given `start_time`.
     #[must_use]
-    pub fn with_time(title: String, enhanced_graphics: bool, start_time: Duration) -> Self {
-        let context = Arc::new(RwLock::new(TuiContext::new(start_time)));
+    pub fn with_time(
+        title: String,
+        enhanced_graphics: bool,
+        max_logs: usize,
+        start_time: Duration,
+    ) -> Self {
+        TuiMonitorBuilder::new()
+            .title(title)
+            .enhanced_graphics(enhanced_graphics)
+            .max_logs(max_logs)
+            .spawn(start_time)
+    }
+
+    /// Creates the monitor, periodically saving the state of the TUI at `path`.
+    /// If a fuzzer already saved its state there, resumes its graphs and its run time.
+    pub fn with_saved_state<P>(
+        title: String,
+        enhanced_graphics: bool,
+        max_logs: usize,
+        path: P,
+    ) -> Result<Self, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        TuiMonitorBuilder::new()
+            .title(title)
+            .enhanced_graphics(enhanced_graphics)
+            .max_logs(max_logs)
+            .saved_state(path)
+            .build()
+    }
+
+    /// Creates a [`TuiMonitorBuilder`], to configure the refresh rate, the theme and the layout
+    #[must_use]
+    pub fn builder() -> TuiMonitorBuilder {
+        TuiMonitorBuilder::new()
+    }
+
+    /// Saves the state of the TUI, if it is persisted
+    fn save_state(&mut self) {
+        if let Some(path) = &self.save_path {
+            self.last_save = current_time();
+            let saved = self.context.read().unwrap().saved_state();
+            if let Err(err) = saved.save(path) {
+                self.log(
+                    LogSeverity::Warn,
+                    &format!("Could not save the TUI state: {}", err),
+                    0,
+                );
+            }
+        }
+    }
+
+    /// Sets the interval between two saves of the TUI state
+    #[must_use]
+    pub fn with_save_interval(mut self, interval: Duration) -> Self {
+        self.save_interval = interval;
+        self
+    }
+
+    /// Sets the directory the series get exported to as CSV, pressing `s`.
+    /// Defaults to the current directory.
+    #[must_use]
+    pub fn with_export_dir<P>(self, dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.context.write().unwrap().export_dir = dir.into();
+        self
+    }
+}
+
+/// The builder for a [`TuiMonitor`], with its refresh rate, its theme and its layout
+#[derive(Debug, Clone)]
+pub struct TuiMonitorBuilder {
+    title: String,
+    enhanced_graphics: bool,
+    max_logs: usize,
+    tick_rate: Duration,
+    theme: TuiTheme,
+    layout: TuiLayout,
+    start_time: Option<Duration>,
+    save_path: Option<PathBuf>,
+    save_interval: Duration,
+    export_dir: Option<PathBuf>,
+}
+
+impl Default for TuiMonitorBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TuiMonitorBuilder {
+    /// Creates a new [`TuiMonitorBuilder`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            title: "LibAFL".into(),
+            enhanced_graphics: true,
+            max_logs: DEFAULT_LOGS_NUMBER,
+            tick_rate: DEFAULT_TICK_RATE,
+            theme: TuiTheme::default(),
+            layout: TuiLayout::default(),
+            start_time: None,
+            save_path: None,
+            save_interval: DEFAULT_SAVE_INTERVAL,
+            export_dir: None,
+        }
+    }
+
+    /// Sets the title of the fuzzer
+    pub fn title<S>(&mut self, title: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.title = title.into();
+        self
+    }
+
+    /// Draws the charts with braille characters, for a higher resolution
+    pub fn enhanced_graphics(&mut self, enhanced_graphics: bool) -> &mut Self {
+        self.enhanced_graphics = enhanced_graphics;
+        self
+    }
+
+    /// Sets the number of entries kept in the log pane
+    pub fn max_logs(&mut self, max_logs: usize) -> &mut Self {
+        self.max_logs = max_logs;
+        self
+    }
+
+    /// Sets the interval between two redraws, see [`DEFAULT_TICK_RATE`].
+    /// A higher interval keeps the TUI usable over slow connections.
+    pub fn tick_rate(&mut self, tick_rate: Duration) -> &mut Self {
+        self.tick_rate = tick_rate;
+        self
+    }
+
+    /// Sets the colors
+    pub fn theme(&mut self, theme: TuiTheme) -> &mut Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Sets the panes and the charts shown
+    pub fn layout(&mut self, layout: TuiLayout) -> &mut Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Shows or hides the stats and the charts
+    pub fn show_stats(&mut self, show: bool) -> &mut Self {
+        self.layout.stats = show;
+        self
+    }
+
+    /// Shows or hides the logs at startup
+    pub fn show_logs(&mut self, show: bool) -> &mut Self {
+        self.layout.logs = show;
+        self
+    }
+
+    /// Shows or hides the heatmap of the coverage at startup
+    pub fn show_heatmap(&mut self, show: bool) -> &mut Self {
+        self.layout.heatmap = show;
+        self
+    }
+
+    /// Sets the charts to switch between, none to hide the charts
+    pub fn charts(&mut self, charts: &[TuiChart]) -> &mut Self {
+        self.layout.charts = charts.to_vec();
+        self
+    }
+
+    /// Sets the start time of the campaign, by default the time the monitor gets built
+    pub fn start_time(&mut self, start_time: Duration) -> &mut Self {
+        self.start_time = Some(start_time);
+        self
+    }
+
+    /// Periodically saves the state of the TUI at `path`, see [`TuiMonitor::with_saved_state`]
+    pub fn saved_state<P>(&mut self, path: P) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.save_path = Some(path.into());
+        self
+    }
+
+    /// Sets the interval between two saves of the TUI state
+    pub fn save_interval(&mut self, interval: Duration) -> &mut Self {
+        self.save_interval = interval;
+        self
+    }
+
+    /// Sets the directory the series get exported to as CSV, pressing `s`
+    pub fn export_dir<P>(&mut self, dir: P) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.export_dir = Some(dir.into());
+        self
+    }
+
+    /// Builds the [`TuiMonitor`], and starts drawing the TUI.
+    /// Fails if the saved state of the TUI cannot be loaded.
+    pub fn build(&self) -> Result<TuiMonitor, Error> {
+        let saved = match &self.save_path {
+            Some(path) => TuiSavedState::load(path)?,
+            None => None,
+        };
+        let start_time = saved
+            .as_ref()
+            .map(|s| s.start_time)
+            .or(self.start_time)
+            .unwrap_or_else(current_time);
+        let monitor = self.spawn(start_time);
+        if let Some(saved) = saved {
+            monitor.context.write().unwrap().restore(saved);
+        }
+        Ok(monitor)
+    }
+
+    /// Creates the monitor and starts the TUI thread
+    fn spawn(&self, start_time: Duration) -> TuiMonitor {
+        let mut context = TuiContext::new(start_time, self.max_logs);
+        if let Some(dir) = &self.export_dir {
+            context.export_dir.clone_from(dir);
+        }
+        let context = Arc::new(RwLock::new(context));
         run_tui_thread(
             context.clone(),
-            Duration::from_millis(250),
-            title,
-            enhanced_graphics,
+            self.tick_rate,
+            TuiUI::new(
+                self.title.clone(),
+                self.enhanced_graphics,
+                self.theme,
+                self.layout.clone(),
+            ),
         );
-        Self {
+        TuiMonitor {
             context,
             start_time,
             client_stats: vec![],
+            save_path: self.save_path.clone(),
+            save_interval: self.save_interval,
+            last_save: Duration::from_secs(0),
         }
     }
 }
 
-fn run_tui_thread(
-    context: Arc<RwLock<TuiContext>>,
-    tick_rate: Duration,
-    title: String,
-    enhanced_graphics: bool,
-) {
+fn run_tui_thread(context: Arc<RwLock<TuiContext>>, tick_rate: Duration, mut ui: TuiUI) {
     thread::spawn(move || -> io::Result<()> {
         // setup terminal
         let mut stdout = io::stdout();
@@ -356,7 +1001,6 @@ fn run_tui_thread(
 
         let backend = CrosstermBackend::new(stdout);
         let mut terminal = Terminal::new(backend)?;
-        let mut ui = TuiUI::new(title, enhanced_graphics);
 
         let mut last_tick = Instant::now();
         let mut cnt = 0;
@@ -373,13 +1017,25 @@ fn run_tui_thread(
                 .unwrap_or_else(|| Duration::from_secs(0));
             if crossterm::event::poll(timeout)? {
                 if let Event::Key(key) = event::read()? {
-                    match key.code {
-                        KeyCode::Char(c) => ui.on_key(c),
-                        KeyCode::Left => ui.on_left(),
-                        //KeyCode::Up => ui.on_up(),
-                        KeyCode::Right => ui.on_right(),
-                        //KeyCode::Down => ui.on_down(),
-                        _ => {}
+                    if ui.is_searching() {
+                        ui.on_search_key(key.code);
+                    } else {
+                        match key.code {
+                            KeyCode::Char('s') => export_stats(&context),
+                            KeyCode::Char('p') => toggle_pause(&context),
+                            KeyCode::Char(c) => ui.on_key(c),
+                            KeyCode::Left => ui.on_left(),
+                            KeyCode::Up => ui.on_up(),
+                            KeyCode::Right => ui.on_right(),
+                            KeyCode::Down => ui.on_down(),
+                            KeyCode::PageUp => ui.on_page_up(),
+                            KeyCode::PageDown => ui.on_page_down(),
+                            KeyCode::End => ui.on_end(),
+                            KeyCode::Enter => ui.on_enter(),
+                            KeyCode::Esc => ui.on_esc(),
+                            KeyCode::Backspace => ui.on_backspace(),
+                            _ => {}
+                        }
                     }
                 }
             }
@@ -413,3 +1069,65 @@ fn run_tui_thread(
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{BoundedLog, TimedStats, TuiContext, TuiSavedState};
+
+    #[test]
+    fn test_timed_stats_csv() {
+        let mut stats = TimedStats::new(Duration::from_secs(60));
+        stats.add(Duration::from_millis(500), 3);
+        stats.add(Duration::from_secs(2), 3);
+        stats.add(Duration::from_secs(4), 5);
+        assert_eq!(stats.to_csv(), "time,value\n0.5,3\n4,5\n");
+    }
+
+    #[test]
+    fn test_tui_saved_state() {
+        let path =
+            std::env::temp_dir().join(format!("libafl_test_tui_state_{}.json", std::process::id()));
+        let mut ctx = TuiContext::new(Duration::from_secs(42), 8);
+        ctx.total_execs = 1000;
+        ctx.corpus_size_timed.add(Duration::from_secs(1), 7);
+        ctx.saved_state().save(&path).unwrap();
+
+        let mut restored = TuiContext::new(Duration::from_secs(0), 8);
+        restored.restore(TuiSavedState::load(&path).unwrap().unwrap());
+        assert_eq!(restored.start_time, Duration::from_secs(42));
+        assert_eq!(restored.total_execs, 1000);
+        assert_eq!(restored.corpus_size_timed.series[0].item, 7);
+        assert!(!restored.paused);
+        assert_eq!(restored.pause_request, None);
+
+        // The clients of the restarted fuzzer get paused again
+        ctx.paused = true;
+        ctx.saved_state().save(&path).unwrap();
+        let mut restored = TuiContext::new(Duration::from_secs(0), 8);
+        restored.restore(TuiSavedState::load(&path).unwrap().unwrap());
+        assert!(restored.paused);
+        assert_eq!(restored.pause_request, Some(true));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_bounded_log() {
+        let mut log = BoundedLog::new(2);
+        log.push("a".into());
+        log.push("b".into());
+        log.push("c".into());
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.iter().cloned().collect::<Vec<_>>(), ["b", "c"]);
+        log.push_from(3, "d".into());
+        assert_eq!(
+            log.iter_with_sender().map(|(id, _)| id).collect::<Vec<_>>(),
+            [None, Some(3)]
+        );
+
+        let mut empty = BoundedLog::new(0);
+        empty.push("a".into());
+        assert!(empty.is_empty());
+    }
+}
@@ -1,4 +1,7 @@
-use super::{current_time, format_duration_hms, Duration, String, TimedStats, TuiContext};
+use super::{
+    current_time, format_duration_hms, ClientTuiContext, CoverageSummary, Duration, String,
+    TimedStats, TuiChart, TuiContext, TuiLayout, TuiTheme, UserStats,
+};
 
 use tui::{
     backend::Backend,
@@ -12,30 +15,56 @@ use tui::{
     Frame,
 };
 
+use crossterm::event::KeyCode;
 use std::{
     cmp::{max, min},
+    fmt::Write as _,
     sync::{Arc, RwLock},
 };
 
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Default)]
 pub struct TuiUI {
     title: String,
     enhanced_graphics: bool,
+    theme: TuiTheme,
+    /// Shows the stats and the charts
+    show_stats: bool,
     show_logs: bool,
+    /// Shows the heatmap of the coverage map, if the clients send coverage summaries
+    show_heatmap: bool,
+    /// Shows the details of the selected client instead of the aggregated stats
+    show_client: bool,
     clients_idx: usize,
     clients: usize,
+    /// The charts to switch between
+    charts: Vec<TuiChart>,
     charts_tab_idx: usize,
     graph_data: Vec<(f64, f64)>,
+    /// How many log entries the log pane is scrolled back from the newest one
+    logs_scroll: usize,
+    /// The number of log entries fitting in the log pane, as last drawn
+    logs_height: usize,
+    /// The id of the client whose log entries are shown, typed with the number keys
+    logs_client_filter: String,
+    /// The text the shown log entries contain, case insensitive
+    logs_search: String,
+    /// The search being typed, after pressing `/`
+    search_prompt: Option<String>,
 
     pub should_quit: bool,
 }
 
 impl TuiUI {
-    pub fn new(title: String, enhanced_graphics: bool) -> Self {
+    pub fn new(title: String, enhanced_graphics: bool, theme: TuiTheme, layout: TuiLayout) -> Self {
         Self {
             title,
             enhanced_graphics,
-            show_logs: true,
+            theme,
+            show_stats: layout.stats,
+            show_logs: layout.logs,
+            show_heatmap: layout.heatmap,
+            charts: layout.charts,
             clients_idx: 1,
             ..TuiUI::default()
         }
@@ -47,18 +76,87 @@ impl TuiUI {
                 self.should_quit = true;
             }
             'g' => {
-                self.charts_tab_idx = (self.charts_tab_idx + 1) % 3;
+                self.charts_tab_idx = (self.charts_tab_idx + 1) % max(self.charts.len(), 1);
             }
             't' => {
                 self.show_logs = !self.show_logs;
             }
+            'h' => {
+                self.show_heatmap = !self.show_heatmap;
+            }
+            '/' => {
+                self.search_prompt = Some(self.logs_search.clone());
+            }
+            '0'..='9' => {
+                self.logs_client_filter.push(c);
+                self.logs_scroll = 0;
+            }
             _ => {}
         }
     }
 
-    //pub fn on_up(&mut self) {}
+    /// Whether the search prompt is open, and gets the keys
+    pub fn is_searching(&self) -> bool {
+        self.search_prompt.is_some()
+    }
+
+    /// Edits the search prompt, applying it on enter
+    pub fn on_search_key(&mut self, key: KeyCode) {
+        if let Some(prompt) = &mut self.search_prompt {
+            match key {
+                KeyCode::Char(c) => prompt.push(c),
+                KeyCode::Backspace => {
+                    prompt.pop();
+                }
+                KeyCode::Enter => {
+                    self.logs_search = self.search_prompt.take().unwrap();
+                    self.logs_scroll = 0;
+                }
+                KeyCode::Esc => self.search_prompt = None,
+                _ => {}
+            }
+        }
+    }
+
+    /// Removes the last digit of the client filter of the logs
+    pub fn on_backspace(&mut self) {
+        self.logs_client_filter.pop();
+        self.logs_scroll = 0;
+    }
+
+    pub fn on_up(&mut self) {
+        self.logs_scroll += 1;
+    }
+
+    pub fn on_down(&mut self) {
+        self.logs_scroll = self.logs_scroll.saturating_sub(1);
+    }
 
-    //pub fn on_down(&mut self) {}
+    pub fn on_page_up(&mut self) {
+        self.logs_scroll += max(self.logs_height, 1);
+    }
+
+    pub fn on_page_down(&mut self) {
+        self.logs_scroll = self.logs_scroll.saturating_sub(max(self.logs_height, 1));
+    }
+
+    /// Follows the newest log entries again
+    pub fn on_end(&mut self) {
+        self.logs_scroll = 0;
+    }
+
+    /// Opens or closes the details of the selected client
+    pub fn on_enter(&mut self) {
+        self.show_client = !self.show_client;
+    }
+
+    /// Closes the details of the selected client, and clears the filters of the logs
+    pub fn on_esc(&mut self) {
+        self.show_client = false;
+        self.logs_client_filter.clear();
+        self.logs_search.clear();
+        self.logs_scroll = 0;
+    }
 
     pub fn on_right(&mut self) {
         // never 0
@@ -80,29 +178,147 @@ impl TuiUI {
     {
         self.clients = app.read().unwrap().clients_num;
 
-        let body = Layout::default()
-            .constraints(if self.show_logs {
-                [Constraint::Percentage(50), Constraint::Percentage(50)].as_ref()
+        // The stats take the upper half, the heatmap a fifth, and the logs the rest
+        let mut constraints = vec![];
+        if self.show_stats {
+            constraints.push(if self.show_logs || self.show_heatmap {
+                Constraint::Percentage(50)
             } else {
-                [Constraint::Percentage(100)].as_ref()
-            })
-            .split(f.size());
+                Constraint::Min(0)
+            });
+        }
+        if self.show_heatmap {
+            constraints.push(if self.show_logs {
+                Constraint::Percentage(20)
+            } else {
+                Constraint::Min(0)
+            });
+        }
+        if self.show_logs {
+            constraints.push(Constraint::Min(0));
+        }
+        if constraints.is_empty() {
+            return;
+        }
+        let body = Layout::default().constraints(constraints).split(f.size());
+
+        let mut pane = 0;
+        if self.show_stats {
+            if self.show_client {
+                self.draw_client_details(f, app, body[pane]);
+            } else {
+                self.draw_overview(f, app, body[pane]);
+            }
+            pane += 1;
+        }
+        if self.show_heatmap {
+            self.draw_heatmap(f, app, body[pane]);
+        }
+        if self.show_logs {
+            self.draw_logs(f, app, body[body.len() - 1]);
+        }
+    }
+
+    /// Draws the coverage of the selected client in the client view, or of all clients
+    fn draw_heatmap<B>(&mut self, f: &mut Frame<B>, app: &Arc<RwLock<TuiContext>>, area: Rect)
+    where
+        B: Backend,
+    {
+        let ctx = app.read().unwrap();
+        let (title, summary) = if self.show_client {
+            (
+                format!("client #{} coverage (`h` to show/hide)", self.clients_idx),
+                ctx.clients
+                    .get(&self.clients_idx)
+                    .and_then(|client| client.coverage_summary.clone()),
+            )
+        } else {
+            (
+                "coverage of all clients (`h` to show/hide)".to_string(),
+                merged_coverage_summary(ctx.clients.values()),
+            )
+        };
+        let block = Block::default().borders(Borders::ALL).title(Span::styled(
+            title,
+            Style::default()
+                .fg(self.theme.pane_title)
+                .add_modifier(Modifier::BOLD),
+        ));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let summary = match summary {
+            Some(summary) if !summary.buckets.is_empty() => summary,
+            _ => {
+                let text = Paragraph::new("no coverage summary, add a `CoverageSummaryStage`");
+                f.render_widget(text, inner);
+                return;
+            }
+        };
+
+        // Each cell shows the densest bucket among the ones it covers
+        let width = inner.width as usize;
+        let cells = width * inner.height as usize;
+        let buckets = summary.buckets.len();
+        let mut lines = vec![];
+        for row in 0..inner.height as usize {
+            let mut spans = vec![];
+            for col in 0..width {
+                let cell = row * width + col;
+                let start = cell * buckets / cells;
+                let end = max((cell + 1) * buckets / cells, start + 1);
+                let density = summary.buckets[start..end]
+                    .iter()
+                    .max()
+                    .copied()
+                    .unwrap_or(0);
+                spans.push(heatmap_cell(density));
+            }
+            lines.push(Spans::from(spans));
+        }
+        f.render_widget(Paragraph::new(lines), inner);
+    }
 
-        let top_layout = Layout::default()
+    /// Splits the stats from the charts, the stats taking the whole `area` without charts
+    fn split_charts(&self, area: Rect) -> Vec<Rect> {
+        let charts = if self.charts.is_empty() { 0 } else { 50 };
+        Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
-            .split(body[0]);
+            .constraints(
+                [
+                    Constraint::Percentage(100 - charts),
+                    Constraint::Percentage(charts),
+                ]
+                .as_ref(),
+            )
+            .split(area)
+    }
+
+    fn draw_overview<B>(&mut self, f: &mut Frame<B>, app: &Arc<RwLock<TuiContext>>, area: Rect)
+    where
+        B: Backend,
+    {
+        let top_layout = self.split_charts(area);
 
         let left_layout = Layout::default()
             .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
             .split(top_layout[0]);
 
-        let text = vec![Spans::from(Span::styled(
+        let mut title = vec![Span::styled(
             &self.title,
             Style::default()
-                .fg(Color::LightMagenta)
+                .fg(self.theme.title)
                 .add_modifier(Modifier::BOLD),
-        ))];
+        )];
+        if app.read().unwrap().paused {
+            title.push(Span::styled(
+                " [PAUSED, `p` to resume]",
+                Style::default()
+                    .fg(self.theme.alert)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        let text = vec![Spans::from(title)];
         let block = Block::default().borders(Borders::ALL);
         let paragraph = Paragraph::new(text)
             .block(block)
@@ -111,74 +327,122 @@ impl TuiUI {
 
         self.draw_text(f, app, left_layout[1]);
 
+        let ctx = app.read().unwrap();
+        self.draw_charts(
+            "charts (`g` switch, `s` to export, `h` for heatmap, `p` to pause)",
+            f,
+            top_layout[1],
+            &ctx.execs_per_sec_timed,
+            &ctx.corpus_size_timed,
+            &ctx.objective_size_timed,
+        );
+    }
+
+    fn draw_client_details<B>(
+        &mut self,
+        f: &mut Frame<B>,
+        app: &Arc<RwLock<TuiContext>>,
+        area: Rect,
+    ) where
+        B: Backend,
+    {
+        let top_layout = self.split_charts(area);
+
+        let ctx = app.read().unwrap();
+        let client = ctx.clients.get(&self.clients_idx);
+        let table = Table::new(client.map(client_rows).unwrap_or_default())
+            .block(
+                Block::default()
+                    .title(Span::styled(
+                        format!(
+                            "client #{} (l/r arrows to switch, enter/esc to close)",
+                            self.clients_idx
+                        ),
+                        Style::default()
+                            .fg(self.theme.pane_title)
+                            .add_modifier(Modifier::BOLD),
+                    ))
+                    .borders(Borders::ALL),
+            )
+            .widths(&[Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)]);
+        f.render_widget(table, top_layout[0]);
+
+        if let Some(client) = client {
+            self.draw_charts(
+                &format!("client #{} charts (`g` switch)", self.clients_idx),
+                f,
+                top_layout[1],
+                &client.execs_per_sec_timed,
+                &client.corpus_size_timed,
+                &client.objective_size_timed,
+            );
+        }
+    }
+
+    fn draw_charts<B>(
+        &mut self,
+        title: &str,
+        f: &mut Frame<B>,
+        area: Rect,
+        execs_per_sec: &TimedStats,
+        corpus_size: &TimedStats,
+        objective_size: &TimedStats,
+    ) where
+        B: Backend,
+    {
+        if self.charts.is_empty() {
+            return;
+        }
         let right_layout = Layout::default()
             .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
-            .split(top_layout[1]);
-        let titles = vec![
-            Spans::from(Span::styled(
-                "speed",
-                Style::default().fg(Color::LightGreen),
-            )),
-            Spans::from(Span::styled(
-                "corpus",
-                Style::default().fg(Color::LightGreen),
-            )),
-            Spans::from(Span::styled(
-                "objectives",
-                Style::default().fg(Color::LightGreen),
-            )),
-        ];
+            .split(area);
+        let titles = self
+            .charts
+            .iter()
+            .map(|chart| {
+                Spans::from(Span::styled(
+                    chart.name(),
+                    Style::default().fg(self.theme.tabs),
+                ))
+            })
+            .collect();
         let tabs = Tabs::new(titles)
             .block(
                 Block::default()
                     .title(Span::styled(
-                        "charts (`g` switch)",
+                        title,
                         Style::default()
-                            .fg(Color::LightCyan)
+                            .fg(self.theme.pane_title)
                             .add_modifier(Modifier::BOLD),
                     ))
                     .borders(Borders::ALL),
             )
-            .highlight_style(Style::default().fg(Color::LightYellow))
+            .highlight_style(Style::default().fg(self.theme.highlight))
             .select(self.charts_tab_idx);
         f.render_widget(tabs, right_layout[0]);
 
-        match self.charts_tab_idx {
-            0 => {
-                let ctx = app.read().unwrap();
-                self.draw_time_chart(
-                    "speed chart",
-                    "exec/sec",
-                    f,
-                    right_layout[1],
-                    &ctx.execs_per_sec_timed,
-                );
+        match self.charts[self.charts_tab_idx % self.charts.len()] {
+            TuiChart::Speed => {
+                self.draw_time_chart("speed chart", "exec/sec", f, right_layout[1], execs_per_sec);
             }
-            1 => {
-                let ctx = app.read().unwrap();
+            TuiChart::Corpus => {
                 self.draw_time_chart(
                     "corpus chart",
                     "corpus size",
                     f,
                     right_layout[1],
-                    &ctx.corpus_size_timed,
+                    corpus_size,
                 );
             }
-            2 => {
-                let ctx = app.read().unwrap();
+            TuiChart::Objectives => {
                 self.draw_time_chart(
-                    "corpus chart",
+                    "objectives chart",
                     "objectives",
                     f,
                     right_layout[1],
-                    &ctx.objective_size_timed,
+                    objective_size,
                 );
             }
-            _ => {}
-        }
-
-        if self.show_logs {
-            self.draw_logs(f, app, body[1]);
         }
     }
 
@@ -267,7 +531,7 @@ impl TuiUI {
             })
             .style(
                 Style::default()
-                    .fg(Color::LightYellow)
+                    .fg(self.theme.highlight)
                     .add_modifier(Modifier::BOLD),
             )
             .data(&self.graph_data)];
@@ -277,7 +541,7 @@ impl TuiUI {
                     .title(Span::styled(
                         title,
                         Style::default()
-                            .fg(Color::LightCyan)
+                            .fg(self.theme.pane_title)
                             .add_modifier(Modifier::BOLD),
                     ))
                     .borders(Borders::ALL),
@@ -285,14 +549,14 @@ impl TuiUI {
             .x_axis(
                 Axis::default()
                     .title("time")
-                    .style(Style::default().fg(Color::Gray))
+                    .style(Style::default().fg(self.theme.axis))
                     .bounds([0.0, max_x as f64])
                     .labels(x_labels),
             )
             .y_axis(
                 Axis::default()
                     .title(y_name)
-                    .style(Style::default().fg(Color::Gray))
+                    .style(Style::default().fg(self.theme.axis))
                     .bounds([min_y as f64, max_y as f64])
                     .labels(vec![
                         Span::styled(
@@ -341,6 +605,17 @@ impl TuiUI {
                         .map_or(0, |x| x.item)
                 ))),
             ]),
+            Row::new(vec![
+                Cell::from(Span::raw("stability")),
+                Cell::from(Span::raw(format_stability(app.read().unwrap().stability))),
+            ]),
+            Row::new(vec![
+                Cell::from(Span::raw("edges")),
+                Cell::from(Span::raw(app.read().unwrap().edges.map_or_else(
+                    || "n/a".into(),
+                    |(covered, total)| UserStats::Ratio(covered, total).to_string(),
+                ))),
+            ]),
         ];
 
         let chunks = Layout::default()
@@ -359,7 +634,7 @@ impl TuiUI {
                     .title(Span::styled(
                         "generic",
                         Style::default()
-                            .fg(Color::LightCyan)
+                            .fg(self.theme.pane_title)
                             .add_modifier(Modifier::BOLD),
                     ))
                     .borders(Borders::ALL),
@@ -369,9 +644,12 @@ impl TuiUI {
 
         let client_block = Block::default()
             .title(Span::styled(
-                format!("client #{} (l/r arrows to switch)", self.clients_idx),
+                format!(
+                    "client #{} (l/r arrows to switch, enter for details)",
+                    self.clients_idx
+                ),
                 Style::default()
-                    .fg(Color::LightCyan)
+                    .fg(self.theme.pane_title)
                     .add_modifier(Modifier::BOLD),
             ))
             .borders(Borders::ALL);
@@ -382,29 +660,8 @@ impl TuiUI {
         {
             let ctx = app.read().unwrap();
             if let Some(client) = ctx.clients.get(&self.clients_idx) {
-                client_items.push(Row::new(vec![
-                    Cell::from(Span::raw("executions")),
-                    Cell::from(Span::raw(format!("{}", client.executions))),
-                ]));
-                client_items.push(Row::new(vec![
-                    Cell::from(Span::raw("exec/sec")),
-                    Cell::from(Span::raw(format!("{}", client.exec_sec))),
-                ]));
-                client_items.push(Row::new(vec![
-                    Cell::from(Span::raw("corpus")),
-                    Cell::from(Span::raw(format!("{}", client.corpus))),
-                ]));
-                client_items.push(Row::new(vec![
-                    Cell::from(Span::raw("objectives")),
-                    Cell::from(Span::raw(format!("{}", client.objectives))),
-                ]));
-                for (key, val) in &client.user_stats {
-                    client_items.push(Row::new(vec![
-                        Cell::from(Span::raw(key.clone())),
-                        Cell::from(Span::raw(format!("{}", val.clone()))),
-                    ]));
-                }
-            };
+                client_items = client_rows(client);
+            }
         }
 
         #[cfg(feature = "introspection")]
@@ -473,7 +730,7 @@ impl TuiUI {
                         .title(Span::styled(
                             "introspection",
                             Style::default()
-                                .fg(Color::LightCyan)
+                                .fg(self.theme.pane_title)
                                 .add_modifier(Modifier::BOLD),
                         ))
                         .borders(Borders::ALL),
@@ -483,25 +740,134 @@ impl TuiUI {
         }
     }
 
-    #[allow(clippy::unused_self)]
     fn draw_logs<B>(&mut self, f: &mut Frame<B>, app: &Arc<RwLock<TuiContext>>, area: Rect)
     where
         B: Backend,
     {
         let app = app.read().unwrap();
-        let logs: Vec<ListItem> = app
+        let client_filter = self.logs_client_filter.parse::<u32>().ok();
+        let search = self.logs_search.to_lowercase();
+        let entries: Vec<&String> = app
             .client_logs
+            .iter_with_sender()
+            .filter(|(sender_id, _)| client_filter.map_or(true, |id| *sender_id == Some(id)))
+            .filter(|(_, msg)| search.is_empty() || msg.to_lowercase().contains(&search))
+            .map(|(_, msg)| msg)
+            .collect();
+
+        // Show the newest entries that fit, unless scrolled back
+        self.logs_height = area.height.saturating_sub(2) as usize;
+        let len = entries.len();
+        self.logs_scroll = min(self.logs_scroll, len.saturating_sub(self.logs_height));
+        let end = len - self.logs_scroll;
+        let start = end.saturating_sub(self.logs_height);
+        let logs: Vec<ListItem> = entries[start..end]
             .iter()
-            .map(|msg| ListItem::new(Span::raw(msg)))
+            .map(|msg| ListItem::new(Span::raw(msg.as_str())))
             .collect();
+
+        let mut title = if let Some(prompt) = &self.search_prompt {
+            format!("search: {}_ (enter to apply, esc to cancel)", prompt)
+        } else if self.logs_scroll == 0 {
+            "clients logs (`t` to show/hide, up/down to scroll, `/` to search, 0-9 to filter by client)"
+                .to_string()
+        } else {
+            format!(
+                "clients logs (`t` to show/hide, up/down to scroll, end to follow) [{}-{}/{}]",
+                start + 1,
+                end,
+                len
+            )
+        };
+        if let Some(id) = client_filter {
+            write!(title, " [client #{}]", id).unwrap();
+        }
+        if !self.logs_search.is_empty() {
+            write!(title, " [/{}]", self.logs_search).unwrap();
+        }
+        if client_filter.is_some() || !self.logs_search.is_empty() {
+            title.push_str(" (esc to clear)");
+        }
         let logs = List::new(logs).block(
             Block::default().borders(Borders::ALL).title(Span::styled(
-                "clients logs (`t` to show/hide)",
+                title,
                 Style::default()
-                    .fg(Color::LightCyan)
+                    .fg(self.theme.pane_title)
                     .add_modifier(Modifier::BOLD),
             )),
         );
         f.render_widget(logs, area);
     }
 }
+
+/// The stats of a client, user stats included, as table rows
+fn client_rows(client: &ClientTuiContext) -> Vec<Row<'static>> {
+    let mut rows = vec![
+        Row::new(vec![
+            Cell::from(Span::raw("executions")),
+            Cell::from(Span::raw(format!("{}", client.executions))),
+        ]),
+        Row::new(vec![
+            Cell::from(Span::raw("exec/sec")),
+            Cell::from(Span::raw(format!("{}", client.exec_sec))),
+        ]),
+        Row::new(vec![
+            Cell::from(Span::raw("corpus")),
+            Cell::from(Span::raw(format!("{}", client.corpus))),
+        ]),
+        Row::new(vec![
+            Cell::from(Span::raw("objectives")),
+            Cell::from(Span::raw(format!("{}", client.objectives))),
+        ]),
+        Row::new(vec![
+            Cell::from(Span::raw("stability")),
+            Cell::from(Span::raw(format_stability(client.stability))),
+        ]),
+    ];
+    for (key, val) in &client.user_stats {
+        rows.push(Row::new(vec![
+            Cell::from(Span::raw(key.clone())),
+            Cell::from(Span::raw(format!("{}", val))),
+        ]));
+    }
+    rows
+}
+
+/// The stability as percentage, if measured
+fn format_stability(stability: Option<f32>) -> String {
+    stability.map_or_else(|| "n/a".into(), |x| format!("{:.2}%", x * 100.0))
+}
+
+/// The summary of the coverage of all clients, the densest value of each bucket
+fn merged_coverage_summary<'a, C>(clients: C) -> Option<CoverageSummary>
+where
+    C: Iterator<Item = &'a ClientTuiContext>,
+{
+    let mut merged: Option<CoverageSummary> = None;
+    for summary in clients.filter_map(|client| client.coverage_summary.as_ref()) {
+        match &mut merged {
+            Some(merged) if merged.buckets.len() == summary.buckets.len() => {
+                for (m, s) in merged.buckets.iter_mut().zip(&summary.buckets) {
+                    *m = max(*m, *s);
+                }
+            }
+            Some(_) => {}
+            None => merged = Some(summary.clone()),
+        }
+    }
+    merged
+}
+
+/// A cell of the heatmap, colored by the ratio of covered entries
+fn heatmap_cell(density: u8) -> Span<'static> {
+    let color = match density {
+        0 => return Span::styled("·", Style::default().fg(Color::DarkGray)),
+        1..=42 => Color::Blue,
+        43..=85 => Color::Cyan,
+        86..=127 => Color::Green,
+        128..=170 => Color::Yellow,
+        171..=212 => Color::LightRed,
+        _ => Color::Red,
+    };
+    Span::styled("█", Style::default().fg(color))
+}
@@ -52,6 +52,8 @@ pub struct MOpt {
     pub core_time: usize,
     /// The swarm identifier that we are currently using in the pilot fuzzing mode
     pub swarm_now: usize,
+    /// The current mode, pilot or core fuzzing
+    pub mode: MOptMode,
     /// A parameter for the PSO algorithm
     x_now: Vec<Vec<f64>>,
     /// A parameter for the PSO algorithm
@@ -101,6 +103,7 @@ impl Debug for MOpt {
             .field("\nw_end", &self.w_end)
             .field("\nw_now", &self.g_now)
             .field("\ng_now", &self.g_max)
+            .field("\nmode", &self.mode)
             .field("\npilot_time", &self.pilot_time)
             .field("\ncore_time", &self.core_time)
             .field("\n\nx_now", &self.x_now)
@@ -155,6 +158,7 @@ impl MOpt {
             pilot_time: 0,
             core_time: 0,
             swarm_now: 0,
+            mode: MOptMode::Pilotfuzzing,
             x_now: vec![vec![0.0; operator_num]; swarm_num],
             l_best: vec![vec![0.0; operator_num]; swarm_num],
             eff_best: vec![vec![0.0; operator_num]; swarm_num],
@@ -366,7 +370,6 @@ where
     MT: MutatorsTuple<I, S>,
     S: HasRand + HasMetadata + HasCorpus<I> + HasSolutions<I>,
 {
-    mode: MOptMode,
     finds_before: usize,
     mutations: MT,
     phantom: PhantomData<(I, S)>,
@@ -416,7 +419,7 @@ where
         let after = state.corpus().count() + state.solutions().count();
 
         let mopt = state.metadata_mut().get_mut::<MOpt>().unwrap();
-        let key_module = self.mode;
+        let key_module = mopt.mode;
         match key_module {
             MOptMode::Corefuzzing => {
                 mopt.core_time += 1;
@@ -440,7 +443,7 @@ where
                         mopt.core_operator_cycles[i] = mopt.core_operator_cycles_v2[i];
                     }
                     mopt.pso_update()?;
-                    self.mode = MOptMode::Pilotfuzzing;
+                    mopt.mode = MOptMode::Pilotfuzzing;
                 }
             }
             MOptMode::Pilotfuzzing => {
@@ -498,7 +501,7 @@ where
                         // If there's only 1 swarm, then no core_fuzzing mode.
                         mopt.pso_update()?;
                     } else if mopt.swarm_now == mopt.swarm_num {
-                        self.mode = MOptMode::Corefuzzing;
+                        mopt.mode = MOptMode::Corefuzzing;
 
                         for i in 0..mopt.operator_num {
                             mopt.core_operator_cycles_v2[i] = mopt.core_operator_cycles[i];
@@ -531,10 +534,16 @@ where
     S: HasRand + HasMetadata + HasCorpus<I> + HasSolutions<I>,
 {
     /// Create a new [`StdMOptMutator`].
+    /// The [`struct@MOpt`] state already in the state, e.g. of a restarted fuzzer, is kept,
+    /// to resume its swarms, as long as it is for as many operators and swarms.
     pub fn new(state: &mut S, mutations: MT, swarm_num: usize) -> Result<Self, Error> {
-        state.add_metadata::<MOpt>(MOpt::new(mutations.len(), swarm_num)?);
+        let resumed = state.metadata().get::<MOpt>().map_or(false, |mopt| {
+            mopt.operator_num == mutations.len() && mopt.swarm_num == swarm_num
+        });
+        if !resumed {
+            state.add_metadata::<MOpt>(MOpt::new(mutations.len(), swarm_num)?);
+        }
         Ok(Self {
-            mode: MOptMode::Pilotfuzzing,
             finds_before: 0,
             mutations,
             phantom: PhantomData,
@@ -654,7 +663,7 @@ where
         input: &mut I,
         stage_idx: i32,
     ) -> Result<MutationResult, Error> {
-        let mode = self.mode;
+        let mode = state.metadata().get::<MOpt>().unwrap().mode;
         match mode {
             MOptMode::Corefuzzing => self.core_mutate(state, input, stage_idx),
             MOptMode::Pilotfuzzing => self.pilot_mutate(state, input, stage_idx),
@@ -0,0 +1,147 @@
+//! Mutators keeping the strings of an input valid, using the [`StringCategoriesMetadata`] of the
+//! [`crate::stages::StringCategoriesStage`].
+
+use crate::{
+    bolts::{rands::Rand, tuples::Named},
+    inputs::{HasBytesVec, Input},
+    mutators::{MutationResult, Mutator},
+    stages::string_categories::StringCategoriesMetadata,
+    state::{HasMetadata, HasRand},
+    Error,
+};
+
+/// The printable ASCII characters, from the space to the `~`
+const PRINTABLE_ASCII: core::ops::RangeInclusive<u8> = b' '..=b'~';
+
+/// Replaces an ASCII character in one of the strings of the input by a random printable ASCII
+/// character, so that the string stays valid ASCII or UTF-8.
+/// It needs the [`StringCategoriesMetadata`] of the input in the state, and skips the inputs
+/// whose length changed since, as their ranges no longer hold.
+#[derive(Default, Debug)]
+pub struct StringCharReplaceMutator;
+
+impl<I, S> Mutator<I, S> for StringCharReplaceMutator
+where
+    I: Input + HasBytesVec,
+    S: HasMetadata + HasRand,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let (start, end) = {
+            let meta = match state.metadata().get::<StringCategoriesMetadata>() {
+                Some(meta) if meta.input_len() == input.bytes().len() => meta,
+                _ => return Ok(MutationResult::Skipped),
+            };
+            let count = meta.text_ranges().count();
+            if count == 0 {
+                return Ok(MutationResult::Skipped);
+            }
+            let idx = state.rand_mut().below(count as u64) as usize;
+            let meta = state.metadata().get::<StringCategoriesMetadata>().unwrap();
+            let range = meta.text_ranges().nth(idx).unwrap();
+            (range.start, range.end)
+        };
+
+        // Only the ASCII bytes of the string, the others are parts of multi-byte characters
+        let ascii = input.bytes()[start..end]
+            .iter()
+            .filter(|byte| byte.is_ascii())
+            .count();
+        if ascii == 0 {
+            return Ok(MutationResult::Skipped);
+        }
+        let nth = state.rand_mut().below(ascii as u64) as usize;
+        let replacement = *PRINTABLE_ASCII.start()
+            + state.rand_mut().below(u64::from(
+                PRINTABLE_ASCII.end() - PRINTABLE_ASCII.start() + 1,
+            )) as u8;
+        let byte = input.bytes_mut()[start..end]
+            .iter_mut()
+            .filter(|byte| byte.is_ascii())
+            .nth(nth)
+            .unwrap();
+        if *byte == replacement {
+            return Ok(MutationResult::Skipped);
+        }
+        *byte = replacement;
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for StringCharReplaceMutator {
+    fn name(&self) -> &str {
+        "StringCharReplaceMutator"
+    }
+}
+
+impl StringCharReplaceMutator {
+    /// Creates a new [`StringCharReplaceMutator`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StringCharReplaceMutator;
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::InMemoryCorpus,
+        inputs::{BytesInput, HasBytesVec},
+        mutators::{MutationResult, Mutator},
+        stages::string_categories::StringCategoriesMetadata,
+        state::{HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_string_char_replace() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            tuple_list!(),
+        );
+        let original = "\x00\x01na\u{ef}ve\x00\x01".as_bytes().to_vec();
+        let mut input = BytesInput::new(original.clone());
+        // No metadata, nothing to do
+        assert_eq!(
+            StringCharReplaceMutator
+                .mutate(&mut state, &mut input, 0)
+                .unwrap(),
+            MutationResult::Skipped
+        );
+
+        state.add_metadata(StringCategoriesMetadata::new(&original, 4));
+        for _ in 0..64 {
+            let mut input = BytesInput::new(original.clone());
+            if StringCharReplaceMutator
+                .mutate(&mut state, &mut input, 0)
+                .unwrap()
+                == MutationResult::Skipped
+            {
+                continue;
+            }
+            let bytes = input.bytes();
+            // The binary parts are left alone, and the string stays valid
+            assert_eq!(bytes[..2], original[..2]);
+            assert_eq!(bytes[8..], original[8..]);
+            let text = core::str::from_utf8(&bytes[2..8]).unwrap();
+            assert!(text.contains('\u{ef}'));
+            assert_ne!(bytes, &original[..]);
+        }
+
+        // Another length, the ranges do not hold
+        let mut input = BytesInput::new(b"naive".to_vec());
+        assert_eq!(
+            StringCharReplaceMutator
+                .mutate(&mut state, &mut input, 0)
+                .unwrap(),
+            MutationResult::Skipped
+        );
+    }
+}
@@ -26,6 +26,7 @@ use crate::{
     inputs::{HasBytesVec, Input},
     mutators::{buffer_self_copy, mutations::buffer_copy, MutationResult, Mutator, Named},
     observers::cmp::{CmpValues, CmpValuesMetadata},
+    stages::colorization::TaintMetadata,
     state::{HasMaxSize, HasMetadata, HasRand},
     Error,
 };
@@ -413,6 +414,7 @@ impl TokenReplace {
 
 /// A `I2SRandReplace` [`Mutator`] replaces a random matching input-2-state comparison operand with the other.
 /// it needs a valid [`CmpValuesMetadata`] in the state.
+/// If the input was colorized by a [`crate::stages::ColorizationStage`], only the colorized bytes get replaced.
 #[derive(Debug, Default)]
 pub struct I2SRandReplace;
 
@@ -451,11 +453,17 @@ where
 
         let meta = state.metadata().get::<CmpValuesMetadata>().unwrap();
         let cmp_values = &meta.list[idx];
+        // Only replace the colorized bytes, if the input was colorized
+        let taint = state.metadata().get::<TaintMetadata>();
+        let allowed = |offset, len| taint.map_or(true, |taint| taint.is_tainted(size, offset, len));
 
         let mut result = MutationResult::Skipped;
         match cmp_values {
             CmpValues::U8(v) => {
-                for byte in bytes.iter_mut().take(len).skip(off) {
+                for (i, byte) in bytes.iter_mut().enumerate().take(len).skip(off) {
+                    if !allowed(i, 1) {
+                        continue;
+                    }
                     if *byte == v.0 {
                         *byte = v.1;
                         result = MutationResult::Mutated;
@@ -470,6 +478,9 @@ where
             CmpValues::U16(v) => {
                 if len >= size_of::<u16>() {
                     for i in off..len - (size_of::<u16>() - 1) {
+                        if !allowed(i, size_of::<u16>()) {
+                            continue;
+                        }
                         let val =
                             u16::from_ne_bytes(bytes[i..i + size_of::<u16>()].try_into().unwrap());
                         if val == v.0 {
@@ -499,6 +510,9 @@ where
             CmpValues::U32(v) => {
                 if len >= size_of::<u32>() {
                     for i in off..len - (size_of::<u32>() - 1) {
+                        if !allowed(i, size_of::<u32>()) {
+                            continue;
+                        }
                         let val =
                             u32::from_ne_bytes(bytes[i..i + size_of::<u32>()].try_into().unwrap());
                         if val == v.0 {
@@ -528,6 +542,9 @@ where
             CmpValues::U64(v) => {
                 if len >= size_of::<u64>() {
                     for i in off..len - (size_of::<u64>() - 1) {
+                        if !allowed(i, size_of::<u64>()) {
+                            continue;
+                        }
                         let val =
                             u64::from_ne_bytes(bytes[i..i + size_of::<u64>()].try_into().unwrap());
                         if val == v.0 {
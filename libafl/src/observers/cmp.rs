@@ -8,7 +8,12 @@ use core::fmt::Debug;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::{
-    bolts::{ownedref::OwnedRefMut, tuples::Named, AsMutSlice, AsSlice},
+    bolts::{
+        ownedref::{OwnedRefMut, OwnedSliceMut},
+        tuples::Named,
+        AsMutSlice, AsSlice,
+    },
+    executors::ExitKind,
     observers::Observer,
     state::HasMetadata,
     Error,
@@ -50,6 +55,31 @@ impl CmpValues {
             CmpValues::Bytes(_) => None,
         }
     }
+
+    /// The value profile score of the compared values, the number of their equal bits.
+    /// For [`CmpValues::Bytes`], the equal bits of their common prefix and of their first
+    /// different byte, up to [`u8::MAX`].
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn value_profile_score(&self) -> u8 {
+        match self {
+            CmpValues::U8((v0, v1)) => (!(v0 ^ v1)).count_ones() as u8,
+            CmpValues::U16((v0, v1)) => (!(v0 ^ v1)).count_ones() as u8,
+            CmpValues::U32((v0, v1)) => (!(v0 ^ v1)).count_ones() as u8,
+            CmpValues::U64((v0, v1)) => (!(v0 ^ v1)).count_ones() as u8,
+            CmpValues::Bytes((v0, v1)) => {
+                let mut score = 0_u32;
+                for (b0, b1) in v0.iter().zip(v1) {
+                    let equal = (!(b0 ^ b1)).count_ones();
+                    score += equal;
+                    if equal != 8 {
+                        break;
+                    }
+                }
+                score.min(u32::from(u8::MAX)) as u8
+            }
+        }
+    }
 }
 
 /// A state metadata holding a list of values logged from comparisons
@@ -107,6 +137,26 @@ pub trait CmpMap: Debug {
 
     /// Reset the state
     fn reset(&mut self) -> Result<(), Error>;
+
+    /// Records the value profile of the first `count` cmps into `map`, like the value profile of
+    /// `libFuzzer`: the entry of each cmp, at its index modulo the length of `map`, keeps the
+    /// highest [`CmpValues::value_profile_score`] of its logged values. A
+    /// [`crate::feedbacks::MaxMapFeedback`] on a map observer of `map` then rewards the inputs
+    /// getting the operands of a cmp closer to each other, e.g. toward a magic value.
+    fn record_value_profile(&self, count: usize, map: &mut [u8]) {
+        if map.is_empty() {
+            return;
+        }
+        for i in 0..count {
+            let idx = i % map.len();
+            for j in 0..self.usable_executions_for(i) {
+                let score = self.values_of(i, j).value_profile_score();
+                if score > map[idx] {
+                    map[idx] = score;
+                }
+            }
+        }
+    }
 }
 
 /// A [`CmpObserver`] observes the traced comparisons during the current execution using a [`CmpMap`]
@@ -196,6 +246,7 @@ where
 {
     cmp_map: OwnedRefMut<'a, CM>,
     size: Option<OwnedRefMut<'a, usize>>,
+    value_profile: Option<OwnedSliceMut<'a, u8>>,
     name: String,
 }
 
@@ -226,6 +277,24 @@ where
 {
     fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
         self.cmp_map.as_mut().reset()?;
+        if let Some(value_profile) = &mut self.value_profile {
+            value_profile.as_mut_slice().fill(0);
+        }
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &I,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        let count = CmpObserver::<CM, I, S>::usable_count(self);
+        if let Some(value_profile) = &mut self.value_profile {
+            self.cmp_map
+                .as_ref()
+                .record_value_profile(count, value_profile.as_mut_slice());
+        }
         Ok(())
     }
 }
@@ -249,6 +318,7 @@ where
         Self {
             name: name.to_string(),
             size: None,
+            value_profile: None,
             cmp_map: OwnedRefMut::Ref(map),
         }
     }
@@ -259,7 +329,16 @@ where
         Self {
             name: name.to_string(),
             size: Some(OwnedRefMut::Ref(size)),
+            value_profile: None,
             cmp_map: OwnedRefMut::Ref(map),
         }
     }
+
+    /// Records the value profile of the cmps of each run into `map`, see
+    /// [`CmpMap::record_value_profile`], for a map observer of `map`
+    #[must_use]
+    pub fn with_value_profile(mut self, map: &'a mut [u8]) -> Self {
+        self.value_profile = Some(OwnedSliceMut::from(map));
+        self
+    }
 }
@@ -0,0 +1,402 @@
+//! The [`CrashContextObserver`] keeps the machine state of the target at its crash, the signal, the
+//! faulting address, the registers and a backtrace, for the triage of the objectives.
+//! The [`crate::executors::PtraceCommandExecutor`] fills it, when found in its observers.
+
+use ahash::AHasher;
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::hash::Hasher;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::Named,
+    executors::ExitKind,
+    observers::{Observer, ObserverWithHashField},
+    Error,
+};
+
+/// The name of the [`CrashContextObserver`], by which the executors find it
+pub const CRASH_CONTEXT_OBSERVER_NAME: &str = "CrashContextObserver";
+
+/// The faulting addresses below this one are taken for `NULL` dereferences
+const NULL_PAGE_END: u64 = 0x10000;
+
+/// The bits of the addresses outside of the modules kept in the hash of a crash, their offset in
+/// their page, which stays the same with ASLR
+const PAGE_OFFSET_MASK: u64 = 0xfff;
+
+/// A file mapped in the memory of the target, e.g. its executable or a library
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MappedModule {
+    /// The first address of the mapping
+    pub start: u64,
+    /// The address after the mapping
+    pub end: u64,
+    /// The address the start of the file would be mapped at, to get the offsets in the file
+    pub base: u64,
+    /// The path of the file
+    pub path: String,
+}
+
+impl MappedModule {
+    /// The name of the file, without its directory, to be the same on other machines
+    #[must_use]
+    pub fn file_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+/// Parses the file mappings of a process in the format of `/proc/<pid>/maps` on Linux,
+/// e.g. `55d0c6a00000-55d0c6a21000 r-xp 00001000 08:01 1234 /usr/bin/target`
+#[must_use]
+pub fn parse_proc_maps(maps: &str) -> Vec<MappedModule> {
+    maps.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (start, end) = fields.next()?.split_once('-')?;
+            let offset = fields.nth(1)?;
+            let path = fields.nth(2)?;
+            if !path.starts_with('/') {
+                // Anonymous mappings, or [stack], [heap] and [vdso]
+                return None;
+            }
+            let start = u64::from_str_radix(start, 16).ok()?;
+            Some(MappedModule {
+                start,
+                end: u64::from_str_radix(end, 16).ok()?,
+                base: start.wrapping_sub(u64::from_str_radix(offset, 16).ok()?),
+                path: path.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// How likely a crash is to be exploitable, as guessed from its [`CrashContextObserver`].
+/// The guess only looks at the signal and the faulting address, without disassembling the
+/// faulting instruction, so anything not obvious is [`Exploitability::Unknown`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exploitability {
+    /// The program counter got corrupted, the target jumped to the faulting address
+    Exploitable,
+    /// The target executed an illegal instruction
+    ProbablyExploitable,
+    /// The target dereferenced a `NULL` pointer
+    ProbablyNotExploitable,
+    /// The target divided by zero
+    NotExploitable,
+    /// Anything else, e.g. an `abort`, or a fault on an address which is not `NULL`, which may be
+    /// a read or a write
+    Unknown,
+}
+
+/// An observer keeping the state of the target at its crash in the last run: the signal, the
+/// faulting address, the program counter, the registers and a backtrace of return addresses.
+/// Everything is empty if the target did not crash.
+/// The hash of the crash tells the crashes apart, for a [`crate::feedbacks::NewHashFeedback`] to
+/// only keep the new ones. It is made of the signal and of the backtrace, or of the program
+/// counter if there is no backtrace, with the addresses taken relative to their module, to be
+/// the same with ASLR. A crash without any of them, e.g. without a signal, still gets a hash.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CrashContextObserver {
+    name: String,
+    signal: Option<i32>,
+    fault_addr: Option<u64>,
+    pc: Option<u64>,
+    registers: Vec<(String, u64)>,
+    backtrace: Vec<u64>,
+    #[serde(skip)]
+    modules: Vec<MappedModule>,
+    hash: Option<u64>,
+}
+
+impl CrashContextObserver {
+    /// Creates a new [`CrashContextObserver`], named [`CRASH_CONTEXT_OBSERVER_NAME`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            name: CRASH_CONTEXT_OBSERVER_NAME.to_string(),
+            signal: None,
+            fault_addr: None,
+            pc: None,
+            registers: Vec::new(),
+            backtrace: Vec::new(),
+            modules: Vec::new(),
+            hash: None,
+        }
+    }
+
+    /// Sets the signal of the crash, and the faulting address, if the signal has one
+    pub fn observe_signal(&mut self, signal: i32, fault_addr: Option<u64>) {
+        self.signal = Some(signal);
+        self.fault_addr = fault_addr;
+        self.rehash();
+    }
+
+    /// Sets the program counter and the registers at the crash
+    pub fn observe_registers(&mut self, pc: u64, registers: Vec<(String, u64)>) {
+        self.pc = Some(pc);
+        self.registers = registers;
+        self.rehash();
+    }
+
+    /// Sets the backtrace at the crash, the program counter then the return addresses
+    pub fn observe_backtrace(&mut self, backtrace: Vec<u64>) {
+        self.backtrace = backtrace;
+        self.rehash();
+    }
+
+    /// Sets the files mapped in the target at the crash, e.g. from [`parse_proc_maps`], to hash
+    /// the addresses relative to their module
+    pub fn observe_modules(&mut self, modules: Vec<MappedModule>) {
+        self.modules = modules;
+        self.rehash();
+    }
+
+    /// Hashes `addr`, as its offset in its module, or as its offset in its page if it is in none
+    fn hash_addr(&self, hasher: &mut AHasher, addr: u64) {
+        match self
+            .modules
+            .iter()
+            .find(|module| module.start <= addr && addr < module.end)
+        {
+            Some(module) => {
+                hasher.write(module.file_name().as_bytes());
+                hasher.write_u64(addr.wrapping_sub(module.base));
+            }
+            None => hasher.write_u64(addr & PAGE_OFFSET_MASK),
+        }
+    }
+
+    /// Computes the hash of the crash, from the signal and the backtrace, or the program counter
+    fn rehash(&mut self) {
+        let mut hasher = AHasher::new_with_keys(0, 0);
+        hasher.write_i32(self.signal.unwrap_or(0));
+        if self.backtrace.is_empty() {
+            if let Some(pc) = self.pc {
+                self.hash_addr(&mut hasher, pc);
+            }
+        } else {
+            for addr in &self.backtrace {
+                self.hash_addr(&mut hasher, *addr);
+            }
+        }
+        self.update_hash(hasher.finish());
+    }
+
+    /// Clears the state of the last crash
+    pub fn clear(&mut self) {
+        self.signal = None;
+        self.fault_addr = None;
+        self.pc = None;
+        self.registers.clear();
+        self.backtrace.clear();
+        self.modules.clear();
+        self.clear_hash();
+    }
+
+    /// The signal of the crash in the last run, if any
+    #[must_use]
+    pub fn signal(&self) -> Option<i32> {
+        self.signal
+    }
+
+    /// The faulting address of the crash in the last run, if the signal has one
+    #[must_use]
+    pub fn fault_addr(&self) -> Option<u64> {
+        self.fault_addr
+    }
+
+    /// The program counter at the crash in the last run, if known
+    #[must_use]
+    pub fn pc(&self) -> Option<u64> {
+        self.pc
+    }
+
+    /// The registers at the crash in the last run, by name, if known
+    #[must_use]
+    pub fn registers(&self) -> &[(String, u64)] {
+        &self.registers
+    }
+
+    /// The value of the register `name` at the crash in the last run, if known
+    #[must_use]
+    pub fn register(&self, name: &str) -> Option<u64> {
+        self.registers
+            .iter()
+            .find(|(register, _)| register == name)
+            .map(|(_, value)| *value)
+    }
+
+    /// The backtrace at the crash in the last run, the program counter then the return addresses
+    #[must_use]
+    pub fn backtrace(&self) -> &[u64] {
+        &self.backtrace
+    }
+
+    /// Guesses how exploitable the crash of the last run is, if the target crashed
+    #[cfg(unix)]
+    #[must_use]
+    pub fn classify(&self) -> Option<Exploitability> {
+        let signal = self.signal?;
+        Some(match (signal, self.fault_addr) {
+            (libc::SIGSEGV | libc::SIGBUS, Some(addr)) if Some(addr) == self.pc => {
+                Exploitability::Exploitable
+            }
+            (libc::SIGILL, _) => Exploitability::ProbablyExploitable,
+            (libc::SIGSEGV | libc::SIGBUS, Some(addr)) if addr < NULL_PAGE_END => {
+                Exploitability::ProbablyNotExploitable
+            }
+            (libc::SIGFPE, _) => Exploitability::NotExploitable,
+            _ => Exploitability::Unknown,
+        })
+    }
+
+    /// Guesses how exploitable the crash of the last run is, if the target crashed.
+    /// Only the signals of unix systems get classified.
+    #[cfg(not(unix))]
+    #[must_use]
+    pub fn classify(&self) -> Option<Exploitability> {
+        self.signal.map(|_| Exploitability::Unknown)
+    }
+}
+
+impl ObserverWithHashField for CrashContextObserver {
+    /// The hash of the crash in the last run, if the target crashed
+    fn hash(&self) -> &Option<u64> {
+        &self.hash
+    }
+
+    fn update_hash(&mut self, hash: u64) {
+        self.hash = Some(hash);
+    }
+
+    fn clear_hash(&mut self) {
+        self.hash = None;
+    }
+}
+
+impl Default for CrashContextObserver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I, S> Observer<I, S> for CrashContextObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.clear();
+        Ok(())
+    }
+
+    fn post_exec(&mut self, _state: &mut S, _input: &I, exit_kind: &ExitKind) -> Result<(), Error> {
+        // A crash nothing was captured of still needs a hash, not to be dropped
+        if *exit_kind == ExitKind::Crash && self.hash.is_none() {
+            self.rehash();
+        }
+        Ok(())
+    }
+}
+
+impl Named for CrashContextObserver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        executors::ExitKind,
+        observers::{
+            crash_context::{parse_proc_maps, CrashContextObserver},
+            Observer, ObserverWithHashField,
+        },
+    };
+
+    /// The maps of a target loaded at `base`
+    fn maps(base: u64) -> String {
+        format!(
+            "{:x}-{:x} r--p 00000000 08:01 42 /usr/bin/target\n\
+             {:x}-{:x} r-xp 00001000 08:01 42 /usr/bin/target\n\
+             7ffd0000-7ffd1000 rw-p 00000000 00:00 0 [stack]\n",
+            base,
+            base + 0x1000,
+            base + 0x1000,
+            base + 0x3000
+        )
+    }
+
+    /// The hash of a crash at `offset` in the code of a target loaded at `base`
+    #[cfg(unix)]
+    fn crash_hash(base: u64, offset: u64) -> u64 {
+        let mut observer = CrashContextObserver::new();
+        observer.observe_signal(libc::SIGSEGV, Some(0));
+        observer.observe_modules(parse_proc_maps(&maps(base)));
+        observer.observe_backtrace(vec![base + offset, base + 0x1800]);
+        observer.hash().unwrap()
+    }
+
+    #[test]
+    fn test_parse_proc_maps() {
+        let modules = parse_proc_maps(&maps(0x5555_0000_0000));
+        assert_eq!(modules.len(), 2);
+        assert_eq!(modules[1].start, 0x5555_0000_1000);
+        assert_eq!(modules[1].end, 0x5555_0000_3000);
+        assert_eq!(modules[1].base, 0x5555_0000_0000);
+        assert_eq!(modules[1].file_name(), "target");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_crash_hash() {
+        // The same under ASLR, different for pcs in the same page offset of other pages
+        assert_eq!(
+            crash_hash(0x5555_0000_0000, 0x1234),
+            crash_hash(0x5600_1234_0000, 0x1234)
+        );
+        assert_ne!(
+            crash_hash(0x5555_0000_0000, 0x1234),
+            crash_hash(0x5555_0000_0000, 0x2234)
+        );
+
+        // Without registers, e.g. on other architectures, the signal still gives a hash
+        let mut observer = CrashContextObserver::new();
+        observer.observe_signal(libc::SIGSEGV, None);
+        let segv = observer.hash().unwrap();
+        observer.observe_signal(libc::SIGBUS, None);
+        assert_ne!(observer.hash().unwrap(), segv);
+
+        // A crash nothing was captured of gets a hash too
+        Observer::<(), ()>::pre_exec(&mut observer, &mut (), &()).unwrap();
+        assert!(observer.hash().is_none());
+        Observer::<(), ()>::post_exec(&mut observer, &mut (), &(), &ExitKind::Crash).unwrap();
+        assert!(observer.hash().is_some());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_classify() {
+        use crate::observers::crash_context::Exploitability;
+
+        let mut observer = CrashContextObserver::new();
+        assert_eq!(observer.classify(), None);
+        observer.observe_registers(0x4141_4141, vec![]);
+        observer.observe_signal(libc::SIGSEGV, Some(0x4141_4141));
+        assert_eq!(observer.classify(), Some(Exploitability::Exploitable));
+        observer.observe_signal(libc::SIGBUS, Some(0x10));
+        assert_eq!(
+            observer.classify(),
+            Some(Exploitability::ProbablyNotExploitable)
+        );
+        observer.observe_signal(libc::SIGILL, None);
+        assert_eq!(
+            observer.classify(),
+            Some(Exploitability::ProbablyExploitable)
+        );
+        observer.observe_signal(libc::SIGFPE, None);
+        assert_eq!(observer.classify(), Some(Exploitability::NotExploitable));
+        observer.observe_signal(libc::SIGABRT, None);
+        assert_eq!(observer.classify(), Some(Exploitability::Unknown));
+    }
+}
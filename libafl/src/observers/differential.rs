@@ -0,0 +1,86 @@
+//! The [`DiffObserver`] keeps how the two runs of a [`crate::executors::DiffExecutor`] went, for
+//! the [`crate::feedbacks::DiffFeedback`] to report the inputs on which the targets disagree.
+
+use alloc::string::{String, ToString};
+use serde::{Deserialize, Serialize};
+
+use crate::{bolts::tuples::Named, executors::ExitKind, observers::Observer, Error};
+
+/// The name of the [`DiffObserver`], by which the [`crate::executors::DiffExecutor`] finds it
+pub const DIFF_OBSERVER_NAME: &str = "DiffObserver";
+
+/// An observer keeping the [`ExitKind`]s, and the hashes of the stdout, if captured, of the
+/// primary and of the secondary target of a [`crate::executors::DiffExecutor`].
+/// It goes into the observers of the primary executor.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiffObserver {
+    name: String,
+    exit_kinds: Option<(ExitKind, ExitKind)>,
+    stdout_hashes: (Option<u64>, Option<u64>),
+}
+
+impl DiffObserver {
+    /// Creates a new [`DiffObserver`], named [`DIFF_OBSERVER_NAME`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            name: DIFF_OBSERVER_NAME.to_string(),
+            exit_kinds: None,
+            stdout_hashes: (None, None),
+        }
+    }
+
+    /// Sets the [`ExitKind`]s of the primary and of the secondary target
+    pub fn observe_exit_kinds(&mut self, primary: ExitKind, secondary: ExitKind) {
+        self.exit_kinds = Some((primary, secondary));
+    }
+
+    /// Sets the hashes of the stdout of the primary and of the secondary target, if captured
+    pub fn observe_stdout_hashes(&mut self, primary: Option<u64>, secondary: Option<u64>) {
+        self.stdout_hashes = (primary, secondary);
+    }
+
+    /// The [`ExitKind`]s of the primary and of the secondary target in the last run
+    #[must_use]
+    pub fn exit_kinds(&self) -> Option<(ExitKind, ExitKind)> {
+        self.exit_kinds
+    }
+
+    /// The hashes of the stdout of the primary and of the secondary target in the last run
+    #[must_use]
+    pub fn stdout_hashes(&self) -> (Option<u64>, Option<u64>) {
+        self.stdout_hashes
+    }
+
+    /// If the targets exited differently in the last run
+    #[must_use]
+    pub fn exit_kinds_differ(&self) -> bool {
+        matches!(self.exit_kinds, Some((primary, secondary)) if primary != secondary)
+    }
+
+    /// If both targets had their stdout captured, and it differed, in the last run
+    #[must_use]
+    pub fn stdout_differs(&self) -> bool {
+        matches!(self.stdout_hashes, (Some(primary), Some(secondary)) if primary != secondary)
+    }
+}
+
+impl Default for DiffObserver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I, S> Observer<I, S> for DiffObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.exit_kinds = None;
+        self.stdout_hashes = (None, None);
+        Ok(())
+    }
+}
+
+impl Named for DiffObserver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
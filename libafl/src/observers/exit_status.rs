@@ -0,0 +1,176 @@
+//! The [`ExitStatusObserver`] keeps how the target terminated in the last run, the terminating
+//! signal or the exit code, for crash triage to tell a `SIGSEGV` from an `abort` or an `exit(1)`.
+//! The executors fill it, when found in their observers.
+
+use alloc::string::{String, ToString};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::{MatchName, Named},
+    observers::Observer,
+    Error,
+};
+
+/// The name of the [`ExitStatusObserver`], by which the executors find it
+pub const EXIT_STATUS_OBSERVER_NAME: &str = "ExitStatusObserver";
+
+/// An observer keeping the terminating signal, or the exit code, of the target in the last run,
+/// and if a sanitizer reported an error.
+/// The in-process executors only know the signal of a crash, the executors running a separate
+/// process know the exit code too, and if a sanitizer reported an error as far as they see its
+/// report, i.e. in the captured stderr or in the `ASan` log.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExitStatusObserver {
+    name: String,
+    signal: Option<i32>,
+    exit_code: Option<i32>,
+    sanitizer_abort: bool,
+}
+
+impl ExitStatusObserver {
+    /// Creates a new [`ExitStatusObserver`], named [`EXIT_STATUS_OBSERVER_NAME`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            name: EXIT_STATUS_OBSERVER_NAME.to_string(),
+            signal: None,
+            exit_code: None,
+            sanitizer_abort: false,
+        }
+    }
+
+    /// Sets the terminating signal, or the exit code, of the last run, and if a sanitizer reported
+    /// an error
+    pub fn observe(&mut self, signal: Option<i32>, exit_code: Option<i32>, sanitizer_abort: bool) {
+        self.signal = signal;
+        self.exit_code = exit_code;
+        self.sanitizer_abort = sanitizer_abort;
+    }
+
+    /// The signal that terminated the target in the last run, if any
+    #[must_use]
+    pub fn signal(&self) -> Option<i32> {
+        self.signal
+    }
+
+    /// The exit code of the target in the last run, if it exited and the executor knows it
+    #[must_use]
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// If a sanitizer reported an error in the last run
+    #[must_use]
+    pub fn sanitizer_abort(&self) -> bool {
+        self.sanitizer_abort
+    }
+}
+
+impl Default for ExitStatusObserver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I, S> Observer<I, S> for ExitStatusObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.observe(None, None, false);
+        Ok(())
+    }
+}
+
+impl Named for ExitStatusObserver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// If `output` contains the error report of a sanitizer, e.g.
+/// `==1234==ERROR: AddressSanitizer: heap-buffer-overflow`
+#[must_use]
+pub fn is_sanitizer_report(output: &str) -> bool {
+    output.lines().any(|line| {
+        (line.contains("ERROR: ") || line.contains("WARNING: ")) && line.contains("Sanitizer: ")
+            || line.contains(": runtime error: ")
+    })
+}
+
+/// If `output` holds the out-of-memory report of a sanitizer, e.g.
+/// `==1234==ERROR: libFuzzer: out-of-memory (malloc(2147483648))` or
+/// `==1234==ERROR: AddressSanitizer: out of memory: allocator is trying to allocate 0x80000000 bytes`
+#[must_use]
+pub fn is_oom_report(output: &str) -> bool {
+    output.lines().any(|line| {
+        // The sanitizers prefix their reports with the pid, as in `==1234==`
+        let line = match line.strip_prefix("==") {
+            Some(rest) => rest.split_once("==").map_or(rest, |(_, report)| report),
+            None => line,
+        };
+        let error = match line.trim_start().strip_prefix("ERROR: ") {
+            Some(error) => error,
+            None => return false,
+        };
+        if error.starts_with("libFuzzer: out-of-memory") {
+            return true;
+        }
+        matches!(error.split_once("Sanitizer: "), Some((sanitizer, report))
+            if !sanitizer.contains(' ')
+                && (report.starts_with("out of memory") || report.starts_with("out-of-memory")))
+    })
+}
+
+/// The terminating signal, or the exit code, in the raw `status` returned by `waitpid`
+#[cfg(unix)]
+pub(crate) fn split_wait_status(status: i32) -> (Option<i32>, Option<i32>) {
+    if libc::WIFSIGNALED(status) {
+        (Some(libc::WTERMSIG(status)), None)
+    } else if libc::WIFEXITED(status) {
+        (None, Some(libc::WEXITSTATUS(status)))
+    } else {
+        (None, None)
+    }
+}
+
+/// Fills the [`ExitStatusObserver`] in `observers`, if any
+pub(crate) fn observe_exit_status<OT>(
+    observers: &mut OT,
+    signal: Option<i32>,
+    exit_code: Option<i32>,
+    sanitizer_abort: bool,
+) where
+    OT: MatchName,
+{
+    if let Some(observer) =
+        observers.match_name_mut::<ExitStatusObserver>(EXIT_STATUS_OBSERVER_NAME)
+    {
+        observer.observe(signal, exit_code, sanitizer_abort);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_oom_report;
+
+    #[test]
+    fn test_oom_report() {
+        assert!(is_oom_report(
+            "INFO: Seed: 1\n==1234== ERROR: libFuzzer: out-of-memory (malloc(2147483648))\n"
+        ));
+        assert!(is_oom_report(
+            "==42==ERROR: AddressSanitizer: out of memory: allocator is trying to allocate 0x80000000 bytes\n"
+        ));
+        assert!(is_oom_report(
+            "ERROR: MemorySanitizer: out of memory: allocator is trying to allocate 0x1 bytes"
+        ));
+        assert!(!is_oom_report("Error: the parser ran out of memory\n"));
+        assert!(!is_oom_report(
+            "out-of-memory\nterminate called after throwing an instance of 'std::bad_alloc'\n"
+        ));
+        assert!(!is_oom_report(
+            "==42==ERROR: AddressSanitizer: heap-buffer-overflow, then out of memory\n"
+        ));
+        assert!(!is_oom_report(
+            "log: ERROR: AddressSanitizer: out of memory\n"
+        ));
+    }
+}
@@ -0,0 +1,110 @@
+//! The [`IntelPTObserver`] collects the coverage of uninstrumented native targets with Intel PT
+
+use alloc::string::{String, ToString};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{
+        os::intel_pt::{IntelPT, INTEL_PT_DEFAULT_AUX_PAGES},
+        ownedref::OwnedSliceMut,
+        tuples::Named,
+        AsMutSlice,
+    },
+    executors::ExitKind,
+    observers::Observer,
+    Error,
+};
+
+/// An observer decoding the Intel PT trace of each run into an edge map.
+///
+/// The observer does not reset the map: pair it with a map observer (such as a
+/// [`crate::observers::HitcountsMapObserver`]) on the same map, placed after it in the observers
+/// tuple, so that the map gets reset before the run and classified after the decoding.
+///
+/// Use [`IntelPTObserver::trace_self`] with in-process executors. Otherwise, the
+/// [`crate::executors::IntelPTCommandExecutor`] attaches a tracer to each child it spawns.
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(clippy::unsafe_derive_deserialize)]
+pub struct IntelPTObserver<'a> {
+    name: String,
+    map: OwnedSliceMut<'a, u8>,
+    /// The tracer of the current run
+    #[serde(skip)]
+    pt: Option<IntelPT>,
+    /// If the tracer follows this process, and is kept across runs
+    trace_self: bool,
+}
+
+impl<'a> IntelPTObserver<'a> {
+    /// Creates a new [`IntelPTObserver`] writing into `map`, waiting for an executor to attach
+    /// a tracer with [`IntelPTObserver::attach`] before each run
+    #[must_use]
+    pub fn new(name: &str, map: &'a mut [u8]) -> Self {
+        Self {
+            name: name.to_string(),
+            map: OwnedSliceMut::from(map),
+            pt: None,
+            trace_self: false,
+        }
+    }
+
+    /// Creates a new [`IntelPTObserver`] writing into `map` and tracing the current process,
+    /// for in-process executors. Restrict the trace to the target with [`IntelPTObserver::set_filter`].
+    pub fn trace_self(name: &str, map: &'a mut [u8]) -> Result<Self, Error> {
+        let mut observer = Self::new(name, map);
+        observer.pt = Some(IntelPT::new(0, INTEL_PT_DEFAULT_AUX_PAGES)?);
+        observer.trace_self = true;
+        Ok(observer)
+    }
+
+    /// Restricts the tracing of the current process, see [`IntelPT::set_filter`]
+    pub fn set_filter(&mut self, filter: &str) -> Result<(), Error> {
+        match &mut self.pt {
+            Some(pt) if self.trace_self => pt.set_filter(filter),
+            _ => Err(Error::IllegalState(
+                "Only observers tracing the current process have a filter".into(),
+            )),
+        }
+    }
+
+    /// Attaches the (enabled) tracer of the upcoming run, the trace gets decoded after the run
+    pub fn attach(&mut self, pt: IntelPT) {
+        self.pt = Some(pt);
+    }
+}
+
+impl<'a> Named for IntelPTObserver<'a> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<'a, I, S> Observer<I, S> for IntelPTObserver<'a> {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        if self.trace_self {
+            if let Some(pt) = &mut self.pt {
+                pt.reset()?;
+                pt.enable()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &I,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        if let Some(pt) = &mut self.pt {
+            pt.disable()?;
+            pt.decode_into(self.map.as_mut_slice())?;
+        }
+        if !self.trace_self {
+            // The traced child is gone
+            self.pt = None;
+        }
+        Ok(())
+    }
+}
@@ -8,7 +8,8 @@ use alloc::{
 use core::{
     fmt::Debug,
     hash::Hasher,
-    iter::Flatten,
+    iter::{self, Flatten},
+    ops::Range,
     slice::{from_raw_parts, Iter, IterMut},
 };
 use intervaltree::IntervalTree;
@@ -646,7 +647,8 @@ where
     }
 }
 
-/// Map observer with hitcounts postprocessing
+/// Map observer with hitcounts postprocessing: the count of hits of each entry gets replaced by
+/// its bucket, see [`HitcountBuckets`]
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(bound = "M: serde::de::DeserializeOwned")]
 pub struct HitcountsMapObserver<M>
@@ -654,6 +656,65 @@ where
     M: Serialize + serde::de::DeserializeOwned,
 {
     base: M,
+    #[serde(default)]
+    buckets: HitcountBuckets,
+    /// The lookup table of the buckets, computed once, and again after deserialization
+    #[serde(skip)]
+    lookup: Option<[u8; 256]>,
+}
+
+/// The buckets of the counts of hits of a [`HitcountsMapObserver`].
+///
+/// The buckets grow with the counts, and `0` stays `0`, for the `MaxMapFeedback` to find a higher
+/// bucket novel. The coarser the buckets, the less sensitive the fuzzer is to the counts of
+/// iterations of loops. Only the buckets of [`HitcountBuckets::Afl`] and
+/// [`HitcountBuckets::Log2`] are distinct bits, as needed by the `AflMapFeedback`, or-ing them.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HitcountBuckets {
+    /// The buckets of AFL: 1, 2, 3, 4-7, 8-15, 16-31, 32-127, 128-255
+    Afl,
+    /// A bucket per power of two: 1, 2-3, 4-7, 8-15, ..., 128-255
+    Log2,
+    /// Buckets of a fixed width, at least 1, e.g. 1-4, 5-8, ... for a width of 4
+    Linear(u8),
+    /// The raw counts, up to a maximum of at least 1, e.g. 1, 2, ..., 15, 16-255 for a maximum
+    /// of 16
+    Saturating(u8),
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for HitcountBuckets {
+    fn default() -> Self {
+        Self::Afl
+    }
+}
+
+impl HitcountBuckets {
+    /// The bucket of each count of hits
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)] // counts are below 256
+    pub fn lookup(&self) -> [u8; 256] {
+        match *self {
+            Self::Afl => COUNT_CLASS_LOOKUP,
+            Self::Log2 => Self::table(|count| ((count + 1).next_power_of_two() / 2) as u8),
+            Self::Linear(width) => {
+                Self::table(|count| ((count - 1) / usize::from(width.max(1)) + 1) as u8)
+            }
+            Self::Saturating(max) => Self::table(|count| count.min(usize::from(max)) as u8),
+        }
+    }
+
+    /// The lookup table of the bucket of each count of hits but `0`, which stays `0`
+    fn table<F>(bucket: F) -> [u8; 256]
+    where
+        F: Fn(usize) -> u8,
+    {
+        let mut lookup = [0; 256];
+        for (count, entry) in lookup.iter_mut().enumerate().skip(1) {
+            *entry = bucket(count);
+        }
+        lookup
+    }
 }
 
 static COUNT_CLASS_LOOKUP: [u8; 256] = [
@@ -682,9 +743,11 @@ where
 
     #[inline]
     fn post_exec(&mut self, state: &mut S, input: &I, exit_kind: &ExitKind) -> Result<(), Error> {
-        let cnt = self.usable_count();
+        let buckets = self.buckets;
+        let lookup = self.lookup.get_or_insert_with(|| buckets.lookup());
+        let cnt = self.base.usable_count();
         for i in 0..cnt {
-            *self.get_mut(i) = COUNT_CLASS_LOOKUP[*self.get(i) as usize];
+            *self.base.get_mut(i) = lookup[*self.base.get(i) as usize];
         }
         self.base.post_exec(state, input, exit_kind)
     }
@@ -777,9 +840,211 @@ impl<M> HitcountsMapObserver<M>
 where
     M: Serialize + serde::de::DeserializeOwned,
 {
-    /// Creates a new [`MapObserver`]
+    /// Creates a new [`MapObserver`], with the [`HitcountBuckets::Afl`] buckets
     pub fn new(base: M) -> Self {
-        Self { base }
+        Self {
+            base,
+            buckets: HitcountBuckets::Afl,
+            lookup: Some(COUNT_CLASS_LOOKUP),
+        }
+    }
+
+    /// Sets the buckets of the counts of hits.
+    /// Fails for [`HitcountBuckets::Saturating`] with a maximum of `0`, putting all the counts
+    /// in the bucket of no hits.
+    pub fn with_buckets(mut self, buckets: HitcountBuckets) -> Result<Self, Error> {
+        if buckets == HitcountBuckets::Saturating(0) {
+            return Err(Error::IllegalArgument(
+                "The maximum of saturating hitcount buckets must be at least 1".into(),
+            ));
+        }
+        self.buckets = buckets;
+        self.lookup = Some(buckets.lookup());
+        Ok(self)
+    }
+
+    /// The buckets of the counts of hits
+    #[must_use]
+    pub fn buckets(&self) -> HitcountBuckets {
+        self.buckets
+    }
+}
+
+/// Map observer only keeping the entries of some indexes of the map of its base observer, e.g. the
+/// edges of the library under test but not of the harness or the libc: the other entries are
+/// reset after each run, for the feedbacks not to find them novel.
+/// The filter is not serialized, not to send it along with every event: a deserialized observer
+/// keeps all the entries.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(bound = "M: serde::de::DeserializeOwned")]
+pub struct FilteredMapObserver<M>
+where
+    M: Serialize + serde::de::DeserializeOwned,
+{
+    base: M,
+    #[serde(skip)]
+    kept: Vec<bool>,
+}
+
+impl<I, S, M> Observer<I, S> for FilteredMapObserver<M>
+where
+    M: MapObserver + Observer<I, S>,
+{
+    #[inline]
+    fn pre_exec(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        self.base.pre_exec(state, input)
+    }
+
+    #[inline]
+    fn post_exec(&mut self, state: &mut S, input: &I, exit_kind: &ExitKind) -> Result<(), Error> {
+        self.base.post_exec(state, input, exit_kind)?;
+        let initial = self.base.initial();
+        let cnt = self.base.usable_count().min(self.kept.len());
+        for i in 0..cnt {
+            if !self.kept[i] {
+                *self.base.get_mut(i) = initial;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<M> Named for FilteredMapObserver<M>
+where
+    M: Named + Serialize + serde::de::DeserializeOwned,
+{
+    #[inline]
+    fn name(&self) -> &str {
+        self.base.name()
+    }
+}
+
+impl<M> HasLen for FilteredMapObserver<M>
+where
+    M: MapObserver,
+{
+    #[inline]
+    fn len(&self) -> usize {
+        self.base.len()
+    }
+}
+
+impl<M> MapObserver for FilteredMapObserver<M>
+where
+    M: MapObserver,
+{
+    type Entry = M::Entry;
+
+    #[inline]
+    fn initial(&self) -> M::Entry {
+        self.base.initial()
+    }
+
+    #[inline]
+    fn initial_mut(&mut self) -> &mut M::Entry {
+        self.base.initial_mut()
+    }
+
+    #[inline]
+    fn set_initial(&mut self, initial: M::Entry) {
+        self.base.set_initial(initial);
+    }
+
+    #[inline]
+    fn usable_count(&self) -> usize {
+        self.base.usable_count()
+    }
+
+    #[inline]
+    fn get(&self, idx: usize) -> &M::Entry {
+        self.base.get(idx)
+    }
+
+    #[inline]
+    fn get_mut(&mut self, idx: usize) -> &mut M::Entry {
+        self.base.get_mut(idx)
+    }
+
+    fn hash(&self) -> u64 {
+        self.base.hash()
+    }
+
+    fn to_vec(&self) -> Vec<M::Entry> {
+        self.base.to_vec()
+    }
+}
+
+impl<M, T> AsSlice<T> for FilteredMapObserver<M>
+where
+    M: MapObserver + AsSlice<T>,
+{
+    #[inline]
+    fn as_slice(&self) -> &[T] {
+        self.base.as_slice()
+    }
+}
+
+impl<M, T> AsMutSlice<T> for FilteredMapObserver<M>
+where
+    M: MapObserver + AsMutSlice<T>,
+{
+    #[inline]
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        self.base.as_mut_slice()
+    }
+}
+
+impl<M> FilteredMapObserver<M>
+where
+    M: MapObserver,
+{
+    /// Creates a new [`FilteredMapObserver`], only keeping the entries of the `allowed` indexes
+    /// of the map of `base`
+    pub fn new(base: M, allowed: &[usize]) -> Self {
+        let mut kept = vec![false; base.len()];
+        for &idx in allowed {
+            if let Some(entry) = kept.get_mut(idx) {
+                *entry = true;
+            }
+        }
+        Self { base, kept }
+    }
+
+    /// Creates a new [`FilteredMapObserver`], keeping the entries of the map of `base` but the
+    /// ones of the `denied` indexes
+    pub fn new_denying(base: M, denied: &[usize]) -> Self {
+        let mut kept = vec![true; base.len()];
+        for &idx in denied {
+            if let Some(entry) = kept.get_mut(idx) {
+                *entry = false;
+            }
+        }
+        Self { base, kept }
+    }
+
+    /// If the entry of the index `idx` is kept
+    #[must_use]
+    pub fn is_kept(&self, idx: usize) -> bool {
+        if self.kept.is_empty() {
+            // Deserialized, without a filter
+            return idx < self.base.len();
+        }
+        self.kept.get(idx).copied().unwrap_or(false)
+    }
+
+    /// The number of kept entries
+    #[must_use]
+    pub fn kept_count(&self) -> usize {
+        if self.kept.is_empty() {
+            return self.base.len();
+        }
+        self.kept.iter().filter(|kept| **kept).count()
+    }
+
+    /// The observer whose map gets filtered
+    #[must_use]
+    pub fn base(&self) -> &M {
+        &self.base
     }
 }
 
@@ -885,7 +1150,7 @@ where
         for map in &self.maps {
             let slice = map.as_slice();
             let ptr = slice.as_ptr() as *const u8;
-            let map_size = slice.len() / core::mem::size_of::<T>();
+            let map_size = core::mem::size_of_val(slice);
             unsafe {
                 hasher.write(from_raw_parts(ptr, map_size));
             }
@@ -893,6 +1158,13 @@ where
         hasher.finish()
     }
 
+    fn to_vec(&self) -> Vec<T> {
+        self.maps
+            .iter()
+            .flat_map(|map| map.as_slice().iter().copied())
+            .collect()
+    }
+
     fn reset_map(&mut self) -> Result<(), Error> {
         let initial = self.initial();
         for map in &mut self.maps {
@@ -915,64 +1187,74 @@ where
     /// Creates a new [`MultiMapObserver`]
     #[must_use]
     pub fn new(name: &'static str, maps: &'a mut [&'a mut [T]]) -> Self {
-        let mut idx = 0;
-        let mut v = 0;
-        let mut initial = T::default();
-        let mut builder = vec![];
-        let maps: Vec<_> = maps
-            .iter_mut()
-            .map(|x| {
-                if !x.is_empty() {
-                    initial = x[0];
-                }
-                let l = x.len();
-                let r = (idx..(idx + l), v);
-                idx += l;
-                builder.push(r);
-                v += 1;
-                OwnedSliceMut::from(x)
-            })
-            .collect();
-        Self {
-            maps,
-            intervals: builder.into_iter().collect::<IntervalTree<usize, usize>>(),
-            len: idx,
-            name: name.to_string(),
-            initial,
-            iter_idx: 0,
+        let mut observer = Self::empty(name);
+        for map in maps.iter_mut() {
+            observer.push_map(OwnedSliceMut::from(map));
         }
+        observer
     }
 
     /// Creates a new [`MultiMapObserver`] with an owned map
     #[must_use]
     pub fn new_owned(name: &'static str, maps: Vec<Vec<T>>) -> Self {
-        let mut idx = 0;
-        let mut v = 0;
-        let mut initial = T::default();
-        let mut builder = vec![];
-        let maps: Vec<_> = maps
-            .into_iter()
-            .map(|x| {
-                if !x.is_empty() {
-                    initial = x[0];
-                }
-                let l = x.len();
-                let r = (idx..(idx + l), v);
-                idx += l;
-                builder.push(r);
-                v += 1;
-                OwnedSliceMut::from(x)
-            })
-            .collect();
+        let mut observer = Self::empty(name);
+        for map in maps {
+            observer.push_map(OwnedSliceMut::from(map));
+        }
+        observer
+    }
+
+    /// Creates a new [`MultiMapObserver`] without maps
+    fn empty(name: &'static str) -> Self {
         Self {
-            maps,
-            intervals: builder.into_iter().collect::<IntervalTree<usize, usize>>(),
-            len: idx,
+            maps: vec![],
+            intervals: iter::empty::<(Range<usize>, usize)>().collect(),
+            len: 0,
             name: name.to_string(),
-            initial,
+            initial: T::default(),
             iter_idx: 0,
         }
     }
+
+    /// Adds `map` after the maps of the observer, returning the index of its first entry in the
+    /// observer, which spans its entries and the entries of the maps before it
+    fn push_map(&mut self, map: OwnedSliceMut<'a, T>) -> usize {
+        let offset = self.len;
+        let len = map.as_slice().len();
+        if len > 0 {
+            self.initial = map.as_slice()[0];
+        }
+        let mut intervals: Vec<_> = self
+            .intervals
+            .iter()
+            .map(|elem| (elem.range.clone(), elem.value))
+            .collect();
+        intervals.push((offset..(offset + len), self.maps.len()));
+        self.intervals = intervals.into_iter().collect();
+        self.maps.push(map);
+        self.len += len;
+        offset
+    }
+
+    /// Adds `map` after the maps of the observer, e.g. the map of a plugin `dlopen`ed in the
+    /// meantime, or of a new thread, returning the index of its first entry in the observer.
+    /// The indexes of the entries of the other maps stay the same, but the observer grows: the
+    /// history map of its `MapFeedbackState` must be large enough for all its maps.
+    pub fn add_map(&mut self, map: &'a mut [T]) -> usize {
+        self.push_map(OwnedSliceMut::from(map))
+    }
+
+    /// The index in the observer of the first entry of its `i`-th map, if any
+    #[must_use]
+    pub fn map_offset(&self, i: usize) -> Option<usize> {
+        (i < self.maps.len()).then(|| self.maps[..i].iter().map(|map| map.as_slice().len()).sum())
+    }
+
+    /// The number of maps of the observer
+    #[must_use]
+    pub fn maps_len(&self) -> usize {
+        self.maps.len()
+    }
 }
 
 impl<'a, 'it, T> IntoIterator for &'it mut MultiMapObserver<'a, T>
@@ -0,0 +1,126 @@
+//! The [`MemUsageObserver`] keeps the peak resident set size of the target in the last run, for
+//! the [`crate::feedbacks::MemUsageFeedback`] to report the inputs exhausting the memory.
+//! The executors reaping the process of the target, the `CommandExecutor` and the
+//! `InProcessForkExecutor`, fill it, when found in their observers.
+
+use alloc::string::{String, ToString};
+use core::{mem::MaybeUninit, ptr::addr_of_mut};
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::{MatchName, Named},
+    observers::Observer,
+    Error,
+};
+
+/// The name of the [`MemUsageObserver`], by which the executors find it
+pub const MEM_USAGE_OBSERVER_NAME: &str = "MemUsageObserver";
+
+/// An observer keeping the peak resident set size, in bytes, of the process of the target in the
+/// last run, as reported by `wait4`.
+/// The executors running the target in the process of the fuzzer, or in a process they do not
+/// reap, e.g. the children of a forkserver, leave it empty.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MemUsageObserver {
+    name: String,
+    max_rss: Option<u64>,
+}
+
+impl MemUsageObserver {
+    /// Creates a new [`MemUsageObserver`], named [`MEM_USAGE_OBSERVER_NAME`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            name: MEM_USAGE_OBSERVER_NAME.to_string(),
+            max_rss: None,
+        }
+    }
+
+    /// Sets the peak resident set size, in bytes, of the last run
+    pub fn observe(&mut self, max_rss: u64) {
+        self.max_rss = Some(max_rss);
+    }
+
+    /// The peak resident set size, in bytes, of the last run, if known
+    #[must_use]
+    pub fn max_rss(&self) -> Option<u64> {
+        self.max_rss
+    }
+}
+
+impl Default for MemUsageObserver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I, S> Observer<I, S> for MemUsageObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.max_rss = None;
+        Ok(())
+    }
+}
+
+impl Named for MemUsageObserver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Waits for the child `pid` with `wait4`, with the `options` of `waitpid`, returning its raw
+/// wait status and its peak resident set size, in bytes, or `None` if it did not terminate yet,
+/// with `WNOHANG`.
+#[allow(clippy::cast_sign_loss)]
+pub(crate) fn wait_with_max_rss(pid: i32, options: i32) -> Result<Option<(i32, u64)>, Error> {
+    let mut status = 0;
+    let mut rusage = MaybeUninit::<libc::rusage>::zeroed();
+    loop {
+        match unsafe { libc::wait4(pid, addr_of_mut!(status), options, rusage.as_mut_ptr()) } {
+            -1 => {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(Error::from(err));
+                }
+            }
+            0 => return Ok(None),
+            _ => break,
+        }
+    }
+    let max_rss = unsafe { rusage.assume_init() }.ru_maxrss as u64;
+    // Linux reports it in KiB, macOS in bytes
+    #[cfg(not(target_vendor = "apple"))]
+    let max_rss = max_rss * 1024;
+    Ok(Some((status, max_rss)))
+}
+
+/// Fills the [`MemUsageObserver`] in `observers`, if any, with the peak resident set size of the
+/// run, in bytes
+pub(crate) fn observe_mem_usage<OT>(observers: &mut OT, max_rss: u64)
+where
+    OT: MatchName,
+{
+    if let Some(observer) = observers.match_name_mut::<MemUsageObserver>(MEM_USAGE_OBSERVER_NAME) {
+        observer.observe(max_rss);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use crate::observers::mem_usage::wait_with_max_rss;
+
+    #[test]
+    fn test_wait_with_max_rss() {
+        let mut child = Command::new("sh").args(["-c", "exit 3"]).spawn().unwrap();
+        #[allow(clippy::cast_possible_wrap)]
+        let (status, max_rss) = wait_with_max_rss(child.id() as i32, 0).unwrap().unwrap();
+        // Already reaped
+        assert!(child.wait().is_err());
+        assert!(libc::WIFEXITED(status));
+        assert_eq!(libc::WEXITSTATUS(status), 3);
+        assert!(max_rss > 0);
+    }
+}
@@ -13,8 +13,43 @@ pub use stacktrace::ASANBacktraceObserver;
 #[cfg(feature = "std")]
 pub use stacktrace::*;
 
+#[cfg(feature = "std")]
+pub mod stdio;
+#[cfg(feature = "std")]
+pub use stdio::{StdErrObserver, StdOutObserver, STDERR_OBSERVER_NAME, STDOUT_OBSERVER_NAME};
+
+#[cfg(feature = "std")]
+pub mod sanitizer;
+#[cfg(feature = "std")]
+pub use sanitizer::{AsanReportMetadata, SanitizerReportObserver, SANITIZER_REPORT_OBSERVER_NAME};
+
+pub mod differential;
+pub use differential::{DiffObserver, DIFF_OBSERVER_NAME};
+
+pub mod exit_status;
+pub use exit_status::{ExitStatusObserver, EXIT_STATUS_OBSERVER_NAME};
+
+#[cfg(all(feature = "std", unix))]
+pub mod mem_usage;
+#[cfg(all(feature = "std", unix))]
+pub use mem_usage::{MemUsageObserver, MEM_USAGE_OBSERVER_NAME};
+
+pub mod stack_depth;
+pub use stack_depth::StackDepthObserver;
+
+pub mod crash_context;
+pub use crash_context::{
+    parse_proc_maps, CrashContextObserver, Exploitability, MappedModule,
+    CRASH_CONTEXT_OBSERVER_NAME,
+};
+
 pub mod concolic;
 
+#[cfg(all(feature = "std", target_os = "linux", target_arch = "x86_64"))]
+pub mod intel_pt;
+#[cfg(all(feature = "std", target_os = "linux", target_arch = "x86_64"))]
+pub use intel_pt::IntelPTObserver;
+
 #[cfg(unstable_feature)]
 pub mod owned;
 #[cfg(unstable_feature)]
@@ -231,9 +266,20 @@ impl Named for TimeObserver {
 mod tests {
 
     use crate::{
-        bolts::tuples::{tuple_list, tuple_list_type, Named},
-        observers::{StdMapObserver, TimeObserver},
+        bolts::{
+            tuples::{tuple_list, tuple_list_type, Named},
+            AsMutSlice, AsSlice, HasLen,
+        },
+        executors::ExitKind,
+        observers::{
+            CmpMap, CmpObserver, CmpValues, FilteredMapObserver, HitcountBuckets,
+            HitcountsMapObserver, MapObserver, MultiMapObserver, Observer, StdCmpObserver,
+            StdMapObserver, TimeObserver,
+        },
+        Error,
     };
+    use alloc::vec::Vec;
+    use serde::{Deserialize, Serialize};
 
     static mut MAP: [u32; 4] = [0; 4];
 
@@ -249,4 +295,171 @@ mod tests {
             postcard::from_bytes(&vec).unwrap();
         assert_eq!(obv.0.name(), obv2.0.name());
     }
+
+    #[test]
+    fn test_multi_map_observer() {
+        let mut first = vec![0_u16; 3];
+        let mut second = vec![0_u16; 2];
+        let mut observer = MultiMapObserver::new_owned("multi", vec![vec![0; 4]]);
+        assert_eq!(observer.add_map(&mut first), 4);
+        assert_eq!(observer.add_map(&mut second), 7);
+        assert_eq!(observer.map_offset(2), Some(7));
+        assert_eq!(observer.map_offset(3), None);
+        assert_eq!(observer.len(), 9);
+
+        let hash = observer.hash();
+        *observer.get_mut(8) = 1;
+        assert_ne!(observer.hash(), hash);
+        assert_eq!(observer.to_vec(), vec![0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        drop(observer);
+        assert_eq!(second[1], 1);
+    }
+
+    #[test]
+    fn test_hitcount_buckets() {
+        for buckets in [
+            HitcountBuckets::Afl,
+            HitcountBuckets::Log2,
+            HitcountBuckets::Linear(4),
+            HitcountBuckets::Saturating(16),
+        ] {
+            let lookup = buckets.lookup();
+            assert_eq!(lookup[0], 0);
+            assert!(lookup.windows(2).all(|pair| pair[0] <= pair[1]));
+        }
+        let log2 = HitcountBuckets::Log2.lookup();
+        assert_eq!((log2[1], log2[3], log2[4], log2[255]), (1, 2, 4, 128));
+        let linear = HitcountBuckets::Linear(4).lookup();
+        assert_eq!((linear[4], linear[5], linear[255]), (1, 2, 64));
+        let saturating = HitcountBuckets::Saturating(16).lookup();
+        assert_eq!((saturating[15], saturating[200]), (15, 16));
+
+        assert!(
+            HitcountsMapObserver::new(StdMapObserver::new_owned("map", vec![0_u8; 4]))
+                .with_buckets(HitcountBuckets::Saturating(0))
+                .is_err()
+        );
+        let mut observer =
+            HitcountsMapObserver::new(StdMapObserver::new_owned("map", vec![0_u8; 4]))
+                .with_buckets(HitcountBuckets::Log2)
+                .unwrap();
+        observer.as_mut_slice().copy_from_slice(&[0, 3, 5, 200]);
+        Observer::<(), ()>::post_exec(&mut observer, &mut (), &(), &ExitKind::Ok).unwrap();
+        assert_eq!(observer.as_slice(), [0, 2, 4, 128]);
+
+        // The lookup table is not serialized, but computed again
+        let serialized = postcard::to_allocvec(&observer).unwrap();
+        let mut observer: HitcountsMapObserver<StdMapObserver<u8>> =
+            postcard::from_bytes(&serialized).unwrap();
+        assert_eq!(observer.buckets(), HitcountBuckets::Log2);
+        observer.as_mut_slice().copy_from_slice(&[0, 3, 5, 200]);
+        Observer::<(), ()>::post_exec(&mut observer, &mut (), &(), &ExitKind::Ok).unwrap();
+        assert_eq!(observer.as_slice(), [0, 2, 4, 128]);
+    }
+
+    #[test]
+    fn test_filtered_map_observer() {
+        let base = StdMapObserver::new_owned("map", vec![0_u8; 4]);
+        let mut observer = FilteredMapObserver::new(base, &[1, 3, 7]);
+        assert_eq!(observer.kept_count(), 2);
+        for idx in 0..4 {
+            *observer.get_mut(idx) = 1;
+        }
+        observer.post_exec(&mut (), &(), &ExitKind::Ok).unwrap();
+        assert_eq!(observer.to_vec(), vec![0, 1, 0, 1]);
+
+        // The filter is not sent along with the map
+        let serialized = postcard::to_allocvec(&observer).unwrap();
+        assert!(serialized.len() < 16);
+        let deserialized: FilteredMapObserver<StdMapObserver<u8>> =
+            postcard::from_bytes(&serialized).unwrap();
+        assert_eq!(deserialized.to_vec(), vec![0, 1, 0, 1]);
+        assert_eq!(deserialized.kept_count(), 4);
+    }
+
+    #[test]
+    fn test_value_profile_score() {
+        assert_eq!(CmpValues::U8((0xff, 0xff)).value_profile_score(), 8);
+        assert_eq!(CmpValues::U16((0x1234, 0x1235)).value_profile_score(), 15);
+        assert_eq!(CmpValues::U64((0, u64::MAX)).value_profile_score(), 0);
+        assert_eq!(
+            CmpValues::Bytes((b"MAGIC".to_vec(), b"MAGxx".to_vec())).value_profile_score(),
+            3 * 8 + 5
+        );
+    }
+
+    /// A small [`CmpMap`] logging the `u8` cmps of an execution
+    #[derive(Debug, Default, Serialize, Deserialize)]
+    struct TestCmpMap {
+        cmps: Vec<Vec<(u8, u8)>>,
+    }
+
+    impl CmpMap for TestCmpMap {
+        fn len(&self) -> usize {
+            self.cmps.len()
+        }
+
+        fn executions_for(&self, idx: usize) -> usize {
+            self.cmps[idx].len()
+        }
+
+        fn usable_executions_for(&self, idx: usize) -> usize {
+            self.cmps[idx].len()
+        }
+
+        fn values_of(&self, idx: usize, execution: usize) -> CmpValues {
+            CmpValues::U8(self.cmps[idx][execution])
+        }
+
+        fn reset(&mut self) -> Result<(), Error> {
+            self.cmps.clear();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_record_value_profile() {
+        let cmps = TestCmpMap {
+            cmps: vec![
+                vec![(0xff, 0x00), (0xff, 0xfe)],
+                vec![(0x12, 0x12)],
+                vec![],
+                vec![(0x0f, 0x00)],
+            ],
+        };
+        let mut map = [0_u8; 3];
+        cmps.record_value_profile(cmps.len(), &mut map);
+        // The best execution of the first cmp, and the fourth cmp wrapping onto its entry
+        assert_eq!(map, [7, 8, 0]);
+
+        // Only the first `count` cmps are recorded
+        let mut map = [0_u8; 4];
+        cmps.record_value_profile(1, &mut map);
+        assert_eq!(map, [7, 0, 0, 0]);
+
+        // Nothing to record into an empty map
+        cmps.record_value_profile(cmps.len(), &mut []);
+    }
+
+    #[test]
+    fn test_cmp_observer_value_profile() {
+        let mut cmps = TestCmpMap::default();
+        let mut map = [0xaa_u8; 2];
+        {
+            let mut obv = StdCmpObserver::new("cmps", &mut cmps).with_value_profile(&mut map);
+            Observer::<(), ()>::pre_exec(&mut obv, &mut (), &()).unwrap();
+            // The harness logs its cmps
+            CmpObserver::<_, (), ()>::cmp_map_mut(&mut obv).cmps =
+                vec![vec![(0x41, 0x41)], vec![(0x41, 0x40), (0x00, 0xff)]];
+            Observer::<(), ()>::post_exec(&mut obv, &mut (), &(), &ExitKind::Ok).unwrap();
+        }
+        assert_eq!(map, [8, 7]);
+
+        // The entries of the last execution are cleared
+        let mut obv = StdCmpObserver::new("cmps", &mut cmps).with_value_profile(&mut map);
+        Observer::<(), ()>::pre_exec(&mut obv, &mut (), &()).unwrap();
+        Observer::<(), ()>::post_exec(&mut obv, &mut (), &(), &ExitKind::Ok).unwrap();
+        drop(obv);
+        assert_eq!(map, [0, 0]);
+    }
 }
@@ -0,0 +1,266 @@
+//! The [`SanitizerReportObserver`] parses the error reports of the sanitizers, `ASan`, `UBSan` or
+//! `MSan`, of the last run into an [`AsanReportMetadata`], for the
+//! [`crate::feedbacks::AsanReportFeedback`] to store it on the objectives.
+//! The `CommandExecutor` and the `ForkserverExecutor` fill it, when found in their observers.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Debug;
+use std::{fs, process};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::{MatchName, Named},
+    executors::ExitKind,
+    observers::{Observer, ASAN_LOG_PATH},
+    Error,
+};
+
+/// The name of the [`SanitizerReportObserver`], by which the executors find it
+pub const SANITIZER_REPORT_OBSERVER_NAME: &str = "SanitizerReportObserver";
+
+/// The maximum number of frames of the first stack of a report kept in an [`AsanReportMetadata`]
+pub const MAX_REPORT_FRAMES: usize = 8;
+
+/// The maximum number of bytes of stderr the executors capture for the sanitizer reports
+pub const MAX_REPORT_LEN: usize = 1024 * 1024;
+
+lazy_static! {
+    static ref HEADER_REGEX: Regex = Regex::new(
+        r"==\d+==(?:ERROR|WARNING): (\w+Sanitizer): ([\w-]+)(?: on (?:unknown )?address 0x([0-9a-f]+))?(?:.*? pc 0x([0-9a-f]+))?",
+    )
+    .unwrap();
+    static ref UBSAN_REGEX: Regex = Regex::new(r": runtime error: (.*)").unwrap();
+    static ref ACCESS_REGEX: Regex = Regex::new(r"^(READ|WRITE) of size (\d+)").unwrap();
+    static ref FRAME_REGEX: Regex = Regex::new(r"^\s*#(\d+) 0x[0-9a-f]+ in (.*)$").unwrap();
+}
+
+/// The error report of a sanitizer, parsed
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AsanReportMetadata {
+    /// The sanitizer reporting the error, e.g. `AddressSanitizer`
+    pub sanitizer: String,
+    /// The type of the error, e.g. `heap-buffer-overflow`, or the message of `UBSan`
+    pub error_type: String,
+    /// The faulting address, if any
+    pub address: Option<u64>,
+    /// The program counter at the error, if any
+    pub pc: Option<u64>,
+    /// The access, `READ` or `WRITE`, and its size, if any
+    pub access: Option<(String, usize)>,
+    /// The top frames of the stack of the error, e.g. `foo /src/foo.c:12:3`
+    pub frames: Vec<String>,
+    /// The shadow bytes around the faulting address, one line per row, if any
+    pub shadow_bytes: Vec<String>,
+}
+
+crate::impl_serdeany!(AsanReportMetadata);
+
+/// Parses a hexadecimal number, without its `0x`
+fn parse_hex(hex: &str) -> Option<u64> {
+    u64::from_str_radix(hex, 16).ok()
+}
+
+impl AsanReportMetadata {
+    /// Parses the first error report of a sanitizer in `output`, e.g. the stderr of the target,
+    /// if any
+    #[must_use]
+    pub fn parse(output: &str) -> Option<Self> {
+        let mut lines = output.lines();
+        let mut report = loop {
+            let line = lines.next()?;
+            if let Some(captures) = HEADER_REGEX.captures(line) {
+                break Self {
+                    sanitizer: captures[1].to_string(),
+                    error_type: captures[2].to_string(),
+                    address: captures.get(3).and_then(|hex| parse_hex(hex.as_str())),
+                    pc: captures.get(4).and_then(|hex| parse_hex(hex.as_str())),
+                    access: None,
+                    frames: Vec::new(),
+                    shadow_bytes: Vec::new(),
+                };
+            }
+            if let Some(captures) = UBSAN_REGEX.captures(line) {
+                break Self {
+                    sanitizer: "UndefinedBehaviorSanitizer".to_string(),
+                    error_type: captures[1].to_string(),
+                    address: None,
+                    pc: None,
+                    access: None,
+                    frames: Vec::new(),
+                    shadow_bytes: Vec::new(),
+                };
+            }
+        };
+
+        let mut in_frames = true;
+        let mut in_shadow = false;
+        for line in lines {
+            if let Some(captures) = ACCESS_REGEX.captures(line) {
+                if report.access.is_none() {
+                    report.access = captures[2]
+                        .parse()
+                        .ok()
+                        .map(|size| (captures[1].to_string(), size));
+                }
+            } else if let Some(captures) = FRAME_REGEX.captures(line) {
+                // The frames of the next stacks, e.g. of the allocation, start at #0 again
+                if in_frames && &captures[1] == "0" && !report.frames.is_empty() {
+                    in_frames = false;
+                }
+                if in_frames && report.frames.len() < MAX_REPORT_FRAMES {
+                    report.frames.push(captures[2].to_string());
+                }
+            } else if line.starts_with("SUMMARY:") {
+                in_frames = false;
+            } else if line.starts_with("Shadow bytes around the buggy address") {
+                in_shadow = true;
+            } else if in_shadow {
+                if line.trim_start().starts_with("0x") || line.starts_with("=>") {
+                    report.shadow_bytes.push(line.to_string());
+                } else {
+                    break;
+                }
+            } else if line.contains("==ABORTING") {
+                break;
+            }
+        }
+        Some(report)
+    }
+}
+
+/// An observer keeping the parsed error report of a sanitizer in the last run, if any.
+/// The executors running the target in a child process fill it with the stderr of the child, or
+/// the log of `ASan`. With the in-process executors, it reads the log of `ASan` when the target
+/// crashes, which needs the `log_path` of
+/// [`crate::observers::get_asan_runtime_flags_with_log_path`] in the `ASAN_OPTIONS` of the fuzzer.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SanitizerReportObserver {
+    name: String,
+    report: Option<AsanReportMetadata>,
+}
+
+impl SanitizerReportObserver {
+    /// Creates a new [`SanitizerReportObserver`], named [`SANITIZER_REPORT_OBSERVER_NAME`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            name: SANITIZER_REPORT_OBSERVER_NAME.to_string(),
+            report: None,
+        }
+    }
+
+    /// Parses the first error report of a sanitizer in `output`, if any, keeping the first report
+    /// of the run
+    pub fn observe(&mut self, output: &str) {
+        if self.report.is_none() {
+            self.report = AsanReportMetadata::parse(output);
+        }
+    }
+
+    /// The error report of the sanitizer in the last run, if any
+    #[must_use]
+    pub fn report(&self) -> Option<&AsanReportMetadata> {
+        self.report.as_ref()
+    }
+}
+
+impl Default for SanitizerReportObserver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I, S> Observer<I, S> for SanitizerReportObserver
+where
+    I: Debug,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.report = None;
+        Ok(())
+    }
+
+    fn post_exec(&mut self, _state: &mut S, _input: &I, exit_kind: &ExitKind) -> Result<(), Error> {
+        // In process, the sanitizer writes its log before aborting the fuzzer
+        if *exit_kind == ExitKind::Crash && self.report.is_none() {
+            let log_path = format!("{}.{}", ASAN_LOG_PATH, process::id());
+            if let Ok(log) = fs::read_to_string(&log_path) {
+                let _ = fs::remove_file(&log_path);
+                self.observe(&log);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Named for SanitizerReportObserver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Fills the [`SanitizerReportObserver`] in `observers`, if any, with the report in `output`, if
+/// the run exiting with `exit_kind` crashed
+pub(crate) fn observe_sanitizer_report<OT>(observers: &mut OT, output: &str, exit_kind: ExitKind)
+where
+    OT: MatchName,
+{
+    if exit_kind != ExitKind::Crash {
+        return;
+    }
+    if let Some(observer) =
+        observers.match_name_mut::<SanitizerReportObserver>(SANITIZER_REPORT_OBSERVER_NAME)
+    {
+        observer.observe(output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::observers::sanitizer::AsanReportMetadata;
+
+    #[test]
+    fn test_parse_asan_report() {
+        let output = "\
+=================================================================
+==1234==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000011 at pc 0x4f1234 bp 0x7ffc1 sp 0x7ffc0
+READ of size 1 at 0x602000000011 thread T0
+    #0 0x4f1233 in parse /src/parse.c:12:5
+    #1 0x4f1500 in LLVMFuzzerTestOneInput /src/harness.c:7:3
+0x602000000011 is located 0 bytes to the right of 1-byte region
+allocated by thread T0 here:
+    #0 0x4c0000 in malloc
+SUMMARY: AddressSanitizer: heap-buffer-overflow /src/parse.c:12:5 in parse
+Shadow bytes around the buggy address:
+  0x0c047fff7fb0: 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00
+=>0x0c047fff8000: fa fa[01]fa fa fa fa fa fa fa fa fa fa fa fa fa
+Shadow byte legend (one shadow byte represents 8 application bytes):
+==1234==ABORTING
+";
+        let report = AsanReportMetadata::parse(output).unwrap();
+        assert_eq!(report.sanitizer, "AddressSanitizer");
+        assert_eq!(report.error_type, "heap-buffer-overflow");
+        assert_eq!(report.address, Some(0x6020_0000_0011));
+        assert_eq!(report.pc, Some(0x4f_1234));
+        assert_eq!(report.access, Some(("READ".to_string(), 1)));
+        assert_eq!(
+            report.frames,
+            vec![
+                "parse /src/parse.c:12:5",
+                "LLVMFuzzerTestOneInput /src/harness.c:7:3"
+            ]
+        );
+        assert_eq!(report.shadow_bytes.len(), 2);
+
+        let report =
+            AsanReportMetadata::parse("a.c:3:5: runtime error: signed integer overflow\n").unwrap();
+        assert_eq!(report.sanitizer, "UndefinedBehaviorSanitizer");
+        assert_eq!(report.error_type, "signed integer overflow");
+        assert!(AsanReportMetadata::parse("all good\n").is_none());
+    }
+}
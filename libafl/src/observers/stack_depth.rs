@@ -0,0 +1,92 @@
+//! The [`StackDepthObserver`] observes the maximum stack depth reached by the target in a run, to
+//! reward the inputs recursing deeper than ever, with the
+//! [`crate::feedbacks::MaxStackDepthFeedback`].
+
+use alloc::string::{String, ToString};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{ownedref::OwnedRefMut, tuples::Named},
+    observers::Observer,
+    Error,
+};
+
+/// An observer of the maximum stack depth reached by the target in a run.
+///
+/// The instrumentation of the target raises the cell observed, e.g. the `STACK_DEPTH_MAX` of
+/// `libafl_targets`, with its `stack_depth` feature, and the target built with
+/// `-finstrument-functions`. The observer resets the cell before each run, and the cell of the
+/// current depth too, if given, for the runs not to start at the depth a crash or a timeout left.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StackDepthObserver<'a> {
+    name: String,
+    max_depth: OwnedRefMut<'a, usize>,
+    depth: Option<OwnedRefMut<'a, usize>>,
+}
+
+impl<'a> StackDepthObserver<'a> {
+    /// Creates a new [`StackDepthObserver`] of the cell `max_depth`, raised by the target
+    #[must_use]
+    pub fn new(name: &'static str, max_depth: &'a mut usize) -> Self {
+        Self {
+            name: name.to_string(),
+            max_depth: OwnedRefMut::Ref(max_depth),
+            depth: None,
+        }
+    }
+
+    /// Creates a new [`StackDepthObserver`] of the cell `max_depth`, raised by the target, also
+    /// resetting the cell of the current `depth` before each run, e.g. the `STACK_DEPTH` of
+    /// `libafl_targets`
+    #[must_use]
+    pub fn with_depth(name: &'static str, max_depth: &'a mut usize, depth: &'a mut usize) -> Self {
+        Self {
+            name: name.to_string(),
+            max_depth: OwnedRefMut::Ref(max_depth),
+            depth: Some(OwnedRefMut::Ref(depth)),
+        }
+    }
+
+    /// The maximum stack depth reached in the last run
+    #[must_use]
+    pub fn max_depth(&self) -> usize {
+        *self.max_depth.as_ref()
+    }
+}
+
+impl<I, S> Observer<I, S> for StackDepthObserver<'_> {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        *self.max_depth.as_mut() = 0;
+        if let Some(depth) = &mut self.depth {
+            *depth.as_mut() = 0;
+        }
+        Ok(())
+    }
+}
+
+impl Named for StackDepthObserver<'_> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::observers::{Observer, StackDepthObserver};
+
+    #[test]
+    fn test_stack_depth_observer() {
+        let (mut max_depth, mut depth) = (0, 0);
+        let mut observer = StackDepthObserver::with_depth("stack", &mut max_depth, &mut depth);
+        // A run crashing at a depth of 3, after reaching 7
+        *observer.max_depth.as_mut() = 7;
+        *observer.depth.as_mut().unwrap().as_mut() = 3;
+        assert_eq!(observer.max_depth(), 7);
+
+        observer.pre_exec(&mut (), &()).unwrap();
+        assert_eq!(observer.max_depth(), 0);
+        drop(observer);
+        assert_eq!((max_depth, depth), (0, 0));
+    }
+}
@@ -327,15 +327,17 @@ where
     I: Debug,
 {
     fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        // A run without an ASAN report must not keep the hash of the last one
+        self.clear_hash();
         Ok(())
     }
 
-    fn post_exec(
-        &mut self,
-        _state: &mut S,
-        _input: &I,
-        _exit_kind: &ExitKind,
-    ) -> Result<(), Error> {
+    fn post_exec(&mut self, _state: &mut S, _input: &I, exit_kind: &ExitKind) -> Result<(), Error> {
+        // A crash without an ASAN report, e.g. a plain segfault, is one more kind of crash, not to
+        // be dropped for the lack of a hash
+        if *exit_kind == ExitKind::Crash && self.hash.is_none() {
+            self.parse_asan_output("");
+        }
         Ok(())
     }
 }
@@ -0,0 +1,122 @@
+//! The [`StdOutObserver`] and [`StdErrObserver`] keep the output of the target child processes,
+//! for feedbacks to match on sanitizer reports or log lines, e.g. with the
+//! [`crate::feedbacks::OutputRegexFeedback`].
+//! The `CommandExecutor` and the `ForkserverExecutor` fill them, when found in their observers.
+
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+use regex::bytes::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{bolts::tuples::Named, executors::ExitKind, observers::Observer, Error};
+
+/// The name of the [`StdOutObserver`], by which the executors find it
+pub const STDOUT_OBSERVER_NAME: &str = "StdOutObserver";
+
+/// The name of the [`StdErrObserver`], by which the executors find it
+pub const STDERR_OBSERVER_NAME: &str = "StdErrObserver";
+
+/// The default maximum number of bytes of output kept per run
+pub const DEFAULT_MAX_OUTPUT_LEN: usize = 64 * 1024;
+
+macro_rules! output_observer {
+    ($observer:ident, $name:ident, $stream:literal) => {
+        #[doc = concat!("An observer keeping the ", $stream, " of the target of the last run, up to a maximum length")]
+        #[derive(Serialize, Deserialize, Debug, Clone)]
+        pub struct $observer {
+            name: String,
+            max_len: usize,
+            output: Option<Vec<u8>>,
+        }
+
+        impl $observer {
+            #[doc = concat!("Creates a new [`", stringify!($observer), "`], named [`", stringify!($name), "`]")]
+            #[must_use]
+            pub fn new() -> Self {
+                Self {
+                    name: $name.to_string(),
+                    max_len: DEFAULT_MAX_OUTPUT_LEN,
+                    output: None,
+                }
+            }
+
+            /// Sets the maximum number of bytes of output kept per run, the rest being dropped
+            #[must_use]
+            pub fn with_max_len(mut self, max_len: usize) -> Self {
+                self.max_len = max_len;
+                self
+            }
+
+            /// The maximum number of bytes of output kept per run
+            #[must_use]
+            pub fn max_len(&self) -> usize {
+                self.max_len
+            }
+
+            #[doc = concat!("Sets the ", $stream, " of the last run, truncated to the maximum length")]
+            pub fn observe(&mut self, output: &[u8]) {
+                let len = output.len().min(self.max_len);
+                self.output = Some(output[..len].to_vec());
+            }
+
+            #[doc = concat!("The ", $stream, " of the last run, if captured")]
+            #[must_use]
+            pub fn output(&self) -> Option<&[u8]> {
+                self.output.as_deref()
+            }
+
+            #[doc = concat!("The ", $stream, " of the last run as text, if captured")]
+            #[must_use]
+            pub fn output_str(&self) -> Option<Cow<'_, str>> {
+                self.output
+                    .as_deref()
+                    .map(|output| String::from_utf8_lossy(output))
+            }
+
+            #[doc = concat!("If the ", $stream, " of the last run was captured, and matches `regex`")]
+            #[must_use]
+            pub fn is_match(&self, regex: &Regex) -> bool {
+                self.output
+                    .as_deref()
+                    .map_or(false, |output| regex.is_match(output))
+            }
+        }
+
+        impl Default for $observer {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<I, S> Observer<I, S> for $observer
+        where
+            I: core::fmt::Debug,
+        {
+            fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+                self.output = None;
+                Ok(())
+            }
+
+            fn post_exec(
+                &mut self,
+                _state: &mut S,
+                _input: &I,
+                _exit_kind: &ExitKind,
+            ) -> Result<(), Error> {
+                Ok(())
+            }
+        }
+
+        impl Named for $observer {
+            fn name(&self) -> &str {
+                &self.name
+            }
+        }
+    };
+}
+
+output_observer!(StdOutObserver, STDOUT_OBSERVER_NAME, "stdout");
+output_observer!(StdErrObserver, STDERR_OBSERVER_NAME, "stderr");
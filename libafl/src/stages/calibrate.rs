@@ -23,6 +23,7 @@ use num_traits::Bounded;
 use serde::{Deserialize, Serialize};
 
 /// The calibration stage will measure the average exec time and the target's stability for this input.
+/// The exec times and the stability of the input get stored as [`struct@CalibrationMetadata`] in the testcase.
 #[derive(Clone, Debug)]
 pub struct CalibrationStage<I, O, OT, S>
 where
@@ -77,9 +78,14 @@ where
         executor.observers_mut().pre_exec_all(state, &input)?;
         let mut start = current_time();
 
+        let mut run_times = Vec::with_capacity(CAL_STAGE_MAX);
+        let mut errors = 0;
         let mut total_time = if executor.run_target(fuzzer, state, mgr, &input)? == ExitKind::Ok {
-            current_time() - start
+            let run_time = current_time() - start;
+            run_times.push(run_time);
+            run_time
         } else {
+            errors += 1;
             mgr.log(
                 state,
                 LogSeverity::Warn,
@@ -101,6 +107,7 @@ where
         let mut has_errors = false;
         let mut unstable_entries: usize = 0;
         let map_len: usize = map_first.len();
+        let mut unstable_map = vec![false; map_len];
         while i < iter {
             let input = state
                 .corpus()
@@ -113,6 +120,9 @@ where
             start = current_time();
 
             if executor.run_target(fuzzer, state, mgr, &input)? != ExitKind::Ok {
+                // The errored runs count, not to calibrate forever an entry always erroring
+                i += 1;
+                errors += 1;
                 if !has_errors {
                     mgr.log(
                         state,
@@ -128,7 +138,9 @@ where
                 continue;
             };
 
-            total_time += current_time() - start;
+            let run_time = current_time() - start;
+            run_times.push(run_time);
+            total_time += run_time;
 
             let map = &executor
                 .observers()
@@ -143,23 +155,41 @@ where
                 .history_map;
 
             for j in 0..map_len {
-                if map_first[j] != map[j] && history_map[j] != O::Entry::max_value() {
-                    history_map[j] = O::Entry::max_value();
-                    unstable_entries += 1;
+                if map_first[j] != map[j] {
+                    unstable_map[j] = true;
+                    if history_map[j] != O::Entry::max_value() {
+                        history_map[j] = O::Entry::max_value();
+                        unstable_entries += 1;
+                    }
                 };
             }
 
             i += 1;
         }
 
-        #[allow(clippy::cast_precision_loss)]
-        if unstable_entries != 0 {
-            *state.stability_mut() = Some((map_len - unstable_entries) as f32 / (map_len as f32));
+        if unstable_entries != 0 && iter < CAL_STAGE_MAX {
+            iter += 2;
+        };
 
-            if iter < CAL_STAGE_MAX {
-                iter += 2;
+        // The stability is the ratio of the covered entries never found to vary, so far
+        let history_map = &state
+            .feedback_states()
+            .match_name::<MapFeedbackState<O::Entry>>(&self.map_observer_name)
+            .unwrap()
+            .history_map;
+        let (covered, unstable) = history_map.iter().fold((0_usize, 0_usize), |(c, u), x| {
+            if *x == O::Entry::max_value() {
+                (c + 1, u + 1)
+            } else if *x == O::Entry::default() {
+                (c, u)
+            } else {
+                (c + 1, u)
             }
-        };
+        });
+        #[allow(clippy::cast_precision_loss)]
+        if covered != 0 {
+            *state.stability_mut() = Some((covered - unstable) as f32 / (covered as f32));
+        }
 
         let psmeta = state
             .metadata_mut()
@@ -192,10 +222,102 @@ where
         data.set_fuzz_level(data.fuzz_level() + 1);
         // println!("data: {:#?}", data);
 
+        let (covered, unstable) = map_first.iter().zip(unstable_map.iter()).fold(
+            (0_usize, 0_usize),
+            |(c, u), (entry, unstable)| {
+                if *unstable {
+                    (c + 1, u + 1)
+                } else if *entry == O::Entry::default() {
+                    (c, u)
+                } else {
+                    (c + 1, u)
+                }
+            },
+        );
+        testcase.add_metadata(CalibrationMetadata::new(
+            i, errors, &run_times, covered, unstable,
+        ));
+
         Ok(())
     }
 }
 
+/// The results of the last calibration of a testcase: how its exec time varies, and how stable
+/// its coverage is, for the schedulers and the monitors.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CalibrationMetadata {
+    /// The number of runs
+    pub runs: usize,
+    /// The number of runs not exiting normally, not counted in the exec times
+    pub errors: usize,
+    /// The mean exec time
+    pub exec_time_mean: Duration,
+    /// The standard deviation of the exec times
+    pub exec_time_stddev: Duration,
+    /// The number of map entries covered by the testcase, in any run
+    pub covered_entries: usize,
+    /// The number of map entries found to vary between the runs
+    pub unstable_entries: usize,
+}
+
+crate::impl_serdeany!(CalibrationMetadata);
+
+impl CalibrationMetadata {
+    /// Creates the [`struct@CalibrationMetadata`] of `runs` runs, measuring the `run_times` of
+    /// the ones exiting normally
+    #[must_use]
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn new(
+        runs: usize,
+        errors: usize,
+        run_times: &[Duration],
+        covered_entries: usize,
+        unstable_entries: usize,
+    ) -> Self {
+        let (mean, stddev) = if run_times.is_empty() {
+            (0.0, 0.0)
+        } else {
+            let count = run_times.len() as f64;
+            let mean = run_times.iter().map(|t| t.as_nanos() as f64).sum::<f64>() / count;
+            let variance = run_times
+                .iter()
+                .map(|t| (t.as_nanos() as f64 - mean) * (t.as_nanos() as f64 - mean))
+                .sum::<f64>()
+                / count;
+            (mean, libm::sqrt(variance))
+        };
+        Self {
+            runs,
+            errors,
+            exec_time_mean: Duration::from_nanos(mean as u64),
+            exec_time_stddev: Duration::from_nanos(stddev as u64),
+            covered_entries,
+            unstable_entries,
+        }
+    }
+
+    /// The ratio of the covered map entries that did not vary between the runs
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn stability(&self) -> f32 {
+        if self.covered_entries == 0 {
+            1.0
+        } else {
+            (self.covered_entries - self.unstable_entries) as f32 / self.covered_entries as f32
+        }
+    }
+
+    /// Whether the coverage of the testcase never varied between the runs
+    #[must_use]
+    pub fn is_stable(&self) -> bool {
+        self.unstable_entries == 0
+    }
+}
+
 /// The n fuzz size
 pub const N_FUZZ_SIZE: usize = 1 << 21;
 
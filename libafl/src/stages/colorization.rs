@@ -0,0 +1,269 @@
+//! The [`ColorizationStage`] is the colorization of `RedQueen`: it replaces as many bytes of
+//! the input as possible with random ones, keeping the same coverage, to tell the bytes the
+//! coverage depends on from the free ones, where the input-to-state replacements make sense.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt::Debug, marker::PhantomData, ops::Range};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::rands::Rand,
+    corpus::Corpus,
+    executors::{Executor, HasObservers},
+    inputs::{HasBytesVec, Input},
+    mark_feature_time,
+    observers::{MapObserver, ObserversTuple},
+    stages::{generalization::run_target_observed, Stage},
+    start_timer,
+    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata, HasRand},
+    Error,
+};
+
+#[cfg(feature = "introspection")]
+use crate::monitors::PerfFeature;
+
+/// The number of executions the colorization of an input may take, per byte of the input
+pub const COLORIZATION_EXECS_PER_BYTE: usize = 2;
+
+/// A metadata holding the ranges of the bytes of a colorized input that can be changed without
+/// changing the coverage.
+/// It is kept in the testcase, and copied to the state for the testcase being fuzzed, where the
+/// [`crate::mutators::I2SRandReplace`] mutator only replaces the bytes in the ranges.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TaintMetadata {
+    /// The length of the colorized input
+    pub input_len: usize,
+    /// The ranges of colorized bytes, sorted and not overlapping
+    pub ranges: Vec<Range<usize>>,
+}
+
+crate::impl_serdeany!(TaintMetadata);
+
+impl TaintMetadata {
+    /// Creates the metadata of a colorized input of `input_len` bytes, merging the adjacent `ranges`
+    #[must_use]
+    pub fn new(input_len: usize, mut ranges: Vec<Range<usize>>) -> Self {
+        ranges.sort_by_key(|range| range.start);
+        let mut merged: Vec<Range<usize>> = vec![];
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if last.end >= range.start => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        Self {
+            input_len,
+            ranges: merged,
+        }
+    }
+
+    /// Whether the `len` bytes at `offset` of an input of `input_len` bytes can be replaced.
+    /// The ranges only hold for inputs with the length of the colorized one, so the bytes of
+    /// inputs with another length can always be replaced.
+    #[must_use]
+    pub fn is_tainted(&self, input_len: usize, offset: usize, len: usize) -> bool {
+        input_len != self.input_len
+            || self
+                .ranges
+                .iter()
+                .any(|range| range.start <= offset && offset + len <= range.end)
+    }
+}
+
+/// A stage colorizing each testcase once, as `RedQueen` does: it replaces ranges of bytes of the
+/// input with random ones, keeping the replacements that leave the hash of the map observer
+/// unchanged, and splitting the others in halves, biggest range first.
+/// The ranges of the colorized input get stored as [`TaintMetadata`].
+#[derive(Clone, Debug)]
+pub struct ColorizationStage<EM, I, O, OT, S, Z>
+where
+    I: Input + HasBytesVec,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions + HasMetadata + HasCorpus<I> + HasRand,
+{
+    map_observer_name: String,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(EM, I, O, OT, S, Z)>,
+}
+
+impl<E, EM, I, O, OT, S, Z> Stage<E, EM, S, Z> for ColorizationStage<EM, I, O, OT, S, Z>
+where
+    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input + HasBytesVec,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions + HasMetadata + HasCorpus<I> + HasRand,
+{
+    #[inline]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        // The taint of the previous testcase does not hold for this one
+        drop(state.metadata_mut().remove::<TaintMetadata>());
+
+        let original = {
+            start_timer!(state);
+            state.corpus().get(corpus_idx)?.borrow_mut().load_input()?;
+            mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
+            let entry = state.corpus().get(corpus_idx)?.borrow();
+            if let Some(taint) = entry.metadata().get::<TaintMetadata>() {
+                let taint = taint.clone();
+                drop(entry);
+                state.add_metadata(taint);
+                return Ok(());
+            }
+            entry.input().as_ref().unwrap().clone()
+        };
+
+        // Colorizing an unstable input would keep nothing, try again next time
+        let hash = self.run_and_hash(fuzzer, executor, state, manager, &original)?;
+        if hash != self.run_and_hash(fuzzer, executor, state, manager, &original)? {
+            return Ok(());
+        }
+
+        let mut colorized = original.bytes().to_vec();
+        let mut candidate = original;
+        let mut ranges = Vec::with_capacity(colorized.len());
+        ranges.push(0..colorized.len());
+        let mut taint = vec![];
+        let mut execs_left = colorized.len() * COLORIZATION_EXECS_PER_BYTE;
+        // Colorize the biggest range first
+        while let Some(biggest) = ranges
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, range)| range.len())
+            .map(|(idx, _)| idx)
+        {
+            if execs_left == 0 {
+                break;
+            }
+            let range = ranges.swap_remove(biggest);
+            if range.is_empty() {
+                continue;
+            }
+
+            let candidate_bytes = candidate.bytes_mut();
+            candidate_bytes.clone_from(&colorized);
+            for byte in &mut candidate_bytes[range.clone()] {
+                // A random byte, different from the current one
+                *byte ^= 1 + state.rand_mut().below(255) as u8;
+            }
+            execs_left -= 1;
+            if self.run_and_hash(fuzzer, executor, state, manager, &candidate)? == hash {
+                colorized.copy_from_slice(candidate.bytes());
+                taint.push(range);
+            } else if range.len() > 1 {
+                let middle = range.start + range.len() / 2;
+                ranges.push(range.start..middle);
+                ranges.push(middle..range.end);
+            }
+        }
+
+        let taint = TaintMetadata::new(colorized.len(), taint);
+        state
+            .corpus()
+            .get(corpus_idx)?
+            .borrow_mut()
+            .add_metadata(taint.clone());
+        state.add_metadata(taint);
+        Ok(())
+    }
+}
+
+impl<EM, I, O, OT, S, Z> ColorizationStage<EM, I, O, OT, S, Z>
+where
+    I: Input + HasBytesVec,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions + HasMetadata + HasCorpus<I> + HasRand,
+{
+    /// Create a new [`ColorizationStage`], keeping the hash of the given map observer
+    #[must_use]
+    pub fn new(map_observer: &O) -> Self {
+        Self::from_name(map_observer.name())
+    }
+
+    /// Create a new [`ColorizationStage`] from the name of the map observer
+    #[must_use]
+    pub fn from_name(map_observer_name: &str) -> Self {
+        Self {
+            map_observer_name: map_observer_name.to_string(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Runs `input`, and hashes the map of the observer
+    fn run_and_hash<E>(
+        &self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        input: &I,
+    ) -> Result<u64, Error>
+    where
+        E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    {
+        run_target_observed(fuzzer, executor, state, manager, input)?;
+        Ok(executor
+            .observers()
+            .match_name::<O>(&self.map_observer_name)
+            .ok_or_else(|| Error::KeyNotFound("MapObserver not found".to_string()))?
+            .hash())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::InMemoryCorpus,
+        inputs::{BytesInput, HasBytesVec},
+        mutators::{I2SRandReplace, MutationResult, Mutator},
+        observers::cmp::{CmpValues, CmpValuesMetadata},
+        stages::colorization::TaintMetadata,
+        state::{HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_taint_metadata() {
+        let taint = TaintMetadata::new(16, vec![8..12, 0..2, 2..4, 10..14]);
+        assert_eq!(taint.ranges, [0..4, 8..14]);
+        assert!(taint.is_tainted(16, 1, 2));
+        assert!(taint.is_tainted(16, 8, 6));
+        assert!(!taint.is_tainted(16, 3, 2));
+        assert!(!taint.is_tainted(16, 14, 1));
+        // Another length, the ranges do not hold
+        assert!(taint.is_tainted(17, 14, 1));
+    }
+
+    #[test]
+    fn test_i2s_taint() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            tuple_list!(),
+        );
+        let mut cmps = CmpValuesMetadata::new();
+        cmps.list.push(CmpValues::U8((1, 2)));
+        state.add_metadata(cmps);
+        state.add_metadata(TaintMetadata::new(4, core::iter::once(3..4).collect()));
+
+        for _ in 0..16 {
+            let mut input = BytesInput::new(vec![1; 4]);
+            let res = I2SRandReplace.mutate(&mut state, &mut input, 0).unwrap();
+            assert_eq!(res, MutationResult::Mutated);
+            assert_eq!(input.bytes(), [1, 1, 1, 2]);
+        }
+    }
+}
@@ -166,7 +166,11 @@ fn generate_mutations(iter: impl Iterator<Item = (SymExprRef, SymExpr)>) -> Vec<
             SymExpr::Integer { value, bits } => {
                 Some(BV::from_u64(&ctx, value, u32::from(bits)).into())
             }
-            SymExpr::Integer128 { high: _, low: _ } => todo!(),
+            SymExpr::Integer128 { high, low } => Some(
+                BV::from_u64(&ctx, high, 64)
+                    .concat(&BV::from_u64(&ctx, low, 64))
+                    .into(),
+            ),
             SymExpr::NullPointer => Some(BV::from_u64(&ctx, 0, usize::BITS).into()),
             SymExpr::True => Some(Bool::from_bool(&ctx, true).into()),
             SymExpr::False => Some(Bool::from_bool(&ctx, false).into()),
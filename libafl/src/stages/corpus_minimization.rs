@@ -0,0 +1,263 @@
+//! The [`CorpusMinimizationStage`] periodically removes the corpus entries whose coverage other
+//! entries already cover, as `afl-cmin` does, but during the campaign.
+
+use alloc::{collections::BinaryHeap, vec::Vec};
+use core::{cmp::Reverse, marker::PhantomData, time::Duration};
+use hashbrown::HashSet;
+
+use crate::{
+    bolts::current_time,
+    corpus::{Corpus, CorpusScheduler},
+    feedbacks::MapIndexesMetadata,
+    inputs::Input,
+    stages::{generalization::GeneralizedIndexesMetadata, Stage},
+    state::{HasCorpus, HasMetadata},
+    Error, HasCorpusScheduler,
+};
+
+/// The default interval between two minimizations of the corpus
+pub const CORPUS_MINIMIZATION_DEFAULT_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+/// The corpus entries to keep to cover all the map entries of `coverages`, the map indexes covered
+/// by each corpus entry: greedily the entry covering the most map entries not covered yet, until
+/// all are covered
+fn covering_entries(coverages: &[(usize, Vec<usize>)]) -> HashSet<usize> {
+    // Lazy greedy: the gains in the heap are upper bounds, as they only decrease
+    let mut heap: BinaryHeap<(usize, Reverse<usize>)> = coverages
+        .iter()
+        .enumerate()
+        .map(|(i, (_, list))| (list.len(), Reverse(i)))
+        .collect();
+    let mut covered = HashSet::new();
+    let mut kept = HashSet::new();
+    while let Some((gain, Reverse(i))) = heap.pop() {
+        if gain == 0 {
+            break;
+        }
+        let (idx, list) = &coverages[i];
+        let new_gain = list
+            .iter()
+            .filter(|map_idx| !covered.contains(*map_idx))
+            .count();
+        if matches!(heap.peek(), Some((next_gain, _)) if new_gain < *next_gain) {
+            heap.push((new_gain, Reverse(i)));
+            continue;
+        }
+        if new_gain == 0 {
+            break;
+        }
+        covered.extend(list.iter().copied());
+        kept.insert(*idx);
+    }
+    kept
+}
+
+/// A stage periodically computing a subset of the corpus covering the same map entries, from the
+/// [`MapIndexesMetadata`] of the entries, and removing the other entries from the corpus, as well
+/// as from the scheduler.
+/// The entries without [`MapIndexesMetadata`], and the entry being fuzzed, are always kept.
+/// The removal shifts the indexes of the next corpus entries: the scheduler remaps its own
+/// metadata in [`CorpusScheduler::on_remove`], and this stage remaps the
+/// [`GeneralizedIndexesMetadata`]. It should come last, after the stages using `corpus_idx`.
+#[derive(Clone, Debug)]
+pub struct CorpusMinimizationStage<CS, I, S>
+where
+    CS: CorpusScheduler<I, S>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata,
+{
+    interval: Duration,
+    last_time: Duration,
+    removed: usize,
+    phantom: PhantomData<(CS, I, S)>,
+}
+
+impl<CS, E, EM, I, S, Z> Stage<E, EM, S, Z> for CorpusMinimizationStage<CS, I, S>
+where
+    CS: CorpusScheduler<I, S>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata,
+    Z: HasCorpusScheduler<CS, I, S>,
+{
+    #[inline]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        _manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let now = current_time();
+        if now < self.last_time + self.interval {
+            return Ok(());
+        }
+        self.last_time = now;
+
+        let mut coverages = vec![];
+        let mut untracked = HashSet::new();
+        for idx in 0..state.corpus().count() {
+            match state
+                .corpus()
+                .get(idx)?
+                .borrow()
+                .metadata()
+                .get::<MapIndexesMetadata>()
+            {
+                Some(meta) => coverages.push((idx, meta.list.clone())),
+                None => {
+                    untracked.insert(idx);
+                }
+            }
+        }
+        let kept = covering_entries(&coverages);
+
+        // Remove from the end, not to shift the indexes of the entries still to remove
+        for idx in (0..state.corpus().count()).rev() {
+            if idx == corpus_idx || kept.contains(&idx) || untracked.contains(&idx) {
+                continue;
+            }
+            let testcase = state.corpus_mut().remove(idx)?;
+            if let Some(current) = state.corpus_mut().current_mut() {
+                if *current > idx {
+                    *current -= 1;
+                }
+            }
+            if let Some(generalized) = state.metadata_mut().get_mut::<GeneralizedIndexesMetadata>()
+            {
+                generalized.remove_corpus_idx(idx);
+            }
+            fuzzer.scheduler_mut().on_remove(state, idx, &testcase)?;
+            self.removed += 1;
+        }
+        Ok(())
+    }
+}
+
+impl<CS, I, S> CorpusMinimizationStage<CS, I, S>
+where
+    CS: CorpusScheduler<I, S>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata,
+{
+    /// Creates a new [`CorpusMinimizationStage`], minimizing the corpus once the first interval
+    /// elapsed
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            interval: CORPUS_MINIMIZATION_DEFAULT_INTERVAL,
+            last_time: current_time(),
+            removed: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets the interval between two minimizations of the corpus
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// The number of corpus entries removed so far
+    #[must_use]
+    pub fn removed(&self) -> usize {
+        self.removed
+    }
+}
+
+impl<CS, I, S> Default for CorpusMinimizationStage<CS, I, S>
+where
+    CS: CorpusScheduler<I, S>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hashbrown::HashSet;
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{Corpus, InMemoryCorpus, QueueCorpusScheduler, Testcase},
+        feedbacks::MapIndexesMetadata,
+        inputs::{BytesInput, HasBytesVec},
+        stages::{
+            corpus_minimization::{covering_entries, CorpusMinimizationStage},
+            generalization::GeneralizedIndexesMetadata,
+            Stage,
+        },
+        state::{HasCorpus, HasMetadata, StdState},
+        StdFuzzer,
+    };
+
+    #[test]
+    fn test_covering_entries() {
+        // Taking the biggest entries first keeps 0, 1 and 2, the greedy cover only 0 and 3
+        let coverages = vec![
+            (0, vec![1, 2, 3, 4]),
+            (1, vec![1, 2, 5]),
+            (2, vec![3, 4, 6]),
+            (3, vec![5, 6]),
+            (4, vec![]),
+        ];
+        let kept = covering_entries(&coverages);
+        assert_eq!(kept, [0, 3].into_iter().collect::<HashSet<_>>());
+    }
+
+    #[test]
+    fn test_corpus_minimization_stage() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        for list in [vec![1, 2, 3, 4], vec![1, 2, 5], vec![3, 4, 6], vec![5, 6]] {
+            let mut testcase = Testcase::new(vec![0; 4]);
+            testcase.add_metadata(MapIndexesMetadata::new(list));
+            corpus.add(testcase).unwrap();
+        }
+        corpus.add(Testcase::new(vec![1; 4])).unwrap();
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<BytesInput>::new(),
+            tuple_list!(),
+        );
+        let mut generalized = GeneralizedIndexesMetadata::new();
+        generalized.indexes.extend([0, 1, 3, 4]);
+        state.add_metadata(generalized);
+
+        let mut fuzzer: StdFuzzer<_, _, _, _, (), _> =
+            StdFuzzer::new(QueueCorpusScheduler::new(), (), ());
+        let mut minimization =
+            CorpusMinimizationStage::new().with_interval(core::time::Duration::ZERO);
+        minimization
+            .perform(&mut fuzzer, &mut (), &mut state, &mut (), 0)
+            .unwrap();
+
+        assert_eq!(minimization.removed(), 2);
+        assert_eq!(state.corpus().count(), 3);
+        assert_eq!(
+            state
+                .metadata()
+                .get::<GeneralizedIndexesMetadata>()
+                .unwrap()
+                .indexes,
+            [0, 1, 2].into_iter().collect::<HashSet<_>>()
+        );
+        // The entry without coverage metadata is kept, now last
+        assert_eq!(
+            state
+                .corpus()
+                .get(2)
+                .unwrap()
+                .borrow()
+                .input()
+                .as_ref()
+                .unwrap()
+                .bytes(),
+            &[1; 4]
+        );
+    }
+}
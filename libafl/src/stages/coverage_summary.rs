@@ -0,0 +1,174 @@
+//! The [`CoverageSummaryStage`] periodically sends a downsampled coverage map to the broker, for
+//! the monitors to show where in the map the coverage is.
+
+use alloc::string::{String, ToString};
+use core::{fmt::Debug, marker::PhantomData, time::Duration};
+
+use num_traits::PrimInt;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    bolts::{current_time, tuples::MatchName},
+    events::{Event, EventFirer},
+    feedbacks::MapFeedbackState,
+    inputs::Input,
+    monitors::CoverageSummary,
+    stages::Stage,
+    state::HasFeedbackStates,
+    Error,
+};
+
+/// The default interval between two coverage summaries
+pub const COVERAGE_SUMMARY_INTERVAL: Duration = Duration::from_secs(15);
+
+/// The default number of buckets of the coverage summaries
+pub const COVERAGE_SUMMARY_BUCKETS: usize = 1024;
+
+/// A stage periodically sending the summary of the coverage seen so far by a map feedback, as
+/// [`Event::UpdateCoverageSummary`], drawn as heatmap by the `TuiMonitor`.
+#[derive(Debug)]
+pub struct CoverageSummaryStage<I, T> {
+    map_feedback_name: String,
+    buckets: usize,
+    interval: Duration,
+    last_time: Duration,
+    phantom: PhantomData<(I, T)>,
+}
+
+impl<I, T> CoverageSummaryStage<I, T>
+where
+    I: Input,
+    T: PrimInt + Default + Copy + 'static + Serialize + DeserializeOwned + Debug,
+{
+    /// Creates a new [`CoverageSummaryStage`], summarizing the history map of the map feedback
+    /// named `map_feedback_name`
+    #[must_use]
+    pub fn new(map_feedback_name: &str) -> Self {
+        Self {
+            map_feedback_name: map_feedback_name.to_string(),
+            buckets: COVERAGE_SUMMARY_BUCKETS,
+            interval: COVERAGE_SUMMARY_INTERVAL,
+            last_time: Duration::from_secs(0),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets the number of buckets of the summaries
+    #[must_use]
+    pub fn with_buckets(mut self, buckets: usize) -> Self {
+        self.buckets = buckets;
+        self
+    }
+
+    /// Sets the interval between two summaries
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+impl<E, EM, I, S, T, Z> Stage<E, EM, S, Z> for CoverageSummaryStage<I, T>
+where
+    EM: EventFirer<I>,
+    I: Input,
+    S: HasFeedbackStates,
+    T: PrimInt + Default + Copy + 'static + Serialize + DeserializeOwned + Debug,
+{
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let now = current_time();
+        if now.checked_sub(self.last_time).unwrap_or_default() < self.interval {
+            return Ok(());
+        }
+        self.last_time = now;
+
+        let history_map = &state
+            .feedback_states()
+            .match_name::<MapFeedbackState<T>>(&self.map_feedback_name)
+            .ok_or_else(|| Error::KeyNotFound("MapFeedbackState not found".to_string()))?
+            .history_map;
+        let summary = CoverageSummary::from_map(history_map, self.buckets);
+        manager.fire(
+            state,
+            Event::UpdateCoverageSummary {
+                summary,
+                phantom: PhantomData,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::InMemoryCorpus,
+        events::{Event, EventFirer},
+        feedbacks::MapFeedbackState,
+        inputs::BytesInput,
+        monitors::CoverageSummary,
+        stages::{CoverageSummaryStage, Stage},
+        state::StdState,
+        Error,
+    };
+
+    /// Keeps the coverage summaries fired
+    #[derive(Debug, Default)]
+    struct SummaryCollector {
+        summaries: Vec<CoverageSummary>,
+    }
+
+    impl EventFirer<BytesInput> for SummaryCollector {
+        fn fire<S>(&mut self, _state: &mut S, event: Event<BytesInput>) -> Result<(), Error> {
+            if let Event::UpdateCoverageSummary { summary, .. } = event {
+                self.summaries.push(summary);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_coverage_summary_stage() {
+        let mut history_map = vec![0_u8; 16];
+        history_map[..4].fill(1);
+        history_map[8] = 1;
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            tuple_list!(MapFeedbackState::with_history_map("edges", history_map)),
+        );
+        let mut mgr = SummaryCollector::default();
+
+        let mut summary_stage =
+            CoverageSummaryStage::<BytesInput, u8>::new("edges").with_buckets(4);
+        summary_stage
+            .perform(&mut (), &mut (), &mut state, &mut mgr, 0)
+            .unwrap();
+        // Not again before the interval elapsed
+        summary_stage
+            .perform(&mut (), &mut (), &mut state, &mut mgr, 0)
+            .unwrap();
+        assert_eq!(
+            mgr.summaries,
+            [CoverageSummary {
+                map_len: 16,
+                buckets: vec![255, 0, 63, 0],
+            }]
+        );
+
+        let mut unknown_stage = CoverageSummaryStage::<BytesInput, u8>::new("cmps");
+        assert!(unknown_stage
+            .perform(&mut (), &mut (), &mut state, &mut mgr, 0)
+            .is_err());
+    }
+}
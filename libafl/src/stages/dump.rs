@@ -0,0 +1,247 @@
+//! The [`DumpToDiskStage`] periodically writes the corpus, and optionally the solutions and the
+//! metadata of the state, to disk, for fuzzers keeping them in memory.
+
+use core::{marker::PhantomData, time::Duration};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    bolts::{current_time, fs::write_file_atomic},
+    corpus::Corpus,
+    inputs::Input,
+    stages::Stage,
+    state::{HasCorpus, HasMetadata, HasSolutions},
+    Error,
+};
+
+/// The default interval between two dumps
+pub const DUMP_TO_DISK_DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The name of the file the metadata of the state get dumped to, as JSON
+pub const DUMP_STATE_METADATA_FILE: &str = "state_metadata.json";
+
+/// A stage periodically dumping the inputs of the corpus to `corpus_dir`, named by a closure.
+/// The files already there are not written again, so the names should identify the inputs,
+/// as [`Input::generate_name`] does for most inputs.
+#[derive(Debug)]
+pub struct DumpToDiskStage<CB, I, S>
+where
+    CB: FnMut(&I, usize) -> String,
+    I: Input,
+    S: HasCorpus<I> + HasSolutions<I> + HasMetadata,
+{
+    corpus_dir: PathBuf,
+    solutions_dir: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+    naming: CB,
+    interval: Duration,
+    last_time: Duration,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<CB, E, EM, I, S, Z> Stage<E, EM, S, Z> for DumpToDiskStage<CB, I, S>
+where
+    CB: FnMut(&I, usize) -> String,
+    I: Input,
+    S: HasCorpus<I> + HasSolutions<I> + HasMetadata,
+{
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        _manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let now = current_time();
+        if now.checked_sub(self.last_time).unwrap_or_default() < self.interval {
+            return Ok(());
+        }
+        self.last_time = now;
+        self.dump(state)
+    }
+}
+
+impl<CB, I, S> DumpToDiskStage<CB, I, S>
+where
+    CB: FnMut(&I, usize) -> String,
+    I: Input,
+    S: HasCorpus<I> + HasSolutions<I> + HasMetadata,
+{
+    /// Creates a new [`DumpToDiskStage`] dumping the corpus to `corpus_dir`, naming the files
+    /// with `naming`, given the input and its index
+    pub fn new<P>(corpus_dir: P, naming: CB) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        fs::create_dir_all(&corpus_dir)?;
+        Ok(Self {
+            corpus_dir: corpus_dir.as_ref().to_path_buf(),
+            solutions_dir: None,
+            state_dir: None,
+            naming,
+            interval: DUMP_TO_DISK_DEFAULT_INTERVAL,
+            last_time: current_time(),
+            phantom: PhantomData,
+        })
+    }
+
+    /// Also dumps the solutions to `solutions_dir`
+    pub fn with_solutions_dir<P>(mut self, solutions_dir: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        fs::create_dir_all(&solutions_dir)?;
+        self.solutions_dir = Some(solutions_dir.as_ref().to_path_buf());
+        Ok(self)
+    }
+
+    /// Also dumps the metadata of the state to [`DUMP_STATE_METADATA_FILE`] in `state_dir`,
+    /// replaced at each dump
+    pub fn with_state_dir<P>(mut self, state_dir: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        fs::create_dir_all(&state_dir)?;
+        self.state_dir = Some(state_dir.as_ref().to_path_buf());
+        Ok(self)
+    }
+
+    /// Sets the interval between two dumps
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Dumps the inputs of `corpus` not in `dir` yet
+    fn dump_corpus<C>(&mut self, corpus: &C, dir: &Path) -> Result<(), Error>
+    where
+        C: Corpus<I>,
+    {
+        for idx in 0..corpus.count() {
+            let mut testcase = corpus.get(idx)?.borrow_mut();
+            let input = testcase.load_input()?;
+            let path = dir.join((self.naming)(input, idx));
+            if !path.exists() {
+                input.to_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Dumps the corpus, and the solutions and the metadata of the state if configured, now
+    pub fn dump(&mut self, state: &S) -> Result<(), Error> {
+        let corpus_dir = self.corpus_dir.clone();
+        self.dump_corpus(state.corpus(), &corpus_dir)?;
+        if let Some(solutions_dir) = self.solutions_dir.clone() {
+            self.dump_corpus(state.solutions(), &solutions_dir)?;
+        }
+        if let Some(state_dir) = &self.state_dir {
+            write_file_atomic(
+                state_dir.join(DUMP_STATE_METADATA_FILE),
+                &serde_json::to_vec_pretty(state.metadata())?,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<I, S> DumpToDiskStage<fn(&I, usize) -> String, I, S>
+where
+    I: Input,
+    S: HasCorpus<I> + HasSolutions<I> + HasMetadata,
+{
+    /// Creates a new [`DumpToDiskStage`] dumping the corpus to `corpus_dir`, naming the files
+    /// with [`Input::generate_name`]
+    pub fn with_generated_names<P>(corpus_dir: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        fn naming<I: Input>(input: &I, idx: usize) -> String {
+            input.generate_name(idx)
+        }
+        Self::new(corpus_dir, naming::<I>)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+    use std::fs;
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        inputs::{BytesInput, Input},
+        stages::{
+            dump::{DumpToDiskStage, DUMP_STATE_METADATA_FILE},
+            Stage,
+        },
+        state::{HasCorpus, HasSolutions, StdState},
+    };
+
+    type TestState =
+        StdState<InMemoryCorpus<BytesInput>, (), BytesInput, StdRand, InMemoryCorpus<BytesInput>>;
+
+    #[test]
+    fn test_dump_to_disk_stage() {
+        let dir = std::env::temp_dir().join(format!("libafl_test_dump_{}", std::process::id()));
+        let corpus_dir = dir.join("corpus");
+        let solutions_dir = dir.join("solutions");
+        let state_dir = dir.join("state");
+
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            tuple_list!(),
+        );
+        state.corpus_mut().add(Testcase::new(vec![1, 2])).unwrap();
+        state.corpus_mut().add(Testcase::new(vec![3])).unwrap();
+        state.solutions_mut().add(Testcase::new(vec![4])).unwrap();
+
+        let mut dump_stage = DumpToDiskStage::new(&corpus_dir, |_input: &BytesInput, idx| {
+            format!("input_{}", idx)
+        })
+        .unwrap()
+        .with_solutions_dir(&solutions_dir)
+        .unwrap()
+        .with_state_dir(&state_dir)
+        .unwrap()
+        .with_interval(Duration::ZERO);
+        dump_stage
+            .perform(&mut (), &mut (), &mut state, &mut (), 0)
+            .unwrap();
+
+        let read = |path| BytesInput::from_file(path).unwrap();
+        assert_eq!(
+            read(corpus_dir.join("input_0")),
+            BytesInput::new(vec![1, 2])
+        );
+        assert_eq!(read(corpus_dir.join("input_1")), BytesInput::new(vec![3]));
+        assert_eq!(
+            read(solutions_dir.join("input_0")),
+            BytesInput::new(vec![4])
+        );
+        assert_eq!(fs::read_dir(&corpus_dir).unwrap().count(), 2);
+        assert_eq!(fs::read_dir(&solutions_dir).unwrap().count(), 1);
+        let metadata = fs::read(state_dir.join(DUMP_STATE_METADATA_FILE)).unwrap();
+        assert!(serde_json::from_slice::<serde_json::Value>(&metadata).is_ok());
+
+        // The files already dumped are not written again
+        BytesInput::new(vec![5])
+            .to_file(corpus_dir.join("input_0"))
+            .unwrap();
+        state.corpus_mut().add(Testcase::new(vec![6])).unwrap();
+        dump_stage
+            .perform(&mut (), &mut (), &mut state, &mut (), 0)
+            .unwrap();
+        assert_eq!(read(corpus_dir.join("input_0")), BytesInput::new(vec![5]));
+        assert_eq!(read(corpus_dir.join("input_2")), BytesInput::new(vec![6]));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,183 @@
+//! The [`EnergyStage`] reports the energy consumed by the client, measured with the RAPL counters
+//! of the CPU, to compare fuzzing configurations on efficiency rather than on raw executions per
+//! second.
+
+use alloc::{string::ToString, vec::Vec};
+use core::{marker::PhantomData, time::Duration};
+
+use crate::{
+    bolts::{current_time, os::rapl::Rapl},
+    events::{Event, EventFirer},
+    inputs::Input,
+    monitors::UserStats,
+    stages::Stage,
+    state::HasExecutions,
+    Error,
+};
+
+/// The default interval between two energy reports
+pub const ENERGY_REPORT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// The name of the user stat holding the energy consumption
+pub const ENERGY_STAT_NAME: &str = "joules/Mexec";
+
+/// A stage periodically reporting the energy consumed per million executions since the
+/// previous report, as user stat, to the monitor.
+///
+/// The counters measure whole CPU packages, see [`crate::bolts::os::rapl`].
+#[derive(Debug)]
+pub struct EnergyStage<I> {
+    rapl: Rapl,
+    interval: Duration,
+    last_time: Duration,
+    last_energy: Vec<u64>,
+    last_executions: usize,
+    phantom: PhantomData<I>,
+}
+
+impl<I> EnergyStage<I>
+where
+    I: Input,
+{
+    /// Creates a new [`EnergyStage`], failing if the RAPL counters are not readable
+    pub fn new() -> Result<Self, Error> {
+        Self::with_rapl(Rapl::new()?)
+    }
+
+    /// Creates a new [`EnergyStage`] reading the given RAPL counters
+    pub fn with_rapl(rapl: Rapl) -> Result<Self, Error> {
+        let last_energy = rapl.read()?;
+        Ok(Self {
+            rapl,
+            interval: ENERGY_REPORT_INTERVAL,
+            last_time: current_time(),
+            last_energy,
+            last_executions: 0,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Sets the interval between two energy reports
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+impl<E, EM, I, S, Z> Stage<E, EM, S, Z> for EnergyStage<I>
+where
+    EM: EventFirer<I>,
+    I: Input,
+    S: HasExecutions,
+{
+    #[allow(clippy::cast_precision_loss)]
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let now = current_time();
+        if now.checked_sub(self.last_time).unwrap_or_default() < self.interval {
+            return Ok(());
+        }
+        let energy = self.rapl.read()?;
+        let executions = *state.executions();
+        let consumed_uj = self.rapl.consumed_since(&self.last_energy, &energy);
+        let new_executions = executions.saturating_sub(self.last_executions);
+
+        self.last_time = now;
+        self.last_energy = energy;
+        self.last_executions = executions;
+
+        if new_executions == 0 {
+            return Ok(());
+        }
+        // Microjoules per execution are joules per million executions
+        let joules_per_mexec = consumed_uj as f64 / new_executions as f64;
+        manager.fire(
+            state,
+            Event::UpdateUserStats {
+                name: ENERGY_STAT_NAME.to_string(),
+                value: UserStats::Float(joules_per_mexec),
+                phantom: PhantomData,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec::Vec};
+    use core::time::Duration;
+    use std::fs;
+
+    use crate::{
+        bolts::{os::rapl::Rapl, rands::StdRand, tuples::tuple_list},
+        corpus::InMemoryCorpus,
+        events::{Event, EventFirer},
+        inputs::BytesInput,
+        monitors::UserStats,
+        stages::{energy::ENERGY_STAT_NAME, EnergyStage, Stage},
+        state::{HasExecutions, StdState},
+        Error,
+    };
+
+    /// Keeps the user stats fired
+    #[derive(Debug, Default)]
+    struct StatsCollector {
+        stats: Vec<(String, UserStats)>,
+    }
+
+    impl EventFirer<BytesInput> for StatsCollector {
+        fn fire<S>(&mut self, _state: &mut S, event: Event<BytesInput>) -> Result<(), Error> {
+            if let Event::UpdateUserStats { name, value, .. } = event {
+                self.stats.push((name, value));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_energy_stage() {
+        let powercap =
+            std::env::temp_dir().join(format!("libafl_test_powercap_{}", std::process::id()));
+        let zone = powercap.join("intel-rapl:0");
+        fs::create_dir_all(&zone).unwrap();
+        fs::write(zone.join("max_energy_range_uj"), "10000000\n").unwrap();
+        fs::write(zone.join("energy_uj"), "1000000\n").unwrap();
+
+        let mut energy_stage =
+            EnergyStage::<BytesInput>::with_rapl(Rapl::with_powercap_path(&powercap).unwrap())
+                .unwrap()
+                .with_interval(Duration::ZERO);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            tuple_list!(),
+        );
+        let mut mgr = StatsCollector::default();
+
+        // 0.5 J for 2000 executions
+        *state.executions_mut() = 2000;
+        fs::write(zone.join("energy_uj"), "1500000\n").unwrap();
+        energy_stage
+            .perform(&mut (), &mut (), &mut state, &mut mgr, 0)
+            .unwrap();
+        // Nothing to report without new executions
+        energy_stage
+            .perform(&mut (), &mut (), &mut state, &mut mgr, 0)
+            .unwrap();
+        fs::remove_dir_all(&powercap).unwrap();
+
+        assert_eq!(mgr.stats.len(), 1);
+        assert_eq!(mgr.stats[0].0, ENERGY_STAT_NAME);
+        assert!(
+            matches!(mgr.stats[0].1, UserStats::Float(joules) if (joules - 250.0).abs() < 1e-9)
+        );
+    }
+}
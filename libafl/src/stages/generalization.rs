@@ -1,19 +1,20 @@
 //! The tracing stage can trace the target and enrich a testcase with metadata, for example for `CmpLog`.
 
+use ahash::AHasher;
 use alloc::{
     string::{String, ToString},
     vec::Vec,
 };
-use core::{fmt::Debug, marker::PhantomData};
-use hashbrown::HashSet;
+use core::{fmt::Debug, hash::Hasher, marker::PhantomData};
+use hashbrown::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     bolts::AsSlice,
     corpus::Corpus,
-    executors::{Executor, HasObservers},
+    executors::{Executor, ExitKind, HasObservers},
     feedbacks::map::MapNoveltiesMetadata,
-    inputs::{GeneralizedInput, GeneralizedItem, HasBytesVec},
+    inputs::{GeneralizedInput, GeneralizedItem, HasBytesVec, Input},
     mark_feature_time,
     observers::{MapObserver, ObserversTuple},
     stages::Stage,
@@ -27,6 +28,25 @@ use crate::monitors::PerfFeature;
 
 const MAX_GENERALIZED_LEN: usize = 8192;
 
+/// The default sizes of the chunks removed by the [`GeneralizationStage`], minus one, from the
+/// largest to the smallest. The chunks of size 1 try to remove each byte.
+pub const DEFAULT_CHUNK_STEPS: [u8; 5] = [255, 127, 63, 31, 0];
+
+/// The default characters the [`GeneralizationStage`] splits the input at, to remove the parts
+/// in between, fitting C-like languages
+pub const DEFAULT_SPLIT_CHARS: [u8; 7] = [b'.', b';', b',', b'\n', b'\r', b'#', b' '];
+
+/// The default pairs of characters the [`GeneralizationStage`] removes the content of, fitting
+/// C-like languages
+pub const DEFAULT_CLOSURES: [(u8, u8); 6] = [
+    (b'(', b')'),
+    (b'[', b']'),
+    (b'{', b'}'),
+    (b'<', b'>'),
+    (b'\'', b'\''),
+    (b'"', b'"'),
+];
+
 /// A state metadata holding the set of indexes related to the generalized corpus entries
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct GeneralizedIndexesMetadata {
@@ -42,6 +62,65 @@ impl GeneralizedIndexesMetadata {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Removes the index of a corpus entry removed from the corpus, shifting the next ones
+    pub fn remove_corpus_idx(&mut self, idx: usize) {
+        self.indexes = self
+            .indexes
+            .iter()
+            .filter(|corpus_idx| **corpus_idx != idx)
+            .map(|corpus_idx| {
+                if *corpus_idx > idx {
+                    corpus_idx - 1
+                } else {
+                    *corpus_idx
+                }
+            })
+            .collect();
+    }
+}
+
+/// A testcase metadata holding the progress of its generalization, to resume it if the fuzzer
+/// gets interrupted, and to not retry it if the generalized input got rejected
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct GeneralizationProgressMetadata {
+    /// The payload generalized so far, `None` standing for a gap
+    pub payload: Vec<Option<u8>>,
+    /// The number of passes of the [`GeneralizationStage`] done on the payload
+    pub passes_done: usize,
+    /// Whether the generalized input got rejected, being longer than the maximum length
+    pub too_long: bool,
+}
+
+crate::impl_serdeany!(GeneralizationProgressMetadata);
+
+/// A pass of the [`GeneralizationStage`] over the payload
+#[derive(Debug, Clone, Copy)]
+enum GeneralizationPass {
+    /// Tries to remove the chunks of the given size, minus one
+    Chunks(u8),
+    /// Tries to remove the parts between the given split character
+    Split(u8),
+    /// Tries to remove the content of the closures of the given characters
+    Closure(u8, u8),
+}
+
+/// The candidates tried while generalizing a testcase, not to run the same candidate twice,
+/// and the executions left in the budget of the testcase
+#[derive(Debug)]
+struct Candidates {
+    /// Whether the candidate with the given hash kept the novelties
+    seen: HashMap<u64, bool>,
+    executions_left: Option<usize>,
+}
+
+impl Candidates {
+    fn new(max_executions: Option<usize>) -> Self {
+        Self {
+            seen: HashMap::default(),
+            executions_left: max_executions,
+        }
+    }
 }
 
 fn increment_by_offset(_list: &[Option<u8>], idx: usize, off: u8) -> usize {
@@ -58,7 +137,73 @@ fn find_next_char(list: &[Option<u8>], mut idx: usize, ch: u8) -> usize {
     idx
 }
 
-/// A stage that runs a tracer executor
+/// Runs `input` through the observers of the executor, as the stages running the target
+/// without evaluating the outcome do
+pub(crate) fn run_target_observed<E, EM, I, OT, S, Z>(
+    fuzzer: &mut Z,
+    executor: &mut E,
+    state: &mut S,
+    manager: &mut EM,
+    input: &I,
+) -> Result<ExitKind, Error>
+where
+    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions,
+{
+    start_timer!(state);
+    executor.observers_mut().pre_exec_all(state, input)?;
+    mark_feature_time!(state, PerfFeature::PreExecObservers);
+
+    start_timer!(state);
+    let exit_kind = executor.run_target(fuzzer, state, manager, input)?;
+    mark_feature_time!(state, PerfFeature::TargetExecution);
+
+    *state.executions_mut() += 1;
+
+    start_timer!(state);
+    executor
+        .observers_mut()
+        .post_exec_all(state, input, &exit_kind)?;
+    mark_feature_time!(state, PerfFeature::PostExecObservers);
+
+    Ok(exit_kind)
+}
+
+/// Runs `input`, and checks whether the map observer named `map_observer_name` still has all
+/// the `novelties` set, i.e. whether the input still reaches the coverage it was kept for
+pub(crate) fn run_and_check_novelties<E, EM, I, O, OT, S, Z>(
+    fuzzer: &mut Z,
+    executor: &mut E,
+    state: &mut S,
+    manager: &mut EM,
+    map_observer_name: &str,
+    novelties: &[usize],
+    input: &I,
+) -> Result<(ExitKind, bool), Error>
+where
+    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions,
+{
+    let exit_kind = run_target_observed(fuzzer, executor, state, manager, input)?;
+
+    let cnt = executor
+        .observers()
+        .match_name::<O>(map_observer_name)
+        .ok_or_else(|| Error::KeyNotFound("MapObserver not found".to_string()))?
+        .how_many_set(novelties);
+
+    Ok((exit_kind, cnt == novelties.len()))
+}
+
+/// A stage generalizing the inputs, as in Grimoire: it replaces the parts of the input not
+/// needed to reach its new coverage with gaps, trying chunks of decreasing sizes, then the parts
+/// between split characters, then the content of closures.
+/// The chunk sizes, split characters and closures can be tuned to the format of the inputs.
 #[derive(Clone, Debug)]
 pub struct GeneralizationStage<EM, O, OT, S, Z>
 where
@@ -67,6 +212,10 @@ where
     S: HasClientPerfMonitor + HasExecutions + HasMetadata + HasCorpus<GeneralizedInput>,
 {
     map_observer_name: String,
+    chunk_steps: Vec<u8>,
+    split_chars: Vec<u8>,
+    closures: Vec<(u8, u8)>,
+    max_executions: Option<usize>,
     #[allow(clippy::type_complexity)]
     phantom: PhantomData<(EM, O, OT, S, Z)>,
 }
@@ -96,12 +245,12 @@ where
             state.add_metadata(GeneralizedIndexesMetadata::new());
         }
 
-        let (mut payload, original, novelties) = {
+        let (mut payload, passes_done, original, novelties) = {
             start_timer!(state);
             state.corpus().get(corpus_idx)?.borrow_mut().load_input()?;
             mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
-            let mut entry = state.corpus().get(corpus_idx)?.borrow_mut();
-            let input = entry.input_mut().as_mut().unwrap();
+            let entry = state.corpus().get(corpus_idx)?.borrow();
+            let input = entry.input().as_ref().unwrap();
 
             if input.generalized().is_some() {
                 drop(entry);
@@ -114,7 +263,15 @@ where
                 return Ok(());
             }
 
-            let payload: Vec<_> = input.bytes().iter().map(|&x| Some(x)).collect();
+            // Resume the generalization, unless it got rejected already
+            let progress = entry.metadata().get::<GeneralizationProgressMetadata>();
+            if progress.map_or(false, |progress| progress.too_long) {
+                return Ok(());
+            }
+            let (payload, passes_done) = match progress {
+                Some(progress) => (progress.payload.clone(), progress.passes_done),
+                None => (input.bytes().iter().map(|&x| Some(x)).collect(), 0),
+            };
             let original = input.clone();
             let meta = entry.metadata().get::<MapNoveltiesMetadata>().ok_or_else(|| {
                     Error::KeyNotFound(format!(
@@ -122,7 +279,7 @@ where
                         corpus_idx
                     ))
                 })?;
-            (payload, original, meta.as_slice().to_vec())
+            (payload, passes_done, original, meta.as_slice().to_vec())
         };
 
         // Do not generalized unstable inputs
@@ -130,193 +287,65 @@ where
             return Ok(());
         }
 
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            increment_by_offset,
-            255,
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            increment_by_offset,
-            127,
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            increment_by_offset,
-            63,
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            increment_by_offset,
-            31,
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            increment_by_offset,
-            0,
-        )?;
-
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            find_next_char,
-            b'.',
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            find_next_char,
-            b';',
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            find_next_char,
-            b',',
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            find_next_char,
-            b'\n',
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            find_next_char,
-            b'\r',
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            find_next_char,
-            b'#',
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            find_next_char,
-            b' ',
-        )?;
+        let mut candidates = Candidates::new(self.max_executions);
+        let passes = self.passes();
+        for (pass_idx, pass) in passes.iter().enumerate().skip(passes_done) {
+            match *pass {
+                GeneralizationPass::Chunks(step) => self.find_gaps(
+                    fuzzer,
+                    executor,
+                    state,
+                    manager,
+                    &mut payload,
+                    &novelties,
+                    &mut candidates,
+                    increment_by_offset,
+                    step,
+                )?,
+                GeneralizationPass::Split(split_char) => self.find_gaps(
+                    fuzzer,
+                    executor,
+                    state,
+                    manager,
+                    &mut payload,
+                    &novelties,
+                    &mut candidates,
+                    find_next_char,
+                    split_char,
+                )?,
+                GeneralizationPass::Closure(opening_char, closing_char) => self
+                    .find_gaps_in_closures(
+                        fuzzer,
+                        executor,
+                        state,
+                        manager,
+                        &mut payload,
+                        &novelties,
+                        &mut candidates,
+                        opening_char,
+                        closing_char,
+                    )?,
+            }
 
-        self.find_gaps_in_closures(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            b'(',
-            b')',
-        )?;
-        self.find_gaps_in_closures(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            b'[',
-            b']',
-        )?;
-        self.find_gaps_in_closures(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            b'{',
-            b'}',
-        )?;
-        self.find_gaps_in_closures(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            b'<',
-            b'>',
-        )?;
-        self.find_gaps_in_closures(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            b'\'',
-            b'\'',
-        )?;
-        self.find_gaps_in_closures(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            b'"',
-            b'"',
-        )?;
+            // Record the progress, to resume after this pass if the fuzzer gets interrupted
+            state.corpus().get(corpus_idx)?.borrow_mut().add_metadata(
+                GeneralizationProgressMetadata {
+                    payload: payload.clone(),
+                    passes_done: pass_idx + 1,
+                    too_long: false,
+                },
+            );
+        }
 
         if payload.len() <= MAX_GENERALIZED_LEN {
             // Save the modified input in the corpus
             {
                 let mut entry = state.corpus().get(corpus_idx)?.borrow_mut();
+                drop(
+                    entry
+                        .metadata_mut()
+                        .remove::<GeneralizationProgressMetadata>(),
+                );
                 entry.load_input()?;
                 entry
                     .input_mut()
@@ -341,6 +370,15 @@ where
                 .unwrap()
                 .indexes
                 .insert(corpus_idx);
+        } else {
+            // Do not generalize it again in the next cycles
+            state.corpus().get(corpus_idx)?.borrow_mut().add_metadata(
+                GeneralizationProgressMetadata {
+                    payload: vec![],
+                    passes_done: passes.len(),
+                    too_long: true,
+                },
+            );
         }
 
         Ok(())
@@ -356,10 +394,7 @@ where
     /// Create a new [`GeneralizationStage`].
     #[must_use]
     pub fn new(map_observer: &O) -> Self {
-        Self {
-            map_observer_name: map_observer.name().to_string(),
-            phantom: PhantomData,
-        }
+        Self::from_name(map_observer.name())
     }
 
     /// Create a new [`GeneralizationStage`] from name
@@ -367,10 +402,60 @@ where
     pub fn from_name(map_observer_name: &str) -> Self {
         Self {
             map_observer_name: map_observer_name.to_string(),
+            chunk_steps: DEFAULT_CHUNK_STEPS.to_vec(),
+            split_chars: DEFAULT_SPLIT_CHARS.to_vec(),
+            closures: DEFAULT_CLOSURES.to_vec(),
+            max_executions: None,
             phantom: PhantomData,
         }
     }
 
+    /// Sets the sizes of the chunks to remove, minus one, see [`DEFAULT_CHUNK_STEPS`]
+    #[must_use]
+    pub fn with_chunk_steps(mut self, chunk_steps: &[u8]) -> Self {
+        self.chunk_steps = chunk_steps.to_vec();
+        self
+    }
+
+    /// Sets the characters to split the input at, see [`DEFAULT_SPLIT_CHARS`]
+    #[must_use]
+    pub fn with_split_chars(mut self, split_chars: &[u8]) -> Self {
+        self.split_chars = split_chars.to_vec();
+        self
+    }
+
+    /// Sets the opening and closing characters of the closures to empty, see [`DEFAULT_CLOSURES`]
+    #[must_use]
+    pub fn with_closures(mut self, closures: &[(u8, u8)]) -> Self {
+        self.closures = closures.to_vec();
+        self
+    }
+
+    /// Limits the executions spent generalizing each testcase. When the budget runs out, the
+    /// parts of the input not tried yet are kept as they are. Unlimited by default.
+    #[must_use]
+    pub fn with_max_executions(mut self, max_executions: usize) -> Self {
+        self.max_executions = Some(max_executions);
+        self
+    }
+
+    /// The passes over the payload, in order. The progress of a testcase counts the passes done,
+    /// so changing them resumes the interrupted generalizations at another pass.
+    fn passes(&self) -> Vec<GeneralizationPass> {
+        self.chunk_steps
+            .iter()
+            .map(|&step| GeneralizationPass::Chunks(step))
+            .chain(
+                self.split_chars
+                    .iter()
+                    .map(|&split_char| GeneralizationPass::Split(split_char)),
+            )
+            .chain(self.closures.iter().map(|&(opening_char, closing_char)| {
+                GeneralizationPass::Closure(opening_char, closing_char)
+            }))
+            .collect()
+    }
+
     fn verify_input<E>(
         &self,
         fuzzer: &mut Z,
@@ -383,29 +468,49 @@ where
     where
         E: Executor<EM, GeneralizedInput, S, Z> + HasObservers<GeneralizedInput, OT, S>,
     {
-        start_timer!(state);
-        executor.observers_mut().pre_exec_all(state, input)?;
-        mark_feature_time!(state, PerfFeature::PreExecObservers);
-
-        start_timer!(state);
-        let exit_kind = executor.run_target(fuzzer, state, manager, input)?;
-        mark_feature_time!(state, PerfFeature::TargetExecution);
-
-        *state.executions_mut() += 1;
-
-        start_timer!(state);
-        executor
-            .observers_mut()
-            .post_exec_all(state, input, &exit_kind)?;
-        mark_feature_time!(state, PerfFeature::PostExecObservers);
-
-        let cnt = executor
-            .observers()
-            .match_name::<O>(&self.map_observer_name)
-            .ok_or_else(|| Error::KeyNotFound("MapObserver not found".to_string()))?
-            .how_many_set(novelties);
+        let (_, keeps_novelties) = run_and_check_novelties::<E, EM, _, O, OT, S, Z>(
+            fuzzer,
+            executor,
+            state,
+            manager,
+            &self.map_observer_name,
+            novelties,
+            input,
+        )?;
+        Ok(keeps_novelties)
+    }
 
-        Ok(cnt == novelties.len())
+    /// Checks whether `candidate` keeps the novelties, running it only if it was not tried yet,
+    /// and if the budget of the testcase is not spent
+    #[allow(clippy::too_many_arguments)]
+    fn try_candidate<E>(
+        &self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        novelties: &[usize],
+        candidates: &mut Candidates,
+        candidate: &GeneralizedInput,
+    ) -> Result<bool, Error>
+    where
+        E: Executor<EM, GeneralizedInput, S, Z> + HasObservers<GeneralizedInput, OT, S>,
+    {
+        let mut hasher = AHasher::new_with_keys(0, 0);
+        hasher.write(candidate.bytes());
+        let hash = hasher.finish();
+        if let Some(keeps_novelties) = candidates.seen.get(&hash) {
+            return Ok(*keeps_novelties);
+        }
+        match &mut candidates.executions_left {
+            Some(0) => return Ok(false),
+            Some(left) => *left -= 1,
+            None => (),
+        }
+        let keeps_novelties =
+            self.verify_input(fuzzer, executor, state, manager, novelties, candidate)?;
+        candidates.seen.insert(hash, keeps_novelties);
+        Ok(keeps_novelties)
     }
 
     fn trim_payload(payload: &mut Vec<Option<u8>>) {
@@ -422,6 +527,7 @@ where
         manager: &mut EM,
         payload: &mut Vec<Option<u8>>,
         novelties: &[usize],
+        candidates: &mut Candidates,
         find_next_index: fn(&[Option<u8>], usize, u8) -> usize,
         split_char: u8,
     ) -> Result<(), Error>
@@ -442,7 +548,9 @@ where
                 .bytes_mut()
                 .extend(payload[end..].iter().flatten());
 
-            if self.verify_input(fuzzer, executor, state, manager, novelties, &candidate)? {
+            if self.try_candidate(
+                fuzzer, executor, state, manager, novelties, candidates, &candidate,
+            )? {
                 for item in &mut payload[start..end] {
                     *item = None;
                 }
@@ -464,6 +572,7 @@ where
         manager: &mut EM,
         payload: &mut Vec<Option<u8>>,
         novelties: &[usize],
+        candidates: &mut Candidates,
         opening_char: u8,
         closing_char: u8,
     ) -> Result<(), Error>
@@ -492,7 +601,9 @@ where
                         .bytes_mut()
                         .extend(payload[end..].iter().flatten());
 
-                    if self.verify_input(fuzzer, executor, state, manager, novelties, &candidate)? {
+                    if self.try_candidate(
+                        fuzzer, executor, state, manager, novelties, candidates, &candidate,
+                    )? {
                         for item in &mut payload[start..end] {
                             *item = None;
                         }
@@ -501,6 +612,8 @@ where
                 }
                 end -= 1;
             }
+            // Go on with the next opening character
+            index += 1;
         }
 
         Self::trim_payload(payload);
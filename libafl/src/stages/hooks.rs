@@ -0,0 +1,447 @@
+//! Hooks running before and after each stage, for each corpus entry, e.g. to reset the global state
+//! of the target or to rotate log files, without wrapping every stage.
+
+use core::marker::PhantomData;
+
+use crate::{
+    stages::{Stage, StagesTuple},
+    Error,
+};
+
+/// A hook called before and after each stage of a [`StagesTuple`] wrapped in [`HookedStages`]
+pub trait StageHook<E, EM, S, Z> {
+    /// Called before each stage
+    fn pre_stage(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        _state: &mut S,
+        _manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called after each stage that succeeded
+    fn post_stage(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        _state: &mut S,
+        _manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// A tuple of [`StageHook`]s, the pre hooks run in order, the post hooks too
+pub trait StageHooksTuple<E, EM, S, Z> {
+    /// Calls [`StageHook::pre_stage`] on all the hooks
+    fn pre_stage_all(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error>;
+
+    /// Calls [`StageHook::post_stage`] on all the hooks
+    fn post_stage_all(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error>;
+}
+
+impl<E, EM, S, Z> StageHooksTuple<E, EM, S, Z> for () {
+    fn pre_stage_all(
+        &mut self,
+        _: &mut Z,
+        _: &mut E,
+        _: &mut S,
+        _: &mut EM,
+        _: usize,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn post_stage_all(
+        &mut self,
+        _: &mut Z,
+        _: &mut E,
+        _: &mut S,
+        _: &mut EM,
+        _: usize,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<Head, Tail, E, EM, S, Z> StageHooksTuple<E, EM, S, Z> for (Head, Tail)
+where
+    Head: StageHook<E, EM, S, Z>,
+    Tail: StageHooksTuple<E, EM, S, Z>,
+{
+    fn pre_stage_all(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        self.0
+            .pre_stage(fuzzer, executor, state, manager, corpus_idx)?;
+        self.1
+            .pre_stage_all(fuzzer, executor, state, manager, corpus_idx)
+    }
+
+    fn post_stage_all(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        self.0
+            .post_stage(fuzzer, executor, state, manager, corpus_idx)?;
+        self.1
+            .post_stage_all(fuzzer, executor, state, manager, corpus_idx)
+    }
+}
+
+/// A [`StageHook`] calling closures before and after each stage
+#[derive(Debug)]
+pub struct ClosureStageHook<PRE, POST, E, EM, S, Z>
+where
+    PRE: FnMut(&mut Z, &mut E, &mut S, &mut EM, usize) -> Result<(), Error>,
+    POST: FnMut(&mut Z, &mut E, &mut S, &mut EM, usize) -> Result<(), Error>,
+{
+    pre: PRE,
+    post: POST,
+    phantom: PhantomData<(E, EM, S, Z)>,
+}
+
+impl<PRE, POST, E, EM, S, Z> StageHook<E, EM, S, Z> for ClosureStageHook<PRE, POST, E, EM, S, Z>
+where
+    PRE: FnMut(&mut Z, &mut E, &mut S, &mut EM, usize) -> Result<(), Error>,
+    POST: FnMut(&mut Z, &mut E, &mut S, &mut EM, usize) -> Result<(), Error>,
+{
+    fn pre_stage(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        (self.pre)(fuzzer, executor, state, manager, corpus_idx)
+    }
+
+    fn post_stage(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        (self.post)(fuzzer, executor, state, manager, corpus_idx)
+    }
+}
+
+impl<PRE, POST, E, EM, S, Z> ClosureStageHook<PRE, POST, E, EM, S, Z>
+where
+    PRE: FnMut(&mut Z, &mut E, &mut S, &mut EM, usize) -> Result<(), Error>,
+    POST: FnMut(&mut Z, &mut E, &mut S, &mut EM, usize) -> Result<(), Error>,
+{
+    /// Creates a new [`ClosureStageHook`], calling `pre` before each stage and `post` after
+    #[must_use]
+    pub fn new(pre: PRE, post: POST) -> Self {
+        Self {
+            pre,
+            post,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// A [`StagesTuple`] running the hooks of a [`StageHooksTuple`] around each of its stages
+#[derive(Debug)]
+pub struct HookedStages<H, ST> {
+    hooks: H,
+    stages: ST,
+}
+
+impl<H, ST> HookedStages<H, ST> {
+    /// Creates a new [`HookedStages`], running `hooks` around each stage of `stages`
+    #[must_use]
+    pub fn new(hooks: H, stages: ST) -> Self {
+        Self { hooks, stages }
+    }
+
+    /// The hooks
+    pub fn hooks(&self) -> &H {
+        &self.hooks
+    }
+
+    /// The hooks (mutable)
+    pub fn hooks_mut(&mut self) -> &mut H {
+        &mut self.hooks
+    }
+}
+
+impl<E, EM, H, S, ST, Z> StagesTuple<E, EM, S, Z> for HookedStages<H, ST>
+where
+    H: StageHooksTuple<E, EM, S, Z>,
+    ST: StagesTuple<E, EM, S, Z>,
+{
+    fn perform_all(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        self.stages.perform_all_hooked(
+            fuzzer,
+            executor,
+            state,
+            manager,
+            corpus_idx,
+            &mut self.hooks,
+        )
+    }
+
+    fn perform_all_hooked<H2>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+        hooks: &mut H2,
+    ) -> Result<(), Error>
+    where
+        H2: StageHooksTuple<E, EM, S, Z>,
+    {
+        // The outer hooks run around the inner ones, for each stage
+        self.stages.perform_all_hooked(
+            fuzzer,
+            executor,
+            state,
+            manager,
+            corpus_idx,
+            &mut OuterHooks {
+                outer: hooks,
+                inner: &mut self.hooks,
+            },
+        )
+    }
+}
+
+/// Nests the hooks of nested [`HookedStages`], the outer ones running first before a stage and
+/// last after it
+struct OuterHooks<'a, H1, H2> {
+    outer: &'a mut H1,
+    inner: &'a mut H2,
+}
+
+impl<E, EM, H1, H2, S, Z> StageHooksTuple<E, EM, S, Z> for OuterHooks<'_, H1, H2>
+where
+    H1: StageHooksTuple<E, EM, S, Z>,
+    H2: StageHooksTuple<E, EM, S, Z>,
+{
+    fn pre_stage_all(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        self.outer
+            .pre_stage_all(fuzzer, executor, state, manager, corpus_idx)?;
+        self.inner
+            .pre_stage_all(fuzzer, executor, state, manager, corpus_idx)
+    }
+
+    fn post_stage_all(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        self.inner
+            .post_stage_all(fuzzer, executor, state, manager, corpus_idx)?;
+        self.outer
+            .post_stage_all(fuzzer, executor, state, manager, corpus_idx)
+    }
+}
+
+/// Runs the `hooked` stage between the hooks
+pub(crate) fn perform_hooked<E, EM, H, S, ST, Z>(
+    hooked: &mut ST,
+    fuzzer: &mut Z,
+    executor: &mut E,
+    state: &mut S,
+    manager: &mut EM,
+    corpus_idx: usize,
+    hooks: &mut H,
+) -> Result<(), Error>
+where
+    H: StageHooksTuple<E, EM, S, Z>,
+    ST: Stage<E, EM, S, Z> + ?Sized,
+{
+    hooks.pre_stage_all(fuzzer, executor, state, manager, corpus_idx)?;
+    hooked.perform(fuzzer, executor, state, manager, corpus_idx)?;
+    hooks.post_stage_all(fuzzer, executor, state, manager, corpus_idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{
+        format,
+        string::{String, ToString},
+        vec::Vec,
+    };
+
+    use crate::{
+        bolts::tuples::tuple_list,
+        stages::{ClosureStageHook, HookedStages, Stage, StageHook, StagesTuple},
+        Error,
+    };
+
+    /// Logs its name into the state when performed, fails if it's `"fail"`
+    struct LogStage(&'static str);
+
+    impl Stage<(), (), Vec<String>, ()> for LogStage {
+        fn perform(
+            &mut self,
+            _fuzzer: &mut (),
+            _executor: &mut (),
+            log: &mut Vec<String>,
+            _manager: &mut (),
+            _corpus_idx: usize,
+        ) -> Result<(), Error> {
+            log.push(self.0.to_string());
+            if self.0 == "fail" {
+                return Err(Error::IllegalState("failing stage".into()));
+            }
+            Ok(())
+        }
+    }
+
+    /// Logs its name into the state around each stage
+    struct LogHook(&'static str);
+
+    impl StageHook<(), (), Vec<String>, ()> for LogHook {
+        fn pre_stage(
+            &mut self,
+            _fuzzer: &mut (),
+            _executor: &mut (),
+            log: &mut Vec<String>,
+            _manager: &mut (),
+            corpus_idx: usize,
+        ) -> Result<(), Error> {
+            log.push(format!("pre {} {}", self.0, corpus_idx));
+            Ok(())
+        }
+
+        fn post_stage(
+            &mut self,
+            _fuzzer: &mut (),
+            _executor: &mut (),
+            log: &mut Vec<String>,
+            _manager: &mut (),
+            corpus_idx: usize,
+        ) -> Result<(), Error> {
+            log.push(format!("post {} {}", self.0, corpus_idx));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_hooked_stages() {
+        let closure_hook = ClosureStageHook::new(
+            |_fuzzer: &mut (),
+             _executor: &mut (),
+             log: &mut Vec<String>,
+             _manager: &mut (),
+             _corpus_idx| {
+                log.push("pre closure".to_string());
+                Ok(())
+            },
+            |_fuzzer: &mut (),
+             _executor: &mut (),
+             log: &mut Vec<String>,
+             _manager: &mut (),
+             _corpus_idx| {
+                log.push("post closure".to_string());
+                Ok(())
+            },
+        );
+        let mut stages = HookedStages::new(
+            tuple_list!(LogHook("a"), closure_hook),
+            tuple_list!(LogStage("one"), LogStage("two")),
+        );
+        let mut log = Vec::new();
+        stages
+            .perform_all(&mut (), &mut (), &mut log, &mut (), 3)
+            .unwrap();
+        assert_eq!(
+            log,
+            [
+                "pre a 3",
+                "pre closure",
+                "one",
+                "post a 3",
+                "post closure",
+                "pre a 3",
+                "pre closure",
+                "two",
+                "post a 3",
+                "post closure",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested_hooked_stages() {
+        let mut stages = HookedStages::new(
+            tuple_list!(LogHook("outer")),
+            HookedStages::new(
+                tuple_list!(LogHook("inner")),
+                tuple_list!(LogStage("one"), LogStage("fail"), LogStage("never")),
+            ),
+        );
+        let mut log = Vec::new();
+        assert!(stages
+            .perform_all(&mut (), &mut (), &mut log, &mut (), 0)
+            .is_err());
+        // The outer hooks run around the inner ones. The post hooks don't run after a failing stage,
+        // nor do the next stages
+        assert_eq!(
+            log,
+            [
+                "pre outer 0",
+                "pre inner 0",
+                "one",
+                "post inner 0",
+                "post outer 0",
+                "pre outer 0",
+                "pre inner 0",
+                "fail",
+            ]
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Stages running other stages only under some conditions, to compose conditional pipelines,
+//! e.g. tracing only every Nth corpus entry, or only the small inputs.
+
+use crate::{
+    stages::{Stage, StagesTuple},
+    Error,
+};
+
+/// A [`Stage`] performing all its inner stages, only when the closure, called with the state and
+/// the current corpus index, returns `true`.
+#[derive(Debug)]
+pub struct IfStage<CB, ST> {
+    closure: CB,
+    if_stages: ST,
+}
+
+impl<CB, E, EM, S, ST, Z> Stage<E, EM, S, Z> for IfStage<CB, ST>
+where
+    CB: FnMut(&mut S, usize) -> Result<bool, Error>,
+    ST: StagesTuple<E, EM, S, Z>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        if (self.closure)(state, corpus_idx)? {
+            self.if_stages
+                .perform_all(fuzzer, executor, state, manager, corpus_idx)?;
+        }
+        Ok(())
+    }
+}
+
+impl<CB, ST> IfStage<CB, ST> {
+    /// Create a new [`IfStage`], performing the `if_stages` tuple when `closure` returns `true`
+    #[must_use]
+    pub fn new(closure: CB, if_stages: ST) -> Self {
+        Self { closure, if_stages }
+    }
+
+    /// The inner stages
+    pub fn if_stages(&self) -> &ST {
+        &self.if_stages
+    }
+
+    /// The inner stages (mutable)
+    pub fn if_stages_mut(&mut self) -> &mut ST {
+        &mut self.if_stages
+    }
+}
+
+/// A [`Stage`] wrapping another one, skipped when the closure, called with the state and the
+/// current corpus index, returns `false`.
+/// Unlike the [`IfStage`], it wraps a single stage, and counts the skips.
+#[derive(Debug)]
+pub struct SkippableStage<CB, ST> {
+    closure: CB,
+    stage: ST,
+    skipped: u64,
+}
+
+impl<CB, E, EM, S, ST, Z> Stage<E, EM, S, Z> for SkippableStage<CB, ST>
+where
+    CB: FnMut(&mut S, usize) -> Result<bool, Error>,
+    ST: Stage<E, EM, S, Z>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        if (self.closure)(state, corpus_idx)? {
+            self.stage
+                .perform(fuzzer, executor, state, manager, corpus_idx)
+        } else {
+            self.skipped += 1;
+            Ok(())
+        }
+    }
+}
+
+impl<CB, ST> SkippableStage<CB, ST> {
+    /// Create a new [`SkippableStage`], performing `stage` when `closure` returns `true`
+    #[must_use]
+    pub fn new(closure: CB, stage: ST) -> Self {
+        Self {
+            closure,
+            stage,
+            skipped: 0,
+        }
+    }
+
+    /// The inner stage
+    pub fn stage(&self) -> &ST {
+        &self.stage
+    }
+
+    /// The inner stage (mutable)
+    pub fn stage_mut(&mut self) -> &mut ST {
+        &mut self.stage
+    }
+
+    /// The number of times the inner stage got skipped
+    #[must_use]
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        bolts::tuples::tuple_list,
+        stages::{IfStage, SkippableStage, Stage},
+        Error,
+    };
+
+    /// Records the corpus indexes it was performed for
+    #[derive(Debug, Default)]
+    struct RecordStage {
+        performed: Vec<usize>,
+    }
+
+    impl<S> Stage<(), (), S, ()> for RecordStage {
+        fn perform(
+            &mut self,
+            _fuzzer: &mut (),
+            _executor: &mut (),
+            _state: &mut S,
+            _manager: &mut (),
+            corpus_idx: usize,
+        ) -> Result<(), Error> {
+            self.performed.push(corpus_idx);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_if_stage() {
+        let mut if_stage = IfStage::new(
+            |_state: &mut (), corpus_idx| Ok(corpus_idx % 2 == 0),
+            tuple_list!(RecordStage::default(), RecordStage::default()),
+        );
+        for corpus_idx in 0..5 {
+            if_stage
+                .perform(&mut (), &mut (), &mut (), &mut (), corpus_idx)
+                .unwrap();
+        }
+        assert_eq!(if_stage.if_stages().0.performed, [0, 2, 4]);
+        assert_eq!(if_stage.if_stages().1 .0.performed, [0, 2, 4]);
+    }
+
+    #[test]
+    fn test_skippable_stage() {
+        // Only every third entry, counted in the state
+        let mut skippable_stage = SkippableStage::new(
+            |runs: &mut u32, _corpus_idx| {
+                *runs += 1;
+                Ok(*runs % 3 == 1)
+            },
+            RecordStage::default(),
+        );
+        let mut runs = 0_u32;
+        for corpus_idx in 0..7 {
+            skippable_stage
+                .perform(&mut (), &mut (), &mut runs, &mut (), corpus_idx)
+                .unwrap();
+        }
+        assert_eq!(skippable_stage.stage().performed, [0, 3, 6]);
+        assert_eq!(skippable_stage.skipped(), 4);
+
+        // The errors of the closure stop the stage
+        let mut failing_stage = SkippableStage::new(
+            |_state: &mut (), _corpus_idx| Err(Error::IllegalState("no".into())),
+            RecordStage::default(),
+        );
+        assert!(failing_stage
+            .perform(&mut (), &mut (), &mut (), &mut (), 0)
+            .is_err());
+        assert!(failing_stage.stage().performed.is_empty());
+    }
+}
@@ -11,10 +11,13 @@ pub use mutational::{MutationalStage, StdMutationalStage};
 pub mod push;
 
 pub mod tracing;
-pub use tracing::{ShadowTracingStage, TracingStage};
+pub use tracing::{
+    ObserversToMetadata, SerializeObserver, SerializedObserversMetadata, ShadowTracingStage,
+    TracingStage,
+};
 
 pub mod calibrate;
-pub use calibrate::{CalibrationStage, PowerScheduleMetadata};
+pub use calibrate::{CalibrationMetadata, CalibrationStage, PowerScheduleMetadata};
 
 pub mod power;
 pub use power::PowerMutationalStage;
@@ -22,9 +25,33 @@ pub use power::PowerMutationalStage;
 pub mod generalization;
 pub use generalization::GeneralizationStage;
 
+pub mod trim;
+pub use trim::TrimStage;
+
+pub mod colorization;
+pub use colorization::{ColorizationStage, TaintMetadata};
+
+pub mod string_categories;
+pub use string_categories::{StringCategoriesMetadata, StringCategoriesStage};
+
 pub mod owned;
 pub use owned::StagesOwnedList;
 
+pub mod logics;
+pub use logics::{IfStage, SkippableStage};
+
+pub mod coverage_summary;
+pub use coverage_summary::CoverageSummaryStage;
+
+pub mod corpus_minimization;
+pub use corpus_minimization::CorpusMinimizationStage;
+
+pub mod stats;
+pub use stats::StatsStage;
+
+pub mod hooks;
+pub use hooks::{ClosureStageHook, HookedStages, StageHook, StageHooksTuple};
+
 #[cfg(feature = "std")]
 pub mod concolic;
 #[cfg(feature = "std")]
@@ -32,11 +59,21 @@ pub use concolic::ConcolicTracingStage;
 #[cfg(feature = "std")]
 pub use concolic::SimpleConcolicMutationalStage;
 
+#[cfg(feature = "std")]
+pub mod dump;
+#[cfg(feature = "std")]
+pub use dump::DumpToDiskStage;
+
 #[cfg(feature = "std")]
 pub mod sync;
 #[cfg(feature = "std")]
 pub use sync::*;
 
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub mod energy;
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub use energy::EnergyStage;
+
 use crate::{
     corpus::CorpusScheduler,
     events::{EventFirer, EventRestarter, HasEventManagerId, ProgressReporter},
@@ -77,6 +114,25 @@ pub trait StagesTuple<E, EM, S, Z> {
         manager: &mut EM,
         corpus_idx: usize,
     ) -> Result<(), Error>;
+
+    /// Performs all `Stages` in this tuple, running the `hooks` before and after each of them.
+    /// By default, the hooks run once around all the `Stages`.
+    fn perform_all_hooked<H>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+        hooks: &mut H,
+    ) -> Result<(), Error>
+    where
+        H: StageHooksTuple<E, EM, S, Z>,
+    {
+        hooks.pre_stage_all(fuzzer, executor, state, manager, corpus_idx)?;
+        self.perform_all(fuzzer, executor, state, manager, corpus_idx)?;
+        hooks.post_stage_all(fuzzer, executor, state, manager, corpus_idx)
+    }
 }
 
 impl<E, EM, S, Z> StagesTuple<E, EM, S, Z> for () {
@@ -90,6 +146,21 @@ impl<E, EM, S, Z> StagesTuple<E, EM, S, Z> for () {
     ) -> Result<(), Error> {
         Ok(())
     }
+
+    fn perform_all_hooked<H>(
+        &mut self,
+        _: &mut Z,
+        _: &mut E,
+        _: &mut S,
+        _: &mut EM,
+        _: usize,
+        _: &mut H,
+    ) -> Result<(), Error>
+    where
+        H: StageHooksTuple<E, EM, S, Z>,
+    {
+        Ok(())
+    }
 }
 
 impl<Head, Tail, E, EM, S, Z> StagesTuple<E, EM, S, Z> for (Head, Tail)
@@ -106,13 +177,49 @@ where
         corpus_idx: usize,
     ) -> Result<(), Error> {
         // Perform the current stage
-        self.0
-            .perform(fuzzer, executor, state, manager, corpus_idx)?;
+        {
+            #[cfg(feature = "tracing")]
+            let _span =
+                ::tracing::debug_span!("stage", name = core::any::type_name::<Head>()).entered();
+            self.0
+                .perform(fuzzer, executor, state, manager, corpus_idx)?;
+        }
 
         // Execute the remaining stages
         self.1
             .perform_all(fuzzer, executor, state, manager, corpus_idx)
     }
+
+    fn perform_all_hooked<H>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+        hooks: &mut H,
+    ) -> Result<(), Error>
+    where
+        H: StageHooksTuple<E, EM, S, Z>,
+    {
+        {
+            #[cfg(feature = "tracing")]
+            let _span =
+                ::tracing::debug_span!("stage", name = core::any::type_name::<Head>()).entered();
+            hooks::perform_hooked(
+                &mut self.0,
+                fuzzer,
+                executor,
+                state,
+                manager,
+                corpus_idx,
+                hooks,
+            )?;
+        }
+
+        self.1
+            .perform_all_hooked(fuzzer, executor, state, manager, corpus_idx, hooks)
+    }
 }
 
 /// A [`Stage`] that will call a closure
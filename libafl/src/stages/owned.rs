@@ -4,7 +4,7 @@ use alloc::{boxed::Box, vec::Vec};
 
 use crate::{
     bolts::anymap::AsAny,
-    stages::{Stage, StagesTuple},
+    stages::{hooks::perform_hooked, Stage, StageHooksTuple, StagesTuple},
     Error,
 };
 
@@ -33,6 +33,32 @@ impl<E, EM, S, Z> StagesTuple<E, EM, S, Z> for StagesOwnedList<E, EM, S, Z> {
         }
         Ok(())
     }
+
+    fn perform_all_hooked<H>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+        hooks: &mut H,
+    ) -> Result<(), Error>
+    where
+        H: StageHooksTuple<E, EM, S, Z>,
+    {
+        for s in &mut self.list {
+            perform_hooked(
+                s.as_mut(),
+                fuzzer,
+                executor,
+                state,
+                manager,
+                corpus_idx,
+                hooks,
+            )?;
+        }
+        Ok(())
+    }
 }
 
 impl<E, EM, S, Z> StagesOwnedList<E, EM, S, Z> {
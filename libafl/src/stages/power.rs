@@ -15,15 +15,22 @@ use crate::{
     Error,
 };
 
-/// The power schedule to use
-#[allow(missing_docs)]
+/// The power schedule to use, from `AFLFast`, as adapted by AFL++.
+/// All of them weigh the energy of a testcase by its execution time, its coverage, its depth
+/// and its handicap, relatively to the averages of the calibration.
 #[derive(Clone, Debug, PartialEq)]
 pub enum PowerSchedule {
+    /// Only the weights above, no factor for the fuzzing frequency of the path
     EXPLORE,
+    /// The energy decreases with the logarithm of the number of times the path was fuzzed
     FAST,
+    /// Cut-off exponential: skips the non-favored testcases of paths fuzzed more than average
     COE,
+    /// The energy grows linearly with the times the testcase was fuzzed, over the path frequency
     LIN,
+    /// The energy grows quadratically with the times the testcase was fuzzed, over the path frequency
     QUAD,
+    /// The maximum factor for all the testcases, as AFL without power schedules
     EXPLOIT,
 }
 
@@ -31,7 +38,10 @@ const POWER_BETA: f64 = 1.0;
 const MAX_FACTOR: f64 = POWER_BETA * 32.0;
 const HAVOC_MAX_MULT: f64 = 64.0;
 
-/// The mutational stage using power schedules
+/// The mutational stage using power schedules, running as many mutations of a testcase as
+/// its energy, see [`PowerSchedule`].
+/// It needs the [`PowerScheduleMetadata`] of a [`crate::stages::CalibrationStage`] in the state,
+/// and the testcases scheduled by a [`crate::corpus::PowerQueueCorpusScheduler`].
 #[derive(Clone, Debug)]
 pub struct PowerMutationalStage<E, EM, I, M, O, OT, S, Z>
 where
@@ -375,8 +385,8 @@ where
             perf_score *= factor / POWER_BETA;
         }
 
-        // Lower bound if the strat is not COE.
-        if self.strat == PowerSchedule::COE && perf_score < 1.0 {
+        // Lower bound if the strat is not COE, which skips the testcases it gives no energy
+        if self.strat != PowerSchedule::COE && perf_score < 1.0 {
             perf_score = 1.0;
         }
 
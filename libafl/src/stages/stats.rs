@@ -0,0 +1,206 @@
+//! The [`StatsStage`] periodically reports user-defined metrics, computed from the state, to the
+//! monitors, as [`Event::UpdateUserStats`].
+
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, marker::PhantomData, time::Duration};
+
+use crate::{
+    bolts::current_time,
+    events::{Event, EventFirer},
+    inputs::Input,
+    monitors::UserStats,
+    stages::Stage,
+    Error,
+};
+
+/// The default interval between two reports of the user stats
+pub const STATS_STAGE_DEFAULT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// A closure computing a user stat from the state, `None` to skip it
+type StatClosure<S> = Box<dyn FnMut(&S) -> Option<UserStats>>;
+
+/// A stage periodically computing user stats with closures over the state, e.g. the size of the
+/// dictionary, and firing them as [`Event::UpdateUserStats`], shown by any monitor.
+pub struct StatsStage<I, S> {
+    stats: Vec<(String, StatClosure<S>)>,
+    interval: Duration,
+    last_time: Duration,
+    phantom: PhantomData<I>,
+}
+
+impl<I, S> fmt::Debug for StatsStage<I, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StatsStage")
+            .field(
+                "stats",
+                &self.stats.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            )
+            .field("interval", &self.interval)
+            .field("last_time", &self.last_time)
+            .finish()
+    }
+}
+
+impl<I, S> StatsStage<I, S>
+where
+    I: Input,
+{
+    /// Creates a new [`StatsStage`], without stats
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            stats: vec![],
+            interval: STATS_STAGE_DEFAULT_INTERVAL,
+            last_time: Duration::from_secs(0),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Adds the stat `name`, computed by `closure`, skipped when it returns `None`
+    #[must_use]
+    pub fn with_stat<CB>(mut self, name: &str, closure: CB) -> Self
+    where
+        CB: FnMut(&S) -> Option<UserStats> + 'static,
+    {
+        self.stats.push((name.to_string(), Box::new(closure)));
+        self
+    }
+
+    /// Sets the interval between two reports
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+impl<I, S> Default for StatsStage<I, S>
+where
+    I: Input,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E, EM, I, S, Z> Stage<E, EM, S, Z> for StatsStage<I, S>
+where
+    EM: EventFirer<I>,
+    I: Input,
+{
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let now = current_time();
+        if now.checked_sub(self.last_time).unwrap_or_default() < self.interval {
+            return Ok(());
+        }
+        self.last_time = now;
+
+        for (name, closure) in &mut self.stats {
+            if let Some(value) = closure(state) {
+                manager.fire(
+                    state,
+                    Event::UpdateUserStats {
+                        name: name.clone(),
+                        value,
+                        phantom: PhantomData,
+                    },
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{
+        string::{String, ToString},
+        vec::Vec,
+    };
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        events::{Event, EventFirer},
+        inputs::{BytesInput, HasBytesVec},
+        monitors::UserStats,
+        stages::{Stage, StatsStage},
+        state::{HasCorpus, StdState},
+        Error,
+    };
+
+    type TestState =
+        StdState<InMemoryCorpus<BytesInput>, (), BytesInput, StdRand, InMemoryCorpus<BytesInput>>;
+
+    /// Keeps the user stats fired, as displayed
+    #[derive(Debug, Default)]
+    struct StatsCollector {
+        stats: Vec<(String, String)>,
+    }
+
+    impl EventFirer<BytesInput> for StatsCollector {
+        fn fire<S>(&mut self, _state: &mut S, event: Event<BytesInput>) -> Result<(), Error> {
+            if let Event::UpdateUserStats { name, value, .. } = event {
+                self.stats.push((name, value.to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_stats_stage() {
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            tuple_list!(),
+        );
+        for len in [4, 12] {
+            state.corpus_mut().add(Testcase::new(vec![0; len])).unwrap();
+        }
+        let mut mgr = StatsCollector::default();
+
+        let mut stats_stage = StatsStage::<BytesInput, TestState>::new()
+            .with_stat("corpus", |state: &TestState| {
+                Some(UserStats::Number(state.corpus().count() as u64))
+            })
+            .with_stat("small", |state: &TestState| {
+                let small = (0..state.corpus().count())
+                    .filter(|idx| {
+                        let testcase = state.corpus().get(*idx).unwrap().borrow();
+                        testcase.input().as_ref().unwrap().bytes().len() < 8
+                    })
+                    .count();
+                Some(UserStats::Ratio(
+                    small as u64,
+                    state.corpus().count() as u64,
+                ))
+            })
+            .with_stat("skipped", |_state: &TestState| None);
+        stats_stage
+            .perform(&mut (), &mut (), &mut state, &mut mgr, 0)
+            .unwrap();
+        // Not again before the interval elapsed
+        stats_stage
+            .perform(&mut (), &mut (), &mut state, &mut mgr, 0)
+            .unwrap();
+        assert_eq!(
+            mgr.stats,
+            [
+                ("corpus".to_string(), "2".to_string()),
+                ("small".to_string(), "1/2 (50.0%)".to_string()),
+            ]
+        );
+    }
+}
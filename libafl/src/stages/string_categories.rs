@@ -0,0 +1,272 @@
+//! The [`StringCategoriesStage`] tells the strings of the corpus entries from their binary
+//! parts, for the mutators to keep the text valid, e.g. for JSON, JS or SQL targets, such as the
+//! [`crate::mutators::StringCharReplaceMutator`].
+
+use alloc::vec::Vec;
+use core::{marker::PhantomData, ops::Range};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Corpus,
+    inputs::{HasBytesVec, Input},
+    stages::Stage,
+    state::{HasCorpus, HasMetadata},
+    Error,
+};
+
+/// The minimum number of characters of a string, shorter ones are considered binary
+pub const DEFAULT_MIN_STRING_CHARS: usize = 4;
+
+/// The category of a range of bytes
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StringCategory {
+    /// Printable ASCII characters and whitespaces
+    Ascii,
+    /// Printable UTF-8 characters and whitespaces, some of them not ASCII
+    Utf8,
+    /// Anything else
+    Binary,
+}
+
+impl StringCategory {
+    /// The range is text, ASCII or UTF-8
+    #[must_use]
+    pub fn is_text(self) -> bool {
+        self != Self::Binary
+    }
+}
+
+/// A testcase metadata splitting the input of the testcase into ranges of strings and binary.
+/// It is kept in the testcase, and copied to the state for the testcase being fuzzed.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct StringCategoriesMetadata {
+    /// The consecutive ranges covering the whole input, with their category
+    pub ranges: Vec<(Range<usize>, StringCategory)>,
+}
+
+crate::impl_serdeany!(StringCategoriesMetadata);
+
+/// Decodes the printable or whitespace character at the start of `bytes`, returning its length
+/// in bytes and whether it is ASCII
+fn text_char(bytes: &[u8]) -> Option<(usize, bool)> {
+    let first = *bytes.first()?;
+    if first.is_ascii() {
+        return if first.is_ascii_graphic() || first.is_ascii_whitespace() {
+            Some((1, true))
+        } else {
+            None
+        };
+    }
+    let len = match first {
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => return None,
+    };
+    let c = core::str::from_utf8(bytes.get(..len)?)
+        .ok()?
+        .chars()
+        .next()?;
+    if c.is_control() {
+        None
+    } else {
+        Some((len, false))
+    }
+}
+
+impl StringCategoriesMetadata {
+    /// Splits `bytes` into strings of at least `min_chars` characters, and binary ranges
+    #[must_use]
+    pub fn new(bytes: &[u8], min_chars: usize) -> Self {
+        let mut ranges: Vec<(Range<usize>, StringCategory)> = vec![];
+        let mut push = |range: Range<usize>, category| match ranges.last_mut() {
+            Some((last, last_category)) if *last_category == category => last.end = range.end,
+            _ => ranges.push((range, category)),
+        };
+
+        let mut pos = 0;
+        while pos < bytes.len() {
+            // The longest string starting at `pos`
+            let (mut end, mut chars, mut ascii) = (pos, 0, true);
+            while let Some((len, is_ascii)) = text_char(&bytes[end..]) {
+                end += len;
+                chars += 1;
+                ascii &= is_ascii;
+            }
+            if chars >= min_chars {
+                let category = if ascii {
+                    StringCategory::Ascii
+                } else {
+                    StringCategory::Utf8
+                };
+                push(pos..end, category);
+                pos = end;
+            } else {
+                let end = end.max(pos + 1);
+                push(pos..end, StringCategory::Binary);
+                pos = end;
+            }
+        }
+        Self { ranges }
+    }
+
+    /// The length of the input the ranges were computed for
+    #[must_use]
+    pub fn input_len(&self) -> usize {
+        self.ranges.last().map_or(0, |(range, _)| range.end)
+    }
+
+    /// The category of the byte at `offset`, if in the input
+    #[must_use]
+    pub fn category_at(&self, offset: usize) -> Option<StringCategory> {
+        self.ranges
+            .iter()
+            .find(|(range, _)| range.contains(&offset))
+            .map(|(_, category)| *category)
+    }
+
+    /// The ranges of text, ASCII or UTF-8
+    pub fn text_ranges(&self) -> impl Iterator<Item = &Range<usize>> {
+        self.ranges
+            .iter()
+            .filter(|(_, category)| category.is_text())
+            .map(|(range, _)| range)
+    }
+}
+
+/// A stage computing the [`StringCategoriesMetadata`] of each corpus entry, once, and putting
+/// the one of the testcase being fuzzed in the state, for the mutators
+#[derive(Clone, Debug)]
+pub struct StringCategoriesStage<I, S> {
+    min_chars: usize,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<E, EM, I, S, Z> Stage<E, EM, S, Z> for StringCategoriesStage<I, S>
+where
+    I: Input + HasBytesVec,
+    S: HasCorpus<I> + HasMetadata,
+{
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        _manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let metadata = {
+            let mut testcase = state.corpus().get(corpus_idx)?.borrow_mut();
+            if let Some(metadata) = testcase.metadata().get::<StringCategoriesMetadata>() {
+                metadata.clone()
+            } else {
+                let metadata =
+                    StringCategoriesMetadata::new(testcase.load_input()?.bytes(), self.min_chars);
+                testcase.add_metadata(metadata.clone());
+                metadata
+            }
+        };
+        // Replaces the metadata of the previous testcase
+        state.add_metadata(metadata);
+        Ok(())
+    }
+}
+
+impl<I, S> StringCategoriesStage<I, S>
+where
+    I: Input + HasBytesVec,
+    S: HasCorpus<I> + HasMetadata,
+{
+    /// Creates a new [`StringCategoriesStage`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            min_chars: DEFAULT_MIN_STRING_CHARS,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets the minimum number of characters of a string
+    #[must_use]
+    pub fn with_min_chars(mut self, min_chars: usize) -> Self {
+        self.min_chars = min_chars;
+        self
+    }
+}
+
+impl<I, S> Default for StringCategoriesStage<I, S>
+where
+    I: Input + HasBytesVec,
+    S: HasCorpus<I> + HasMetadata,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StringCategoriesMetadata, StringCategory};
+
+    #[test]
+    fn test_string_categories() {
+        let meta = StringCategoriesMetadata::new(b"\x00\x01{\"key\": 1}\xff\xfeab", 4);
+        assert_eq!(
+            meta.ranges,
+            [
+                (0..2, StringCategory::Binary),
+                (2..12, StringCategory::Ascii),
+                (12..16, StringCategory::Binary),
+            ]
+        );
+        assert_eq!(meta.input_len(), 16);
+        assert_eq!(meta.category_at(2), Some(StringCategory::Ascii));
+        assert_eq!(meta.category_at(15), Some(StringCategory::Binary));
+        assert_eq!(meta.category_at(16), None);
+        assert_eq!(meta.text_ranges().collect::<Vec<_>>(), [&(2..12)]);
+    }
+
+    #[test]
+    fn test_string_categories_utf8() {
+        let meta = StringCategoriesMetadata::new("\x00caf\u{e9} na\u{ef}ve\x00".as_bytes(), 4);
+        assert_eq!(
+            meta.ranges,
+            [
+                (0..1, StringCategory::Binary),
+                (1..13, StringCategory::Utf8),
+                (13..14, StringCategory::Binary),
+            ]
+        );
+
+        // A truncated multi-byte character, or a control character, ends the string
+        let meta = StringCategoriesMetadata::new(b"text\xc3", 4);
+        assert_eq!(
+            meta.ranges,
+            [
+                (0..4, StringCategory::Ascii),
+                (4..5, StringCategory::Binary)
+            ]
+        );
+        let meta = StringCategoriesMetadata::new("abcd\u{85}".as_bytes(), 4);
+        assert_eq!(
+            meta.ranges,
+            [
+                (0..4, StringCategory::Ascii),
+                (4..6, StringCategory::Binary)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_string_categories_min_chars() {
+        // Too short to be a string
+        let meta = StringCategoriesMetadata::new(b"\x00abc\x00", 4);
+        assert_eq!(meta.ranges, [(0..5, StringCategory::Binary)]);
+        let meta = StringCategoriesMetadata::new(b"\x00abc\x00", 3);
+        assert_eq!(meta.category_at(1), Some(StringCategory::Ascii));
+        assert_eq!(
+            StringCategoriesMetadata::new(b"", 4),
+            StringCategoriesMetadata::default()
+        );
+    }
+}
@@ -1,7 +1,9 @@
-//| The [`MutationalStage`] is the default stage used during fuzzing.
-//! For the current input, it will perform a range of random mutations, and then run them in the executor.
+//! The [`SyncFromDiskStage`] imports the testcases other fuzzers, such as AFL++, write to their
+//! output directories, to run mixed campaigns.
 
-use core::marker::PhantomData;
+use alloc::vec::Vec;
+use core::{marker::PhantomData, time::Duration};
+use hashbrown::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 use std::{
     fs,
@@ -10,6 +12,7 @@ use std::{
 };
 
 use crate::{
+    bolts::current_time,
     fuzzer::Evaluator,
     inputs::Input,
     stages::Stage,
@@ -18,10 +21,13 @@ use crate::{
 };
 
 /// Metadata used to store information about disk sync time
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct SyncFromDiskMetadata {
-    /// The last time the sync was done
-    pub last_time: SystemTime,
+    /// The modification time of the newest files loaded, for each sync directory
+    pub last_times: HashMap<PathBuf, SystemTime>,
+    /// The files modified at the last time of each sync directory, already loaded, as other
+    /// files may still get written with the same modification time
+    pub last_files: HashMap<PathBuf, HashSet<PathBuf>>,
 }
 
 crate::impl_serdeany!(SyncFromDiskMetadata);
@@ -29,12 +35,41 @@ crate::impl_serdeany!(SyncFromDiskMetadata);
 impl SyncFromDiskMetadata {
     /// Create a new [`struct@SyncFromDiskMetadata`]
     #[must_use]
-    pub fn new(last_time: SystemTime) -> Self {
-        Self { last_time }
+    pub fn new() -> Self {
+        Self::default()
     }
 }
 
-/// A stage that loads testcases from disk to sync with other fuzzers such as AFL++
+/// The newest files found scanning a sync directory
+#[derive(Debug, Default)]
+struct NewestFiles {
+    time: Option<SystemTime>,
+    files: HashSet<PathBuf>,
+}
+
+impl NewestFiles {
+    fn add(&mut self, path: PathBuf, time: SystemTime) {
+        match self.time {
+            Some(newest) if time < newest => (),
+            Some(newest) if time == newest => {
+                self.files.insert(path);
+            }
+            _ => {
+                self.time = Some(time);
+                self.files.clear();
+                self.files.insert(path);
+            }
+        }
+    }
+}
+
+/// The default interval between two scans of the sync directories
+pub const SYNC_FROM_DISK_DEFAULT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A stage that loads testcases from disk to sync with other fuzzers such as AFL++.
+/// Every `interval`, it scans the sync directories recursively, for example the `queue/`
+/// directories of AFL++ instances, and evaluates the files modified since the last scan.
+/// The hidden files and directories, such as the `.state/` of AFL++, get skipped.
 #[derive(Debug)]
 pub struct SyncFromDiskStage<CB, E, EM, I, S, Z>
 where
@@ -43,8 +78,10 @@ where
     S: HasClientPerfMonitor + HasCorpus<I> + HasRand + HasMetadata,
     Z: Evaluator<E, EM, I, S>,
 {
-    sync_dir: PathBuf,
+    sync_dirs: Vec<PathBuf>,
     load_callback: CB,
+    interval: Duration,
+    last_scan: Option<Duration>,
     #[allow(clippy::type_complexity)]
     phantom: PhantomData<(E, EM, I, S, Z)>,
 }
@@ -65,25 +102,10 @@ where
         manager: &mut EM,
         _corpus_idx: usize,
     ) -> Result<(), Error> {
-        let last = state
-            .metadata()
-            .get::<SyncFromDiskMetadata>()
-            .map(|m| m.last_time);
-        let path = self.sync_dir.clone();
-        if let Some(max_time) =
-            self.load_from_directory(&path, &last, fuzzer, executor, state, manager)?
-        {
-            if last.is_none() {
-                state
-                    .metadata_mut()
-                    .insert(SyncFromDiskMetadata::new(max_time));
-            } else {
-                state
-                    .metadata_mut()
-                    .get_mut::<SyncFromDiskMetadata>()
-                    .unwrap()
-                    .last_time = max_time;
-            }
+        let now = current_time();
+        if !matches!(self.last_scan, Some(last_scan) if now < last_scan + self.interval) {
+            self.last_scan = Some(now);
+            self.sync(fuzzer, executor, state, manager)?;
         }
 
         #[cfg(feature = "introspection")]
@@ -100,28 +122,100 @@ where
     S: HasClientPerfMonitor + HasCorpus<I> + HasRand + HasMetadata,
     Z: Evaluator<E, EM, I, S>,
 {
-    /// Creates a new [`SyncFromDiskStage`]
+    /// Creates a new [`SyncFromDiskStage`] loading the files of the `sync_dirs` with `load_callback`
     #[must_use]
-    pub fn new(sync_dir: PathBuf, load_callback: CB) -> Self {
+    pub fn new(sync_dirs: Vec<PathBuf>, load_callback: CB) -> Self {
         Self {
-            sync_dir,
+            sync_dirs,
             load_callback,
+            interval: SYNC_FROM_DISK_DEFAULT_INTERVAL,
+            last_scan: None,
             phantom: PhantomData,
         }
     }
 
+    /// Sets the interval between two scans of the sync directories
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// The directories synced from
+    #[must_use]
+    pub fn sync_dirs(&self) -> &[PathBuf] {
+        &self.sync_dirs
+    }
+
+    /// Loads the files modified since the last sync from all the sync directories
+    fn sync(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        if state.metadata().get::<SyncFromDiskMetadata>().is_none() {
+            state.add_metadata(SyncFromDiskMetadata::new());
+        }
+        for path in self.sync_dirs.clone() {
+            // The directories of the other fuzzers may not be created yet
+            if !path.is_dir() {
+                continue;
+            }
+            let meta = state.metadata().get::<SyncFromDiskMetadata>().unwrap();
+            let last = meta.last_times.get(&path).copied();
+            let last_files = meta.last_files.get(&path).cloned().unwrap_or_default();
+
+            let mut newest = NewestFiles::default();
+            self.load_from_directory(
+                &path,
+                last,
+                &last_files,
+                &mut newest,
+                fuzzer,
+                executor,
+                state,
+                manager,
+            )?;
+
+            if let Some(time) = newest.time {
+                let meta = state
+                    .metadata_mut()
+                    .get_mut::<SyncFromDiskMetadata>()
+                    .unwrap();
+                if last == Some(time) {
+                    // Newer files with the same time as the last ones
+                    meta.last_files
+                        .entry(path)
+                        .or_default()
+                        .extend(newest.files);
+                } else {
+                    meta.last_times.insert(path.clone(), time);
+                    meta.last_files.insert(path, newest.files);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn load_from_directory(
         &mut self,
         in_dir: &Path,
-        last: &Option<SystemTime>,
+        last: Option<SystemTime>,
+        last_files: &HashSet<PathBuf>,
+        newest: &mut NewestFiles,
         fuzzer: &mut Z,
         executor: &mut E,
         state: &mut S,
         manager: &mut EM,
-    ) -> Result<Option<SystemTime>, Error> {
-        let mut max_time = None;
+    ) -> Result<(), Error> {
         for entry in fs::read_dir(in_dir)? {
             let entry = entry?;
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
             let path = entry.path();
             let attributes = fs::metadata(&path);
 
@@ -133,25 +227,28 @@ where
 
             if attr.is_file() && attr.len() > 0 {
                 if let Ok(time) = attr.modified() {
-                    if let Some(l) = last {
-                        if time.duration_since(*l).is_err() {
-                            continue;
-                        }
+                    // The files of the last sync are not new anymore, but the files written at
+                    // the same time as the last ones may be
+                    let loaded = match last {
+                        Some(last) if time < last => true,
+                        Some(last) if time == last => last_files.contains(&path),
+                        _ => false,
+                    };
+                    if loaded {
+                        continue;
                     }
-                    max_time = Some(max_time.map_or(time, |t: SystemTime| t.max(time)));
                     let input = (self.load_callback)(fuzzer, state, &path)?;
                     drop(fuzzer.evaluate_input(state, executor, manager, input)?);
+                    newest.add(path, time);
                 }
             } else if attr.is_dir() {
-                let dir_max_time =
-                    self.load_from_directory(&path, last, fuzzer, executor, state, manager)?;
-                if let Some(time) = dir_max_time {
-                    max_time = Some(max_time.map_or(time, |t: SystemTime| t.max(time)));
-                }
+                self.load_from_directory(
+                    &path, last, last_files, newest, fuzzer, executor, state, manager,
+                )?;
             }
         }
 
-        Ok(max_time)
+        Ok(())
     }
 }
 
@@ -164,14 +261,126 @@ where
 {
     /// Creates a new [`SyncFromDiskStage`] invoking `Input::from_file` to load inputs
     #[must_use]
-    pub fn with_from_file(sync_dir: PathBuf) -> Self {
+    pub fn with_from_file(sync_dirs: Vec<PathBuf>) -> Self {
         fn load_callback<Z, S, I: Input>(_: &mut Z, _: &mut S, p: &Path) -> Result<I, Error> {
             I::from_file(p)
         }
-        Self {
-            sync_dir,
-            load_callback: load_callback::<_, _, I>,
-            phantom: PhantomData,
+        Self::new(sync_dirs, load_callback::<_, _, I>)
+    }
+
+    /// Creates a new [`SyncFromDiskStage`] importing the `queue/` directories of all the AFL++
+    /// instances of the output directory `afl_out_dir`, as `-M`/`-S` instances do, loading the
+    /// inputs with `Input::from_file`.
+    /// The instances started later get synced from after the next restart.
+    pub fn with_afl_out_dir<P>(afl_out_dir: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut sync_dirs = vec![];
+        for entry in fs::read_dir(afl_out_dir)? {
+            let queue = entry?.path().join("queue");
+            if queue.is_dir() {
+                sync_dirs.push(queue);
+            }
         }
+        Ok(Self::with_from_file(sync_dirs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+    use std::{fs, path::Path, process, time::SystemTime};
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::InMemoryCorpus,
+        fuzzer::{Evaluator, ExecuteInputResult},
+        inputs::{BytesInput, Input},
+        stages::sync::SyncFromDiskStage,
+        Error,
+    };
+
+    /// Only collects the inputs to evaluate or to add
+    #[derive(Debug, Default)]
+    struct Collector {
+        inputs: Vec<BytesInput>,
+    }
+
+    impl<S> Evaluator<(), (), BytesInput, S> for Collector {
+        fn evaluate_input_events(
+            &mut self,
+            _state: &mut S,
+            _executor: &mut (),
+            _manager: &mut (),
+            input: BytesInput,
+            _send_events: bool,
+        ) -> Result<(ExecuteInputResult, Option<usize>), Error> {
+            self.inputs.push(input);
+            Ok((ExecuteInputResult::None, None))
+        }
+
+        fn add_input(
+            &mut self,
+            _state: &mut S,
+            _executor: &mut (),
+            _manager: &mut (),
+            input: BytesInput,
+        ) -> Result<usize, Error> {
+            self.inputs.push(input);
+            Ok(self.inputs.len() - 1)
+        }
+    }
+
+    fn write_file(path: &Path, content: &[u8], time: SystemTime) {
+        fs::write(path, content).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(time)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sync_from_disk() {
+        let root = std::env::temp_dir().join(format!("libafl_test_sync_{}", process::id()));
+        let (dir_a, dir_b) = (root.join("a"), root.join("b"));
+        fs::create_dir_all(&dir_a).unwrap();
+        fs::create_dir_all(&dir_b).unwrap();
+        let time = SystemTime::now() - Duration::from_secs(3600);
+
+        let mut state = crate::state::StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            tuple_list!(),
+        );
+        let mut fuzzer = Collector::default();
+        let mut from_disk = SyncFromDiskStage::new(
+            vec![dir_a.clone(), dir_b.clone()],
+            |_: &mut Collector, _: &mut _, path: &Path| BytesInput::from_file(path),
+        );
+        let mut sync = |fuzzer: &mut Collector| {
+            fuzzer.inputs.clear();
+            from_disk
+                .sync(fuzzer, &mut (), &mut state, &mut ())
+                .unwrap();
+            fuzzer.inputs.len()
+        };
+
+        write_file(&dir_a.join("1"), b"a1", time);
+        write_file(&dir_b.join("1"), b"b1", time - Duration::from_secs(100));
+        assert_eq!(sync(&mut fuzzer), 2);
+        assert_eq!(sync(&mut fuzzer), 0);
+
+        // Written with the same time as the last file loaded
+        write_file(&dir_a.join("2"), b"a2", time);
+        // Older than the last file of the other directory
+        write_file(&dir_b.join("2"), b"b2", time - Duration::from_secs(50));
+        assert_eq!(sync(&mut fuzzer), 2);
+        assert_eq!(sync(&mut fuzzer), 0);
+
+        fs::remove_dir_all(&root).unwrap();
     }
 }
@@ -1,42 +1,161 @@
 //! The tracing stage can trace the target and enrich a testcase with metadata, for example for `CmpLog`.
 
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
 use core::{fmt::Debug, marker::PhantomData};
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    corpus::Corpus,
+    bolts::tuples::{MatchName, Named},
+    corpus::{Corpus, Testcase},
     executors::{Executor, HasObservers, ShadowExecutor},
     inputs::Input,
     mark_feature_time,
     observers::ObserversTuple,
     stages::Stage,
     start_timer,
-    state::{HasClientPerfMonitor, HasCorpus, HasExecutions},
+    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata},
     Error,
 };
 
 #[cfg(feature = "introspection")]
 use crate::monitors::PerfFeature;
 
-/// A stage that runs a tracer executor
+/// Turns the observers of the tracer executor, after tracing a testcase, into metadata of the
+/// testcase, for example a call trace.
+/// Tuples of [`ObserversToMetadata`] run them all, `()` adds nothing.
+pub trait ObserversToMetadata<I, OT>
+where
+    I: Input,
+{
+    /// Adds the metadata from the `observers` to the traced `testcase`
+    fn to_metadata(&mut self, observers: &OT, testcase: &mut Testcase<I>) -> Result<(), Error>;
+}
+
+impl<I, OT> ObserversToMetadata<I, OT> for ()
+where
+    I: Input,
+{
+    fn to_metadata(&mut self, _observers: &OT, _testcase: &mut Testcase<I>) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<Head, Tail, I, OT> ObserversToMetadata<I, OT> for (Head, Tail)
+where
+    Head: ObserversToMetadata<I, OT>,
+    I: Input,
+    Tail: ObserversToMetadata<I, OT>,
+{
+    fn to_metadata(&mut self, observers: &OT, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        self.0.to_metadata(observers, testcase)?;
+        self.1.to_metadata(observers, testcase)
+    }
+}
+
+/// A testcase metadata holding the serialized state of observers of the tracer executor, by name,
+/// see [`SerializeObserver`]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SerializedObserversMetadata {
+    /// The observers serialized with `postcard`, by name
+    pub map: HashMap<String, Vec<u8>>,
+}
+
+crate::impl_serdeany!(SerializedObserversMetadata);
+
+impl SerializedObserversMetadata {
+    /// Deserializes the observer named `name`
+    pub fn get<O>(&self, name: &str) -> Result<Option<O>, Error>
+    where
+        O: for<'de> Deserialize<'de>,
+    {
+        self.map
+            .get(name)
+            .map(|bytes| postcard::from_bytes(bytes))
+            .transpose()
+            .map_err(Error::from)
+    }
+}
+
+/// An [`ObserversToMetadata`] copying the serialized state of the observer named `name` into the
+/// [`SerializedObserversMetadata`] of the testcase
+#[derive(Clone, Debug)]
+pub struct SerializeObserver<O> {
+    name: String,
+    phantom: PhantomData<O>,
+}
+
+impl<O> SerializeObserver<O>
+where
+    O: Named,
+{
+    /// Creates a new [`SerializeObserver`], copying the given observer
+    #[must_use]
+    pub fn new(observer: &O) -> Self {
+        Self::from_name(observer.name())
+    }
+
+    /// Creates a new [`SerializeObserver`], copying the observer named `name`
+    #[must_use]
+    pub fn from_name(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<I, O, OT> ObserversToMetadata<I, OT> for SerializeObserver<O>
+where
+    I: Input,
+    O: Named + Serialize + 'static,
+    OT: MatchName,
+{
+    fn to_metadata(&mut self, observers: &OT, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        let observer = observers
+            .match_name::<O>(&self.name)
+            .ok_or_else(|| Error::KeyNotFound(format!("Observer {} not found", self.name)))?;
+        let bytes = postcard::to_allocvec(observer)?;
+        if !testcase.has_metadata::<SerializedObserversMetadata>() {
+            testcase.add_metadata(SerializedObserversMetadata::default());
+        }
+        testcase
+            .metadata_mut()
+            .get_mut::<SerializedObserversMetadata>()
+            .unwrap()
+            .map
+            .insert(self.name.clone(), bytes);
+        Ok(())
+    }
+}
+
+/// A stage that runs a tracer executor, and turns its observers into metadata of the testcase
+/// with an [`ObserversToMetadata`], if any
 #[derive(Clone, Debug)]
-pub struct TracingStage<EM, I, OT, S, TE, Z>
+pub struct TracingStage<EM, I, OT, S, TE, Z, OM = ()>
 where
     I: Input,
     TE: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
     OT: ObserversTuple<I, S>,
     S: HasClientPerfMonitor + HasExecutions + HasCorpus<I>,
+    OM: ObserversToMetadata<I, OT>,
 {
     tracer_executor: TE,
+    to_metadata: OM,
     #[allow(clippy::type_complexity)]
     phantom: PhantomData<(EM, I, OT, S, TE, Z)>,
 }
 
-impl<E, EM, I, OT, S, TE, Z> Stage<E, EM, S, Z> for TracingStage<EM, I, OT, S, TE, Z>
+impl<E, EM, I, OM, OT, S, TE, Z> Stage<E, EM, S, Z> for TracingStage<EM, I, OT, S, TE, Z, OM>
 where
     I: Input,
     TE: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
     OT: ObserversTuple<I, S>,
     S: HasClientPerfMonitor + HasExecutions + HasCorpus<I>,
+    OM: ObserversToMetadata<I, OT>,
 {
     #[inline]
     fn perform(
@@ -76,6 +195,11 @@ where
             .post_exec_all(state, &input, &exit_kind)?;
         mark_feature_time!(state, PerfFeature::PostExecObservers);
 
+        self.to_metadata.to_metadata(
+            self.tracer_executor.observers(),
+            &mut state.corpus().get(corpus_idx)?.borrow_mut(),
+        )?;
+
         Ok(())
     }
 }
@@ -89,8 +213,24 @@ where
 {
     /// Creates a new default stage
     pub fn new(tracer_executor: TE) -> Self {
+        Self::with_metadata(tracer_executor, ())
+    }
+}
+
+impl<EM, I, OM, OT, S, TE, Z> TracingStage<EM, I, OT, S, TE, Z, OM>
+where
+    I: Input,
+    TE: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions + HasCorpus<I>,
+    OM: ObserversToMetadata<I, OT>,
+{
+    /// Creates a new stage, adding the metadata of `to_metadata` to the traced testcases,
+    /// e.g. `tuple_list!(SerializeObserver::new(&call_trace_observer))`
+    pub fn with_metadata(tracer_executor: TE, to_metadata: OM) -> Self {
         Self {
             tracer_executor,
+            to_metadata,
             phantom: PhantomData,
         }
     }
@@ -176,3 +316,89 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list, AsMutSlice, AsSlice},
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        executors::{Executor, ExitKind, HasObservers},
+        inputs::{BytesInput, HasBytesVec},
+        observers::StdMapObserver,
+        stages::{
+            tracing::{SerializeObserver, SerializedObserversMetadata},
+            Stage, TracingStage,
+        },
+        state::{HasCorpus, HasMetadata, StdState},
+        Error,
+    };
+
+    /// Copies the input into the map of its observer
+    #[derive(Debug)]
+    struct CopyExecutor {
+        observers: (StdMapObserver<'static, u8>, ()),
+    }
+
+    impl<EM, S, Z> Executor<EM, BytesInput, S, Z> for CopyExecutor {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            _state: &mut S,
+            _mgr: &mut EM,
+            input: &BytesInput,
+        ) -> Result<ExitKind, Error> {
+            let bytes = input.bytes();
+            self.observers.0.as_mut_slice()[..bytes.len()].copy_from_slice(bytes);
+            Ok(ExitKind::Ok)
+        }
+    }
+
+    impl<S> HasObservers<BytesInput, (StdMapObserver<'static, u8>, ()), S> for CopyExecutor {
+        fn observers(&self) -> &(StdMapObserver<'static, u8>, ()) {
+            &self.observers
+        }
+
+        fn observers_mut(&mut self) -> &mut (StdMapObserver<'static, u8>, ()) {
+            &mut self.observers
+        }
+    }
+
+    #[test]
+    fn test_serialized_observers_metadata() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        corpus.add(Testcase::new(vec![1, 2, 3])).unwrap();
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<BytesInput>::new(),
+            tuple_list!(),
+        );
+
+        let observer = StdMapObserver::new_owned("map", vec![0; 4]);
+        let to_metadata = tuple_list!(SerializeObserver::new(&observer));
+        let mut tracing = TracingStage::with_metadata(
+            CopyExecutor {
+                observers: tuple_list!(observer),
+            },
+            to_metadata,
+        );
+        tracing
+            .perform(&mut (), &mut (), &mut state, &mut (), 0)
+            .unwrap();
+
+        // The metadata survives a round-trip of the testcase, e.g. to another node
+        let testcase = state.corpus().get(0).unwrap().borrow().clone();
+        let serialized = postcard::to_allocvec(&testcase).unwrap();
+        let testcase: Testcase<BytesInput> = postcard::from_bytes(&serialized).unwrap();
+        let metadata = testcase
+            .metadata()
+            .get::<SerializedObserversMetadata>()
+            .unwrap();
+        let observer: StdMapObserver<u8> = metadata.get("map").unwrap().unwrap();
+        assert_eq!(observer.as_slice(), [1, 2, 3, 0]);
+        assert!(metadata
+            .get::<StdMapObserver<u8>>("missing")
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,334 @@
+//! The [`TrimStage`] minimizes the corpus entries while they keep reaching the coverage they
+//! were kept for, as AFL trims its queue entries, so that the mutations focus on the bytes
+//! that matter.
+
+use alloc::string::{String, ToString};
+use core::{
+    cmp::{max, min},
+    fmt::Debug,
+    marker::PhantomData,
+    time::Duration,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{current_time, AsSlice, HasLen},
+    corpus::{Corpus, CorpusScheduler},
+    executors::{Executor, ExitKind, HasObservers},
+    feedbacks::map::MapNoveltiesMetadata,
+    fuzzer::HasCorpusScheduler,
+    inputs::{HasBytesVec, Input},
+    mark_feature_time,
+    observers::{MapObserver, ObserversTuple},
+    stages::{generalization::run_and_check_novelties, Stage},
+    start_timer,
+    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata},
+    Error,
+};
+
+#[cfg(feature = "introspection")]
+use crate::monitors::PerfFeature;
+
+/// The first chunks removed are the size of the input, rounded up to a power of two, divided
+/// by this number
+pub const TRIM_START_STEPS: usize = 16;
+/// The last chunks removed are the size of the input, rounded up to a power of two, divided
+/// by this number
+pub const TRIM_END_STEPS: usize = 1024;
+/// The minimum size of the chunks removed
+pub const TRIM_MIN_BYTES: usize = 4;
+
+/// A testcase metadata marking it as trimmed, not to trim it again
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TrimmedMetadata {
+    /// The length of the input before the trimming
+    pub original_len: usize,
+}
+
+crate::impl_serdeany!(TrimmedMetadata);
+
+/// A stage trimming each corpus entry once, removing chunks of decreasing sizes as long as the
+/// novelties of the entry in the map observer are still reached, like `afl-tmin`.
+/// The map feedback must track the novelties, see [`MapNoveltiesMetadata`].
+/// A trimmed entry gets the execution time of its trimmed input, and is rated again by the
+/// scheduler, e.g. for the favored entries of a [`crate::corpus::MinimizerCorpusScheduler`].
+#[derive(Clone, Debug)]
+pub struct TrimStage<CS, EM, I, O, OT, S, Z>
+where
+    CS: CorpusScheduler<I, S>,
+    I: Input + HasBytesVec + HasLen,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions + HasMetadata + HasCorpus<I>,
+{
+    map_observer_name: String,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(CS, EM, I, O, OT, S, Z)>,
+}
+
+impl<CS, E, EM, I, O, OT, S, Z> Stage<E, EM, S, Z> for TrimStage<CS, EM, I, O, OT, S, Z>
+where
+    CS: CorpusScheduler<I, S>,
+    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input + HasBytesVec + HasLen,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions + HasMetadata + HasCorpus<I>,
+    Z: HasCorpusScheduler<CS, I, S>,
+{
+    #[inline]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let (original, novelties) = {
+            start_timer!(state);
+            state.corpus().get(corpus_idx)?.borrow_mut().load_input()?;
+            mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
+            let entry = state.corpus().get(corpus_idx)?.borrow();
+            if entry.has_metadata::<TrimmedMetadata>() {
+                return Ok(());
+            }
+
+            let original = entry.input().as_ref().unwrap().clone();
+            let meta = entry.metadata().get::<MapNoveltiesMetadata>().ok_or_else(|| {
+                    Error::KeyNotFound(format!(
+                        "MapNoveltiesMetadata needed for TrimStage not found in testcase #{} (check the arguments of MapFeedback::new(...))",
+                        corpus_idx
+                    ))
+                })?;
+            (original, meta.as_slice().to_vec())
+        };
+        let original_len = original.bytes().len();
+
+        // Without novelties, or with an unstable input, any trimming would lose coverage
+        if original_len > TRIM_MIN_BYTES
+            && !novelties.is_empty()
+            && self
+                .keeps_novelties(fuzzer, executor, state, manager, &novelties, &original)?
+                .is_some()
+        {
+            let mut exec_time = None;
+            let mut bytes = original.bytes().to_vec();
+            let mut candidate = original;
+
+            let len_p2 = original_len.next_power_of_two();
+            let end_len = max(len_p2 / TRIM_END_STEPS, TRIM_MIN_BYTES);
+            let mut remove_len = max(len_p2 / TRIM_START_STEPS, TRIM_MIN_BYTES);
+            while remove_len >= end_len {
+                // As AFL, keep the first chunk, usually holding the magic bytes of the format
+                let mut pos = remove_len;
+                while pos < bytes.len() {
+                    let trim_len = min(remove_len, bytes.len() - pos);
+                    let candidate_bytes = candidate.bytes_mut();
+                    candidate_bytes.clear();
+                    candidate_bytes.extend_from_slice(&bytes[..pos]);
+                    candidate_bytes.extend_from_slice(&bytes[pos + trim_len..]);
+
+                    if let Some(time) = self
+                        .keeps_novelties(fuzzer, executor, state, manager, &novelties, &candidate)?
+                    {
+                        exec_time = Some(time);
+                        // The next chunk moved to `pos`
+                        bytes.drain(pos..pos + trim_len);
+                    } else {
+                        pos += remove_len;
+                    }
+                }
+                remove_len /= 2;
+            }
+
+            if bytes.len() < original_len {
+                let testcase = {
+                    let mut entry = state.corpus().get(corpus_idx)?.borrow_mut();
+                    entry.load_input()?;
+                    *entry.input_mut().as_mut().unwrap().bytes_mut() = bytes;
+                    *entry.exec_time_mut() = exec_time;
+                    // Refresh the length cached for the schedulers, while the input is loaded
+                    entry.cached_len()?;
+                    entry.store_input()?;
+                    entry.clone()
+                };
+                fuzzer
+                    .scheduler()
+                    .on_replace(state, corpus_idx, &testcase)?;
+            }
+        }
+
+        state
+            .corpus()
+            .get(corpus_idx)?
+            .borrow_mut()
+            .add_metadata(TrimmedMetadata { original_len });
+        Ok(())
+    }
+}
+
+impl<CS, EM, I, O, OT, S, Z> TrimStage<CS, EM, I, O, OT, S, Z>
+where
+    CS: CorpusScheduler<I, S>,
+    I: Input + HasBytesVec + HasLen,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions + HasMetadata + HasCorpus<I>,
+{
+    /// Create a new [`TrimStage`], keeping the novelties of the given map observer
+    #[must_use]
+    pub fn new(map_observer: &O) -> Self {
+        Self::from_name(map_observer.name())
+    }
+
+    /// Create a new [`TrimStage`] from the name of the map observer
+    #[must_use]
+    pub fn from_name(map_observer_name: &str) -> Self {
+        Self {
+            map_observer_name: map_observer_name.to_string(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// The execution time of `input`, if it runs fine, still reaching all the novelties
+    fn keeps_novelties<E>(
+        &self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        novelties: &[usize],
+        input: &I,
+    ) -> Result<Option<Duration>, Error>
+    where
+        E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    {
+        let start = current_time();
+        let (exit_kind, keeps_novelties) = run_and_check_novelties::<E, EM, I, O, OT, S, Z>(
+            fuzzer,
+            executor,
+            state,
+            manager,
+            &self.map_observer_name,
+            novelties,
+            input,
+        )?;
+        let time = current_time() - start;
+        Ok(if exit_kind == ExitKind::Ok && keeps_novelties {
+            Some(time)
+        } else {
+            None
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list, AsMutSlice},
+        corpus::{
+            Corpus, CorpusScheduler, InMemoryCorpus, IndexesLenTimeMinimizerCorpusScheduler,
+            QueueCorpusScheduler, Testcase, TopRatedsMetadata,
+        },
+        executors::{Executor, ExitKind, HasObservers},
+        feedbacks::{MapIndexesMetadata, MapNoveltiesMetadata},
+        inputs::{BytesInput, HasBytesVec},
+        observers::StdMapObserver,
+        stages::{trim::TrimmedMetadata, Stage, TrimStage},
+        state::{HasCorpus, HasMetadata, StdState},
+        Error, StdFuzzer,
+    };
+
+    /// Covers an entry for each byte of the `FUZZ` prefix of the input
+    #[derive(Debug)]
+    struct PrefixExecutor {
+        observers: (StdMapObserver<'static, u8>, ()),
+    }
+
+    impl<EM, S, Z> Executor<EM, BytesInput, S, Z> for PrefixExecutor {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            _state: &mut S,
+            _mgr: &mut EM,
+            input: &BytesInput,
+        ) -> Result<ExitKind, Error> {
+            let map = self.observers.0.as_mut_slice();
+            for (idx, _) in input
+                .bytes()
+                .iter()
+                .zip(b"FUZZ")
+                .enumerate()
+                .take_while(|(_, (byte, expected))| byte == expected)
+            {
+                map[idx] = 1;
+            }
+            Ok(ExitKind::Ok)
+        }
+    }
+
+    impl<S> HasObservers<BytesInput, (StdMapObserver<'static, u8>, ()), S> for PrefixExecutor {
+        fn observers(&self) -> &(StdMapObserver<'static, u8>, ()) {
+            &self.observers
+        }
+
+        fn observers_mut(&mut self) -> &mut (StdMapObserver<'static, u8>, ()) {
+            &mut self.observers
+        }
+    }
+
+    #[test]
+    fn test_trim_stage() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        let mut padded = b"FUZZ".to_vec();
+        padded.resize(64, b'x');
+        let mut testcase = Testcase::new(padded);
+        testcase.add_metadata(MapNoveltiesMetadata::new(vec![0, 1, 2, 3]));
+        testcase.add_metadata(MapIndexesMetadata::new(vec![0, 1, 2, 3]));
+        corpus.add(testcase).unwrap();
+        // Smaller before the trimming, so favored for its entries
+        let mut testcase = Testcase::new(b"FUxxxxxxxxxx".to_vec());
+        testcase.add_metadata(MapIndexesMetadata::new(vec![0, 1]));
+        corpus.add(testcase).unwrap();
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<BytesInput>::new(),
+            tuple_list!(),
+        );
+
+        let scheduler = IndexesLenTimeMinimizerCorpusScheduler::new(QueueCorpusScheduler::new());
+        scheduler.on_add(&mut state, 0).unwrap();
+        scheduler.on_add(&mut state, 1).unwrap();
+        assert_eq!(
+            state.metadata().get::<TopRatedsMetadata>().unwrap().map[&0],
+            1
+        );
+        let mut fuzzer: StdFuzzer<_, _, _, _, (), _> = StdFuzzer::new(scheduler, (), ());
+
+        let observer = StdMapObserver::new_owned("map", vec![0; 16]);
+        let mut trim = TrimStage::new(&observer);
+        let mut executor = PrefixExecutor {
+            observers: tuple_list!(observer),
+        };
+        trim.perform(&mut fuzzer, &mut executor, &mut state, &mut (), 0)
+            .unwrap();
+
+        let mut entry = state.corpus().get(0).unwrap().borrow_mut();
+        assert_eq!(entry.input().as_ref().unwrap().bytes(), b"FUZZ");
+        assert_eq!(entry.cached_len().unwrap(), 4);
+        assert!(entry.exec_time().is_some());
+        assert_eq!(
+            entry
+                .metadata()
+                .get::<TrimmedMetadata>()
+                .unwrap()
+                .original_len,
+            64
+        );
+        // The trimmed entry got rated again, and is now the best for all of its entries
+        let top_rated = state.metadata().get::<TopRatedsMetadata>().unwrap();
+        assert!((0..4).all(|elem| top_rated.map[&elem] == 0));
+    }
+}
@@ -0,0 +1,322 @@
+//! Checkpoint bundles, to move a running campaign to another machine.
+//!
+//! A checkpoint packages the whole [`State`], including its metadata, together with the inputs of
+//! the corpus and of the objectives that are only stored on disk, and their `.metadata` files,
+//! into a single file. The files are stored with their paths relative to the directory of the
+//! campaign, and get written back into the directory the campaign gets imported into.
+//! The corpora keep adding their new testcases to the directories they were created with, so
+//! create them with paths relative to the working directory to move them along.
+//! The file is checked for integrity when importing it.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::time::Duration;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::{
+    bolts::current_time,
+    corpus::Corpus,
+    inputs::Input,
+    state::{HasCorpus, HasExecutions, HasSolutions, State},
+    Error,
+};
+
+/// The magic bytes every checkpoint bundle starts with
+pub const CHECKPOINT_MAGIC: &[u8; 8] = b"LIBAFLCK";
+
+/// The version of the checkpoint format
+pub const CHECKPOINT_VERSION: u32 = 2;
+
+/// Describes a checkpoint bundle
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CheckpointInfo {
+    /// The time the checkpoint was created, since the epoch
+    pub created: Duration,
+    /// The version of `LibAFL` that created the checkpoint
+    pub libafl_version: String,
+    /// The executions done so far
+    pub executions: usize,
+    /// The number of corpus entries
+    pub corpus_count: usize,
+    /// The number of objectives
+    pub solutions_count: usize,
+}
+
+/// The files of a testcase stored on disk
+#[derive(Serialize, Deserialize)]
+struct CheckpointFile {
+    /// The index of the testcase
+    idx: usize,
+    /// The path of the input, relative to the directory of the campaign
+    path: String,
+    /// The serialized input
+    input: Vec<u8>,
+    /// The content of the `.metadata` file of the testcase, if any
+    metadata: Option<Vec<u8>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CheckpointPayload {
+    info: CheckpointInfo,
+    /// The state, with the paths of the testcases relative to the directory of the campaign
+    state: Vec<u8>,
+    /// The files of the corpus entries stored on disk
+    corpus_files: Vec<CheckpointFile>,
+    /// The files of the objectives stored on disk
+    solutions_files: Vec<CheckpointFile>,
+}
+
+/// The path of the `.metadata` file of the testcase stored at `filename`, as the
+/// [`crate::corpus::OnDiskCorpus`] writes it
+fn metadata_path(filename: &Path) -> PathBuf {
+    let mut path = filename.to_path_buf();
+    if let Some(name) = filename.file_name() {
+        path.set_file_name(format!(".{}.metadata", name.to_string_lossy()));
+    }
+    path
+}
+
+/// Collects the files of the testcases stored on disk, as the state only refers to them, and
+/// makes the paths of the testcases relative to `root`
+fn collect_files<C, I>(corpus: &C, root: &Path) -> Result<Vec<CheckpointFile>, Error>
+where
+    C: Corpus<I>,
+    I: Input,
+{
+    let mut files = vec![];
+    for idx in 0..corpus.count() {
+        let mut testcase = corpus.get(idx)?.borrow_mut();
+        let filename = match testcase.filename() {
+            Some(filename) => PathBuf::from(filename),
+            None => continue,
+        };
+        let path = filename
+            .strip_prefix(root)
+            .map_err(|_| {
+                Error::IllegalArgument(format!(
+                    "The testcase {} is not in the campaign directory {}",
+                    filename.display(),
+                    root.display()
+                ))
+            })?
+            .to_string_lossy()
+            .to_string();
+        let input = match testcase.input() {
+            Some(input) => postcard::to_allocvec(input)?,
+            None => postcard::to_allocvec(&I::from_file(&filename)?)?,
+        };
+        let metadata = fs::read(metadata_path(&filename)).ok();
+        testcase.set_filename(path.clone());
+        files.push(CheckpointFile {
+            idx,
+            path,
+            input,
+            metadata,
+        });
+    }
+    Ok(files)
+}
+
+/// Writes back the files of the testcases stored on disk, into `root`
+fn restore_files<C, I>(corpus: &C, files: &[CheckpointFile], root: &Path) -> Result<(), Error>
+where
+    C: Corpus<I>,
+    I: Input,
+{
+    for file in files {
+        let filename = root.join(&file.path);
+        if let Some(parent) = filename.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        postcard::from_bytes::<I>(&file.input)?.to_file(&filename)?;
+        if let Some(metadata) = &file.metadata {
+            fs::write(metadata_path(&filename), metadata)?;
+        }
+        corpus
+            .get(file.idx)?
+            .borrow_mut()
+            .set_filename(filename.to_string_lossy().to_string());
+    }
+    Ok(())
+}
+
+/// Exports the state, and the files of its corpus and objectives, into the bundle at `path`.
+/// The testcases stored on disk must be in the directory of the campaign, `root`.
+/// Returns the description of the bundle.
+pub fn export_checkpoint<I, P, R, S>(state: &S, root: R, path: P) -> Result<CheckpointInfo, Error>
+where
+    I: Input,
+    P: AsRef<Path>,
+    R: AsRef<Path>,
+    S: State + HasCorpus<I> + HasSolutions<I> + HasExecutions,
+{
+    let root = root.as_ref();
+    let info = CheckpointInfo {
+        created: current_time(),
+        libafl_version: env!("CARGO_PKG_VERSION").into(),
+        executions: *state.executions(),
+        corpus_count: state.corpus().count(),
+        solutions_count: state.solutions().count(),
+    };
+    // A copy of the state, to make the paths of its testcases relative
+    let relative: S = postcard::from_bytes(&postcard::to_allocvec(state)?)?;
+    let corpus_files = collect_files(relative.corpus(), root)?;
+    let solutions_files = collect_files(relative.solutions(), root)?;
+    let payload = postcard::to_allocvec(&CheckpointPayload {
+        info: info.clone(),
+        state: postcard::to_allocvec(&relative)?,
+        corpus_files,
+        solutions_files,
+    })?;
+
+    let mut bundle = Vec::with_capacity(payload.len() + 20);
+    bundle.extend_from_slice(CHECKPOINT_MAGIC);
+    bundle.extend_from_slice(&CHECKPOINT_VERSION.to_le_bytes());
+    bundle.extend_from_slice(&xxh3_64(&payload).to_le_bytes());
+    bundle.extend_from_slice(&payload);
+
+    // Write it at once, not to leave a truncated bundle behind
+    let path = path.as_ref();
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bundle)?;
+    fs::rename(tmp, path)?;
+    Ok(info)
+}
+
+/// Reads the bundle at `path`, checking its integrity
+fn read_payload(path: &Path) -> Result<CheckpointPayload, Error> {
+    let bundle = fs::read(path)?;
+    if bundle.len() < 20 || &bundle[..8] != CHECKPOINT_MAGIC {
+        return Err(Error::IllegalArgument(format!(
+            "{} is not a checkpoint bundle",
+            path.display()
+        )));
+    }
+    let version = u32::from_le_bytes(bundle[8..12].try_into().unwrap());
+    if version != CHECKPOINT_VERSION {
+        return Err(Error::IllegalArgument(format!(
+            "Unsupported checkpoint version {} (expected {})",
+            version, CHECKPOINT_VERSION
+        )));
+    }
+    let checksum = u64::from_le_bytes(bundle[12..20].try_into().unwrap());
+    let payload = &bundle[20..];
+    if xxh3_64(payload) != checksum {
+        return Err(Error::IllegalState(format!(
+            "The checkpoint {} is corrupted",
+            path.display()
+        )));
+    }
+    Ok(postcard::from_bytes(payload)?)
+}
+
+/// Describes the bundle at `path`, without importing it
+pub fn checkpoint_info<P>(path: P) -> Result<CheckpointInfo, Error>
+where
+    P: AsRef<Path>,
+{
+    Ok(read_payload(path.as_ref())?.info)
+}
+
+/// Imports the bundle at `path`, writing back the files of the corpus and of the objectives
+/// stored on disk into the directory of the campaign, `root`, and returns the state to resume
+/// the campaign with
+pub fn import_checkpoint<I, P, R, S>(path: P, root: R) -> Result<S, Error>
+where
+    I: Input,
+    P: AsRef<Path>,
+    R: AsRef<Path>,
+    S: State + HasCorpus<I> + HasSolutions<I>,
+{
+    let root = root.as_ref();
+    let payload = read_payload(path.as_ref())?;
+    let state: S = postcard::from_bytes(&payload.state)?;
+    restore_files(state.corpus(), &payload.corpus_files, root)?;
+    restore_files(state.solutions(), &payload.solutions_files, root)?;
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::{export_checkpoint, import_checkpoint};
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{ondisk::OnDiskMetadataFormat, Corpus, InMemoryCorpus, OnDiskCorpus, Testcase},
+        inputs::BytesInput,
+        state::{HasCorpus, HasSolutions, StdState},
+    };
+
+    type TestState =
+        StdState<InMemoryCorpus<BytesInput>, (), BytesInput, StdRand, OnDiskCorpus<BytesInput>>;
+
+    #[test]
+    fn test_checkpoint_roundtrip() {
+        let dir =
+            std::env::temp_dir().join(format!("libafl_test_checkpoint_{}", std::process::id()));
+        let root = dir.join("campaign");
+        let mut corpus = InMemoryCorpus::new();
+        corpus
+            .add(Testcase::new(BytesInput::new(b"corpus".to_vec())))
+            .unwrap();
+        let mut solutions =
+            OnDiskCorpus::new_save_meta(root.join("crashes"), Some(OnDiskMetadataFormat::Json))
+                .unwrap();
+        solutions
+            .add(Testcase::new(BytesInput::new(b"crash".to_vec())))
+            .unwrap();
+        let state: TestState = StdState::new(StdRand::with_seed(0), corpus, solutions, ());
+        let crash_name = {
+            let crash = state.solutions().get(0).unwrap().borrow();
+            let filename = crash.filename().clone().unwrap();
+            Path::new(&filename).file_name().unwrap().to_owned()
+        };
+
+        let bundle = dir.join("checkpoint.bin");
+        let info = export_checkpoint(&state, &root, &bundle).unwrap();
+        assert_eq!(info.corpus_count, 1);
+        assert_eq!(info.solutions_count, 1);
+        // The paths of the testcases are relative in the bundle
+        let content = fs::read(&bundle).unwrap();
+        let crash_path = root.join("crashes").join(&crash_name);
+        let crash_path = crash_path.to_string_lossy();
+        assert!(!content
+            .windows(crash_path.len())
+            .any(|window| window == crash_path.as_bytes()));
+
+        // Imported into another directory
+        let moved = dir.join("moved");
+        let imported: TestState = import_checkpoint(&bundle, &moved).unwrap();
+        assert_eq!(imported.corpus().count(), 1);
+        let crash = imported.solutions().get(0).unwrap().borrow();
+        let filename = crash.filename().clone().unwrap();
+        assert_eq!(
+            Path::new(&filename),
+            moved.join("crashes").join(&crash_name)
+        );
+        assert_eq!(fs::read(&filename).unwrap(), b"crash");
+        let metadata = moved
+            .join("crashes")
+            .join(format!(".{}.metadata", crash_name.to_string_lossy()));
+        assert!(fs::read_to_string(metadata).unwrap().contains("executions"));
+
+        // Outside of the campaign directory
+        assert!(export_checkpoint(&state, dir.join("other"), &bundle).is_err());
+
+        let mut corrupted = fs::read(&bundle).unwrap();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 0xff;
+        fs::write(&bundle, corrupted).unwrap();
+        assert!(import_checkpoint::<BytesInput, _, _, TestState>(&bundle, &moved).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
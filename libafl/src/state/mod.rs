@@ -23,6 +23,11 @@ use crate::{
     Error,
 };
 
+#[cfg(feature = "std")]
+pub mod checkpoint;
+#[cfg(feature = "std")]
+pub use checkpoint::{export_checkpoint, import_checkpoint, CheckpointInfo};
+
 /// The maximum size of a testcase
 pub const DEFAULT_MAX_SIZE: usize = 1_048_576;
 
@@ -152,6 +152,7 @@ fn main() {
         };
 
         println!("cargo:rerun-if-changed=src/common-llvm.h");
+        println!("cargo:rerun-if-changed=src/instrument-list.h");
         println!("cargo:rerun-if-changed=src/cmplog-routines-pass.cc");
         println!("cargo:rerun-if-changed=src/afl-coverage-pass.cc");
         println!("cargo:rerun-if-changed=src/autotokens-pass.cc");
@@ -31,6 +31,8 @@
 #include <fstream>
 #include <set>
 
+#include "instrument-list.h"
+
 #include "llvm/Config/llvm-config.h"
 #include "llvm/ADT/Statistic.h"
 #include "llvm/IR/IRBuilder.h"
@@ -231,7 +233,7 @@ bool AutoTokensPass::runOnModule(Module &M) {
 
   for (auto &F : M) {
 
-    if (isIgnoreFunction(&F)) continue;
+    if (isIgnoreFunction(&F) || !isInInstrumentList(&F)) continue;
 
     /*  Some implementation notes.
      *
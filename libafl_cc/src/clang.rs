@@ -22,6 +22,47 @@ fn dll_extension<'a>() -> &'a str {
 
 include!(concat!(env!("OUT_DIR"), "/clang_constants.rs"));
 
+/// The name of the static library linked by [`ClangWrapper::default_runner`]
+pub const DEFAULT_RUNNER_LIB: &str = "libafl_libfuzzer";
+
+/// The maximum size of the ngram for ngram coverage, as supported by the coverage pass
+pub const NGRAM_SIZE_MAX: u32 = 16;
+
+/// The maximum `k` for `k`-context sensitive coverage, as supported by the coverage pass
+pub const CTX_MAX_K: u32 = 32;
+
+/// Returns `true` if the given env var is set to anything but `0` or an empty string
+fn env_flag_set(name: &str) -> bool {
+    env::var(name).map_or(false, |value| !value.is_empty() && value != "0")
+}
+
+/// The arguments to link the whole static library `name` in `dir`
+fn staticlib_link_args(dir: &Path, name: &str) -> Vec<String> {
+    let mut args = vec![];
+    if cfg!(target_vendor = "apple") {
+        //args.push("-force_load".into());
+    } else {
+        args.push("-Wl,--whole-archive".into());
+    }
+    args.push(
+        dir.join(format!("{}{}.{}", LIB_PREFIX, name, LIB_EXT))
+            .into_os_string()
+            .into_string()
+            .unwrap(),
+    );
+    if !cfg!(target_vendor = "apple") {
+        args.push("-Wl,-no-whole-archive".into());
+    }
+    args
+}
+
+/// Parses the numeric value of a wrapper flag or env var
+fn parse_u32(name: &str, value: &str) -> Result<u32, Error> {
+    value
+        .parse()
+        .map_err(|_| Error::InvalidArguments(format!("Invalid value {} for {}", value, name)))
+}
+
 /// The supported LLVM passes
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -77,6 +118,15 @@ pub struct ClangWrapper {
     cc_args: Vec<String>,
     link_args: Vec<String>,
     passes: Vec<LLVMPasses>,
+    cmplog: bool,
+    autotokens: bool,
+    stack_depth: bool,
+    ctx: bool,
+    ctx_k: u32,
+    ngram: u32,
+    allowlist: Option<PathBuf>,
+    denylist: Option<PathBuf>,
+    default_runner: Option<PathBuf>,
 }
 
 #[allow(clippy::match_same_arms)] // for the linking = false wip for "shared"
@@ -106,6 +156,9 @@ impl CompilerWrapper for ClangWrapper {
             ));
         }
 
+        // Instrumentation can also be selected from the environment, i.e., for configure scripts
+        self.parse_env()?;
+
         self.name = args[0].as_ref().to_string();
         // Detect C++ compiler looking at the wrapper name
         self.is_cpp = self.is_cpp || self.name.ends_with("++");
@@ -130,6 +183,7 @@ impl CompilerWrapper for ClangWrapper {
                     self.has_libafl_arg = true;
                     continue;
                 }
+                arg if self.parse_instrumentation_arg(arg)? => continue,
                 "-x" => self.x_set = true,
                 "-m32" => self.bit_mode = 32,
                 "-m64" => self.bit_mode = 64,
@@ -142,6 +196,8 @@ impl CompilerWrapper for ClangWrapper {
         }
         self.linking = linking;
 
+        self.check_coverage_options()?;
+
         if self.optimize {
             new_args.push("-g".into());
             new_args.push("-O3".into());
@@ -202,22 +258,10 @@ impl CompilerWrapper for ClangWrapper {
     where
         S: AsRef<str>,
     {
-        if cfg!(target_vendor = "apple") {
-            //self.add_link_arg("-force_load".into())?;
-        } else {
-            self.add_link_arg("-Wl,--whole-archive");
-        }
-        self.add_link_arg(
-            dir.join(format!("{}{}.{}", LIB_PREFIX, name.as_ref(), LIB_EXT))
-                .into_os_string()
-                .into_string()
-                .unwrap(),
-        );
-        if cfg!(target_vendor = "apple") {
-            self
-        } else {
-            self.add_link_arg("-Wl,-no-whole-archive")
+        for arg in staticlib_link_args(dir, name.as_ref()) {
+            self.add_link_arg(arg);
         }
+        self
     }
 
     fn command(&mut self) -> Result<Vec<String>, Error> {
@@ -232,10 +276,64 @@ impl CompilerWrapper for ClangWrapper {
             return Ok(args);
         }
 
-        if !self.passes.is_empty() {
+        // The passes read the lists from the env, sancov takes them as arguments
+        if let Some(allowlist) = &self.allowlist {
+            env::set_var("LIBAFL_ALLOWLIST", allowlist);
+            args.push(format!(
+                "-fsanitize-coverage-allowlist={}",
+                allowlist.to_string_lossy()
+            ));
+        }
+        if let Some(denylist) = &self.denylist {
+            env::set_var("LIBAFL_DENYLIST", denylist);
+            args.push(format!(
+                "-fsanitize-coverage-ignorelist={}",
+                denylist.to_string_lossy()
+            ));
+        }
+
+        if self.default_runner.is_some() {
+            // The default runner expects edges and comparisons from sancov
+            args.push("-fsanitize-coverage=trace-pc-guard".into());
+        }
+
+        let mut passes = self.passes.clone();
+        if self.cmplog || self.default_runner.is_some() {
+            // Routines are logged by the pass, instructions by the sancov `trace-cmp` callbacks
+            args.push("-fsanitize-coverage=trace-cmp".into());
+            if !passes.contains(&LLVMPasses::CmpLogRtn) {
+                passes.push(LLVMPasses::CmpLogRtn);
+            }
+        }
+        if self.autotokens && !passes.contains(&LLVMPasses::AutoTokens) {
+            passes.push(LLVMPasses::AutoTokens);
+        }
+        if self.stack_depth {
+            // The entry and exit hooks are in `libafl_targets`, with its `stack_depth` feature
+            args.push("-finstrument-functions".into());
+        }
+        if self.ctx || self.ctx_k > 0 || self.ngram > 0 {
+            if !passes.contains(&LLVMPasses::AFLCoverage) {
+                passes.push(LLVMPasses::AFLCoverage);
+            }
+            if self.ctx {
+                args.push("-mllvm".into());
+                args.push("-ctx".into());
+            }
+            if self.ctx_k > 0 {
+                args.push("-mllvm".into());
+                args.push(format!("-ctx_k={}", self.ctx_k));
+            }
+            if self.ngram > 0 {
+                args.push("-mllvm".into());
+                args.push(format!("-ngram={}", self.ngram));
+            }
+        }
+
+        if !passes.is_empty() {
             args.push("-fno-experimental-new-pass-manager".into());
         }
-        for pass in &self.passes {
+        for pass in &passes {
             args.push("-Xclang".into());
             args.push("-load".into());
             args.push("-Xclang".into());
@@ -249,6 +347,10 @@ impl CompilerWrapper for ClangWrapper {
 
             args.extend_from_slice(self.link_args.as_slice());
 
+            if let Some(dir) = &self.default_runner {
+                args.extend(staticlib_link_args(dir, DEFAULT_RUNNER_LIB));
+            }
+
             if cfg!(unix) {
                 args.push("-pthread".into());
                 args.push("-ldl".into());
@@ -283,6 +385,72 @@ impl Default for ClangWrapper {
 }
 
 impl ClangWrapper {
+    /// Parses the `--libafl-*` arguments selecting the instrumentation.
+    /// Returns `true` if the argument was one of them.
+    fn parse_instrumentation_arg(&mut self, arg: &str) -> Result<bool, Error> {
+        if let Some(value) = arg.strip_prefix("--libafl-ctx-k=") {
+            self.ctx_k = parse_u32("--libafl-ctx-k", value)?;
+        } else if let Some(value) = arg.strip_prefix("--libafl-ngram=") {
+            self.ngram = parse_u32("--libafl-ngram", value)?;
+        } else if let Some(value) = arg.strip_prefix("--libafl-allowlist=") {
+            self.allowlist = Some(PathBuf::from(value));
+        } else if let Some(value) = arg.strip_prefix("--libafl-denylist=") {
+            self.denylist = Some(PathBuf::from(value));
+        } else if let Some(value) = arg.strip_prefix("--libafl-default-runner=") {
+            self.default_runner = Some(PathBuf::from(value));
+        } else {
+            match arg {
+                "--libafl-cmplog" => self.cmplog = true,
+                "--libafl-autotokens" => self.autotokens = true,
+                "--libafl-stack-depth" => self.stack_depth = true,
+                "--libafl-ctx" => self.ctx = true,
+                _ => return Ok(false),
+            }
+        }
+        Ok(true)
+    }
+
+    /// Reads the instrumentation options from the `LIBAFL_*` env vars
+    fn parse_env(&mut self) -> Result<(), Error> {
+        self.cmplog = self.cmplog || env_flag_set("LIBAFL_CMPLOG");
+        self.autotokens = self.autotokens || env_flag_set("LIBAFL_AUTOTOKENS");
+        self.stack_depth = self.stack_depth || env_flag_set("LIBAFL_STACK_DEPTH");
+        self.ctx = self.ctx || env_flag_set("LIBAFL_CTX");
+        if let Ok(value) = env::var("LIBAFL_CTX_K") {
+            self.ctx_k = parse_u32("LIBAFL_CTX_K", &value)?;
+        }
+        if let Ok(value) = env::var("LIBAFL_NGRAM") {
+            self.ngram = parse_u32("LIBAFL_NGRAM", &value)?;
+        }
+        if self.allowlist.is_none() {
+            self.allowlist = env::var_os("LIBAFL_ALLOWLIST").map(PathBuf::from);
+        }
+        if self.default_runner.is_none() {
+            self.default_runner = env::var_os("LIBAFL_DEFAULT_RUNNER").map(PathBuf::from);
+        }
+        if self.denylist.is_none() {
+            self.denylist = env::var_os("LIBAFL_DENYLIST").map(PathBuf::from);
+        }
+        Ok(())
+    }
+
+    /// Checks the context sensitivity and ngram options against the limits of the coverage pass
+    fn check_coverage_options(&self) -> Result<(), Error> {
+        if self.ngram != 0 && !(2..=NGRAM_SIZE_MAX).contains(&self.ngram) {
+            return Err(Error::InvalidArguments(format!(
+                "The ngram size must be between 2 and {}",
+                NGRAM_SIZE_MAX
+            )));
+        }
+        if self.ctx_k > CTX_MAX_K {
+            return Err(Error::InvalidArguments(format!(
+                "The k for k-context sensitivity must be between 1 and {}",
+                CTX_MAX_K
+            )));
+        }
+        Ok(())
+    }
+
     /// Create a new Clang Wrapper
     #[must_use]
     pub fn new() -> Self {
@@ -302,6 +470,15 @@ impl ClangWrapper {
             cc_args: vec![],
             link_args: vec![],
             passes: vec![],
+            cmplog: false,
+            autotokens: false,
+            stack_depth: false,
+            ctx: false,
+            ctx_k: 0,
+            ngram: 0,
+            allowlist: None,
+            denylist: None,
+            default_runner: None,
             is_silent: false,
         }
     }
@@ -336,6 +513,80 @@ impl ClangWrapper {
         self
     }
 
+    /// Enable `CmpLog` instrumentation: the `CmpLog` routines pass, and `trace-cmp` for instructions.
+    /// Can also be enabled with `--libafl-cmplog` or the `LIBAFL_CMPLOG=1` env var.
+    pub fn cmplog(&mut self, value: bool) -> &'_ mut Self {
+        self.cmplog = value;
+        self
+    }
+
+    /// Enable the autotokens pass, which embeds the constants of comparisons in the binary,
+    /// to be read back as dictionary with `libafl_targets::autotokens()`.
+    /// Can also be enabled with `--libafl-autotokens` or the `LIBAFL_AUTOTOKENS=1` env var.
+    pub fn autotokens(&mut self, value: bool) -> &'_ mut Self {
+        self.autotokens = value;
+        self
+    }
+
+    /// Enable the tracking of the call depth, for the `StackDepthObserver`, with
+    /// `-finstrument-functions`. Link `libafl_targets` with its `stack_depth` feature.
+    /// Can also be enabled with `--libafl-stack-depth` or the `LIBAFL_STACK_DEPTH=1` env var.
+    pub fn stack_depth(&mut self, value: bool) -> &'_ mut Self {
+        self.stack_depth = value;
+        self
+    }
+
+    /// Enable full context sensitive edge coverage: each edge is combined with the call stack.
+    /// Can also be enabled with `--libafl-ctx` or the `LIBAFL_CTX=1` env var.
+    pub fn ctx(&mut self, value: bool) -> &'_ mut Self {
+        self.ctx = value;
+        self
+    }
+
+    /// Enable `k`-context sensitive edge coverage, combining each edge with the last `k` callers.
+    /// Use `0` to disable. Can also be set with `--libafl-ctx-k=<k>` or the `LIBAFL_CTX_K` env var.
+    pub fn ctx_k(&mut self, k: u32) -> &'_ mut Self {
+        self.ctx_k = k;
+        self
+    }
+
+    /// Enable `N`-gram edge coverage, combining each edge with the last `N - 1` locations.
+    /// Use `0` to disable. Can also be set with `--libafl-ngram=<N>` or the `LIBAFL_NGRAM` env var.
+    pub fn ngram(&mut self, n: u32) -> &'_ mut Self {
+        self.ngram = n;
+        self
+    }
+
+    /// Only instrument the source files (`src: <pattern>`) and functions (`fun: <pattern>`) listed
+    /// in the given file, one per line.
+    /// The file is also passed to clang's `-fsanitize-coverage-allowlist`, which needs all entries to
+    /// have one of these prefixes. Can also be set with `--libafl-allowlist=<file>` or the
+    /// `LIBAFL_ALLOWLIST` env var.
+    pub fn allowlist<P: AsRef<Path>>(&mut self, path: P) -> &'_ mut Self {
+        self.allowlist = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Never instrument the source files and functions listed in the given file, see
+    /// [`Self::allowlist`] for the format. Can also be set with `--libafl-denylist=<file>` or the
+    /// `LIBAFL_DENYLIST` env var.
+    pub fn denylist<P: AsRef<Path>>(&mut self, path: P) -> &'_ mut Self {
+        self.denylist = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Link a default fuzzer into the binary, so that it can be fuzzed right away.
+    /// The runner is the `libafl_libfuzzer` static library, built to the given directory
+    /// (i.e., `target/release`). It fuzzes `LLVMFuzzerTestOneInput` in-process and understands the
+    /// common `libFuzzer` flags. This also enables `trace-pc-guard` coverage and `CmpLog`.
+    /// This in-process runner is the only one supported: to fuzz with another fuzzer, such as a
+    /// forkserver, link it with [`CompilerWrapper::link_staticlib`] instead.
+    /// Can also be set with `--libafl-default-runner=<dir>` or the `LIBAFL_DEFAULT_RUNNER` env var.
+    pub fn default_runner<P: AsRef<Path>>(&mut self, dir: P) -> &'_ mut Self {
+        self.default_runner = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
     /// Set if linking
     pub fn linking(&mut self, value: bool) -> &'_ mut Self {
         self.linking = value;
@@ -351,7 +602,9 @@ impl ClangWrapper {
 
 #[cfg(test)]
 mod tests {
-    use crate::{ClangWrapper, CompilerWrapper};
+    use std::path::Path;
+
+    use crate::{clang::DEFAULT_RUNNER_LIB, ClangWrapper, CompilerWrapper, LIB_EXT, LIB_PREFIX};
 
     #[test]
     fn test_clang_version() {
@@ -363,4 +616,115 @@ mod tests {
             println!("Ignored error {:?} - clang is probably not installed.", res);
         }
     }
+
+    #[test]
+    fn test_cmplog_arg() {
+        let args = ClangWrapper::new()
+            .parse_args(&["my-clang", "--libafl-cmplog", "-c", "test.c"])
+            .unwrap()
+            .command()
+            .unwrap();
+        assert!(args
+            .iter()
+            .any(|arg| arg == "-fsanitize-coverage=trace-cmp"));
+        assert!(!args.iter().any(|arg| arg == "--libafl-cmplog"));
+    }
+
+    #[test]
+    fn test_allowlist_denylist_args() {
+        let args = ClangWrapper::new()
+            .allowlist("allow.txt")
+            .parse_args(&["my-clang", "--libafl-denylist=deny.txt", "-c", "test.c"])
+            .unwrap()
+            .command()
+            .unwrap();
+        assert!(args
+            .iter()
+            .any(|arg| arg == "-fsanitize-coverage-allowlist=allow.txt"));
+        assert!(args
+            .iter()
+            .any(|arg| arg == "-fsanitize-coverage-ignorelist=deny.txt"));
+        assert!(!args.iter().any(|arg| arg.starts_with("--libafl-")));
+        // The passes read the lists from the env
+        assert_eq!(std::env::var("LIBAFL_ALLOWLIST").unwrap(), "allow.txt");
+        assert_eq!(std::env::var("LIBAFL_DENYLIST").unwrap(), "deny.txt");
+    }
+
+    #[test]
+    fn test_default_runner_args() {
+        let runner = Path::new("runner_dir")
+            .join(format!("{}{}.{}", LIB_PREFIX, DEFAULT_RUNNER_LIB, LIB_EXT))
+            .into_os_string()
+            .into_string()
+            .unwrap();
+
+        let args = ClangWrapper::new()
+            .parse_args(&[
+                "my-clang",
+                "--libafl-default-runner=runner_dir",
+                "test.c",
+                "-o",
+                "test",
+            ])
+            .unwrap()
+            .command()
+            .unwrap();
+        let runner_pos = args.iter().position(|arg| *arg == runner).unwrap();
+        #[cfg(not(target_vendor = "apple"))]
+        {
+            assert_eq!(args[runner_pos - 1], "-Wl,--whole-archive");
+            assert_eq!(args[runner_pos + 1], "-Wl,-no-whole-archive");
+        }
+        assert!(args
+            .iter()
+            .any(|arg| arg == "-fsanitize-coverage=trace-pc-guard"));
+        assert!(args
+            .iter()
+            .any(|arg| arg == "-fsanitize-coverage=trace-cmp"));
+
+        // Only linked into the binary
+        let args = ClangWrapper::new()
+            .default_runner("runner_dir")
+            .parse_args(&["my-clang", "-c", "test.c"])
+            .unwrap()
+            .command()
+            .unwrap();
+        assert!(!args.contains(&runner));
+        assert!(args
+            .iter()
+            .any(|arg| arg == "-fsanitize-coverage=trace-pc-guard"));
+    }
+
+    #[test]
+    fn test_ctx_ngram_args() {
+        let args = ClangWrapper::new()
+            .parse_args(&[
+                "my-clang",
+                "--libafl-ctx-k=4",
+                "--libafl-ngram=8",
+                "-c",
+                "test.c",
+            ])
+            .unwrap()
+            .command()
+            .unwrap();
+        assert!(args
+            .windows(2)
+            .any(|pair| pair[0] == "-mllvm" && pair[1] == "-ctx_k=4"));
+        assert!(args
+            .windows(2)
+            .any(|pair| pair[0] == "-mllvm" && pair[1] == "-ngram=8"));
+        assert!(!args.iter().any(|arg| arg == "-ctx"));
+
+        for arg in [
+            "--libafl-ngram=1",
+            "--libafl-ngram=17",
+            "--libafl-ctx-k=33",
+            "--libafl-ctx-k=many",
+        ] {
+            assert!(ClangWrapper::new()
+                .parse_args(&["my-clang", arg, "-c", "test.c"])
+                .is_err());
+        }
+    }
 }
@@ -48,6 +48,8 @@
 
 #include <set>
 
+#include "instrument-list.h"
+
 using namespace llvm;
 
 namespace {
@@ -247,7 +249,7 @@ bool CmpLogRoutines::hookRtns(Module &M) {
   /* iterate over all functions, bbs and instruction and add suitable calls */
   for (auto &F : M) {
 
-    if (isIgnoreFunction(&F)) continue;
+    if (isIgnoreFunction(&F) || !isInInstrumentList(&F)) continue;
 
     for (auto &BB : F) {
 
@@ -27,6 +27,7 @@
  */
 
 #include "common-llvm.h"
+#include "instrument-list.h"
 
 #include <time.h>
 
@@ -182,7 +183,7 @@ bool AFLCoverage::runOnModule(Module &M) {
       fprintf(stderr, "FUNCTION: %s (%zu)\n", F.getName().str().c_str(),
               F.size());
 
-    // if (!isInInstrumentList(&F)) { continue; }
+    if (!isInInstrumentList(&F)) { continue; }
 
     if (F.size() < function_minimum_size) { continue; }
 
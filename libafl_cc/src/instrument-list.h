@@ -0,0 +1,158 @@
+#ifndef LIBAFL_INSTRUMENT_LIST_H
+#define LIBAFL_INSTRUMENT_LIST_H
+
+/*
+   AFL-style allowlist and denylist for the LibAFL passes.
+
+   The lists are read from the files in the LIBAFL_ALLOWLIST and
+   LIBAFL_DENYLIST env vars (AFL_LLVM_ALLOWLIST and AFL_LLVM_DENYLIST work,
+   too). Each line is one of
+     src: <source file pattern>
+     fun: <function name pattern>
+     <source file pattern>
+   Empty lines and lines starting with '#' are ignored. Patterns may contain
+   shell wildcards; a source pattern also matches at the end of the full path.
+
+   If an allowlist is given, only matching functions get instrumented.
+   Functions matching the denylist never get instrumented.
+*/
+
+#include <stdlib.h>
+#include <string.h>
+#ifndef _WIN32
+  #include <fnmatch.h>
+#endif
+
+#include <fstream>
+#include <string>
+#include <vector>
+
+#include "llvm/IR/DebugInfoMetadata.h"
+#include "llvm/IR/Function.h"
+#include "llvm/IR/Module.h"
+
+struct InstrumentList {
+
+  bool                     present = false;
+  std::vector<std::string> sources;
+  std::vector<std::string> functions;
+
+};
+
+static std::string instrumentListTrim(const std::string &s) {
+
+  size_t start = s.find_first_not_of(" \t\r\n");
+  if (start == std::string::npos) return "";
+  size_t end = s.find_last_not_of(" \t\r\n");
+  return s.substr(start, end - start + 1);
+
+}
+
+static void loadInstrumentList(InstrumentList &list, const char *env,
+                               const char *fallback_env) {
+
+  const char *path = getenv(env);
+  if (!path) path = getenv(fallback_env);
+  if (!path || !*path) return;
+
+  std::ifstream file(path);
+  if (!file.is_open()) {
+
+    fprintf(stderr, "FATAL: Could not open instrument list %s\n", path);
+    exit(1);
+
+  }
+
+  list.present = true;
+  std::string line;
+  while (std::getline(file, line)) {
+
+    line = instrumentListTrim(line);
+    if (line.empty() || line[0] == '#') continue;
+
+    if (line.compare(0, 4, "fun:") == 0) {
+
+      list.functions.push_back(instrumentListTrim(line.substr(4)));
+
+    } else if (line.compare(0, 4, "src:") == 0) {
+
+      list.sources.push_back(instrumentListTrim(line.substr(4)));
+
+    } else {
+
+      list.sources.push_back(line);
+
+    }
+
+  }
+
+}
+
+static bool instrumentListMatch(const std::string &pattern,
+                                const std::string &value, bool suffix) {
+
+  if (value.empty()) return false;
+#ifndef _WIN32
+  if (fnmatch(pattern.c_str(), value.c_str(), 0) == 0) return true;
+#else
+  if (pattern == value) return true;
+#endif
+  // A relative source pattern also matches the end of an absolute path
+  return suffix && value.size() > pattern.size() &&
+         value.compare(value.size() - pattern.size(), pattern.size(),
+                       pattern) == 0 &&
+         (value[value.size() - pattern.size() - 1] == '/' ||
+          value[value.size() - pattern.size() - 1] == '\\');
+
+}
+
+static bool instrumentListContains(const InstrumentList &list,
+                                   const llvm::Function *F) {
+
+  std::string name = F->getName().str();
+  for (const std::string &pattern : list.functions)
+    if (instrumentListMatch(pattern, name, false)) return true;
+
+  std::string source;
+  if (llvm::DISubprogram *SP = F->getSubprogram()) {
+
+    source = SP->getFilename().str();
+    std::string dir = SP->getDirectory().str();
+    if (!source.empty() && source[0] != '/' && !dir.empty())
+      source = dir + "/" + source;
+
+  } else {
+
+    source = F->getParent()->getSourceFileName();
+
+  }
+
+  for (const std::string &pattern : list.sources)
+    if (instrumentListMatch(pattern, source, true)) return true;
+
+  return false;
+
+}
+
+// Returns true if the function should be instrumented
+static bool isInInstrumentList(const llvm::Function *F) {
+
+  static bool           loaded = false;
+  static InstrumentList allowlist, denylist;
+
+  if (!loaded) {
+
+    loadInstrumentList(allowlist, "LIBAFL_ALLOWLIST", "AFL_LLVM_ALLOWLIST");
+    loadInstrumentList(denylist, "LIBAFL_DENYLIST", "AFL_LLVM_DENYLIST");
+    loaded = true;
+
+  }
+
+  if (F->isDeclaration()) return false;
+  if (allowlist.present && !instrumentListContains(allowlist, F)) return false;
+  if (denylist.present && instrumentListContains(denylist, F)) return false;
+  return true;
+
+}
+
+#endif  // LIBAFL_INSTRUMENT_LIST_H
@@ -59,7 +59,7 @@
 use std::{convert::Into, path::Path, process::Command, string::String, vec::Vec};
 
 pub mod clang;
-pub use clang::{ClangWrapper, LLVMPasses};
+pub use clang::{ClangWrapper, LLVMPasses, CTX_MAX_K, DEFAULT_RUNNER_LIB, NGRAM_SIZE_MAX};
 
 /// `LibAFL` CC Error Type
 #[derive(Debug)]
@@ -1,6 +1,13 @@
 //! Functionality regarding binary-only coverage collection.
 use core::ptr::addr_of_mut;
 use dynasmrt::{dynasm, DynasmApi, DynasmLabelApi};
+use libafl::{
+    bolts::{
+        shmem::{ShMem, ShMemProvider, StdShMemProvider},
+        AsMutSlice,
+    },
+    Error,
+};
 use rangemap::RangeMap;
 
 #[cfg(target_arch = "aarch64")]
@@ -18,10 +25,32 @@ use crate::helper::FridaRuntime;
 /// (Default) map size for frida coverage reporting
 pub const MAP_SIZE: usize = 64 * 1024;
 
+/// The shared memory type used to share the coverage map with other processes
+pub type CoverageShMem = <StdShMemProvider as ShMemProvider>::ShMem;
+
+/// The backing memory of the coverage map
+#[derive(Debug)]
+enum CoverageMap {
+    /// The map is owned by the runtime
+    Owned(Box<[u8]>),
+    /// The map lives in shared memory, so that other processes can read it live
+    Shared(CoverageShMem),
+}
+
+impl CoverageMap {
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        match self {
+            CoverageMap::Owned(map) => map,
+            CoverageMap::Shared(shmem) => shmem.as_mut_slice(),
+        }
+    }
+}
+
 /// Frida binary-only coverage
 #[derive(Debug)]
 pub struct CoverageRuntime {
-    map: [u8; MAP_SIZE],
+    map: CoverageMap,
+    map_size: usize,
     previous_pc: u64,
     current_log_impl: u64,
     blob_maybe_log: Option<Box<[u8]>>,
@@ -60,20 +89,79 @@ impl FridaRuntime for CoverageRuntime {
 }
 
 impl CoverageRuntime {
-    /// Create a new coverage runtime
+    /// Create a new coverage runtime, with a map of the default [`MAP_SIZE`]
     #[must_use]
     pub fn new() -> Self {
+        Self::with_map_size(MAP_SIZE)
+    }
+
+    /// Create a new coverage runtime with a map of the given size.
+    ///
+    /// # Panics
+    /// Panics if `map_size` is not a power of two.
+    #[must_use]
+    pub fn with_map_size(map_size: usize) -> Self {
+        assert!(
+            map_size.is_power_of_two(),
+            "The coverage map size must be a power of two, got {}",
+            map_size
+        );
         Self {
-            map: [0_u8; MAP_SIZE],
+            map: CoverageMap::Owned(vec![0_u8; map_size].into_boxed_slice()),
+            map_size,
             previous_pc: 0,
             current_log_impl: 0,
             blob_maybe_log: None,
         }
     }
 
+    /// Create a new coverage runtime that writes its coverage into the given shared memory.
+    /// This way, other processes (for example a monitoring tool) can read the map live.
+    /// The map size is the largest power of two that fits into the shared memory.
+    pub fn with_shmem(shmem: CoverageShMem) -> Result<Self, Error> {
+        if shmem.is_empty() {
+            return Err(Error::IllegalArgument(
+                "The shared memory for the coverage map must not be empty".into(),
+            ));
+        }
+        // Round down to a power of two, so that we can mask locations into the map
+        let map_size = 1 << (usize::BITS - 1 - shmem.len().leading_zeros());
+        Ok(Self {
+            map: CoverageMap::Shared(shmem),
+            map_size,
+            previous_pc: 0,
+            current_log_impl: 0,
+            blob_maybe_log: None,
+        })
+    }
+
+    /// Create a new coverage runtime backed by fresh shared memory of the given size,
+    /// which is then published in the environment variable `env_name`.
+    pub fn with_shmem_in_env(map_size: usize, env_name: &str) -> Result<Self, Error> {
+        let mut shmem_provider = StdShMemProvider::new()?;
+        let shmem = shmem_provider.new_shmem(map_size)?;
+        shmem.write_to_env(env_name)?;
+        Self::with_shmem(shmem)
+    }
+
     /// Retrieve the coverage map pointer
     pub fn map_ptr_mut(&mut self) -> *mut u8 {
-        self.map.as_mut_ptr()
+        self.map.as_mut_slice().as_mut_ptr()
+    }
+
+    /// The size of the coverage map
+    #[must_use]
+    pub fn map_size(&self) -> usize {
+        self.map_size
+    }
+
+    /// The shared memory backing the coverage map, if any
+    #[must_use]
+    pub fn shmem(&self) -> Option<&CoverageShMem> {
+        match &self.map {
+            CoverageMap::Owned(_) => None,
+            CoverageMap::Shared(shmem) => Some(shmem),
+        }
     }
 
     /// Retrieve the `maybe_log` code blob, that will write coverage into the map
@@ -96,7 +184,7 @@ impl CoverageRuntime {
             ;   ldr x2, >previous_loc
             ;   ldr x4, [x2]
             ;   eor x4, x4, x0
-            ;   mov x3, ((MAP_SIZE - 1) as u32) as u64
+            ;   mov x3, ((self.map_size - 1) as u32) as u64
             ;   and x4, x4, x3
             ;   ldr x3, [x1, x4]
             ;   add x3, x3, #1
@@ -107,7 +195,7 @@ impl CoverageRuntime {
             ;   ldp x1, x2, [sp], #0x10
             ;   ret
             ;map_addr:
-            ;.qword &mut self.map as *mut _ as *mut c_void as i64
+            ;.qword self.map_ptr_mut() as *mut c_void as i64
             ;previous_loc:
             ;.qword 0
         );
@@ -143,7 +231,7 @@ impl CoverageRuntime {
             ;   popfq
             ;   ret
             ;map_addr:
-            ;.qword addr_of_mut!(self.map) as i64
+            ;.qword self.map_ptr_mut() as i64
             ;previous_loc:
             ;.qword 0
         );
@@ -154,6 +242,13 @@ impl CoverageRuntime {
     /// Emits coverage mapping into the current basic block.
     #[inline]
     pub fn emit_coverage_mapping(&mut self, address: u64, output: &StalkerOutput) {
+        self.emit_coverage_mapping_for_location(Self::location_hash(address), output);
+    }
+
+    /// Computes the (unmasked) location id of the basic block at the given `address`.
+    #[must_use]
+    #[inline]
+    pub fn location_hash(address: u64) -> u64 {
         let tmp = (address >> 32) + ((address & 0xffffffff) << 32);
         let bitflip = 0x1cad21f72c81017c ^ 0xdb979082e96dd4de;
         let mut h64 = tmp ^ bitflip;
@@ -162,7 +257,19 @@ impl CoverageRuntime {
         h64 ^= (h64 >> 35) + 8;
         h64 *= 0x9FB21C651E98DF25;
         h64 ^= h64 >> 28;
+        h64
+    }
+
+    /// The index in the coverage map of the location id `h64`
+    #[must_use]
+    #[inline]
+    pub fn map_index(&self, h64: u64) -> u64 {
+        h64 & (self.map_size as u64 - 1)
+    }
 
+    /// Emits coverage mapping for an arbitrary location id into the current basic block.
+    /// This is used for blocks that have no stable address, such as jitted code.
+    pub fn emit_coverage_mapping_for_location(&mut self, h64: u64, output: &StalkerOutput) {
         let writer = output.writer();
         #[allow(clippy::cast_possible_wrap)] // gum redzone size is u32, we need an offset as i32.
         let redzone_size = i64::from(frida_gum_sys::GUM_RED_ZONE_SIZE);
@@ -189,7 +296,7 @@ impl CoverageRuntime {
         {
             writer.put_lea_reg_reg_offset(X86Register::Rsp, X86Register::Rsp, -(redzone_size));
             writer.put_push_reg(X86Register::Rdi);
-            writer.put_mov_reg_address(X86Register::Rdi, h64 & (MAP_SIZE as u64 - 1));
+            writer.put_mov_reg_address(X86Register::Rdi, self.map_index(h64));
             writer.put_call_address(self.current_log_impl);
             writer.put_pop_reg(X86Register::Rdi);
             writer.put_lea_reg_reg_offset(X86Register::Rsp, X86Register::Rsp, redzone_size);
@@ -203,7 +310,7 @@ impl CoverageRuntime {
                 -(16 + redzone_size),
                 IndexMode::PreAdjust,
             );
-            writer.put_ldr_reg_u64(Aarch64Register::X0, h64 & (MAP_SIZE as u64 - 1));
+            writer.put_ldr_reg_u64(Aarch64Register::X0, self.map_index(h64));
 
             writer.put_bl_imm(self.current_log_impl);
             writer.put_ldp_reg_reg_reg_offset(
@@ -216,3 +323,41 @@ impl CoverageRuntime {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use libafl::bolts::shmem::{ShMem, ShMemProvider, StdShMemProvider};
+
+    use crate::coverage_rt::{CoverageRuntime, MAP_SIZE};
+
+    #[test]
+    fn test_coverage_shmem_map() {
+        let shmem = StdShMemProvider::new().unwrap().new_shmem(100_000).unwrap();
+        let mut rt = CoverageRuntime::with_shmem(shmem).unwrap();
+        // The map is the largest power of two fitting in the shared memory
+        assert_eq!(rt.map_size(), 65536);
+
+        for address in [0, 0x1000, 0x7fff_1234_5678, u64::MAX] {
+            let index = rt.map_index(CoverageRuntime::location_hash(address));
+            assert!(index < 65536);
+            unsafe {
+                *rt.map_ptr_mut().add(index as usize) = 1;
+            }
+            assert_eq!(rt.shmem().unwrap().as_slice()[index as usize], 1);
+        }
+    }
+
+    #[test]
+    fn test_coverage_owned_map() {
+        let rt = CoverageRuntime::new();
+        assert_eq!(rt.map_size(), MAP_SIZE);
+        assert!(rt.shmem().is_none());
+        assert_eq!(rt.map_index(MAP_SIZE as u64 + 3), 3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_coverage_map_size_power_of_two() {
+        let _rt = CoverageRuntime::with_map_size(1000);
+    }
+}
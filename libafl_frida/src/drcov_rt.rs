@@ -51,6 +51,11 @@ impl FridaRuntime for DrCovRuntime {
 
         Ok(())
     }
+
+    /// Keep the module list of the written traces up to date
+    fn ranges_changed(&mut self, ranges: &RangeMap<usize, (u16, String)>) {
+        self.ranges = ranges.clone();
+    }
 }
 
 impl DrCovRuntime {
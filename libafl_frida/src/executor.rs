@@ -5,6 +5,8 @@ use frida_gum::{
     stalker::{NoneEventSink, Stalker},
     Gum, MemoryRange, NativePointer,
 };
+#[cfg(target_os = "linux")]
+use hashbrown::HashSet;
 use std::{ffi::c_void, marker::PhantomData};
 
 use libafl::{
@@ -16,6 +18,10 @@ use libafl::{
 
 #[cfg(unix)]
 use crate::asan::errors::ASAN_ERRORS;
+#[cfg(unix)]
+use crate::jit_rt::JitRuntime;
+#[cfg(target_os = "linux")]
+use crate::utils::{current_thread_id, enumerate_threads};
 
 #[cfg(windows)]
 use libafl::executors::inprocess::{HasInProcessHandlers, InProcessHandlers};
@@ -33,6 +39,9 @@ where
     /// User provided callback for instrumentation
     helper: &'c mut FridaInstrumentationHelper<'b, RT>,
     followed: bool,
+    /// Threads spawned by the target that are currently followed by the stalker
+    #[cfg(target_os = "linux")]
+    followed_threads: HashSet<usize>,
     _phantom: PhantomData<&'b u8>,
 }
 
@@ -69,6 +78,21 @@ where
         input: &I,
     ) -> Result<ExitKind, Error> {
         self.helper.pre_exec(input)?;
+        #[cfg(unix)]
+        if self.followed {
+            if let Some(rt) = self.helper.runtime_mut::<JitRuntime>() {
+                // Code was rewritten since the last run, drop all stale translations by
+                // following the thread anew, which re-instruments everything on first execution.
+                if !rt.take_invalidated_ranges().is_empty() {
+                    self.stalker.unfollow_me();
+                    self.followed = false;
+                    #[cfg(target_os = "linux")]
+                    for thread_id in self.followed_threads.drain() {
+                        self.stalker.unfollow(thread_id);
+                    }
+                }
+            }
+        }
         if self.helper.stalker_enabled() {
             if self.followed {
                 self.stalker.activate(NativePointer(core::ptr::null_mut()));
@@ -78,6 +102,10 @@ where
                     .follow_me::<NoneEventSink>(self.helper.transformer(), None);
             }
         }
+        #[cfg(target_os = "linux")]
+        if self.helper.stalker_enabled() && self.helper.options().follow_threads() {
+            self.follow_threads();
+        }
         let res = self.base.run_target(fuzzer, state, mgr, input);
         if self.helper.stalker_enabled() {
             self.stalker.deactivate();
@@ -125,6 +153,10 @@ where
         base: InProcessExecutor<'a, H, I, OT, S>,
         helper: &'c mut FridaInstrumentationHelper<'b, RT>,
     ) -> Self {
+        // The stalker hides crashes from the unhandled exception filter, intercept them early.
+        #[cfg(windows)]
+        crate::windows_hooks::initialize(gum);
+
         let mut stalker = Stalker::new(gum);
         // Include the current module (the fuzzer) in stalked ranges. We clone the ranges so that
         // we don't add it to the INSTRUMENTED ranges.
@@ -140,12 +172,31 @@ where
                 break;
             }
         }
-        for range in ranges.gaps(&(0..usize::MAX)) {
-            println!("excluding range: {:x}-{:x}", range.start, range.end);
-            stalker.exclude(&MemoryRange::new(
-                NativePointer(range.start as *mut c_void),
-                range.end - range.start,
-            ));
+        if helper.options().jit_enabled() || helper.options().module_tracking_enabled() {
+            // Code generated or loaded at runtime lives outside of the current ranges, which we
+            // need to follow. Only exclude modules that are neither instrumented nor the fuzzer.
+            for module in frida_gum::Module::enumerate_modules() {
+                if !ranges.contains_key(&module.base_address) {
+                    println!(
+                        "excluding module: {} {:x}-{:x}",
+                        module.name,
+                        module.base_address,
+                        module.base_address + module.size
+                    );
+                    stalker.exclude(&MemoryRange::new(
+                        NativePointer(module.base_address as *mut c_void),
+                        module.size,
+                    ));
+                }
+            }
+        } else {
+            for range in ranges.gaps(&(0..usize::MAX)) {
+                println!("excluding range: {:x}-{:x}", range.start, range.end);
+                stalker.exclude(&MemoryRange::new(
+                    NativePointer(range.start as *mut c_void),
+                    range.end - range.start,
+                ));
+            }
         }
 
         Self {
@@ -153,23 +204,46 @@ where
             stalker,
             helper,
             followed: false,
+            #[cfg(target_os = "linux")]
+            followed_threads: HashSet::new(),
             _phantom: PhantomData,
         }
     }
+
+    /// Follow all threads of the target that are allowed by the options and not followed yet.
+    /// Threads that exited in the meantime are forgotten.
+    #[cfg(target_os = "linux")]
+    fn follow_threads(&mut self) {
+        let current = current_thread_id();
+        let threads = enumerate_threads();
+        self.followed_threads
+            .retain(|tid| threads.iter().any(|(thread_id, _)| thread_id == tid));
+        for (thread_id, name) in threads {
+            if thread_id == current
+                || self.followed_threads.contains(&thread_id)
+                || !self.helper.options().should_follow_thread(&name)
+            {
+                continue;
+            }
+            self.stalker
+                .follow::<NoneEventSink>(thread_id, self.helper.transformer(), None);
+            self.followed_threads.insert(thread_id);
+        }
+    }
 }
 
 #[cfg(windows)]
-impl<'a, 'b, 'c, FH, H, I, OT, S> HasInProcessHandlers
-    for FridaInProcessExecutor<'a, 'b, 'c, FH, H, I, OT, S>
+impl<'a, 'b, 'c, H, I, OT, RT, S> HasInProcessHandlers
+    for FridaInProcessExecutor<'a, 'b, 'c, H, I, OT, RT, S>
 where
     H: FnMut(&I) -> ExitKind,
     I: Input + HasTargetBytes,
     OT: ObserversTuple<I, S>,
-    FH: FridaHelper<'b>,
+    RT: FridaRuntimeTuple,
 {
     /// the timeout handler
     #[inline]
     fn inprocess_handlers(&self) -> &InProcessHandlers {
-        &self.base.handlers()
+        self.base.handlers()
     }
 }
@@ -10,8 +10,12 @@ use crate::cmplog_rt::CmpLogRuntime;
 #[cfg(windows)]
 use crate::FridaOptions;
 #[cfg(unix)]
-use crate::{asan::asan_rt::AsanRuntime, FridaOptions};
-use crate::{coverage_rt::CoverageRuntime, drcov_rt::DrCovRuntime};
+use crate::{asan::asan_rt::AsanRuntime, jit_rt::JitRuntime, FridaOptions};
+use crate::{
+    coverage_rt::CoverageRuntime,
+    drcov_rt::DrCovRuntime,
+    module_tracker::{take_modules_changed, update_ranges, ModuleLoadListener},
+};
 #[cfg(target_arch = "aarch64")]
 use capstone::{
     arch::{self, BuildsCapstone},
@@ -53,6 +57,10 @@ pub trait FridaRuntime: 'static + Debug {
 
     /// Method called after execution
     fn post_exec<I: Input + HasTargetBytes>(&mut self, input: &I) -> Result<(), Error>;
+
+    /// Method called when the instrumented ranges changed, because modules were loaded or
+    /// unloaded at runtime
+    fn ranges_changed(&mut self, _ranges: &RangeMap<usize, (u16, String)>) {}
 }
 
 /// The tuple for Frida Runtime
@@ -70,6 +78,9 @@ pub trait FridaRuntimeTuple: MatchFirstType + Debug {
 
     /// Method called after execution
     fn post_exec_all<I: Input + HasTargetBytes>(&mut self, input: &I) -> Result<(), Error>;
+
+    /// Method called when the instrumented ranges changed
+    fn ranges_changed_all(&mut self, ranges: &RangeMap<usize, (u16, String)>);
 }
 
 impl FridaRuntimeTuple for () {
@@ -86,6 +97,7 @@ impl FridaRuntimeTuple for () {
     fn post_exec_all<I: Input + HasTargetBytes>(&mut self, _input: &I) -> Result<(), Error> {
         Ok(())
     }
+    fn ranges_changed_all(&mut self, _ranges: &RangeMap<usize, (u16, String)>) {}
 }
 
 impl<Head, Tail> FridaRuntimeTuple for (Head, Tail)
@@ -112,6 +124,11 @@ where
         self.0.post_exec(input)?;
         self.1.post_exec_all(input)
     }
+
+    fn ranges_changed_all(&mut self, ranges: &RangeMap<usize, (u16, String)>) {
+        self.0.ranges_changed(ranges);
+        self.1.ranges_changed_all(ranges);
+    }
 }
 
 /// An helper that feeds `FridaInProcessExecutor` with edge-coverage instrumentation
@@ -122,6 +139,9 @@ pub struct FridaInstrumentationHelper<'a, RT> {
     capstone: Capstone,
     ranges: RangeMap<usize, (u16, String)>,
     module_map: ModuleMap,
+    modules_to_instrument: &'a [&'a str],
+    /// Keeps track of modules loaded at runtime, if enabled
+    module_load_listener: Option<Box<ModuleLoadListener>>,
     options: &'a FridaOptions,
     runtimes: RT,
 }
@@ -173,7 +193,7 @@ where
         gum: &'a Gum,
         options: &'a FridaOptions,
         _harness_module_name: &str,
-        modules_to_instrument: &'a [&str],
+        modules_to_instrument: &'a [&'a str],
         runtimes: RT,
     ) -> Self {
         // workaround frida's frida-gum-allocate-near bug:
@@ -219,6 +239,8 @@ where
                 .expect("Failed to create Capstone object"),
             ranges: RangeMap::new(),
             module_map: ModuleMap::new_from_names(modules_to_instrument),
+            modules_to_instrument,
+            module_load_listener: None,
             options,
             runtimes,
         };
@@ -243,7 +265,15 @@ where
                 }
             }
 
+            if helper.options().module_tracking_enabled() {
+                helper.module_load_listener = Some(ModuleLoadListener::install(gum));
+            }
+
             let transformer = Transformer::from_callback(gum, |basic_block, output| {
+                // Make sure freshly loaded modules are instrumented before they first execute
+                if helper.module_load_listener.is_some() {
+                    helper.update_module_ranges();
+                }
                 let mut first = true;
                 for instruction in basic_block {
                     let instr = instruction.instr();
@@ -343,6 +373,14 @@ where
                                 address as usize,
                             );
                         }
+                    } else if first {
+                        #[cfg(unix)]
+                        if let Some(location) = helper.jit_location(address) {
+                            first = false;
+                            if let Some(rt) = helper.runtime_mut::<CoverageRuntime>() {
+                                rt.emit_coverage_mapping_for_location(location, &output);
+                            }
+                        }
                     }
                     instruction.keep();
                 }
@@ -355,6 +393,33 @@ where
         helper
     }
 
+    /// Returns the coverage location id for a block at `address`, if it lies in jitted code
+    #[cfg(unix)]
+    fn jit_location(&self, address: u64) -> Option<u64> {
+        let rt = self.runtime::<JitRuntime>()?;
+        if !rt.is_jit_address(address as usize) {
+            return None;
+        }
+        if rt.separate_hashing() {
+            Some(rt.block_hash(address as usize))
+        } else {
+            Some(CoverageRuntime::location_hash(address))
+        }
+    }
+
+    /// Update the instrumented ranges, if modules were loaded or unloaded since the last call.
+    /// Newly loaded modules that should be instrumented are added, unloaded modules are removed.
+    pub fn update_module_ranges(&mut self) {
+        if !take_modules_changed() {
+            return;
+        }
+        let loaded = frida_gum::Module::enumerate_modules();
+        let changed = update_ranges(&mut self.ranges, &loaded, self.modules_to_instrument);
+        if changed {
+            self.runtimes.ranges_changed_all(&self.ranges);
+        }
+    }
+
     /// Return the runtime
     pub fn runtime<R>(&self) -> Option<&R>
     where
@@ -410,6 +475,12 @@ where
         }
     }
 
+    /// Size of the coverage map
+    pub fn map_size(&self) -> Option<usize> {
+        self.runtime::<CoverageRuntime>()
+            .map(CoverageRuntime::map_size)
+    }
+
     /// Ranges
     pub fn ranges(&self) -> &RangeMap<usize, (u16, String)> {
         &self.ranges
@@ -0,0 +1,255 @@
+//! Support for targets that generate code at runtime, such as `JavaScript` engines or the `JVM`.
+//!
+//! The [`JitRuntime`] tracks executable memory that is created (or re-protected) after startup,
+//! so that freshly emitted code gets instrumented, and stale translations of rewritten code are
+//! dropped before the next execution.
+use ahash::AHasher;
+use core::{
+    fmt::{self, Debug, Formatter},
+    hash::Hasher,
+    ops::Range,
+};
+use frida_gum::{
+    interceptor::{Interceptor, InvocationContext, InvocationListener},
+    Gum, Module,
+};
+use libafl::{
+    inputs::{HasTargetBytes, Input},
+    Error,
+};
+use rangemap::RangeMap;
+
+use crate::helper::FridaRuntime;
+
+/// `PROT_EXEC`, as passed to `mmap` and `mprotect`
+const PROT_EXEC: usize = libc::PROT_EXEC as usize;
+
+/// The maximum number of bytes of a jitted block that are used to compute its coverage hash
+const JIT_BLOCK_HASH_BYTES: usize = 32;
+
+/// Listens to the memory management functions of the target, to find out about new code.
+struct JitMemoryListener {
+    /// The runtime to notify. Set in [`JitRuntime::init`], after which the runtime must not move.
+    runtime: *mut JitRuntime,
+    /// The arguments of the current call, recorded in `on_enter`
+    pending: Option<(usize, usize, usize)>,
+    /// The kind of function this listener is attached to
+    kind: JitMemoryFunction,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum JitMemoryFunction {
+    Mmap,
+    Mprotect,
+    Munmap,
+}
+
+impl InvocationListener for JitMemoryListener {
+    fn on_enter(&mut self, context: InvocationContext) {
+        self.pending = Some(match self.kind {
+            // mmap(addr, length, prot, ...), the address is only known on return
+            JitMemoryFunction::Mmap => (0, context.arg(1), context.arg(2)),
+            // mprotect(addr, length, prot)
+            JitMemoryFunction::Mprotect => (context.arg(0), context.arg(1), context.arg(2)),
+            // munmap(addr, length)
+            JitMemoryFunction::Munmap => (context.arg(0), context.arg(1), 0),
+        });
+    }
+
+    fn on_leave(&mut self, context: InvocationContext) {
+        let (addr, len, prot) = match self.pending.take() {
+            Some(pending) => pending,
+            None => return,
+        };
+        let runtime = unsafe { &mut *self.runtime };
+        match self.kind {
+            JitMemoryFunction::Mmap => {
+                let ret = context.return_value();
+                // MAP_FAILED is (void *)-1
+                if ret != usize::MAX && prot & PROT_EXEC != 0 {
+                    runtime.add_jit_range(ret..ret + len);
+                }
+            }
+            JitMemoryFunction::Mprotect => {
+                if context.return_value() == 0 {
+                    if prot & PROT_EXEC == 0 {
+                        // The code is (probably) about to be rewritten
+                        runtime.invalidate_range(addr..addr + len);
+                    } else {
+                        runtime.add_jit_range(addr..addr + len);
+                    }
+                }
+            }
+            JitMemoryFunction::Munmap => {
+                if context.return_value() == 0 {
+                    runtime.remove_jit_range(addr..addr + len);
+                }
+            }
+        }
+    }
+}
+
+/// Tracks code regions generated at runtime and makes sure they are (re-)instrumented.
+pub struct JitRuntime {
+    /// Executable regions created at runtime, mapped to the number of times they have been
+    /// (re-)generated
+    jit_ranges: RangeMap<usize, u32>,
+    /// Regions whose instrumented copies are stale and need to be dropped
+    invalidated_ranges: Vec<Range<usize>>,
+    /// Static ranges of the instrumented modules, these are never considered to be jitted
+    module_ranges: RangeMap<usize, (u16, String)>,
+    /// If set, coverage of jitted blocks is hashed based on the block's content, not its address
+    separate_hashing: bool,
+    listeners: Vec<Box<JitMemoryListener>>,
+}
+
+impl Debug for JitRuntime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JitRuntime")
+            .field("jit_ranges", &self.jit_ranges)
+            .field("invalidated_ranges", &self.invalidated_ranges)
+            .field("separate_hashing", &self.separate_hashing)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FridaRuntime for JitRuntime {
+    /// Initialize the runtime, attaching to the target's memory management functions.
+    /// Take care not to move the runtime instance after this function has been called.
+    fn init(
+        &mut self,
+        gum: &Gum,
+        ranges: &RangeMap<usize, (u16, String)>,
+        _modules_to_instrument: &[&str],
+    ) {
+        self.module_ranges = ranges.clone();
+
+        let mut interceptor = Interceptor::obtain(gum);
+        for (name, kind) in [
+            ("mmap", JitMemoryFunction::Mmap),
+            ("mprotect", JitMemoryFunction::Mprotect),
+            ("munmap", JitMemoryFunction::Munmap),
+        ] {
+            let mut listener = Box::new(JitMemoryListener {
+                runtime: self as *mut _,
+                pending: None,
+                kind,
+            });
+            if let Some(function) = Module::find_export_by_name(None, name) {
+                interceptor.attach(function, listener.as_mut());
+            }
+            self.listeners.push(listener);
+        }
+    }
+
+    fn pre_exec<I: Input + HasTargetBytes>(&mut self, _input: &I) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn post_exec<I: Input + HasTargetBytes>(&mut self, _input: &I) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn ranges_changed(&mut self, ranges: &RangeMap<usize, (u16, String)>) {
+        self.module_ranges = ranges.clone();
+    }
+}
+
+impl JitRuntime {
+    /// Creates a new [`JitRuntime`].
+    /// If `separate_hashing` is set, coverage for jitted code is computed from the content of each
+    /// block, which stays stable even if the jit places the code at a different address each run.
+    #[must_use]
+    pub fn new(separate_hashing: bool) -> Self {
+        Self {
+            jit_ranges: RangeMap::new(),
+            invalidated_ranges: vec![],
+            module_ranges: RangeMap::new(),
+            separate_hashing,
+            listeners: vec![],
+        }
+    }
+
+    /// Registers a region of executable code that was generated at runtime
+    pub fn add_jit_range(&mut self, range: Range<usize>) {
+        if range.is_empty() || self.module_ranges.contains_key(&range.start) {
+            return;
+        }
+        let generation = self
+            .jit_ranges
+            .get(&range.start)
+            .map_or(0, |generation| generation + 1);
+        // Newly executable memory at a known location means the code was rewritten
+        if generation > 0 {
+            self.invalidated_ranges.push(range.clone());
+        }
+        self.jit_ranges.insert(range, generation);
+    }
+
+    /// Marks a region as stale, so that it will be re-instrumented on its next execution
+    pub fn invalidate_range(&mut self, range: Range<usize>) {
+        if !range.is_empty() && self.overlaps_jit_ranges(&range) {
+            self.invalidated_ranges.push(range);
+        }
+    }
+
+    /// Forgets about a region, for example after the target unmapped it
+    pub fn remove_jit_range(&mut self, range: Range<usize>) {
+        if !range.is_empty() && self.overlaps_jit_ranges(&range) {
+            self.invalidated_ranges.push(range.clone());
+            self.jit_ranges.remove(range);
+        }
+    }
+
+    /// Returns `true` if any part of `range` is known jitted code
+    fn overlaps_jit_ranges(&self, range: &Range<usize>) -> bool {
+        self.jit_ranges.gaps(range).next().as_ref() != Some(range)
+    }
+
+    /// Returns `true` if the given address lies in code that was generated at runtime
+    #[must_use]
+    #[inline]
+    pub fn is_jit_address(&self, address: usize) -> bool {
+        self.jit_ranges.contains_key(&address)
+    }
+
+    /// The regions of code that were generated at runtime
+    #[must_use]
+    pub fn jit_ranges(&self) -> &RangeMap<usize, u32> {
+        &self.jit_ranges
+    }
+
+    /// Returns `true` if coverage for jitted code is hashed by content
+    #[must_use]
+    #[inline]
+    pub fn separate_hashing(&self) -> bool {
+        self.separate_hashing
+    }
+
+    /// Takes the list of regions invalidated since the last call.
+    /// If this is not empty, the stalker has to drop its translated code before the next run.
+    pub fn take_invalidated_ranges(&mut self) -> Vec<Range<usize>> {
+        core::mem::take(&mut self.invalidated_ranges)
+    }
+
+    /// Computes a location id for a jitted block, based on (up to) its first bytes.
+    /// Blocks with the same code get the same id, independent of where the jit placed them.
+    #[must_use]
+    pub fn block_hash(&self, address: usize) -> u64 {
+        let end = self
+            .jit_ranges
+            .get_key_value(&address)
+            .map_or(address, |(range, _)| range.end);
+        let len = (end - address).min(JIT_BLOCK_HASH_BYTES);
+        let bytes = unsafe { core::slice::from_raw_parts(address as *const u8, len) };
+        let mut hasher = AHasher::new_with_keys(0, 0);
+        hasher.write(bytes);
+        hasher.finish()
+    }
+}
+
+impl Default for JitRuntime {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
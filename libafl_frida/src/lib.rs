@@ -77,12 +77,20 @@ pub mod helper;
 
 pub mod drcov_rt;
 
+pub mod module_tracker;
+
+#[cfg(unix)]
+pub mod jit_rt;
+
 /// The frida executor
 pub mod executor;
 
 /// Utilities
 pub mod utils;
 
+#[cfg(windows)]
+pub mod windows_hooks;
+
 // for parsing asan and cmplog cores
 use libafl::bolts::os::{CoreId, Cores};
 
@@ -104,6 +112,11 @@ pub struct FridaOptions {
     enable_drcov: bool,
     instrument_suppress_locations: Option<Vec<(String, usize)>>,
     enable_cmplog: bool,
+    enable_jit: bool,
+    jit_separate_hashing: bool,
+    follow_threads: bool,
+    thread_allowlist: Option<Vec<String>>,
+    enable_module_tracking: bool,
 }
 
 impl FridaOptions {
@@ -197,6 +210,32 @@ impl FridaOptions {
                     "cmplog-cores" => {
                         cmplog_cores = Cores::from_cmdline(value).ok();
                     }
+                    "jit" => {
+                        options.enable_jit = value.parse().unwrap();
+                        #[cfg(windows)]
+                        assert!(
+                            !options.enable_jit,
+                            "jit support is not currently supported on windows"
+                        );
+                    }
+                    "jit-separate-hashing" => {
+                        options.jit_separate_hashing = value.parse().unwrap();
+                    }
+                    "follow-threads" => {
+                        options.follow_threads = value.parse().unwrap();
+                        #[cfg(not(target_os = "linux"))]
+                        assert!(
+                            !options.follow_threads,
+                            "Following threads is not currently supported on targets other than linux"
+                        );
+                    }
+                    "track-modules" => {
+                        options.enable_module_tracking = value.parse().unwrap();
+                    }
+                    "thread-allowlist" => {
+                        options.thread_allowlist =
+                            Some(value.split(',').map(ToString::to_string).collect());
+                    }
                     _ => {
                         panic!("unknown FRIDA option: '{}'", option);
                     }
@@ -259,6 +298,52 @@ impl FridaOptions {
         self.enable_cmplog
     }
 
+    /// Is support for code generated at runtime enabled?
+    #[must_use]
+    #[inline]
+    pub fn jit_enabled(&self) -> bool {
+        self.enable_jit
+    }
+
+    /// Should coverage of jitted code be hashed by content, instead of by address?
+    #[must_use]
+    #[inline]
+    pub fn jit_separate_hashing(&self) -> bool {
+        self.jit_separate_hashing
+    }
+
+    /// Should modules loaded at runtime be tracked (and instrumented, if requested)?
+    #[must_use]
+    #[inline]
+    pub fn module_tracking_enabled(&self) -> bool {
+        self.enable_module_tracking
+    }
+
+    /// Should threads spawned by the target be followed by the stalker, too?
+    /// Coverage of all followed threads is merged into the same coverage map.
+    #[must_use]
+    #[inline]
+    pub fn follow_threads(&self) -> bool {
+        self.follow_threads
+    }
+
+    /// The names of the threads which may be followed, if [`Self::follow_threads`] is set.
+    /// If this is `None`, all threads are followed.
+    #[must_use]
+    #[inline]
+    pub fn thread_allowlist(&self) -> Option<&[String]> {
+        self.thread_allowlist.as_deref()
+    }
+
+    /// Should the thread with the given name be followed by the stalker?
+    #[must_use]
+    pub fn should_follow_thread(&self, name: &str) -> bool {
+        self.follow_threads
+            && self.thread_allowlist.as_ref().map_or(true, |allowlist| {
+                allowlist.iter().any(|allowed| allowed == name)
+            })
+    }
+
     /// Should ASAN detect leaks
     #[must_use]
     #[inline]
@@ -330,6 +415,11 @@ impl Default for FridaOptions {
             enable_drcov: false,
             instrument_suppress_locations: None,
             enable_cmplog: false,
+            enable_jit: false,
+            jit_separate_hashing: false,
+            follow_threads: false,
+            thread_allowlist: None,
+            enable_module_tracking: false,
         }
     }
 }
@@ -0,0 +1,168 @@
+//! Tracks modules that are loaded (or unloaded) while the target is running, such as plugins.
+//!
+//! The loader functions of the platform are intercepted; whenever they succeed, the set of loaded
+//! modules is marked as changed, so that the [`crate::helper::FridaInstrumentationHelper`] can
+//! update its ranges before the new code gets executed for the first time.
+use core::sync::atomic::{AtomicBool, Ordering};
+use frida_gum::{
+    interceptor::{Interceptor, InvocationContext, InvocationListener},
+    Gum, Module, ModuleDetailsOwned,
+};
+use rangemap::RangeMap;
+
+/// Set whenever a module was loaded or unloaded, and not yet handled
+static MODULES_CHANGED: AtomicBool = AtomicBool::new(false);
+
+/// The loader functions to intercept, as `(module, function)`
+#[cfg(unix)]
+const LOADER_FUNCTIONS: &[(Option<&str>, &str)] = &[(None, "dlopen"), (None, "dlclose")];
+/// The loader functions to intercept, as `(module, function)`.
+/// All of `LoadLibrary*` and `FreeLibrary` end up in these.
+#[cfg(windows)]
+const LOADER_FUNCTIONS: &[(Option<&str>, &str)] = &[
+    (Some("ntdll.dll"), "LdrLoadDll"),
+    (Some("ntdll.dll"), "LdrUnloadDll"),
+];
+
+/// Listens to the loader functions of the target
+#[derive(Debug, Default)]
+pub struct ModuleLoadListener {}
+
+impl InvocationListener for ModuleLoadListener {
+    fn on_enter(&mut self, _context: InvocationContext) {}
+
+    fn on_leave(&mut self, _context: InvocationContext) {
+        // We don't bother to check for success here, spurious updates are cheap enough.
+        MODULES_CHANGED.store(true, Ordering::Release);
+    }
+}
+
+impl ModuleLoadListener {
+    /// Attach a new listener to all loader functions.
+    /// The returned listener needs to be kept alive for as long as the target runs.
+    #[must_use]
+    pub fn install(gum: &Gum) -> Box<Self> {
+        let mut listener = Box::new(Self::default());
+        let mut interceptor = Interceptor::obtain(gum);
+        for (module_name, function_name) in LOADER_FUNCTIONS {
+            if let Some(function) = Module::find_export_by_name(*module_name, function_name) {
+                interceptor.attach(function, listener.as_mut());
+            }
+        }
+        listener
+    }
+}
+
+/// Returns `true` if modules were loaded or unloaded since the last call
+#[must_use]
+pub fn take_modules_changed() -> bool {
+    MODULES_CHANGED.swap(false, Ordering::AcqRel)
+}
+
+/// Updates the instrumented `ranges` to the `loaded` modules: the ranges of unloaded modules are
+/// removed, and the loaded modules whose name or path is in `modules_to_instrument` are added, with
+/// new ids. Returns `true` if the ranges changed.
+pub fn update_ranges(
+    ranges: &mut RangeMap<usize, (u16, String)>,
+    loaded: &[ModuleDetailsOwned],
+    modules_to_instrument: &[&str],
+) -> bool {
+    let mut changed = false;
+
+    let unloaded: Vec<_> = ranges
+        .iter()
+        .filter(|(range, (_, path))| {
+            !loaded.iter().any(|module| {
+                module.path == *path
+                    && module.base_address <= range.start
+                    && range.start < module.base_address + module.size
+            })
+        })
+        .map(|(range, _)| range.clone())
+        .collect();
+    for range in unloaded {
+        ranges.remove(range);
+        changed = true;
+    }
+
+    let mut next_id = ranges.iter().map(|(_, (id, _))| id + 1).max().unwrap_or(0);
+    for module in loaded {
+        if ranges.contains_key(&module.base_address)
+            || !modules_to_instrument
+                .iter()
+                .any(|name| *name == module.name || *name == module.path)
+        {
+            continue;
+        }
+        ranges.insert(
+            module.base_address..(module.base_address + module.size),
+            (next_id, module.path.clone()),
+        );
+        next_id += 1;
+        changed = true;
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use frida_gum::ModuleDetailsOwned;
+    use rangemap::RangeMap;
+
+    use crate::module_tracker::update_ranges;
+
+    fn module(name: &str, base_address: usize, size: usize) -> ModuleDetailsOwned {
+        ModuleDetailsOwned {
+            name: name.to_string(),
+            path: format!("/lib/{}", name),
+            base_address,
+            size,
+        }
+    }
+
+    #[test]
+    fn test_update_ranges() {
+        let mut ranges = RangeMap::new();
+        let loaded = [
+            module("target", 0x1000, 0x1000),
+            module("libc.so", 0x10000, 0x8000),
+            module("plugin.so", 0x40000, 0x2000),
+        ];
+        assert!(update_ranges(
+            &mut ranges,
+            &loaded[..2],
+            &["target", "/lib/plugin.so"]
+        ));
+        assert_eq!(ranges.get(&0x1fff), Some(&(0, "/lib/target".to_string())));
+        assert_eq!(ranges.get(&0x10000), None);
+        assert!(!update_ranges(
+            &mut ranges,
+            &loaded[..2],
+            &["target", "/lib/plugin.so"]
+        ));
+
+        // A plugin is loaded, by its path, with a new id
+        assert!(update_ranges(
+            &mut ranges,
+            &loaded,
+            &["target", "/lib/plugin.so"]
+        ));
+        assert_eq!(
+            ranges.get(&0x40010),
+            Some(&(1, "/lib/plugin.so".to_string()))
+        );
+
+        // The target is unloaded, the plugin keeps its id
+        assert!(update_ranges(
+            &mut ranges,
+            &loaded[1..],
+            &["target", "/lib/plugin.so"]
+        ));
+        assert_eq!(ranges.get(&0x1000), None);
+        assert_eq!(
+            ranges.get(&0x41fff),
+            Some(&(1, "/lib/plugin.so".to_string()))
+        );
+        assert_eq!(ranges.get(&0x42000), None);
+    }
+}
@@ -134,3 +134,28 @@ pub fn writer_register(reg: capstone::RegId) -> X86Register {
         _ => X86Register::None, // Ignore Xax..Xip
     }
 }
+
+/// The id of the calling thread, as used by the stalker
+#[cfg(target_os = "linux")]
+#[must_use]
+pub fn current_thread_id() -> usize {
+    unsafe { libc::syscall(libc::SYS_gettid) as usize }
+}
+
+/// Enumerate all threads of the current process, as `(thread_id, name)` tuples
+#[cfg(target_os = "linux")]
+#[must_use]
+pub fn enumerate_threads() -> Vec<(usize, String)> {
+    let tasks = match std::fs::read_dir("/proc/self/task") {
+        Ok(tasks) => tasks,
+        Err(_) => return vec![],
+    };
+    tasks
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let tid = entry.file_name().to_str()?.parse().ok()?;
+            let name = std::fs::read_to_string(entry.path().join("comm")).unwrap_or_default();
+            Some((tid, name.trim_end().to_string()))
+        })
+        .collect()
+}
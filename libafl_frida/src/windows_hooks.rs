@@ -0,0 +1,330 @@
+//! Crash interception for the frida executor on windows.
+//!
+//! With the stalker enabled, the target runs on instrumented copies of its code, so the usual
+//! unhandled exception filter is never reached. We install a vectored exception handler instead,
+//! which captures the crashing context before handing over to `LibAFL`'s crash handler, and route
+//! heap corruption reports (which would otherwise terminate the process silently) through it.
+//! Add a [`CrashContextFeedback`] to the objective to store the context of each crash as metadata
+//! of its testcase.
+use core::{
+    ffi::c_void,
+    fmt::{self, Display, Formatter},
+    ptr,
+};
+use frida_gum::{interceptor::Interceptor, Gum, Module, NativePointer};
+use libafl::{
+    bolts::{
+        os::windows_exceptions::{
+            AddVectoredExceptionHandler, ExceptionCode, EXCEPTION_POINTERS, STATUS_HEAP_CORRUPTION,
+        },
+        tuples::Named,
+    },
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    observers::ObserversTuple,
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error, SerdeAny,
+};
+use serde::{Deserialize, Serialize};
+
+/// Let the next handler (`LibAFL`'s crash handler) process the exception
+const EXCEPTION_CONTINUE_SEARCH: i32 = 0;
+/// The exception can not be continued
+const EXCEPTION_NONCONTINUABLE: u32 = 1;
+
+extern "system" {
+    fn RaiseException(code: u32, flags: u32, number_of_arguments: u32, arguments: *const usize);
+}
+
+/// The context of the last crash in the target, stored as testcase metadata by the
+/// [`CrashContextFeedback`]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, SerdeAny)]
+#[allow(missing_docs)]
+pub struct CrashContext {
+    /// The raw exception code
+    pub code: u32,
+    /// The address the exception was raised at
+    pub address: usize,
+    pub rip: u64,
+    pub rsp: u64,
+    pub rbp: u64,
+    pub rax: u64,
+    pub rbx: u64,
+    pub rcx: u64,
+    pub rdx: u64,
+    pub rsi: u64,
+    pub rdi: u64,
+    pub r8: u64,
+    pub r9: u64,
+    pub r10: u64,
+    pub r11: u64,
+    pub r12: u64,
+    pub r13: u64,
+    pub r14: u64,
+    pub r15: u64,
+}
+
+impl Display for CrashContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let code = ExceptionCode::try_from(self.code as i32).unwrap_or(ExceptionCode::Other);
+        writeln!(
+            f,
+            "{} (0x{:08x}) at 0x{:016x}",
+            code, self.code, self.address
+        )?;
+        writeln!(
+            f,
+            "rip: 0x{:016x} rsp: 0x{:016x} rbp: 0x{:016x}",
+            self.rip, self.rsp, self.rbp
+        )?;
+        writeln!(
+            f,
+            "rax: 0x{:016x} rbx: 0x{:016x} rcx: 0x{:016x} rdx: 0x{:016x}",
+            self.rax, self.rbx, self.rcx, self.rdx
+        )?;
+        writeln!(
+            f,
+            "rsi: 0x{:016x} rdi: 0x{:016x} r8:  0x{:016x} r9:  0x{:016x}",
+            self.rsi, self.rdi, self.r8, self.r9
+        )?;
+        writeln!(
+            f,
+            "r10: 0x{:016x} r11: 0x{:016x} r12: 0x{:016x} r13: 0x{:016x}",
+            self.r10, self.r11, self.r12, self.r13
+        )?;
+        write!(f, "r14: 0x{:016x} r15: 0x{:016x}", self.r14, self.r15)
+    }
+}
+
+/// The context captured for the last crash, if any
+static mut CRASH_CONTEXT: Option<CrashContext> = None;
+
+/// Set once the hooks are in place
+static mut INITIALIZED: bool = false;
+
+/// Returns the context of the last crash in the target, if any
+#[must_use]
+pub fn last_crash_context() -> Option<CrashContext> {
+    unsafe { CRASH_CONTEXT }
+}
+
+/// Takes the context of the last crash in the target, if any, so that it is not reported again
+/// for a later crash that did not go through the hooks
+#[must_use]
+pub fn take_crash_context() -> Option<CrashContext> {
+    unsafe { CRASH_CONTEXT.take() }
+}
+
+/// A [`Feedback`] that never deems an input interesting, but stores the [`CrashContext`] of a
+/// crashing input as metadata of its testcase. Combine it with the objective feedback, e.g.
+/// `feedback_or!(CrashFeedback::new(), CrashContextFeedback::new())`.
+#[derive(Debug, Default)]
+pub struct CrashContextFeedback {
+    crash_context: Option<CrashContext>,
+}
+
+impl<I, S> Feedback<I, S> for CrashContextFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        _observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let crash_context = take_crash_context();
+        if *exit_kind == ExitKind::Crash {
+            self.crash_context = crash_context;
+        }
+        Ok(false)
+    }
+
+    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if let Some(crash_context) = self.crash_context.take() {
+            testcase.add_metadata(crash_context);
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.crash_context = None;
+        Ok(())
+    }
+}
+
+impl Named for CrashContextFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        "CrashContext"
+    }
+}
+
+impl CrashContextFeedback {
+    /// Create a new [`CrashContextFeedback`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            crash_context: None,
+        }
+    }
+}
+
+/// Vectored exception handler that runs before `LibAFL`'s handler and records the crash context.
+unsafe extern "system" fn capture_crash_context(
+    exception_pointers: *mut EXCEPTION_POINTERS,
+) -> i32 {
+    if let Some(pointers) = exception_pointers.as_ref() {
+        if let (Some(record), Some(context)) = (
+            pointers.ExceptionRecord.as_ref(),
+            pointers.ContextRecord.as_ref(),
+        ) {
+            let crash_context = CrashContext {
+                code: record.ExceptionCode.0 as u32,
+                address: record.ExceptionAddress as usize,
+                rip: context.Rip,
+                rsp: context.Rsp,
+                rbp: context.Rbp,
+                rax: context.Rax,
+                rbx: context.Rbx,
+                rcx: context.Rcx,
+                rdx: context.Rdx,
+                rsi: context.Rsi,
+                rdi: context.Rdi,
+                r8: context.R8,
+                r9: context.R9,
+                r10: context.R10,
+                r11: context.R11,
+                r12: context.R12,
+                r13: context.R13,
+                r14: context.R14,
+                r15: context.R15,
+            };
+            if libafl::bolts::os::windows_exceptions::CRASH_EXCEPTIONS.contains(
+                &ExceptionCode::try_from(crash_context.code as i32).unwrap_or(ExceptionCode::Other),
+            ) {
+                eprintln!("Crash context:\n{}", crash_context);
+            }
+            CRASH_CONTEXT = Some(crash_context);
+        }
+    }
+    EXCEPTION_CONTINUE_SEARCH
+}
+
+/// Replacement for `ntdll!RtlReportCriticalFailure`, which is used to report heap corruption.
+/// Instead of terminating the process, raise a proper exception our handlers can deal with.
+unsafe extern "system" fn replacement_report_critical_failure(
+    _status: i32,
+    _parameter: *mut c_void,
+) {
+    RaiseException(
+        STATUS_HEAP_CORRUPTION as u32,
+        EXCEPTION_NONCONTINUABLE,
+        0,
+        ptr::null(),
+    );
+}
+
+/// Install the crash interception hooks. Calling this more than once has no effect.
+pub fn initialize(gum: &Gum) {
+    unsafe {
+        if INITIALIZED {
+            return;
+        }
+        INITIALIZED = true;
+
+        if let Some(report_critical_failure) =
+            Module::find_export_by_name(Some("ntdll.dll"), "RtlReportCriticalFailure")
+        {
+            let mut interceptor = Interceptor::obtain(gum);
+            interceptor
+                .replace(
+                    report_critical_failure,
+                    NativePointer(replacement_report_critical_failure as *mut c_void),
+                    NativePointer(ptr::null_mut()),
+                )
+                .ok();
+        }
+
+        // `LibAFL`'s handler is registered first in the list, too. Since handlers that are added
+        // later with `first` set end up at the front, ours gets to see the exception first.
+        AddVectoredExceptionHandler(
+            1,
+            Some(core::mem::transmute(capture_crash_context as *const c_void)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::Feedback,
+        inputs::BytesInput,
+        state::{HasMetadata, StdState},
+    };
+
+    use super::{CrashContext, CrashContextFeedback, CRASH_CONTEXT};
+
+    #[test]
+    fn test_crash_context_feedback() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            tuple_list!(),
+        );
+        let mut feedback = CrashContextFeedback::new();
+        let input = BytesInput::new(vec![0; 4]);
+        // As captured by the vectored exception handler
+        unsafe {
+            CRASH_CONTEXT = Some(CrashContext {
+                code: 0xc000_0005,
+                address: 0x1234,
+                rip: 0x1234,
+                ..CrashContext::default()
+            });
+        }
+        assert!(!feedback
+            .is_interesting(
+                &mut state,
+                &mut NopEventManager {},
+                &input,
+                &(),
+                &ExitKind::Crash
+            )
+            .unwrap());
+        let mut testcase = Testcase::new(input.clone());
+        feedback.append_metadata(&mut state, &mut testcase).unwrap();
+        let crash_context = testcase.metadata().get::<CrashContext>().unwrap();
+        assert_eq!(crash_context.code, 0xc000_0005);
+        assert_eq!(crash_context.rip, 0x1234);
+
+        // Taken, so not reported again
+        assert!(!feedback
+            .is_interesting(
+                &mut state,
+                &mut NopEventManager {},
+                &input,
+                &(),
+                &ExitKind::Crash
+            )
+            .unwrap());
+        let mut testcase = Testcase::new(input);
+        feedback.append_metadata(&mut state, &mut testcase).unwrap();
+        assert!(testcase.metadata().get::<CrashContext>().is_none());
+    }
+}
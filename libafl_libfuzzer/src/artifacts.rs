@@ -0,0 +1,215 @@
+//! The artifacts, i.e. the crashing and the timeouting inputs, written as `libFuzzer` does, to
+//! `<artifact_prefix><kind>-<hash>`, e.g. `./crash-<hash>` by default, `out/crash-<hash>` for
+//! `-artifact_prefix=out/` and `fuzz-crash-<hash>` for `-artifact_prefix=fuzz-`.
+
+use core::cell::RefCell;
+use std::{fs, path::PathBuf};
+
+use libafl::{
+    bolts::tuples::Named,
+    corpus::{Corpus, InMemoryCorpus, Testcase},
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::{BytesInput, HasBytesVec, Input},
+    observers::ObserversTuple,
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error,
+};
+use serde::{Deserialize, Serialize};
+
+/// How the run finding an artifact ended
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArtifactKindMetadata {
+    /// The exit of the run
+    pub exit_kind: ExitKind,
+}
+
+libafl::impl_serdeany!(ArtifactKindMetadata);
+
+/// The path of the artifact of `input`, found by a run ending with `exit_kind`
+#[must_use]
+pub fn artifact_path(prefix: &str, exit_kind: ExitKind, input: &BytesInput) -> PathBuf {
+    let kind = match exit_kind {
+        ExitKind::Timeout => "timeout",
+        ExitKind::Oom => "oom",
+        _ => "crash",
+    };
+    PathBuf::from(format!("{}{}-{}", prefix, kind, input.generate_name(0)))
+}
+
+/// A feedback keeping how the runs of the objectives ended, in their [`ArtifactKindMetadata`], for
+/// the [`ArtifactCorpus`] to name them. It never finds a run interesting.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ArtifactKindFeedback {
+    exit_kind: Option<ExitKind>,
+}
+
+impl ArtifactKindFeedback {
+    /// Creates a new [`ArtifactKindFeedback`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<S> Feedback<BytesInput, S> for ArtifactKindFeedback
+where
+    S: HasClientPerfMonitor,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &BytesInput,
+        _observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<BytesInput>,
+        OT: ObserversTuple<BytesInput, S>,
+    {
+        self.exit_kind = Some(*exit_kind);
+        Ok(false)
+    }
+
+    fn append_metadata(
+        &mut self,
+        _state: &mut S,
+        testcase: &mut Testcase<BytesInput>,
+    ) -> Result<(), Error> {
+        if let Some(exit_kind) = self.exit_kind.take() {
+            testcase.add_metadata(ArtifactKindMetadata { exit_kind });
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &BytesInput) -> Result<(), Error> {
+        self.exit_kind = None;
+        Ok(())
+    }
+}
+
+impl Named for ArtifactKindFeedback {
+    fn name(&self) -> &str {
+        "ArtifactKindFeedback"
+    }
+}
+
+/// The corpus of the objectives, writing each of them as an artifact when added
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ArtifactCorpus {
+    prefix: String,
+    inner: InMemoryCorpus<BytesInput>,
+}
+
+impl ArtifactCorpus {
+    /// Creates a new [`ArtifactCorpus`], writing the artifacts to `<prefix><kind>-<hash>`
+    #[must_use]
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            inner: InMemoryCorpus::new(),
+        }
+    }
+}
+
+impl Corpus<BytesInput> for ArtifactCorpus {
+    fn count(&self) -> usize {
+        self.inner.count()
+    }
+
+    fn add(&mut self, mut testcase: Testcase<BytesInput>) -> Result<usize, Error> {
+        let exit_kind = testcase
+            .metadata()
+            .get::<ArtifactKindMetadata>()
+            .map_or(ExitKind::Crash, |metadata| metadata.exit_kind);
+        let input = testcase.load_input()?;
+        let path = artifact_path(&self.prefix, exit_kind, input);
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        fs::write(&path, input.bytes())?;
+        println!(
+            "artifact_prefix='{}'; Test unit written to {}",
+            self.prefix,
+            path.display()
+        );
+        self.inner.add(testcase)
+    }
+
+    fn replace(&mut self, idx: usize, testcase: Testcase<BytesInput>) -> Result<(), Error> {
+        self.inner.replace(idx, testcase)
+    }
+
+    fn remove(&mut self, idx: usize) -> Result<Option<Testcase<BytesInput>>, Error> {
+        self.inner.remove(idx)
+    }
+
+    fn get(&self, idx: usize) -> Result<&RefCell<Testcase<BytesInput>>, Error> {
+        self.inner.get(idx)
+    }
+
+    fn current(&self) -> &Option<usize> {
+        self.inner.current()
+    }
+
+    fn current_mut(&mut self) -> &mut Option<usize> {
+        self.inner.current_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use libafl::{
+        corpus::{Corpus, Testcase},
+        executors::ExitKind,
+        inputs::{BytesInput, Input},
+        state::HasMetadata,
+    };
+
+    use crate::artifacts::{artifact_path, ArtifactCorpus, ArtifactKindMetadata};
+
+    #[test]
+    fn test_artifact_path() {
+        let input = BytesInput::new(b"boom".to_vec());
+        let hash = input.generate_name(0);
+        assert_eq!(
+            artifact_path("", ExitKind::Crash, &input),
+            PathBuf::from(format!("crash-{}", hash))
+        );
+        assert_eq!(
+            artifact_path("crash-", ExitKind::Crash, &input),
+            PathBuf::from(format!("crash-crash-{}", hash))
+        );
+        assert_eq!(
+            artifact_path("out/", ExitKind::Timeout, &input),
+            PathBuf::from(format!("out/timeout-{}", hash))
+        );
+    }
+
+    #[test]
+    fn test_artifact_corpus() {
+        let dir = std::env::temp_dir().join(format!(
+            "libafl_libfuzzer_test_artifacts_{}",
+            std::process::id()
+        ));
+        let prefix = format!("{}/fuzz-", dir.display());
+        let mut corpus = ArtifactCorpus::new(&prefix);
+        let input = BytesInput::new(b"slow".to_vec());
+        let mut testcase = Testcase::new(input.clone());
+        testcase.add_metadata(ArtifactKindMetadata {
+            exit_kind: ExitKind::Timeout,
+        });
+        corpus.add(testcase).unwrap();
+
+        assert_eq!(corpus.count(), 1);
+        let path = format!("{}timeout-{}", prefix, input.generate_name(0));
+        assert_eq!(fs::read(path).unwrap(), b"slow");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
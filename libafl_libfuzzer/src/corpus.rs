@@ -0,0 +1,133 @@
+//! The corpus in the first corpus directory, where `libFuzzer` writes the new inputs
+
+use core::cell::RefCell;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use libafl::{
+    corpus::{Corpus, OnDiskCorpus, Testcase},
+    inputs::{BytesInput, Input},
+    Error,
+};
+use serde::{Deserialize, Serialize};
+
+/// The corpus of the first corpus directory. The new inputs are written to it under their hash,
+/// as by `libFuzzer`, while the seeds loaded from it keep their file instead of being written again.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OutputCorpus {
+    inner: OnDiskCorpus<BytesInput>,
+    /// The files in the directory at the start, by the name their content would get
+    existing: HashMap<String, PathBuf>,
+}
+
+impl OutputCorpus {
+    /// Creates a new [`OutputCorpus`] in `dir`, creating it if needed
+    pub fn new(dir: &Path) -> Result<Self, Error> {
+        let inner = OnDiskCorpus::new(dir)?;
+        let mut existing = HashMap::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            // Skip the lock and metadata files of the corpus
+            let hidden = path
+                .file_name()
+                .map_or(true, |name| name.to_string_lossy().starts_with('.'));
+            if hidden || !path.is_file() {
+                continue;
+            }
+            let name = BytesInput::new(fs::read(&path)?).generate_name(0);
+            existing.insert(name, path);
+        }
+        Ok(Self { inner, existing })
+    }
+}
+
+impl Corpus<BytesInput> for OutputCorpus {
+    fn count(&self) -> usize {
+        self.inner.count()
+    }
+
+    fn add(&mut self, mut testcase: Testcase<BytesInput>) -> Result<usize, Error> {
+        if testcase.filename().is_none() {
+            let name = testcase.load_input()?.generate_name(0);
+            if let Some(path) = self.existing.remove(&name) {
+                testcase.set_filename(path.to_string_lossy().into());
+            }
+        }
+        self.inner.add(testcase)
+    }
+
+    fn replace(&mut self, idx: usize, testcase: Testcase<BytesInput>) -> Result<(), Error> {
+        self.inner.replace(idx, testcase)
+    }
+
+    fn remove(&mut self, idx: usize) -> Result<Option<Testcase<BytesInput>>, Error> {
+        self.inner.remove(idx)
+    }
+
+    fn get(&self, idx: usize) -> Result<&RefCell<Testcase<BytesInput>>, Error> {
+        self.inner.get(idx)
+    }
+
+    fn current(&self) -> &Option<usize> {
+        self.inner.current()
+    }
+
+    fn current_mut(&mut self) -> &mut Option<usize> {
+        self.inner.current_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use libafl::{
+        corpus::{Corpus, Testcase},
+        inputs::{BytesInput, Input},
+    };
+
+    use crate::corpus::OutputCorpus;
+
+    /// The visible files of the directory, sorted
+    fn files(dir: &std::path::Path) -> Vec<String> {
+        let mut files: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| !name.starts_with('.'))
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_output_corpus() {
+        let dir = std::env::temp_dir().join(format!(
+            "libafl_libfuzzer_test_output_corpus_{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("seed"), b"seed").unwrap();
+
+        let mut corpus = OutputCorpus::new(&dir).unwrap();
+        let idx = corpus
+            .add(Testcase::new(BytesInput::new(b"seed".to_vec())))
+            .unwrap();
+        assert_eq!(
+            corpus.get(idx).unwrap().borrow().filename().as_deref(),
+            dir.join("seed").to_str()
+        );
+        assert_eq!(files(&dir), ["seed"]);
+
+        let input = BytesInput::new(b"new".to_vec());
+        corpus.add(Testcase::new(input.clone())).unwrap();
+        let mut expected = vec!["seed".to_string(), input.generate_name(0)];
+        expected.sort();
+        assert_eq!(files(&dir), expected);
+        assert_eq!(fs::read(dir.join(input.generate_name(0))).unwrap(), b"new");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,347 @@
+//! A [`libFuzzer`](https://www.llvm.org/docs/LibFuzzer.html) drop-in replacement built on `LibAFL`.
+//!
+//! Link a target that was compiled with `-fsanitize-coverage=trace-pc-guard,trace-cmp` against this
+//! library instead of `libFuzzer`. The `main` of `libafl_targets` calls into [`libafl_main`], which
+//! understands the common `libFuzzer` flags (see [`LibfuzzerOptions`]) and then fuzzes
+//! `LLVMFuzzerTestOneInput`, or runs the given inputs once.
+
+#![deny(rustdoc::broken_intra_doc_links)]
+#![deny(clippy::pedantic)]
+#![allow(
+    clippy::unreadable_literal,
+    clippy::type_repetition_in_bounds,
+    clippy::missing_errors_doc,
+    clippy::cast_possible_truncation,
+    clippy::used_underscore_binding,
+    clippy::ptr_as_ptr,
+    clippy::missing_panics_doc,
+    clippy::missing_docs_in_private_items,
+    clippy::module_name_repetitions
+)]
+#![cfg_attr(debug_assertions, warn(
+    missing_debug_implementations,
+    missing_docs,
+    //trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    //unused_results
+))]
+#![cfg_attr(not(debug_assertions), deny(
+    missing_debug_implementations,
+    missing_docs,
+    //trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    //unused_results
+))]
+#![cfg_attr(
+    not(debug_assertions),
+    deny(
+        bad_style,
+        const_err,
+        dead_code,
+        improper_ctypes,
+        non_shorthand_field_patterns,
+        no_mangle_generic_items,
+        overflowing_literals,
+        path_statements,
+        patterns_in_fns_without_body,
+        private_in_public,
+        unconditional_recursion,
+        unused,
+        unused_allocation,
+        unused_comparisons,
+        unused_parens,
+        while_true
+    )
+)]
+
+pub mod artifacts;
+pub use artifacts::{ArtifactCorpus, ArtifactKindFeedback};
+pub mod corpus;
+pub use corpus::OutputCorpus;
+pub mod options;
+pub use options::LibfuzzerOptions;
+
+use core::{fmt::Debug, time::Duration};
+use std::{env, fs, process, time::Instant};
+
+use libafl::{
+    bolts::{
+        current_nanos, current_time,
+        rands::StdRand,
+        tuples::{tuple_list, Merge},
+        AsSlice,
+    },
+    corpus::{
+        Corpus, InMemoryCorpus, IndexesLenTimeMinimizerCorpusScheduler, QueueCorpusScheduler,
+    },
+    events::{ProgressReporter, SimpleEventManager},
+    executors::{
+        inprocess::InProcessExecutor, Executor, ExitKind, HasObservers, ShadowExecutor,
+        TimeoutExecutor,
+    },
+    feedback_or, feedback_or_fast,
+    feedbacks::{CrashFeedback, MapFeedbackState, MaxMapFeedback, TimeFeedback, TimeoutFeedback},
+    fuzzer::{Fuzzer, StdFuzzer},
+    generators::RandBytesGenerator,
+    inputs::{BytesInput, HasTargetBytes, Input},
+    monitors::SimpleMonitor,
+    mutators::scheduled::{havoc_mutations, tokens_mutations, StdScheduledMutator},
+    mutators::token_mutations::{I2SRandReplace, Tokens},
+    observers::{HitcountsMapObserver, ObserversTuple, StdMapObserver, TimeObserver},
+    stages::{ShadowTracingStage, StdMutationalStage},
+    state::{HasCorpus, HasExecutions, HasMaxSize, HasMetadata, StdState},
+    Error,
+};
+use libafl_targets::{
+    libfuzzer_initialize, libfuzzer_test_one_input, CmpLogObserver, CMPLOG_MAP, EDGES_MAP,
+    MAX_EDGES_NUM,
+};
+
+/// How often the fuzzer prints its stats
+const STATS_INTERVAL: Duration = Duration::from_secs(15);
+
+/// The number of random inputs to start with, if no corpus is given
+const INITIAL_INPUTS: usize = 8;
+
+/// An executor stopping the fuzzer, with [`Error::ShuttingDown`], once it ran the target the
+/// number of times of `-runs`, however far the current stage got
+#[derive(Debug)]
+struct RunsLimitExecutor<E> {
+    executor: E,
+    runs: Option<usize>,
+}
+
+impl<E, EM, I, S, Z> Executor<EM, I, S, Z> for RunsLimitExecutor<E>
+where
+    E: Executor<EM, I, S, Z>,
+    I: Input,
+    S: HasExecutions,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        if matches!(self.runs, Some(runs) if *state.executions() >= runs) {
+            return Err(Error::ShuttingDown);
+        }
+        self.executor.run_target(fuzzer, state, mgr, input)
+    }
+
+    fn post_run_reset(&mut self) {
+        self.executor.post_run_reset();
+    }
+}
+
+impl<E, I, OT, S> HasObservers<I, OT, S> for RunsLimitExecutor<E>
+where
+    E: HasObservers<I, OT, S>,
+    OT: ObserversTuple<I, S>,
+{
+    fn observers(&self) -> &OT {
+        self.executor.observers()
+    }
+
+    fn observers_mut(&mut self) -> &mut OT {
+        self.executor.observers_mut()
+    }
+}
+
+/// Turns the [`Error::ShuttingDown`] of the [`RunsLimitExecutor`] into the end of the fuzzing
+fn until_runs_done<T>(res: Result<T, Error>) -> Result<bool, Error> {
+    match res {
+        Ok(_) => Ok(false),
+        Err(Error::ShuttingDown) => Ok(true),
+        Err(err) => Err(err),
+    }
+}
+
+/// The entry point, called by the `main` of `libafl_targets`
+#[no_mangle]
+pub extern "C" fn libafl_main() {
+    let args: Vec<String> = env::args().collect();
+    let options = match LibfuzzerOptions::parse(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    };
+    for flag in &options.unknown {
+        eprintln!("WARNING: unrecognized flag '{}'; ignoring", flag);
+    }
+
+    // Call LLVMFuzzerInitialize() if present.
+    if libfuzzer_initialize(&args) == -1 {
+        println!("Warning: LLVMFuzzerInitialize failed with -1");
+    }
+
+    let res = if options.is_reproducing() {
+        run_inputs(&options)
+    } else {
+        fuzz(&options)
+    };
+    if let Err(err) = res {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+/// Runs each of the given inputs once, like `libFuzzer` does for files on the command line.
+pub fn run_inputs(options: &LibfuzzerOptions) -> Result<(), Error> {
+    for path in &options.inputs {
+        println!("Running: {}", path.display());
+        let buf = fs::read(path)?;
+        let start = Instant::now();
+        libfuzzer_test_one_input(&buf);
+        println!(
+            "Executed {} in {} ms",
+            path.display(),
+            start.elapsed().as_millis()
+        );
+    }
+    Ok(())
+}
+
+/// Fuzzes the target with the given options.
+/// New inputs are written to the first corpus directory, if any, else they are kept in memory.
+/// The seeds already in the first directory are not written again, see [`OutputCorpus`].
+pub fn fuzz(options: &LibfuzzerOptions) -> Result<(), Error> {
+    match options.corpus_dirs.first() {
+        Some(dir) => fuzz_with_corpus(options, OutputCorpus::new(dir)?),
+        None => fuzz_with_corpus(options, InMemoryCorpus::new()),
+    }
+}
+
+/// Fuzzes the target, storing new inputs in the given corpus
+#[allow(clippy::too_many_lines)]
+fn fuzz_with_corpus<C>(options: &LibfuzzerOptions, corpus: C) -> Result<(), Error>
+where
+    C: Corpus<BytesInput> + Debug,
+{
+    let seed = options.seed.unwrap_or_else(current_nanos);
+    println!("INFO: Seed: {}", seed);
+
+    let monitor = SimpleMonitor::new(|s| println!("{}", s));
+    let mut mgr = SimpleEventManager::new(monitor);
+
+    // Create an observation channel using the coverage map
+    let edges = unsafe { &mut EDGES_MAP[0..MAX_EDGES_NUM] };
+    let edges_observer = HitcountsMapObserver::new(StdMapObserver::new("edges", edges));
+
+    // Create an observation channel to keep track of the execution time
+    let time_observer = TimeObserver::new("time");
+
+    let cmplog = unsafe { &mut CMPLOG_MAP };
+    let cmplog_observer = CmpLogObserver::new("cmplog", cmplog, true);
+
+    // The state of the edges feedback.
+    let feedback_state = MapFeedbackState::with_observer(&edges_observer);
+
+    // Feedback to rate the interestingness of an input
+    let feedback = feedback_or!(
+        MaxMapFeedback::new_tracking(&feedback_state, &edges_observer, true, false),
+        TimeFeedback::new_with_observer(&time_observer)
+    );
+
+    // A feedback to choose if an input is a solution or not, keeping how its run ended
+    let objective = feedback_or!(
+        ArtifactKindFeedback::new(),
+        feedback_or_fast!(CrashFeedback::new(), TimeoutFeedback::new())
+    );
+
+    // libFuzzer writes its artifacts to the current directory by default
+    let artifacts = ArtifactCorpus::new(options.artifact_prefix.as_deref().unwrap_or("./"));
+
+    let mut state = StdState::new(
+        StdRand::with_seed(seed),
+        corpus,
+        artifacts,
+        tuple_list!(feedback_state),
+    );
+    state.set_max_size(options.max_len);
+
+    if let Some(dict) = &options.dict {
+        state.add_metadata(Tokens::from_file(dict)?);
+    }
+
+    // A minimization+queue policy to get testcasess from the corpus
+    let scheduler = IndexesLenTimeMinimizerCorpusScheduler::new(QueueCorpusScheduler::new());
+
+    // A fuzzer with feedbacks and a corpus scheduler
+    let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
+
+    // The wrapped harness function, calling out to the LLVM-style harness
+    let mut harness = |input: &BytesInput| {
+        let target = input.target_bytes();
+        let buf = target.as_slice();
+        libfuzzer_test_one_input(buf);
+        ExitKind::Ok
+    };
+
+    // A timeout of 0 disables the timer, as in libFuzzer
+    let mut executor = ShadowExecutor::new(
+        RunsLimitExecutor {
+            executor: TimeoutExecutor::new(
+                InProcessExecutor::new(
+                    &mut harness,
+                    tuple_list!(edges_observer, time_observer),
+                    &mut fuzzer,
+                    &mut state,
+                    &mut mgr,
+                )?,
+                Duration::from_secs(options.timeout),
+            ),
+            runs: options.runs,
+        },
+        tuple_list!(cmplog_observer),
+    );
+
+    let mut done = if options.corpus_dirs.is_empty() {
+        let mut generator = RandBytesGenerator::new(options.max_len.clamp(1, 32));
+        until_runs_done(state.generate_initial_inputs(
+            &mut fuzzer,
+            &mut executor,
+            &mut generator,
+            &mut mgr,
+            INITIAL_INPUTS,
+        ))?
+    } else {
+        until_runs_done(state.load_initial_inputs(
+            &mut fuzzer,
+            &mut executor,
+            &mut mgr,
+            &options.corpus_dirs,
+        ))?
+    };
+    println!("INFO: {} inputs in the corpus", state.corpus().count());
+
+    // Setup a tracing stage in which we log comparisons
+    let tracing = ShadowTracingStage::new(&mut executor);
+
+    // Setup a randomic Input2State stage
+    let i2s = StdMutationalStage::new(StdScheduledMutator::new(tuple_list!(I2SRandReplace::new())));
+
+    // Setup a basic mutator, the token mutations are no-ops without a dictionary
+    let mutator = StdScheduledMutator::new(havoc_mutations().merge(tokens_mutations()));
+    let mutational = StdMutationalStage::new(mutator);
+
+    let mut stages = tuple_list!(tracing, i2s, mutational);
+
+    let mut last = current_time();
+    while !done {
+        done = until_runs_done(fuzzer.fuzz_one(&mut stages, &mut executor, &mut state, &mut mgr))?;
+        last = mgr.maybe_report_progress(&mut state, last, STATS_INTERVAL)?;
+    }
+    println!("Done {} runs", state.executions());
+
+    Ok(())
+}
@@ -0,0 +1,197 @@
+//! Parsing of the `libFuzzer` command line.
+//! Flags have the form `-name=value`, all other arguments are corpus directories or inputs.
+
+use std::path::PathBuf;
+
+use libafl::Error;
+
+/// The default timeout of `libFuzzer`, in seconds
+pub const DEFAULT_TIMEOUT_SECS: u64 = 1200;
+
+/// The default maximum input length, if `-max_len` is not given
+pub const DEFAULT_MAX_LEN: usize = 4096;
+
+/// The `libFuzzer` modes not implemented by this shim. They error, as ignoring them would fuzz
+/// instead, e.g. forever instead of merging for `-merge=1`.
+pub const UNSUPPORTED_MODES: [&str; 7] = [
+    "merge",
+    "set_cover_merge",
+    "fork",
+    "jobs",
+    "workers",
+    "minimize_crash",
+    "cleanse_crash",
+];
+
+/// The subset of the `libFuzzer` options understood by this shim
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LibfuzzerOptions {
+    /// The number of executions to run, `None` means forever (`-runs`)
+    pub runs: Option<usize>,
+    /// The maximum length of generated inputs (`-max_len`)
+    pub max_len: usize,
+    /// The timeout of a single execution, in seconds (`-timeout`)
+    pub timeout: u64,
+    /// The seed of the random number generator, `None` for a random seed (`-seed`)
+    pub seed: Option<u64>,
+    /// A dictionary file in the `AFL`/`libFuzzer` format (`-dict`)
+    pub dict: Option<PathBuf>,
+    /// The prefix of the paths of the crashing and timeouting inputs, prepended verbatim: `out/`
+    /// writes them into the `out` directory, `fuzz-` names them `fuzz-crash-<hash>`
+    /// (`-artifact_prefix`)
+    pub artifact_prefix: Option<String>,
+    /// The corpus directories. New inputs are written to the first one.
+    pub corpus_dirs: Vec<PathBuf>,
+    /// Single inputs to run once, instead of fuzzing
+    pub inputs: Vec<PathBuf>,
+    /// Flags we don't know about. They are reported, but otherwise ignored.
+    pub unknown: Vec<String>,
+}
+
+impl Default for LibfuzzerOptions {
+    fn default() -> Self {
+        Self {
+            runs: None,
+            max_len: DEFAULT_MAX_LEN,
+            timeout: DEFAULT_TIMEOUT_SECS,
+            seed: None,
+            dict: None,
+            artifact_prefix: None,
+            corpus_dirs: vec![],
+            inputs: vec![],
+            unknown: vec![],
+        }
+    }
+}
+
+/// Parses the value of a numeric flag
+fn parse_num<T: core::str::FromStr>(name: &str, value: &str) -> Result<T, Error> {
+    value
+        .parse()
+        .map_err(|_| Error::IllegalArgument(format!("Invalid value {} for flag -{}", value, name)))
+}
+
+impl LibfuzzerOptions {
+    /// Parses the given command line. The first item is the name of the program and is skipped.
+    /// Errors on the [`UNSUPPORTED_MODES`], unless they are turned off with `0`.
+    pub fn parse<S: AsRef<str>>(args: &[S]) -> Result<Self, Error> {
+        let mut options = Self::default();
+        for arg in args.iter().skip(1).map(AsRef::as_ref) {
+            let flag = if let Some(flag) = arg.strip_prefix('-') {
+                flag
+            } else {
+                let path = PathBuf::from(arg);
+                if path.is_file() {
+                    options.inputs.push(path);
+                } else {
+                    options.corpus_dirs.push(path);
+                }
+                continue;
+            };
+            let (name, value) = flag.split_once('=').unwrap_or((flag, ""));
+            match name {
+                // -runs=-1 (the default) means to fuzz forever
+                "runs" => {
+                    let runs: i64 = parse_num(name, value)?;
+                    options.runs = usize::try_from(runs).ok();
+                }
+                "max_len" => options.max_len = parse_num(name, value)?,
+                "timeout" => options.timeout = parse_num(name, value)?,
+                "seed" => {
+                    let seed: u64 = parse_num(name, value)?;
+                    // libFuzzer uses 0 for a random seed
+                    options.seed = if seed == 0 { None } else { Some(seed) };
+                }
+                "dict" => options.dict = Some(PathBuf::from(value)),
+                "artifact_prefix" => options.artifact_prefix = Some(value.to_string()),
+                _ if UNSUPPORTED_MODES.contains(&name) => {
+                    if value != "0" {
+                        return Err(Error::IllegalArgument(format!(
+                            "The -{} mode is not supported",
+                            name
+                        )));
+                    }
+                }
+                _ => options.unknown.push(arg.to_string()),
+            }
+        }
+        Ok(options)
+    }
+
+    /// Returns `true` if the given inputs should only be run once, instead of fuzzing
+    #[must_use]
+    pub fn is_reproducing(&self) -> bool {
+        !self.inputs.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::options::{LibfuzzerOptions, DEFAULT_MAX_LEN, DEFAULT_TIMEOUT_SECS};
+
+    #[test]
+    fn test_parse_flags() {
+        let options = LibfuzzerOptions::parse(&[
+            "fuzzer",
+            "-runs=1000",
+            "-max_len=64",
+            "-timeout=5",
+            "-seed=42",
+            "-dict=tokens.dict",
+            "-artifact_prefix=crash-",
+            "-use_value_profile=1",
+        ])
+        .unwrap();
+        assert_eq!(options.runs, Some(1000));
+        assert_eq!(options.max_len, 64);
+        assert_eq!(options.timeout, 5);
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(options.dict, Some(PathBuf::from("tokens.dict")));
+        assert_eq!(options.artifact_prefix.as_deref(), Some("crash-"));
+        assert_eq!(options.unknown, ["-use_value_profile=1"]);
+        assert!(!options.is_reproducing());
+    }
+
+    #[test]
+    fn test_parse_defaults() {
+        let options = LibfuzzerOptions::parse(&["fuzzer", "-runs=-1", "-seed=0"]).unwrap();
+        assert_eq!(options.runs, None);
+        assert_eq!(options.seed, None);
+        assert_eq!(options.max_len, DEFAULT_MAX_LEN);
+        assert_eq!(options.timeout, DEFAULT_TIMEOUT_SECS);
+        assert_eq!(options.artifact_prefix, None);
+
+        assert!(LibfuzzerOptions::parse(&["fuzzer", "-runs=many"]).is_err());
+        assert!(LibfuzzerOptions::parse(&["fuzzer", "-max_len"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_unsupported_modes() {
+        for flag in [
+            "-merge=1",
+            "-jobs=4",
+            "-workers=2",
+            "-fork=1",
+            "-minimize_crash=1",
+        ] {
+            assert!(LibfuzzerOptions::parse(&["fuzzer", flag, "corpus"]).is_err());
+        }
+        let options = LibfuzzerOptions::parse(&["fuzzer", "-merge=0", "-fork=0"]).unwrap();
+        assert!(options.unknown.is_empty());
+    }
+
+    #[test]
+    fn test_parse_paths() {
+        // The manifest is a file, run once, the others are corpus directories
+        let options =
+            LibfuzzerOptions::parse(&["fuzzer", "corpus", "Cargo.toml", "seeds"]).unwrap();
+        assert_eq!(
+            options.corpus_dirs,
+            [PathBuf::from("corpus"), PathBuf::from("seeds")]
+        );
+        assert_eq!(options.inputs, [PathBuf::from("Cargo.toml")]);
+        assert!(options.is_reproducing());
+    }
+}
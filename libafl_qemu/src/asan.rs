@@ -1,5 +1,16 @@
-use libafl::{executors::ExitKind, inputs::Input, observers::ObserversTuple, state::HasMetadata};
+use libafl::{
+    bolts::tuples::Named,
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    observers::{Observer, ObserversTuple},
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error,
+};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
+use serde::{Deserialize, Serialize};
 use std::{env, fs, ptr};
 
 use crate::{
@@ -111,6 +122,61 @@ unsafe extern "C" fn asan_giovese_populate_context(ctx: *mut CallContext, _pc: u
     ctx.size = 0;
 }
 
+/// The kind of access that triggered a `QASan` report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QasanAccess {
+    Read,
+    Write,
+    BadFree,
+}
+
+/// A memory error detected by `QASan` in the emulated target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QasanError {
+    pub access: QasanAccess,
+    pub addr: u64,
+    pub size: usize,
+    pub pc: u64,
+}
+
+/// The errors detected by `QASan`, attached to the objectives
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QasanErrorsMetadata {
+    pub errors: Vec<QasanError>,
+}
+
+libafl::impl_serdeany!(QasanErrorsMetadata);
+
+/// The errors detected during the current run.
+/// They are recorded before `QASan` aborts, so that the crash handler can still report them.
+static mut QASAN_ERRORS: Vec<QasanError> = vec![];
+
+/// Records the error and reports it, aborting the target
+unsafe fn report_and_crash(emulator: &Emulator, access: QasanAccess, addr: u64, size: usize) {
+    let pc = emulator.read_reg(Regs::Pc).unwrap_or(u64::MAX);
+    let sp = emulator.read_reg(Regs::Sp).unwrap_or(u64::MAX);
+    QASAN_ERRORS.push(QasanError {
+        access,
+        addr,
+        size,
+        pc,
+    });
+    let access_type = i32::from(access == QasanAccess::Write);
+    asan_giovese_report_and_crash(access_type, addr, size, pc, 0, sp);
+}
+
+/// Records the invalid free and reports it, aborting the target
+unsafe fn report_badfree(emulator: &Emulator, addr: u64) {
+    let pc = emulator.read_reg(Regs::Pc).unwrap_or(u64::MAX);
+    QASAN_ERRORS.push(QasanError {
+        access: QasanAccess::BadFree,
+        addr,
+        size: 0,
+        pc,
+    });
+    asan_giovese_badfree(addr, pc);
+}
+
 static mut ASAN_INITED: bool = false;
 
 pub fn init_with_asan(args: &mut Vec<String>, env: &mut [(String, String)]) -> Emulator {
@@ -217,14 +283,14 @@ impl QemuAsanHelper {
             if let Some(ck) = ckinfo.as_mut() {
                 if ck.start != addr {
                     // Free not the start of the chunk
-                    asan_giovese_badfree(addr, emulator.read_reg(Regs::Pc).unwrap_or(u64::MAX));
+                    report_badfree(emulator, addr);
                 }
                 let ctx: *const CallContext =
                     libc::calloc(core::mem::size_of::<CallContext>(), 1) as *const _;
                 ck.free_ctx = ctx;
             } else {
                 // Free of wild ptr
-                asan_giovese_badfree(addr, emulator.read_reg(Regs::Pc).unwrap_or(u64::MAX));
+                report_badfree(emulator, addr);
             }
         }
     }
@@ -238,14 +304,7 @@ impl QemuAsanHelper {
     pub fn read_1(&mut self, emulator: &Emulator, addr: GuestAddr) {
         unsafe {
             if self.enabled() && asan_giovese_load1(emulator.g2h(addr)) != 0 {
-                asan_giovese_report_and_crash(
-                    0,
-                    addr.into(),
-                    1,
-                    emulator.read_reg(Regs::Pc).unwrap_or(u64::MAX),
-                    0,
-                    emulator.read_reg(Regs::Sp).unwrap_or(u64::MAX),
-                );
+                report_and_crash(emulator, QasanAccess::Read, addr.into(), 1);
             }
         }
     }
@@ -253,14 +312,7 @@ impl QemuAsanHelper {
     pub fn read_2(&mut self, emulator: &Emulator, addr: GuestAddr) {
         unsafe {
             if self.enabled() && asan_giovese_load2(emulator.g2h(addr)) != 0 {
-                asan_giovese_report_and_crash(
-                    0,
-                    addr.into(),
-                    2,
-                    emulator.read_reg(Regs::Pc).unwrap_or(u64::MAX),
-                    0,
-                    emulator.read_reg(Regs::Sp).unwrap_or(u64::MAX),
-                );
+                report_and_crash(emulator, QasanAccess::Read, addr.into(), 2);
             }
         }
     }
@@ -268,14 +320,7 @@ impl QemuAsanHelper {
     pub fn read_4(&mut self, emulator: &Emulator, addr: GuestAddr) {
         unsafe {
             if self.enabled() && asan_giovese_load4(emulator.g2h(addr)) != 0 {
-                asan_giovese_report_and_crash(
-                    0,
-                    addr.into(),
-                    4,
-                    emulator.read_reg(Regs::Pc).unwrap_or(u64::MAX),
-                    0,
-                    emulator.read_reg(Regs::Sp).unwrap_or(u64::MAX),
-                );
+                report_and_crash(emulator, QasanAccess::Read, addr.into(), 4);
             }
         }
     }
@@ -283,14 +328,7 @@ impl QemuAsanHelper {
     pub fn read_8(&mut self, emulator: &Emulator, addr: GuestAddr) {
         unsafe {
             if self.enabled() && asan_giovese_load8(emulator.g2h(addr)) != 0 {
-                asan_giovese_report_and_crash(
-                    0,
-                    addr.into(),
-                    8,
-                    emulator.read_reg(Regs::Pc).unwrap_or(u64::MAX),
-                    0,
-                    emulator.read_reg(Regs::Sp).unwrap_or(u64::MAX),
-                );
+                report_and_crash(emulator, QasanAccess::Read, addr.into(), 8);
             }
         }
     }
@@ -298,14 +336,7 @@ impl QemuAsanHelper {
     pub fn read_n(&mut self, emulator: &Emulator, addr: GuestAddr, size: usize) {
         unsafe {
             if self.enabled() && asan_giovese_loadN(emulator.g2h(addr), size) != 0 {
-                asan_giovese_report_and_crash(
-                    0,
-                    addr.into(),
-                    size,
-                    emulator.read_reg(Regs::Pc).unwrap_or(u64::MAX),
-                    0,
-                    emulator.read_reg(Regs::Sp).unwrap_or(u64::MAX),
-                );
+                report_and_crash(emulator, QasanAccess::Read, addr.into(), size);
             }
         }
     }
@@ -313,14 +344,7 @@ impl QemuAsanHelper {
     pub fn write_1(&mut self, emulator: &Emulator, addr: GuestAddr) {
         unsafe {
             if self.enabled() && asan_giovese_store1(emulator.g2h(addr)) != 0 {
-                asan_giovese_report_and_crash(
-                    1,
-                    addr.into(),
-                    1,
-                    emulator.read_reg(Regs::Pc).unwrap_or(u64::MAX),
-                    0,
-                    emulator.read_reg(Regs::Sp).unwrap_or(u64::MAX),
-                );
+                report_and_crash(emulator, QasanAccess::Write, addr.into(), 1);
             }
         }
     }
@@ -328,14 +352,7 @@ impl QemuAsanHelper {
     pub fn write_2(&mut self, emulator: &Emulator, addr: GuestAddr) {
         unsafe {
             if self.enabled() && asan_giovese_store2(emulator.g2h(addr)) != 0 {
-                asan_giovese_report_and_crash(
-                    1,
-                    addr.into(),
-                    2,
-                    emulator.read_reg(Regs::Pc).unwrap_or(u64::MAX),
-                    0,
-                    emulator.read_reg(Regs::Sp).unwrap_or(u64::MAX),
-                );
+                report_and_crash(emulator, QasanAccess::Write, addr.into(), 2);
             }
         }
     }
@@ -343,14 +360,7 @@ impl QemuAsanHelper {
     pub fn write_4(&mut self, emulator: &Emulator, addr: GuestAddr) {
         unsafe {
             if self.enabled() && asan_giovese_store4(emulator.g2h(addr)) != 0 {
-                asan_giovese_report_and_crash(
-                    1,
-                    addr.into(),
-                    4,
-                    emulator.read_reg(Regs::Pc).unwrap_or(u64::MAX),
-                    0,
-                    emulator.read_reg(Regs::Sp).unwrap_or(u64::MAX),
-                );
+                report_and_crash(emulator, QasanAccess::Write, addr.into(), 4);
             }
         }
     }
@@ -358,14 +368,7 @@ impl QemuAsanHelper {
     pub fn write_8(&mut self, emulator: &Emulator, addr: GuestAddr) {
         unsafe {
             if self.enabled() && asan_giovese_store8(emulator.g2h(addr)) != 0 {
-                asan_giovese_report_and_crash(
-                    1,
-                    addr.into(),
-                    8,
-                    emulator.read_reg(Regs::Pc).unwrap_or(u64::MAX),
-                    0,
-                    emulator.read_reg(Regs::Sp).unwrap_or(u64::MAX),
-                );
+                report_and_crash(emulator, QasanAccess::Write, addr.into(), 8);
             }
         }
     }
@@ -373,14 +376,7 @@ impl QemuAsanHelper {
     pub fn write_n(&mut self, emulator: &Emulator, addr: GuestAddr, size: usize) {
         unsafe {
             if self.enabled() && asan_giovese_storeN(emulator.g2h(addr), size) != 0 {
-                asan_giovese_report_and_crash(
-                    1,
-                    addr.into(),
-                    size,
-                    emulator.read_reg(Regs::Pc).unwrap_or(u64::MAX),
-                    0,
-                    emulator.read_reg(Regs::Sp).unwrap_or(u64::MAX),
-                );
+                report_and_crash(emulator, QasanAccess::Write, addr.into(), size);
             }
         }
     }
@@ -677,3 +673,194 @@ where
         SyscallHookResult::new(None)
     }
 }
+
+/// An [`Observer`] for the errors detected by the [`QemuAsanHelper`] during a run
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct QasanErrorsObserver {}
+
+impl QasanErrorsObserver {
+    /// Creates a new [`QasanErrorsObserver`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// The errors detected during the last run
+    #[must_use]
+    #[allow(clippy::unused_self)]
+    pub fn errors(&self) -> &[QasanError] {
+        unsafe { &QASAN_ERRORS }
+    }
+}
+
+impl<I, S> Observer<I, S> for QasanErrorsObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        unsafe { QASAN_ERRORS.clear() };
+        Ok(())
+    }
+}
+
+impl Named for QasanErrorsObserver {
+    #[inline]
+    fn name(&self) -> &str {
+        "QasanErrors"
+    }
+}
+
+/// A [`Feedback`] reporting the runs in which `QASan` detected an error, to be used as objective.
+/// The errors are attached to the testcase as [`QasanErrorsMetadata`].
+#[derive(Debug, Default)]
+pub struct QasanErrorsFeedback {
+    errors: Option<Vec<QasanError>>,
+}
+
+impl QasanErrorsFeedback {
+    /// Creates a new [`QasanErrorsFeedback`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self { errors: None }
+    }
+}
+
+impl<I, S> Feedback<I, S> for QasanErrorsFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let observer = observers
+            .match_name::<QasanErrorsObserver>("QasanErrors")
+            .expect("A QasanErrorsFeedback needs a QasanErrorsObserver");
+        if observer.errors().is_empty() {
+            Ok(false)
+        } else {
+            self.errors = Some(observer.errors().to_vec());
+            Ok(true)
+        }
+    }
+
+    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if let Some(errors) = self.errors.take() {
+            testcase.add_metadata(QasanErrorsMetadata { errors });
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.errors = None;
+        Ok(())
+    }
+}
+
+impl Named for QasanErrorsFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        "QasanErrors"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::Feedback,
+        inputs::BytesInput,
+        observers::Observer,
+        state::{HasMetadata, StdState},
+    };
+
+    use crate::{
+        asan::{
+            QasanAccess, QasanError, QasanErrorsFeedback, QasanErrorsMetadata, QasanErrorsObserver,
+            QemuAsanHelper, QASAN_ERRORS,
+        },
+        helper::QemuInstrumentationFilter,
+    };
+
+    #[test]
+    fn test_qasan_errors_feedback() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            tuple_list!(),
+        );
+        let input = BytesInput::new(vec![0; 4]);
+        let mut observers = tuple_list!(QasanErrorsObserver::new());
+        let mut feedback = QasanErrorsFeedback::new();
+
+        observers.0.pre_exec(&mut state, &input).unwrap();
+        // As recorded by the hooks, before QASan aborts
+        unsafe {
+            QASAN_ERRORS.push(QasanError {
+                access: QasanAccess::Write,
+                addr: 0x1234,
+                size: 4,
+                pc: 0x400000,
+            });
+        }
+        assert!(feedback
+            .is_interesting(
+                &mut state,
+                &mut NopEventManager {},
+                &input,
+                &observers,
+                &ExitKind::Crash
+            )
+            .unwrap());
+        let mut testcase = Testcase::new(input.clone());
+        feedback.append_metadata(&mut state, &mut testcase).unwrap();
+        let errors = &testcase
+            .metadata()
+            .get::<QasanErrorsMetadata>()
+            .unwrap()
+            .errors;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].access, QasanAccess::Write);
+        assert_eq!((errors[0].addr, errors[0].size), (0x1234, 4));
+
+        // The next run starts without errors
+        observers.0.pre_exec(&mut state, &input).unwrap();
+        assert!(observers.0.errors().is_empty());
+        assert!(!feedback
+            .is_interesting(
+                &mut state,
+                &mut NopEventManager {},
+                &input,
+                &observers,
+                &ExitKind::Ok
+            )
+            .unwrap());
+    }
+
+    #[test]
+    fn test_asan_instrumentation_filter() {
+        let helper = QemuAsanHelper::with_instrumentation_filter(
+            QemuInstrumentationFilter::AllowList(vec![0x1000..0x2000]),
+        );
+        assert!(helper.must_instrument(0x1000));
+        assert!(!helper.must_instrument(0x2000));
+
+        let helper =
+            QemuAsanHelper::with_instrumentation_filter(QemuInstrumentationFilter::DenyList(vec![
+                0x1000..0x2000,
+            ]));
+        assert!(!helper.must_instrument(0x1fff));
+        assert!(helper.must_instrument(0x800));
+        assert!(helper.enabled());
+    }
+}
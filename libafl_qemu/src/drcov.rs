@@ -0,0 +1,306 @@
+//! Generates [`DrCov`](https://dynamorio.org/page_drcov.html) traces of the emulated target,
+//! to be loaded into coverage analysis tools, such as Lighthouse or Cartographer.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    fs,
+    hash::Hasher,
+    path::PathBuf,
+};
+
+use libafl::{
+    bolts::AsSlice,
+    executors::ExitKind,
+    inputs::{HasTargetBytes, Input},
+    observers::ObserversTuple,
+};
+use libafl_targets::drcov::{DrCovBasicBlock, DrCovWriter};
+use rangemap::RangeMap;
+
+#[cfg(any(cpu_target = "arm", cpu_target = "i386"))]
+use crate::SYS_mmap2;
+use crate::{
+    emu::Emulator,
+    executor::QemuExecutor,
+    helper::{QemuHelper, QemuHelperTuple, QemuInstrumentationFilter},
+    SYS_mmap, SYS_mprotect, SYS_munmap,
+};
+
+/// A [`QemuHelper`] writing the blocks executed by the target to `DrCov` files.
+///
+/// By default, each run writes the unique blocks it executed to `<output_dir>/<input hash>.drcov`.
+/// The hooks only get the start of the translated blocks, so the blocks in the traces span one byte,
+/// which coverage tools attribute to the whole basic block.
+/// The modules of the traces are read from the mappings of the target again after the runs mapping
+/// or unmapping code, e.g. with `dlopen`, or executing blocks outside of the known modules.
+#[derive(Debug)]
+pub struct QemuDrCovHelper {
+    filter: QemuInstrumentationFilter,
+    output_dir: PathBuf,
+    full_trace: bool,
+    merge: bool,
+    module_mapping: RangeMap<usize, (u16, String)>,
+    modules_changed: bool,
+    blocks: Vec<u64>,
+    seen: HashSet<u64>,
+    merged: HashSet<u64>,
+}
+
+impl QemuDrCovHelper {
+    /// Creates a new [`QemuDrCovHelper`] writing its traces to `output_dir`
+    #[must_use]
+    pub fn new(filter: QemuInstrumentationFilter, output_dir: PathBuf) -> Self {
+        Self {
+            filter,
+            output_dir,
+            full_trace: false,
+            merge: false,
+            module_mapping: RangeMap::new(),
+            modules_changed: false,
+            blocks: vec![],
+            seen: HashSet::new(),
+            merged: HashSet::new(),
+        }
+    }
+
+    /// Keeps every execution of a block in the traces, in order, instead of the unique blocks only
+    #[must_use]
+    pub fn full_trace(mut self, full_trace: bool) -> Self {
+        self.full_trace = full_trace;
+        self
+    }
+
+    /// Merges the blocks of all runs into a single `<output_dir>/merged.drcov`,
+    /// rewritten each time a run executes new blocks, instead of writing one trace per input
+    #[must_use]
+    pub fn merge(mut self, merge: bool) -> Self {
+        self.merge = merge;
+        self
+    }
+
+    #[must_use]
+    pub fn must_instrument(&self, addr: u64) -> bool {
+        self.filter.allowed(addr)
+    }
+
+    /// Records the execution of the block at `pc`
+    pub fn trace_block(&mut self, pc: u64) {
+        if self.seen.insert(pc) || self.full_trace {
+            self.blocks.push(pc);
+        }
+    }
+
+    /// Notes that the target mapped or unmapped code, to read its modules again
+    pub fn modules_changed(&mut self) {
+        self.modules_changed = true;
+    }
+
+    /// The executable mappings of the target, as `DrCov` modules
+    fn update_module_mapping(&mut self, emulator: &Emulator) {
+        let mut paths: Vec<String> = vec![];
+        self.module_mapping = RangeMap::new();
+        for map in emulator.mappings() {
+            if !map.flags().is_x() {
+                continue;
+            }
+            let path = map.path().unwrap_or("").to_string();
+            let id = paths.iter().position(|p| *p == path).unwrap_or_else(|| {
+                paths.push(path.clone());
+                paths.len() - 1
+            });
+            if map.start() < map.end() {
+                self.module_mapping
+                    .insert(map.start() as usize..map.end() as usize, (id as u16, path));
+            }
+        }
+    }
+
+    fn write_blocks<'b>(&self, filename: &str, blocks: impl Iterator<Item = &'b u64>) {
+        let drcov_blocks: Vec<DrCovBasicBlock> = blocks
+            .filter(|pc| self.module_mapping.get(&(**pc as usize)).is_some())
+            .map(|pc| DrCovBasicBlock::new_with_size(*pc as usize, 1))
+            .collect();
+        if let Err(err) = DrCovWriter::new(&self.module_mapping)
+            .write(self.output_dir.join(filename), &drcov_blocks)
+        {
+            println!("Could not write the DrCov trace {}: {:?}", filename, err);
+        }
+    }
+}
+
+impl<I, S> QemuHelper<I, S> for QemuDrCovHelper
+where
+    I: Input + HasTargetBytes,
+{
+    fn init<'a, H, OT, QT>(&self, executor: &QemuExecutor<'a, H, I, OT, QT, S>)
+    where
+        H: FnMut(&I) -> ExitKind,
+        OT: ObserversTuple<I, S>,
+        QT: QemuHelperTuple<I, S>,
+    {
+        fs::create_dir_all(&self.output_dir)
+            .expect("failed to create directory for coverage files");
+        executor.hook_block_generation(gen_drcov_block_ids::<I, QT, S>);
+        executor.hook_block_execution(trace_drcov_block::<I, QT, S>);
+        executor.hook_after_syscalls(trace_drcov_mmap::<I, QT, S>);
+    }
+
+    fn pre_exec(&mut self, emulator: &Emulator, _input: &I) {
+        if self.module_mapping.iter().next().is_none() {
+            self.update_module_mapping(emulator);
+        }
+        self.blocks.clear();
+        self.seen.clear();
+    }
+
+    fn post_exec(&mut self, emulator: &Emulator, input: &I) {
+        if self.modules_changed
+            || self
+                .blocks
+                .iter()
+                .any(|pc| self.module_mapping.get(&(*pc as usize)).is_none())
+        {
+            self.update_module_mapping(emulator);
+            self.modules_changed = false;
+        }
+        if self.merge {
+            let merged_len = self.merged.len();
+            self.merged.extend(self.blocks.iter().copied());
+            if self.merged.len() != merged_len {
+                let mut blocks: Vec<u64> = self.merged.iter().copied().collect();
+                blocks.sort_unstable();
+                self.write_blocks("merged.drcov", blocks.iter());
+            }
+        } else {
+            let mut hasher = DefaultHasher::new();
+            hasher.write(input.target_bytes().as_slice());
+            let filename = format!("{:016x}.drcov", hasher.finish());
+            // The same input yields the same trace
+            if !self.output_dir.join(&filename).exists() {
+                self.write_blocks(&filename, self.blocks.iter());
+            }
+        }
+    }
+}
+
+pub fn gen_drcov_block_ids<I, QT, S>(
+    _emulator: &Emulator,
+    helpers: &mut QT,
+    _state: &mut S,
+    pc: u64,
+) -> Option<u64>
+where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    let h = helpers.match_first_type::<QemuDrCovHelper>().unwrap();
+    if h.must_instrument(pc) {
+        Some(pc)
+    } else {
+        None
+    }
+}
+
+pub fn trace_drcov_block<I, QT, S>(_emulator: &Emulator, helpers: &mut QT, _state: &mut S, id: u64)
+where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    let h = helpers.match_first_type_mut::<QemuDrCovHelper>().unwrap();
+    h.trace_block(id);
+}
+
+/// Notes the mappings and unmappings of code, changing the modules
+#[allow(clippy::too_many_arguments)]
+pub fn trace_drcov_mmap<I, QT, S>(
+    _emulator: &Emulator,
+    helpers: &mut QT,
+    _state: &mut S,
+    result: u64,
+    sys_num: i32,
+    _a0: u64,
+    _a1: u64,
+    a2: u64,
+    _a3: u64,
+    _a4: u64,
+    _a5: u64,
+    _a6: u64,
+    _a7: u64,
+) -> u64
+where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    // The syscalls return -errno on errors
+    if result as i64 >= -4095 && (result as i64) < 0 {
+        return result;
+    }
+    let sys = i64::from(sys_num);
+    #[cfg(any(cpu_target = "arm", cpu_target = "i386"))]
+    let is_mmap = sys == SYS_mmap || sys == SYS_mmap2;
+    #[cfg(not(any(cpu_target = "arm", cpu_target = "i386")))]
+    let is_mmap = sys == SYS_mmap;
+    let maps_code = (is_mmap || sys == SYS_mprotect) && a2 & (libc::PROT_EXEC as u64) != 0;
+    if maps_code || sys == SYS_munmap {
+        helpers
+            .match_first_type_mut::<QemuDrCovHelper>()
+            .unwrap()
+            .modules_changed();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{drcov::QemuDrCovHelper, helper::QemuInstrumentationFilter};
+
+    #[test]
+    fn test_drcov_blocks() {
+        let output_dir =
+            std::env::temp_dir().join(format!("libafl_qemu_drcov_{}", std::process::id()));
+        fs::create_dir_all(&output_dir).unwrap();
+        let mut helper = QemuDrCovHelper::new(QemuInstrumentationFilter::None, output_dir.clone());
+        helper
+            .module_mapping
+            .insert(0x1000..0x2000, (0, "/bin/target".to_string()));
+        helper
+            .module_mapping
+            .insert(0x7000..0x8000, (1, "/lib/libc.so".to_string()));
+
+        // Repeated blocks are only traced once, blocks outside of the modules are dropped
+        for pc in [0x1010, 0x7020, 0x1010, 0x9000] {
+            helper.trace_block(pc);
+        }
+        assert_eq!(helper.blocks, [0x1010, 0x7020, 0x9000]);
+        helper.write_blocks("test.drcov", helper.blocks.iter());
+
+        let trace = fs::read(output_dir.join("test.drcov")).unwrap();
+        fs::remove_dir_all(&output_dir).unwrap();
+        let header = "DRCOV VERSION: 2\n\
+            DRCOV FLAVOR: libafl\n\
+            Module Table: version 2, count 2\n\
+            Columns: id, base, end, entry, checksum, timestamp, path\n\
+            000, 0x1000, 0x2000, 0x00000000, 0x00000000, 0x00000000, /bin/target\n\
+            001, 0x7000, 0x8000, 0x00000000, 0x00000000, 0x00000000, /lib/libc.so\n\
+            BB Table: 2 bbs\n";
+        assert_eq!(&trace[..header.len()], header.as_bytes());
+        // Each block is its offset in its module, its size and the id of its module
+        let mut blocks = header.as_bytes().to_vec();
+        blocks.extend_from_slice(&[0x10, 0, 0, 0, 1, 0, 0, 0]);
+        blocks.extend_from_slice(&[0x20, 0, 0, 0, 1, 0, 1, 0]);
+        assert_eq!(trace, blocks);
+    }
+
+    #[test]
+    fn test_drcov_full_trace() {
+        let mut helper =
+            QemuDrCovHelper::new(QemuInstrumentationFilter::None, std::env::temp_dir())
+                .full_trace(true);
+        for pc in [0x1010, 0x1020, 0x1010] {
+            helper.trace_block(pc);
+        }
+        assert_eq!(helper.blocks, [0x1010, 0x1020, 0x1010]);
+    }
+}
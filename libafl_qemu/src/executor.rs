@@ -59,6 +59,58 @@ where
     }
 }
 
+/// The instruction hooks, by the index given to `QEMU`. The slots of removed hooks are reused.
+#[derive(Debug)]
+struct InstructionHooks {
+    entries: Vec<Option<(GuestAddr, *const c_void)>>,
+}
+
+impl InstructionHooks {
+    const fn new() -> Self {
+        Self { entries: vec![] }
+    }
+
+    /// Adds the hook on `addr`, returning its index
+    fn add(&mut self, addr: GuestAddr, hook: *const c_void) -> usize {
+        if let Some(idx) = self.entries.iter().position(Option::is_none) {
+            self.entries[idx] = Some((addr, hook));
+            idx
+        } else {
+            self.entries.push(Some((addr, hook)));
+            self.entries.len() - 1
+        }
+    }
+
+    /// Removes all the hooks on `addr`
+    fn remove(&mut self, addr: GuestAddr) {
+        for entry in &mut self.entries {
+            if matches!(entry, Some((pc, _)) if *pc == addr) {
+                *entry = None;
+            }
+        }
+    }
+
+    fn get(&self, idx: usize) -> Option<(GuestAddr, *const c_void)> {
+        self.entries.get(idx).copied().flatten()
+    }
+}
+
+static mut INSTRUCTION_HOOKS: InstructionHooks = InstructionHooks::new();
+extern "C" fn instruction_hook_wrapper<I, QT, S>(idx: u64)
+where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    let helpers = unsafe { (QEMU_HELPERS_PTR as *mut QT).as_mut().unwrap() };
+    let state = inprocess_get_state::<S>().unwrap();
+    let emulator = Emulator::new_empty();
+    // A hook removed while its translated block still runs
+    if let Some((pc, hook)) = unsafe { INSTRUCTION_HOOKS.get(idx as usize) } {
+        let func: fn(&Emulator, &mut QT, &mut S, GuestAddr) = unsafe { transmute(hook) };
+        (func)(&emulator, helpers, state, pc);
+    }
+}
+
 static mut GEN_BLOCK_HOOK_PTR: *const c_void = ptr::null();
 extern "C" fn gen_block_hook_wrapper<I, QT, S>(pc: u64) -> u64
 where
@@ -69,7 +121,8 @@ where
         let helpers = (QEMU_HELPERS_PTR as *mut QT).as_mut().unwrap();
         let state = inprocess_get_state::<S>().unwrap();
         let emulator = Emulator::new_empty();
-        let func: fn(&Emulator, &mut QT, &mut S, u64) -> Option<u64> = transmute(GEN_EDGE_HOOK_PTR);
+        let func: fn(&Emulator, &mut QT, &mut S, u64) -> Option<u64> =
+            transmute(GEN_BLOCK_HOOK_PTR);
         (func)(&emulator, helpers, state, pc).map_or(SKIP_EXEC_HOOK, |id| id)
     }
 }
@@ -551,6 +604,27 @@ where
             .set_exec_edge_hook(edge_hooks_wrapper::<I, QT, S>);
     }
 
+    /// Calls the hook each time the instruction at `addr` is about to be executed
+    #[allow(clippy::unused_self)]
+    pub fn hook_instruction(
+        &self,
+        addr: GuestAddr,
+        hook: fn(&Emulator, &mut QT, &mut S, pc: GuestAddr),
+    ) {
+        let idx = unsafe { INSTRUCTION_HOOKS.add(addr, hook as *const _) };
+        self.emulator
+            .set_hook(addr, instruction_hook_wrapper::<I, QT, S>, idx as u64);
+    }
+
+    /// Removes the hooks on the instruction at `addr`
+    #[allow(clippy::unused_self)]
+    pub fn remove_instruction_hook(&self, addr: GuestAddr) {
+        self.emulator.remove_hook(addr);
+        unsafe {
+            INSTRUCTION_HOOKS.remove(addr);
+        }
+    }
+
     #[allow(clippy::unused_self)]
     pub fn hook_block_generation(
         &self,
@@ -840,3 +914,32 @@ where
         self.inner.observers_mut()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::ffi::c_void;
+
+    use super::InstructionHooks;
+
+    #[test]
+    fn test_instruction_hooks() {
+        let first = 1 as *const c_void;
+        let second = 2 as *const c_void;
+        let mut hooks = InstructionHooks::new();
+        assert_eq!(hooks.add(0x1000, first), 0);
+        assert_eq!(hooks.add(0x2000, second), 1);
+        assert_eq!(hooks.add(0x1000, second), 2);
+
+        hooks.remove(0x1000);
+        assert_eq!(hooks.get(0), None);
+        assert_eq!(hooks.get(2), None);
+        // The other hooks keep their index
+        assert_eq!(hooks.get(1), Some((0x2000, second)));
+        assert_eq!(hooks.get(3), None);
+
+        // The free slots get reused
+        assert_eq!(hooks.add(0x3000, first), 0);
+        assert_eq!(hooks.get(0), Some((0x3000, first)));
+        assert_eq!(hooks.entries.len(), 3);
+    }
+}
@@ -1,3 +1,8 @@
+//! `libafl_qemu` lets you fuzz binary-only targets under QEMU usermode, for Linux binaries of foreign architectures.
+//!
+//! Full-system emulation, for kernels and firmware, is not implemented: the pinned
+//! `qemu-libafl-bridge` only builds the `linux-user` targets.
+
 // This lint triggers too often on the current GuestAddr type when emulating 64-bit targets because
 // u64::from(GuestAddr) is a no-op, but the .into() call is needed when GuestAddr is u32.
 #![cfg_attr(
@@ -47,9 +52,17 @@ pub mod snapshot;
 #[cfg(target_os = "linux")]
 pub use snapshot::QemuSnapshotHelper;
 #[cfg(target_os = "linux")]
+pub mod drcov;
+#[cfg(target_os = "linux")]
+pub use drcov::QemuDrCovHelper;
+#[cfg(target_os = "linux")]
+pub mod syscall;
+#[cfg(target_os = "linux")]
+pub use syscall::QemuSyscallHelper;
+#[cfg(target_os = "linux")]
 pub mod asan;
 #[cfg(target_os = "linux")]
-pub use asan::{init_with_asan, QemuAsanHelper};
+pub use asan::{init_with_asan, QasanErrorsFeedback, QasanErrorsObserver, QemuAsanHelper};
 
 #[cfg(target_os = "linux")]
 pub mod executor;
@@ -1,11 +1,12 @@
 use libafl::{executors::ExitKind, inputs::Input, observers::ObserversTuple, state::HasMetadata};
 use std::collections::HashMap;
+use strum::IntoEnumIterator;
 
 use crate::{
     emu::Emulator,
     executor::QemuExecutor,
     helper::{QemuHelper, QemuHelperTuple},
-    GuestAddr, SYS_mmap, SYS_mremap,
+    GuestAddr, Regs, SYS_mmap, SYS_mremap,
 };
 
 pub const SNAPSHOT_PAGE_SIZE: usize = 4096;
@@ -25,6 +26,7 @@ pub struct QemuSnapshotHelper {
     pub pages: HashMap<GuestAddr, SnapshotPageInfo>,
     pub dirty: Vec<GuestAddr>,
     pub brk: GuestAddr,
+    pub regs: Vec<(Regs, GuestAddr)>,
     pub new_maps: Vec<(GuestAddr, usize)>,
     pub empty: bool,
 }
@@ -38,6 +40,7 @@ impl QemuSnapshotHelper {
             pages: HashMap::default(),
             dirty: vec![],
             brk: 0,
+            regs: vec![],
             new_maps: vec![],
             empty: true,
         }
@@ -45,6 +48,9 @@ impl QemuSnapshotHelper {
 
     pub fn snapshot(&mut self, emulator: &Emulator) {
         self.brk = emulator.get_brk();
+        self.regs = Regs::iter()
+            .filter_map(|reg| emulator.read_reg(reg).ok().map(|val| (reg, val)))
+            .collect();
         self.pages.clear();
         for map in emulator.mappings() {
             // TODO track all the pages OR track mproctect
@@ -87,9 +93,9 @@ impl QemuSnapshotHelper {
 
     pub fn access(&mut self, addr: GuestAddr, size: usize) {
         debug_assert!(size > 0);
-        let page = addr & (SNAPSHOT_PAGE_SIZE as GuestAddr - 1);
+        let page = addr & !(SNAPSHOT_PAGE_SIZE as GuestAddr - 1);
         self.page_access(page);
-        let second_page = (addr + size as GuestAddr - 1) & (SNAPSHOT_PAGE_SIZE as GuestAddr - 1);
+        let second_page = (addr + size as GuestAddr - 1) & !(SNAPSHOT_PAGE_SIZE as GuestAddr - 1);
         if page != second_page {
             self.page_access(second_page);
         }
@@ -106,6 +112,14 @@ impl QemuSnapshotHelper {
         }
         emulator.set_brk(self.brk);
         self.reset_maps(emulator);
+        self.reset_regs(emulator);
+    }
+
+    /// Restores the registers to their state at the time of the snapshot
+    pub fn reset_regs(&self, emulator: &Emulator) {
+        for (reg, val) in &self.regs {
+            drop(emulator.write_reg(*reg, *val));
+        }
     }
 
     pub fn add_mapped(&mut self, start: GuestAddr, size: usize) {
@@ -274,3 +288,64 @@ where
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        snapshot::{QemuSnapshotHelper, SnapshotPageInfo, SNAPSHOT_PAGE_SIZE},
+        GuestAddr,
+    };
+
+    fn snapshot_page(helper: &mut QemuSnapshotHelper, addr: GuestAddr) {
+        helper.pages.insert(
+            addr,
+            SnapshotPageInfo {
+                addr,
+                dirty: false,
+                data: [0; SNAPSHOT_PAGE_SIZE],
+            },
+        );
+    }
+
+    #[test]
+    fn test_snapshot_dirty_pages() {
+        let mut helper = QemuSnapshotHelper::new();
+        for addr in [0x1000, 0x2000, 0x3000] {
+            snapshot_page(&mut helper, addr);
+        }
+
+        // A write within a page dirties it once
+        helper.access(0x1010, 8);
+        helper.access(0x1020, 4);
+        assert_eq!(helper.dirty, [0x1000]);
+        assert!(helper.pages[&0x1000].dirty);
+
+        // A write across pages dirties both
+        helper.access(0x2ffe, 4);
+        assert_eq!(helper.dirty, [0x1000, 0x2000, 0x3000]);
+
+        // Out of the access cache, the page is still known as dirty
+        for page in [0x10000, 0x11000, 0x12000, 0x13000] {
+            helper.access(page, 1);
+        }
+        helper.access(0x1000, 1);
+        assert_eq!(
+            helper.dirty.iter().filter(|page| **page == 0x1000).count(),
+            1
+        );
+        assert!(!helper.pages.contains_key(&0x10000));
+        assert_eq!(helper.dirty.len(), 7);
+    }
+
+    #[test]
+    fn test_snapshot_new_maps() {
+        let mut helper = QemuSnapshotHelper::new();
+        assert!(helper.empty);
+        helper.add_mapped(0x4000_0000, 0x2000);
+        helper.add_mapped(0x5000_0000, SNAPSHOT_PAGE_SIZE);
+        assert_eq!(
+            helper.new_maps,
+            [(0x4000_0000, 0x2000), (0x5000_0000, SNAPSHOT_PAGE_SIZE)]
+        );
+    }
+}
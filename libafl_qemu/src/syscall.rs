@@ -0,0 +1,365 @@
+//! A syscall virtualization layer for the emulated target.
+//!
+//! The [`QemuSyscallHelper`] serves reads of a file (or of `stdin`) from the current input,
+//! stubs the network and fixes the time sources, so that binary-only targets can be fuzzed
+//! without patching them or writing the input to disk.
+//!
+//! The input gets opened from an in-memory file holding it, so that all the syscalls on the
+//! file descriptor, such as `read`, `pread64`, `lseek`, `fstat` and `mmap`, see the input.
+
+use core::mem::size_of;
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::CStr,
+};
+
+use libafl::{
+    bolts::AsSlice,
+    executors::ExitKind,
+    inputs::{HasTargetBytes, Input},
+    observers::ObserversTuple,
+    Error,
+};
+
+#[cfg(not(cpu_target = "aarch64"))]
+use crate::SYS_open;
+use crate::{
+    emu::{Emulator, SyscallHookResult},
+    executor::QemuExecutor,
+    helper::{QemuHelper, QemuHelperTuple},
+    GuestAddr, SYS_bind, SYS_clock_gettime, SYS_close, SYS_connect, SYS_gettimeofday, SYS_listen,
+    SYS_openat, SYS_socket,
+};
+
+/// Turns an errno into the return value of a failing syscall
+#[allow(clippy::cast_sign_loss)]
+const fn syscall_error(errno: i32) -> u64 {
+    -(errno as i64) as u64
+}
+
+/// A [`QemuHelper`] that virtualizes some of the syscalls of the target
+#[derive(Debug, Default)]
+pub struct QemuSyscallHelper {
+    /// The current input
+    input: Vec<u8>,
+    /// Reads of this file are served from the input
+    input_file: Option<String>,
+    /// Reads of `stdin` are served from the input
+    input_stdin: bool,
+    /// The in-memory file holding the current input
+    input_memfd: Option<i32>,
+    /// The file descriptors of the input opened by the target in the current run
+    input_fds: HashSet<i32>,
+    /// Fail all network syscalls
+    stub_network: bool,
+    /// The fixed time, in seconds since the epoch
+    fixed_time: Option<u64>,
+    /// Syscalls that get skipped, returning the given value
+    results: HashMap<i32, u64>,
+}
+
+impl QemuSyscallHelper {
+    /// Creates a new [`QemuSyscallHelper`] that lets all syscalls through
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serves the reads of the file at `path` from the input.
+    /// Opening the file always succeeds, whether it exists or not.
+    #[must_use]
+    pub fn input_file(mut self, path: &str) -> Self {
+        self.input_file = Some(path.to_string());
+        self
+    }
+
+    /// Serves the reads of `stdin` from the input
+    #[must_use]
+    pub fn input_stdin(mut self) -> Self {
+        self.input_stdin = true;
+        self
+    }
+
+    /// Makes all attempts to use the network fail with `ENETUNREACH`
+    #[must_use]
+    pub fn stub_network(mut self) -> Self {
+        self.stub_network = true;
+        self
+    }
+
+    /// Fixes the wall clock and the monotonic clocks of the target to the given time
+    #[must_use]
+    pub fn fixed_time(mut self, secs: u64) -> Self {
+        self.fixed_time = Some(secs);
+        self
+    }
+
+    /// Skips the syscall `sys_num`, returning `result` instead.
+    /// Use negative errno values to make it fail.
+    #[must_use]
+    pub fn syscall_result(mut self, sys_num: i32, result: u64) -> Self {
+        self.results.insert(sys_num, result);
+        self
+    }
+
+    fn is_input_path(&self, emulator: &Emulator, addr: u64) -> bool {
+        match &self.input_file {
+            Some(path) if addr != 0 => {
+                let guest_path =
+                    unsafe { CStr::from_ptr(emulator.g2h::<libc::c_char>(addr as GuestAddr)) };
+                guest_path.to_bytes() == path.as_bytes()
+            }
+            _ => false,
+        }
+    }
+
+    /// Writes the input to the in-memory file, created on the first run
+    fn write_input(&mut self) -> Result<i32, Error> {
+        let memfd = match self.input_memfd {
+            Some(memfd) => memfd,
+            None => {
+                let memfd = unsafe { libc::memfd_create(b"input\0".as_ptr() as *const _, 0) };
+                if memfd < 0 {
+                    return Err(Error::Unknown(
+                        "Could not create the in-memory input file".to_string(),
+                    ));
+                }
+                self.input_memfd = Some(memfd);
+                memfd
+            }
+        };
+        let written = unsafe {
+            libc::ftruncate(memfd, 0);
+            libc::pwrite(memfd, self.input.as_ptr() as *const _, self.input.len(), 0)
+        };
+        if usize::try_from(written) == Ok(self.input.len()) {
+            Ok(memfd)
+        } else {
+            Err(Error::Unknown(
+                "Could not write the in-memory input file".to_string(),
+            ))
+        }
+    }
+
+    /// Opens the input, with an offset of its own
+    fn open_input(&mut self) -> u64 {
+        let memfd = match self.input_memfd {
+            Some(memfd) => memfd,
+            None => return syscall_error(libc::ENOENT),
+        };
+        let path = format!("/proc/self/fd/{}\0", memfd);
+        let fd = unsafe { libc::open(path.as_ptr() as *const _, libc::O_RDONLY) };
+        if fd < 0 {
+            return syscall_error(libc::EMFILE);
+        }
+        self.input_fds.insert(fd);
+        fd as u64
+    }
+
+    /// Writes two guest words, as in `struct timespec` and `struct timeval`
+    fn write_time(emulator: &Emulator, addr: u64, secs: u64) {
+        if addr == 0 {
+            return;
+        }
+        let word = size_of::<GuestAddr>();
+        let mut buf = [0; 2 * size_of::<GuestAddr>()];
+        buf[..word].copy_from_slice(&(secs as GuestAddr).to_le_bytes());
+        unsafe {
+            emulator.write_mem(addr as GuestAddr, &buf);
+        }
+    }
+
+    /// Handles a syscall of the target, returns the result if the syscall is virtualized
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn syscall(&mut self, emulator: &Emulator, sys_num: i32, a0: u64, a1: u64) -> Option<u64> {
+        if let Some(result) = self.results.get(&sys_num) {
+            return Some(*result);
+        }
+        let sys = i64::from(sys_num);
+        #[cfg(not(cpu_target = "aarch64"))]
+        if sys == SYS_open && self.is_input_path(emulator, a0) {
+            return Some(self.open_input());
+        }
+        if sys == SYS_openat && self.is_input_path(emulator, a1) {
+            Some(self.open_input())
+        } else if sys == SYS_close {
+            // The input gets closed by the real syscall
+            self.input_fds.remove(&(a0 as i32));
+            None
+        } else if self.stub_network
+            && (sys == SYS_socket || sys == SYS_connect || sys == SYS_bind || sys == SYS_listen)
+        {
+            Some(syscall_error(libc::ENETUNREACH))
+        } else if let Some(secs) = self.fixed_time {
+            if sys == SYS_clock_gettime {
+                Self::write_time(emulator, a1, secs);
+                Some(0)
+            } else if sys == SYS_gettimeofday {
+                Self::write_time(emulator, a0, secs);
+                Some(0)
+            } else {
+                self.fixed_time_legacy(emulator, sys, a0, secs)
+            }
+        } else {
+            None
+        }
+    }
+
+    /// The `time` syscall, only present on some architectures
+    #[cfg(any(cpu_target = "x86_64", cpu_target = "i386"))]
+    #[allow(clippy::unused_self)]
+    fn fixed_time_legacy(&self, emulator: &Emulator, sys: i64, a0: u64, secs: u64) -> Option<u64> {
+        if sys == crate::SYS_time {
+            if a0 != 0 {
+                unsafe {
+                    emulator.write_mem(a0 as GuestAddr, &(secs as GuestAddr).to_le_bytes());
+                }
+            }
+            Some(secs)
+        } else {
+            None
+        }
+    }
+
+    /// The `time` syscall, only present on some architectures
+    #[cfg(not(any(cpu_target = "x86_64", cpu_target = "i386")))]
+    #[allow(clippy::unused_self)]
+    fn fixed_time_legacy(
+        &self,
+        _emulator: &Emulator,
+        _sys: i64,
+        _a0: u64,
+        _secs: u64,
+    ) -> Option<u64> {
+        None
+    }
+}
+
+impl<I, S> QemuHelper<I, S> for QemuSyscallHelper
+where
+    I: Input + HasTargetBytes,
+{
+    fn init<'a, H, OT, QT>(&self, executor: &QemuExecutor<'a, H, I, OT, QT, S>)
+    where
+        H: FnMut(&I) -> ExitKind,
+        OT: ObserversTuple<I, S>,
+        QT: QemuHelperTuple<I, S>,
+    {
+        executor.hook_syscalls(syscall_virt_hook::<I, QT, S>);
+    }
+
+    fn pre_exec(&mut self, _emulator: &Emulator, input: &I) {
+        self.input.clear();
+        self.input
+            .extend_from_slice(input.target_bytes().as_slice());
+        // The inputs the last run left open are stale
+        for fd in self.input_fds.drain() {
+            unsafe {
+                libc::close(fd);
+            }
+        }
+        if self.input_file.is_none() && !self.input_stdin {
+            return;
+        }
+        let memfd = self
+            .write_input()
+            .expect("Could not write the input to memory");
+        if self.input_stdin {
+            // A fresh stdin, reading the input from its start
+            let path = format!("/proc/self/fd/{}\0", memfd);
+            unsafe {
+                let fd = libc::open(path.as_ptr() as *const _, libc::O_RDONLY);
+                assert!(fd >= 0, "Could not open the input as stdin");
+                libc::dup2(fd, 0);
+                libc::close(fd);
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn syscall_virt_hook<I, QT, S>(
+    emulator: &Emulator,
+    helpers: &mut QT,
+    _state: &mut S,
+    sys_num: i32,
+    a0: u64,
+    a1: u64,
+    _a2: u64,
+    _a3: u64,
+    _a4: u64,
+    _a5: u64,
+    _a6: u64,
+    _a7: u64,
+) -> SyscallHookResult
+where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    let h = helpers.match_first_type_mut::<QemuSyscallHelper>().unwrap();
+    SyscallHookResult::new(h.syscall(emulator, sys_num, a0, a1))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        emu::Emulator,
+        syscall::{syscall_error, QemuSyscallHelper},
+        SYS_close, SYS_connect, SYS_socket,
+    };
+
+    #[test]
+    fn test_syscall_results() {
+        // None of these syscalls reach the emulator
+        let emulator = Emulator::new_empty();
+        let mut helper = QemuSyscallHelper::new()
+            .stub_network()
+            .syscall_result(SYS_socket as i32, syscall_error(libc::EPERM));
+        assert_eq!(
+            helper.syscall(&emulator, SYS_socket as i32, 0, 0),
+            Some(syscall_error(libc::EPERM))
+        );
+        assert_eq!(
+            helper.syscall(&emulator, SYS_connect as i32, 0, 0),
+            Some(syscall_error(libc::ENETUNREACH))
+        );
+        assert_eq!(helper.syscall(&emulator, SYS_close as i32, 3, 0), None);
+        assert_eq!(syscall_error(libc::ENOENT) as i64, -i64::from(libc::ENOENT));
+    }
+
+    #[test]
+    fn test_syscall_input_fds() {
+        let emulator = Emulator::new_empty();
+        let mut helper = QemuSyscallHelper::new();
+        // Without the in-memory file, there is no input to open
+        assert_eq!(helper.open_input(), syscall_error(libc::ENOENT));
+
+        helper.input = b"first input".to_vec();
+        helper.write_input().unwrap();
+        helper.input = b"input".to_vec();
+        helper.write_input().unwrap();
+
+        let fd = helper.open_input() as i32;
+        assert!(fd >= 0);
+        assert!(helper.input_fds.contains(&fd));
+        let mut buf = [0; 16];
+        let read = unsafe { libc::read(fd, buf.as_mut_ptr() as *mut _, buf.len()) };
+        assert_eq!(&buf[..read as usize], b"input");
+
+        // Each open has its own offset
+        let other_fd = helper.open_input() as i32;
+        let read = unsafe { libc::read(other_fd, buf.as_mut_ptr() as *mut _, buf.len()) };
+        assert_eq!(read, 5);
+
+        for fd in [fd, other_fd] {
+            assert_eq!(
+                helper.syscall(&emulator, SYS_close as i32, fd as u64, 0),
+                None
+            );
+            assert!(!helper.input_fds.contains(&fd));
+            unsafe {
+                libc::close(fd);
+            }
+        }
+    }
+}
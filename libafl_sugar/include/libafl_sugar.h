@@ -0,0 +1,61 @@
+#ifndef __LIBAFL_SUGAR__
+#define __LIBAFL_SUGAR__
+
+// C API of libafl_sugar, link with the libafl_sugar shared library.
+// The target still needs the coverage instrumentation of libafl_targets
+// (e.g. -fsanitize-coverage=trace-pc-guard).
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Return codes of the C API
+#define LIBAFL_OK 0
+#define LIBAFL_ERR_INVALID_ARGUMENT (-1)
+#define LIBAFL_ERR_NO_HARNESS (-2)
+#define LIBAFL_ERR_IO (-3)
+#define LIBAFL_ERR_FUZZER (-4)
+
+typedef struct LibaflFuzzer LibaflFuzzer;
+
+typedef void (*libafl_harness_fn)(const uint8_t *data, size_t len,
+                                  void *user_data);
+
+// Creates a new fuzzer, storing its queue and crashes in output_dir.
+// Returns NULL on error.
+LibaflFuzzer *libafl_fuzzer_new(const char *output_dir);
+void          libafl_fuzzer_free(LibaflFuzzer *fuzzer);
+
+// The setters returning int return LIBAFL_OK on success,
+// LIBAFL_ERR_INVALID_ARGUMENT on error.
+int  libafl_fuzzer_add_input_dir(LibaflFuzzer *fuzzer, const char *dir);
+// Cores in the command line format, e.g. "0,2-4" or "all"
+int  libafl_fuzzer_set_cores(LibaflFuzzer *fuzzer, const char *cores);
+void libafl_fuzzer_set_broker_port(LibaflFuzzer *fuzzer, uint16_t port);
+void libafl_fuzzer_set_timeout(LibaflFuzzer *fuzzer, uint64_t secs);
+int  libafl_fuzzer_set_tokens_file(LibaflFuzzer *fuzzer, const char *path);
+void libafl_fuzzer_set_cmplog(LibaflFuzzer *fuzzer, bool enable);
+void libafl_fuzzer_set_iterations(LibaflFuzzer *fuzzer, uint64_t iterations);
+void libafl_fuzzer_set_harness(LibaflFuzzer *fuzzer, libafl_harness_fn harness,
+                               void *user_data);
+
+// Runs the fuzzer. The calling process becomes the broker of the fuzzing
+// clients, and only returns when the user stops the fuzzer (LIBAFL_OK),
+// or on errors: LIBAFL_ERR_NO_HARNESS without a harness, LIBAFL_ERR_IO
+// if the output dir can't be created, LIBAFL_ERR_FUZZER otherwise.
+int libafl_fuzzer_run(LibaflFuzzer *fuzzer);
+
+// Custom stats, to be called from the harness, shown by the monitor
+void libafl_report_stat(const char *name, uint64_t value);
+void libafl_report_stat_ratio(const char *name, uint64_t value, uint64_t total);
+void libafl_report_stat_string(const char *name, const char *value);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,501 @@
+//! C API of the sugar, to embed `LibAFL` into existing C/C++ harness infrastructure.
+//!
+//! The API wraps the [`InMemoryBytesCoverageSugar`]: create a fuzzer, configure it, register the
+//! harness callback, and run the fuzzing loop. The harness can report custom stats, shown by the
+//! monitor next to the builtin ones. See `include/libafl_sugar.h` for the declarations.
+
+use core::{
+    ffi::c_void,
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+};
+use std::{
+    ffi::CStr,
+    os::raw::{c_char, c_int},
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    sync::{Mutex, PoisonError},
+};
+
+use libafl::{
+    bolts::os::Cores,
+    events::{Event, EventFirer},
+    inputs::BytesInput,
+    monitors::UserStats,
+    stages::Stage,
+    Error,
+};
+
+use crate::InMemoryBytesCoverageSugar;
+
+/// The harness callback, called with the input and the user data given at registration
+pub type LibaflHarnessFn = extern "C" fn(data: *const u8, len: usize, user_data: *mut c_void);
+
+/// The return code of the C API on success
+pub const LIBAFL_OK: c_int = 0;
+/// The return code of the C API for an invalid argument, e.g. a null pointer or a string that
+/// is not UTF-8
+pub const LIBAFL_ERR_INVALID_ARGUMENT: c_int = -1;
+/// The return code of [`libafl_fuzzer_run`] without a harness
+pub const LIBAFL_ERR_NO_HARNESS: c_int = -2;
+/// The return code of [`libafl_fuzzer_run`] for an I/O error, e.g. on the output directory
+pub const LIBAFL_ERR_IO: c_int = -3;
+/// The return code of [`libafl_fuzzer_run`] for the other errors of the fuzzer
+pub const LIBAFL_ERR_FUZZER: c_int = -4;
+
+/// The return code of the C API for `err`
+fn error_code(err: &Error) -> c_int {
+    match err {
+        Error::File(_) => LIBAFL_ERR_IO,
+        Error::IllegalArgument(_) => LIBAFL_ERR_INVALID_ARGUMENT,
+        _ => LIBAFL_ERR_FUZZER,
+    }
+}
+
+/// The stats reported by the harness, not yet sent to the monitor.
+/// The harness may report them from any thread.
+static PENDING_STATS: Mutex<Vec<(String, UserStats)>> = Mutex::new(Vec::new());
+
+/// Reports a custom stat, sent to the monitor by the next [`UserStatsStage`]
+pub fn report_user_stat(name: &str, value: UserStats) {
+    PENDING_STATS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push((name.to_string(), value));
+}
+
+/// Takes the stats reported since the last call
+fn take_user_stats() -> Vec<(String, UserStats)> {
+    core::mem::take(&mut *PENDING_STATS.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+/// A stage sending the stats reported by the harness with [`report_user_stat`] to the monitor
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UserStatsStage;
+
+impl UserStatsStage {
+    /// Creates a new [`UserStatsStage`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<E, EM, S, Z> Stage<E, EM, S, Z> for UserStatsStage
+where
+    EM: EventFirer<BytesInput>,
+{
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        for (name, value) in take_user_stats() {
+            manager.fire(
+                state,
+                Event::UpdateUserStats {
+                    name,
+                    value,
+                    phantom: PhantomData,
+                },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The configuration of a fuzzer created through the C API
+pub struct LibaflFuzzer {
+    output_dir: PathBuf,
+    input_dirs: Vec<PathBuf>,
+    cores: Cores,
+    broker_port: u16,
+    timeout: Option<u64>,
+    tokens_file: Option<PathBuf>,
+    use_cmplog: Option<bool>,
+    iterations: Option<u64>,
+    harness: Option<(LibaflHarnessFn, *mut c_void)>,
+}
+
+impl Debug for LibaflFuzzer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LibaflFuzzer")
+            .field("output_dir", &self.output_dir)
+            .field("input_dirs", &self.input_dirs)
+            .field("cores", &self.cores)
+            .field("broker_port", &self.broker_port)
+            .field("timeout", &self.timeout)
+            .field("tokens_file", &self.tokens_file)
+            .field("use_cmplog", &self.use_cmplog)
+            .field("iterations", &self.iterations)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reads a C string, `None` if null or not valid UTF-8
+unsafe fn c_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        None
+    } else {
+        CStr::from_ptr(s).to_str().ok()
+    }
+}
+
+/// Creates a new fuzzer, storing its queue and crashes in `output_dir`.
+/// Returns null if `output_dir` is not a valid string.
+///
+/// # Safety
+/// `output_dir` needs to be a nul-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn libafl_fuzzer_new(output_dir: *const c_char) -> *mut LibaflFuzzer {
+    let output_dir = match c_str(output_dir) {
+        Some(output_dir) => output_dir,
+        None => return core::ptr::null_mut(),
+    };
+    Box::into_raw(Box::new(LibaflFuzzer {
+        output_dir: PathBuf::from(output_dir),
+        input_dirs: vec![],
+        cores: Cores::from_cmdline("0").unwrap(),
+        broker_port: 1337,
+        timeout: None,
+        tokens_file: None,
+        use_cmplog: None,
+        iterations: None,
+        harness: None,
+    }))
+}
+
+/// Frees a fuzzer created with [`libafl_fuzzer_new`]
+///
+/// # Safety
+/// `fuzzer` needs to come from [`libafl_fuzzer_new`], and must not be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn libafl_fuzzer_free(fuzzer: *mut LibaflFuzzer) {
+    if !fuzzer.is_null() {
+        drop(Box::from_raw(fuzzer));
+    }
+}
+
+/// Adds a directory of initial inputs. Returns [`LIBAFL_OK`], or [`LIBAFL_ERR_INVALID_ARGUMENT`].
+///
+/// # Safety
+/// `fuzzer` needs to come from [`libafl_fuzzer_new`], `dir` needs to be a nul-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn libafl_fuzzer_add_input_dir(
+    fuzzer: *mut LibaflFuzzer,
+    dir: *const c_char,
+) -> c_int {
+    match (fuzzer.as_mut(), c_str(dir)) {
+        (Some(fuzzer), Some(dir)) => {
+            fuzzer.input_dirs.push(PathBuf::from(dir));
+            LIBAFL_OK
+        }
+        _ => LIBAFL_ERR_INVALID_ARGUMENT,
+    }
+}
+
+/// Sets the cores to run on, in the format of the command line of the fuzzers, e.g. `0,2-4` or
+/// `all`. Returns [`LIBAFL_OK`], or [`LIBAFL_ERR_INVALID_ARGUMENT`].
+///
+/// # Safety
+/// `fuzzer` needs to come from [`libafl_fuzzer_new`], `cores` needs to be a nul-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn libafl_fuzzer_set_cores(
+    fuzzer: *mut LibaflFuzzer,
+    cores: *const c_char,
+) -> c_int {
+    match (fuzzer.as_mut(), c_str(cores).map(Cores::from_cmdline)) {
+        (Some(fuzzer), Some(Ok(cores))) => {
+            fuzzer.cores = cores;
+            LIBAFL_OK
+        }
+        _ => LIBAFL_ERR_INVALID_ARGUMENT,
+    }
+}
+
+/// Sets the port of the broker
+///
+/// # Safety
+/// `fuzzer` needs to come from [`libafl_fuzzer_new`]
+#[no_mangle]
+pub unsafe extern "C" fn libafl_fuzzer_set_broker_port(fuzzer: *mut LibaflFuzzer, port: u16) {
+    if let Some(fuzzer) = fuzzer.as_mut() {
+        fuzzer.broker_port = port;
+    }
+}
+
+/// Sets the timeout of a run, in seconds
+///
+/// # Safety
+/// `fuzzer` needs to come from [`libafl_fuzzer_new`]
+#[no_mangle]
+pub unsafe extern "C" fn libafl_fuzzer_set_timeout(fuzzer: *mut LibaflFuzzer, secs: u64) {
+    if let Some(fuzzer) = fuzzer.as_mut() {
+        fuzzer.timeout = Some(secs);
+    }
+}
+
+/// Sets the dictionary. Returns [`LIBAFL_OK`], or [`LIBAFL_ERR_INVALID_ARGUMENT`].
+///
+/// # Safety
+/// `fuzzer` needs to come from [`libafl_fuzzer_new`], `path` needs to be a nul-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn libafl_fuzzer_set_tokens_file(
+    fuzzer: *mut LibaflFuzzer,
+    path: *const c_char,
+) -> c_int {
+    match (fuzzer.as_mut(), c_str(path)) {
+        (Some(fuzzer), Some(path)) => {
+            fuzzer.tokens_file = Some(PathBuf::from(path));
+            LIBAFL_OK
+        }
+        _ => LIBAFL_ERR_INVALID_ARGUMENT,
+    }
+}
+
+/// Enables the input-to-state stages, for targets instrumented with `CmpLog`
+///
+/// # Safety
+/// `fuzzer` needs to come from [`libafl_fuzzer_new`]
+#[no_mangle]
+pub unsafe extern "C" fn libafl_fuzzer_set_cmplog(fuzzer: *mut LibaflFuzzer, enable: bool) {
+    if let Some(fuzzer) = fuzzer.as_mut() {
+        fuzzer.use_cmplog = Some(enable);
+    }
+}
+
+/// Fuzzes for the given number of iterations instead of indefinitely
+///
+/// # Safety
+/// `fuzzer` needs to come from [`libafl_fuzzer_new`]
+#[no_mangle]
+pub unsafe extern "C" fn libafl_fuzzer_set_iterations(fuzzer: *mut LibaflFuzzer, iterations: u64) {
+    if let Some(fuzzer) = fuzzer.as_mut() {
+        fuzzer.iterations = Some(iterations);
+    }
+}
+
+/// Registers the harness, called with each input and `user_data`
+///
+/// # Safety
+/// `fuzzer` needs to come from [`libafl_fuzzer_new`], `user_data` needs to stay valid while
+/// the fuzzer runs
+#[no_mangle]
+pub unsafe extern "C" fn libafl_fuzzer_set_harness(
+    fuzzer: *mut LibaflFuzzer,
+    harness: LibaflHarnessFn,
+    user_data: *mut c_void,
+) {
+    if let Some(fuzzer) = fuzzer.as_mut() {
+        fuzzer.harness = Some((harness, user_data));
+    }
+}
+
+/// Runs the fuzzing loop. The calling process becomes the broker of the fuzzing clients, spawned
+/// on the cores, so this only returns when the user stops the fuzzer, with [`LIBAFL_OK`], or on
+/// errors. With iterations, the clients exit after them, but the broker keeps running.
+/// Returns [`LIBAFL_ERR_INVALID_ARGUMENT`] for a null `fuzzer`, [`LIBAFL_ERR_NO_HARNESS`]
+/// without a harness, [`LIBAFL_ERR_IO`] if the output directory can't be created, and
+/// [`LIBAFL_ERR_FUZZER`] if the fuzzer failed.
+///
+/// # Safety
+/// `fuzzer` needs to come from [`libafl_fuzzer_new`]
+#[no_mangle]
+pub unsafe extern "C" fn libafl_fuzzer_run(fuzzer: *mut LibaflFuzzer) -> c_int {
+    let fuzzer = match fuzzer.as_mut() {
+        Some(fuzzer) => fuzzer,
+        None => return LIBAFL_ERR_INVALID_ARGUMENT,
+    };
+    let (harness, user_data) = match fuzzer.harness {
+        Some(harness) => harness,
+        None => return LIBAFL_ERR_NO_HARNESS,
+    };
+    // Panics must not unwind into the C caller
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        InMemoryBytesCoverageSugar::builder()
+            .input_dirs(&fuzzer.input_dirs)
+            .output_dir(fuzzer.output_dir.clone())
+            .broker_port(fuzzer.broker_port)
+            .cores(&fuzzer.cores)
+            .harness(|buf: &[u8]| harness(buf.as_ptr(), buf.len(), user_data))
+            .use_cmplog(fuzzer.use_cmplog)
+            .timeout(fuzzer.timeout)
+            .tokens_file(fuzzer.tokens_file.clone())
+            .iterations(fuzzer.iterations)
+            .build()
+            .try_run()
+    }));
+    match res {
+        Ok(Ok(())) => LIBAFL_OK,
+        Ok(Err(err)) => error_code(&err),
+        Err(_) => LIBAFL_ERR_FUZZER,
+    }
+}
+
+/// Reports a custom numeric stat from the harness, shown by the monitor
+///
+/// # Safety
+/// `name` needs to be a nul-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn libafl_report_stat(name: *const c_char, value: u64) {
+    if let Some(name) = c_str(name) {
+        report_user_stat(name, UserStats::Number(value));
+    }
+}
+
+/// Reports a custom ratio stat from the harness, such as `covered / total`, shown by the monitor
+///
+/// # Safety
+/// `name` needs to be a nul-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn libafl_report_stat_ratio(name: *const c_char, value: u64, total: u64) {
+    if let Some(name) = c_str(name) {
+        report_user_stat(name, UserStats::Ratio(value, total));
+    }
+}
+
+/// Reports a custom string stat from the harness, shown by the monitor
+///
+/// # Safety
+/// `name` and `value` need to be nul-terminated strings
+#[no_mangle]
+pub unsafe extern "C" fn libafl_report_stat_string(name: *const c_char, value: *const c_char) {
+    if let (Some(name), Some(value)) = (c_str(name), c_str(value)) {
+        report_user_stat(name, UserStats::String(value.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{ffi::c_void, ptr};
+    use std::{ffi::CString, fs, path::PathBuf, thread};
+
+    use libafl::{
+        events::{Event, EventFirer},
+        inputs::BytesInput,
+        monitors::UserStats,
+        stages::Stage,
+        Error,
+    };
+
+    use super::{
+        libafl_fuzzer_add_input_dir, libafl_fuzzer_free, libafl_fuzzer_new, libafl_fuzzer_run,
+        libafl_fuzzer_set_cores, libafl_fuzzer_set_harness, libafl_fuzzer_set_iterations,
+        libafl_fuzzer_set_timeout, libafl_report_stat, libafl_report_stat_ratio,
+        libafl_report_stat_string, report_user_stat, UserStatsStage, LIBAFL_ERR_INVALID_ARGUMENT,
+        LIBAFL_ERR_IO, LIBAFL_ERR_NO_HARNESS, LIBAFL_OK,
+    };
+
+    extern "C" fn harness(_data: *const u8, _len: usize, _user_data: *mut c_void) {}
+
+    #[test]
+    fn test_capi_fuzzer() {
+        let output_dir =
+            std::env::temp_dir().join(format!("libafl_test_capi_{}", std::process::id()));
+        // Not a directory, the fuzzer fails before starting
+        fs::write(&output_dir, b"").unwrap();
+        let c_output_dir = CString::new(output_dir.to_str().unwrap()).unwrap();
+        let dir = CString::new("./corpus").unwrap();
+        let cores = CString::new("0,2-3").unwrap();
+        let bad_cores = CString::new("zero").unwrap();
+        unsafe {
+            assert!(libafl_fuzzer_new(ptr::null()).is_null());
+            let fuzzer = libafl_fuzzer_new(c_output_dir.as_ptr());
+            assert!(!fuzzer.is_null());
+
+            assert_eq!(libafl_fuzzer_add_input_dir(fuzzer, dir.as_ptr()), LIBAFL_OK);
+            assert_eq!(
+                libafl_fuzzer_add_input_dir(fuzzer, ptr::null()),
+                LIBAFL_ERR_INVALID_ARGUMENT
+            );
+            assert_eq!(
+                libafl_fuzzer_add_input_dir(ptr::null_mut(), dir.as_ptr()),
+                LIBAFL_ERR_INVALID_ARGUMENT
+            );
+            assert_eq!(libafl_fuzzer_set_cores(fuzzer, cores.as_ptr()), LIBAFL_OK);
+            assert_eq!(
+                libafl_fuzzer_set_cores(fuzzer, bad_cores.as_ptr()),
+                LIBAFL_ERR_INVALID_ARGUMENT
+            );
+            libafl_fuzzer_set_timeout(fuzzer, 3);
+            libafl_fuzzer_set_iterations(fuzzer, 10);
+            {
+                let config = &*fuzzer;
+                assert_eq!(config.input_dirs, [PathBuf::from("./corpus")]);
+                assert_eq!(config.cores.ids.len(), 3);
+                assert_eq!(config.timeout, Some(3));
+                assert_eq!(config.iterations, Some(10));
+            }
+
+            assert_eq!(
+                libafl_fuzzer_run(ptr::null_mut()),
+                LIBAFL_ERR_INVALID_ARGUMENT
+            );
+            assert_eq!(libafl_fuzzer_run(fuzzer), LIBAFL_ERR_NO_HARNESS);
+            libafl_fuzzer_set_harness(fuzzer, harness, ptr::null_mut());
+            assert_eq!(libafl_fuzzer_run(fuzzer), LIBAFL_ERR_IO);
+            libafl_fuzzer_free(fuzzer);
+        }
+        fs::remove_file(output_dir).unwrap();
+    }
+
+    /// Records the stats fired
+    #[derive(Debug, Default)]
+    struct StatsRecorder {
+        stats: Vec<(String, UserStats)>,
+    }
+
+    impl EventFirer<BytesInput> for StatsRecorder {
+        fn fire<S>(&mut self, _state: &mut S, event: Event<BytesInput>) -> Result<(), Error> {
+            if let Event::UpdateUserStats { name, value, .. } = event {
+                self.stats.push((name, value));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_capi_stats() {
+        let name = CString::new("format").unwrap();
+        let value = CString::new("json").unwrap();
+        unsafe {
+            libafl_report_stat(name.as_ptr(), 3);
+            libafl_report_stat_ratio(name.as_ptr(), 1, 4);
+            libafl_report_stat_string(name.as_ptr(), value.as_ptr());
+            libafl_report_stat(ptr::null(), 3);
+        }
+        // From another thread of the harness
+        thread::spawn(|| report_user_stat("threads", UserStats::Number(2)))
+            .join()
+            .unwrap();
+
+        let mut mgr = StatsRecorder::default();
+        UserStatsStage::new()
+            .perform(&mut (), &mut (), &mut (), &mut mgr, 0)
+            .unwrap();
+        let stats: Vec<String> = mgr
+            .stats
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect();
+        assert_eq!(
+            stats,
+            [
+                "format: 3",
+                "format: 1/4 (25.0%)",
+                "format: json",
+                "threads: 2"
+            ]
+        );
+
+        // The stats are sent once
+        let mut mgr = StatsRecorder::default();
+        UserStatsStage::new()
+            .perform(&mut (), &mut (), &mut (), &mut mgr, 0)
+            .unwrap();
+        assert!(mgr.stats.is_empty());
+    }
+}
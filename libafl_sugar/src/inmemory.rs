@@ -37,7 +37,7 @@ use libafl::{
 
 use libafl_targets::{CmpLogObserver, CMPLOG_MAP, EDGES_MAP, MAX_EDGES_NUM};
 
-use crate::{CORPUS_CACHE_SIZE, DEFAULT_TIMEOUT_SECS};
+use crate::{capi::UserStatsStage, CORPUS_CACHE_SIZE, DEFAULT_TIMEOUT_SECS};
 
 /// In-Memory fuzzing made easy.
 /// Use this sugar for scaling `libfuzzer`-style fuzzers.
@@ -102,6 +102,7 @@ where
                     &"None"
                 },
             )
+            .field("iterations", &self.iterations)
             .finish()
     }
 }
@@ -111,9 +112,18 @@ impl<'a, H> InMemoryBytesCoverageSugar<'a, H>
 where
     H: FnMut(&[u8]),
 {
-    /// Run the fuzzer
-    #[allow(clippy::too_many_lines, clippy::similar_names)]
+    /// Run the fuzzer, panicking on errors, see [`InMemoryBytesCoverageSugar::try_run`]
     pub fn run(&mut self) {
+        if let Err(err) = self.try_run() {
+            panic!("Fuzzing failed {:?}", err);
+        }
+    }
+
+    /// Run the fuzzer. The calling process becomes the broker of the fuzzing clients, and only
+    /// returns when the user stops the fuzzer, or on errors. With `iterations`, the clients exit
+    /// after them, but the broker keeps running.
+    #[allow(clippy::too_many_lines, clippy::similar_names)]
+    pub fn try_run(&mut self) -> Result<(), Error> {
         let conf = match self.configuration.as_ref() {
             Some(name) => EventConfig::from_name(name),
             None => EventConfig::AlwaysUnique,
@@ -122,21 +132,22 @@ where
         let timeout = Duration::from_secs(self.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS));
 
         let mut out_dir = self.output_dir.clone();
-        if fs::create_dir(&out_dir).is_err() {
+        if let Err(err) = fs::create_dir(&out_dir) {
+            if !out_dir.is_dir() {
+                return Err(Error::File(err));
+            }
             println!("Out dir at {:?} already exists.", &out_dir);
-            assert!(
-                out_dir.is_dir(),
-                "Out dir at {:?} is not a valid directory!",
-                &out_dir
-            );
         }
         let mut crashes = out_dir.clone();
         crashes.push("crashes");
         out_dir.push("queue");
 
-        let mut harness_bytes = self.harness.take().unwrap();
+        let mut harness_bytes = self
+            .harness
+            .take()
+            .ok_or_else(|| Error::IllegalState("The sugar already ran".into()))?;
 
-        let shmem_provider = StdShMemProvider::new().expect("Failed to init shared memory");
+        let shmem_provider = StdShMemProvider::new()?;
 
         let monitor = MultiMonitor::new(|s| println!("{}", s));
 
@@ -268,7 +279,7 @@ where
 
                 // The order of the stages matter!
                 if self.use_cmplog.unwrap_or(false) {
-                    let mut stages = tuple_list!(tracing, i2s, mutational);
+                    let mut stages = tuple_list!(tracing, i2s, mutational, UserStatsStage::new());
                     if let Some(iters) = self.iterations {
                         fuzzer.fuzz_loop_for(
                             &mut stages,
@@ -283,7 +294,7 @@ where
                         fuzzer.fuzz_loop(&mut stages, &mut executor, &mut state, &mut mgr)?;
                     }
                 } else {
-                    let mut stages = tuple_list!(mutational);
+                    let mut stages = tuple_list!(mutational, UserStatsStage::new());
                     if let Some(iters) = self.iterations {
                         fuzzer.fuzz_loop_for(
                             &mut stages,
@@ -305,7 +316,7 @@ where
 
                 // The order of the stages matter!
                 if self.use_cmplog.unwrap_or(false) {
-                    let mut stages = tuple_list!(tracing, i2s, mutational);
+                    let mut stages = tuple_list!(tracing, i2s, mutational, UserStatsStage::new());
                     if let Some(iters) = self.iterations {
                         fuzzer.fuzz_loop_for(
                             &mut stages,
@@ -320,7 +331,7 @@ where
                         fuzzer.fuzz_loop(&mut stages, &mut executor, &mut state, &mut mgr)?;
                     }
                 } else {
-                    let mut stages = tuple_list!(mutational);
+                    let mut stages = tuple_list!(mutational, UserStatsStage::new());
                     if let Some(iters) = self.iterations {
                         fuzzer.fuzz_loop_for(
                             &mut stages,
@@ -351,9 +362,12 @@ where
         #[cfg(unix)]
         let launcher = launcher.stdout_file(Some("/dev/null"));
         match launcher.build().launch() {
-            Ok(()) => (),
-            Err(Error::ShuttingDown) => println!("\nFuzzing stopped by user. Good Bye."),
-            Err(err) => panic!("Fuzzingg failed {:?}", err),
+            Ok(()) => Ok(()),
+            Err(Error::ShuttingDown) => {
+                println!("\nFuzzing stopped by user. Good Bye.");
+                Ok(())
+            }
+            Err(err) => Err(err),
         }
     }
 }
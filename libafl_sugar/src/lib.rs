@@ -1,4 +1,33 @@
 //! Sugar API to simplify the life of the naive user of `LibAFL`
+//!
+//! Each sugar is a builder for a common fuzzer archetype, assembling the state, the scheduler,
+//! the feedbacks, the stages and the launcher from a handful of options:
+//! * [`InMemoryBytesCoverageSugar`], an in-process fuzzer for `libfuzzer`-style harnesses
+//!   instrumented with `SanitizerCoverage`,
+//! * `ForkserverBytesCoverageSugar`, on Unix, a fuzzer for binaries instrumented with an AFL
+//!   forkserver,
+//! * `QemuBytesCoverageSugar`, on Linux, an in-process fuzzer for binaries emulated with `QEMU`
+//!   usermode.
+//!
+//! ```rust,ignore
+//! use std::path::PathBuf;
+//!
+//! use libafl::bolts::os::Cores;
+//! use libafl_sugar::InMemoryBytesCoverageSugar;
+//! use libafl_targets::libfuzzer_test_one_input;
+//!
+//! InMemoryBytesCoverageSugar::builder()
+//!     .input_dirs(&[PathBuf::from("./input")])
+//!     .output_dir(PathBuf::from("./output"))
+//!     .cores(&Cores::all().unwrap())
+//!     .harness(|buf| {
+//!         libfuzzer_test_one_input(buf);
+//!     })
+//!     .build()
+//!     .run();
+//! ```
+//!
+//! The [`capi`] exposes the in-process sugar to C and C++ harnesses.
 
 #![deny(rustdoc::broken_intra_doc_links)]
 #![deny(clippy::pedantic)]
@@ -56,6 +85,8 @@
     )
 )]
 
+pub mod capi;
+
 pub mod inmemory;
 pub use inmemory::InMemoryBytesCoverageSugar;
 
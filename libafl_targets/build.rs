@@ -80,6 +80,14 @@ fn main() {
             .compile("sancov_cmp");
     }
 
+    println!("cargo:rerun-if-changed=src/sancov_div_gep.c");
+    println!("cargo:rerun-if-changed=src/value_profile.h");
+
+    cc::Build::new()
+        .define("CMP_MAP_SIZE", Some(&*format!("{}", cmp_map_size)))
+        .file(src_dir.join("sancov_div_gep.c"))
+        .compile("sancov_div_gep");
+
     #[cfg(feature = "libfuzzer")]
     {
         println!("cargo:rerun-if-changed=src/libfuzzer.c");
@@ -89,6 +97,20 @@ fn main() {
             .compile("libfuzzer");
     }
 
+    #[cfg(any(
+        feature = "sancov_pcguard_edges",
+        feature = "sancov_pcguard_hitcounts",
+        feature = "sancov_8bit",
+        feature = "sancov_pcs"
+    ))]
+    if env::var("CARGO_CFG_TARGET_OS").map_or(false, |os| os == "windows") {
+        println!("cargo:rerun-if-changed=src/sancov_win_sections.c");
+
+        cc::Build::new()
+            .file(src_dir.join("sancov_win_sections.c"))
+            .compile("sancov_win_sections");
+    }
+
     println!("cargo:rerun-if-changed=src/common.h");
     println!("cargo:rerun-if-changed=src/common.c");
 
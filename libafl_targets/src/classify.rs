@@ -0,0 +1,201 @@
+//! `AFL`-style classification of hit counts, applied right on the coverage maps of the target.
+//!
+//! Raw hit counts are put into the buckets `1`, `2`, `3`, `4-7`, `8-15`, `16-31`, `32-127` and `128+`,
+//! so that feedbacks only see a change once a count moves to another bucket, as in `AFL`.
+
+use alloc::string::String;
+use core::fmt::{self, Debug, Formatter};
+
+use libafl::{
+    bolts::{ownedref::OwnedSliceMut, tuples::Named, AsMutSlice, AsSlice},
+    executors::ExitKind,
+    observers::Observer,
+    Error,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::coverage::edges_max_num;
+
+/// Returns the bucket of a single hit count
+const fn count_class(count: u8) -> u8 {
+    match count {
+        0 => 0,
+        1 => 1,
+        2 => 2,
+        3 => 4,
+        4..=7 => 8,
+        8..=15 => 16,
+        16..=31 => 32,
+        32..=127 => 64,
+        _ => 128,
+    }
+}
+
+/// Builds the lookup table for all possible hit counts
+const fn count_class_lookup() -> [u8; 256] {
+    let mut lookup = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        lookup[i] = count_class(i as u8);
+        i += 1;
+    }
+    lookup
+}
+
+/// The bucket for each possible hit count
+static COUNT_CLASS_LOOKUP: [u8; 256] = count_class_lookup();
+
+/// Classifies all hit counts in the given map, in place.
+/// Runs of zero entries (the vast majority, usually) are skipped a word at a time.
+pub fn classify_counts(map: &mut [u8]) {
+    let mut chunks = map.chunks_exact_mut(8);
+    for chunk in &mut chunks {
+        if u64::from_ne_bytes(chunk.try_into().unwrap()) == 0 {
+            continue;
+        }
+        for count in chunk {
+            *count = COUNT_CLASS_LOOKUP[*count as usize];
+        }
+    }
+    for count in chunks.into_remainder() {
+        *count = COUNT_CLASS_LOOKUP[*count as usize];
+    }
+}
+
+/// Returns the part of the edges map of the target that is in use
+///
+/// # Safety
+/// With `pointer_maps`, the `EDGES_MAP_PTR` needs to point to a valid map.
+/// The returned slice aliases the edges map, just like the slices handed to map observers do.
+#[must_use]
+pub unsafe fn edges_map_in_use<'a>() -> OwnedSliceMut<'a, u8> {
+    #[cfg(feature = "pointer_maps")]
+    {
+        let len = edges_max_num().min(crate::coverage::EDGES_MAP_PTR_SIZE);
+        OwnedSliceMut::from_raw_parts_mut(crate::coverage::EDGES_MAP_PTR, len)
+    }
+    #[cfg(not(feature = "pointer_maps"))]
+    {
+        let len = edges_max_num().min(crate::coverage::EDGES_MAP.len());
+        OwnedSliceMut::from_raw_parts_mut(crate::coverage::EDGES_MAP.as_mut_ptr(), len)
+    }
+}
+
+/// Classifies the hit counts in the edges map of the target, in place.
+///
+/// # Safety
+/// See [`edges_map_in_use`].
+pub unsafe fn classify_edges_map() {
+    classify_counts(edges_map_in_use().as_mut_slice());
+}
+
+/// An [`Observer`] that classifies the hit counts of a map after each execution.
+/// Put it in front of the observer of the same map in the observers tuple, so that the map
+/// observer (and its feedbacks) only see the classified counts.
+#[derive(Serialize, Deserialize)]
+#[allow(clippy::unsafe_derive_deserialize)]
+pub struct ClassifyCountsObserver<'a> {
+    map: OwnedSliceMut<'a, u8>,
+    name: String,
+}
+
+impl<'a> Debug for ClassifyCountsObserver<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClassifyCountsObserver")
+            .field("name", &self.name)
+            .field("len", &self.map.as_slice().len())
+            .finish()
+    }
+}
+
+impl<'a> ClassifyCountsObserver<'a> {
+    /// Creates a new [`ClassifyCountsObserver`] for the given map
+    #[must_use]
+    pub fn new(name: &'static str, map: &'a mut [u8]) -> Self {
+        Self {
+            map: OwnedSliceMut::from(map),
+            name: name.to_string(),
+        }
+    }
+
+    /// Creates a new [`ClassifyCountsObserver`] for the edges map of the target
+    ///
+    /// # Safety
+    /// See [`edges_map_in_use`]. Create this observer after all guards have been registered.
+    #[must_use]
+    pub unsafe fn edges(name: &'static str) -> Self {
+        Self {
+            map: edges_map_in_use(),
+            name: name.to_string(),
+        }
+    }
+}
+
+impl<'a, I, S> Observer<I, S> for ClassifyCountsObserver<'a> {
+    #[inline]
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &I,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        classify_counts(self.map.as_mut_slice());
+        Ok(())
+    }
+}
+
+impl<'a> Named for ClassifyCountsObserver<'a> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl::{executors::ExitKind, observers::Observer};
+
+    use crate::classify::{classify_counts, count_class, ClassifyCountsObserver};
+
+    #[test]
+    fn test_count_class() {
+        let expected = [
+            (0, 0),
+            (1, 1),
+            (2, 2),
+            (3, 4),
+            (4, 8),
+            (7, 8),
+            (8, 16),
+            (15, 16),
+            (16, 32),
+            (31, 32),
+            (32, 64),
+            (127, 64),
+            (128, 128),
+            (255, 128),
+        ];
+        for (count, class) in expected {
+            assert_eq!(count_class(count), class);
+        }
+    }
+
+    #[test]
+    fn test_classify_counts() {
+        // Longer than a word, with a zero word and a remainder
+        let mut map = [0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 9, 0, 0, 0, 0, 200, 1, 40];
+        classify_counts(&mut map);
+        assert_eq!(
+            map,
+            [0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 16, 0, 0, 0, 0, 128, 1, 64]
+        );
+    }
+
+    #[test]
+    fn test_classify_counts_observer() {
+        let mut map = [5, 0, 17];
+        let mut observer = ClassifyCountsObserver::new("classify", &mut map);
+        Observer::<(), ()>::post_exec(&mut observer, &mut (), &(), &ExitKind::Ok).unwrap();
+        drop(observer);
+        assert_eq!(map, [8, 0, 32]);
+    }
+}
@@ -94,13 +94,16 @@ static long area_is_valid(void *ptr, size_t len) {
 
 }
 
-void __libafl_targets_cmplog_routines(uintptr_t k, uint8_t *ptr1, uint8_t *ptr2) {
+void __libafl_targets_cmplog_routines_len(uintptr_t k, uint8_t *ptr1, uint8_t *ptr2, size_t max_len) {
 
   if (!libafl_cmplog_enabled) return;
 
+  if (max_len > CMPLOG_RTN_LEN) max_len = CMPLOG_RTN_LEN;
+  if (!max_len) return;
+
   int l1, l2;
-  if ((l1 = area_is_valid(ptr1, CMPLOG_RTN_LEN)) <= 0 ||
-      (l2 = area_is_valid(ptr2, CMPLOG_RTN_LEN)) <= 0)
+  if ((l1 = area_is_valid(ptr1, max_len)) <= 0 ||
+      (l2 = area_is_valid(ptr2, max_len)) <= 0)
     return;
   int len = MIN(l1, l2);
 
@@ -123,16 +126,109 @@ void __libafl_targets_cmplog_routines(uintptr_t k, uint8_t *ptr1, uint8_t *ptr2)
 
 }
 
-void __cmplog_rtn_hook(uint8_t *ptr1, uint8_t *ptr2) {
+void __libafl_targets_cmplog_routines(uintptr_t k, uint8_t *ptr1, uint8_t *ptr2) {
+
+  __libafl_targets_cmplog_routines_len(k, ptr1, ptr2, CMPLOG_RTN_LEN);
+
+}
+
+// Log two C strings, up to (and including) the first terminator of either one
+static void __libafl_targets_cmplog_strings(uintptr_t k, uint8_t *ptr1, uint8_t *ptr2, size_t max_len) {
+
+  if (!libafl_cmplog_enabled) return;
+
+  if (max_len > CMPLOG_RTN_LEN) max_len = CMPLOG_RTN_LEN;
+
+  int l1, l2;
+  if ((l1 = area_is_valid(ptr1, max_len)) <= 0 ||
+      (l2 = area_is_valid(ptr2, max_len)) <= 0)
+    return;
+
+  size_t len = 0;
+  size_t valid = MIN(l1, l2);
+  while (len < valid) {
+    uint8_t c1 = ptr1[len], c2 = ptr2[len];
+    len++;
+    if (!c1 || !c2) break;
+  }
+
+  __libafl_targets_cmplog_routines_len(k, ptr1, ptr2, len);
+
+}
+
+static inline uintptr_t __libafl_targets_cmplog_rtn_key(uintptr_t k) {
 
-  uintptr_t k = RETADDR;
   k = (k >> 4) ^ (k << 8);
-  k &= CMPLOG_MAP_W - 1;
+  return k & (CMPLOG_MAP_W - 1);
+
+}
+
+void __cmplog_rtn_hook(uint8_t *ptr1, uint8_t *ptr2) {
 
-  __libafl_targets_cmplog_routines(k, ptr1, ptr2);
+  __libafl_targets_cmplog_routines(__libafl_targets_cmplog_rtn_key(RETADDR), ptr1, ptr2);
 
 }
 
+void __cmplog_rtn_hook_n(uint8_t *ptr1, uint8_t *ptr2, size_t len) {
+
+  __libafl_targets_cmplog_routines_len(__libafl_targets_cmplog_rtn_key(RETADDR), ptr1, ptr2, len);
+
+}
+
+void __cmplog_rtn_hook_str(uint8_t *ptr1, uint8_t *ptr2) {
+
+  __libafl_targets_cmplog_strings(__libafl_targets_cmplog_rtn_key(RETADDR), ptr1, ptr2, CMPLOG_RTN_LEN);
+
+}
+
+void __cmplog_rtn_hook_strn(uint8_t *ptr1, uint8_t *ptr2, size_t len) {
+
+  __libafl_targets_cmplog_strings(__libafl_targets_cmplog_rtn_key(RETADDR), ptr1, ptr2, len);
+
+}
+
+#if defined(__unix__) || (defined(__APPLE__) && defined(__MACH__))
+
+// Called by the interceptors of the sanitizer runtimes (ASan, libFuzzer) for
+// each call to the libc comparison functions, also in uninstrumented code.
+
+__attribute__((weak)) void __sanitizer_weak_hook_memcmp(void *called_pc, const void *s1, const void *s2, size_t n, int result) {
+
+  if (!result) return;
+  __libafl_targets_cmplog_routines_len(__libafl_targets_cmplog_rtn_key((uintptr_t)called_pc), (uint8_t *)s1, (uint8_t *)s2, n);
+
+}
+
+__attribute__((weak)) void __sanitizer_weak_hook_strncmp(void *called_pc, const char *s1, const char *s2, size_t n, int result) {
+
+  if (!result) return;
+  __libafl_targets_cmplog_strings(__libafl_targets_cmplog_rtn_key((uintptr_t)called_pc), (uint8_t *)s1, (uint8_t *)s2, n);
+
+}
+
+__attribute__((weak)) void __sanitizer_weak_hook_strcmp(void *called_pc, const char *s1, const char *s2, int result) {
+
+  if (!result) return;
+  __libafl_targets_cmplog_strings(__libafl_targets_cmplog_rtn_key((uintptr_t)called_pc), (uint8_t *)s1, (uint8_t *)s2, CMPLOG_RTN_LEN);
+
+}
+
+__attribute__((weak)) void __sanitizer_weak_hook_strncasecmp(void *called_pc, const char *s1, const char *s2, size_t n, int result) {
+
+  if (!result) return;
+  __libafl_targets_cmplog_strings(__libafl_targets_cmplog_rtn_key((uintptr_t)called_pc), (uint8_t *)s1, (uint8_t *)s2, n);
+
+}
+
+__attribute__((weak)) void __sanitizer_weak_hook_strcasecmp(void *called_pc, const char *s1, const char *s2, int result) {
+
+  if (!result) return;
+  __libafl_targets_cmplog_strings(__libafl_targets_cmplog_rtn_key((uintptr_t)called_pc), (uint8_t *)s1, (uint8_t *)s2, CMPLOG_RTN_LEN);
+
+}
+
+#endif
+
 // gcc libstdc++
 // _ZNKSt7__cxx1112basic_stringIcSt11char_traitsIcESaIcEE7compareEPKc
 static uint8_t *get_gcc_stdstring(uint8_t *string) {
@@ -170,7 +266,7 @@ void __cmplog_rtn_gcc_stdstring_cstring(uint8_t *stdstring, uint8_t *cstring) {
   if (area_is_valid(stdstring, 32) <= 0)
     return;
 
-  __cmplog_rtn_hook(get_gcc_stdstring(stdstring), cstring);
+  __libafl_targets_cmplog_routines(__libafl_targets_cmplog_rtn_key(RETADDR), get_gcc_stdstring(stdstring), cstring);
 
 }
 
@@ -180,8 +276,9 @@ void __cmplog_rtn_gcc_stdstring_stdstring(uint8_t *stdstring1, uint8_t *stdstrin
   if (area_is_valid(stdstring1, 32) <= 0 || area_is_valid(stdstring2, 32) <= 0)
     return;
 
-  __cmplog_rtn_hook(get_gcc_stdstring(stdstring1),
-                    get_gcc_stdstring(stdstring2));
+  __libafl_targets_cmplog_routines(__libafl_targets_cmplog_rtn_key(RETADDR),
+                                   get_gcc_stdstring(stdstring1),
+                                   get_gcc_stdstring(stdstring2));
 
 }
 
@@ -191,7 +288,7 @@ void __cmplog_rtn_llvm_stdstring_cstring(uint8_t *stdstring, uint8_t *cstring) {
   if (area_is_valid(stdstring, 32) <= 0)
     return;
 
-  __cmplog_rtn_hook(get_llvm_stdstring(stdstring), cstring);
+  __libafl_targets_cmplog_routines(__libafl_targets_cmplog_rtn_key(RETADDR), get_llvm_stdstring(stdstring), cstring);
 
 }
 
@@ -201,8 +298,9 @@ void __cmplog_rtn_llvm_stdstring_stdstring(uint8_t *stdstring1, uint8_t *stdstri
   if (area_is_valid(stdstring1, 32) <= 0 || area_is_valid(stdstring2, 32) <= 0)
     return;
 
-  __cmplog_rtn_hook(get_llvm_stdstring(stdstring1),
-                    get_llvm_stdstring(stdstring2));
+  __libafl_targets_cmplog_routines(__libafl_targets_cmplog_rtn_key(RETADDR),
+                                   get_llvm_stdstring(stdstring1),
+                                   get_llvm_stdstring(stdstring2));
 
 }
 
@@ -1,6 +1,7 @@
 #ifndef __LIBAFL_TARGETS_CMPLOG__
 #define __LIBAFL_TARGETS_CMPLOG__
 
+#include <stddef.h>
 #include "common.h"
 
 #ifndef CMPLOG_MAP_W
@@ -49,6 +50,13 @@ void __libafl_targets_cmplog_instructions(uintptr_t k, uint8_t shape, uint64_t a
 
 void __libafl_targets_cmplog_routines(uintptr_t k, uint8_t *ptr1, uint8_t *ptr2);
 
+void __libafl_targets_cmplog_routines_len(uintptr_t k, uint8_t *ptr1, uint8_t *ptr2, size_t max_len);
+
+void __cmplog_rtn_hook(uint8_t *ptr1, uint8_t *ptr2);
+void __cmplog_rtn_hook_n(uint8_t *ptr1, uint8_t *ptr2, size_t len);
+void __cmplog_rtn_hook_str(uint8_t *ptr1, uint8_t *ptr2);
+void __cmplog_rtn_hook_strn(uint8_t *ptr1, uint8_t *ptr2, size_t len);
+
 static inline void __libafl_targets_cmplog(uintptr_t k, uint8_t shape, uint64_t arg1, uint64_t arg2) {
 
   if (!libafl_cmplog_enabled) return;
@@ -4,14 +4,22 @@
 
 use core::fmt::{self, Debug, Formatter};
 
+#[cfg(feature = "std")]
+use libafl::observers::CmpValuesMetadata;
 use libafl::{
-    bolts::{ownedref::OwnedRefMut, tuples::Named},
+    bolts::{
+        ownedref::{OwnedRefMut, OwnedSliceMut},
+        tuples::Named,
+        AsMutSlice,
+    },
     executors::ExitKind,
     observers::{CmpMap, CmpObserver, CmpValues, Observer},
     state::HasMetadata,
     Error,
 };
 
+#[cfg(feature = "std")]
+use crate::cmplog_shared::{SharedCmpLog, SHARED_CMPLOG_MERGE_MAX};
 use crate::{CMPLOG_MAP_H, CMPLOG_MAP_W};
 
 /// The `CmpLog` map size
@@ -30,9 +38,18 @@ pub const CMPLOG_KIND_INS: u8 = 0;
 pub const CMPLOG_KIND_RTN: u8 = 1;
 
 // void __libafl_targets_cmplog_instructions(uintptr_t k, uint8_t shape, uint64_t arg1, uint64_t arg2)
+// void __libafl_targets_cmplog_routines_len(uintptr_t k, uint8_t *ptr1, uint8_t *ptr2, size_t max_len)
 extern "C" {
     /// Logs an instruction for feedback during fuzzing
     pub fn __libafl_targets_cmplog_instructions(k: usize, shape: u8, arg1: u64, arg2: u64);
+
+    /// Logs the operands of a call to a comparison routine, such as `memcmp`, up to `max_len` bytes
+    pub fn __libafl_targets_cmplog_routines_len(
+        k: usize,
+        ptr1: *const u8,
+        ptr2: *const u8,
+        max_len: usize,
+    );
 }
 
 /// The header for `CmpLog` hits.
@@ -167,12 +184,22 @@ pub static mut libafl_cmplog_enabled: u8 = 0;
 
 pub use libafl_cmplog_enabled as CMPLOG_ENABLED;
 
+/// The value profile map of the cmps logged by `CmpLog`, for a [`CmpLogObserver`] built with
+/// [`CmpLogObserver::with_value_profile`] and a map observer feeding a `MaxMapFeedback`
+#[no_mangle]
+pub static mut libafl_cmplog_value_profile_map: [u8; CMPLOG_MAP_W] = [0; CMPLOG_MAP_W];
+
+pub use libafl_cmplog_value_profile_map as CMPLOG_VALUE_PROFILE_MAP;
+
 /// A [`CmpObserver`] observer for `CmpLog`
 #[derive(Debug)]
 pub struct CmpLogObserver<'a> {
     map: OwnedRefMut<'a, CmpLogMap>,
     size: Option<OwnedRefMut<'a, usize>>,
     add_meta: bool,
+    value_profile: Option<OwnedSliceMut<'a, u8>>,
+    #[cfg(feature = "std")]
+    shared: Option<SharedCmpLog>,
     name: String,
 }
 
@@ -204,6 +231,9 @@ where
 {
     fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
         self.map.as_mut().reset()?;
+        if let Some(value_profile) = &mut self.value_profile {
+            value_profile.as_mut_slice().fill(0);
+        }
         unsafe {
             CMPLOG_ENABLED = 1;
         }
@@ -214,8 +244,22 @@ where
         unsafe {
             CMPLOG_ENABLED = 0;
         }
+        let count = self.usable_count();
+        if let Some(value_profile) = &mut self.value_profile {
+            self.map
+                .as_ref()
+                .record_value_profile(count, value_profile.as_mut_slice());
+        }
         if self.add_meta {
             self.add_cmpvalues_meta(state);
+            #[cfg(feature = "std")]
+            if let Some(shared) = &mut self.shared {
+                if let Some(meta) = state.metadata_mut().get_mut::<CmpValuesMetadata>() {
+                    shared.publish(&meta.list);
+                    meta.list
+                        .extend(shared.sibling_values(SHARED_CMPLOG_MERGE_MAX));
+                }
+            }
         }
         Ok(())
     }
@@ -235,9 +279,39 @@ impl<'a> CmpLogObserver<'a> {
             name: name.to_string(),
             size: None,
             add_meta,
+            value_profile: None,
+            #[cfg(feature = "std")]
+            shared: None,
             map: OwnedRefMut::Ref(map),
         }
     }
 
+    /// Records the value profile of the cmps of each run into `map`, e.g. the
+    /// [`CMPLOG_VALUE_PROFILE_MAP`], see [`CmpMap::record_value_profile`]. A `MaxMapFeedback` on a
+    /// map observer of `map` then rewards the inputs getting closer to the magic values.
+    #[must_use]
+    pub fn with_value_profile(mut self, map: &'a mut [u8]) -> Self {
+        self.value_profile = Some(OwnedSliceMut::from(map));
+        self
+    }
+
+    /// Shares the logged values with the other clients on this machine using the given pool.
+    /// After each run, the values logged by this client are published to the pool, and the
+    /// most recent values of its siblings are added to the metadata, too.
+    /// Only has an effect if the observer adds metadata.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn with_shared(mut self, shared: SharedCmpLog) -> Self {
+        self.shared = Some(shared);
+        self
+    }
+
+    /// The pool shared with the other clients, if any
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn shared(&self) -> Option<&SharedCmpLog> {
+        self.shared.as_ref()
+    }
+
     // TODO with_size
 }
@@ -0,0 +1,323 @@
+//! A pool of `CmpLog` values in shared memory, so that clients on the same machine
+//! can reuse the comparisons their siblings discovered.
+//!
+//! Every client publishes the values its [`crate::CmpLogObserver`] collected into a ring of entries,
+//! each tagged with the id of the client that logged it, and guarded by a sequence counter. When adding the
+//! [`CmpValuesMetadata`](libafl::observers::CmpValuesMetadata), the observer then appends the most
+//! recent entries of the other clients, so that input-to-state stages also try their values.
+
+use alloc::vec::Vec;
+use core::{
+    fmt::{self, Debug, Formatter},
+    mem::size_of,
+    ptr,
+    sync::atomic::{fence, AtomicU32, AtomicUsize, Ordering},
+};
+
+use libafl::{
+    bolts::{
+        shmem::{ShMem, ShMemProvider, StdShMemProvider},
+        AsMutSlice, AsSlice,
+    },
+    observers::CmpValues,
+    Error,
+};
+
+use crate::CMPLOG_RTN_LEN;
+
+/// The default number of entries in a [`SharedCmpLog`]
+pub const SHARED_CMPLOG_ENTRIES: usize = 1 << 14;
+
+/// The maximum number of sibling entries appended to the metadata after each run
+pub const SHARED_CMPLOG_MERGE_MAX: usize = 256;
+
+/// The shared memory type backing a [`SharedCmpLog`]
+pub type SharedCmpLogShMem = <StdShMemProvider as ShMemProvider>::ShMem;
+
+/// The header of the shared pool
+#[repr(C)]
+struct SharedCmpLogHeader {
+    /// The total number of entries ever written, the next slot is this modulo the capacity
+    next: AtomicUsize,
+}
+
+/// An entry of the shared pool
+#[repr(C)]
+struct SharedCmpLogEntry {
+    /// The sequence counter of this entry, odd while it is being written, zero while it is empty
+    seq: AtomicU32,
+    /// The values of this entry, only valid if `seq` is even and didn't change while reading them
+    data: SharedCmpLogData,
+}
+
+/// The values of an entry of the shared pool
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SharedCmpLogData {
+    /// The client that logged this entry
+    owner: u32,
+    /// The size of the operands in bytes, `0` for routine arguments
+    shape: u8,
+    /// The length of the routine arguments
+    len: u8,
+    v0: [u8; CMPLOG_RTN_LEN],
+    v1: [u8; CMPLOG_RTN_LEN],
+}
+
+/// A pool of `CmpLog` values in shared memory, with entries tagged by the client that logged them.
+///
+/// The pool is lock-free: each entry is a seqlock, so a reader racing with a writer skips the
+/// entry, as does a writer racing with another writer on the same entry. This is fine, as the
+/// values only serve as hints for mutations.
+pub struct SharedCmpLog {
+    shmem: SharedCmpLogShMem,
+    owner: u32,
+    capacity: usize,
+}
+
+impl Debug for SharedCmpLog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedCmpLog")
+            .field("id", &self.shmem.id())
+            .field("owner", &self.owner)
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+impl SharedCmpLog {
+    /// The size in bytes of the shared memory for a pool with `entries` entries
+    #[must_use]
+    pub const fn shmem_size(entries: usize) -> usize {
+        size_of::<SharedCmpLogHeader>() + entries * size_of::<SharedCmpLogEntry>()
+    }
+
+    /// Uses the given (zero-initialized) shared memory as pool, logging as client `owner`.
+    /// All clients sharing the pool need to use distinct owner ids, such as their core id.
+    pub fn new(shmem: SharedCmpLogShMem, owner: u32) -> Result<Self, Error> {
+        if shmem.len() < Self::shmem_size(1) {
+            return Err(Error::IllegalArgument(format!(
+                "The shared memory for the cmplog pool needs at least {} bytes, got {}",
+                Self::shmem_size(1),
+                shmem.len()
+            )));
+        }
+        let capacity =
+            (shmem.len() - size_of::<SharedCmpLogHeader>()) / size_of::<SharedCmpLogEntry>();
+        Ok(Self {
+            shmem,
+            owner,
+            capacity,
+        })
+    }
+
+    /// Creates a new pool with `entries` entries and publishes it in the environment variable
+    /// `env_name`, so that clients spawned afterwards can attach to it using [`Self::from_env`].
+    /// Keep the returned pool alive as long as the clients run.
+    pub fn create_in_env(entries: usize, env_name: &str, owner: u32) -> Result<Self, Error> {
+        let mut shmem_provider = StdShMemProvider::new()?;
+        let shmem = shmem_provider.new_shmem(Self::shmem_size(entries))?;
+        shmem.write_to_env(env_name)?;
+        Self::new(shmem, owner)
+    }
+
+    /// Attaches to the pool published in the environment variable `env_name`
+    pub fn from_env(env_name: &str, owner: u32) -> Result<Self, Error> {
+        let mut shmem_provider = StdShMemProvider::new()?;
+        let shmem = shmem_provider.existing_from_env(env_name)?;
+        Self::new(shmem, owner)
+    }
+
+    /// The id of the client logging to this pool
+    #[must_use]
+    pub fn owner(&self) -> u32 {
+        self.owner
+    }
+
+    /// The number of entries in this pool
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // The shared memory is page aligned, and the entries are aligned to their size
+    #[allow(clippy::cast_ptr_alignment)]
+    fn header(&self) -> &SharedCmpLogHeader {
+        unsafe { &*(self.shmem.as_slice().as_ptr() as *const SharedCmpLogHeader) }
+    }
+
+    #[allow(clippy::cast_ptr_alignment)]
+    fn entry(&mut self, idx: usize) -> *mut SharedCmpLogEntry {
+        debug_assert!(idx < self.capacity);
+        unsafe {
+            let entries = self
+                .shmem
+                .as_mut_slice()
+                .as_mut_ptr()
+                .add(size_of::<SharedCmpLogHeader>());
+            (entries as *mut SharedCmpLogEntry).add(idx)
+        }
+    }
+
+    /// Publishes the given values, tagged with the owner of this pool
+    pub fn publish(&mut self, values: &[CmpValues]) {
+        for value in values {
+            let (shape, v0, v1) = match value {
+                CmpValues::U8(v) => (1, u64::from(v.0), u64::from(v.1)),
+                CmpValues::U16(v) => (2, u64::from(v.0), u64::from(v.1)),
+                CmpValues::U32(v) => (4, u64::from(v.0), u64::from(v.1)),
+                CmpValues::U64(v) => (8, v.0, v.1),
+                CmpValues::Bytes(v) => {
+                    self.write_entry(0, &v.0, &v.1);
+                    continue;
+                }
+            };
+            self.write_entry(shape, &v0.to_le_bytes(), &v1.to_le_bytes());
+        }
+    }
+
+    fn write_entry(&mut self, shape: u8, v0: &[u8], v1: &[u8]) {
+        let len = v0.len().min(v1.len()).min(CMPLOG_RTN_LEN);
+        let idx = self.header().next.fetch_add(1, Ordering::Relaxed) % self.capacity;
+        let mut data = SharedCmpLogData {
+            owner: self.owner,
+            shape,
+            len: len as u8,
+            v0: [0; CMPLOG_RTN_LEN],
+            v1: [0; CMPLOG_RTN_LEN],
+        };
+        data.v0[..len].copy_from_slice(&v0[..len]);
+        data.v1[..len].copy_from_slice(&v1[..len]);
+
+        let entry = self.entry(idx);
+        let seq = unsafe { &(*entry).seq };
+        let start = seq.load(Ordering::Relaxed);
+        // Another client is writing this entry, drop the value
+        if start % 2 == 1
+            || seq
+                .compare_exchange(start, start + 1, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+        {
+            return;
+        }
+        fence(Ordering::Release);
+        unsafe {
+            ptr::write_volatile(ptr::addr_of_mut!((*entry).data), data);
+        }
+        seq.store(start.wrapping_add(2), Ordering::Release);
+    }
+
+    /// Reads back the entry at `idx`, if it was logged by another client
+    fn read_sibling_entry(&mut self, idx: usize) -> Option<CmpValues> {
+        let entry = self.entry(idx);
+        let seq = unsafe { &(*entry).seq };
+        let start = seq.load(Ordering::Acquire);
+        if start == 0 || start % 2 == 1 {
+            return None;
+        }
+        let data = unsafe { ptr::read_volatile(ptr::addr_of!((*entry).data)) };
+        fence(Ordering::Acquire);
+        // The entry got overwritten while we were reading it
+        if seq.load(Ordering::Relaxed) != start || data.owner == self.owner {
+            return None;
+        }
+        let shape = data.shape;
+        let len = (data.len as usize).min(CMPLOG_RTN_LEN);
+        let bytes0 = data.v0;
+        let bytes1 = data.v1;
+        let v0 = u64::from_le_bytes(bytes0[..8].try_into().unwrap());
+        let v1 = u64::from_le_bytes(bytes1[..8].try_into().unwrap());
+        match shape {
+            0 => Some(CmpValues::Bytes((
+                bytes0[..len].to_vec(),
+                bytes1[..len].to_vec(),
+            ))),
+            1 => Some(CmpValues::U8((v0 as u8, v1 as u8))),
+            2 => Some(CmpValues::U16((v0 as u16, v1 as u16))),
+            4 => Some(CmpValues::U32((v0 as u32, v1 as u32))),
+            8 => Some(CmpValues::U64((v0, v1))),
+            _ => None,
+        }
+    }
+
+    /// Returns up to `max` of the most recent values logged by other clients, newest first
+    pub fn sibling_values(&mut self, max: usize) -> Vec<CmpValues> {
+        let next = self.header().next.load(Ordering::Acquire);
+        let mut values = vec![];
+        for i in 1..=next.min(self.capacity) {
+            if values.len() >= max {
+                break;
+            }
+            if let Some(value) = self.read_sibling_entry((next - i) % self.capacity) {
+                values.push(value);
+            }
+        }
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::Ordering;
+
+    use libafl::{
+        bolts::shmem::{ShMem, ShMemProvider, StdShMemProvider},
+        observers::CmpValues,
+    };
+
+    use super::SharedCmpLog;
+
+    /// `CmpValues` are not `PartialEq`, compare their debug output instead
+    fn assert_values(values: &[CmpValues], expected: &[CmpValues]) {
+        assert_eq!(format!("{:?}", values), format!("{:?}", expected));
+    }
+
+    #[test]
+    fn test_shared_cmplog() {
+        let mut shmem_provider = StdShMemProvider::new().unwrap();
+        let size = SharedCmpLog::shmem_size(8);
+        let shmem = shmem_provider.new_shmem(size).unwrap();
+        let other_shmem = shmem_provider
+            .shmem_from_id_and_size(shmem.id(), size)
+            .unwrap();
+        let mut first = SharedCmpLog::new(shmem, 0).unwrap();
+        let mut second = SharedCmpLog::new(other_shmem, 1).unwrap();
+        assert_eq!(first.capacity(), 8);
+
+        first.publish(&[
+            CmpValues::U16((0x1234, 0x4321)),
+            CmpValues::Bytes((b"abc".to_vec(), b"xyz".to_vec())),
+        ]);
+        second.publish(&[CmpValues::U64((1, 2))]);
+
+        // Newest first, without the own values
+        assert_values(
+            &second.sibling_values(16),
+            &[
+                CmpValues::Bytes((b"abc".to_vec(), b"xyz".to_vec())),
+                CmpValues::U16((0x1234, 0x4321)),
+            ],
+        );
+        assert_values(&first.sibling_values(16), &[CmpValues::U64((1, 2))]);
+        assert_eq!(second.sibling_values(1).len(), 1);
+
+        // An entry being written gets skipped, then read once the write is done
+        let seq = unsafe { &(*second.entry(0)).seq };
+        seq.fetch_add(1, Ordering::Relaxed);
+        assert_values(
+            &second.sibling_values(16),
+            &[CmpValues::Bytes((b"abc".to_vec(), b"xyz".to_vec()))],
+        );
+        seq.fetch_add(1, Ordering::Relaxed);
+        assert_eq!(second.sibling_values(16).len(), 2);
+
+        // A writer skips an entry another client is writing: the ninth value wraps to the first entry
+        seq.fetch_add(1, Ordering::Relaxed);
+        for _ in 0..5 {
+            first.publish(&[CmpValues::U8((1, 2))]);
+        }
+        first.publish(&[CmpValues::U8((3, 4))]);
+        seq.fetch_add(1, Ordering::Relaxed);
+        assert!(!format!("{:?}", second.sibling_values(16)).contains("U8((3, 4))"));
+    }
+}
@@ -0,0 +1,195 @@
+//! The edges of the instrumented code matching an allowlist or a denylist of `libafl_cc`, for the
+//! [`libafl::observers::FilteredMapObserver`] to only reward the coverage of the code under test.
+//!
+//! The lists are the files of `ClangWrapper::allowlist` and `ClangWrapper::denylist`: each line is
+//! one of `src: <source file pattern>`, `fun: <function name pattern>` or
+//! `<source file pattern>`. Patterns may contain the `*` and `?` wildcards, and a source pattern
+//! also matches the end of a full path. The edges get matched by their location, resolved from
+//! the `PC-Table` and the debug info of the target, built with `-fsanitize-coverage=pc-table`.
+//! Note that `fun:` patterns match the demangled names of the functions.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use std::{fs, path::Path};
+
+use libafl::Error;
+
+use crate::sancov_pcs::{sancov_pcs, symbolize_pc, PcSymbol};
+
+/// Matches `value` against the shell wildcard `pattern`, with `*` and `?`
+fn wildcard_match(pattern: &[u8], value: &[u8]) -> bool {
+    let (mut p, mut v) = (0, 0);
+    // The position after the last `*`, and the position in `value` it matched up to
+    let mut backtrack = None;
+    while v < value.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                p += 1;
+                backtrack = Some((p, v));
+            }
+            Some(&c) if c == b'?' || c == value[v] => {
+                p += 1;
+                v += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_v)) => {
+                    p = star_p;
+                    v = star_v + 1;
+                    backtrack = Some((star_p, star_v + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+/// An allowlist or a denylist of `libafl_cc`, of source files and functions
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InstrumentList {
+    sources: Vec<String>,
+    functions: Vec<String>,
+}
+
+impl InstrumentList {
+    /// Parses the content of a list
+    #[must_use]
+    pub fn parse(list: &str) -> Self {
+        let mut parsed = Self::default();
+        for line in list.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(function) = line.strip_prefix("fun:") {
+                parsed.functions.push(function.trim().to_string());
+            } else {
+                let source = line.strip_prefix("src:").unwrap_or(line);
+                parsed.sources.push(source.trim().to_string());
+            }
+        }
+        parsed
+    }
+
+    /// Reads the list in the file at `path`
+    pub fn from_file<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// If the location `symbol` matches the list
+    #[must_use]
+    pub fn matches(&self, symbol: &PcSymbol) -> bool {
+        if let Some(function) = &symbol.function {
+            if self
+                .functions
+                .iter()
+                .any(|pattern| wildcard_match(pattern.as_bytes(), function.as_bytes()))
+            {
+                return true;
+            }
+        }
+        let file = match &symbol.file {
+            Some(file) => file.to_string_lossy(),
+            None => return false,
+        };
+        // The path, and its ends starting at a path component
+        let file = file.as_bytes();
+        let mut suffixes = core::iter::once(file).chain(
+            file.iter()
+                .enumerate()
+                .filter(|(_, c)| **c == b'/' || **c == b'\\')
+                .map(|(idx, _)| &file[idx + 1..]),
+        );
+        suffixes.any(|suffix| {
+            self.sources
+                .iter()
+                .any(|pattern| wildcard_match(pattern.as_bytes(), suffix))
+        })
+    }
+}
+
+/// Returns the indexes, in the edges map, of the edges whose location matches `filter`
+pub fn sancov_edges_matching<F>(mut filter: F) -> Vec<usize>
+where
+    F: FnMut(&PcSymbol) -> bool,
+{
+    sancov_pcs()
+        .enumerate()
+        .filter(|(_, entry)| filter(&symbolize_pc(entry.pc)))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Returns the indexes, in the edges map, of the edges allowed by the `allowlist`, if any, and not
+/// denied by the `denylist`, if any, for a [`libafl::observers::FilteredMapObserver`]
+#[must_use]
+pub fn sancov_edges_in_lists(
+    allowlist: Option<&InstrumentList>,
+    denylist: Option<&InstrumentList>,
+) -> Vec<usize> {
+    sancov_edges_matching(|symbol| {
+        !matches!(allowlist, Some(list) if !list.matches(symbol))
+            && !matches!(denylist, Some(list) if list.matches(symbol))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        coverage_filter::{wildcard_match, InstrumentList},
+        sancov_pcs::PcSymbol,
+    };
+
+    fn symbol(function: Option<&str>, file: Option<&str>) -> PcSymbol {
+        PcSymbol {
+            pc: 0,
+            function: function.map(ToString::to_string),
+            file: file.map(PathBuf::from),
+            line: None,
+        }
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match(b"png_*", b"png_read_info"));
+        assert!(wildcard_match(b"*.c", b"src/png.c"));
+        assert!(wildcard_match(b"a?c", b"abc"));
+        assert!(wildcard_match(b"*a*b*", b"xxaxxbxx"));
+        assert!(wildcard_match(b"**", b""));
+        assert!(!wildcard_match(b"a?c", b"ac"));
+        assert!(!wildcard_match(b"png_*", b"libpng_read"));
+        assert!(!wildcard_match(b"*.c", b"png.cc"));
+        assert!(!wildcard_match(b"", b"a"));
+    }
+
+    #[test]
+    fn test_instrument_list() {
+        let list = InstrumentList::parse(
+            "# the library\nsrc: lib/*.c\n\nfun: png_*\n  parser.c  \nsrc:*/vendor/*\n",
+        );
+
+        // `fun:` lines only match functions
+        assert!(list.matches(&symbol(Some("png_read_info"), None)));
+        assert!(list.matches(&symbol(Some("png_read_info"), Some("/x/harness.c"))));
+        assert!(!list.matches(&symbol(Some("main"), Some("/x/png_main.c"))));
+
+        // `src:` lines, and lines without a prefix, only match source files
+        assert!(list.matches(&symbol(Some("main"), Some("lib/png.c"))));
+        assert!(!list.matches(&symbol(Some("lib_init"), Some("harness.c"))));
+        assert!(list.matches(&symbol(None, Some("/build/src/vendor/zlib.c"))));
+        assert!(!list.matches(&symbol(Some("parser.c"), None)));
+
+        // A source pattern matches the end of a full path, from a path component on
+        assert!(list.matches(&symbol(None, Some("/home/user/parser.c"))));
+        assert!(list.matches(&symbol(None, Some("parser.c"))));
+        assert!(!list.matches(&symbol(None, Some("/home/user/myparser.c"))));
+        assert!(list.matches(&symbol(None, Some("/build/lib/png.c"))));
+        assert!(!list.matches(&symbol(None, Some("/build/mylib/png.c"))));
+    }
+}
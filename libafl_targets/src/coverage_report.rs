@@ -0,0 +1,388 @@
+//! Coverage reports of a whole campaign, in the `lcov` tracefile format and as simple HTML pages.
+//!
+//! A [`CoverageReport`] accumulates coverage maps (such as the history map of the
+//! [`MapFeedbackState`]), and maps their entries back to source lines using the
+//! [`PC-Table`](crate::sancov_pcs) and the debug info of the target. Generate the reports on demand,
+//! or once the fuzzing loop returns.
+//!
+//! The maps behind a `HitcountsMapObserver` hold hitcount buckets, not the number of times an
+//! entry was hit, so the reports show the highest bucket of each line and function, e.g. `8` for
+//! an edge hit 4 to 7 times in a single run. The `lcov` tools treat them as hit counts, which is
+//! only meaningful as hit or missed.
+
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write as _;
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use libafl::{
+    bolts::tuples::MatchName, feedbacks::MapFeedbackState, state::HasFeedbackStates, Error,
+};
+
+use crate::sancov_pcs::{sancov_pcs, symbolize_pc};
+
+/// The coverage of a function
+#[derive(Clone, Debug, Default)]
+pub struct FunctionCoverage {
+    /// The line the function starts at
+    pub line: u32,
+    /// The highest hitcount bucket of the entry block of the function, `0` if never entered
+    pub bucket: u8,
+}
+
+/// The coverage of a source file
+#[derive(Clone, Debug, Default)]
+pub struct FileCoverage {
+    /// The instrumented lines, with the highest hitcount bucket of their entries, `0` if never hit
+    pub lines: BTreeMap<u32, u8>,
+    /// The instrumented functions
+    pub functions: BTreeMap<String, FunctionCoverage>,
+}
+
+impl FileCoverage {
+    /// The number of lines that were hit at least once
+    #[must_use]
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|bucket| **bucket != 0).count()
+    }
+
+    /// The number of functions that were entered at least once
+    #[must_use]
+    pub fn functions_hit(&self) -> usize {
+        self.functions.values().filter(|f| f.bucket != 0).count()
+    }
+}
+
+/// Accumulates coverage maps to report the coverage of the target by source line
+#[derive(Clone, Debug, Default)]
+pub struct CoverageReport {
+    /// The highest hitcount bucket, per entry of the edges map
+    buckets: Vec<u8>,
+}
+
+impl CoverageReport {
+    /// Creates a new, empty, [`CoverageReport`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a coverage map of hitcount buckets, indexed like the `PC-Table`.
+    /// Each entry keeps the highest bucket among the added maps.
+    pub fn add_map(&mut self, map: &[u8]) {
+        if self.buckets.len() < map.len() {
+            self.buckets.resize(map.len(), 0);
+        }
+        for (bucket, value) in self.buckets.iter_mut().zip(map.iter()) {
+            *bucket = (*bucket).max(*value);
+        }
+    }
+
+    /// The highest hitcount bucket of each entry of the edges map
+    #[must_use]
+    pub fn buckets(&self) -> &[u8] {
+        &self.buckets
+    }
+
+    /// Adds the history map of the [`MapFeedbackState`] named `name`,
+    /// that is, everything the campaign covered so far
+    pub fn add_feedback_state<S>(&mut self, state: &S, name: &str) -> Result<(), Error>
+    where
+        S: HasFeedbackStates,
+    {
+        let feedback_state = state
+            .feedback_states()
+            .match_name::<MapFeedbackState<u8>>(name)
+            .ok_or_else(|| Error::KeyNotFound(format!("MapFeedbackState {} not found", name)))?;
+        self.add_map(&feedback_state.history_map);
+        Ok(())
+    }
+
+    /// Maps the hitcount buckets to source files and lines.
+    /// Locations without debug info are left out.
+    #[must_use]
+    pub fn files(&self) -> BTreeMap<PathBuf, FileCoverage> {
+        let mut files: BTreeMap<PathBuf, FileCoverage> = BTreeMap::new();
+        for (idx, entry) in sancov_pcs().enumerate() {
+            let bucket = self.buckets.get(idx).copied().unwrap_or(0);
+            let symbol = symbolize_pc(entry.pc);
+            let (file, line) = match (symbol.file, symbol.line) {
+                (Some(file), Some(line)) => (file, line),
+                _ => continue,
+            };
+            let coverage = files.entry(file).or_default();
+            let line_bucket = coverage.lines.entry(line).or_default();
+            *line_bucket = (*line_bucket).max(bucket);
+            if entry.is_function_entry() {
+                if let Some(function) = symbol.function {
+                    let function =
+                        coverage
+                            .functions
+                            .entry(function)
+                            .or_insert_with(|| FunctionCoverage {
+                                line,
+                                ..FunctionCoverage::default()
+                            });
+                    function.bucket = function.bucket.max(bucket);
+                }
+            }
+        }
+        files
+    }
+
+    /// Writes the report as `lcov` tracefile, to be used with `genhtml` and other `lcov` tools.
+    /// The counts of the tracefile are the hitcount buckets.
+    pub fn write_lcov<P>(&self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(lcov_tracefile(&self.files()).as_bytes())?;
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Writes the report as HTML pages into `dir`: an `index.html` summing up the coverage of each
+    /// file, linking to a page per file showing its source, if readable, with the hitcount bucket
+    /// of each line
+    pub fn write_html<P>(&self, dir: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        write_html_pages(dir.as_ref(), &self.files())
+    }
+}
+
+/// The `lcov` tracefile of the coverage of the `files`
+fn lcov_tracefile(files: &BTreeMap<PathBuf, FileCoverage>) -> String {
+    let mut out = String::new();
+    writeln!(out, "TN:").unwrap();
+    for (file, coverage) in files {
+        writeln!(out, "SF:{}", file.display()).unwrap();
+        for (name, function) in &coverage.functions {
+            writeln!(out, "FN:{},{}", function.line, name).unwrap();
+        }
+        for (name, function) in &coverage.functions {
+            writeln!(out, "FNDA:{},{}", function.bucket, name).unwrap();
+        }
+        writeln!(out, "FNF:{}", coverage.functions.len()).unwrap();
+        writeln!(out, "FNH:{}", coverage.functions_hit()).unwrap();
+        for (line, bucket) in &coverage.lines {
+            writeln!(out, "DA:{},{}", line, bucket).unwrap();
+        }
+        writeln!(out, "LF:{}", coverage.lines.len()).unwrap();
+        writeln!(out, "LH:{}", coverage.lines_hit()).unwrap();
+        writeln!(out, "end_of_record").unwrap();
+    }
+    out
+}
+
+/// Writes the HTML pages of the coverage of the `files` into `dir`
+fn write_html_pages(dir: &Path, files: &BTreeMap<PathBuf, FileCoverage>) -> Result<(), Error> {
+    fs::create_dir_all(dir)?;
+
+    let mut index = html_header("Coverage report");
+    let total_lines: usize = files.values().map(|c| c.lines.len()).sum();
+    let total_hit: usize = files.values().map(FileCoverage::lines_hit).sum();
+    writeln!(
+        index,
+        "<p>Lines: {} / {} ({})</p>\n<table>\n<tr><th>File</th><th>Lines</th><th>Functions</th></tr>",
+        total_hit,
+        total_lines,
+        percent(total_hit, total_lines)
+    )
+    .unwrap();
+    for (idx, (file, coverage)) in files.iter().enumerate() {
+        let page = format!("file{}.html", idx);
+        writeln!(
+            index,
+            "<tr><td><a href=\"{}\">{}</a></td><td>{} / {} ({})</td><td>{} / {}</td></tr>",
+            page,
+            html_escape(&file.display().to_string()),
+            coverage.lines_hit(),
+            coverage.lines.len(),
+            percent(coverage.lines_hit(), coverage.lines.len()),
+            coverage.functions_hit(),
+            coverage.functions.len(),
+        )
+        .unwrap();
+        fs::write(dir.join(page), file_page(file, coverage))?;
+    }
+    index.push_str("</table>\n</body>\n</html>\n");
+    fs::write(dir.join("index.html"), index)?;
+    Ok(())
+}
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; }
+table { border-collapse: collapse; }
+td, th { padding: 0 1em; text-align: left; }
+pre { margin: 0; }
+.hit { background: #c8f0c8; }
+.missed { background: #f0c8c8; }
+.count { color: #888; text-align: right; }";
+
+fn html_header(title: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        html_escape(title),
+        HTML_STYLE,
+        html_escape(title)
+    )
+}
+
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn percent(hit: usize, total: usize) -> String {
+    if total == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.1}%", hit as f64 * 100.0 / total as f64)
+    }
+}
+
+/// The page of a single file, with the source if it is readable, the instrumented lines otherwise
+fn file_page(file: &Path, coverage: &FileCoverage) -> String {
+    let mut page = html_header(&file.display().to_string());
+    writeln!(
+        page,
+        "<p><a href=\"index.html\">Back</a> - Lines: {} / {} ({})</p>\n<table>\n<tr><th>Line</th><th>Hitcount bucket</th><th>Source</th></tr>",
+        coverage.lines_hit(),
+        coverage.lines.len(),
+        percent(coverage.lines_hit(), coverage.lines.len())
+    )
+    .unwrap();
+    let line_row = |page: &mut String, line: u32, text: &str| {
+        let (class, count) = match coverage.lines.get(&line) {
+            Some(0) => ("missed", "0".to_string()),
+            Some(bucket) => ("hit", bucket.to_string()),
+            None => ("", String::new()),
+        };
+        writeln!(
+            page,
+            "<tr class=\"{}\"><td class=\"count\">{}</td><td class=\"count\">{}</td><td><pre>{}</pre></td></tr>",
+            class,
+            line,
+            count,
+            html_escape(text)
+        )
+        .unwrap();
+    };
+    if let Ok(source) = fs::read_to_string(file) {
+        for (idx, text) in source.lines().enumerate() {
+            line_row(&mut page, idx as u32 + 1, text);
+        }
+    } else {
+        for line in coverage.lines.keys() {
+            line_row(&mut page, *line, "");
+        }
+    }
+    page.push_str("</table>\n</body>\n</html>\n");
+    page
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeMap;
+    use std::{fs, path::PathBuf};
+
+    use super::{lcov_tracefile, write_html_pages, CoverageReport, FileCoverage, FunctionCoverage};
+
+    /// A file with a hit function on line 1 and a missed one on line 3
+    fn file_coverage() -> FileCoverage {
+        let mut coverage = FileCoverage::default();
+        coverage.lines.insert(1, 2);
+        coverage.lines.insert(2, 8);
+        coverage.lines.insert(3, 0);
+        coverage
+            .functions
+            .insert("main".to_string(), FunctionCoverage { line: 1, bucket: 2 });
+        coverage.functions.insert(
+            "max<int>".to_string(),
+            FunctionCoverage { line: 3, bucket: 0 },
+        );
+        coverage
+    }
+
+    #[test]
+    fn test_add_map() {
+        let mut report = CoverageReport::new();
+        report.add_map(&[1, 0, 8]);
+        report.add_map(&[2, 0, 4, 128]);
+        assert_eq!(report.buckets(), [2, 0, 8, 128]);
+
+        let mut coverage = FileCoverage::default();
+        coverage.lines.insert(1, 0);
+        coverage.lines.insert(2, 8);
+        assert_eq!(coverage.lines_hit(), 1);
+    }
+
+    #[test]
+    fn test_lcov_tracefile() {
+        let mut files = BTreeMap::new();
+        files.insert(PathBuf::from("/src/main.c"), file_coverage());
+        assert_eq!(
+            lcov_tracefile(&files),
+            "TN:
+SF:/src/main.c
+FN:1,main
+FN:3,max<int>
+FNDA:2,main
+FNDA:0,max<int>
+FNF:2
+FNH:1
+DA:1,2
+DA:2,8
+DA:3,0
+LF:3
+LH:2
+end_of_record
+"
+        );
+    }
+
+    #[test]
+    fn test_html_pages() {
+        let dir =
+            std::env::temp_dir().join(format!("libafl_coverage_report_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("a<b>.c");
+        fs::write(&source, "int main() {\n  return a < b && c;\n}\n").unwrap();
+        let mut files = BTreeMap::new();
+        files.insert(source, file_coverage());
+
+        let pages = dir.join("html");
+        write_html_pages(&pages, &files).unwrap();
+        let index = fs::read_to_string(pages.join("index.html")).unwrap();
+        let page = fs::read_to_string(pages.join("file0.html")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(index.contains("<p>Lines: 2 / 3 (66.7%)</p>"));
+        assert!(index.contains("a&lt;b&gt;.c</a></td><td>2 / 3 (66.7%)</td><td>1 / 2</td>"));
+        assert!(page.contains(
+            "<tr class=\"hit\"><td class=\"count\">2</td><td class=\"count\">8</td><td><pre>  return a &lt; b &amp;&amp; c;</pre></td></tr>"
+        ));
+        assert!(page.contains(
+            "<tr class=\"missed\"><td class=\"count\">3</td><td class=\"count\">0</td><td><pre>}</pre></td></tr>"
+        ));
+    }
+}
@@ -68,6 +68,21 @@ pub mod sancov_pcguard;
 #[cfg(any(feature = "sancov_pcguard_edges", feature = "sancov_pcguard_hitcounts",))]
 pub use sancov_pcguard::*;
 
+#[cfg(feature = "sancov_pcs")]
+pub mod sancov_pcs;
+#[cfg(feature = "sancov_pcs")]
+pub use sancov_pcs::*;
+
+#[cfg(all(feature = "sancov_pcs", feature = "std"))]
+pub mod coverage_report;
+#[cfg(all(feature = "sancov_pcs", feature = "std"))]
+pub use coverage_report::CoverageReport;
+
+#[cfg(all(feature = "sancov_pcs", feature = "std"))]
+pub mod coverage_filter;
+#[cfg(all(feature = "sancov_pcs", feature = "std"))]
+pub use coverage_filter::{sancov_edges_in_lists, sancov_edges_matching, InstrumentList};
+
 #[cfg(any(feature = "sancov_cmplog", feature = "sancov_value_profile"))]
 pub mod sancov_cmp;
 #[cfg(any(feature = "sancov_cmplog", feature = "sancov_value_profile"))]
@@ -83,14 +98,27 @@ pub mod sancov_8bit;
 #[cfg(feature = "sancov_8bit")]
 pub use sancov_8bit::*;
 
+#[cfg(feature = "stack_depth")]
+pub mod stack_depth;
+#[cfg(feature = "stack_depth")]
+pub use stack_depth::*;
+
 pub mod coverage;
 pub use coverage::*;
 
+pub mod classify;
+pub use classify::*;
+
 pub mod value_profile;
 pub use value_profile::*;
 
 pub mod cmplog;
 pub use cmplog::*;
 
+#[cfg(feature = "std")]
+pub mod cmplog_shared;
+#[cfg(feature = "std")]
+pub use cmplog_shared::*;
+
 #[cfg(feature = "std")]
 pub mod drcov;
@@ -0,0 +1,32 @@
+#include "common.h"
+#include "value_profile.h"
+
+// Emitted by -fsanitize-coverage=trace-div,trace-gep, and by MSVC's /fsanitize-coverage=trace-div,
+// independently of the comparisons, so these are always defined
+
+void __sanitizer_cov_trace_div4(uint32_t val) {
+
+  uintptr_t k = RETADDR;
+  k = (k >> 4) ^ (k << 8);
+  k &= CMP_MAP_SIZE - 1;
+  __libafl_targets_value_profile_div_gep4(k, val);
+
+}
+
+void __sanitizer_cov_trace_div8(uint64_t val) {
+
+  uintptr_t k = RETADDR;
+  k = (k >> 4) ^ (k << 8);
+  k &= CMP_MAP_SIZE - 1;
+  __libafl_targets_value_profile_div_gep8(k, val);
+
+}
+
+void __sanitizer_cov_trace_gep(uintptr_t idx) {
+
+  uintptr_t k = RETADDR;
+  k = (k >> 4) ^ (k << 8);
+  k &= CMP_MAP_SIZE - 1;
+  __libafl_targets_value_profile_div_gep8(k, (uint64_t)idx);
+
+}
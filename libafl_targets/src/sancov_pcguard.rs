@@ -3,6 +3,10 @@
 use crate::coverage::{EDGES_MAP, MAX_EDGES_NUM};
 #[cfg(feature = "pointer_maps")]
 use crate::coverage::{EDGES_MAP_PTR, EDGES_MAP_PTR_SIZE};
+#[cfg(feature = "pointer_maps")]
+use alloc::vec::Vec;
+#[cfg(feature = "pointer_maps")]
+use core::{mem::size_of, slice};
 
 #[cfg(all(feature = "sancov_pcguard_edges", feature = "sancov_pcguard_hitcounts"))]
 #[cfg(not(any(doc, feature = "clippy")))]
@@ -45,7 +49,36 @@ pub unsafe extern "C" fn __sanitizer_cov_trace_pc_guard(guard: *mut u32) {
     }
 }
 
+/// The edges maps allocated once the guards no longer fit into the static [`EDGES_MAP`], the
+/// last one in use. The previous maps are kept alive, as observers may still point into them.
+#[cfg(feature = "pointer_maps")]
+static mut EDGES_MAPS_GROWN: Vec<Vec<u8>> = Vec::new();
+
+/// Grows the edges map to hold at least `size` entries, moving the current content over.
+/// Maps not allocated by us (for example shared maps set by the user) are never touched.
+#[cfg(feature = "pointer_maps")]
+unsafe fn grow_edges_map(size: usize) {
+    if size <= EDGES_MAP_PTR_SIZE {
+        return;
+    }
+    let owned = EDGES_MAP_PTR == EDGES_MAP.as_mut_ptr()
+        || matches!(EDGES_MAPS_GROWN.last(), Some(map) if map.as_ptr() == EDGES_MAP_PTR);
+    if !owned {
+        // Someone else owns the map, new edges will wrap around
+        return;
+    }
+    let mut map = vec![0; size.next_power_of_two()];
+    map[..EDGES_MAP_PTR_SIZE]
+        .copy_from_slice(slice::from_raw_parts(EDGES_MAP_PTR, EDGES_MAP_PTR_SIZE));
+    EDGES_MAP_PTR = map.as_mut_ptr();
+    EDGES_MAP_PTR_SIZE = map.len();
+    EDGES_MAPS_GROWN.push(map);
+}
+
 /// Initialize the sancov `pc_guard` - usually called by `llvm`.
+/// With the `pointer_maps` feature, the edges map is grown as needed to fit all guards,
+/// so observers should be created from [`crate::coverage::edges_map_from_ptr`] after all
+/// modules are loaded.
 ///
 /// # Safety
 /// Dereferences at `start` and writes to it.
@@ -61,6 +94,9 @@ pub unsafe extern "C" fn __sanitizer_cov_trace_pc_guard_init(mut start: *mut u32
         return;
     }
 
+    #[cfg(feature = "pointer_maps")]
+    grow_edges_map(MAX_EDGES_NUM + (stop as usize - start as usize) / size_of::<u32>());
+
     while start < stop {
         *start = MAX_EDGES_NUM as u32;
         start = start.offset(1);
@@ -72,7 +108,41 @@ pub unsafe extern "C" fn __sanitizer_cov_trace_pc_guard_init(mut start: *mut u32
         #[cfg(not(feature = "pointer_maps"))]
         {
             MAX_EDGES_NUM = MAX_EDGES_NUM.wrapping_add(1);
-            assert!((MAX_EDGES_NUM <= EDGES_MAP.len()), "The number of edges reported by SanitizerCoverage exceed the size of the edges map ({}). Use the LIBAFL_EDGES_MAP_SIZE env to increase it at compile time.", EDGES_MAP.len());
+            assert!((MAX_EDGES_NUM <= EDGES_MAP.len()), "The number of edges reported by SanitizerCoverage exceed the size of the edges map ({}). Use the LIBAFL_EDGES_MAP_SIZE env to increase it at compile time, or enable the `pointer_maps` feature to size it at runtime.", EDGES_MAP.len());
+        }
+    }
+}
+
+#[cfg(all(test, feature = "pointer_maps"))]
+mod tests {
+    use crate::{
+        coverage::{EDGES_MAP, EDGES_MAP_PTR, EDGES_MAP_PTR_SIZE},
+        sancov_pcguard::grow_edges_map,
+    };
+
+    #[test]
+    fn test_grow_edges_map() {
+        unsafe {
+            EDGES_MAP_PTR = EDGES_MAP.as_mut_ptr();
+            EDGES_MAP_PTR_SIZE = EDGES_MAP.len();
+            EDGES_MAP_PTR.write(7);
+
+            let static_len = EDGES_MAP_PTR_SIZE;
+            grow_edges_map(static_len + 1);
+            let (first_ptr, first_len) = (EDGES_MAP_PTR, EDGES_MAP_PTR_SIZE);
+            assert!(first_len > static_len);
+            assert_eq!(first_ptr.read(), 7);
+
+            grow_edges_map(first_len + 1);
+            assert!(EDGES_MAP_PTR_SIZE > first_len);
+            assert_ne!(EDGES_MAP_PTR, first_ptr);
+            assert_eq!(EDGES_MAP_PTR.read(), 7);
+
+            // The previous map is still alive, for the observers built over it
+            for i in 0..first_len {
+                first_ptr.add(i).write(1);
+            }
+            assert_eq!(first_ptr.add(first_len - 1).read(), 1);
         }
     }
 }
@@ -0,0 +1,180 @@
+//! [`LLVM` `PC-Table`](https://clang.llvm.org/docs/SanitizerCoverage.html#pc-table) runtime for `LibAFL`.
+//!
+//! When compiling with `-fsanitize-coverage=pc-table`, each instrumented module registers a table
+//! holding the program counter of each of its instrumented blocks. Together with `trace-pc-guard`,
+//! the position of a block in these tables equals its index in the [`crate::EDGES_MAP`], which
+//! allows to map coverage back to code locations.
+
+use alloc::vec::Vec;
+use core::{mem::size_of, slice};
+
+/// The entry of a block that starts a function has this flag set
+pub const PC_FLAG_FUNCTION_ENTRY: usize = 1;
+
+/// One entry of the `PC-Table`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub struct PcTableEntry {
+    /// The program counter of the instrumented block
+    pub pc: usize,
+    /// Flags, see [`PC_FLAG_FUNCTION_ENTRY`]
+    pub flags: usize,
+}
+
+impl PcTableEntry {
+    /// Returns `true` if this block is the entry block of a function
+    #[must_use]
+    pub fn is_function_entry(&self) -> bool {
+        self.flags & PC_FLAG_FUNCTION_ENTRY != 0
+    }
+}
+
+/// The tables registered by all instrumented modules, in order of registration
+static mut PC_TABLES: Vec<&'static [PcTableEntry]> = Vec::new();
+
+/// The entries of all the tables, indexed like the edges map
+static mut PCS: Vec<PcTableEntry> = Vec::new();
+
+/// Initialize the sancov `pc-table` - usually called by `llvm` for each instrumented module.
+///
+/// # Safety
+/// Keeps a reference to the memory between `pcs_beg` and `pcs_end`, which must be valid for the
+/// whole lifetime of the process.
+#[no_mangle]
+pub unsafe extern "C" fn __sanitizer_cov_pcs_init(pcs_beg: *const usize, pcs_end: *const usize) {
+    if pcs_beg.is_null() || pcs_end <= pcs_beg {
+        return;
+    }
+    let len = (pcs_end as usize - pcs_beg as usize) / size_of::<PcTableEntry>();
+    let table = slice::from_raw_parts(pcs_beg as *const PcTableEntry, len);
+    PC_TABLES.push(table);
+    PCS.extend_from_slice(table);
+}
+
+/// Returns the `PC-Table`s of all instrumented modules, in order of registration.
+#[must_use]
+pub fn sancov_pc_tables() -> &'static [&'static [PcTableEntry]] {
+    unsafe { &PC_TABLES }
+}
+
+/// Returns the entries of all `PC-Table`s. The index of an entry is the index of the matching
+/// location in the edges map.
+#[must_use]
+pub fn sancov_pc_entries() -> &'static [PcTableEntry] {
+    unsafe { &PCS }
+}
+
+/// Iterates over the entries of all `PC-Table`s. The position of an entry is the index of the
+/// matching location in the edges map.
+pub fn sancov_pcs() -> impl Iterator<Item = &'static PcTableEntry> {
+    sancov_pc_entries().iter()
+}
+
+/// Returns the `PC-Table` entry for the given index into the edges map, if known
+#[must_use]
+pub fn sancov_pc_for_edge(edge: usize) -> Option<&'static PcTableEntry> {
+    sancov_pc_entries().get(edge)
+}
+
+/// Returns the program counters of all locations that are set in the given coverage map
+#[must_use]
+pub fn sancov_covered_pcs(map: &[u8]) -> Vec<usize> {
+    sancov_pcs()
+        .zip(map.iter())
+        .filter(|(_, hits)| **hits != 0)
+        .map(|(entry, _)| entry.pc)
+        .collect()
+}
+
+#[cfg(feature = "std")]
+pub use symbolize::*;
+
+/// The symbolization of the pcs, with `backtrace`, enabled by the `sancov_pcs` feature
+#[cfg(feature = "std")]
+mod symbolize {
+    use alloc::{string::String, vec::Vec};
+    use core::ffi::c_void;
+    use std::path::PathBuf;
+
+    use super::sancov_pcs;
+
+    /// The source location of a program counter, as found in the debug info of the target
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct PcSymbol {
+        /// The program counter
+        pub pc: usize,
+        /// The (demangled) name of the function, if known
+        pub function: Option<String>,
+        /// The source file, if known
+        pub file: Option<PathBuf>,
+        /// The line in the source file, if known
+        pub line: Option<u32>,
+    }
+
+    /// Resolves the given program counter to its source location
+    #[must_use]
+    pub fn symbolize_pc(pc: usize) -> PcSymbol {
+        let mut symbol = PcSymbol {
+            pc,
+            ..PcSymbol::default()
+        };
+        backtrace::resolve(pc as *mut c_void, |resolved| {
+            if symbol.function.is_none() {
+                symbol.function = resolved.name().map(|name| name.to_string());
+                symbol.file = resolved.filename().map(PathBuf::from);
+                symbol.line = resolved.lineno();
+            }
+        });
+        symbol
+    }
+
+    /// Resolves the source locations of all locations set in the given coverage map
+    #[must_use]
+    pub fn symbolize_covered_pcs(map: &[u8]) -> Vec<PcSymbol> {
+        sancov_pcs()
+            .zip(map.iter())
+            .filter(|(_, hits)| **hits != 0)
+            .map(|(entry, _)| symbolize_pc(entry.pc))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sancov_pcs::{
+        __sanitizer_cov_pcs_init, sancov_covered_pcs, sancov_pc_for_edge, sancov_pc_tables,
+        PcTableEntry, PC_FLAG_FUNCTION_ENTRY,
+    };
+
+    static FIRST: [PcTableEntry; 2] = [
+        PcTableEntry {
+            pc: 0x1000,
+            flags: PC_FLAG_FUNCTION_ENTRY,
+        },
+        PcTableEntry {
+            pc: 0x1010,
+            flags: 0,
+        },
+    ];
+    static SECOND: [PcTableEntry; 1] = [PcTableEntry {
+        pc: 0x2000,
+        flags: PC_FLAG_FUNCTION_ENTRY,
+    }];
+
+    #[test]
+    fn test_sancov_pcs() {
+        for table in [&FIRST[..], &SECOND[..]] {
+            let range = table.as_ptr_range();
+            unsafe {
+                __sanitizer_cov_pcs_init(range.start.cast(), range.end.cast());
+            }
+        }
+        assert_eq!(sancov_pc_tables().len(), 2);
+        // The edges of the modules follow each other
+        assert_eq!(sancov_pc_for_edge(1), Some(&FIRST[1]));
+        assert_eq!(sancov_pc_for_edge(2), Some(&SECOND[0]));
+        assert_eq!(sancov_pc_for_edge(3), None);
+        assert!(sancov_pc_for_edge(2).unwrap().is_function_entry());
+        assert_eq!(sancov_covered_pcs(&[0, 1, 3]), [0x1010, 0x2000]);
+    }
+}
@@ -0,0 +1,34 @@
+// Section boundaries for SanitizerCoverage on Windows, from compiler-rt's
+// sanitizer_coverage_win_sections.cpp.
+//
+// COFF has no linker-generated __start_/__stop_ symbols. Instead, the linker
+// sorts sections with the same name before the `$` by their suffix, so we
+// place markers in the first (`A`) and last (`Z`) subsection of each sancov
+// section. The compiler emits the guards, counters and pcs in the `M`
+// subsections and, knowing this layout, skips the 8 bytes of the start marker.
+//
+// The linker may add zero padding between the subsections, which shows up as
+// additional (never hit) entries. The pcguard init assigns them map slots, too.
+
+#if defined(_WIN32) && defined(_MSC_VER)
+
+#include <stdint.h>
+
+#pragma section(".SCOV$GA", read, write)
+__declspec(allocate(".SCOV$GA")) uint64_t __start___sancov_guards = 0;
+#pragma section(".SCOV$GZ", read, write)
+__declspec(allocate(".SCOV$GZ")) __declspec(align(1)) uint8_t __stop___sancov_guards = 0;
+
+// A 1 byte stop marker keeps the linker from aligning the section, which would
+// make the number of counters differ from the number of pcs.
+#pragma section(".SCOV$CA", read, write)
+__declspec(allocate(".SCOV$CA")) uint64_t __start___sancov_cntrs = 0;
+#pragma section(".SCOV$CZ", read, write)
+__declspec(allocate(".SCOV$CZ")) __declspec(align(1)) uint8_t __stop___sancov_cntrs = 0;
+
+#pragma section(".SCOVP$A", read)
+__declspec(allocate(".SCOVP$A")) uint64_t __start___sancov_pcs = 0;
+#pragma section(".SCOVP$Z", read)
+__declspec(allocate(".SCOVP$Z")) __declspec(align(1)) uint8_t __stop___sancov_pcs = 0;
+
+#endif
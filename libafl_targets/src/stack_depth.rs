@@ -0,0 +1,85 @@
+//! Stack depth runtime for `LibAFL`, for the
+//! [`libafl::observers::StackDepthObserver`].
+//!
+//! The target must be built with `-finstrument-functions` (see `ClangWrapper::stack_depth` of
+//! `libafl_cc`): each instrumented function calls these hooks on entry and on exit, which track
+//! the depth of the calls, and raise [`STACK_DEPTH_MAX`].
+
+use core::ffi::c_void;
+
+use libafl::observers::StackDepthObserver;
+
+/// The current depth of the calls of instrumented functions
+pub static mut STACK_DEPTH: usize = 0;
+
+/// The maximum depth of the calls of instrumented functions, reset by the
+/// [`libafl::observers::StackDepthObserver`] before each run
+pub static mut STACK_DEPTH_MAX: usize = 0;
+
+/// Called on the entry of each instrumented function, by `-finstrument-functions`
+#[no_mangle]
+pub extern "C" fn __cyg_profile_func_enter(_func: *const c_void, _caller: *const c_void) {
+    unsafe {
+        STACK_DEPTH += 1;
+        if STACK_DEPTH > STACK_DEPTH_MAX {
+            STACK_DEPTH_MAX = STACK_DEPTH;
+        }
+    }
+}
+
+/// Called on the exit of each instrumented function, by `-finstrument-functions`
+#[no_mangle]
+pub extern "C" fn __cyg_profile_func_exit(_func: *const c_void, _caller: *const c_void) {
+    unsafe {
+        STACK_DEPTH = STACK_DEPTH.saturating_sub(1);
+    }
+}
+
+/// Resets the current depth, e.g. after a run left functions without returning, by `longjmp` or
+/// a timeout
+pub fn reset_stack_depth() {
+    unsafe {
+        STACK_DEPTH = 0;
+    }
+}
+
+/// Creates a [`StackDepthObserver`] of [`STACK_DEPTH_MAX`], resetting it, and the current depth,
+/// before each run
+///
+/// # Safety
+/// The observer accesses the static depths, don't create more than one
+#[must_use]
+pub unsafe fn stack_depth_observer(name: &'static str) -> StackDepthObserver<'static> {
+    StackDepthObserver::with_depth(name, &mut STACK_DEPTH_MAX, &mut STACK_DEPTH)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ptr;
+
+    use libafl::observers::Observer;
+
+    use crate::stack_depth::{
+        __cyg_profile_func_enter, __cyg_profile_func_exit, stack_depth_observer, STACK_DEPTH,
+        STACK_DEPTH_MAX,
+    };
+
+    #[test]
+    fn test_stack_depth() {
+        let mut observer = unsafe { stack_depth_observer("stack") };
+        observer.pre_exec(&mut (), &()).unwrap();
+        for _ in 0..3 {
+            __cyg_profile_func_enter(ptr::null(), ptr::null());
+        }
+        __cyg_profile_func_exit(ptr::null(), ptr::null());
+        __cyg_profile_func_enter(ptr::null(), ptr::null());
+        assert_eq!(observer.max_depth(), 3);
+        assert_eq!(unsafe { STACK_DEPTH }, 3);
+
+        // The run stopped without returning from the functions
+        observer.pre_exec(&mut (), &()).unwrap();
+        assert_eq!(unsafe { (STACK_DEPTH, STACK_DEPTH_MAX) }, (0, 0));
+        __cyg_profile_func_enter(ptr::null(), ptr::null());
+        assert_eq!(observer.max_depth(), 1);
+    }
+}
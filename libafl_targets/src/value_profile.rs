@@ -1,5 +1,7 @@
 //! Value profile support for `LibAFL`
 
+use libafl::observers::StdMapObserver;
+
 use crate::CMP_MAP_SIZE;
 
 /// The constant cmplog map for the current `LibAFL` target
@@ -8,6 +10,31 @@ pub static mut libafl_cmp_map: [u8; CMP_MAP_SIZE] = [0; CMP_MAP_SIZE];
 
 pub use libafl_cmp_map as CMP_MAP;
 
+/// The map for the value profile of divisors and array indices (`-fsanitize-coverage=trace-div,trace-gep`).
+/// Each entry holds the maximum number of zero bits seen for its location.
+#[no_mangle]
+pub static mut libafl_div_gep_map: [u8; CMP_MAP_SIZE] = [0; CMP_MAP_SIZE];
+
+pub use libafl_div_gep_map as DIV_GEP_MAP;
+
+extern "C" {
+    /// Trace the divisor of a 32 bit division
+    pub fn __sanitizer_cov_trace_div4(val: u32);
+    /// Trace the divisor of a 64 bit division
+    pub fn __sanitizer_cov_trace_div8(val: u64);
+    /// Trace an array index
+    pub fn __sanitizer_cov_trace_gep(idx: usize);
+}
+
+/// Creates a [`StdMapObserver`] of the [`DIV_GEP_MAP`], which resets it before each run
+///
+/// # Safety
+/// The observer accesses the static map, don't create more than one
+#[must_use]
+pub unsafe fn div_gep_map_observer(name: &'static str) -> StdMapObserver<'static, u8> {
+    StdMapObserver::new(name, &mut DIV_GEP_MAP)
+}
+
 /*
 extern {
     #[link_name = "llvm.returnaddress"]
@@ -24,3 +51,33 @@ pub unsafe extern "C" fn __sanitizer_cov_trace_cmp1(arg1: u8, arg2: u8) {
 */
 
 // TODO complete when linking to LLVM intrinsic will land to stable Rust
+
+#[cfg(test)]
+mod tests {
+    use libafl::observers::{MapObserver, Observer};
+
+    use crate::value_profile::{
+        __sanitizer_cov_trace_div4, __sanitizer_cov_trace_div8, div_gep_map_observer,
+    };
+
+    #[test]
+    fn test_div_gep_map() {
+        let mut observer = unsafe { div_gep_map_observer("div_gep") };
+        observer.pre_exec(&mut (), &()).unwrap();
+        assert_eq!(observer.count_bytes(), 0);
+
+        // A divisor of 0 has all its bits unset, the entry keeps the maximum
+        unsafe {
+            __sanitizer_cov_trace_div4(0);
+        }
+        assert_eq!((&observer).into_iter().max(), Some(&32));
+        assert_eq!(observer.count_bytes(), 1);
+        unsafe {
+            __sanitizer_cov_trace_div8(0);
+        }
+        assert_eq!((&observer).into_iter().max(), Some(&64));
+
+        observer.pre_exec(&mut (), &()).unwrap();
+        assert_eq!(observer.count_bytes(), 0);
+    }
+}
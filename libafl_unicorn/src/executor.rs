@@ -0,0 +1,461 @@
+//! An executor emulating a snippet of code with `Unicorn`
+
+use core::{
+    cell::RefCell,
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    time::Duration,
+};
+use std::rc::Rc;
+
+use libafl::{
+    bolts::AsSlice,
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::{HasTargetBytes, Input},
+    observers::ObserversTuple,
+    Error,
+};
+use libafl_targets::{EDGES_MAP, EDGES_MAP_SIZE};
+use unicorn_engine::{unicorn_const::Permission, Unicorn};
+
+/// The default timeout of a run
+pub const UNICORN_DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The edges map of one executor, and the previous location to compute edges from blocks
+#[derive(Debug)]
+struct EdgeTracer {
+    map_ptr: *mut u8,
+    map_size: usize,
+    prev_loc: u64,
+}
+
+impl EdgeTracer {
+    /// Logs the edge to the block at `pc` into the edges map
+    fn trace_block(&mut self, pc: u64) {
+        let cur_loc = (pc >> 4) ^ (pc << 8);
+        let idx = ((cur_loc ^ self.prev_loc) as usize) % self.map_size;
+        unsafe {
+            let entry = self.map_ptr.add(idx);
+            *entry = (*entry).wrapping_add(1);
+        }
+        self.prev_loc = cur_loc >> 1;
+    }
+}
+
+/// A memory region of the emulated target
+#[derive(Debug, Clone)]
+pub struct UnicornRegion {
+    addr: u64,
+    size: usize,
+    perms: Permission,
+    data: Vec<u8>,
+}
+
+impl UnicornRegion {
+    /// Creates a new region of `size` bytes at `addr`, initially zeroed.
+    /// Both `addr` and `size` need to be aligned to the page size of `Unicorn` (4 KiB).
+    #[must_use]
+    pub fn new(addr: u64, size: usize, perms: Permission) -> Self {
+        Self {
+            addr,
+            size,
+            perms,
+            data: vec![],
+        }
+    }
+
+    /// Sets the initial content of the region, such as the code of the snippet.
+    /// The region is restored to this content before each run.
+    #[must_use]
+    pub fn with_data(mut self, data: Vec<u8>) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// The start address of this region
+    #[must_use]
+    pub fn addr(&self) -> u64 {
+        self.addr
+    }
+
+    /// The size of this region
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// The content of a region mapped in the emulator, restored before each run
+#[derive(Debug, Clone)]
+struct RegionSnapshot {
+    addr: u64,
+    size: usize,
+    perms: Permission,
+    data: Vec<u8>,
+}
+
+/// The hook to set up the emulator with the input, e.g. to put its length into a register
+type UnicornPreRunHook<'a> = Box<dyn FnMut(&mut Unicorn<'a, ()>, &[u8]) -> Result<(), Error> + 'a>;
+
+/// An [`Executor`] emulating a snippet of code with `Unicorn`.
+///
+/// Each run restores the memory as mapped at the creation of the executor, writes the input at the input address and emulates the
+/// code from the start address until the end address is reached. Runs raising an emulation error
+/// (such as an unmapped memory access) are reported as crashes, runs stopping before the end
+/// address as timeouts.
+///
+/// The edges go to the `EDGES_MAP` of `libafl_targets` by default, use
+/// [`UnicornExecutor::with_edges_map_ptr`] to give each executor its own map.
+pub struct UnicornExecutor<'a, I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    emu: Unicorn<'a, ()>,
+    regions: Vec<UnicornRegion>,
+    snapshot: Vec<RegionSnapshot>,
+    start: u64,
+    end: u64,
+    input_addr: u64,
+    input_max_len: usize,
+    timeout: Duration,
+    pre_run: Option<UnicornPreRunHook<'a>>,
+    tracer: Rc<RefCell<EdgeTracer>>,
+    observers: OT,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<'a, I, OT, S> Debug for UnicornExecutor<'a, I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnicornExecutor")
+            .field("regions", &self.regions)
+            .field("start", &self.start)
+            .field("end", &self.end)
+            .field("input_addr", &self.input_addr)
+            .field("input_max_len", &self.input_max_len)
+            .field("timeout", &self.timeout)
+            .field("observers", &self.observers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, I, OT, S> UnicornExecutor<'a, I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    /// Creates a new [`UnicornExecutor`] on the given emulator.
+    /// The `regions` get mapped, and the input is written at `input_addr`, truncated to `input_max_len`.
+    /// The input needs to fit into one of the `regions`.
+    pub fn new(
+        mut emu: Unicorn<'a, ()>,
+        regions: Vec<UnicornRegion>,
+        start: u64,
+        end: u64,
+        input_addr: u64,
+        input_max_len: usize,
+        observers: OT,
+    ) -> Result<Self, Error> {
+        for region in &regions {
+            if region.data.len() > region.size {
+                return Err(Error::IllegalArgument(format!(
+                    "The data of the region at {:#x} is larger than the region",
+                    region.addr
+                )));
+            }
+            emu.mem_map(region.addr, region.size, region.perms)
+                .map_err(|err| {
+                    Error::IllegalArgument(format!(
+                        "Could not map the region at {:#x}: {:?}",
+                        region.addr, err
+                    ))
+                })?;
+            emu.mem_write(region.addr, &region.data)
+                .map_err(|err| Error::Unknown(format!("Could not write the region: {:?}", err)))?;
+        }
+        if !regions.iter().any(|region| {
+            region.addr <= input_addr
+                && input_addr + input_max_len as u64 <= region.addr + region.size as u64
+        }) {
+            return Err(Error::IllegalArgument(format!(
+                "The input at {:#x} does not fit into any region",
+                input_addr
+            )));
+        }
+        let tracer = Rc::new(RefCell::new(EdgeTracer {
+            map_ptr: unsafe { EDGES_MAP.as_mut_ptr() },
+            map_size: EDGES_MAP_SIZE,
+            prev_loc: 0,
+        }));
+        let hook_tracer = tracer.clone();
+        emu.add_block_hook(move |_emu, pc, _size| hook_tracer.borrow_mut().trace_block(pc))
+            .map_err(|err| Error::Unknown(format!("Could not add the block hook: {:?}", err)))?;
+
+        // All the regions, including the ones mapped before, get restored in full
+        let mut snapshot = vec![];
+        for region in emu
+            .mem_regions()
+            .map_err(|err| Error::Unknown(format!("Could not list the regions: {:?}", err)))?
+        {
+            let size = (region.end - region.begin + 1) as usize;
+            let data = emu.mem_read_as_vec(region.begin, size).map_err(|err| {
+                Error::Unknown(format!(
+                    "Could not read the region at {:#x}: {:?}",
+                    region.begin, err
+                ))
+            })?;
+            snapshot.push(RegionSnapshot {
+                addr: region.begin,
+                size,
+                perms: region.perms,
+                data,
+            });
+        }
+
+        Ok(Self {
+            emu,
+            regions,
+            snapshot,
+            start,
+            end,
+            input_addr,
+            input_max_len,
+            timeout: UNICORN_DEFAULT_TIMEOUT,
+            pre_run: None,
+            tracer,
+            observers,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Sets the timeout of each run
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Logs the edges into the map at `map_ptr` instead of the global `EDGES_MAP`, so that
+    /// several executors can live in the same process. Observe it with
+    /// `StdMapObserver::new_from_ptr`.
+    ///
+    /// # Safety
+    /// The map needs to be valid for `map_size` bytes, for the whole life of the executor.
+    #[must_use]
+    pub unsafe fn with_edges_map_ptr(self, map_ptr: *mut u8, map_size: usize) -> Self {
+        assert!(map_size > 0, "The edges map can't be empty");
+        {
+            let mut tracer = self.tracer.borrow_mut();
+            tracer.map_ptr = map_ptr;
+            tracer.map_size = map_size;
+        }
+        self
+    }
+
+    /// Sets a hook called right before each run, with the (truncated) input.
+    /// Use it to set up the registers, e.g. to pass the input length to the snippet.
+    #[must_use]
+    pub fn with_pre_run<F>(mut self, pre_run: F) -> Self
+    where
+        F: FnMut(&mut Unicorn<'a, ()>, &[u8]) -> Result<(), Error> + 'a,
+    {
+        self.pre_run = Some(Box::new(pre_run));
+        self
+    }
+
+    /// The emulator
+    #[must_use]
+    pub fn emu(&self) -> &Unicorn<'a, ()> {
+        &self.emu
+    }
+
+    /// The emulator (mutable)
+    pub fn emu_mut(&mut self) -> &mut Unicorn<'a, ()> {
+        &mut self.emu
+    }
+
+    /// Restores the memory as mapped at the creation of the executor: the regions mapped,
+    /// split or reprotected by the last run get unmapped, the missing ones mapped again, and all
+    /// get their content restored in full
+    fn restore_regions(&mut self) -> Result<(), Error> {
+        let mapped = self
+            .emu
+            .mem_regions()
+            .map_err(|err| Error::Unknown(format!("Could not list the regions: {:?}", err)))?;
+        for region in &mapped {
+            let size = (region.end - region.begin + 1) as usize;
+            if !self.snapshot.iter().any(|snapshot| {
+                snapshot.addr == region.begin
+                    && snapshot.size == size
+                    && snapshot.perms == region.perms
+            }) {
+                self.emu.mem_unmap(region.begin, size).map_err(|err| {
+                    Error::Unknown(format!("Could not unmap the region: {:?}", err))
+                })?;
+            }
+        }
+        for snapshot in &self.snapshot {
+            if !mapped.iter().any(|region| {
+                region.begin == snapshot.addr
+                    && (region.end - region.begin + 1) as usize == snapshot.size
+                    && region.perms == snapshot.perms
+            }) {
+                self.emu
+                    .mem_map(snapshot.addr, snapshot.size, snapshot.perms)
+                    .map_err(|err| {
+                        Error::Unknown(format!("Could not map the region again: {:?}", err))
+                    })?;
+            }
+            self.emu
+                .mem_write(snapshot.addr, &snapshot.data)
+                .map_err(|err| {
+                    Error::Unknown(format!("Could not restore the region: {:?}", err))
+                })?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, EM, I, OT, S, Z> Executor<EM, I, S, Z> for UnicornExecutor<'a, I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    fn run_target(
+        &mut self,
+        _fuzzer: &mut Z,
+        _state: &mut S,
+        _mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        self.restore_regions()?;
+
+        let target_bytes = input.target_bytes();
+        let bytes = target_bytes.as_slice();
+        let bytes = &bytes[..bytes.len().min(self.input_max_len)];
+        self.emu
+            .mem_write(self.input_addr, bytes)
+            .map_err(|err| Error::Unknown(format!("Could not write the input: {:?}", err)))?;
+        if let Some(pre_run) = &mut self.pre_run {
+            pre_run(&mut self.emu, bytes)?;
+        }
+
+        self.tracer.borrow_mut().prev_loc = 0;
+        let res = self
+            .emu
+            .emu_start(self.start, self.end, self.timeout.as_micros() as u64, 0);
+        Ok(match res {
+            // Unicorn stops silently on timeout, before reaching the end
+            Ok(()) => match self.emu.pc_read() {
+                Ok(pc) if pc == self.end => ExitKind::Ok,
+                _ => ExitKind::Timeout,
+            },
+            Err(_) => ExitKind::Crash,
+        })
+    }
+}
+
+impl<'a, I, OT, S> HasObservers<I, OT, S> for UnicornExecutor<'a, I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    #[inline]
+    fn observers(&self) -> &OT {
+        &self.observers
+    }
+
+    #[inline]
+    fn observers_mut(&mut self) -> &mut OT {
+        &mut self.observers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use libafl::{
+        executors::{Executor, ExitKind},
+        inputs::BytesInput,
+    };
+    use unicorn_engine::{
+        unicorn_const::{Arch, Mode, Permission},
+        Unicorn,
+    };
+
+    use super::{UnicornExecutor, UnicornRegion};
+
+    const CODE_ADDR: u64 = 0x1000;
+    const DATA_ADDR: u64 = 0x2000;
+
+    /// Crashes on `C` or if the marker is already set, loops forever on `T`, else sets the marker
+    #[rustfmt::skip]
+    const CODE: [u8; 0x2d] = [
+        0x80, 0x3c, 0x25, 0x00, 0x28, 0x00, 0x00, 0x00, // cmp byte [0x2800], 0
+        0x75, 0x19,                                     // jne crash
+        0x8a, 0x04, 0x25, 0x00, 0x20, 0x00, 0x00,       // mov al, [0x2000]
+        0x3c, 0x43,                                     // cmp al, 'C'
+        0x74, 0x0e,                                     // je crash
+        0x3c, 0x54,                                     // cmp al, 'T'
+        0x74, 0x11,                                     // je loop
+        0xc6, 0x04, 0x25, 0x00, 0x28, 0x00, 0x00, 0x01, // mov byte [0x2800], 1
+        0xeb, 0x09,                                     // jmp end
+        0x8a, 0x04, 0x25, 0x00, 0x00, 0x10, 0x00,       // crash: mov al, [0x100000]
+        0xeb, 0xfe,                                     // loop: jmp loop
+        0x90,                                           // end: nop
+    ];
+    const END_ADDR: u64 = CODE_ADDR + 0x2c;
+
+    fn executor<'a>(map: &mut [u8]) -> UnicornExecutor<'a, BytesInput, (), ()> {
+        let emu = Unicorn::new(Arch::X86, Mode::MODE_64).unwrap();
+        let regions = vec![
+            UnicornRegion::new(CODE_ADDR, 0x1000, Permission::READ | Permission::EXEC)
+                .with_data(CODE.to_vec()),
+            UnicornRegion::new(DATA_ADDR, 0x1000, Permission::READ | Permission::WRITE),
+        ];
+        let executor =
+            UnicornExecutor::new(emu, regions, CODE_ADDR, END_ADDR, DATA_ADDR, 0x100, ())
+                .unwrap()
+                .with_timeout(Duration::from_millis(100));
+        unsafe { executor.with_edges_map_ptr(map.as_mut_ptr(), map.len()) }
+    }
+
+    fn run(executor: &mut UnicornExecutor<BytesInput, (), ()>, input: &[u8]) -> ExitKind {
+        executor
+            .run_target(&mut (), &mut (), &mut (), &BytesInput::new(input.to_vec()))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_unicorn_executor() {
+        let mut map = vec![0_u8; 1 << 16];
+        let mut executor = executor(&mut map);
+
+        assert_eq!(run(&mut executor, b"A"), ExitKind::Ok);
+        assert_eq!(executor.emu().mem_read_as_vec(0x2800, 1).unwrap(), [1]);
+        // The marker of the last run is gone, else this would crash
+        assert_eq!(run(&mut executor, b"A"), ExitKind::Ok);
+        assert_eq!(run(&mut executor, b"C"), ExitKind::Crash);
+        assert_eq!(executor.emu().mem_read_as_vec(0x2800, 1).unwrap(), [0]);
+        assert_eq!(run(&mut executor, b"T"), ExitKind::Timeout);
+        assert_eq!(run(&mut executor, b"A"), ExitKind::Ok);
+        assert!(map.iter().any(|&hits| hits != 0));
+    }
+
+    #[test]
+    fn test_unicorn_executor_own_map() {
+        let mut first_map = vec![0_u8; 1 << 16];
+        let mut second_map = vec![0_u8; 1 << 16];
+        let mut first = executor(&mut first_map);
+        let _second = executor(&mut second_map);
+
+        assert_eq!(run(&mut first, b"A"), ExitKind::Ok);
+        assert!(first_map.iter().any(|&hits| hits != 0));
+        assert!(second_map.iter().all(|&hits| hits == 0));
+    }
+}
@@ -0,0 +1,64 @@
+//! A [`Unicorn`](https://www.unicorn-engine.org/) backend for `LibAFL`.
+//!
+//! The [`UnicornExecutor`] maps a user-provided memory layout, writes each input to a fixed
+//! address and emulates the code between a start and an end address, reporting the edges it
+//! executed in the edges map of `libafl_targets`. This is handy to fuzz parsers lifted out of
+//! firmware images, without having to emulate the whole device.
+
+#![deny(rustdoc::broken_intra_doc_links)]
+#![deny(clippy::pedantic)]
+#![allow(
+    clippy::unreadable_literal,
+    clippy::type_repetition_in_bounds,
+    clippy::missing_errors_doc,
+    clippy::cast_possible_truncation,
+    clippy::used_underscore_binding,
+    clippy::ptr_as_ptr,
+    clippy::missing_panics_doc,
+    clippy::missing_docs_in_private_items,
+    clippy::module_name_repetitions
+)]
+#![cfg_attr(debug_assertions, warn(
+    missing_debug_implementations,
+    missing_docs,
+    //trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    //unused_results
+))]
+#![cfg_attr(not(debug_assertions), deny(
+    missing_debug_implementations,
+    missing_docs,
+    //trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    //unused_results
+))]
+#![cfg_attr(
+    not(debug_assertions),
+    deny(
+        bad_style,
+        const_err,
+        dead_code,
+        improper_ctypes,
+        non_shorthand_field_patterns,
+        no_mangle_generic_items,
+        overflowing_literals,
+        path_statements,
+        patterns_in_fns_without_body,
+        private_in_public,
+        unconditional_recursion,
+        unused,
+        unused_allocation,
+        unused_comparisons,
+        unused_parens,
+        while_true
+    )
+)]
+
+pub mod executor;
+pub use executor::{UnicornExecutor, UnicornRegion};
@@ -0,0 +1,279 @@
+//! An executor running a WebAssembly module with `wasmtime`
+
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+};
+
+use libafl::{
+    bolts::AsSlice,
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::{HasTargetBytes, Input},
+    observers::ObserversTuple,
+    Error,
+};
+use libafl_targets::{EDGES_MAP, EDGES_MAP_SIZE};
+use wasmtime::{Config, Engine, Linker, Module, Store};
+
+use crate::instrument::{instrument_coverage, TRACE_BLOCK_FUNC, TRACE_MODULE};
+
+/// The default export fed with the inputs, `int LLVMFuzzerTestOneInput(const uint8_t *Data, size_t Size)`
+pub const WASM_DEFAULT_TARGET: &str = "LLVMFuzzerTestOneInput";
+
+/// The default export allocating the inputs in the memory of the module
+pub const WASM_DEFAULT_ALLOCATOR: &str = "malloc";
+
+/// The default fuel of a run, about the number of wasm instructions it may execute
+pub const WASM_DEFAULT_FUEL: u64 = 1 << 32;
+
+/// The previous block, to compute edges from blocks
+static mut PREV_LOC: u32 = 0;
+
+/// Logs the edge to the block `id` into the edges map
+#[allow(clippy::cast_sign_loss)]
+fn trace_block(id: i32) {
+    let cur_loc = id as u32;
+    unsafe {
+        let idx = ((cur_loc ^ PREV_LOC) as usize) % EDGES_MAP_SIZE;
+        EDGES_MAP[idx] = EDGES_MAP[idx].wrapping_add(1);
+        PREV_LOC = cur_loc >> 1;
+    }
+}
+
+/// An [`Executor`] running a WebAssembly module with `wasmtime`.
+///
+/// The module gets instrumented with [`instrument_coverage`], and each run instantiates it anew,
+/// allocates the input in its memory with its allocator export, and calls its target export with
+/// the address and the length of the input. Runs trapping, e.g. on an out-of-bounds access or an
+/// `abort`, are reported as crashes, and runs exhausting their fuel as timeouts.
+/// The module may only import the functions defined in the [`Linker`], see
+/// [`WasmExecutor::linker_mut`]: build it without WASI, e.g. for `wasm32-unknown-unknown`.
+pub struct WasmExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    engine: Engine,
+    module: Module,
+    linker: Linker<()>,
+    target: String,
+    allocator: String,
+    fuel: u64,
+    observers: OT,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<I, OT, S> Debug for WasmExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WasmExecutor")
+            .field("target", &self.target)
+            .field("allocator", &self.allocator)
+            .field("fuel", &self.fuel)
+            .field("observers", &self.observers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<I, OT, S> WasmExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    /// Creates a new [`WasmExecutor`] for the (not yet instrumented) WebAssembly module `wasm`,
+    /// feeding the inputs to its [`WASM_DEFAULT_TARGET`] export
+    pub fn new(wasm: &[u8], observers: OT) -> Result<Self, Error> {
+        let (wasm, _) = instrument_coverage(wasm)?;
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)
+            .map_err(|err| Error::Unknown(format!("Could not create the engine: {}", err)))?;
+        let module = Module::new(&engine, wasm).map_err(|err| {
+            Error::IllegalArgument(format!("Could not compile the module: {}", err))
+        })?;
+        let mut linker = Linker::new(&engine);
+        linker
+            .func_wrap(TRACE_MODULE, TRACE_BLOCK_FUNC, trace_block)
+            .map_err(|err| Error::Unknown(format!("Could not define the trace import: {}", err)))?;
+        Ok(Self {
+            engine,
+            module,
+            linker,
+            target: WASM_DEFAULT_TARGET.to_string(),
+            allocator: WASM_DEFAULT_ALLOCATOR.to_string(),
+            fuel: WASM_DEFAULT_FUEL,
+            observers,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Sets the export fed with the inputs, taking the address and the length of the input as
+    /// `i32`s, and returning an `i32`
+    #[must_use]
+    pub fn with_target(mut self, target: &str) -> Self {
+        self.target = target.to_string();
+        self
+    }
+
+    /// Sets the export allocating the inputs, taking their length as an `i32`, and returning
+    /// their address
+    #[must_use]
+    pub fn with_allocator(mut self, allocator: &str) -> Self {
+        self.allocator = allocator.to_string();
+        self
+    }
+
+    /// Sets the fuel of each run, the runs exhausting it are reported as timeouts
+    #[must_use]
+    pub fn with_fuel(mut self, fuel: u64) -> Self {
+        self.fuel = fuel;
+        self
+    }
+
+    /// The linker, defining the imports of the module
+    #[must_use]
+    pub fn linker(&self) -> &Linker<()> {
+        &self.linker
+    }
+
+    /// The linker (mutable), to define the other imports of the module
+    pub fn linker_mut(&mut self) -> &mut Linker<()> {
+        &mut self.linker
+    }
+}
+
+impl<EM, I, OT, S, Z> Executor<EM, I, S, Z> for WasmExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_possible_wrap,
+        clippy::cast_sign_loss
+    )]
+    fn run_target(
+        &mut self,
+        _fuzzer: &mut Z,
+        _state: &mut S,
+        _mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        let mut store = Store::new(&self.engine, ());
+        store
+            .add_fuel(self.fuel)
+            .map_err(|err| Error::Unknown(format!("Could not add fuel: {}", err)))?;
+        let instance = self
+            .linker
+            .instantiate(&mut store, &self.module)
+            .map_err(|err| Error::Unknown(format!("Could not instantiate the module: {}", err)))?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| Error::KeyNotFound("The module exports no memory".to_string()))?;
+        let allocator = instance
+            .get_typed_func::<i32, i32, _>(&mut store, &self.allocator)
+            .map_err(|err| {
+                Error::KeyNotFound(format!("No allocator {}: {}", self.allocator, err))
+            })?;
+        let target = instance
+            .get_typed_func::<(i32, i32), i32, _>(&mut store, &self.target)
+            .map_err(|err| Error::KeyNotFound(format!("No target {}: {}", self.target, err)))?;
+
+        let target_bytes = input.target_bytes();
+        let bytes = target_bytes.as_slice();
+        let len = bytes.len() as i32;
+        let addr = allocator
+            .call(&mut store, len)
+            .map_err(|err| Error::Unknown(format!("Could not allocate the input: {}", err)))?;
+        memory
+            .write(&mut store, addr as u32 as usize, bytes)
+            .map_err(|err| Error::Unknown(format!("Could not write the input: {}", err)))?;
+
+        unsafe {
+            PREV_LOC = 0;
+        }
+        Ok(match target.call(&mut store, (addr, len)) {
+            Ok(_) => ExitKind::Ok,
+            // The trap of an exhausted fuel has no dedicated code
+            Err(_) if store.fuel_consumed() >= Some(self.fuel) => ExitKind::Timeout,
+            Err(_) => ExitKind::Crash,
+        })
+    }
+}
+
+impl<I, OT, S> HasObservers<I, OT, S> for WasmExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    #[inline]
+    fn observers(&self) -> &OT {
+        &self.observers
+    }
+
+    #[inline]
+    fn observers_mut(&mut self) -> &mut OT {
+        &mut self.observers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl::{
+        executors::{Executor, ExitKind},
+        inputs::BytesInput,
+    };
+
+    use super::WasmExecutor;
+
+    /// Crashes on a `C`, loops forever on a `T`
+    const TARGET: &str = r#"(module
+        (memory (export "memory") 1)
+        (func (export "malloc") (param i32) (result i32)
+            (i32.const 1024))
+        (func (export "LLVMFuzzerTestOneInput") (param i32 i32) (result i32)
+            (if (i32.eqz (local.get 1))
+                (then (return (i32.const 0))))
+            (if (i32.eq (i32.load8_u (local.get 0)) (i32.const 67))
+                (then unreachable))
+            (if (i32.eq (i32.load8_u (local.get 0)) (i32.const 84))
+                (then (loop (br 0))))
+            (i32.const 0)))"#;
+
+    #[test]
+    fn test_wasm_executor() {
+        let wasm = wat::parse_str(TARGET).unwrap();
+        let mut executor = WasmExecutor::<BytesInput, (), ()>::new(&wasm, ())
+            .unwrap()
+            .with_fuel(100_000);
+        for (input, exit_kind) in [
+            (&b""[..], ExitKind::Ok),
+            (b"A", ExitKind::Ok),
+            (b"C", ExitKind::Crash),
+            (b"T", ExitKind::Timeout),
+            // Each run gets a fresh instance, and fuel
+            (b"A", ExitKind::Ok),
+        ] {
+            assert_eq!(
+                executor
+                    .run_target(&mut (), &mut (), &mut (), &BytesInput::new(input.to_vec()))
+                    .unwrap(),
+                exit_kind
+            );
+        }
+    }
+
+    #[test]
+    fn test_wasm_executor_missing_export() {
+        let wasm = wat::parse_str(TARGET).unwrap();
+        let mut executor = WasmExecutor::<BytesInput, (), ()>::new(&wasm, ())
+            .unwrap()
+            .with_target("missing");
+        assert!(executor
+            .run_target(&mut (), &mut (), &mut (), &BytesInput::new(vec![]))
+            .is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! The coverage instrumentation of WebAssembly modules
+
+use libafl::Error;
+use walrus::{
+    ir::{dfs_pre_order_mut, Call, Const, InstrSeq, Value, VisitorMut},
+    FunctionId, InstrLocId, Module, ValType,
+};
+
+/// The module of the import called at the start of each block of an instrumented module
+pub const TRACE_MODULE: &str = "libafl";
+
+/// The name of the import called at the start of each block of an instrumented module, with the
+/// id of the block, as an `i32`
+pub const TRACE_BLOCK_FUNC: &str = "trace_block";
+
+/// Spreads the sequential ids of the blocks over the whole `u32` range, for their edges to be
+/// spread over the edges map
+const BLOCK_ID_MULTIPLIER: u32 = 0x9e37_79b9;
+
+/// Inserts a call to the trace function at the start of each instruction sequence
+struct BlockTracer {
+    trace_block: FunctionId,
+    blocks: u32,
+}
+
+impl VisitorMut for BlockTracer {
+    #[allow(clippy::cast_possible_wrap)]
+    fn start_instr_seq_mut(&mut self, seq: &mut InstrSeq) {
+        let id = self.blocks.wrapping_mul(BLOCK_ID_MULTIPLIER);
+        self.blocks += 1;
+        seq.instrs.insert(
+            0,
+            (
+                Const {
+                    value: Value::I32(id as i32),
+                }
+                .into(),
+                InstrLocId::default(),
+            ),
+        );
+        seq.instrs.insert(
+            1,
+            (
+                Call {
+                    func: self.trace_block,
+                }
+                .into(),
+                InstrLocId::default(),
+            ),
+        );
+    }
+}
+
+/// Instruments the WebAssembly module `wasm` for coverage.
+///
+/// Each block of the functions of the module, i.e. each function body, `block`, `loop` and arm of
+/// an `if`, starts with a call to the [`TRACE_BLOCK_FUNC`] import of the [`TRACE_MODULE`] module,
+/// with the id of the block. The [`crate::WasmExecutor`] provides this import.
+/// Returns the instrumented module, and the number of instrumented blocks.
+pub fn instrument_coverage(wasm: &[u8]) -> Result<(Vec<u8>, usize), Error> {
+    let mut module = Module::from_buffer(wasm)
+        .map_err(|err| Error::IllegalArgument(format!("Could not parse the module: {}", err)))?;
+    let ty = module.types.add(&[ValType::I32], &[]);
+    let (trace_block, _) = module.add_import_func(TRACE_MODULE, TRACE_BLOCK_FUNC, ty);
+
+    let mut tracer = BlockTracer {
+        trace_block,
+        blocks: 0,
+    };
+    for (_, func) in module.funcs.iter_local_mut() {
+        let entry = func.entry_block();
+        dfs_pre_order_mut(&mut tracer, func, entry);
+    }
+    Ok((module.emit_wasm(), tracer.blocks as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use walrus::{ImportKind, Module};
+
+    use super::{instrument_coverage, TRACE_BLOCK_FUNC, TRACE_MODULE};
+
+    #[test]
+    fn test_instrument_coverage() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (func (export "f") (param i32) (result i32)
+                    (block
+                        (loop
+                            (br_if 1 (local.get 0))))
+                    (if (result i32) (local.get 0)
+                        (then (i32.const 1))
+                        (else (i32.const 2))))
+                (func (export "g")))"#,
+        )
+        .unwrap();
+        let (instrumented, blocks) = instrument_coverage(&wasm).unwrap();
+        // The two function bodies, the block, the loop, and the two arms of the if
+        assert_eq!(blocks, 6);
+
+        let module = Module::from_buffer(&instrumented).unwrap();
+        let trace_block = module
+            .imports
+            .iter()
+            .find(|import| import.module == TRACE_MODULE && import.name == TRACE_BLOCK_FUNC)
+            .unwrap();
+        assert!(matches!(trace_block.kind, ImportKind::Function(_)));
+
+        assert!(instrument_coverage(b"not wasm").is_err());
+    }
+}
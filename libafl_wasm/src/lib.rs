@@ -0,0 +1,72 @@
+//! A [`WebAssembly`](https://webassembly.org/) backend for `LibAFL`, based on
+//! [`wasmtime`](https://wasmtime.dev/).
+//!
+//! The [`instrument_coverage`] pass inserts a call to the fuzzer at the start of each block of a
+//! module, and the [`WasmExecutor`] runs the instrumented module, feeding each input to one of its
+//! exports and reporting the edges it executed in the edges map of `libafl_targets`. Fuzzing the
+//! wasm builds of parsers catches their out-of-bounds accesses and aborts cheaply, without native
+//! instrumentation.
+//!
+//! The coverage comes from rewriting the module, as `wasmtime` has no hooks into the execution of
+//! the blocks of a module.
+
+#![deny(rustdoc::broken_intra_doc_links)]
+#![deny(clippy::pedantic)]
+#![allow(
+    clippy::unreadable_literal,
+    clippy::type_repetition_in_bounds,
+    clippy::missing_errors_doc,
+    clippy::cast_possible_truncation,
+    clippy::used_underscore_binding,
+    clippy::ptr_as_ptr,
+    clippy::missing_panics_doc,
+    clippy::missing_docs_in_private_items,
+    clippy::module_name_repetitions
+)]
+#![cfg_attr(debug_assertions, warn(
+    missing_debug_implementations,
+    missing_docs,
+    //trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    //unused_results
+))]
+#![cfg_attr(not(debug_assertions), deny(
+    missing_debug_implementations,
+    missing_docs,
+    //trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    //unused_results
+))]
+#![cfg_attr(
+    not(debug_assertions),
+    deny(
+        bad_style,
+        const_err,
+        dead_code,
+        improper_ctypes,
+        non_shorthand_field_patterns,
+        no_mangle_generic_items,
+        overflowing_literals,
+        path_statements,
+        patterns_in_fns_without_body,
+        private_in_public,
+        unconditional_recursion,
+        unused,
+        unused_allocation,
+        unused_comparisons,
+        unused_parens,
+        while_true
+    )
+)]
+
+pub mod instrument;
+pub use instrument::{instrument_coverage, TRACE_BLOCK_FUNC, TRACE_MODULE};
+
+pub mod executor;
+pub use executor::WasmExecutor;